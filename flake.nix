@@ -37,13 +37,12 @@
           # Runtime dependencies
           propagatedBuildInputs = with pkgs; [
             restic
-            awscli2
           ];
 
           # Ensure runtime dependencies are available in PATH
           postInstall = ''
             wrapProgram $out/bin/restic-backup-service \
-              --prefix PATH : ${pkgs.lib.makeBinPath [pkgs.restic pkgs.awscli2]}
+              --prefix PATH : ${pkgs.lib.makeBinPath [pkgs.restic]}
           '';
 
           meta = with pkgs.lib; {
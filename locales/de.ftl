@@ -0,0 +1,157 @@
+# Deutscher Katalog. Fehlende Schlüssel fallen auf Englisch zurück.
+
+## Interaktive Wiederherstellung
+prompt-select-host = Hostname auswählen
+prompt-select-restore-scope = Was soll wiederhergestellt werden?
+prompt-select-repositories = Repositories auswählen (Leertaste zum Markieren, Enter zum Bestätigen)
+prompt-select-repository = Repository auswählen
+prompt-select-user = Benutzer auswählen
+prompt-select-time-window = Zeitfenster auswählen [1]
+prompt-clear-destination = Fortfahren und das Verzeichnis leeren?
+prompt-post-restore = Was soll mit den wiederhergestellten Dateien passieren?
+prompt-post-restore-chunked = Die Wiederherstellung erfolgt in Teilen; wie soll jeder Teil abgelegt werden?
+prompt-place-file = Die wiederhergestellte Datei unter { $path } ablegen?
+prompt-place-file-overwrite = { $path } existiert. Durch die wiederhergestellte Version ersetzen?
+
+scope-all = Alles
+scope-user-home = Benutzerverzeichnisse (alle Benutzerordner)
+scope-single-user = Ein Benutzer (alle Repositories unter /home/<benutzer>)
+scope-docker = Docker-Volumes (alle Docker-Volumes)
+scope-system = System (alle Systempfade)
+scope-custom = Eigene Auswahl (bestimmte Repositories wählen)
+scope-single = Einzelnes Repository (Einzelauswahl)
+
+repo-item = { $path } ({ $count } Snapshots)
+user-item = { $user } ({ $count } Repositories)
+found-backups = Gefundene Backups: Benutzerverzeichnisse ({ $user_home }), Docker-Volumes ({ $docker }), System ({ $system })
+time-windows-header = Verfügbare Zeitfenster zur Wiederherstellung (5-Minuten-Gruppen):
+time-window-label = { $start } bis { $end } ({ $count } Snapshots)
+
+plain-enter-number = Nummer eingeben [{ $default }]:
+plain-invalid-number = Bitte eine Nummer zwischen 1 und { $max } eingeben.
+plain-enter-numbers = Nummern durch Kommas getrennt eingeben (z. B. 1,3,5-7):
+plain-invalid-numbers = Bitte Nummern zwischen 1 und { $max } durch Kommas getrennt eingeben.
+plain-answer-yes-no = Bitte mit j oder n antworten.
+destructive-warning = ⚠️  Gleich wird { $operation } für alle Repositories von Host { $host } ausgeführt. Dies kann nicht rückgängig gemacht werden.
+prompt-type-confirmation = Zum Fortfahren den Hostnamen ({ $host }) oder den Code { $code } eingeben:
+confirm-mismatch = Der Wert von --confirm '{ $value }' passt nicht zu Host '{ $host }'
+confirm-required = --yes erfordert --confirm { $host }, um { $operation } ohne Rückfrage auszuführen
+confirm-no-terminal = { $operation } wird ohne Terminal nicht ausgeführt.
+confirm-no-terminal-hint = Mit --yes --confirm { $host } ohne Rückfrage ausführen.
+confirm-aborted = Bestätigung stimmt nicht überein; Abbruch
+host-protected = { $operation } für Host '{ $host }' verweigert: Er ist in { $variable } eingetragen.
+host-protected-hint = Zuerst aus { $variable } entfernen, falls dies wirklich beabsichtigt ist.
+
+action-copy = An den ursprünglichen Ort kopieren (vorhandene Dateien ersetzen)
+action-move = An den ursprünglichen Ort verschieben (vorhandene Dateien ersetzen)
+action-leave = Dateien am temporären Ort belassen
+
+## Zusammenfassungen
+restore-summary-header = Zusammenfassung der Wiederherstellung:
+restore-summary-restored = Erfolgreich wiederhergestellt: { $count } Repositories
+restore-summary-skipped = Übersprungen: { $count } Repositories
+restore-summary-destination = Ziel: { $path }
+restore-dry-run-header = Vorschau der Wiederherstellung (Probelauf):
+restore-dry-run-repo = { $path } aus { $snapshot }: { $files } Dateien ({ $size }), { $changed_files } weichen vom Original ab ({ $changed_size }); bereitgestellt unter { $staged }
+restore-dry-run-skipped = { $path }: kein passender Snapshot, würde übersprungen
+restore-dry-run-nothing-written = Es wurde nichts geschrieben. Denselben Befehl ohne --dry-run ausführen, um wiederherzustellen
+
+backup-failed = BACKUP FEHLGESCHLAGEN: Es wurden keine Daten gesichert! Bitte die Fehler oben prüfen
+backup-partial = Backup teilweise abgeschlossen
+backup-degraded = BACKUP BEEINTRÄCHTIGT: Einige Snapshots sind leer oder deutlich kleiner als zuvor. Bitte prüfen, ob alle Quellen eingehängt sind
+backup-budget-exceeded = Backup abgeschlossen, aber Repositories überschreiten ihre Budgets (REPO_MAX_*)
+backup-success = Backup erfolgreich abgeschlossen
+backup-slowest-header = Langsamste Pfade:
+backup-excludes-header = Ausgeschlossene Muster:
+backup-resources-header = Ressourcenverbrauch:
+backup-preflight-header = VORABPRÜFUNG: Einige Backup-Pfade sind für diesen Prozess nicht vollständig lesbar:
+backup-preflight-hint = Backup als root ausführen (wie der NixOS-Dienst) oder Lesezugriff auf die oben genannten Pfade gewähren
+prompt-include-sensitive = Auch die sensiblen Pfade sichern ({ $paths })?
+backup-content-header = INHALTSRICHTLINIE: Es wurden Dateien gefunden, die wie Zugangsdaten aussehen:
+prompt-include-credentials = { $count } Dateien aus { $path }, die wie Zugangsdaten aussehen, sichern?
+
+list-paths-header = ÜBERSICHT DER BACKUP-PFADE:
+list-timeline-header = SNAPSHOT-ZEITLEISTE:
+list-category-user-home = Benutzerverzeichnisse ({ $count } Pfade):
+list-category-docker = Docker-Volumes ({ $count } Pfade):
+list-category-system = System ({ $count } Pfade):
+list-none = Keine
+list-no-snapshots = Keine Snapshots gefunden
+list-incomplete-header = UNVOLLSTÄNDIGE AUFLISTUNG (einige Backups konnten nicht gelesen werden):
+list-more-time-points = ... und { $count } weitere Zeitpunkte
+list-all-hosts-totals = GESAMT: { $hosts } Hosts, { $repositories } Repositories, { $snapshots } Snapshots
+snapshots-header = SNAPSHOTS:
+snapshots-count = { $count } Snapshots
+find-header = TREFFER:
+find-no-matches = Keine passenden Dateien in den durchsuchten Snapshots
+find-count = { $count } Treffer in { $snapshots } Snapshots
+stats-header = REPOSITORY-GRÖSSEN (gespeichert, Wiederherstellung, Dateien, Snapshots):
+stats-no-repositories = Keine Repositories gefunden
+stats-total = { $repos } Repositories: { $raw } gespeichert, { $restore } bei Wiederherstellung, { $files } Dateien
+ls-header = { $path } im Snapshot { $snapshot } ({ $time }):
+ls-empty = Keine passenden Einträge in diesem Verzeichnis
+ls-count = { $count } Einträge
+doctor-header = DIAGNOSE:
+doctor-summary = { $passed } bestanden, { $warnings } Warnungen, { $failed } fehlgeschlagen
+history-header = SICHERUNGSVERLAUF ({ $file }):
+history-empty = Noch keine Läufe aufgezeichnet
+history-runs = { $runs } Läufe, { $failures } fehlgeschlagen, zuletzt { $status } um { $time }
+history-last-success = letzter erfolgreicher Lauf: { $time }
+history-never-succeeded = kein erfolgreicher Lauf in diesem Zeitraum
+history-growth = { $total } hinzugefügt ({ $average } pro Lauf), im Schnitt { $duration } pro Sicherung
+status-header = SICHERUNGSSTATUS: { $host } (veraltet nach { $max_age }):
+status-never = nie gesichert
+status-ok = letzte Sicherung { $time } (vor { $age })
+status-stale = VERALTET: letzte Sicherung { $time } (vor { $age })
+status-behind = { $behind } hinter dem Zeitplan
+status-last-run = letzter Lauf { $status } um { $time }
+status-budget-header = ÜBER BUDGET nach dem letzten Lauf (REPO_MAX_*):
+
+## Dashboard (tui)
+tui-hosts = Hosts
+tui-no-hosts = Keine Hosts unter der Repository-Basis gefunden
+tui-repositories = Repositories
+tui-repositories-of = Repositories von { $host }
+tui-no-repositories = Keine Repositories
+tui-snapshots = Snapshots
+tui-no-snapshots = Keine Snapshots
+tui-timeline = Snapshots pro Tag (letzte { $days } Tage)
+tui-loading = { $host } wird durchsucht...
+tui-loaded = { $count } Repositories auf { $host }
+tui-discovery-errors = { $count } Repositories auf { $host }, { $failures } Teile konnten nicht gelesen werden
+tui-load-failed = Durchsuchen von { $host } fehlgeschlagen: { $error }
+tui-help = Tab/Pfeile: bewegen  Enter: öffnen  r: wiederherstellen  R: neu einlesen  q: beenden
+tui-wizard-title = Wiederherstellen
+tui-wizard-overwrite = Was soll mit bereits vorhandenen Dateien passieren?
+tui-wizard-confirm = { $path } von { $host } vom { $time } (Snapshot { $snapshot }) wiederherstellen?
+tui-wizard-staging-note = Das Staging-Verzeichnis wird vorher geleert.
+tui-wizard-help = Hoch/Runter: wählen  Enter: weiter  Esc: zurück
+tui-wizard-run = Enter: wiederherstellen  Esc: zurück
+overwrite-replace = Vorhandene Dateien ersetzen
+overwrite-skip = Vorhandene Dateien behalten, nur fehlende ergänzen
+overwrite-keep-both = Vorhandene Dateien als <name>.pre-restore-<zeitstempel> behalten
+
+## Fehler
+lock-holder-pid = PID { $pid }
+lock-holder-unknown-pid = unbekannte PID
+lock-holder-started = { $holder }, gestartet vor { $elapsed }
+already-running = Ein anderer Backup-Lauf ist bereits aktiv ({ $holder }), dieser wird übersprungen
+delete-host-not-found = Keine Repositories für Host { $host } gefunden.
+delete-host-not-found-hint = Mit `restic-backup-service hosts` die Hosts in der Repository-Basis anzeigen
+delete-host-not-forgotten = { $count } Repositories von Host { $host } wurden nicht vergessen: { $repositories }
+budget-snapshot-count = { $actual } Snapshots überschreiten das Budget von { $limit }
+budget-size = Größe des neuesten Snapshots { $actual } überschreitet das Budget von { $limit }
+budget-file-count = Neuester Snapshot enthält { $actual } Dateien und überschreitet das Budget von { $limit }
+
+## Fehlerhinweise
+hint-prefix = Hinweis
+hint-authentication = AWS_ACCESS_KEY_ID / AWS_SECRET_ACCESS_KEY und die Bucket-Berechtigungen für diesen Schlüssel prüfen.
+hint-network = Netzwerkverbindung prüfen und ob AWS_S3_ENDPOINT erreichbar ist.
+hint-locked = Möglicherweise läuft noch ein anderer restic-Prozess. Falls nicht, veraltete Sperren mit `restic-backup-service unlock` entfernen.
+hint-wrong-password = RESTIC_PASSWORD passt nicht zu diesem Repository. Passwort in der Secrets-Datei prüfen.
+hint-quota = Der Bucket oder das Konto hat keinen Speicherplatz mehr. Speicher freigeben, alte Snapshots bereinigen oder das Kontingent erhöhen.
+hint-clock-skew = Die Systemuhr geht falsch. Zeit synchronisieren (z. B. `timedatectl set-ntp true`) und erneut versuchen.
+hint-tls = Zertifikat des Endpunkts und die CA-Zertifikate des Systems prüfen sowie das erwartete Schema des Endpunkts.
+hint-throttled = Das Speicher-Backend drosselt Anfragen. Kurz warten und erneut versuchen.
+hint-disk-full = Ein Datenträger auf diesem Rechner ist voll, meist der mit dem Cache oder den temporären Dateien von restic. Platz freigeben oder sie mit RESTIC_CACHE_DIR / TMPDIR verlegen.
+hint-restic-version = restic aktualisieren, oder `restic-backup-service self install-restic` ausführen und RESTIC_BIN darauf zeigen lassen, falls es nicht als erstes im PATH liegt.
@@ -0,0 +1,157 @@
+# English catalog (default). Keys are shared with every other catalog.
+
+## Interactive restore prompts
+prompt-select-host = Select hostname
+prompt-select-restore-scope = Select what to restore
+prompt-select-repositories = Select repositories (space to toggle, enter to confirm)
+prompt-select-repository = Select repository
+prompt-select-user = Select user
+prompt-select-time-window = Select time window [1]
+prompt-clear-destination = Continue and clear the directory?
+prompt-post-restore = What would you like to do with the restored files?
+prompt-post-restore-chunked = The restore runs in chunks; how should each chunk be placed?
+prompt-place-file = Put the restored file at { $path }?
+prompt-place-file-overwrite = { $path } exists. Replace it with the restored version?
+
+scope-all = All (everything)
+scope-user-home = User Home (all user directories)
+scope-single-user = One User (all repositories under /home/<user>)
+scope-docker = Docker Volumes (all docker volumes)
+scope-system = System (all system paths)
+scope-custom = Custom Selection (choose specific repositories)
+scope-single = Individual Repository (single selection)
+
+repo-item = { $path } ({ $count } snapshots)
+user-item = { $user } ({ $count } repositories)
+found-backups = Found backups: User Home ({ $user_home }), Docker Volumes ({ $docker }), System ({ $system })
+time-windows-header = Available restore time windows (5-minute groups):
+time-window-label = { $start } to { $end } ({ $count } snapshots)
+
+plain-enter-number = Enter a number [{ $default }]:
+plain-invalid-number = Please enter a number between 1 and { $max }.
+plain-enter-numbers = Enter numbers separated by commas (e.g. 1,3,5-7):
+plain-invalid-numbers = Please enter numbers between 1 and { $max }, separated by commas.
+plain-answer-yes-no = Please answer y or n.
+destructive-warning = ⚠️  About to { $operation } all repositories of host { $host }. This cannot be undone.
+prompt-type-confirmation = Type the hostname ({ $host }) or the code { $code } to continue:
+confirm-mismatch = --confirm value '{ $value }' does not match host '{ $host }'
+confirm-required = --yes requires --confirm { $host } to { $operation } non-interactively
+confirm-no-terminal = Refusing to { $operation } without a terminal.
+confirm-no-terminal-hint = Pass --yes --confirm { $host } to run non-interactively.
+confirm-aborted = Confirmation did not match; aborting
+host-protected = Refusing to { $operation } host '{ $host }': it is listed in { $variable }.
+host-protected-hint = Remove it from { $variable } first if this is really intended.
+
+action-copy = Copy to original location (replace existing files)
+action-move = Move to original location (replace existing files)
+action-leave = Leave files in temporary location
+
+## Summaries
+restore-summary-header = Restoration Summary:
+restore-summary-restored = Successfully restored: { $count } repositories
+restore-summary-skipped = Skipped: { $count } repositories
+restore-summary-destination = Destination: { $path }
+restore-dry-run-header = Restore preview (dry run):
+restore-dry-run-repo = { $path } from { $snapshot }: { $files } files ({ $size }), { $changed_files } differ from the originals ({ $changed_size }); staged at { $staged }
+restore-dry-run-skipped = { $path }: no suitable snapshot, would be skipped
+restore-dry-run-nothing-written = Nothing was written. Run the same command without --dry-run to restore
+
+backup-failed = BACKUP FAILED: No data was backed up! Please check the errors above
+backup-partial = Backup partially completed
+backup-degraded = BACKUP DEGRADED: Some snapshots are empty or much smaller than before. Check that all sources are mounted
+backup-budget-exceeded = Backup completed, but repositories exceed their budgets (REPO_MAX_*)
+backup-success = Backup completed successfully
+backup-slowest-header = Slowest paths:
+backup-excludes-header = Excluded patterns:
+backup-resources-header = Resource usage:
+backup-preflight-header = PREFLIGHT: Some backup paths are not fully readable by this process:
+backup-preflight-hint = Run the backup as root (the NixOS service does) or grant read access to the paths above
+prompt-include-sensitive = Also back up the sensitive paths ({ $paths })?
+backup-content-header = CONTENT POLICY: Files that look like credentials were found:
+prompt-include-credentials = Back up { $count } files from { $path } that look like credentials?
+
+list-paths-header = BACKUP PATHS SUMMARY:
+list-timeline-header = SNAPSHOT TIMELINE:
+list-category-user-home = User Home ({ $count } paths):
+list-category-docker = Docker Volumes ({ $count } paths):
+list-category-system = System ({ $count } paths):
+list-none = None
+list-no-snapshots = No snapshots found
+list-incomplete-header = INCOMPLETE LISTING (some backups could not be read):
+list-more-time-points = ... and { $count } more time points
+list-all-hosts-totals = TOTAL: { $hosts } hosts, { $repositories } repositories, { $snapshots } snapshots
+snapshots-header = SNAPSHOTS:
+snapshots-count = { $count } snapshots
+find-header = MATCHES:
+find-no-matches = No matching files in the searched snapshots
+find-count = { $count } matches in { $snapshots } snapshots
+stats-header = REPOSITORY SIZES (stored, restore size, files, snapshots):
+stats-no-repositories = No repositories found
+stats-total = { $repos } repositories: { $raw } stored, { $restore } restore size, { $files } files
+ls-header = { $path } in snapshot { $snapshot } ({ $time }):
+ls-empty = No matching entries in this directory
+ls-count = { $count } entries
+doctor-header = DOCTOR:
+doctor-summary = { $passed } passed, { $warnings } warnings, { $failed } failed
+history-header = BACKUP HISTORY ({ $file }):
+history-empty = No runs recorded yet
+history-runs = { $runs } runs, { $failures } failed, last { $status } at { $time }
+history-last-success = last successful run: { $time }
+history-never-succeeded = no successful run in this window
+history-growth = { $total } added ({ $average } per run), { $duration } per backup on average
+status-header = BACKUP STATUS: { $host } (stale after { $max_age }):
+status-never = never backed up
+status-ok = last backup { $time } ({ $age } ago)
+status-stale = STALE: last backup { $time } ({ $age } ago)
+status-behind = { $behind } behind schedule
+status-last-run = last run { $status } at { $time }
+status-budget-header = OVER BUDGET after the last run (REPO_MAX_*):
+
+## Dashboard (tui)
+tui-hosts = Hosts
+tui-no-hosts = No hosts found below the repository base
+tui-repositories = Repositories
+tui-repositories-of = Repositories of { $host }
+tui-no-repositories = No repositories
+tui-snapshots = Snapshots
+tui-no-snapshots = No snapshots
+tui-timeline = Snapshots per day (last { $days } days)
+tui-loading = Scanning { $host }...
+tui-loaded = { $count } repositories on { $host }
+tui-discovery-errors = { $count } repositories on { $host }, { $failures } parts could not be read
+tui-load-failed = Scanning { $host } failed: { $error }
+tui-help = Tab/arrows: move  Enter: open  r: restore  R: rescan  q: quit
+tui-wizard-title = Restore
+tui-wizard-overwrite = What should happen with files that already exist?
+tui-wizard-confirm = Restore { $path } of { $host } from { $time } (snapshot { $snapshot })?
+tui-wizard-staging-note = The staging directory is cleared first.
+tui-wizard-help = Up/Down: choose  Enter: next  Esc: back
+tui-wizard-run = Enter: restore  Esc: back
+overwrite-replace = Replace existing files
+overwrite-skip = Keep existing files, only add missing ones
+overwrite-keep-both = Keep existing files as <name>.pre-restore-<timestamp>
+
+## Errors
+lock-holder-pid = pid { $pid }
+lock-holder-unknown-pid = unknown pid
+lock-holder-started = { $holder }, started { $elapsed } ago
+already-running = Another backup run is already in progress ({ $holder }), skipping this one
+delete-host-not-found = No repositories found for host { $host }.
+delete-host-not-found-hint = Run `restic-backup-service hosts` to see the hosts in the repository base
+delete-host-not-forgotten = { $count } repositories of host { $host } were not forgotten: { $repositories }
+budget-snapshot-count = { $actual } snapshots exceed the budget of { $limit }
+budget-size = latest snapshot size { $actual } exceeds the budget of { $limit }
+budget-file-count = latest snapshot holds { $actual } files, exceeding the budget of { $limit }
+
+## Error hints
+hint-prefix = Hint
+hint-authentication = Check AWS_ACCESS_KEY_ID / AWS_SECRET_ACCESS_KEY and the bucket permissions for this key.
+hint-network = Check network connectivity and that AWS_S3_ENDPOINT is reachable.
+hint-locked = Another restic process may be running. If not, remove stale locks with `restic-backup-service unlock`.
+hint-wrong-password = RESTIC_PASSWORD does not match this repository. Verify the password in your secrets file.
+hint-quota = The bucket or account is out of space. Free up storage, prune old snapshots, or raise the quota.
+hint-clock-skew = The system clock is off. Sync time (e.g. `timedatectl set-ntp true`) and retry.
+hint-tls = Verify the endpoint certificate and system CA bundle, and that the endpoint uses the expected scheme.
+hint-throttled = The storage backend is rate limiting requests. Wait a moment and retry.
+hint-disk-full = A disk on this machine is full, usually the one holding restic's cache or temp files. Free space, or move them with RESTIC_CACHE_DIR / TMPDIR.
+hint-restic-version = Upgrade restic, or run `restic-backup-service self install-restic` and point RESTIC_BIN at it if it is not first in PATH.
@@ -17,11 +17,11 @@
 
     nativeBuildInputs = with pkgs; [pkg-config makeWrapper];
     buildInputs = with pkgs; [openssl];
-    propagatedBuildInputs = with pkgs; [restic awscli2];
+    propagatedBuildInputs = with pkgs; [restic];
 
     postInstall = ''
       wrapProgram $out/bin/restic-backup-service \
-        --prefix PATH : ${pkgs.lib.makeBinPath [pkgs.restic pkgs.awscli2]}
+        --prefix PATH : ${pkgs.lib.makeBinPath [pkgs.restic pkgs.systemd pkgs.openssh]}
     '';
   };
 
@@ -33,6 +33,28 @@
     ${lib.optionalString (cfg.exclude.file != null) ("BACKUP_EXCLUDE_FILE=" + lib.escapeShellArg (toString cfg.exclude.file))}
     ${lib.optionalString (cfg.exclude.largerThan != null) ("BACKUP_EXCLUDE_LARGER_THAN=" + lib.escapeShellArg cfg.exclude.largerThan)}
     ${lib.optionalString (cfg.exclude.ifPresent != []) ("BACKUP_EXCLUDE_IF_PRESENT=" + lib.escapeShellArg (lib.concatStringsSep "," cfg.exclude.ifPresent))}
+    ${lib.optionalString (cfg.networkMounts != {}) ("BACKUP_NETWORK_MOUNTS=" + lib.escapeShellArg (lib.concatStringsSep "," (lib.mapAttrsToList (path: unit: if unit == null then path else "${path}=${unit}") cfg.networkMounts)))}
+    ${lib.optionalString (cfg.metrics.textfile != null) ("METRICS_TEXTFILE=" + lib.escapeShellArg cfg.metrics.textfile)}
+    ${lib.optionalString (cfg.metrics.pushgatewayUrl != null) ("METRICS_PUSHGATEWAY_URL=" + lib.escapeShellArg cfg.metrics.pushgatewayUrl)}
+    ${lib.optionalString (cfg.healthcheck.url != null) ("HEALTHCHECK_URL=" + lib.escapeShellArg cfg.healthcheck.url)}
+    ${lib.optionalString (cfg.healthcheck.startUrl != null) ("HEALTHCHECK_START_URL=" + lib.escapeShellArg cfg.healthcheck.startUrl)}
+    ${lib.optionalString (cfg.healthcheck.successUrl != null) ("HEALTHCHECK_SUCCESS_URL=" + lib.escapeShellArg cfg.healthcheck.successUrl)}
+    ${lib.optionalString (cfg.healthcheck.failUrl != null) ("HEALTHCHECK_FAIL_URL=" + lib.escapeShellArg cfg.healthcheck.failUrl)}
+    ${lib.optionalString (cfg.notify.webhookUrl != null) ("NOTIFY_WEBHOOK_URL=" + lib.escapeShellArg cfg.notify.webhookUrl)}
+    ${lib.optionalString (cfg.notify.ntfyUrl != null) ("NOTIFY_NTFY_URL=" + lib.escapeShellArg cfg.notify.ntfyUrl)}
+    ${lib.optionalString (cfg.notify.chatWebhookUrl != null) ("NOTIFY_CHAT_WEBHOOK_URL=" + lib.escapeShellArg cfg.notify.chatWebhookUrl)}
+    NOTIFY_ON=${cfg.notify.on}
+    ${lib.optionalString (cfg.notify.email.smtpHost != null) ("REPORT_SMTP_HOST=" + lib.escapeShellArg cfg.notify.email.smtpHost)}
+    ${lib.optionalString (cfg.notify.email.smtpPort != null) ("REPORT_SMTP_PORT=" + toString cfg.notify.email.smtpPort)}
+    REPORT_SMTP_TLS=${cfg.notify.email.tls}
+    ${lib.optionalString (cfg.notify.email.from != null) ("REPORT_SMTP_FROM=" + lib.escapeShellArg cfg.notify.email.from)}
+    ${lib.optionalString (cfg.notify.email.to != []) ("REPORT_SMTP_TO=" + lib.escapeShellArg (lib.concatStringsSep "," cfg.notify.email.to))}
+    REPORT_SMTP_ON=${cfg.notify.email.on}
+    ${lib.optionalString (cfg.mirror.repoBase != null) ("MIRROR_REPO_BASE=" + lib.escapeShellArg cfg.mirror.repoBase)}
+    MIRROR_AFTER_BACKUP=${lib.boolToString cfg.mirror.afterBackup}
+    PRUNE_AFTER_BACKUP=${lib.boolToString cfg.prune.afterBackup}
+    ${lib.optionalString (cfg.exclude.perPath != {}) ("BACKUP_PATH_EXCLUDES=" + lib.escapeShellArg (lib.concatStringsSep ";" (lib.mapAttrsToList (path: patterns: "${path}=${lib.concatStringsSep "," patterns}") cfg.exclude.perPath)))}
+    ${lib.optionalString (cfg.pathTags != {}) ("BACKUP_PATH_TAGS=" + lib.escapeShellArg (lib.concatStringsSep ";" (lib.mapAttrsToList (path: tags: "${path}=${lib.concatStringsSep "," tags}") cfg.pathTags)))}
   '';
   # Secrets file path provided via NixOS option
   envInlineFile = cfg.secret_file_path;
@@ -82,6 +104,92 @@
     exec "${cfg.package}/bin/restic-backup-service" run ${lib.concatStringsSep " " cfg.extraArgs}
   '';
 
+  # Hostname the binary will resolve, repeated to satisfy the destructive-operation guard
+  confirmHost =
+    if cfg.hostname != null
+    then cfg.hostname
+    else config.networking.hostName;
+
+  # Repository compaction runner (restic prune with tuned repack settings)
+  pruneScript = pkgs.writeShellScript "restic-backup-prune-runner" ''
+    set -euo pipefail
+
+    set -a
+    source ${envFile}
+    ${lib.optionalString (cfg.restic.repoBase != null) ''
+      RESTIC_REPO_BASE="${cfg.restic.repoBase}"
+    ''}
+    ${lib.optionalString (cfg.aws.s3Endpoint != null) ''
+      AWS_S3_ENDPOINT="${cfg.aws.s3Endpoint}"
+    ''}
+    set +a
+
+    RBS_LOG_DIR=/var/log/restic-backup
+    export RBS_LOG_DIR
+
+    exec "${cfg.package}/bin/restic-backup-service" prune --yes --confirm ${lib.escapeShellArg confirmHost}
+  '';
+
+  # Repository integrity check runner
+  checkScript = pkgs.writeShellScript "restic-backup-check-runner" ''
+    set -euo pipefail
+
+    set -a
+    source ${envFile}
+    ${lib.optionalString (cfg.restic.repoBase != null) ''
+      RESTIC_REPO_BASE="${cfg.restic.repoBase}"
+    ''}
+    ${lib.optionalString (cfg.aws.s3Endpoint != null) ''
+      AWS_S3_ENDPOINT="${cfg.aws.s3Endpoint}"
+    ''}
+    set +a
+
+    RBS_LOG_DIR=/var/log/restic-backup
+    export RBS_LOG_DIR
+
+    exec "${cfg.package}/bin/restic-backup-service" check${lib.optionalString (cfg.check.readDataSubset != null) " --read-data-subset ${lib.escapeShellArg cfg.check.readDataSubset}"}
+  '';
+
+  # Restore drill runner
+  drillScript = pkgs.writeShellScript "restic-backup-drill-runner" ''
+    set -euo pipefail
+
+    set -a
+    source ${envFile}
+    ${lib.optionalString (cfg.restic.repoBase != null) ''
+      RESTIC_REPO_BASE="${cfg.restic.repoBase}"
+    ''}
+    ${lib.optionalString (cfg.aws.s3Endpoint != null) ''
+      AWS_S3_ENDPOINT="${cfg.aws.s3Endpoint}"
+    ''}
+    set +a
+
+    RBS_LOG_DIR=/var/log/restic-backup
+    export RBS_LOG_DIR
+
+    exec "${cfg.package}/bin/restic-backup-service" drill --sample ${toString cfg.drill.sample}
+  '';
+
+  # Periodic digest runner (one summary per host instead of per-run notifications)
+  digestScript = pkgs.writeShellScript "restic-backup-digest-runner" ''
+    set -euo pipefail
+
+    set -a
+    source ${envFile}
+    ${lib.optionalString (cfg.restic.repoBase != null) ''
+      RESTIC_REPO_BASE="${cfg.restic.repoBase}"
+    ''}
+    ${lib.optionalString (cfg.aws.s3Endpoint != null) ''
+      AWS_S3_ENDPOINT="${cfg.aws.s3Endpoint}"
+    ''}
+    set +a
+
+    RBS_LOG_DIR=/var/log/restic-backup
+    export RBS_LOG_DIR
+
+    exec "${cfg.package}/bin/restic-backup-service" report digest --days ${toString cfg.digest.days} --send
+  '';
+
   # Provide a CLI wrapper that sources the same env files for manual usage
   cliWrapper = pkgs.writeShellScriptBin "restic-backup-service-env" ''
     set -euo pipefail
@@ -121,7 +229,430 @@ in {
         example = "2G";
         description = "Size threshold for --exclude-larger-than (e.g. 100M, 2G).";
       };
+
+      perPath = lib.mkOption {
+        type = lib.types.attrsOf (lib.types.listOf lib.types.str);
+        default = {};
+        example = {"/home/tim" = ["node_modules" "**/shadercache"]; "/srv/games" = ["@/etc/games.exclude"];};
+        description = "Per-path --exclude patterns (BACKUP_PATH_EXCLUDES), replacing BACKUP_EXCLUDES at and below each path; @FILE entries are passed as --exclude-file.";
+      };
+    };
+    pathTags = lib.mkOption {
+      type = lib.types.attrsOf (lib.types.listOf lib.types.str);
+      default = {};
+      example = {"/home/tim" = ["critical"]; "/srv/games" = ["games" "large"];};
+      description = "Extra restic --tag values per backup path (BACKUP_PATH_TAGS); a path gets the tags of every entry at or above it.";
+    };
+    networkMounts = lib.mkOption {
+      type = lib.types.attrsOf (lib.types.nullOr lib.types.str);
+      default = {};
+      example = {"/mnt/nas" = "mnt-nas.mount"; "/mnt/share" = null;};
+      description = "Network mountpoints (BACKUP_NETWORK_MOUNTS) that must be mounted before paths on them are backed up; a unit name is started when the share is missing and stopped after the run.";
+    };
+    metrics = {
+      textfile = lib.mkOption {
+        type = lib.types.nullOr lib.types.str;
+        default = null;
+        example = "/var/lib/prometheus-node-exporter-text-files/restic_backup.prom";
+        description = "Write Prometheus metrics of every backup run to this file for node_exporter's textfile collector (METRICS_TEXTFILE).";
+      };
+      pushgatewayUrl = lib.mkOption {
+        type = lib.types.nullOr lib.types.str;
+        default = null;
+        example = "http://pushgateway:9091";
+        description = "Push Prometheus metrics of every backup run to this Pushgateway (METRICS_PUSHGATEWAY_URL).";
+      };
+    };
+    healthcheck = {
+      url = lib.mkOption {
+        type = lib.types.nullOr lib.types.str;
+        default = null;
+        example = "https://hc-ping.com/your-check-uuid";
+        description = "Dead man's switch check pinged at <url>/start, <url> and <url>/fail around every backup run (HEALTHCHECK_URL).";
+      };
+      startUrl = lib.mkOption {
+        type = lib.types.nullOr lib.types.str;
+        default = null;
+        description = "Ping URL for run starts instead of <url>/start (HEALTHCHECK_START_URL).";
+      };
+      successUrl = lib.mkOption {
+        type = lib.types.nullOr lib.types.str;
+        default = null;
+        description = "Ping URL for successful runs instead of <url> (HEALTHCHECK_SUCCESS_URL).";
+      };
+      failUrl = lib.mkOption {
+        type = lib.types.nullOr lib.types.str;
+        default = null;
+        description = "Ping URL for failed runs instead of <url>/fail (HEALTHCHECK_FAIL_URL).";
+      };
+    };
+    notify = {
+      webhookUrl = lib.mkOption {
+        type = lib.types.nullOr lib.types.str;
+        default = null;
+        description = "POST every backup and restore notification as JSON to this URL (NOTIFY_WEBHOOK_URL).";
+      };
+      ntfyUrl = lib.mkOption {
+        type = lib.types.nullOr lib.types.str;
+        default = null;
+        example = "https://ntfy.sh/my-backups";
+        description = "Publish notifications to this ntfy topic (NOTIFY_NTFY_URL); put NOTIFY_NTFY_TOKEN in the env file for protected topics.";
+      };
+      chatWebhookUrl = lib.mkOption {
+        type = lib.types.nullOr lib.types.str;
+        default = null;
+        description = "Slack or Discord incoming webhook for notifications (NOTIFY_CHAT_WEBHOOK_URL).";
+      };
+      on = lib.mkOption {
+        type = lib.types.enum ["always" "failure"];
+        default = "failure";
+        description = "Notify about every run, or only about runs that did not fully succeed (NOTIFY_ON).";
+      };
+      email = {
+        smtpHost = lib.mkOption {
+          type = lib.types.nullOr lib.types.str;
+          default = null;
+          example = "smtp.example.com";
+          description = "Mail a summary of each backup run through this SMTP server (REPORT_SMTP_HOST); put REPORT_SMTP_USERNAME and REPORT_SMTP_PASSWORD in the secrets file.";
+        };
+        smtpPort = lib.mkOption {
+          type = lib.types.nullOr lib.types.port;
+          default = null;
+          description = "SMTP port (REPORT_SMTP_PORT); 587, 465 or 25 depending on tls when null.";
+        };
+        tls = lib.mkOption {
+          type = lib.types.enum ["starttls" "tls" "none"];
+          default = "starttls";
+          description = "How the SMTP connection is secured (REPORT_SMTP_TLS).";
+        };
+        from = lib.mkOption {
+          type = lib.types.nullOr lib.types.str;
+          default = null;
+          example = "Backups <backup@example.com>";
+          description = "Sender of the run reports (REPORT_SMTP_FROM).";
+        };
+        to = lib.mkOption {
+          type = lib.types.listOf lib.types.str;
+          default = [];
+          description = "Recipients of the run reports (REPORT_SMTP_TO).";
+        };
+        on = lib.mkOption {
+          type = lib.types.enum ["always" "failure"];
+          default = "failure";
+          description = "Mail every run, or only runs that did not fully succeed (REPORT_SMTP_ON).";
+        };
+      };
+    };
+    contentPolicy = lib.mkOption {
+      type = lib.types.enum ["off" "warn" "exclude" "confirm"];
+      default = "off";
+      description = "How backups treat files that look like credentials (BACKUP_CONTENT_POLICY); scheduled runs cannot confirm, so `confirm` excludes them there.";
+    };
+    dockerQuiesce = lib.mkOption {
+      type = lib.types.enum ["off" "pause" "stop"];
+      default = "off";
+      description = "Pause or stop running containers using a docker volume while the volume is backed up (BACKUP_DOCKER_QUIESCE); the service user needs access to the docker socket.";
+    };
+    backupConcurrency = lib.mkOption {
+      type = lib.types.nullOr lib.types.ints.positive;
+      default = null;
+      description = "Paths backed up in parallel (BACKUP_CONCURRENCY); 1 when null. Seeding always runs one path at a time.";
+    };
+    autoUnlockStale = lib.mkOption {
+      type = lib.types.bool;
+      default = false;
+      description = "Remove stale locks of a repository found locked during a backup and retry the path once (BACKUP_AUTO_UNLOCK_STALE).";
+    };
+    unlockMinAge = lib.mkOption {
+      type = lib.types.nullOr lib.types.str;
+      default = null;
+      example = "2h";
+      description = "Age after which a lock taken on another machine counts as stale (UNLOCK_MIN_AGE, at least 30m); 1h when null.";
+    };
+    secretPatterns = lib.mkOption {
+      type = lib.types.listOf lib.types.str;
+      default = [];
+      example = ["*.pem" "id_*" "*.kdbx"];
+      description = "File name patterns the content policy flags regardless of content (BACKUP_SECRET_PATTERNS); empty keeps the built-in list.";
+    };
+    pathEncoding = lib.mkOption {
+      type = lib.types.nullOr (lib.types.enum [1 2]);
+      default = null;
+      description = "Naming of new repositories (REPO_PATH_ENCODING): 2 (default when null) escapes _ and % so every path gets its own repository, 1 is the old lossy naming. Existing repositories of either version keep being used.";
+    };
+    pathOverlap = lib.mkOption {
+      type = lib.types.nullOr (lib.types.enum ["warn" "skip-nested" "keep"]);
+      default = null;
+      description = "What happens to a backup path nested below another one (BACKUP_PATH_OVERLAP): warn (default when null), skip-nested, or keep both without a warning.";
+    };
+    sensitivePaths = lib.mkOption {
+      type = lib.types.listOf lib.types.str;
+      default = [];
+      example = ["~/.gnupg" "~/.ssh"];
+      description = "Paths only backed up by runs with --include-sensitive (or a confirmed prompt), tagged `sensitive` (BACKUP_SENSITIVE_PATHS; ~/ expands to every home in /home). Scheduled runs skip them.";
+    };
+    hostBasePaths = lib.mkOption {
+      type = lib.types.attrsOf lib.types.str;
+      default = {};
+      example = {
+        oldbox = "legacy";
+        nas = "/archive/restic";
+      };
+      description = "Hosts whose repositories live under an alternate prefix (HOST_BASE_PATHS): relative to the repository base, or from the bucket root with a leading slash.";
+    };
+
+    protectHosts = lib.mkOption {
+      type = lib.types.listOf lib.types.str;
+      default = [];
+      example = ["nas" "prod-db"];
+      description = "Hosts whose repositories destructive operations such as prune must never touch (PROTECT_HOSTS).";
+    };
+
+    restoreJobs = lib.mkOption {
+      type = lib.types.nullOr lib.types.ints.positive;
+      default = null;
+      description = "Repositories restored in parallel (RESTORE_JOBS); 4 when null.";
+    };
+
+    scanJobs = lib.mkOption {
+      type = lib.types.nullOr lib.types.ints.positive;
+      default = null;
+      description = "Repositories whose snapshots are listed at once when scanning a host (SCAN_JOBS); 8 when null.";
+    };
+
+    tuning = {
+      profile = lib.mkOption {
+        type = lib.types.enum ["auto" "off" "r2" "s3" "minio" "sftp" "local"];
+        default = "auto";
+        description = "restic settings profile for the storage backend (RESTIC_TUNING); auto detects it from repoBase.";
+      };
+
+      packSize = lib.mkOption {
+        type = lib.types.nullOr (lib.types.ints.between 4 128);
+        default = null;
+        description = "Pack size in MiB, overriding the profile (RESTIC_TUNING_PACK_SIZE).";
+      };
+
+      connections = lib.mkOption {
+        type = lib.types.nullOr lib.types.ints.positive;
+        default = null;
+        description = "Backend connections, overriding the profile (RESTIC_TUNING_CONNECTIONS).";
+      };
+
+      retryLock = lib.mkOption {
+        type = lib.types.nullOr lib.types.str;
+        default = null;
+        example = "5m";
+        description = "How long restic waits for a locked repository, overriding the profile (RESTIC_TUNING_RETRY_LOCK); \"none\" fails immediately.";
+      };
+
+      compression = lib.mkOption {
+        type = lib.types.nullOr (lib.types.enum ["auto" "off" "max"]);
+        default = null;
+        description = "Compression of new backup data (RESTIC_COMPRESSION); restic's auto when null.";
+      };
+
+      pathCompression = lib.mkOption {
+        type = lib.types.attrsOf (lib.types.enum ["auto" "off" "max"]);
+        default = {};
+        example = {
+          "/var/log" = "max";
+          "/srv/media" = "off";
+        };
+        description = "Compression per backup path and everything below it (BACKUP_PATH_COMPRESSION), overriding compression.";
+      };
+    };
+
+    retry = {
+      attempts = lib.mkOption {
+        type = lib.types.nullOr lib.types.ints.positive;
+        default = null;
+        description = "Attempts per restic command after network errors or throttling, the first one included (RESTIC_RETRY_ATTEMPTS); 3 when null, 1 disables retries.";
+      };
+
+      baseDelay = lib.mkOption {
+        type = lib.types.nullOr lib.types.ints.unsigned;
+        default = null;
+        description = "Seconds before the first retry, doubled for each further one (RESTIC_RETRY_BASE_DELAY); 2 when null.";
+      };
+
+      maxDelay = lib.mkOption {
+        type = lib.types.nullOr lib.types.ints.unsigned;
+        default = null;
+        description = "Longest wait between two attempts in seconds (RESTIC_RETRY_MAX_DELAY); 60 when null.";
+      };
+    };
+
+    globalCommandTimeout = lib.mkOption {
+      type = lib.types.nullOr lib.types.ints.unsigned;
+      default = null;
+      description = "Upper bound in seconds for any single restic command, backups and restores included, and any S3 request (COMMAND_TIMEOUT_SECS); no bound when null.";
+    };
+
+    commandTimeout = lib.mkOption {
+      type = lib.types.nullOr lib.types.ints.unsigned;
+      default = null;
+      description = "Seconds a restic metadata command (snapshots, stats, ls) may run before it is killed (RESTIC_COMMAND_TIMEOUT); 1800 when null, 0 disables. Backups, restores, copies, prune and check are never cut off.";
+    };
+
+    discoverHomes = lib.mkOption {
+      type = lib.types.bool;
+      default = false;
+      description = "Back up every /home/<user> directory in addition to backupPaths (BACKUP_DISCOVER_HOMES). backupPaths entries may also use * and ? globs, e.g. /home/*/Documents.";
+    };
+
+    homeExclude = lib.mkOption {
+      type = lib.types.listOf lib.types.str;
+      default = [];
+      example = ["guest" "test*"];
+      description = "Users (names or * / ? patterns) left out by discoverHomes (BACKUP_HOME_EXCLUDE).";
+    };
+
+    logRetentionDays = lib.mkOption {
+      type = lib.types.nullOr lib.types.ints.unsigned;
+      default = null;
+      description = "Days rolled log files in /var/log/restic-backup are kept (LOG_RETENTION_DAYS); 30 when null, 0 keeps them forever.";
+    };
+
+    logMaxSize = lib.mkOption {
+      type = lib.types.nullOr lib.types.str;
+      default = null;
+      example = "500M";
+      description = "Total size the log files may take (LOG_MAX_SIZE); the oldest rolled files are deleted first. No cap when null.";
+    };
+
+    prune = {
+      schedule = lib.mkOption {
+        type = lib.types.nullOr lib.types.str;
+        default = null;
+        example = "Sun 03:00";
+        description = "OnCalendar schedule for repository compaction via `prune` (null disables the prune timer).";
+      };
+
+      preset = lib.mkOption {
+        type = lib.types.nullOr (lib.types.enum ["r2" "s3" "local"]);
+        default = null;
+        description = "Repack tuning preset (PRUNE_PRESET); detected from the repository base when null.";
+      };
+
+      maxUnused = lib.mkOption {
+        type = lib.types.nullOr lib.types.str;
+        default = null;
+        example = "10%";
+        description = "Value for restic --max-unused (PRUNE_MAX_UNUSED); overrides the preset.";
+      };
+
+      repackCacheableOnly = lib.mkOption {
+        type = lib.types.nullOr lib.types.bool;
+        default = null;
+        description = "Pass restic --repack-cacheable-only (PRUNE_REPACK_CACHEABLE_ONLY); overrides the preset.";
+      };
+
+      retention = lib.mkOption {
+        type = lib.types.nullOr lib.types.str;
+        default = null;
+        example = "daily=7,weekly=4,monthly=12";
+        description = "Default keep-* policy for scheduled prunes (RETENTION_POLICY); a host group's retention wins.";
+      };
+
+      categoryRetention = lib.mkOption {
+        type = lib.types.attrsOf lib.types.str;
+        default = {};
+        example = {
+          user_home = "daily=30,monthly=12";
+          docker_volume = "daily=7";
+        };
+        description = "keep-* policy per repository category (user_home, docker_volume, system) as RETENTION_POLICY_<CATEGORY>; wins over retention and host group retention.";
+      };
+
+      keepTags = lib.mkOption {
+        type = lib.types.listOf lib.types.str;
+        default = [];
+        example = ["critical" "pre-upgrade"];
+        description = "Tags whose snapshots prune never forgets (RETENTION_KEEP_TAGS).";
+      };
+
+      jobs = lib.mkOption {
+        type = lib.types.nullOr lib.types.ints.positive;
+        default = null;
+        description = "Repositories pruned in parallel (PRUNE_JOBS); 4 when null.";
+      };
+
+      afterBackup = lib.mkOption {
+        type = lib.types.bool;
+        default = false;
+        description = "forget --prune each repository a backup run just saved a clean snapshot to, with the retention above (PRUNE_AFTER_BACKUP).";
+      };
     };
+
+    check = {
+      schedule = lib.mkOption {
+        type = lib.types.nullOr lib.types.str;
+        default = null;
+        example = "Sat 04:00";
+        description = "OnCalendar schedule for repository integrity verification via `check` (null disables the check timer).";
+      };
+
+      readDataSubset = lib.mkOption {
+        type = lib.types.nullOr lib.types.str;
+        default = null;
+        example = "1/10";
+        description = "Pass restic --read-data-subset so scheduled checks also verify a share of the pack data.";
+      };
+    };
+
+    drill = {
+      schedule = lib.mkOption {
+        type = lib.types.nullOr lib.types.str;
+        default = null;
+        example = "Sun 05:00";
+        description = "OnCalendar schedule for restore drills via `drill`: a random sample of repositories is restored to a private /tmp, verified and deleted (null disables the drill timer).";
+      };
+
+      sample = lib.mkOption {
+        type = lib.types.ints.positive;
+        default = 3;
+        description = "Repositories restored per scheduled drill.";
+      };
+    };
+
+    mirror = {
+      repoBase = lib.mkOption {
+        type = lib.types.nullOr lib.types.str;
+        default = null;
+        example = "sftp:backup@offsite:/srv/restic";
+        description = "Secondary repository base that `mirror` copies snapshots to (MIRROR_REPO_BASE); put MIRROR_PASSWORD in the secrets file if it differs.";
+      };
+
+      afterBackup = lib.mkOption {
+        type = lib.types.bool;
+        default = false;
+        description = "Mirror the host's repositories after every successful backup run (MIRROR_AFTER_BACKUP).";
+      };
+    };
+
+    digest = {
+      schedule = lib.mkOption {
+        type = lib.types.nullOr lib.types.str;
+        default = null;
+        example = "Mon 08:00";
+        description = "OnCalendar schedule for `report digest --send` (null disables the digest timer). Set REPORT_WEBHOOK_URL in the secrets file and/or emailTo.";
+      };
+
+      days = lib.mkOption {
+        type = lib.types.ints.positive;
+        default = 7;
+        description = "Length of the period each digest covers, in days.";
+      };
+
+      emailTo = lib.mkOption {
+        type = lib.types.nullOr lib.types.str;
+        default = null;
+        example = "ops@example.com";
+        description = "Comma-separated digest recipients (REPORT_EMAIL_TO), mailed via sendmail.";
+      };
+    };
+
     enable = lib.mkEnableOption "Restic backup service";
 
     package = lib.mkOption {
@@ -148,14 +679,21 @@ in {
         type = lib.types.nullOr lib.types.path;
         default = null;
         example = lib.literalExpression "config.sops.secrets.restic-password.path";
-        description = "Path to file containing restic repository password";
+        description = "Path to file containing restic repository password (RESTIC_PASSWORD_FILE); leave RESTIC_PASSWORD out of secretsFile when set.";
+      };
+
+      passwordCommand = lib.mkOption {
+        type = lib.types.nullOr lib.types.str;
+        default = null;
+        example = "cat /run/agenix/restic";
+        description = "Shell command printing the restic repository password (RESTIC_PASSWORD_COMMAND); exclusive with passwordFile.";
       };
 
       repoBase = lib.mkOption {
         type = lib.types.nullOr lib.types.str;
         default = null;
         example = "s3:https://account-id.r2.cloudflarestorage.com/bucket/restic";
-        description = "Restic repository base URL (can also be provided via secretsFile)";
+        description = "Restic repository base: s3:https://..., sftp:user@host:/path or a local directory (can also be provided via secretsFile). AWS credentials are only needed for s3.";
       };
     };
 
@@ -299,6 +837,7 @@ in {
       ];
 
       systemd.services.restic-backup = {
+        path = lib.optional (cfg.dockerQuiesce != "off") pkgs.docker;
         description = "Restic backup service";
         after = ["network-online.target"];
         wants = ["network-online.target"];
@@ -311,6 +850,8 @@ in {
           Group = cfg.group;
           WorkingDirectory = "/"; # Explicitly set to root to match default behavior
           ExecStart = "${backupScript}";
+          # 75: skipped because another run (e.g. an external cron job) holds the run lock
+          SuccessExitStatus = [75];
 
           # Security settings
           PrivateTmp = true;
@@ -345,6 +886,135 @@ in {
         };
       };
 
+      # Optional compaction service and timer
+      systemd.services.restic-backup-prune = lib.mkIf (cfg.prune.schedule != null) {
+        description = "Restic repository compaction";
+        after = ["network-online.target"];
+        wants = ["network-online.target"];
+
+        serviceConfig = {
+          Type = "oneshot";
+          User = cfg.user;
+          Group = cfg.group;
+          WorkingDirectory = "/";
+          ExecStart = "${pruneScript}";
+
+          PrivateTmp = true;
+          ProtectSystem = "strict";
+          ReadWritePaths = ["/tmp" "/var/log"];
+          NoNewPrivileges = true;
+
+          StandardOutput = "journal";
+          StandardError = "journal";
+          SyslogIdentifier = "restic-backup-prune";
+        };
+      };
+
+      systemd.timers.restic-backup-prune = lib.mkIf (cfg.prune.schedule != null) {
+        description = "Timer for restic repository compaction";
+        wantedBy = ["timers.target"];
+        timerConfig = {
+          OnCalendar = cfg.prune.schedule;
+          Persistent = true;
+        };
+      };
+
+      # Optional repository check service and timer
+      systemd.services.restic-backup-check = lib.mkIf (cfg.check.schedule != null) {
+        description = "Restic repository integrity check";
+        after = ["network-online.target"];
+        wants = ["network-online.target"];
+
+        serviceConfig = {
+          Type = "oneshot";
+          User = cfg.user;
+          Group = cfg.group;
+          WorkingDirectory = "/";
+          ExecStart = "${checkScript}";
+
+          PrivateTmp = true;
+          ProtectSystem = "strict";
+          ReadWritePaths = ["/tmp" "/var/log"];
+
+          StandardOutput = "journal";
+          StandardError = "journal";
+          SyslogIdentifier = "restic-backup-check";
+        };
+      };
+
+      systemd.timers.restic-backup-check = lib.mkIf (cfg.check.schedule != null) {
+        description = "Timer for the restic repository integrity check";
+        wantedBy = ["timers.target"];
+        timerConfig = {
+          OnCalendar = cfg.check.schedule;
+          Persistent = true;
+        };
+      };
+
+      # Optional restore drill service and timer
+      systemd.services.restic-backup-drill = lib.mkIf (cfg.drill.schedule != null) {
+        description = "Restic restore drill";
+        after = ["network-online.target"];
+        wants = ["network-online.target"];
+
+        serviceConfig = {
+          Type = "oneshot";
+          User = cfg.user;
+          Group = cfg.group;
+          WorkingDirectory = "/";
+          ExecStart = "${drillScript}";
+
+          PrivateTmp = true;
+          ProtectSystem = "strict";
+          ReadWritePaths = ["/tmp" "/var/log"];
+
+          StandardOutput = "journal";
+          StandardError = "journal";
+          SyslogIdentifier = "restic-backup-drill";
+        };
+      };
+
+      systemd.timers.restic-backup-drill = lib.mkIf (cfg.drill.schedule != null) {
+        description = "Timer for the restic restore drill";
+        wantedBy = ["timers.target"];
+        timerConfig = {
+          OnCalendar = cfg.drill.schedule;
+          Persistent = true;
+        };
+      };
+
+      # Optional digest service and timer
+      systemd.services.restic-backup-digest = lib.mkIf (cfg.digest.schedule != null) {
+        description = "Restic backup digest";
+        after = ["network-online.target"];
+        wants = ["network-online.target"];
+
+        serviceConfig = {
+          Type = "oneshot";
+          User = cfg.user;
+          Group = cfg.group;
+          WorkingDirectory = "/";
+          ExecStart = "${digestScript}";
+
+          PrivateTmp = true;
+          ProtectSystem = "strict";
+          ReadWritePaths = ["/tmp" "/var/log"];
+
+          StandardOutput = "journal";
+          StandardError = "journal";
+          SyslogIdentifier = "restic-backup-digest";
+        };
+      };
+
+      systemd.timers.restic-backup-digest = lib.mkIf (cfg.digest.schedule != null) {
+        description = "Timer for the restic backup digest";
+        wantedBy = ["timers.target"];
+        timerConfig = {
+          OnCalendar = cfg.digest.schedule;
+          Persistent = true;
+        };
+      };
+
       # Ensure the package and CLI wrapper are available in the system
       environment.systemPackages = [cfg.package cliWrapper];
 
@@ -365,7 +1035,45 @@ in {
           ++ lib.optional (cfg.exclude.file != null) ("BACKUP_EXCLUDE_FILE=" + (toString cfg.exclude.file))
           ++ lib.optional (cfg.exclude.file == null && cfg.exclude.patterns != []) "BACKUP_EXCLUDE_FILE=/etc/restic-backup.exclude"
           ++ lib.optional (cfg.exclude.largerThan != null) ("BACKUP_EXCLUDE_LARGER_THAN=" + cfg.exclude.largerThan)
-          ++ lib.optional (cfg.exclude.ifPresent != []) ("BACKUP_EXCLUDE_IF_PRESENT=" + (lib.concatStringsSep "," cfg.exclude.ifPresent));
+          ++ lib.optional (cfg.exclude.ifPresent != []) ("BACKUP_EXCLUDE_IF_PRESENT=" + (lib.concatStringsSep "," cfg.exclude.ifPresent))
+          ++ lib.optional (cfg.digest.emailTo != null) ("REPORT_EMAIL_TO=" + cfg.digest.emailTo)
+          ++ lib.optional (cfg.sensitivePaths != []) ("BACKUP_SENSITIVE_PATHS=" + (lib.concatStringsSep "," cfg.sensitivePaths))
+          ++ lib.optional (cfg.pathOverlap != null) ("BACKUP_PATH_OVERLAP=" + cfg.pathOverlap)
+          ++ lib.optional (cfg.pathEncoding != null) ("REPO_PATH_ENCODING=" + toString cfg.pathEncoding)
+          ++ lib.optional (cfg.contentPolicy != "off") ("BACKUP_CONTENT_POLICY=" + cfg.contentPolicy)
+          ++ lib.optional (cfg.dockerQuiesce != "off") ("BACKUP_DOCKER_QUIESCE=" + cfg.dockerQuiesce)
+          ++ lib.optional (cfg.backupConcurrency != null) ("BACKUP_CONCURRENCY=" + toString cfg.backupConcurrency)
+          ++ lib.optional cfg.autoUnlockStale "BACKUP_AUTO_UNLOCK_STALE=true"
+          ++ lib.optional (cfg.restic.passwordFile != null) ("RESTIC_PASSWORD_FILE=" + toString cfg.restic.passwordFile)
+          ++ lib.optional (cfg.restic.passwordCommand != null) ("RESTIC_PASSWORD_COMMAND=" + cfg.restic.passwordCommand)
+          ++ lib.optional (cfg.unlockMinAge != null) ("UNLOCK_MIN_AGE=" + cfg.unlockMinAge)
+          ++ lib.optional (cfg.secretPatterns != []) ("BACKUP_SECRET_PATTERNS=" + (lib.concatStringsSep "," cfg.secretPatterns))
+          ++ lib.optional (cfg.hostBasePaths != {}) ("HOST_BASE_PATHS=" + (lib.concatStringsSep "," (lib.mapAttrsToList (host: prefix: "${host}=${prefix}") cfg.hostBasePaths)))
+          ++ lib.optional (cfg.protectHosts != []) ("PROTECT_HOSTS=" + (lib.concatStringsSep "," cfg.protectHosts))
+          ++ lib.optional (cfg.restoreJobs != null) ("RESTORE_JOBS=" + toString cfg.restoreJobs)
+          ++ lib.optional (cfg.scanJobs != null) ("SCAN_JOBS=" + toString cfg.scanJobs)
+          ++ lib.optional (cfg.tuning.profile != "auto") ("RESTIC_TUNING=" + cfg.tuning.profile)
+          ++ lib.optional (cfg.tuning.packSize != null) ("RESTIC_TUNING_PACK_SIZE=" + toString cfg.tuning.packSize)
+          ++ lib.optional (cfg.tuning.connections != null) ("RESTIC_TUNING_CONNECTIONS=" + toString cfg.tuning.connections)
+          ++ lib.optional (cfg.tuning.retryLock != null) ("RESTIC_TUNING_RETRY_LOCK=" + cfg.tuning.retryLock)
+          ++ lib.optional (cfg.tuning.compression != null) ("RESTIC_COMPRESSION=" + cfg.tuning.compression)
+          ++ lib.optional (cfg.tuning.pathCompression != {}) ("BACKUP_PATH_COMPRESSION=" + lib.concatStringsSep "," (lib.mapAttrsToList (path: mode: "${path}=${mode}") cfg.tuning.pathCompression))
+          ++ lib.optional (cfg.retry.attempts != null) ("RESTIC_RETRY_ATTEMPTS=" + toString cfg.retry.attempts)
+          ++ lib.optional (cfg.retry.baseDelay != null) ("RESTIC_RETRY_BASE_DELAY=" + toString cfg.retry.baseDelay)
+          ++ lib.optional (cfg.retry.maxDelay != null) ("RESTIC_RETRY_MAX_DELAY=" + toString cfg.retry.maxDelay)
+          ++ lib.optional (cfg.commandTimeout != null) ("RESTIC_COMMAND_TIMEOUT=" + toString cfg.commandTimeout)
+          ++ lib.optional cfg.discoverHomes "BACKUP_DISCOVER_HOMES=true"
+          ++ lib.optional (cfg.homeExclude != []) ("BACKUP_HOME_EXCLUDE=" + (lib.concatStringsSep "," cfg.homeExclude))
+          ++ lib.optional (cfg.logRetentionDays != null) ("LOG_RETENTION_DAYS=" + toString cfg.logRetentionDays)
+          ++ lib.optional (cfg.logMaxSize != null) ("LOG_MAX_SIZE=" + cfg.logMaxSize)
+          ++ lib.optional (cfg.globalCommandTimeout != null) ("COMMAND_TIMEOUT_SECS=" + toString cfg.globalCommandTimeout)
+          ++ lib.optional (cfg.prune.preset != null) ("PRUNE_PRESET=" + cfg.prune.preset)
+          ++ lib.optional (cfg.prune.maxUnused != null) ("PRUNE_MAX_UNUSED=" + cfg.prune.maxUnused)
+          ++ lib.optional (cfg.prune.repackCacheableOnly != null) ("PRUNE_REPACK_CACHEABLE_ONLY=" + lib.boolToString cfg.prune.repackCacheableOnly)
+          ++ lib.optional (cfg.prune.retention != null) ("RETENTION_POLICY=" + cfg.prune.retention)
+          ++ lib.mapAttrsToList (category: policy: "RETENTION_POLICY_${lib.toUpper category}=${policy}") cfg.prune.categoryRetention
+          ++ lib.optional (cfg.prune.keepTags != []) ("RETENTION_KEEP_TAGS=" + lib.concatStringsSep "," cfg.prune.keepTags)
+          ++ lib.optional (cfg.prune.jobs != null) ("PRUNE_JOBS=" + toString cfg.prune.jobs);
       in
         (lib.concatStringsSep "\n" lines) + "\n";
 
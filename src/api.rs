@@ -0,0 +1,104 @@
+//! Non-interactive entry points for embedding the service
+//!
+//! Every function here runs without a terminal: prompts are answered with the unattended
+//! defaults (sensitive paths and possible credentials are left out, restores clear the
+//! target and apply the requested action), so dialoguer is never reached.
+
+use crate::config::Config;
+use crate::errors::BackupServiceError;
+use crate::shared::backup_workflow::{BackupOptions, BackupSummary, BackupWorkflow};
+use crate::shared::operations::{RepositoryOperations, ScanResult};
+use crate::shared::restore_workflow::{RestoreOptions, RestoreWorkflow};
+
+/// What to restore; the host and timestamp default to this host's latest snapshot
+#[derive(Debug, Clone, Default)]
+pub struct RestoreRequest {
+    /// Original path of the backup, e.g. `/home/tim`
+    pub path: String,
+    pub host: Option<String>,
+    /// Restore point in any format the CLI accepts (`2h ago`, `yesterday 14:00`, RFC 3339)
+    pub timestamp: Option<String>,
+    pub options: RestoreOptions,
+}
+
+/// Back up the configured paths plus `paths`, returning the result of every path
+///
+/// Failed paths under `BACKUP_ERROR_POLICY=continue` are part of the summary rather than an
+/// error; check [`BackupSummary::is_success`].
+pub async fn backup(
+    config: Config,
+    paths: Vec<String>,
+    options: BackupOptions,
+) -> Result<BackupSummary, BackupServiceError> {
+    let options = non_interactive_backup(options);
+    BackupWorkflow::new(config, paths, options)?.run().await
+}
+
+/// Restore one path without prompting
+pub async fn restore(config: Config, request: RestoreRequest) -> Result<(), BackupServiceError> {
+    let workflow = RestoreWorkflow::new(
+        config,
+        request.host,
+        Some(request.path),
+        request.timestamp,
+        non_interactive_restore(request.options),
+    )?;
+    workflow.execute_interactive_restore().await
+}
+
+/// Hosts that have backups in the bucket
+pub async fn hosts(config: Config) -> Result<Vec<String>, BackupServiceError> {
+    config.set_aws_env()?;
+    RepositoryOperations::new(config)?
+        .get_available_hosts()
+        .await
+}
+
+/// Repositories and snapshots of one host, plus the repositories that could not be read
+pub async fn repositories(config: Config, host: &str) -> Result<ScanResult, BackupServiceError> {
+    config.set_aws_env()?;
+    RepositoryOperations::new(config)?
+        .collect_backup_data(host)
+        .await
+}
+
+/// Force the unattended answers; JSON output is meant for a terminal, not an embedding tool
+fn non_interactive_backup(options: BackupOptions) -> BackupOptions {
+    BackupOptions {
+        unattended: true,
+        json_output: false,
+        ..options
+    }
+}
+
+fn non_interactive_restore(options: RestoreOptions) -> RestoreOptions {
+    RestoreOptions {
+        assume_yes: true,
+        json_output: false,
+        ..options
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_options_never_prompt() {
+        let backup = non_interactive_backup(BackupOptions {
+            json_output: true,
+            seed: true,
+            ..BackupOptions::default()
+        });
+        assert!(backup.unattended);
+        assert!(!backup.json_output);
+        assert!(backup.seed);
+
+        let restore = non_interactive_restore(RestoreOptions {
+            action: Some("copy".to_string()),
+            ..RestoreOptions::default()
+        });
+        assert!(restore.assume_yes);
+        assert_eq!(restore.action.as_deref(), Some("copy"));
+    }
+}
@@ -1,11 +1,28 @@
 use crate::config::Config;
 use crate::errors::BackupServiceError;
-use crate::shared::backup_workflow::execute_backup_workflow;
+use crate::shared::backup_workflow::{BackupOptions, execute_backup_workflow};
+use crate::shared::instance_lock::InstanceLock;
+use chrono::Utc;
+use tracing::debug;
 
 /// Main entry point for backup operations - now uses the modular BackupWorkflow
+///
+/// Holds the `run` instance lock so a second trigger (timer + cron) becomes a no-op
+/// instead of colliding with the first on restic's repository locks.
 pub async fn run_backup(
     config: Config,
     additional_paths: Vec<String>,
+    options: BackupOptions,
 ) -> Result<(), BackupServiceError> {
-    execute_backup_workflow(config, additional_paths).await
+    // A dry run writes nothing, so it may run next to a real one
+    let _lock = if options.dry_run {
+        None
+    } else {
+        let lock = InstanceLock::acquire("run")?
+            .map_err(|holder| BackupServiceError::AlreadyRunning(holder.describe(Utc::now())))?;
+        debug!(lock = %lock.path().display(), "Acquired run lock");
+        Some(lock)
+    };
+
+    execute_backup_workflow(config, additional_paths, options).await
 }
@@ -0,0 +1,12 @@
+use crate::config::Config;
+use crate::errors::BackupServiceError;
+use crate::shared::check_workflow::{CheckOptions, execute_check_workflow};
+
+// CLI command to verify the integrity of all repositories of a host
+pub async fn run_check(
+    config: Config,
+    host: Option<String>,
+    options: CheckOptions,
+) -> Result<(), BackupServiceError> {
+    execute_check_workflow(config, host, options).await
+}
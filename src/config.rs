@@ -1,8 +1,137 @@
 use crate::errors::BackupServiceError;
+use crate::shared::config_file;
+use crate::shared::secret_source::SecretSource;
+use crate::shared::secrets;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::env;
 use std::path::PathBuf;
 
+/// Env var mapping hosts to alternate base prefixes: `oldbox=legacy,nas=/archive/restic`
+pub const HOST_BASE_PATHS_ENV_VAR: &str = "HOST_BASE_PATHS";
+
+/// Per-host base prefixes for repositories outside the standard `<base>/<host>/` layout
+///
+/// A relative prefix is placed below the repository base (`<base>/legacy/<host>/...`);
+/// a prefix starting with `/` is taken from the bucket root (`<bucket>/archive/<host>/...`).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HostBasePaths {
+    prefixes: BTreeMap<String, String>,
+}
+
+impl HostBasePaths {
+    pub fn parse(value: &str) -> Result<Self, BackupServiceError> {
+        let mut prefixes = BTreeMap::new();
+        for entry in value.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let (host, prefix) = entry
+                .split_once('=')
+                .map(|(h, p)| (h.trim(), p.trim().trim_end_matches('/')))
+                .filter(|(h, p)| !h.is_empty() && !p.is_empty() && *p != "/")
+                .ok_or_else(|| {
+                    BackupServiceError::ConfigurationError(format!(
+                        "Invalid {} entry: {}.\n\nExpected host=prefix pairs, e.g. oldbox=legacy,nas=/archive/restic",
+                        HOST_BASE_PATHS_ENV_VAR, entry
+                    ))
+                })?;
+            prefixes.insert(host.to_string(), prefix.to_string());
+        }
+        Ok(Self { prefixes })
+    }
+
+    pub fn from_env() -> Result<Self, BackupServiceError> {
+        Self::parse(&env::var(HOST_BASE_PATHS_ENV_VAR).unwrap_or_default())
+    }
+
+    pub fn prefix_for(&self, host: &str) -> Option<&str> {
+        self.prefixes.get(host).map(String::as_str)
+    }
+
+    /// Mapped hosts with their prefixes
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.prefixes.iter().map(|(h, p)| (h.as_str(), p.as_str()))
+    }
+}
+
+/// Storage behind RESTIC_REPO_BASE
+#[derive(Debug, Clone, PartialEq)]
+pub enum RepoBackend {
+    /// `s3:https://<endpoint>/<bucket>[/base]`
+    S3,
+    /// `sftp:user@host:/path` or `sftp://user@host[:port]//path`
+    Sftp {
+        target: String,
+        port: Option<u16>,
+        path: String,
+    },
+    /// `/path` or `local:/path`
+    Local { path: String },
+}
+
+impl RepoBackend {
+    pub fn parse(repo_base: &str) -> Result<Self, BackupServiceError> {
+        let repo_base = repo_base.trim().trim_end_matches('/');
+        if repo_base.starts_with("s3:") {
+            return Ok(RepoBackend::S3);
+        }
+        if let Some(rest) = repo_base.strip_prefix("sftp:") {
+            let (target, port, path) = match rest.strip_prefix("//") {
+                // sftp://user@host:2222//srv/restic
+                Some(url) => {
+                    let (authority, path) = url.split_once('/').unwrap_or((url, ""));
+                    match authority.rsplit_once(':') {
+                        Some((host, port)) if port.parse::<u16>().is_ok() => {
+                            (host, port.parse().ok(), path)
+                        }
+                        _ => (authority, None, path),
+                    }
+                }
+                // sftp:user@host:/srv/restic
+                None => {
+                    let (target, path) = rest.split_once(':').unwrap_or((rest, ""));
+                    (target, None, path)
+                }
+            };
+            if target.is_empty() || !path.starts_with('/') {
+                return Err(BackupServiceError::ConfigurationError(format!(
+                    "Invalid SFTP repository base: {}.\n\nUse an absolute path, e.g. sftp:backup@nas:/srv/restic or sftp://backup@nas:2222//srv/restic",
+                    repo_base
+                )));
+            }
+            return Ok(RepoBackend::Sftp {
+                target: target.to_string(),
+                port,
+                path: path.to_string(),
+            });
+        }
+        let local = repo_base.strip_prefix("local:").unwrap_or(repo_base);
+        if local.starts_with('/') {
+            return Ok(RepoBackend::Local {
+                path: local.to_string(),
+            });
+        }
+        Err(BackupServiceError::ConfigurationError(format!(
+            "Unsupported repository base: {}.\n\nRESTIC_REPO_BASE must be s3:https://<endpoint>/<bucket>[/base], sftp:user@host:/path or an absolute local directory",
+            repo_base
+        )))
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            RepoBackend::S3 => "s3",
+            RepoBackend::Sftp { .. } => "sftp",
+            RepoBackend::Local { .. } => "local",
+        }
+    }
+
+    /// Directory holding the repositories on SFTP and local backends
+    pub fn path(&self) -> Option<&str> {
+        match self {
+            RepoBackend::S3 => None,
+            RepoBackend::Sftp { path, .. } | RepoBackend::Local { path } => Some(path),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
     pub restic_password: String,
@@ -16,7 +145,11 @@ pub struct Config {
 }
 
 impl Config {
-    pub fn load() -> Result<Self, BackupServiceError> {
+    /// Build the configuration from the environment, filling unset values from the TOML
+    /// settings file (see [`config_file::apply`] for the precedence)
+    pub async fn load() -> Result<Self, BackupServiceError> {
+        config_file::apply()?;
+
         // If a secrets file has been specified, verify it is readable for the current user.
         if let Ok(secrets_path) = std::env::var("BACKUP_SECRETS_FILE") {
             let path = std::path::Path::new(&secrets_path);
@@ -33,15 +166,34 @@ impl Config {
             }
         }
 
-        let restic_password = Self::required_var("RESTIC_PASSWORD")?;
-        let restic_repo_base = Self::required_var("RESTIC_REPO_BASE")?;
-        let aws_access_key_id = Self::required_var("AWS_ACCESS_KEY_ID")?;
-        let aws_secret_access_key = Self::required_var("AWS_SECRET_ACCESS_KEY")?;
+        // SECRET_PROVIDER (Vault, SOPS) values win over the environment
+        let secrets = secrets::load().await?;
+        // Without a provider value: RESTIC_PASSWORD, or its file, command or keyring entry
+        let restic_password = match secrets.get("RESTIC_PASSWORD") {
+            Some(password) => password.clone(),
+            None => match SecretSource::from_env()? {
+                Some(source) => source.resolve()?,
+                None => Self::required_var("RESTIC_PASSWORD")?,
+            },
+        };
+        let restic_repo_base = Self::secret_var(&secrets, "RESTIC_REPO_BASE")?;
+        // S3 credentials are only required when the repositories live on S3
+        let s3 = RepoBackend::parse(&restic_repo_base)? == RepoBackend::S3;
+        let s3_var = |key: &str| match s3 {
+            true => Self::secret_var(&secrets, key),
+            false => Ok(secrets
+                .get(key)
+                .cloned()
+                .or_else(|| env::var(key).ok())
+                .unwrap_or_default()),
+        };
+        let aws_access_key_id = s3_var("AWS_ACCESS_KEY_ID")?;
+        let aws_secret_access_key = s3_var("AWS_SECRET_ACCESS_KEY")?;
 
         let aws_default_region =
             env::var("AWS_DEFAULT_REGION").unwrap_or_else(|_| "auto".to_string());
 
-        let aws_s3_endpoint = Self::required_var("AWS_S3_ENDPOINT")?;
+        let aws_s3_endpoint = s3_var("AWS_S3_ENDPOINT")?;
 
         let backup_paths = env::var("BACKUP_PATHS")
             .unwrap_or_default()
@@ -72,11 +224,40 @@ impl Config {
     // Provide a clearer error when required config values are missing
     fn required_var(key: &str) -> Result<String, BackupServiceError> {
         env::var(key).map_err(|_| BackupServiceError::ConfigurationError(format!(
-            "Missing required configuration: {}.\n\nExpected env file (one per line; keys must be CAPITALIZED exactly as shown):\n\n  RESTIC_PASSWORD=...\n  RESTIC_REPO_BASE=s3:https://<endpoint>/<bucket>[/optional/base]\n  AWS_ACCESS_KEY_ID=...\n  AWS_SECRET_ACCESS_KEY=...\n  AWS_DEFAULT_REGION=auto\n  AWS_S3_ENDPOINT=https://<endpoint>\n  BACKUP_PATHS=/path/one,/path/two (optional)\n  BACKUP_HOSTNAME=custom-host (optional)",
+            "Missing required configuration: {}.\n\nExpected env file (one per line; keys must be CAPITALIZED exactly as shown):\n\n  RESTIC_PASSWORD=... (or RESTIC_PASSWORD_FILE, RESTIC_PASSWORD_COMMAND, RESTIC_PASSWORD_KEYRING)\n  RESTIC_REPO_BASE=s3:https://<endpoint>/<bucket>[/optional/base] (or sftp:user@host:/path, /local/dir)\n  AWS_ACCESS_KEY_ID=...\n  AWS_SECRET_ACCESS_KEY=...\n  AWS_DEFAULT_REGION=auto\n  AWS_S3_ENDPOINT=https://<endpoint>\n  BACKUP_PATHS=/path/one,/path/two (optional)\n  BACKUP_HOSTNAME=custom-host (optional)",
             key
         )))
     }
 
+    fn secret_var(
+        secrets: &BTreeMap<String, String>,
+        key: &str,
+    ) -> Result<String, BackupServiceError> {
+        match secrets.get(key) {
+            Some(value) => Ok(value.clone()),
+            None => Self::required_var(key),
+        }
+    }
+
+    /// Re-read the provider's credentials (fetched again once the secret cache expired)
+    ///
+    /// Long-running commands call this before each run so rotated credentials are picked up.
+    pub async fn reload_secrets(&mut self) -> Result<(), BackupServiceError> {
+        let secrets = secrets::load().await?;
+        for (key, field) in [
+            ("RESTIC_PASSWORD", &mut self.restic_password),
+            ("RESTIC_REPO_BASE", &mut self.restic_repo_base),
+            ("AWS_ACCESS_KEY_ID", &mut self.aws_access_key_id),
+            ("AWS_SECRET_ACCESS_KEY", &mut self.aws_secret_access_key),
+            ("AWS_S3_ENDPOINT", &mut self.aws_s3_endpoint),
+        ] {
+            if let Some(value) = secrets.get(key) {
+                *field = value.clone();
+            }
+        }
+        Ok(())
+    }
+
     pub fn s3_endpoint(&self) -> Result<String, BackupServiceError> {
         // Parse endpoint from s3:https://domain.com/bucket/path format
         if let Some(endpoint) = self.restic_repo_base.strip_prefix("s3:")
@@ -125,6 +306,19 @@ impl Config {
         Ok(String::new())
     }
 
+    pub fn backend(&self) -> Result<RepoBackend, BackupServiceError> {
+        RepoBackend::parse(&self.restic_repo_base)
+    }
+
+    /// Path of the repository base below the storage root: the key prefix inside the bucket
+    /// on S3, the directory without its leading `/` on SFTP and local backends
+    pub fn store_base_path(&self) -> Result<String, BackupServiceError> {
+        match self.backend()?.path() {
+            None => self.s3_base_path(),
+            Some(path) => Ok(path.trim_matches('/').to_string()),
+        }
+    }
+
     // Set environment variables for AWS SDK/CLI usage
     pub fn set_aws_env(&self) -> Result<(), BackupServiceError> {
         // SAFETY: Called once at startup before spawning threads or async tasks.
@@ -149,10 +343,71 @@ impl Config {
         hostname: &str,
         subpath: &str,
     ) -> Result<String, BackupServiceError> {
-        Ok(format!(
-            "{}/{}/{}",
-            self.restic_repo_base, hostname, subpath
-        ))
+        self.repo_url_with(&HostBasePaths::from_env()?, hostname, subpath)
+    }
+
+    /// Repository URL, honouring a host's alternate base prefix
+    pub fn repo_url_with(
+        &self,
+        base_paths: &HostBasePaths,
+        hostname: &str,
+        subpath: &str,
+    ) -> Result<String, BackupServiceError> {
+        let host_base = match base_paths.prefix_for(hostname) {
+            None => format!("{}/{}", self.restic_repo_base, hostname),
+            Some(prefix) => match prefix.strip_prefix('/') {
+                Some(from_root) => match self.backend()? {
+                    RepoBackend::S3 => format!(
+                        "s3:{}/{}/{}/{}",
+                        self.s3_endpoint()?,
+                        self.s3_bucket()?,
+                        from_root,
+                        hostname
+                    ),
+                    // SFTP and local repositories: the prefix replaces the base directory,
+                    // keeping the `sftp:user@host:` / `local:` part of the base
+                    backend => {
+                        let base = self.restic_repo_base.trim().trim_end_matches('/');
+                        let location = backend
+                            .path()
+                            .and_then(|path| base.strip_suffix(path))
+                            .unwrap_or_default();
+                        format!("{}{}/{}", location, prefix, hostname)
+                    }
+                },
+                None => format!("{}/{}/{}", self.restic_repo_base, prefix, hostname),
+            },
+        };
+        Ok(format!("{}/{}", host_base, subpath))
+    }
+
+    /// Path below the storage root (bucket or `/`) under which a host's repositories live
+    pub fn host_store_path(&self, hostname: &str) -> Result<String, BackupServiceError> {
+        self.host_store_path_with(&HostBasePaths::from_env()?, hostname)
+    }
+
+    pub fn host_store_path_with(
+        &self,
+        base_paths: &HostBasePaths,
+        hostname: &str,
+    ) -> Result<String, BackupServiceError> {
+        let base = match base_paths.prefix_for(hostname) {
+            None => self.store_base_path()?,
+            Some(prefix) => match prefix.strip_prefix('/') {
+                Some(from_root) => from_root.to_string(),
+                None => [self.store_base_path()?, prefix.to_string()]
+                    .iter()
+                    .filter(|p| !p.is_empty())
+                    .cloned()
+                    .collect::<Vec<_>>()
+                    .join("/"),
+            },
+        };
+        Ok(if base.is_empty() {
+            hostname.to_string()
+        } else {
+            format!("{}/{}", base, hostname)
+        })
     }
 
     // Backwards-compat shim if needed by tests calling older method name
@@ -525,4 +780,174 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_host_base_paths_relative_and_from_bucket_root() -> Result<(), BackupServiceError> {
+        let config = create_test_config("s3:https://s3.example.com/bucket/restic");
+        let base_paths = HostBasePaths::parse("oldbox=legacy/, nas=/archive/restic")?;
+
+        assert_eq!(
+            config.repo_url_with(&base_paths, "oldbox", "system/etc")?,
+            "s3:https://s3.example.com/bucket/restic/legacy/oldbox/system/etc"
+        );
+        assert_eq!(
+            config.repo_url_with(&base_paths, "nas", "system/etc")?,
+            "s3:https://s3.example.com/bucket/archive/restic/nas/system/etc"
+        );
+        assert_eq!(
+            config.repo_url_with(&base_paths, "web1", "system/etc")?,
+            "s3:https://s3.example.com/bucket/restic/web1/system/etc"
+        );
+
+        assert_eq!(
+            config.host_store_path_with(&base_paths, "oldbox")?,
+            "restic/legacy/oldbox"
+        );
+        assert_eq!(
+            config.host_store_path_with(&base_paths, "nas")?,
+            "archive/restic/nas"
+        );
+        assert_eq!(
+            config.host_store_path_with(&base_paths, "web1")?,
+            "restic/web1"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_repo_backend_parsing() -> Result<(), BackupServiceError> {
+        assert_eq!(
+            RepoBackend::parse("s3:https://s3.example.com/bucket")?,
+            RepoBackend::S3
+        );
+        assert_eq!(
+            RepoBackend::parse("sftp:backup@nas:/srv/restic/")?,
+            RepoBackend::Sftp {
+                target: "backup@nas".to_string(),
+                port: None,
+                path: "/srv/restic".to_string(),
+            }
+        );
+        assert_eq!(
+            RepoBackend::parse("sftp://backup@nas:2222//srv/restic")?,
+            RepoBackend::Sftp {
+                target: "backup@nas".to_string(),
+                port: Some(2222),
+                path: "/srv/restic".to_string(),
+            }
+        );
+        assert_eq!(
+            RepoBackend::parse("local:/mnt/backup")?,
+            RepoBackend::Local {
+                path: "/mnt/backup".to_string()
+            }
+        );
+        for invalid in ["sftp:nas:restic", "b2:bucket:path", "relative/dir"] {
+            assert!(RepoBackend::parse(invalid).is_err(), "{}", invalid);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_filesystem_backends_repo_urls_and_store_paths() -> Result<(), BackupServiceError> {
+        let base_paths = HostBasePaths::parse("oldbox=legacy,nas=/archive/restic")?;
+
+        let config = create_test_config("sftp:backup@nas:/srv/restic");
+        assert_eq!(
+            config.repo_url_with(&base_paths, "web1", "system/etc")?,
+            "sftp:backup@nas:/srv/restic/web1/system/etc"
+        );
+        assert_eq!(
+            config.repo_url_with(&base_paths, "nas", "system/etc")?,
+            "sftp:backup@nas:/archive/restic/nas/system/etc"
+        );
+        assert_eq!(
+            config.host_store_path_with(&base_paths, "oldbox")?,
+            "srv/restic/legacy/oldbox"
+        );
+        assert_eq!(
+            config.host_store_path_with(&base_paths, "nas")?,
+            "archive/restic/nas"
+        );
+
+        let config = create_test_config("sftp://backup@nas:2222//srv/restic");
+        assert_eq!(
+            config.repo_url_with(&base_paths, "nas", "system/etc")?,
+            "sftp://backup@nas:2222//archive/restic/nas/system/etc"
+        );
+
+        let config = create_test_config("/mnt/backup");
+        assert_eq!(
+            config.repo_url_with(&base_paths, "web1", "system/etc")?,
+            "/mnt/backup/web1/system/etc"
+        );
+        assert_eq!(
+            config.repo_url_with(&base_paths, "nas", "system/etc")?,
+            "/archive/restic/nas/system/etc"
+        );
+        assert_eq!(
+            config.host_store_path_with(&base_paths, "web1")?,
+            "mnt/backup/web1"
+        );
+        Ok(())
+    }
+
+    /// Object key restic writes an `s3:https://endpoint/bucket/...` repository under: Go's
+    /// `url.Parse` percent-decodes the path before the bucket is split off
+    fn s3_key_prefix(url: &str) -> String {
+        let path = url.split_once("://").unwrap().1.split_once('/').unwrap().1;
+        let bytes = path.as_bytes();
+        let mut decoded = Vec::new();
+        let mut idx = 0;
+        while idx < bytes.len() {
+            if bytes[idx] == b'%'
+                && let Some(byte) = path
+                    .get(idx + 1..idx + 3)
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            {
+                decoded.push(byte);
+                idx += 3;
+            } else {
+                decoded.push(bytes[idx]);
+                idx += 1;
+            }
+        }
+        let decoded = String::from_utf8(decoded).unwrap();
+        decoded.split_once('/').unwrap().1.to_string()
+    }
+
+    #[test]
+    fn test_escaped_repo_names_survive_s3_url_parsing() -> Result<(), BackupServiceError> {
+        use crate::shared::paths::{PathEncoding, PathMapper};
+        use std::path::Path;
+
+        let config = create_test_config("s3:https://s3.example.com/backups/restic");
+        let key = |native: &str| -> Result<String, BackupServiceError> {
+            let subpath = PathMapper::encode(Path::new(native), PathEncoding::Escaped);
+            Ok(s3_key_prefix(
+                &config.get_repo_url_for_host("web1", &subpath)?,
+            ))
+        };
+        assert_eq!(key("/var/log_app")?, "restic/web1/system/var_log~5Fapp");
+        assert_eq!(key("/var/log/app")?, "restic/web1/system/var_log_app");
+        assert_eq!(
+            key("/home/tim/100%_done")?,
+            "restic/web1/user_home/tim/100~25~5Fdone"
+        );
+        // The key decodes back to the path it was created for
+        assert_eq!(
+            PathMapper::repo_subpath_to_path(
+                key("/var/log_app")?.trim_start_matches("restic/web1/")
+            ),
+            Some(Path::new("/var/log_app").to_path_buf())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_host_base_paths_rejects_malformed_entries() {
+        for value in ["oldbox", "=legacy", "oldbox=", "oldbox=/"] {
+            assert!(HostBasePaths::parse(value).is_err(), "{}", value);
+        }
+    }
 }
@@ -0,0 +1,8 @@
+use crate::config::Config;
+use crate::errors::BackupServiceError;
+use crate::shared::daemon_workflow::{DaemonOptions, execute_daemon};
+
+// CLI command to stay resident and back up on a cron schedule
+pub async fn run_daemon(config: Config, options: DaemonOptions) -> Result<(), BackupServiceError> {
+    execute_daemon(config, options).await
+}
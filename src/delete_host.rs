@@ -0,0 +1,12 @@
+use crate::config::Config;
+use crate::errors::BackupServiceError;
+use crate::shared::delete_host_workflow::{DeleteHostOptions, execute_delete_host_workflow};
+
+// CLI command to remove every repository of a decommissioned host
+pub async fn run_delete_host(
+    config: Config,
+    host: String,
+    options: DeleteHostOptions,
+) -> Result<(), BackupServiceError> {
+    execute_delete_host_workflow(config, host, options).await
+}
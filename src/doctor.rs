@@ -0,0 +1,12 @@
+use crate::config::Config;
+use crate::errors::BackupServiceError;
+use crate::shared::doctor_workflow::{DoctorOptions, execute_doctor_workflow};
+
+// CLI command for the end-to-end health report
+pub async fn run_doctor(
+    config: Config,
+    host: Option<String>,
+    options: DoctorOptions,
+) -> Result<(), BackupServiceError> {
+    execute_doctor_workflow(config, host, options).await
+}
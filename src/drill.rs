@@ -0,0 +1,12 @@
+use crate::config::Config;
+use crate::errors::BackupServiceError;
+use crate::shared::drill_workflow::{DrillOptions, execute_drill_workflow};
+
+// CLI command to prove restores work by restoring a sample of repositories to scratch space
+pub async fn run_drill(
+    config: Config,
+    host: Option<String>,
+    options: DrillOptions,
+) -> Result<(), BackupServiceError> {
+    execute_drill_workflow(config, host, options).await
+}
@@ -16,6 +16,28 @@ pub enum BackupServiceError {
     #[error("Command execution failed: {0}")]
     CommandFailed(String),
 
+    #[error("Repository is locked: {0}")]
+    RepositoryLocked(String),
+
+    #[error("Wrong repository password: no key found for {0}")]
+    WrongPassword(String),
+
+    #[error("Storage quota exceeded")]
+    QuotaExceeded,
+
+    #[error("Clock skew: request time differs too much from server time")]
+    ClockSkew,
+
+    #[error("TLS error: {0}")]
+    TlsError(String),
+
+    #[error("Request throttled by storage backend")]
+    Throttled,
+
+    /// ENOSPC on this machine (restic's cache or temp files, a local repository)
+    #[error("Local disk full: {0}")]
+    DiskFull(String),
+
     // Context-specific operation errors
     #[error("Credential validation failed: {0}")]
     CredentialValidationFailed(#[source] Box<BackupServiceError>),
@@ -39,30 +61,184 @@ pub enum BackupServiceError {
     #[error("Command not found or execution error: {0}")]
     CommandNotFound(String),
 
+    /// The restic binary is older than the oldest supported release
+    #[error("Unsupported restic version: {binary} is {found}, {required} or newer is required")]
+    UnsupportedResticVersion {
+        binary: String,
+        found: String,
+        required: String,
+    },
+
     #[error("Configuration error: {0}")]
     ConfigurationError(String),
+
+    /// Another instance holds the operation's lock; the run is skipped, not failed
+    #[error("Already running: {0}")]
+    AlreadyRunning(String),
+
+    /// Some backup paths failed while others were saved
+    #[error("Partial backup: {0}")]
+    PartialBackup(String),
+
+    /// `status` found paths whose last successful backup is older than allowed
+    #[error("Stale backups: {0}")]
+    StaleBackups(String),
+
+    /// `status` found repositories over a REPO_MAX_* budget after the newest run
+    #[error("Repository budget exceeded: {0}")]
+    BudgetExceeded(String),
 }
 
+/// Process exit code of each failure type, for systemd OnFailure handlers and scripts
+/// (`--print-exit-codes` prints this table)
+pub const EXIT_CODES: &[(i32, &str)] = &[
+    (0, "success"),
+    (1, "other failure (a command or restic run failed)"),
+    (2, "invalid command-line arguments"),
+    (
+        10,
+        "authentication failed: credentials rejected or access denied",
+    ),
+    (11, "network error: repository unreachable"),
+    (12, "repository not found"),
+    (13, "repository locked by another process"),
+    (14, "wrong repository password"),
+    (15, "storage quota exceeded"),
+    (16, "clock skew between this machine and the storage"),
+    (17, "TLS error"),
+    (18, "throttled by the storage backend"),
+    (19, "local disk full"),
+    (
+        20,
+        "partial backup: some paths failed, the others were saved",
+    ),
+    (
+        21,
+        "stale backups: `status` found paths past their maximum age",
+    ),
+    (
+        22,
+        "over budget: `status` found repositories past a REPO_MAX_* budget",
+    ),
+    (30, "configuration error"),
+    (
+        31,
+        "external program missing, not executable or too old (restic)",
+    ),
+    (75, "skipped: another run holds the lock"),
+    (130, "interrupted by SIGINT/SIGTERM"),
+];
+
 impl BackupServiceError {
     pub fn with_validation_context(self) -> BackupServiceError {
         BackupServiceError::CredentialValidationFailed(Box::new(self))
     }
 
-    /// Standard error for AWS CLI execution failure
-    pub fn aws_command_failed() -> Self {
-        BackupServiceError::CommandNotFound("Failed to execute aws".to_string())
-    }
-
     /// Standard error for restic command execution failure
     pub fn restic_command_failed() -> Self {
         BackupServiceError::CommandNotFound("Failed to execute restic".to_string())
     }
 
+    /// Process exit code for this error, one per failure type (see `EXIT_CODES`; runs
+    /// skipped due to an overlap use EX_TEMPFAIL)
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            BackupServiceError::AuthenticationFailed => 10,
+            BackupServiceError::NetworkError => 11,
+            BackupServiceError::RepositoryNotFound(_) => 12,
+            BackupServiceError::RepositoryLocked(_) => 13,
+            BackupServiceError::WrongPassword(_) => 14,
+            BackupServiceError::QuotaExceeded => 15,
+            BackupServiceError::ClockSkew => 16,
+            BackupServiceError::TlsError(_) => 17,
+            BackupServiceError::Throttled => 18,
+            BackupServiceError::DiskFull(_) => 19,
+            BackupServiceError::CredentialValidationFailed(inner) => inner.exit_code(),
+            BackupServiceError::PartialBackup(_) => 20,
+            BackupServiceError::StaleBackups(_) => 21,
+            BackupServiceError::BudgetExceeded(_) => 22,
+            BackupServiceError::ConfigurationError(_) | BackupServiceError::EnvVarError(_) => 30,
+            BackupServiceError::CommandNotFound(_)
+            | BackupServiceError::UnsupportedResticVersion { .. } => 31,
+            BackupServiceError::AlreadyRunning(_) => {
+                crate::shared::instance_lock::ALREADY_RUNNING_EXIT_CODE
+            }
+            BackupServiceError::CommandFailed(_)
+            | BackupServiceError::IoError(_)
+            | BackupServiceError::JsonError(_)
+            | BackupServiceError::ChronoError(_)
+            | BackupServiceError::DialogueError(_) => 1,
+        }
+    }
+
+    /// Short remediation hint rendered below the error in CLI output
+    pub fn hint(&self) -> Option<String> {
+        let key = match self {
+            BackupServiceError::AuthenticationFailed => "hint-authentication",
+            BackupServiceError::NetworkError => "hint-network",
+            BackupServiceError::RepositoryLocked(_) => "hint-locked",
+            BackupServiceError::WrongPassword(_) => "hint-wrong-password",
+            BackupServiceError::QuotaExceeded => "hint-quota",
+            BackupServiceError::ClockSkew => "hint-clock-skew",
+            BackupServiceError::TlsError(_) => "hint-tls",
+            BackupServiceError::Throttled => "hint-throttled",
+            BackupServiceError::DiskFull(_) => "hint-disk-full",
+            BackupServiceError::UnsupportedResticVersion { .. } => "hint-restic-version",
+            BackupServiceError::CredentialValidationFailed(inner) => return inner.hint(),
+            _ => return None,
+        };
+        Some(crate::i18n::t(key))
+    }
+
+    /// Transient failures worth another attempt; everything else fails right away
+    ///
+    /// Authentication, password, quota or TLS problems do not go away by waiting, and a
+    /// locked repository is already waited for by restic's `--retry-lock`.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            BackupServiceError::NetworkError | BackupServiceError::Throttled => true,
+            BackupServiceError::CredentialValidationFailed(inner) => inner.is_retryable(),
+            _ => false,
+        }
+    }
+
     /// Parse stderr output to determine specific error type
     pub fn from_stderr(stderr: &str, context: &str) -> Self {
         let stderr_lower = stderr.to_lowercase();
 
-        if stderr_lower.contains("access denied")
+        // Specific failure modes are checked first since their messages often
+        // also contain generic words like "repository" or "connection"
+        if stderr_lower.contains("repository is already locked")
+            || stderr_lower.contains("unable to create lock")
+        {
+            BackupServiceError::RepositoryLocked(context.to_string())
+        } else if stderr_lower.contains("wrong password") {
+            BackupServiceError::WrongPassword(context.to_string())
+        } else if stderr_lower.contains("requesttimetooskewed")
+            || stderr_lower.contains("clock skew")
+            || stderr_lower.contains("difference between the request time")
+        {
+            BackupServiceError::ClockSkew
+        } else if stderr_lower.contains("x509:")
+            || stderr_lower.contains("tls: ")
+            || stderr_lower.contains("certificate signed by unknown authority")
+            || stderr_lower.contains("tls handshake")
+        {
+            // Only Go's TLS errors: paths like /etc/ssl or "certificate" in a file name are not
+            BackupServiceError::TlsError(stderr.trim().to_string())
+        } else if stderr_lower.contains("no space left on device") {
+            BackupServiceError::DiskFull(stderr.trim().to_string())
+        } else if stderr_lower.contains("quotaexceeded")
+            || stderr_lower.contains("quota exceeded")
+            || stderr_lower.contains("insufficient storage")
+        {
+            BackupServiceError::QuotaExceeded
+        } else if stderr_lower.contains("slowdown")
+            || stderr_lower.contains("reduce your request rate")
+            || stderr_lower.contains("too many requests")
+        {
+            BackupServiceError::Throttled
+        } else if stderr_lower.contains("access denied")
             || stderr_lower.contains("invalid credentials")
             || stderr_lower.contains("authorization")
             || stderr_lower.contains("forbidden")
@@ -131,6 +307,146 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_error_from_stderr_extended_taxonomy() {
+        assert!(matches!(
+            BackupServiceError::from_stderr(
+                "Fatal: unable to create lock in backend: repository is already locked by PID 1234",
+                "test"
+            ),
+            BackupServiceError::RepositoryLocked(_)
+        ));
+
+        assert!(matches!(
+            BackupServiceError::from_stderr("Fatal: wrong password or no key found", "test"),
+            BackupServiceError::WrongPassword(_)
+        ));
+
+        assert!(matches!(
+            BackupServiceError::from_stderr(
+                "An error occurred (QuotaExceeded) when calling the PutObject operation",
+                "test"
+            ),
+            BackupServiceError::QuotaExceeded
+        ));
+
+        assert!(matches!(
+            BackupServiceError::from_stderr(
+                "An error occurred (RequestTimeTooSkewed): The difference between the request time and the current time is too large.",
+                "test"
+            ),
+            BackupServiceError::ClockSkew
+        ));
+
+        assert!(matches!(
+            BackupServiceError::from_stderr(
+                "Get \"https://minio.local/\": x509: certificate signed by unknown authority",
+                "test"
+            ),
+            BackupServiceError::TlsError(_)
+        ));
+
+        assert!(matches!(
+            BackupServiceError::from_stderr(
+                "An error occurred (SlowDown): Please reduce your request rate.",
+                "test"
+            ),
+            BackupServiceError::Throttled
+        ));
+
+        assert!(matches!(
+            BackupServiceError::from_stderr("remote error: tls: bad certificate", "test"),
+            BackupServiceError::TlsError(_)
+        ));
+        // "ssl" or "certificate" in a path is not a TLS error
+        assert!(matches!(
+            BackupServiceError::from_stderr(
+                "read /etc/ssl/certificate.pem: connection reset by peer",
+                "test"
+            ),
+            BackupServiceError::NetworkError
+        ));
+        assert!(matches!(
+            BackupServiceError::from_stderr(
+                "open /srv/certificates/ca.pem: permission denied",
+                "test"
+            ),
+            BackupServiceError::CommandFailed(_)
+        ));
+
+        // Local ENOSPC is not the bucket's quota
+        let full = BackupServiceError::from_stderr(
+            "Fatal: unable to save snapshot: write /root/.cache/restic/data/4f: no space left on device",
+            "test",
+        );
+        assert!(matches!(full, BackupServiceError::DiskFull(_)));
+        assert_eq!(full.exit_code(), 19);
+        assert!(full.hint().unwrap().contains("RESTIC_CACHE_DIR"));
+    }
+
+    #[test]
+    fn test_error_hints() {
+        assert!(BackupServiceError::ClockSkew.hint().is_some());
+        assert!(
+            BackupServiceError::RepositoryLocked("repo".to_string())
+                .hint()
+                .unwrap()
+                .contains("unlock")
+        );
+        assert!(
+            BackupServiceError::WrongPassword("repo".to_string())
+                .with_validation_context()
+                .hint()
+                .is_some()
+        );
+        assert!(
+            BackupServiceError::CommandFailed("x".to_string())
+                .hint()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_retryable_errors() {
+        assert!(BackupServiceError::NetworkError.is_retryable());
+        assert!(BackupServiceError::Throttled.is_retryable());
+        assert!(!BackupServiceError::AuthenticationFailed.is_retryable());
+        assert!(!BackupServiceError::RepositoryLocked("repo".to_string()).is_retryable());
+        assert!(
+            BackupServiceError::NetworkError
+                .with_validation_context()
+                .is_retryable()
+        );
+    }
+
+    #[test]
+    fn test_exit_codes() {
+        let errors = [
+            BackupServiceError::AuthenticationFailed.with_validation_context(),
+            BackupServiceError::NetworkError,
+            BackupServiceError::RepositoryNotFound("repo".to_string()),
+            BackupServiceError::WrongPassword("repo".to_string()),
+            BackupServiceError::PartialBackup("1 of 3 paths".to_string()),
+            BackupServiceError::StaleBackups("/home/tim".to_string()),
+            BackupServiceError::BudgetExceeded("/home/tim".to_string()),
+            BackupServiceError::ConfigurationError("bad".to_string()),
+            BackupServiceError::CommandNotFound("restic".to_string()),
+            BackupServiceError::UnsupportedResticVersion {
+                binary: "restic".to_string(),
+                found: "0.12.1".to_string(),
+                required: "0.16.0".to_string(),
+            },
+            BackupServiceError::AlreadyRunning("pid 42".to_string()),
+            BackupServiceError::CommandFailed("exit 1".to_string()),
+        ];
+        let codes: Vec<i32> = errors.iter().map(|e| e.exit_code()).collect();
+        assert_eq!(codes, [10, 11, 12, 14, 20, 21, 22, 30, 31, 31, 75, 1]);
+        // Every code a failure can exit with is documented
+        for code in codes {
+            assert!(EXIT_CODES.iter().any(|(c, _)| *c == code));
+        }
+    }
+
     #[test]
     fn test_error_context_wrapping() {
         let base_error = BackupServiceError::AuthenticationFailed;
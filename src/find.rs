@@ -0,0 +1,12 @@
+use crate::config::Config;
+use crate::errors::BackupServiceError;
+use crate::shared::find_workflow::{FindOptions, execute_find_workflow};
+
+// CLI command to search the snapshots of every repository of a host for files
+pub async fn find_files(
+    config: Config,
+    host: Option<String>,
+    options: FindOptions,
+) -> Result<(), BackupServiceError> {
+    execute_find_workflow(config, host, options).await
+}
@@ -0,0 +1,28 @@
+use crate::config::Config;
+use crate::errors::BackupServiceError;
+use crate::shared::fleet_workflow::{execute_fleet_groups, execute_fleet_prune, execute_fleet_run};
+use crate::shared::prune_workflow::PruneOptions;
+
+// CLI command to show configured host groups
+pub async fn show_groups() -> Result<(), BackupServiceError> {
+    execute_fleet_groups()
+}
+
+// CLI command to run a backup on every member of a group
+pub async fn run_group(
+    group: String,
+    hosts: Vec<String>,
+    args: Vec<String>,
+) -> Result<(), BackupServiceError> {
+    execute_fleet_run(&group, &hosts, &args).await
+}
+
+// CLI command to prune every member of a group
+pub async fn prune_group(
+    config: Config,
+    group: String,
+    hosts: Vec<String>,
+    options: PruneOptions,
+) -> Result<(), BackupServiceError> {
+    execute_fleet_prune(config, &group, &hosts, options).await
+}
@@ -0,0 +1,7 @@
+use crate::errors::BackupServiceError;
+use crate::shared::history_workflow::{HistoryOptions, execute_history_workflow};
+
+// CLI command to show how each path's backups developed over the recorded runs
+pub async fn show_history(options: HistoryOptions) -> Result<(), BackupServiceError> {
+    execute_history_workflow(options).await
+}
@@ -0,0 +1,159 @@
+use fluent_bundle::concurrent::FluentBundle;
+use fluent_bundle::{FluentArgs, FluentResource};
+use std::sync::OnceLock;
+use unic_langid::LanguageIdentifier;
+
+const EN_CATALOG: &str = include_str!("../locales/en.ftl");
+const DE_CATALOG: &str = include_str!("../locales/de.ftl");
+
+/// Supported user-facing languages
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    English,
+    German,
+}
+
+impl Language {
+    /// Resolve from a locale string like `de_DE.UTF-8`, `de`, or `en_US`
+    pub fn from_locale(locale: &str) -> Option<Self> {
+        let lang = locale
+            .split(['_', '-', '.', '@'])
+            .next()
+            .unwrap_or_default()
+            .to_lowercase();
+        match lang.as_str() {
+            "de" => Some(Language::German),
+            "en" | "c" | "posix" => Some(Language::English),
+            _ => None,
+        }
+    }
+
+    /// Resolve from RBS_LANG, then the standard LC_ALL / LC_MESSAGES / LANG variables
+    pub fn from_env() -> Self {
+        ["RBS_LANG", "LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|key| std::env::var(key).ok())
+            .find(|v| !v.trim().is_empty())
+            .and_then(|v| Self::from_locale(&v))
+            .unwrap_or(Language::English)
+    }
+
+    fn catalog(&self) -> (&'static str, &'static str) {
+        match self {
+            Language::English => ("en", EN_CATALOG),
+            Language::German => ("de", DE_CATALOG),
+        }
+    }
+}
+
+/// Message catalogs for the active language with English fallback
+struct Localizer {
+    primary: FluentBundle<FluentResource>,
+    fallback: FluentBundle<FluentResource>,
+}
+
+impl Localizer {
+    fn new(language: Language) -> Self {
+        Self {
+            primary: build_bundle(language),
+            fallback: build_bundle(Language::English),
+        }
+    }
+
+    fn format(&self, key: &str, args: Option<&FluentArgs>) -> String {
+        format_with(&self.primary, key, args)
+            .or_else(|| format_with(&self.fallback, key, args))
+            .unwrap_or_else(|| key.to_string())
+    }
+}
+
+fn build_bundle(language: Language) -> FluentBundle<FluentResource> {
+    let (tag, source) = language.catalog();
+    let langid: LanguageIdentifier = tag.parse().unwrap_or_default();
+    let mut bundle = FluentBundle::new_concurrent(vec![langid]);
+    // Unicode isolation marks render as garbage in plain terminals and log files
+    bundle.set_use_isolating(false);
+
+    // Catalogs are compiled in; a broken entry is skipped rather than aborting
+    let resource = FluentResource::try_new(source.to_string()).unwrap_or_else(|(res, _)| res);
+    let _ = bundle.add_resource(resource);
+    bundle
+}
+
+fn format_with(
+    bundle: &FluentBundle<FluentResource>,
+    key: &str,
+    args: Option<&FluentArgs>,
+) -> Option<String> {
+    let pattern = bundle.get_message(key)?.value()?;
+    let mut errors = Vec::new();
+    Some(
+        bundle
+            .format_pattern(pattern, args, &mut errors)
+            .into_owned(),
+    )
+}
+
+fn localizer() -> &'static Localizer {
+    static LOCALIZER: OnceLock<Localizer> = OnceLock::new();
+    LOCALIZER.get_or_init(|| Localizer::new(Language::from_env()))
+}
+
+/// Translate a message key for the active language
+pub fn t(key: &str) -> String {
+    localizer().format(key, None)
+}
+
+/// Translate a message key with named arguments
+pub fn t_args(key: &str, args: &[(&str, String)]) -> String {
+    let mut fluent_args = FluentArgs::new();
+    for (name, value) in args {
+        fluent_args.set(*name, value.clone());
+    }
+    localizer().format(key, Some(&fluent_args))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_language_from_locale() {
+        assert_eq!(Language::from_locale("de_DE.UTF-8"), Some(Language::German));
+        assert_eq!(Language::from_locale("de"), Some(Language::German));
+        assert_eq!(Language::from_locale("en_US"), Some(Language::English));
+        assert_eq!(Language::from_locale("C.UTF-8"), Some(Language::English));
+        assert_eq!(Language::from_locale("fr_FR"), None);
+    }
+
+    #[test]
+    fn test_catalogs_have_matching_keys() {
+        let en = Localizer::new(Language::English);
+        let de = Localizer::new(Language::German);
+
+        for line in EN_CATALOG.lines() {
+            if let Some((key, _)) = line.split_once(" = ") {
+                assert!(
+                    format_with(&de.primary, key.trim(), None).is_some(),
+                    "German catalog is missing key: {}",
+                    key
+                );
+                assert!(format_with(&en.primary, key.trim(), None).is_some());
+            }
+        }
+    }
+
+    #[test]
+    fn test_format_with_args_and_fallback() {
+        let de = Localizer::new(Language::German);
+        let mut args = FluentArgs::new();
+        args.set("count", "3");
+        assert_eq!(
+            de.format("restore-summary-skipped", Some(&args)),
+            "Übersprungen: 3 Repositories"
+        );
+
+        // Unknown keys fall back to the key itself
+        assert_eq!(de.format("no-such-key", None), "no-such-key");
+    }
+}
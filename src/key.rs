@@ -0,0 +1,12 @@
+use crate::config::Config;
+use crate::errors::BackupServiceError;
+use crate::shared::key_workflow::{KeyAction, KeyOptions, execute_key_workflow};
+
+// CLI command to list, add, remove or rotate the repository keys of every repository of a host
+pub async fn run_key(
+    config: Config,
+    action: KeyAction,
+    options: KeyOptions,
+) -> Result<(), BackupServiceError> {
+    execute_key_workflow(config, action, options).await
+}
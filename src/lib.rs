@@ -0,0 +1,46 @@
+//! Restic backups to S3, as a library
+//!
+//! The `restic-backup-service` binary is a thin CLI over this crate. Other Rust tools can
+//! run backups and restores in-process through [`api`], which never prompts, or drive
+//! [`BackupWorkflow`], [`RestoreWorkflow`] and [`RepositoryOperations`] directly.
+
+pub mod api;
+pub mod backup;
+pub mod check;
+pub mod config;
+pub mod daemon;
+pub mod delete_host;
+pub mod doctor;
+pub mod drill;
+pub mod errors;
+pub mod find;
+pub mod fleet;
+pub mod history;
+pub mod i18n;
+pub mod key;
+pub mod list;
+pub mod logs;
+pub mod ls;
+pub mod migrate_host;
+pub mod mirror;
+pub mod permissions;
+pub mod prune;
+pub mod report;
+pub mod repository;
+pub mod restore;
+pub mod self_update;
+pub mod shared;
+pub mod snapshots;
+pub mod stats;
+pub mod status;
+pub mod tui;
+pub mod unlock;
+pub mod utils;
+
+pub use config::Config;
+pub use errors::BackupServiceError;
+pub use shared::backup_workflow::{
+    BackupOptions, BackupStatus, BackupSummary, BackupWorkflow, PathBackupResult,
+};
+pub use shared::operations::{RepositoryData, RepositoryOperations, ScanResult, SnapshotInfo};
+pub use shared::restore_workflow::{RestoreOptions, RestoreWorkflow};
@@ -1,13 +1,17 @@
 use crate::config::Config;
 use crate::errors::BackupServiceError;
+use crate::i18n::t_args;
+use crate::repository::BackupRepo;
+use crate::shared::budgets::{BudgetAlert, check_repository_budgets};
 use crate::shared::display::DisplayFormatter;
-use crate::shared::operations::RepositoryOperations;
+use crate::shared::operations::{DiscoveryFailure, RepositoryOperations, SnapshotInfo};
+use crate::shared::path_tags::retain_tagged;
 use crate::utils::validate_credentials;
-use serde_json::json;
+use serde_json::{Value, json};
 use tracing::{info, warn};
 
 // CLI command to retrieve and display available backup hosts from S3
-pub async fn list_hosts(config: Config) -> Result<(), BackupServiceError> {
+pub async fn list_hosts(config: Config, json_output: bool) -> Result<(), BackupServiceError> {
     info!("Getting available hosts...");
     config.set_aws_env()?;
 
@@ -18,7 +22,9 @@ pub async fn list_hosts(config: Config) -> Result<(), BackupServiceError> {
     let operations = RepositoryOperations::new(config)?;
     let hosts = operations.get_available_hosts().await?;
 
-    if hosts.is_empty() {
+    if json_output {
+        DisplayFormatter::print_json(&json!({ "hosts": hosts }))?;
+    } else if hosts.is_empty() {
         warn!("No hosts found in backup repository (repository is empty)");
     } else {
         info!("\nAvailable hosts:");
@@ -30,11 +36,70 @@ pub async fn list_hosts(config: Config) -> Result<(), BackupServiceError> {
     Ok(())
 }
 
+/// Repositories, snapshots and alerts of one host, as `list` shows them
+struct HostListing {
+    host: String,
+    repos: Vec<BackupRepo>,
+    snapshots: Vec<SnapshotInfo>,
+    budget_alerts: Vec<BudgetAlert>,
+    failures: Vec<DiscoveryFailure>,
+}
+
+impl HostListing {
+    async fn collect(
+        config: &Config,
+        operations: &RepositoryOperations,
+        hostname: &str,
+        refresh: bool,
+        tags: &[String],
+    ) -> Result<Self, BackupServiceError> {
+        let scan = operations
+            .scan_repositories_cached(hostname, refresh)
+            .await?;
+        let budget_alerts = check_repository_budgets(config, hostname, &scan.repos).await?;
+        let tagged = retain_tagged(scan.repos, tags);
+        Ok(Self {
+            host: hostname.to_string(),
+            repos: operations.convert_to_backup_repos(tagged.clone())?,
+            snapshots: operations.extract_all_snapshots(&tagged),
+            budget_alerts,
+            failures: scan.failures,
+        })
+    }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "host": self.host,
+            "repositories": self.repos.iter().map(|r| json!({
+                "path": r.native_path.to_string_lossy(),
+                "category": r.category().unwrap_or("unknown"),
+                "snapshot_count": r.snapshot_count
+            })).collect::<Vec<_>>(),
+            "snapshots": self.snapshots.iter().map(|s| json!({
+                "time": s.time.to_rfc3339(),
+                "path": s.path.to_string_lossy(),
+                "id": s.id,
+                "tags": s.tags
+            })).collect::<Vec<_>>(),
+            "budget_alerts": self.budget_alerts,
+            "discovery_errors": self.failures
+        })
+    }
+
+    fn display(&self) -> Result<(), BackupServiceError> {
+        DisplayFormatter::display_backup_summary(&self.repos, &self.snapshots)?;
+        DisplayFormatter::display_discovery_failures(&self.failures)
+    }
+}
+
 // Main CLI command to list backups with human-readable or JSON output
 pub async fn list_backups(
     config: Config,
     host: Option<String>,
     json_output: bool,
+    refresh: bool,
+    tags: Vec<String>,
+    categories: Vec<String>,
 ) -> Result<(), BackupServiceError> {
     // Use provided hostname or fall back to config hostname
     let hostname = host.unwrap_or_else(|| config.hostname.clone());
@@ -46,34 +111,72 @@ pub async fn list_backups(
 
     validate_credentials(&config).await?;
 
-    // Collect and process repository data for display
-    let (repos, all_snapshots) = {
-        let operations = RepositoryOperations::new(config)?;
-        let repo_data = operations.collect_backup_data(&hostname).await?;
-        (
-            operations.convert_to_backup_repos(repo_data.clone())?,
-            operations.extract_all_snapshots(&repo_data),
-        )
-    };
+    let operations = RepositoryOperations::new(config.clone())?.with_categories(categories)?;
+    let listing = HostListing::collect(&config, &operations, &hostname, refresh, &tags).await?;
 
     if json_output {
         // Format output as structured JSON for scripting
+        DisplayFormatter::print_json(&listing.to_json())?;
+    } else {
+        listing.display()?;
+    }
+
+    Ok(())
+}
+
+// CLI command to list the backups of every host in the bucket, grouped by host with totals
+pub async fn list_all_hosts(
+    config: Config,
+    json_output: bool,
+    refresh: bool,
+    tags: Vec<String>,
+    categories: Vec<String>,
+) -> Result<(), BackupServiceError> {
+    config.set_aws_env()?;
+    validate_credentials(&config).await?;
+
+    let operations = RepositoryOperations::new(config.clone())?.with_categories(categories)?;
+    let hosts = operations.get_available_hosts().await?;
+    let mut listings = Vec::with_capacity(hosts.len());
+    for hostname in &hosts {
+        if !json_output {
+            info!(hostname = %hostname, "Listing backups from S3 bucket");
+        }
+        let listing = HostListing::collect(&config, &operations, hostname, refresh, &tags).await?;
+        if !json_output {
+            listing.display()?;
+        }
+        listings.push(listing);
+    }
+
+    let repositories: usize = listings.iter().map(|l| l.repos.len()).sum();
+    let snapshots: usize = listings.iter().map(|l| l.snapshots.len()).sum();
+    let discovery_errors: usize = listings.iter().map(|l| l.failures.len()).sum();
+    if json_output {
         let output = json!({
-            "host": hostname,
-            "repositories": repos.iter().map(|r| json!({
-                "path": r.native_path.to_string_lossy(),
-                "category": r.category().unwrap_or("unknown"),
-                "snapshot_count": r.snapshot_count
-            })).collect::<Vec<_>>(),
-            "snapshots": all_snapshots.iter().map(|s| json!({
-                "time": s.time.to_rfc3339(),
-                "path": s.path.to_string_lossy(),
-                "id": s.id
-            })).collect::<Vec<_>>()
+            "hosts": listings.iter().map(HostListing::to_json).collect::<Vec<_>>(),
+            "totals": {
+                "hosts": listings.len(),
+                "repositories": repositories,
+                "snapshots": snapshots,
+                "discovery_errors": discovery_errors,
+            },
         });
-        info!("{}", serde_json::to_string_pretty(&output)?);
+        DisplayFormatter::print_json(&output)?;
+    } else if listings.is_empty() {
+        warn!("No hosts found in backup repository (repository is empty)");
     } else {
-        DisplayFormatter::display_backup_summary(&repos, &all_snapshots)?;
+        info!(
+            "{}",
+            t_args(
+                "list-all-hosts-totals",
+                &[
+                    ("hosts", listings.len().to_string()),
+                    ("repositories", repositories.to_string()),
+                    ("snapshots", snapshots.to_string()),
+                ]
+            )
+        );
     }
 
     Ok(())
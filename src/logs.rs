@@ -0,0 +1,7 @@
+use crate::errors::BackupServiceError;
+use crate::shared::logs_workflow::{LogsOptions, execute_logs};
+
+// CLI command to print or follow the rolling service logs
+pub async fn show_logs(options: LogsOptions) -> Result<(), BackupServiceError> {
+    execute_logs(options).await
+}
@@ -0,0 +1,13 @@
+use crate::config::Config;
+use crate::errors::BackupServiceError;
+use crate::shared::ls_workflow::{LsOptions, execute_ls_workflow};
+
+// CLI command to show the files of a snapshot below a path
+pub async fn list_snapshot_contents(
+    config: Config,
+    host: Option<String>,
+    path: String,
+    options: LsOptions,
+) -> Result<(), BackupServiceError> {
+    execute_ls_workflow(config, host, path, options).await
+}
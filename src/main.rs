@@ -1,14 +1,11 @@
-use clap::{Parser, Subcommand};
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum, error::ErrorKind};
 use tracing::{info, warn};
 
-mod backup;
-mod config;
-mod errors;
-mod list;
-mod repository;
-mod restore;
-mod shared;
-mod utils;
+use restic_backup_service::{
+    backup, check, config, daemon, delete_host, doctor, drill, errors, find, fleet, history, i18n,
+    key, list, logs, ls, migrate_host, mirror, permissions, prune, report, restore, self_update,
+    shared, snapshots, stats, status, tui, unlock,
+};
 
 #[derive(Parser)]
 #[command(name = "restic-backup-service")]
@@ -17,11 +14,44 @@ mod utils;
         "A Rust-based restic backup service for S3 storage ",
         "(Version: ", env!("CARGO_PKG_VERSION"), ")"
     ),
-    long_about = None
+    long_about = None,
+    arg_required_else_help = true
 )]
 struct Cli {
     #[command(subcommand)]
-    command: Commands,
+    command: Option<Commands>,
+
+    /// Print the exit code of each failure type and exit
+    #[arg(long, exclusive = true)]
+    print_exit_codes: bool,
+
+    /// Developer mode: simulate failures (s3-throttle, restic-failure, restic-network,
+    /// partial-output).
+    /// Only honored when RBS_ALLOW_FAULT_INJECTION=1
+    #[arg(long, global = true, hide = true)]
+    inject_fault: Option<String>,
+
+    /// Use numbered text prompts instead of arrow-key menus (screen readers, serial consoles)
+    #[arg(long, global = true)]
+    plain_prompts: bool,
+
+    /// Print results as JSON on stdout and log to stderr (same as --output json)
+    #[arg(short, long, global = true)]
+    json: bool,
+
+    /// Result format of hosts, stats, list, restore --yes and the other reporting commands
+    #[arg(long, global = true, value_enum, default_value_t = OutputFormat::Text)]
+    output: OutputFormat,
+
+    /// Apply this [profiles.<name>] section of the config file (repository, paths, policies)
+    #[arg(long, global = true, value_name = "NAME")]
+    profile: Option<String>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    Text,
+    Json,
 }
 //
 
@@ -31,14 +61,145 @@ enum Commands {
         /// Optional specific paths to backup (otherwise uses config)
         #[arg(value_delimiter = ',')]
         paths: Vec<String>,
+        /// Only back up these categories (user_home, docker_volume, system; comma-separated)
+        #[arg(long, value_delimiter = ',')]
+        only: Vec<String>,
+        /// Skip these categories (comma-separated)
+        #[arg(long, value_delimiter = ',')]
+        skip: Vec<String>,
+        /// Also back up BACKUP_SENSITIVE_PATHS without asking (tagged `sensitive`)
+        #[arg(long)]
+        include_sensitive: bool,
+        /// Initial seeding: smallest paths first within SEED_DAILY_BUDGET, resuming where the last run stopped
+        #[arg(long)]
+        seed: bool,
+        /// Pause or stop running containers using a docker volume while it is backed up:
+        /// off, pause, stop (default: BACKUP_DOCKER_QUIESCE)
+        #[arg(long, value_name = "MODE")]
+        docker_quiesce: Option<String>,
+        /// Paths to back up in parallel (default: BACKUP_CONCURRENCY or 1)
+        #[arg(long)]
+        jobs: Option<usize>,
+        /// When a repository is locked, remove its locks if they are stale (see `unlock`)
+        /// and retry once (default: BACKUP_AUTO_UNLOCK_STALE)
+        #[arg(long)]
+        auto_unlock_stale: bool,
+        /// Report per path what would be added (files, bytes) with restic's backup --dry-run,
+        /// without writing snapshots or initializing repositories
+        #[arg(long, conflicts_with = "seed")]
+        dry_run: bool,
+    },
+    /// Stay running and back up on a cron schedule (SIGTERM finishes the current path)
+    Daemon {
+        /// Cron expression, e.g. "0 3 * * *" (default: BACKUP_SCHEDULE)
+        #[arg(long)]
+        schedule: Option<String>,
+        /// Only back up these categories (comma-separated)
+        #[arg(long, value_delimiter = ',')]
+        only: Vec<String>,
+        /// Skip these categories (comma-separated)
+        #[arg(long, value_delimiter = ',')]
+        skip: Vec<String>,
+        /// Include BACKUP_SENSITIVE_PATHS in every scheduled run
+        #[arg(long)]
+        include_sensitive: bool,
+        /// Serve Prometheus metrics of the latest run, e.g. 0.0.0.0:9099 (default: METRICS_LISTEN)
+        #[arg(long, value_name = "ADDR")]
+        metrics_listen: Option<String>,
     },
     List {
         /// Hostname to list backups for (default: current host)
         #[arg(short = 'H', long)]
         host: Option<String>,
-        /// Return data as JSON (for scripting)
+        /// Every host in the bucket, grouped by host with totals
+        #[arg(long, conflicts_with = "host")]
+        all_hosts: bool,
+        /// Rescan the repositories instead of using a scan cached within SCAN_CACHE_TTL
+        #[arg(long)]
+        refresh: bool,
+        /// Only snapshots carrying all of these tags (comma-separated or repeated)
+        #[arg(long, value_delimiter = ',')]
+        tag: Vec<String>,
+        /// Only scan repositories of these categories: user_home, docker_volume, system
+        /// (comma-separated)
+        #[arg(long, value_delimiter = ',')]
+        category: Vec<String>,
+    },
+    /// Remove stale locks left behind by crashed restic runs from all repositories of a host
+    Unlock {
+        /// Hostname whose repositories to unlock (default: current host)
+        #[arg(short = 'H', long)]
+        host: Option<String>,
+        /// Only remove locks from other machines older than this (default: UNLOCK_MIN_AGE or 1h)
+        #[arg(long)]
+        min_age: Option<String>,
+        /// Show the locks and what would be removed without changing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// List snapshots across all repositories of a host, optionally filtered
+    Snapshots {
+        /// Hostname whose snapshots to list (default: current host)
+        #[arg(short = 'H', long)]
+        host: Option<String>,
+        /// Only snapshots of this path or of paths below it
         #[arg(short, long)]
-        json: bool,
+        path: Option<String>,
+        /// Only snapshots taken at or after this time ("7d", "2025-01-15", "yesterday 14:00")
+        #[arg(long)]
+        since: Option<String>,
+        /// Only snapshots taken at or before this time
+        #[arg(long)]
+        until: Option<String>,
+        /// Only snapshots carrying all of these tags (comma-separated or repeated)
+        #[arg(long, value_delimiter = ',')]
+        tag: Vec<String>,
+        /// Only these categories: user_home, docker_volume, system (comma-separated)
+        #[arg(long, value_delimiter = ',')]
+        category: Vec<String>,
+    },
+    /// Search the snapshots of every repository of a host for files
+    Find {
+        /// restic patterns, e.g. "*.docx" or "/home/tim/Documents/report*" (any of them matches)
+        #[arg(required = true)]
+        patterns: Vec<String>,
+        /// Hostname whose snapshots to search (default: current host)
+        #[arg(short = 'H', long)]
+        host: Option<String>,
+        /// Only snapshots of this path or of paths below it
+        #[arg(short, long)]
+        path: Option<String>,
+        /// Only snapshots taken at or after this time ("30d", "2025-01-15")
+        #[arg(long)]
+        since: Option<String>,
+        /// Only snapshots taken at or before this time
+        #[arg(long)]
+        until: Option<String>,
+        /// Only snapshots carrying all of these tags (comma-separated or repeated)
+        #[arg(long, value_delimiter = ',')]
+        tag: Vec<String>,
+        /// Only these categories: user_home, docker_volume, system (comma-separated)
+        #[arg(long, value_delimiter = ',')]
+        category: Vec<String>,
+        /// Match the patterns case-insensitively
+        #[arg(short, long)]
+        ignore_case: bool,
+    },
+    /// Show the files of a snapshot below a path, e.g. to check a file exists before restoring
+    Ls {
+        /// Directory to list, the backed-up path or any directory inside it
+        path: String,
+        /// Snapshot ID (default: the latest snapshot of the path's repository)
+        snapshot: Option<String>,
+        /// Hostname whose backup to browse (default: current host)
+        #[arg(short = 'H', long)]
+        host: Option<String>,
+        /// Levels below PATH to show (0: the whole tree)
+        #[arg(short, long, default_value_t = 1)]
+        depth: usize,
+        /// Only entries whose name matches this pattern (* and ?; repeatable), e.g. "*.docx"
+        #[arg(short, long, value_name = "PATTERN")]
+        glob: Vec<String>,
     },
     Restore {
         /// Non-interactive mode with specific options
@@ -46,39 +207,489 @@ enum Commands {
         host: Option<String>,
         #[arg(short, long)]
         path: Option<String>,
+        /// Restore point: RFC 3339, local time ("2025-01-15 14:00", "14:00"),
+        /// "yesterday 14:00" or "2h ago"
         #[arg(short, long)]
         timestamp: Option<String>,
+        /// Limit restore download bandwidth (KiB/s)
+        #[arg(long)]
+        limit_download: Option<u32>,
+        /// Wait until this time before restoring (HH:MM local, or RFC 3339)
+        #[arg(long = "at")]
+        start_at: Option<String>,
+        /// Only download the selected snapshots into RESTORE_PREFETCH_DIR; a later
+        /// restore of the same snapshots moves the staged data into place
+        #[arg(long)]
+        prefetch: bool,
+        /// After copying/moving, start the containers using restored docker volumes
+        /// and report whether they come up healthy
+        #[arg(long)]
+        verify_containers: bool,
+        /// Never prompt: restore the current host's latest snapshot unless -H/-t say
+        /// otherwise, clear a non-empty staging directory and apply --action (default leave)
+        #[arg(short = 'y', long, visible_alias = "non-interactive")]
+        yes: bool,
+        /// What to do with the restored files: copy, move or leave (skips the prompt)
+        #[arg(long)]
+        action: Option<String>,
+        /// What copy/move do with existing files: replace (default), skip (keep them, add
+        /// only missing files) or keep-both (rename them to <name>.pre-restore-<timestamp>)
+        #[arg(long, value_name = "POLICY")]
+        overwrite: Option<String>,
+        /// Restore into this directory instead of RESTORE_STAGING_DIR; it must be empty
+        /// unless --wipe-target is given
+        #[arg(long, value_name = "DIR")]
+        target: Option<String>,
+        /// Delete the contents of a non-empty --target before restoring into it
+        #[arg(long, requires = "target")]
+        wipe_target: bool,
+        /// Repositories to restore in parallel (default: RESTORE_JOBS or 4); a
+        /// --limit-download cap applies to each of them
+        #[arg(short, long)]
+        jobs: Option<usize>,
+        /// Only restore files matching this restic pattern (repeatable), e.g.
+        /// "Documents/**/*.docx"; a pattern without a leading / matches at any depth
+        #[arg(long, value_name = "PATTERN")]
+        include: Vec<String>,
+        /// Leave out files matching this restic pattern (repeatable)
+        #[arg(long, value_name = "PATTERN")]
+        exclude: Vec<String>,
+        /// Only show per repository how many files and bytes would be restored, how many
+        /// differ from the originals and where they would land; nothing is written
+        #[arg(long)]
+        dry_run: bool,
+        /// Rescan the repositories instead of using a scan cached within SCAN_CACHE_TTL
+        #[arg(long)]
+        refresh: bool,
+        /// Only offer snapshots carrying all of these tags (comma-separated or repeated)
+        #[arg(long, value_delimiter = ',')]
+        tag: Vec<String>,
+        /// Only scan repositories of these categories: user_home, docker_volume, system
+        /// (comma-separated)
+        #[arg(long, value_delimiter = ',')]
+        category: Vec<String>,
+        /// Restore only this file or directory (its path in the snapshot); the repository
+        /// holding it is selected automatically
+        #[arg(long, value_name = "PATH")]
+        file: Option<String>,
+        /// Put the --file here instead of its original path
+        #[arg(long, value_name = "PATH", requires = "file")]
+        to: Option<String>,
     },
-    Size {
-        path: String,
+    /// Full-screen dashboard: hosts, repositories by category, a snapshot timeline and
+    /// a restore wizard
+    Tui {
+        /// Hostname to open first (default: current host)
+        #[arg(short = 'H', long)]
+        host: Option<String>,
+        /// Rescan the first host instead of using a scan cached within SCAN_CACHE_TTL
+        #[arg(long)]
+        refresh: bool,
+    },
+    /// Show the stored size, restore size and file count of every repository of a host,
+    /// largest first, or the raw-data size of one path
+    #[command(alias = "size")]
+    Stats {
+        /// Only this path's repository (raw-data size of its latest snapshot)
+        path: Option<String>,
+        /// Hostname whose repositories to measure (default: current host)
+        #[arg(short = 'H', long)]
+        host: Option<String>,
+    },
+    /// Per path: last successful backup, how far behind schedule, warnings of the last run;
+    /// exits with 21 when a path is older than the maximum age
+    Status {
+        /// Hostname whose runs to report (default: current host)
+        #[arg(short = 'H', long)]
+        host: Option<String>,
+        /// Age after which a path is stale (default: STATUS_MAX_AGE or 26h)
+        #[arg(long)]
+        max_age: Option<String>,
+    },
+    Prune {
+        /// Hostname whose repositories to prune (default: current host)
+        #[arg(short = 'H', long)]
+        host: Option<String>,
+        /// Every host in the bucket, one after another (PROTECT_HOSTS are skipped; confirm
+        /// with `all-hosts`)
+        #[arg(long, conflicts_with = "host")]
+        all_hosts: bool,
+        /// Storage preset for repack tuning: r2, s3, local (default: detected from repo base)
+        #[arg(long)]
+        preset: Option<String>,
+        /// Passed to restic as --max-unused (e.g. 5%, 2G, unlimited)
+        #[arg(long)]
+        max_unused: Option<String>,
+        /// Passed to restic as --repack-cacheable-only
+        #[arg(long)]
+        repack_cacheable_only: Option<bool>,
+        /// Forget snapshots first, keeping the last N (restic --keep-last)
+        #[arg(long)]
+        keep_last: Option<u32>,
+        /// Keep N daily snapshots
+        #[arg(long)]
+        keep_daily: Option<u32>,
+        /// Keep N weekly snapshots
+        #[arg(long)]
+        keep_weekly: Option<u32>,
+        /// Keep N monthly snapshots
+        #[arg(long)]
+        keep_monthly: Option<u32>,
+        /// Keep N yearly snapshots
+        #[arg(long)]
+        keep_yearly: Option<u32>,
+        /// Snapshot grouping for forget (must include paths; default: host,paths)
+        #[arg(long)]
+        group_by: Option<String>,
+        /// Client-side retention rules instead of --keep-* (default: RETENTION_RULES),
+        /// e.g. "first-monthly=all,tag:pre-upgrade,daily=7,within=14d"
+        #[arg(long)]
+        rules: Option<String>,
+        /// Only forget among snapshots carrying all of these tags (comma-separated or repeated)
+        #[arg(long, value_delimiter = ',')]
+        tag: Vec<String>,
+        /// Never forget snapshots carrying any of these tags, in addition to RETENTION_KEEP_TAGS
+        #[arg(long, value_delimiter = ',')]
+        keep_tag: Vec<String>,
+        /// Show what would be forgotten and pruned without changing anything
+        #[arg(long)]
+        dry_run: bool,
+        /// Repositories to prune in parallel (default: PRUNE_JOBS or 4)
+        #[arg(short, long)]
+        jobs: Option<usize>,
+        /// Skip the interactive confirmation; requires --confirm <HOST>
+        #[arg(short = 'y', long)]
+        yes: bool,
+        /// Hostname being pruned (`all-hosts` with --all-hosts), repeated as a safeguard for --yes
+        #[arg(long, value_name = "HOST")]
+        confirm: Option<String>,
+    },
+    /// Remove every repository of a decommissioned host from the storage
+    DeleteHost {
+        /// Host whose repositories to remove
+        host: String,
+        /// List the repositories (and on S3 the objects and bytes) that would be removed
+        #[arg(long)]
+        dry_run: bool,
+        /// Forget every snapshot and prune each repository instead of deleting the host's
+        /// directory
+        #[arg(long)]
+        forget_only: bool,
+        /// Skip the interactive confirmation; requires --confirm <HOST>
+        #[arg(short = 'y', long)]
+        yes: bool,
+        /// Hostname being deleted, repeated as a safeguard for --yes
+        #[arg(long, value_name = "HOST")]
+        confirm: Option<String>,
+    },
+    /// Relocate the repositories of a renamed host below its new hostname
+    ///
+    /// The copied snapshots are rewritten to the new hostname (restic >= 0.17), so backups
+    /// under the new name continue their history and retention thins the old snapshots too.
+    MigrateHost {
+        /// Hostname the repositories are stored under
+        old: String,
+        /// Hostname to copy them to
+        new: String,
+        /// List the repositories (and on S3 the objects and bytes) that would be copied
+        #[arg(long)]
+        dry_run: bool,
+        /// Remove the old host's repositories once everything was copied
+        #[arg(long = "move")]
+        move_source: bool,
+        /// Skip the interactive confirmation of --move; requires --confirm <OLD>
+        #[arg(short = 'y', long)]
+        yes: bool,
+        /// Old hostname, repeated as a safeguard for --move --yes
+        #[arg(long, value_name = "HOST")]
+        confirm: Option<String>,
+    },
+    /// Verify every repository of a host with restic check
+    Check {
+        /// Hostname whose repositories to check (default: current host)
+        #[arg(short = 'H', long)]
+        host: Option<String>,
+        /// Every host in the bucket, grouped by host with totals
+        #[arg(long, conflicts_with = "host")]
+        all_hosts: bool,
+        /// Also read this share of the pack data: n/t (1/5), a percentage (10%) or a size (2G)
+        #[arg(long)]
+        read_data_subset: Option<String>,
+    },
+    /// Check everything end to end: restic/aws binaries, credentials, the repository base,
+    /// every repository of a host, locks and clock skew
+    Doctor {
+        /// Hostname whose repositories to open (default: current host)
+        #[arg(short = 'H', long)]
+        host: Option<String>,
+    },
+    /// Copy new snapshots of every repository of a host to the MIRROR_REPO_BASE repositories
+    Mirror {
+        /// Hostname whose repositories to mirror (default: current host)
+        #[arg(short = 'H', long)]
+        host: Option<String>,
+        /// Show how many snapshots would be copied without touching the mirror
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Restore a random sample of repositories to scratch space and verify the files
+    Drill {
+        /// Hostname whose repositories to drill (default: current host)
+        #[arg(short = 'H', long)]
+        host: Option<String>,
+        /// Repositories to restore (default: DRILL_SAMPLE or 3)
+        #[arg(short, long)]
+        sample: Option<usize>,
+        /// Directory to restore into (default: DRILL_SCRATCH_DIR or /tmp/restic/drill)
+        #[arg(long)]
+        scratch: Option<std::path::PathBuf>,
+        /// Keep the restored files instead of deleting them after verification
+        #[arg(long)]
+        keep: bool,
+    },
+    /// Trends of recorded backup runs per path: growth, failure streaks, durations
+    History {
+        /// Only runs of this host (default: every host in the history file)
+        #[arg(short = 'H', long)]
+        host: Option<String>,
+        /// Only this backup path
+        #[arg(short, long)]
+        path: Option<String>,
+        /// Most recent runs per path to look at (default 30)
+        #[arg(short = 'n', long)]
+        runs: Option<usize>,
     },
     Hosts,
+    /// Print or follow the service's rolling log files (RBS_LOG_DIR)
+    Logs {
+        /// Keep printing new lines as they are written
+        #[arg(short, long)]
+        follow: bool,
+        /// Only lines newer than this (e.g. 30m, 1h, 2d)
+        #[arg(long)]
+        since: Option<String>,
+        /// Minimum level to show (trace, debug, info, warn, error)
+        #[arg(short, long)]
+        level: Option<String>,
+        /// Show at most this many lines (default: last 100 of today's file without --since)
+        #[arg(short = 'n', long)]
+        lines: Option<usize>,
+    },
+    /// Manage the restic keys (passwords) of every repository of a host
+    Key {
+        #[command(subcommand)]
+        action: KeyAction,
+    },
+    /// Verify what the configured S3 credentials can do
+    Permissions {
+        #[command(subcommand)]
+        action: PermissionsAction,
+    },
+    /// Periodic summaries of backup activity
+    Report {
+        #[command(subcommand)]
+        action: ReportAction,
+    },
+    /// Act on named host groups (HOST_GROUP_<NAME>=host1,host2)
+    Fleet {
+        #[command(subcommand)]
+        action: FleetAction,
+    },
     Init,
+    /// Install or update this binary from GitHub release assets
+    #[command(name = "self")]
+    SelfManage {
+        #[command(subcommand)]
+        action: SelfAction,
+    },
+}
+
+#[derive(Subcommand)]
+enum KeyAction {
+    /// List the keys of every repository
+    List {
+        /// Hostname whose repositories to list (default: current host)
+        #[arg(short = 'H', long)]
+        host: Option<String>,
+    },
+    /// Add a key for another password to every repository
+    Add {
+        /// File holding the password of the new key
+        #[arg(long, value_name = "FILE")]
+        new_password_file: std::path::PathBuf,
+        /// Hostname whose repositories to change (default: current host)
+        #[arg(short = 'H', long)]
+        host: Option<String>,
+    },
+    /// Remove the key of a retired password from every repository
+    Remove {
+        /// File holding the password whose key to remove
+        #[arg(long, value_name = "FILE")]
+        password_file: std::path::PathBuf,
+        /// Hostname whose repositories to change (default: current host)
+        #[arg(short = 'H', long)]
+        host: Option<String>,
+        /// Skip the interactive confirmation; requires --confirm <HOST>
+        #[arg(short = 'y', long)]
+        yes: bool,
+        /// Hostname being changed, repeated as a safeguard for --yes
+        #[arg(long, value_name = "HOST")]
+        confirm: Option<String>,
+    },
+    /// Replace the repository password: add the new key everywhere, verify it, then remove
+    /// the old key
+    Rotate {
+        /// File holding the new password
+        #[arg(long, value_name = "FILE")]
+        new_password_file: std::path::PathBuf,
+        /// Hostname whose repositories to change (default: current host)
+        #[arg(short = 'H', long)]
+        host: Option<String>,
+        /// Skip the interactive confirmation; requires --confirm <HOST>
+        #[arg(short = 'y', long)]
+        yes: bool,
+        /// Hostname being changed, repeated as a safeguard for --yes
+        #[arg(long, value_name = "HOST")]
+        confirm: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum PermissionsAction {
+    /// Probe list/get/put/delete with throwaway objects and compare with what commands need
+    Check {
+        /// Intended use of the key: append-only (run/list/restore) or admin (also prune)
+        #[arg(long = "key-profile", default_value = "append-only")]
+        profile: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum ReportAction {
+    /// Aggregate the period's snapshots, new data, missed backups and notable changes per host
+    Digest {
+        /// Only these hosts (comma-separated; default: every host in the bucket)
+        #[arg(short = 'H', long, value_delimiter = ',')]
+        host: Vec<String>,
+        /// Length of the period in days
+        #[arg(long, default_value_t = 7)]
+        days: u32,
+        /// Deliver to REPORT_WEBHOOK_URL and/or REPORT_EMAIL_TO
+        #[arg(long)]
+        send: bool,
+    },
+    /// Score how much of this machine's data is backed up and list unprotected directories
+    Coverage {
+        /// Smallest unprotected directory to list (default: COVERAGE_MIN_SIZE or 1G)
+        #[arg(long)]
+        min_size: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum FleetAction {
+    /// Show host groups with their members and inherited policies
+    Groups,
+    /// Run a backup on every host of a group over SSH
+    Run {
+        /// Host group to target
+        #[arg(short, long)]
+        group: String,
+        /// Only these members of the group (comma-separated)
+        #[arg(short = 'H', long, value_delimiter = ',')]
+        host: Vec<String>,
+        /// Arguments passed through to the remote `run` command (after --)
+        #[arg(last = true)]
+        args: Vec<String>,
+    },
+    /// Prune every host of a group, using the group's retention by default
+    Prune {
+        /// Host group to target
+        #[arg(short, long)]
+        group: String,
+        /// Only these members of the group (comma-separated)
+        #[arg(short = 'H', long, value_delimiter = ',')]
+        host: Vec<String>,
+        /// Skip the interactive confirmation; requires --confirm <GROUP>
+        #[arg(short = 'y', long)]
+        yes: bool,
+        /// Group name being pruned, repeated as a safeguard for --yes
+        #[arg(long, value_name = "GROUP")]
+        confirm: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum SelfAction {
+    /// Download the release binary for this platform and install it at a path
+    Install {
+        /// Destination path
+        #[arg(long, default_value = "/usr/local/bin/restic-backup-service")]
+        path: std::path::PathBuf,
+        /// Release tag to install (default: latest)
+        #[arg(long)]
+        version: Option<String>,
+    },
+    /// Replace the running executable with the latest (or given) release
+    Update {
+        /// Release tag to update to (default: latest)
+        #[arg(long)]
+        version: Option<String>,
+        /// Reinstall even if already on that version
+        #[arg(long)]
+        force: bool,
+    },
+    /// Download the official static restic binary and install it at a path
+    InstallRestic {
+        /// Destination path (point RESTIC_BIN at it unless it comes first in PATH)
+        #[arg(long, default_value = "/usr/local/bin/restic")]
+        path: std::path::PathBuf,
+        /// restic release to install (default: the pinned, tested release)
+        #[arg(long)]
+        version: Option<String>,
+    },
 }
 
-fn init_logging() -> Result<(), crate::errors::BackupServiceError> {
+fn init_logging(json_output: bool) -> Result<(), errors::BackupServiceError> {
     use tracing_appender::rolling;
-    use tracing_subscriber::{EnvFilter, fmt::writer::MakeWriterExt};
+    use tracing_subscriber::{
+        EnvFilter,
+        fmt::writer::{BoxMakeWriter, MakeWriterExt},
+    };
 
-    // Get log directory from env var or default to ./logs
-    let log_dir = std::env::var("RBS_LOG_DIR").unwrap_or_else(|_| "./logs".to_string());
+    // RBS_LOG_DIR/LOG_DIR, else the XDG state directory
+    let log_dir = shared::logs_workflow::log_dir();
 
     // Create logs directory if it doesn't exist
     std::fs::create_dir_all(&log_dir)?;
 
-    let file_appender = rolling::daily(&log_dir, "restic-backup.log");
+    let file_appender = rolling::daily(&log_dir, shared::logs_workflow::LOG_FILE_PREFIX);
     let (non_blocking, _guard) = tracing_appender::non_blocking(file_appender);
 
     let env_filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
 
+    // With JSON results on stdout, log lines must not interleave with them; while the
+    // tui dashboard is shown they only go to the file
+    let console = BoxMakeWriter::new(move || -> Box<dyn std::io::Write> {
+        if shared::logs_workflow::console_muted() {
+            Box::new(std::io::sink())
+        } else if json_output {
+            Box::new(std::io::stderr())
+        } else {
+            Box::new(std::io::stdout())
+        }
+    });
+
     tracing_subscriber::fmt()
-        .with_writer(std::io::stdout.and(non_blocking))
+        .with_writer(console.and(non_blocking))
         .with_env_filter(env_filter)
         .init();
 
     // Keep tracing guard alive for entire program lifetime
     std::mem::forget(_guard);
 
+    shared::logs_workflow::cleanup_log_files(&log_dir);
+
     Ok(())
 }
 
@@ -89,35 +700,13 @@ fn preload_env_files() {
     }
 
     fn load_env_literal(path: &str) {
-        use std::fs;
-        use std::io::Read;
-        use std::path::Path;
-        if !Path::new(path).exists() {
+        let Ok(entries) = shared::config_file::read_env_file(std::path::Path::new(path)) else {
             return;
-        }
-        let mut f = match fs::File::open(path) {
-            Ok(f) => f,
-            Err(_) => return,
         };
-        let mut s = String::new();
-        if f.read_to_string(&mut s).is_err() {
-            return;
-        }
-        for line in s.lines() {
-            let lead = line.trim_start();
-            if lead.is_empty() || lead.starts_with('#') {
-                continue;
-            }
-            if let Some(eq) = line.find('=') {
-                let key = line[..eq].trim();
-                let mut val = line[eq + 1..].to_string();
-                if val.ends_with('\r') {
-                    val.pop();
-                }
-                if std::env::var_os(key).is_none() {
-                    // SAFETY: Called during init before the async runtime starts.
-                    unsafe { std::env::set_var(key, val) };
-                }
+        for (key, val) in entries {
+            if std::env::var_os(&key).is_none() {
+                // SAFETY: Called during init before the async runtime starts.
+                unsafe { std::env::set_var(key, val) };
             }
         }
     }
@@ -135,41 +724,501 @@ fn preload_env_files() {
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // Initialize tracing logging
-    init_logging()?;
+    let cli = Cli::parse();
+    let json_output = cli.json || cli.output == OutputFormat::Json;
 
-    // Attempt to load env files for CLI usage
+    if cli.print_exit_codes {
+        print_exit_codes();
+        return Ok(());
+    }
+    let Some(command) = cli.command else {
+        Cli::command()
+            .error(ErrorKind::MissingSubcommand, "a command is required")
+            .exit();
+    };
+
+    // Env files come first so they can set the log directory and retention
     preload_env_files();
 
-    let cli = Cli::parse();
+    // Initialize tracing logging
+    init_logging(json_output)?;
+
+    if let Some(profile) = &cli.profile {
+        // SAFETY: Called during startup before any commands or tasks are spawned.
+        unsafe { std::env::set_var(shared::config_file::PROFILE_ENV_VAR, profile) };
+    }
 
-    // Load configuration for all commands except init
-    let config = match &cli.command {
-        Commands::Init => None,
-        _ => match config::Config::load() {
+    if cli.plain_prompts {
+        // SAFETY: Called during startup before any commands or tasks are spawned.
+        unsafe { std::env::set_var(shared::ui::PLAIN_PROMPTS_ENV_VAR, "1") };
+    }
+
+    if let Some(fault) = &cli.inject_fault {
+        enable_fault_injection(fault);
+    }
+
+    // Load configuration for all commands except init and self management
+    let config = match &command {
+        Commands::Init
+        | Commands::SelfManage { .. }
+        | Commands::Logs { .. }
+        | Commands::History { .. } => None,
+        _ => match config::Config::load().await {
             Ok(c) => Some(c),
             Err(e) => {
                 render_pretty_error(&e);
-                std::process::exit(1);
+                std::process::exit(e.exit_code());
             }
         },
     };
 
+    // Fail before any work starts when an external program the command spawns is missing
+    if let Err(e) = shared::dependencies::ensure_available(&required_dependencies(&command)) {
+        render_pretty_error(&e);
+        std::process::exit(e.exit_code());
+    }
+
+    // The daemon handles SIGTERM/SIGINT itself; everything else stops its restic children on Ctrl-C
+    if !matches!(command, Commands::Daemon { .. }) {
+        shared::shutdown::handle_interrupts();
+    }
+
     // Dispatch CLI commands to their respective handlers and render errors nicely
-    let result = match cli.command {
-        Commands::Run { paths } => backup::run_backup(config.unwrap(), paths).await,
-        Commands::List { host, json } => list::list_backups(config.unwrap(), host, json).await,
+    let result = match command {
+        Commands::Run {
+            paths,
+            only,
+            skip,
+            include_sensitive,
+            seed,
+            docker_quiesce,
+            jobs,
+            auto_unlock_stale,
+            dry_run,
+        } => {
+            let options = shared::backup_workflow::BackupOptions {
+                only_categories: only,
+                skip_categories: skip,
+                json_output,
+                include_sensitive,
+                unattended: false,
+                seed,
+                docker_quiesce,
+                jobs,
+                auto_unlock_stale,
+                dry_run,
+            };
+            backup::run_backup(config.unwrap(), paths, options).await
+        }
+        Commands::Daemon {
+            schedule,
+            only,
+            skip,
+            include_sensitive,
+            metrics_listen,
+        } => {
+            let options = shared::daemon_workflow::DaemonOptions {
+                schedule,
+                metrics_listen,
+                backup: shared::backup_workflow::BackupOptions {
+                    only_categories: only,
+                    skip_categories: skip,
+                    json_output: false,
+                    include_sensitive,
+                    unattended: true,
+                    seed: false,
+                    docker_quiesce: None,
+                    jobs: None,
+                    auto_unlock_stale: false,
+                    dry_run: false,
+                },
+            };
+            daemon::run_daemon(config.unwrap(), options).await
+        }
+        Commands::List {
+            host,
+            all_hosts,
+            refresh,
+            tag,
+            category,
+        } => {
+            if all_hosts {
+                list::list_all_hosts(config.unwrap(), json_output, refresh, tag, category).await
+            } else {
+                list::list_backups(config.unwrap(), host, json_output, refresh, tag, category).await
+            }
+        }
+        Commands::Unlock {
+            host,
+            min_age,
+            dry_run,
+        } => {
+            let options = shared::unlock_workflow::UnlockOptions {
+                min_age,
+                dry_run,
+                json_output,
+            };
+            unlock::run_unlock(config.unwrap(), host, options).await
+        }
+        Commands::Snapshots {
+            host,
+            path,
+            since,
+            until,
+            tag,
+            category,
+        } => {
+            let filter = shared::snapshot_filter::SnapshotFilter::new(
+                path,
+                since.as_deref(),
+                until.as_deref(),
+                tag,
+                category,
+            );
+            match filter {
+                Ok(filter) => {
+                    let options = shared::snapshots_workflow::SnapshotsOptions {
+                        filter,
+                        json_output,
+                    };
+                    snapshots::list_snapshots(config.unwrap(), host, options).await
+                }
+                Err(e) => Err(e),
+            }
+        }
+        Commands::Find {
+            patterns,
+            host,
+            path,
+            since,
+            until,
+            tag,
+            category,
+            ignore_case,
+        } => {
+            let filter = shared::snapshot_filter::SnapshotFilter::new(
+                path,
+                since.as_deref(),
+                until.as_deref(),
+                tag,
+                category,
+            );
+            match filter {
+                Ok(filter) => {
+                    let options = shared::find_workflow::FindOptions {
+                        patterns,
+                        filter,
+                        ignore_case,
+                        json_output,
+                    };
+                    find::find_files(config.unwrap(), host, options).await
+                }
+                Err(e) => Err(e),
+            }
+        }
+        Commands::Ls {
+            path,
+            snapshot,
+            host,
+            depth,
+            glob,
+        } => {
+            let options = shared::ls_workflow::LsOptions {
+                snapshot,
+                depth,
+                globs: glob,
+                json_output,
+            };
+            ls::list_snapshot_contents(config.unwrap(), host, path, options).await
+        }
         Commands::Restore {
             host,
             path,
             timestamp,
-        } => restore::restore_interactive(config.unwrap(), host, path, timestamp).await,
-        Commands::Size { path } => utils::show_size(config.unwrap(), path).await,
-        Commands::Hosts => list::list_hosts(config.unwrap()).await,
+            limit_download,
+            start_at,
+            prefetch,
+            verify_containers,
+            yes,
+            action,
+            overwrite,
+            target,
+            wipe_target,
+            jobs,
+            include,
+            exclude,
+            dry_run,
+            refresh,
+            tag,
+            category,
+            file,
+            to,
+        } => {
+            let options = shared::restore_workflow::RestoreOptions {
+                limit_download,
+                start_at,
+                prefetch,
+                verify_containers,
+                assume_yes: yes,
+                action,
+                target: target.map(std::path::PathBuf::from),
+                wipe_target,
+                jobs,
+                json_output,
+                include,
+                exclude,
+                dry_run,
+                refresh,
+                tags: tag,
+                categories: category,
+                file: file.map(std::path::PathBuf::from),
+                file_to: to.map(std::path::PathBuf::from),
+                overwrite,
+            };
+            restore::restore_interactive(config.unwrap(), host, path, timestamp, options).await
+        }
+        Commands::Tui { host, refresh } => {
+            let options = shared::tui_workflow::TuiOptions { refresh };
+            tui::run_dashboard(config.unwrap(), host, options).await
+        }
+        Commands::Stats { path, host } => {
+            let options = shared::stats_workflow::StatsOptions { json_output };
+            stats::show_stats(config.unwrap(), host, path, options).await
+        }
+        Commands::Status { host, max_age } => {
+            let options = shared::status_workflow::StatusOptions {
+                host,
+                max_age,
+                json_output,
+            };
+            status::show_status(config.unwrap(), options).await
+        }
+        Commands::History { host, path, runs } => {
+            let options = shared::history_workflow::HistoryOptions {
+                host,
+                path,
+                runs,
+                json_output,
+            };
+            history::show_history(options).await
+        }
+        Commands::Hosts => list::list_hosts(config.unwrap(), json_output).await,
+        Commands::Check {
+            host,
+            all_hosts,
+            read_data_subset,
+        } => {
+            let options = shared::check_workflow::CheckOptions {
+                read_data_subset,
+                all_hosts,
+                json_output,
+            };
+            check::run_check(config.unwrap(), host, options).await
+        }
+        Commands::Doctor { host } => {
+            let options = shared::doctor_workflow::DoctorOptions { json_output };
+            doctor::run_doctor(config.unwrap(), host, options).await
+        }
+        Commands::Mirror { host, dry_run } => {
+            let options = shared::mirror_workflow::MirrorOptions {
+                dry_run,
+                json_output,
+            };
+            mirror::run_mirror(config.unwrap(), host, options).await
+        }
+        Commands::Drill {
+            host,
+            sample,
+            scratch,
+            keep,
+        } => {
+            let options = shared::drill_workflow::DrillOptions {
+                sample,
+                scratch_dir: scratch,
+                keep,
+                json_output,
+            };
+            drill::run_drill(config.unwrap(), host, options).await
+        }
+        Commands::Prune {
+            host,
+            all_hosts,
+            preset,
+            max_unused,
+            repack_cacheable_only,
+            keep_last,
+            keep_daily,
+            keep_weekly,
+            keep_monthly,
+            keep_yearly,
+            group_by,
+            rules,
+            tag,
+            keep_tag,
+            dry_run,
+            jobs,
+            yes,
+            confirm,
+        } => {
+            let options = shared::prune_workflow::PruneOptions {
+                preset,
+                max_unused,
+                repack_cacheable_only,
+                assume_yes: yes,
+                confirm,
+                retention: shared::retention::RetentionPolicy {
+                    keep_last,
+                    keep_daily,
+                    keep_weekly,
+                    keep_monthly,
+                    keep_yearly,
+                },
+                group_by,
+                rules,
+                tags: tag,
+                keep_tags: keep_tag,
+                dry_run,
+                jobs,
+                all_hosts,
+            };
+            prune::run_prune(config.unwrap(), host, options).await
+        }
+        Commands::DeleteHost {
+            host,
+            dry_run,
+            forget_only,
+            yes,
+            confirm,
+        } => {
+            let options = shared::delete_host_workflow::DeleteHostOptions {
+                dry_run,
+                forget_only,
+                assume_yes: yes,
+                confirm,
+                json_output,
+            };
+            delete_host::run_delete_host(config.unwrap(), host, options).await
+        }
+        Commands::MigrateHost {
+            old,
+            new,
+            dry_run,
+            move_source,
+            yes,
+            confirm,
+        } => {
+            let options = shared::migrate_host_workflow::MigrateHostOptions {
+                dry_run,
+                move_source,
+                assume_yes: yes,
+                confirm,
+                json_output,
+            };
+            migrate_host::run_migrate_host(config.unwrap(), old, new, options).await
+        }
+        Commands::Logs {
+            follow,
+            since,
+            level,
+            lines,
+        } => {
+            let options = shared::logs_workflow::LogsOptions {
+                follow,
+                since,
+                level,
+                lines,
+            };
+            logs::show_logs(options).await
+        }
+        Commands::Key { action } => {
+            use shared::key_workflow::{KeyAction as Action, KeyOptions};
+            let (action, host, yes, confirm) = match action {
+                KeyAction::List { host } => (Action::List, host, false, None),
+                KeyAction::Add {
+                    new_password_file,
+                    host,
+                } => (Action::Add { new_password_file }, host, false, None),
+                KeyAction::Remove {
+                    password_file,
+                    host,
+                    yes,
+                    confirm,
+                } => (Action::Remove { password_file }, host, yes, confirm),
+                KeyAction::Rotate {
+                    new_password_file,
+                    host,
+                    yes,
+                    confirm,
+                } => (Action::Rotate { new_password_file }, host, yes, confirm),
+            };
+            let options = KeyOptions {
+                host,
+                assume_yes: yes,
+                confirm,
+                json_output,
+            };
+            key::run_key(config.unwrap(), action, options).await
+        }
+        Commands::Permissions { action } => match action {
+            PermissionsAction::Check { profile } => {
+                permissions::check_permissions(config.unwrap(), profile, json_output).await
+            }
+        },
+        Commands::Report { action } => match action {
+            ReportAction::Digest { host, days, send } => {
+                let options = shared::digest_workflow::DigestOptions {
+                    hosts: host,
+                    days,
+                    json_output,
+                    send,
+                };
+                report::send_digest(config.unwrap(), options).await
+            }
+            ReportAction::Coverage { min_size } => {
+                let options = shared::coverage_workflow::CoverageOptions {
+                    min_size,
+                    json_output,
+                };
+                report::show_coverage(config.unwrap(), options).await
+            }
+        },
+        Commands::Fleet { action } => match action {
+            FleetAction::Groups => fleet::show_groups().await,
+            FleetAction::Run { group, host, args } => fleet::run_group(group, host, args).await,
+            FleetAction::Prune {
+                group,
+                host,
+                yes,
+                confirm,
+            } => {
+                let options = shared::prune_workflow::PruneOptions {
+                    assume_yes: yes,
+                    confirm,
+                    ..Default::default()
+                };
+                fleet::prune_group(config.unwrap(), group, host, options).await
+            }
+        },
+        Commands::SelfManage { action } => match action {
+            SelfAction::Install { path, version } => {
+                let options = shared::self_update_workflow::SelfUpdateOptions {
+                    version,
+                    force: true,
+                };
+                self_update::run_self_install(path, options).await
+            }
+            SelfAction::Update { version, force } => {
+                let options = shared::self_update_workflow::SelfUpdateOptions { version, force };
+                self_update::run_self_update(options).await
+            }
+            SelfAction::InstallRestic { path, version } => {
+                self_update::run_restic_install(path, version).await
+            }
+        },
         Commands::Init => {
             if let Err(e) = init_env_file() {
                 render_pretty_error(&e);
-                std::process::exit(1);
+                std::process::exit(e.exit_code());
             }
             Ok(())
         }
@@ -177,14 +1226,188 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     if let Err(e) = result {
         render_pretty_error(&e);
-        std::process::exit(1);
+        std::process::exit(e.exit_code());
     }
 
     Ok(())
 }
 
-fn render_pretty_error(e: &crate::errors::BackupServiceError) {
-    use crate::errors::BackupServiceError::*;
+// External programs a command spawns (S3 access is built in and needs none)
+fn required_dependencies(command: &Commands) -> Vec<shared::dependencies::Dependency> {
+    use shared::dependencies::Dependency;
+
+    let env_set = |key: &str| std::env::var(key).is_ok_and(|v| !v.trim().is_empty());
+    let notifications_configured =
+        || shared::notify::Notifiers::from_env().is_ok_and(|notifiers| !notifiers.is_empty());
+    // Reports are delivered with curl (webhook) and sendmail (email)
+    let delivery = || {
+        let mut deps = Vec::new();
+        if env_set(shared::digest_workflow::DIGEST_WEBHOOK_ENV_VAR) {
+            deps.push(Dependency::curl("posting reports to REPORT_WEBHOOK_URL"));
+        }
+        if env_set(shared::digest_workflow::DIGEST_EMAIL_ENV_VAR) {
+            deps.push(Dependency::sendmail());
+        }
+        deps
+    };
+
+    let mut deps = match command {
+        // doctor reports missing programs itself
+        Commands::Init
+        | Commands::Logs { .. }
+        | Commands::History { .. }
+        | Commands::Status { .. }
+        | Commands::Hosts
+        | Commands::Permissions { .. }
+        | Commands::Doctor { .. } => Vec::new(),
+        Commands::Run { .. } | Commands::Daemon { .. } => {
+            let mut deps = vec![Dependency::restic()];
+            // Only mounts declared with a unit are started via systemctl
+            if std::env::var(shared::network_mounts::NETWORK_MOUNTS_ENV_VAR)
+                .is_ok_and(|v| v.contains('='))
+            {
+                deps.push(Dependency::systemctl());
+            }
+            let quiesce_flag = match command {
+                Commands::Run { docker_quiesce, .. } => docker_quiesce.as_deref(),
+                _ => None,
+            };
+            if shared::container_quiesce::QuiesceMode::resolve(quiesce_flag)
+                .is_ok_and(|mode| mode != shared::container_quiesce::QuiesceMode::Off)
+            {
+                deps.push(Dependency::docker(
+                    "pausing or stopping containers for BACKUP_DOCKER_QUIESCE",
+                ));
+            }
+            if env_set(shared::metrics::METRICS_PUSHGATEWAY_ENV_VAR) {
+                deps.push(Dependency::curl(
+                    "pushing metrics to METRICS_PUSHGATEWAY_URL",
+                ));
+            }
+            if shared::healthcheck::Healthcheck::from_env().is_configured() {
+                deps.push(Dependency::curl("sending healthcheck pings"));
+            }
+            if notifications_configured() {
+                deps.push(Dependency::curl("sending notifications"));
+            }
+            deps
+        }
+        Commands::Drill { .. } => {
+            let mut deps = vec![Dependency::restic()];
+            if env_set(shared::metrics::METRICS_PUSHGATEWAY_ENV_VAR) {
+                deps.push(Dependency::curl(
+                    "pushing metrics to METRICS_PUSHGATEWAY_URL",
+                ));
+            }
+            deps
+        }
+        Commands::Restore {
+            verify_containers, ..
+        } => {
+            let mut deps = vec![Dependency::restic()];
+            if *verify_containers {
+                deps.push(Dependency::docker(
+                    "restarting containers for --verify-containers",
+                ));
+                if env_set(shared::container_verify::VERIFY_PROBES_ENV_VAR) {
+                    deps.push(Dependency::curl("probing restored containers"));
+                }
+            }
+            if env_set(shared::restore_transcript::TRANSCRIPT_NOTIFY_ENV_VAR) {
+                deps.extend(delivery());
+            }
+            if notifications_configured() {
+                deps.push(Dependency::curl("sending notifications"));
+            }
+            deps
+        }
+        Commands::Report {
+            action: ReportAction::Digest { send: true, .. },
+        } => {
+            let mut deps = vec![Dependency::restic()];
+            deps.extend(delivery());
+            deps
+        }
+        // Deleting the host's directory goes through the storage API, not restic
+        Commands::DeleteHost { forget_only, .. } => {
+            if *forget_only {
+                vec![Dependency::restic()]
+            } else {
+                Vec::new()
+            }
+        }
+        // The copies are always given the new hostname with `restic rewrite`
+        Commands::MigrateHost { dry_run, .. } => {
+            if *dry_run {
+                Vec::new()
+            } else {
+                vec![Dependency::restic()]
+            }
+        }
+        Commands::Fleet {
+            action: FleetAction::Groups,
+        } => Vec::new(),
+        Commands::Fleet {
+            action: FleetAction::Run { .. },
+        } => vec![Dependency::ssh()],
+        Commands::SelfManage {
+            action: SelfAction::InstallRestic { .. },
+        } => vec![
+            Dependency::curl("downloading releases"),
+            Dependency::bzip2(),
+        ],
+        Commands::SelfManage { .. } => {
+            let mut deps = vec![Dependency::curl("downloading releases")];
+            if env_set(shared::self_update_workflow::RELEASE_PUBKEY_ENV_VAR) {
+                deps.push(Dependency::minisign());
+            }
+            deps
+        }
+        _ => vec![Dependency::restic()],
+    };
+    // restic reaches an SFTP mirror through ssh as well
+    let mirrors = matches!(command, Commands::Mirror { .. })
+        || (matches!(command, Commands::Run { .. } | Commands::Daemon { .. })
+            && shared::mirror_workflow::mirror_after_backup());
+    if mirrors
+        && std::env::var(shared::mirror_workflow::MIRROR_REPO_BASE_ENV_VAR)
+            .is_ok_and(|v| v.trim().starts_with("sftp:"))
+    {
+        deps.push(Dependency::sftp_ssh());
+    }
+    // Repository discovery on an SFTP backend lists directories over ssh
+    let discovers = matches!(
+        command,
+        Commands::Hosts | Commands::DeleteHost { .. } | Commands::MigrateHost { .. }
+    ) || deps.contains(&Dependency::restic());
+    if discovers && std::env::var("RESTIC_REPO_BASE").is_ok_and(|v| v.trim().starts_with("sftp:")) {
+        deps.push(Dependency::sftp_ssh());
+    }
+    deps
+}
+
+// Export the requested fault for the executor layer, refusing unless explicitly gated
+fn enable_fault_injection(fault: &str) {
+    use shared::faults::{FAULT_ENV_VAR, FAULT_GATE_ENV_VAR, FaultKind};
+
+    if std::env::var(FAULT_GATE_ENV_VAR).ok().as_deref() != Some("1") {
+        warn!(
+            "--inject-fault ignored: set {}=1 to enable fault injection",
+            FAULT_GATE_ENV_VAR
+        );
+        return;
+    }
+    if FaultKind::parse(fault).is_none() {
+        warn!(fault = %fault, "Unknown fault kind, ignoring --inject-fault");
+        return;
+    }
+    warn!(fault = %fault, "Fault injection enabled");
+    // SAFETY: Called during startup before any commands or tasks are spawned.
+    unsafe { std::env::set_var(FAULT_ENV_VAR, fault) };
+}
+
+fn render_pretty_error(e: &errors::BackupServiceError) {
+    use errors::BackupServiceError::*;
     use tracing::{error, info};
 
     match e {
@@ -208,14 +1431,43 @@ fn render_pretty_error(e: &crate::errors::BackupServiceError) {
         NetworkError => error!("Network error: cannot connect to repository"),
         RepositoryNotFound(ctx) => error!("Repository not found: {}", ctx),
         CommandFailed(msg) => error!("Command execution failed: {}", msg),
+        // Missing dependencies: header, then one line per program with its install command
+        CommandNotFound(msg) if msg.contains('\n') => {
+            if let Some((first, rest)) = msg.split_once('\n') {
+                error!("{}", first.trim());
+                for line in rest.lines().map(str::trim).filter(|l| !l.is_empty()) {
+                    info!("  {}", line);
+                }
+            }
+        }
         CommandNotFound(cmd) => error!("Command not found or execution error: {}", cmd),
         CredentialValidationFailed(inner) => render_pretty_error(inner),
+        AlreadyRunning(holder) => {
+            warn!(
+                "{}",
+                i18n::t_args("already-running", &[("holder", holder.clone())])
+            )
+        }
         other => error!("{}", other),
     }
+
+    // Nested errors render their own hint through the recursive call above
+    if !matches!(e, CredentialValidationFailed(_))
+        && let Some(hint) = e.hint()
+    {
+        info!("{}: {}", i18n::t("hint-prefix"), hint);
+    }
+}
+
+// Exit codes by failure type, for OnFailure handlers and scripts
+fn print_exit_codes() {
+    for (code, meaning) in errors::EXIT_CODES {
+        println!("{:>3}  {}", code, meaning);
+    }
 }
 
 // Create sample .env file with configuration template for first-time setup
-fn init_env_file() -> Result<(), crate::errors::BackupServiceError> {
+fn init_env_file() -> Result<(), errors::BackupServiceError> {
     use std::fs;
     use std::path::Path;
 
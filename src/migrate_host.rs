@@ -0,0 +1,13 @@
+use crate::config::Config;
+use crate::errors::BackupServiceError;
+use crate::shared::migrate_host_workflow::{MigrateHostOptions, execute_migrate_host_workflow};
+
+// CLI command to relocate a renamed host's repositories below its new hostname
+pub async fn run_migrate_host(
+    config: Config,
+    old: String,
+    new: String,
+    options: MigrateHostOptions,
+) -> Result<(), BackupServiceError> {
+    execute_migrate_host_workflow(config, old, new, options).await
+}
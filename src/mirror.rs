@@ -0,0 +1,12 @@
+use crate::config::Config;
+use crate::errors::BackupServiceError;
+use crate::shared::mirror_workflow::{MirrorOptions, execute_mirror_workflow};
+
+// CLI command to replicate all repositories of a host to the secondary repository base
+pub async fn run_mirror(
+    config: Config,
+    host: Option<String>,
+    options: MirrorOptions,
+) -> Result<(), BackupServiceError> {
+    execute_mirror_workflow(config, host, options).await
+}
@@ -0,0 +1,13 @@
+use crate::config::Config;
+use crate::errors::BackupServiceError;
+use crate::shared::permissions_workflow::{KeyProfile, execute_permissions_check};
+
+// CLI command to probe the effective permissions of the configured S3 credentials
+pub async fn check_permissions(
+    config: Config,
+    profile: String,
+    json: bool,
+) -> Result<(), BackupServiceError> {
+    let profile = KeyProfile::parse(&profile)?;
+    execute_permissions_check(config, profile, json).await
+}
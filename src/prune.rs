@@ -0,0 +1,12 @@
+use crate::config::Config;
+use crate::errors::BackupServiceError;
+use crate::shared::prune_workflow::{PruneOptions, execute_prune_workflow};
+
+// CLI command to compact all repositories of a host with tuned repack settings
+pub async fn run_prune(
+    config: Config,
+    host: Option<String>,
+    options: PruneOptions,
+) -> Result<(), BackupServiceError> {
+    execute_prune_workflow(config, host, options).await
+}
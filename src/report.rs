@@ -0,0 +1,17 @@
+use crate::config::Config;
+use crate::errors::BackupServiceError;
+use crate::shared::coverage_workflow::{CoverageOptions, execute_coverage};
+use crate::shared::digest_workflow::{DigestOptions, execute_digest};
+
+// CLI command to summarize a period of backups per host in one notification
+pub async fn send_digest(config: Config, options: DigestOptions) -> Result<(), BackupServiceError> {
+    execute_digest(config, options).await
+}
+
+// CLI command to score backup coverage and list unprotected directories
+pub async fn show_coverage(
+    config: Config,
+    options: CoverageOptions,
+) -> Result<(), BackupServiceError> {
+    execute_coverage(config, options).await
+}
@@ -1,6 +1,6 @@
 use crate::config::Config;
 use crate::errors::BackupServiceError;
-use crate::shared::restore_workflow::RestoreWorkflow;
+use crate::shared::restore_workflow::{RestoreOptions, RestoreWorkflow};
 
 // CLI command for interactive restore with optional pre-filled parameters
 pub async fn restore_interactive(
@@ -8,7 +8,8 @@ pub async fn restore_interactive(
     host_opt: Option<String>,
     path_opt: Option<String>,
     timestamp_opt: Option<String>,
+    options: RestoreOptions,
 ) -> Result<(), BackupServiceError> {
-    let workflow = RestoreWorkflow::new(config, host_opt, path_opt, timestamp_opt)?;
+    let workflow = RestoreWorkflow::new(config, host_opt, path_opt, timestamp_opt, options)?;
     workflow.execute_interactive_restore().await
 }
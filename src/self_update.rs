@@ -0,0 +1,26 @@
+use crate::errors::BackupServiceError;
+use crate::shared::self_update_workflow::{
+    SelfUpdateOptions, execute_restic_install, execute_self_install, execute_self_update,
+};
+use std::path::PathBuf;
+
+// CLI command to install the release binary for this platform at a path
+pub async fn run_self_install(
+    path: PathBuf,
+    options: SelfUpdateOptions,
+) -> Result<(), BackupServiceError> {
+    execute_self_install(path, options)
+}
+
+// CLI command to install the official static restic binary at a path
+pub async fn run_restic_install(
+    path: PathBuf,
+    version: Option<String>,
+) -> Result<(), BackupServiceError> {
+    execute_restic_install(path, version)
+}
+
+// CLI command to replace the running executable with the latest (or given) release
+pub async fn run_self_update(options: SelfUpdateOptions) -> Result<(), BackupServiceError> {
+    execute_self_update(options)
+}
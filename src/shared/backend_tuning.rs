@@ -0,0 +1,300 @@
+use crate::config::RepoBackend;
+use crate::errors::BackupServiceError;
+
+/// Prefix of the tuning env vars: `RESTIC_TUNING` picks the profile (`auto` by default),
+/// `RESTIC_TUNING_<OPTION>` overrides one value of it
+const ENV_PREFIX: &str = "RESTIC_TUNING";
+
+const PACK_SIZE: &str = "PACK_SIZE";
+/// restic's own pack size variable; honored when RESTIC_TUNING_PACK_SIZE is unset, since the
+/// `--pack-size` flag of the profile would otherwise override it
+const RESTIC_PACK_SIZE_ENV_VAR: &str = "RESTIC_PACK_SIZE";
+const CONNECTIONS: &str = "CONNECTIONS";
+const RETRY_LOCK: &str = "RETRY_LOCK";
+
+/// Pack sizes restic accepts, in MiB
+const PACK_SIZE_RANGE: std::ops::RangeInclusive<u32> = 4..=128;
+
+/// Storage profiles with restic settings that suit them better than restic's defaults
+/// (16 MiB packs, 5 S3 connections, no lock retries)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackendProfile {
+    /// Cloudflare R2: writes are billed per request and parallel uploads are cheap
+    R2,
+    /// AWS S3: high per-request latency, scales with connections
+    AwsS3,
+    /// MinIO and other self-hosted S3-compatible servers
+    Minio,
+    /// Every connection is an ssh channel to the same host
+    Sftp,
+    /// Local or mounted directory; more files per directory is harmless
+    Local,
+}
+
+impl BackendProfile {
+    pub fn parse(name: &str) -> Result<Self, BackupServiceError> {
+        match name.trim().to_lowercase().as_str() {
+            "r2" => Ok(BackendProfile::R2),
+            "s3" => Ok(BackendProfile::AwsS3),
+            "minio" => Ok(BackendProfile::Minio),
+            "sftp" => Ok(BackendProfile::Sftp),
+            "local" => Ok(BackendProfile::Local),
+            _ => Err(BackupServiceError::ConfigurationError(format!(
+                "Unknown backend tuning profile: {}.\n\nValid values for {} are: auto, off, r2, s3, minio, sftp, local",
+                name, ENV_PREFIX
+            ))),
+        }
+    }
+
+    /// Pick a profile from the repository base URL; unknown S3 endpoints count as MinIO
+    pub fn detect(repo_base: &str) -> Result<Self, BackupServiceError> {
+        Ok(match RepoBackend::parse(repo_base)? {
+            RepoBackend::S3 if repo_base.contains("r2.cloudflarestorage.com") => BackendProfile::R2,
+            RepoBackend::S3 if repo_base.contains("amazonaws.com") => BackendProfile::AwsS3,
+            RepoBackend::S3 => BackendProfile::Minio,
+            RepoBackend::Sftp { .. } => BackendProfile::Sftp,
+            RepoBackend::Local { .. } => BackendProfile::Local,
+        })
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            BackendProfile::R2 => "r2",
+            BackendProfile::AwsS3 => "s3",
+            BackendProfile::Minio => "minio",
+            BackendProfile::Sftp => "sftp",
+            BackendProfile::Local => "local",
+        }
+    }
+
+    /// Pack size in MiB, backend connections and how long to wait for a locked repository
+    fn defaults(&self) -> (u32, u32, &'static str) {
+        match self {
+            // Bigger packs mean fewer billed writes
+            BackendProfile::R2 => (64, 8, "2m"),
+            BackendProfile::AwsS3 => (32, 10, "2m"),
+            BackendProfile::Minio => (32, 8, "1m"),
+            // sftp servers often cap sessions per user; stay at restic's default
+            BackendProfile::Sftp => (16, 5, "1m"),
+            BackendProfile::Local => (32, 2, "1m"),
+        }
+    }
+}
+
+/// restic options applied to every call against the repository
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BackendTuning {
+    /// None when tuning is off
+    pub profile: Option<BackendProfile>,
+    pub pack_size_mib: Option<u32>,
+    pub connections: Option<u32>,
+    /// Duration in restic's format (`2m`, `30s`)
+    pub retry_lock: Option<String>,
+    /// Extended option namespace of the repository's backend (`s3`, `sftp`, `local`)
+    namespace: &'static str,
+}
+
+impl BackendTuning {
+    /// Tuning for `repo_base` from the environment
+    pub fn from_env(repo_base: &str) -> Result<Self, BackupServiceError> {
+        Self::from_lookup(repo_base, |name| std::env::var(name).ok())
+    }
+
+    fn from_lookup(
+        repo_base: &str,
+        lookup: impl Fn(&str) -> Option<String>,
+    ) -> Result<Self, BackupServiceError> {
+        let namespace = match RepoBackend::parse(repo_base)? {
+            RepoBackend::S3 => "s3",
+            RepoBackend::Sftp { .. } => "sftp",
+            RepoBackend::Local { .. } => "local",
+        };
+        let profile = match lookup(ENV_PREFIX).map(|v| v.trim().to_lowercase()) {
+            Some(v) if v == "off" || v == "none" => {
+                return Ok(Self {
+                    namespace,
+                    ..Self::default()
+                });
+            }
+            Some(v) if !v.is_empty() && v != "auto" => BackendProfile::parse(&v)?,
+            _ => BackendProfile::detect(repo_base)?,
+        };
+
+        // An override replaces one value of the profile; "none" leaves it to restic
+        let value = |option: &str| {
+            lookup(&format!("{}_{}", ENV_PREFIX, option)).map(|v| v.trim().to_string())
+        };
+        let (default_pack_size, default_connections, default_retry_lock) = profile.defaults();
+        let pack_size_mib = match value(PACK_SIZE)
+            .or_else(|| lookup(RESTIC_PACK_SIZE_ENV_VAR).map(|v| v.trim().to_string()))
+        {
+            None => Some(default_pack_size),
+            Some(v) if v.is_empty() || v == "none" => None,
+            Some(v) => match v.trim_end_matches(['M', 'i', 'B']).parse::<u32>() {
+                Ok(size) if PACK_SIZE_RANGE.contains(&size) => Some(size),
+                _ => return Err(invalid_option(PACK_SIZE, &v, "a size in MiB from 4 to 128")),
+            },
+        };
+        let connections = match value(CONNECTIONS) {
+            None => Some(default_connections),
+            Some(v) if v.is_empty() || v == "none" => None,
+            Some(v) => match v.parse::<u32>() {
+                Ok(count) if count > 0 => Some(count),
+                _ => return Err(invalid_option(CONNECTIONS, &v, "a positive number")),
+            },
+        };
+        let retry_lock = match value(RETRY_LOCK) {
+            None => Some(default_retry_lock.to_string()),
+            Some(v) if v.is_empty() || v == "none" => None,
+            Some(v) if is_restic_duration(&v) => Some(v),
+            Some(v) => {
+                return Err(invalid_option(
+                    RETRY_LOCK,
+                    &v,
+                    "a duration such as 30s, 2m or 1h30m",
+                ));
+            }
+        };
+
+        Ok(Self {
+            profile: Some(profile),
+            pack_size_mib,
+            connections,
+            retry_lock,
+            namespace,
+        })
+    }
+
+    /// Global restic flags for this tuning
+    pub fn restic_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if let Some(size) = self.pack_size_mib {
+            args.extend(["--pack-size".to_string(), size.to_string()]);
+        }
+        if let Some(count) = self.connections {
+            args.extend([
+                "-o".to_string(),
+                format!("{}.connections={}", self.namespace, count),
+            ]);
+        }
+        if let Some(duration) = &self.retry_lock {
+            args.extend(["--retry-lock".to_string(), duration.clone()]);
+        }
+        args
+    }
+}
+
+/// Go duration syntax restricted to what makes sense for waiting: `30s`, `2m`, `1h30m`
+fn is_restic_duration(value: &str) -> bool {
+    let mut digits = 0;
+    let mut units = 0;
+    for c in value.chars() {
+        match c {
+            '0'..='9' => digits += 1,
+            'h' | 'm' | 's' if digits > 0 => {
+                digits = 0;
+                units += 1;
+            }
+            _ => return false,
+        }
+    }
+    digits == 0 && units > 0
+}
+
+fn invalid_option(option: &str, value: &str, expected: &str) -> BackupServiceError {
+    BackupServiceError::ConfigurationError(format!(
+        "Invalid {}_{}: {}.\n\nExpected {}, or none to keep restic's default",
+        ENV_PREFIX, option, value, expected
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn lookup(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        move |name| vars.get(name).cloned()
+    }
+
+    #[test]
+    fn test_profile_detection() -> Result<(), BackupServiceError> {
+        let detect = BackendProfile::detect;
+        assert_eq!(
+            detect("s3:https://abc.r2.cloudflarestorage.com/restic")?,
+            BackendProfile::R2
+        );
+        assert_eq!(
+            detect("s3:https://s3.eu-central-1.amazonaws.com/bucket")?,
+            BackendProfile::AwsS3
+        );
+        assert_eq!(
+            detect("s3:https://minio.lan:9000/restic")?,
+            BackendProfile::Minio
+        );
+        assert_eq!(detect("sftp:backup@nas:/srv/restic")?, BackendProfile::Sftp);
+        assert_eq!(detect("/srv/restic")?, BackendProfile::Local);
+        Ok(())
+    }
+
+    #[test]
+    fn test_tuning_args_and_overrides() -> Result<(), BackupServiceError> {
+        let r2 = "s3:https://abc.r2.cloudflarestorage.com/restic";
+        let detected = BackendTuning::from_lookup(r2, lookup(&[]))?;
+        assert_eq!(
+            detected.restic_args(),
+            vec![
+                "--pack-size",
+                "64",
+                "-o",
+                "s3.connections=8",
+                "--retry-lock",
+                "2m"
+            ]
+        );
+
+        // Forced profile, one value overridden and one left to restic
+        let tuned = BackendTuning::from_lookup(
+            "sftp:backup@nas:/srv/restic",
+            lookup(&[
+                ("RESTIC_TUNING", "local"),
+                ("RESTIC_TUNING_CONNECTIONS", "3"),
+                ("RESTIC_TUNING_RETRY_LOCK", "none"),
+            ]),
+        )?;
+        assert_eq!(tuned.profile, Some(BackendProfile::Local));
+        assert_eq!(
+            tuned.restic_args(),
+            vec!["--pack-size", "32", "-o", "sftp.connections=3"]
+        );
+
+        // restic's own RESTIC_PACK_SIZE replaces the profile's size, the tuning override wins
+        let restic_env = BackendTuning::from_lookup(r2, lookup(&[("RESTIC_PACK_SIZE", "128")]))?;
+        assert_eq!(restic_env.pack_size_mib, Some(128));
+        let both = BackendTuning::from_lookup(
+            r2,
+            lookup(&[
+                ("RESTIC_PACK_SIZE", "128"),
+                ("RESTIC_TUNING_PACK_SIZE", "16"),
+            ]),
+        )?;
+        assert_eq!(both.pack_size_mib, Some(16));
+
+        let off = BackendTuning::from_lookup(r2, lookup(&[("RESTIC_TUNING", "off")]))?;
+        assert!(off.restic_args().is_empty());
+
+        for (name, value) in [
+            ("RESTIC_TUNING", "glacier"),
+            ("RESTIC_TUNING_PACK_SIZE", "256"),
+            ("RESTIC_TUNING_CONNECTIONS", "0"),
+            ("RESTIC_TUNING_RETRY_LOCK", "5d"),
+        ] {
+            assert!(BackendTuning::from_lookup(r2, lookup(&[(name, value)])).is_err());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,243 @@
+use crate::utils::format_bytes;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::io::{IsTerminal, Write};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::info;
+
+/// Seconds between progress log lines when stderr is not a terminal (0 disables them)
+pub const PROGRESS_INTERVAL_ENV_VAR: &str = "BACKUP_PROGRESS_INTERVAL";
+const DEFAULT_PROGRESS_INTERVAL_SECS: u64 = 30;
+
+/// One `message_type: status` line of `restic backup --json`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BackupStatusLine {
+    pub percent_done: f64,
+    pub files_done: u64,
+    pub total_files: u64,
+    pub bytes_done: u64,
+    pub total_bytes: u64,
+    /// restic's estimate; absent while the scan is still running
+    pub seconds_remaining: Option<u64>,
+}
+
+impl BackupStatusLine {
+    pub fn from_json(value: &Value) -> Option<Self> {
+        if value["message_type"] != "status" {
+            return None;
+        }
+        Some(Self {
+            percent_done: value["percent_done"].as_f64().unwrap_or(0.0),
+            files_done: value["files_done"].as_u64().unwrap_or(0),
+            total_files: value["total_files"].as_u64().unwrap_or(0),
+            bytes_done: value["bytes_done"].as_u64().unwrap_or(0),
+            total_bytes: value["total_bytes"].as_u64().unwrap_or(0),
+            seconds_remaining: value["seconds_remaining"].as_u64(),
+        })
+    }
+
+    /// `42.0%, 1.20 GB / 3.00 GB, 1200 / 5000 files, ETA 3m 12s`
+    pub fn describe(&self) -> String {
+        let bytes = |b| format_bytes(b).unwrap_or_else(|_| format!("{} B", b));
+        let mut text = format!(
+            "{:.1}%, {} / {}, {} / {} files",
+            self.percent_done * 100.0,
+            bytes(self.bytes_done),
+            bytes(self.total_bytes),
+            self.files_done,
+            self.total_files
+        );
+        if let Some(secs) = self.seconds_remaining {
+            text.push_str(&format!(", ETA {}", format_eta(secs)));
+        }
+        text
+    }
+}
+
+/// Latest status of every path being backed up, drawn as one terminal line
+#[derive(Debug, Default)]
+struct Board {
+    active: BTreeMap<String, BackupStatusLine>,
+    drawn: bool,
+}
+
+/// Shared by the paths of a parallel run (BACKUP_CONCURRENCY) so their lines never interleave
+static BOARD: Mutex<Board> = Mutex::new(Board {
+    active: BTreeMap::new(),
+    drawn: false,
+});
+
+/// One path: its own status; several: their sums and the longest ETA
+fn board_line(active: &BTreeMap<String, BackupStatusLine>) -> String {
+    if let [(path, status)] = active.iter().collect::<Vec<_>>().as_slice() {
+        return format!("{}: {}", path, status.describe());
+    }
+    let bytes = |b| format_bytes(b).unwrap_or_else(|_| format!("{} B", b));
+    let sum = |f: fn(&BackupStatusLine) -> u64| active.values().map(f).sum::<u64>();
+    let mut text = format!(
+        "{} paths: {} / {}, {} / {} files",
+        active.len(),
+        bytes(sum(|s| s.bytes_done)),
+        bytes(sum(|s| s.total_bytes)),
+        sum(|s| s.files_done),
+        sum(|s| s.total_files)
+    );
+    if let Some(secs) = active.values().filter_map(|s| s.seconds_remaining).max() {
+        text.push_str(&format!(", ETA {}", format_eta(secs)));
+    }
+    text
+}
+
+/// Clear the status line before logging while other paths are still running
+pub fn clear_status_line() {
+    let mut board = BOARD.lock().unwrap_or_else(|e| e.into_inner());
+    if board.drawn {
+        let mut stderr = std::io::stderr();
+        let _ = write!(stderr, "\r\x1b[2K");
+        let _ = stderr.flush();
+        board.drawn = false;
+    }
+}
+
+/// `45s`, `3m 12s`, `2h 05m`
+pub fn format_eta(secs: u64) -> String {
+    match secs {
+        0..60 => format!("{}s", secs),
+        60..3600 => format!("{}m {:02}s", secs / 60, secs % 60),
+        _ => format!("{}h {:02}m", secs / 3600, (secs % 3600) / 60),
+    }
+}
+
+/// Shows the live progress of one path's backup
+///
+/// On a terminal the status line is redrawn in place on stderr, combined with the other
+/// paths of a parallel run; otherwise (journald, log files) a line is logged every
+/// BACKUP_PROGRESS_INTERVAL seconds.
+pub struct BackupProgress {
+    path: String,
+    terminal: bool,
+    interval: Option<Duration>,
+    last_report: Instant,
+    on_board: bool,
+}
+
+impl BackupProgress {
+    pub fn new(path: &str) -> Self {
+        let interval = std::env::var(PROGRESS_INTERVAL_ENV_VAR)
+            .ok()
+            .and_then(|v| v.trim().parse().ok())
+            .unwrap_or(DEFAULT_PROGRESS_INTERVAL_SECS);
+        Self {
+            path: path.to_string(),
+            terminal: std::io::stderr().is_terminal(),
+            interval: (interval > 0).then(|| Duration::from_secs(interval)),
+            last_report: Instant::now(),
+            on_board: false,
+        }
+    }
+
+    pub fn update(&mut self, status: &BackupStatusLine) {
+        if self.terminal {
+            let mut board = BOARD.lock().unwrap_or_else(|e| e.into_inner());
+            board.active.insert(self.path.clone(), status.clone());
+            let mut stderr = std::io::stderr();
+            let _ = write!(stderr, "\r\x1b[2K{}", board_line(&board.active));
+            let _ = stderr.flush();
+            board.drawn = true;
+            self.on_board = true;
+        } else if let Some(interval) = self.interval
+            && self.last_report.elapsed() >= interval
+        {
+            self.last_report = Instant::now();
+            info!(path = %self.path, progress = %status.describe(), "Backup progress");
+        }
+    }
+
+    /// Take the path off the status line; the line is cleared so the next log line starts
+    /// on an empty row and redrawn by the next update of another path
+    pub fn finish(&mut self) {
+        if self.on_board {
+            BOARD
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .active
+                .remove(&self.path);
+            self.on_board = false;
+            clear_status_line();
+        }
+    }
+}
+
+impl Drop for BackupProgress {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_status_line_parsing() {
+        let line = json!({
+            "message_type": "status",
+            "seconds_elapsed": 12,
+            "seconds_remaining": 192,
+            "percent_done": 0.42,
+            "total_files": 5000,
+            "files_done": 1200,
+            "total_bytes": 3_221_225_472u64,
+            "bytes_done": 1_288_490_189u64
+        });
+        let status = BackupStatusLine::from_json(&line).unwrap();
+        assert_eq!(status.files_done, 1200);
+        assert_eq!(
+            status.describe(),
+            "42.0%, 1.20 GB / 3.00 GB, 1200 / 5000 files, ETA 3m 12s"
+        );
+
+        // The scan has not finished yet: no total and no estimate
+        let early = json!({ "message_type": "status", "percent_done": 0, "files_done": 3 });
+        assert_eq!(
+            BackupStatusLine::from_json(&early)
+                .unwrap()
+                .seconds_remaining,
+            None
+        );
+        assert!(BackupStatusLine::from_json(&json!({ "message_type": "summary" })).is_none());
+    }
+
+    #[test]
+    fn test_board_line_combines_parallel_paths() {
+        let status = |bytes_done, files_done, seconds_remaining| BackupStatusLine {
+            percent_done: 0.5,
+            files_done,
+            total_files: 2 * files_done,
+            bytes_done,
+            total_bytes: 2 * bytes_done,
+            seconds_remaining,
+        };
+        let mut active = BTreeMap::new();
+        active.insert("/etc".to_string(), status(1024, 10, Some(30)));
+        assert_eq!(
+            board_line(&active),
+            "/etc: 50.0%, 1.00 KB / 2.00 KB, 10 / 20 files, ETA 30s"
+        );
+
+        active.insert("/home/tim".to_string(), status(2048, 5, Some(192)));
+        assert_eq!(
+            board_line(&active),
+            "2 paths: 3.00 KB / 6.00 KB, 15 / 30 files, ETA 3m 12s"
+        );
+    }
+
+    #[test]
+    fn test_format_eta() {
+        assert_eq!(format_eta(45), "45s");
+        assert_eq!(format_eta(192), "3m 12s");
+        assert_eq!(format_eta(7_500), "2h 05m");
+    }
+}
@@ -0,0 +1,192 @@
+use chrono::DateTime;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Per-snapshot statistics as reported by restic at the end of a backup
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ResticSummary {
+    pub snapshot_id: Option<String>,
+    pub files_new: u64,
+    pub files_changed: u64,
+    pub files_unmodified: u64,
+    pub total_files_processed: u64,
+    /// Bytes added to the repository (after deduplication, before compression)
+    pub data_added: u64,
+    /// Bytes actually stored after compression, i.e. sent to the backend (restic >= 0.17)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data_added_packed: Option<u64>,
+    pub duration_secs: Option<f64>,
+}
+
+impl ResticSummary {
+    /// Parse a `backup --json` summary message or a snapshot carrying a `summary` object
+    pub fn from_json(value: &Value) -> Option<Self> {
+        // Snapshot listings (restic >= 0.17) nest the summary and keep the ID outside
+        let (summary, snapshot_id) = match value.get("summary") {
+            Some(summary) => (summary, value["short_id"].as_str()),
+            None => (value, value["snapshot_id"].as_str()),
+        };
+        if !summary.is_object() || summary.get("files_new").is_none() {
+            return None;
+        }
+
+        let duration_secs = summary["total_duration"].as_f64().or_else(|| {
+            let start = DateTime::parse_from_rfc3339(summary["backup_start"].as_str()?).ok()?;
+            let end = DateTime::parse_from_rfc3339(summary["backup_end"].as_str()?).ok()?;
+            Some((end - start).num_milliseconds() as f64 / 1000.0)
+        });
+
+        Some(Self {
+            snapshot_id: snapshot_id.map(str::to_string),
+            files_new: summary["files_new"].as_u64().unwrap_or(0),
+            files_changed: summary["files_changed"].as_u64().unwrap_or(0),
+            files_unmodified: summary["files_unmodified"].as_u64().unwrap_or(0),
+            total_files_processed: summary["total_files_processed"].as_u64().unwrap_or(0),
+            data_added: summary["data_added"].as_u64().unwrap_or(0),
+            data_added_packed: summary["data_added_packed"].as_u64(),
+            duration_secs,
+        })
+    }
+
+    /// Parse the human-readable summary printed by `restic backup`
+    pub fn from_text(output: &str) -> Option<Self> {
+        let mut summary = Self::default();
+        let mut found = false;
+
+        for line in output.lines().map(str::trim) {
+            if let Some(rest) = line.strip_prefix("Files:") {
+                // "Files:         5 new,     2 changed,   100 unmodified"
+                for part in rest.split(',') {
+                    let mut words = part.split_whitespace();
+                    let count = words.next().and_then(|n| n.parse().ok()).unwrap_or(0);
+                    match words.next() {
+                        Some("new") => summary.files_new = count,
+                        Some("changed") => summary.files_changed = count,
+                        Some("unmodified") => summary.files_unmodified = count,
+                        _ => {}
+                    }
+                }
+                found = true;
+            } else if let Some(rest) = line.strip_prefix("Added to the repository:") {
+                // "Added to the repository: 1.234 MiB (600.123 KiB stored)"
+                let mut words = rest.split_whitespace();
+                if let (Some(value), Some(unit)) = (words.next(), words.next()) {
+                    summary.data_added = parse_restic_bytes(value, unit).unwrap_or(0);
+                }
+                if let (Some(value), Some(unit)) = (words.next(), words.next()) {
+                    summary.data_added_packed =
+                        parse_restic_bytes(value.trim_start_matches('('), unit);
+                }
+            } else if let Some(rest) = line.strip_prefix("processed ") {
+                // "processed 107 files, 3.432 GiB in 0:12"
+                summary.total_files_processed = rest
+                    .split_whitespace()
+                    .next()
+                    .and_then(|n| n.parse().ok())
+                    .unwrap_or(0);
+                summary.duration_secs = rest
+                    .rsplit_once(" in ")
+                    .and_then(|(_, d)| parse_restic_duration(d));
+            } else if line.starts_with("snapshot ") && line.ends_with(" saved") {
+                summary.snapshot_id = line.split_whitespace().nth(1).map(str::to_string);
+                found = true;
+            }
+        }
+
+        found.then_some(summary)
+    }
+}
+
+/// Convert restic's formatted sizes (`1.234 MiB`) back to bytes
+pub fn parse_restic_bytes(value: &str, unit: &str) -> Option<u64> {
+    let value: f64 = value.parse().ok()?;
+    let multiplier: u64 = match unit {
+        "B" => 1,
+        "KiB" => 1 << 10,
+        "MiB" => 1 << 20,
+        "GiB" => 1 << 30,
+        "TiB" => 1 << 40,
+        _ => return None,
+    };
+    Some((value * multiplier as f64).round() as u64)
+}
+
+/// Convert restic's elapsed time (`0:12`, `1:02:03`) to seconds
+fn parse_restic_duration(value: &str) -> Option<f64> {
+    value.trim().split(':').try_fold(0.0, |acc, part| {
+        Some(acc * 60.0 + part.parse::<f64>().ok()?)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary_from_json_message() {
+        let message: Value = serde_json::from_str(
+            r#"{"message_type":"summary","files_new":5,"files_changed":2,"files_unmodified":100,
+                "total_files_processed":107,"data_added":1293942,"total_duration":12.5,
+                "snapshot_id":"1a2b3c4d5e6f"}"#,
+        )
+        .unwrap();
+        let summary = ResticSummary::from_json(&message).unwrap();
+        assert_eq!(summary.snapshot_id.as_deref(), Some("1a2b3c4d5e6f"));
+        assert_eq!(summary.files_new, 5);
+        assert_eq!(summary.files_changed, 2);
+        assert_eq!(summary.files_unmodified, 100);
+        assert_eq!(summary.total_files_processed, 107);
+        assert_eq!(summary.data_added, 1293942);
+        assert_eq!(summary.data_added_packed, None);
+        assert_eq!(summary.duration_secs, Some(12.5));
+    }
+
+    #[test]
+    fn test_summary_from_snapshot_listing() {
+        let snapshot: Value = serde_json::from_str(
+            r#"{"time":"2025-01-15T10:30:12Z","short_id":"abc123","summary":{
+                "backup_start":"2025-01-15T10:30:00Z","backup_end":"2025-01-15T10:30:12Z",
+                "files_new":1,"files_changed":0,"files_unmodified":9,"total_files_processed":10,
+                "data_added":2048}}"#,
+        )
+        .unwrap();
+        let summary = ResticSummary::from_json(&snapshot).unwrap();
+        assert_eq!(summary.snapshot_id.as_deref(), Some("abc123"));
+        assert_eq!(summary.total_files_processed, 10);
+        assert_eq!(summary.duration_secs, Some(12.0));
+
+        // Snapshots written by older restic versions carry no summary
+        let bare: Value = serde_json::from_str(r#"{"short_id":"abc123"}"#).unwrap();
+        assert_eq!(ResticSummary::from_json(&bare), None);
+    }
+
+    #[test]
+    fn test_summary_from_text_output() {
+        let output = "\
+Files:           5 new,     2 changed,   100 unmodified
+Dirs:            0 new,     1 changed,     9 unmodified
+Added to the repository: 1.500 MiB (600.123 KiB stored)
+
+processed 107 files, 3.432 GiB in 1:02
+snapshot 1a2b3c4d saved
+";
+        let summary = ResticSummary::from_text(output).unwrap();
+        assert_eq!(summary.snapshot_id.as_deref(), Some("1a2b3c4d"));
+        assert_eq!(summary.files_new, 5);
+        assert_eq!(summary.files_changed, 2);
+        assert_eq!(summary.files_unmodified, 100);
+        assert_eq!(summary.total_files_processed, 107);
+        assert_eq!(summary.data_added, 1_572_864);
+        assert_eq!(summary.data_added_packed, Some(614_526));
+        assert_eq!(summary.duration_secs, Some(62.0));
+
+        assert_eq!(ResticSummary::from_text("Fatal: unable to open repo"), None);
+    }
+
+    #[test]
+    fn test_parse_restic_duration() {
+        assert_eq!(parse_restic_duration("0:12"), Some(12.0));
+        assert_eq!(parse_restic_duration("1:02:03"), Some(3723.0));
+        assert_eq!(parse_restic_duration("soon"), None);
+    }
+}
@@ -1,40 +1,568 @@
 use crate::config::Config;
 use crate::errors::BackupServiceError;
+use crate::i18n::{t, t_args};
+use crate::repository::BackupRepo;
+use crate::shared::backend_tuning::BackendTuning;
+use crate::shared::backup_progress::clear_status_line;
+use crate::shared::backup_summary::ResticSummary;
+use crate::shared::budgets::{BudgetAlert, check_host_budgets};
 use crate::shared::commands::ResticCommandExecutor;
-use crate::shared::paths::{PathMapper, PathUtilities};
-use crate::utils::validate_credentials;
+use crate::shared::compression::CompressionRules;
+use crate::shared::constants::{CATEGORY_DOCKER_VOLUME, CATEGORY_SYSTEM, CATEGORY_USER_HOME};
+use crate::shared::container_quiesce::{DockerCli, QuiesceMode, QuiescedContainers};
+use crate::shared::content_policy::{
+    ContentFinding, ContentPolicy, ContentScanner, exclude_pattern,
+};
+use crate::shared::display::DisplayFormatter;
+use crate::shared::email_report::EmailReporter;
+use crate::shared::error_policy::{BACKUP_ERROR_POLICY_ENV_VAR, ErrorPolicy};
+use crate::shared::excludes::{ExcludeRules, exclude_args};
+use crate::shared::healthcheck::Healthcheck;
+use crate::shared::history_workflow;
+use crate::shared::metrics::{self, PathMetrics, RunMetrics};
+use crate::shared::mirror_workflow;
+use crate::shared::network_mounts::MountSession;
+use crate::shared::notify::{Notification, Notifiers, Severity};
+use crate::shared::path_tags::PathTags;
+use crate::shared::paths::{OverlapPolicy, PathMapper, PathUtilities, resolve_repo_subpath};
+use crate::shared::preflight::{PathAccess, check_path_access, sample_size};
+use crate::shared::prune_workflow::PostBackupPrune;
+use crate::shared::resource_limits::ResourceLimits;
+use crate::shared::resource_usage::{ChildUsage, ResourceUsage};
+use crate::shared::scan_cache;
+use crate::shared::seed::SeedRun;
+use crate::shared::sensitive_paths::{
+    SENSITIVE_TAG, is_sensitive, nested_sensitive, sensitive_paths,
+};
+use crate::shared::shutdown;
+use crate::shared::ui::confirm_action;
+use crate::shared::unlock_workflow::{StaleLockPolicy, unlock_stale};
+use crate::utils::{format_bytes, resolve_jobs, validate_credentials};
+use serde::Serialize;
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::io::IsTerminal;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::task::JoinSet;
 use tracing::{error, info, warn};
 
+/// Paths backed up in parallel (`run --jobs` overrides it)
+pub const BACKUP_CONCURRENCY_ENV_VAR: &str = "BACKUP_CONCURRENCY";
+
+/// Unlock repositories left locked by a crashed run (`run --auto-unlock-stale` enables it too)
+pub const AUTO_UNLOCK_STALE_ENV_VAR: &str = "BACKUP_AUTO_UNLOCK_STALE";
+
 /// Overall backup summary
-#[derive(Debug)]
-struct BackupSummary {
-    success_count: usize,
-    skip_count: usize,
+#[derive(Debug, Clone, Default)]
+pub struct BackupSummary {
+    /// Paths selected for this run, including those never started (shutdown, seed budget)
+    pub total_paths: usize,
+    pub success_count: usize,
+    pub skip_count: usize,
+    pub degraded_count: usize,
+    pub failed_count: usize,
+    pub results: Vec<PathBackupResult>,
+    /// Paths with permission problems found before the run
+    pub preflight: BTreeMap<String, PathAccess>,
+    /// CPU, memory, disk reads and uploads of the restic processes
+    pub resources: ResourceUsage,
+    /// A shutdown request stopped the run before every path was backed up
+    pub interrupted: bool,
+    /// Repositories of the host over a REPO_MAX_* budget after the run
+    pub budget_alerts: Vec<BudgetAlert>,
+}
+
+impl BackupSummary {
+    /// No path failed and the run was not cut short
+    pub fn is_success(&self) -> bool {
+        self.failed_count == 0 && !self.interrupted
+    }
+
+    /// One `<status> <path>[: <error>]` line per path that did not complete cleanly, then
+    /// one `budget <path>: <violation>` line per exceeded repository budget
+    pub fn problem_lines(&self) -> Vec<String> {
+        let paths = self
+            .results
+            .iter()
+            .filter(|r| r.status != BackupStatus::Completed)
+            .map(|r| match &r.error {
+                Some(error) => format!("{} {}: {}", r.status.label(), r.path, error),
+                None => format!("{} {}", r.status.label(), r.path),
+            });
+        let budgets = self
+            .budget_alerts
+            .iter()
+            .map(|a| format!("budget {}: {}", a.path.display(), a.describe()));
+        paths.chain(budgets).collect()
+    }
+}
+
+/// How the backup of a single path ended
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackupStatus {
+    Completed,
+    /// Snapshot saved, but its contents look wrong (e.g. an unmounted source)
+    Degraded,
+    Skipped,
+    /// restic failed for this path and the run continued (`BACKUP_ERROR_POLICY=continue`)
+    Failed,
+}
+
+impl BackupStatus {
+    /// Same spelling as the JSON output
+    pub fn label(self) -> &'static str {
+        match self {
+            BackupStatus::Completed => "completed",
+            BackupStatus::Degraded => "degraded",
+            BackupStatus::Skipped => "skipped",
+            BackupStatus::Failed => "failed",
+        }
+    }
+}
+
+/// Why a freshly saved snapshot looks suspicious
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SnapshotDegradation {
+    /// The snapshot contains no files at all
+    Empty,
+    /// The snapshot has far fewer files than the previous one for the same path
+    Shrunk { previous: u64, current: u64 },
+}
+
+/// Structured result for one backed-up path
+#[derive(Debug, Clone, Serialize)]
+pub struct PathBackupResult {
+    pub path: String,
+    pub status: BackupStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub degradation: Option<SnapshotDegradation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<ResticSummary>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timing: Option<PathTiming>,
+    /// BACKUP_EXCLUDES / BACKUP_PATH_EXCLUDES patterns applied (`@file` for exclude files)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub excludes: Vec<String>,
+    /// Snapshots in the repository after the run, read only when metrics are exported
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub snapshot_count: Option<usize>,
+    /// Files that look like credentials (BACKUP_CONTENT_POLICY)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub content_findings: Vec<ContentFinding>,
+    /// Containers paused or stopped during the backup (BACKUP_DOCKER_QUIESCE)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub quiesced_containers: Vec<String>,
+    /// Space freed by `forget --prune` after the run (PRUNE_AFTER_BACKUP)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reclaimed_bytes: Option<u64>,
+}
+
+/// Wall-clock breakdown of one path's backup, in seconds
+///
+/// restic scans and uploads concurrently, so `backup_secs` covers both; restic's own
+/// scan+upload time (without repository open and index loading) is `summary.duration_secs`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct PathTiming {
+    /// Repository lookup and `restic init` check
+    pub setup_secs: f64,
+    /// The `restic backup` process
+    pub backup_secs: f64,
+    /// Reading back and comparing snapshot statistics
+    pub verify_secs: f64,
+    pub total_secs: f64,
+}
+
+/// Default number of paths listed in the "slowest paths" section
+const DEFAULT_SLOWEST_PATHS: usize = 5;
+
+/// Results with timing, slowest first, limited to `n`
+pub fn slowest_paths(results: &[PathBackupResult], n: usize) -> Vec<&PathBackupResult> {
+    let mut timed: Vec<&PathBackupResult> = results.iter().filter(|r| r.timing.is_some()).collect();
+    timed.sort_by(|a, b| {
+        let total = |r: &PathBackupResult| r.timing.as_ref().map_or(0.0, |t| t.total_secs);
+        total(b).total_cmp(&total(a))
+    });
+    timed.truncate(n);
+    timed
+}
+
+/// Slowest-path count from BACKUP_SLOWEST_PATHS (0 hides the section)
+fn slowest_paths_count() -> usize {
+    std::env::var("BACKUP_SLOWEST_PATHS")
+        .ok()
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(DEFAULT_SLOWEST_PATHS)
+}
+
+impl PathBackupResult {
+    fn skipped(path: &Path) -> Self {
+        Self {
+            path: path.display().to_string(),
+            status: BackupStatus::Skipped,
+            degradation: None,
+            summary: None,
+            error: None,
+            timing: None,
+            excludes: Vec::new(),
+            snapshot_count: None,
+            content_findings: Vec::new(),
+            quiesced_containers: Vec::new(),
+            reclaimed_bytes: None,
+        }
+    }
+
+    fn failed(path: &Path, error: &BackupServiceError) -> Self {
+        Self {
+            status: BackupStatus::Failed,
+            error: Some(error.to_string()),
+            ..Self::skipped(path)
+        }
+    }
+}
+
+/// Default minimum file count of a new snapshot relative to the previous one, in percent
+const DEFAULT_MIN_FILE_PERCENT: u64 = 50;
+
+/// Compare a new snapshot's file count against the previous one for the same path
+pub fn assess_snapshot(
+    current: u64,
+    previous: Option<u64>,
+    min_percent: u64,
+) -> Option<SnapshotDegradation> {
+    if current == 0 {
+        return Some(SnapshotDegradation::Empty);
+    }
+    match previous {
+        Some(previous) if current * 100 < previous * min_percent => {
+            Some(SnapshotDegradation::Shrunk { previous, current })
+        }
+        _ => None,
+    }
+}
+
+/// Shrink threshold from BACKUP_MIN_FILE_PERCENT (0 disables the comparison)
+fn min_file_percent() -> u64 {
+    std::env::var("BACKUP_MIN_FILE_PERCENT")
+        .ok()
+        .and_then(|v| v.trim().trim_end_matches('%').parse().ok())
+        .unwrap_or(DEFAULT_MIN_FILE_PERCENT)
+}
+
+/// Optional backup tuning beyond the path list
+#[derive(Debug, Clone, Default)]
+pub struct BackupOptions {
+    /// Only back up these categories (`--only`, default from BACKUP_ONLY_CATEGORIES)
+    pub only_categories: Vec<String>,
+    /// Skip these categories (`--skip`, default from BACKUP_SKIP_CATEGORIES)
+    pub skip_categories: Vec<String>,
+    /// Print per-path results as JSON after the run (`--json`)
+    pub json_output: bool,
+    /// Back up BACKUP_SENSITIVE_PATHS without asking (`--include-sensitive`)
+    pub include_sensitive: bool,
+    /// Never prompt (scheduled runs from `daemon`)
+    pub unattended: bool,
+    /// Initial seeding: smallest paths first, daily upload budget, resumable (`--seed`)
+    pub seed: bool,
+    /// Pause or stop containers using a docker volume while it is backed up
+    /// (`--docker-quiesce`, default from BACKUP_DOCKER_QUIESCE)
+    pub docker_quiesce: Option<String>,
+    /// Paths backed up in parallel (`--jobs`, default from BACKUP_CONCURRENCY, else 1)
+    pub jobs: Option<usize>,
+    /// Remove stale locks of a repository that turns out locked, then retry once
+    /// (`--auto-unlock-stale`, default from BACKUP_AUTO_UNLOCK_STALE)
+    pub auto_unlock_stale: bool,
+    /// Report what each path would add instead of backing up (`--dry-run`); honoured by
+    /// [`BackupWorkflow::execute_backup`], see [`BackupWorkflow::dry_run`]
+    pub dry_run: bool,
+}
+
+/// What `run --dry-run` found for one path
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DryRunResult {
+    pub path: String,
+    pub repo_subpath: Option<String>,
+    /// The repository does not exist yet; the first backup would initialize it
+    pub new_repository: bool,
+    /// `backup --dry-run` summary: files new, changed and unmodified, bytes it would add
+    pub summary: Option<ResticSummary>,
+    /// BACKUP_EXCLUDES / BACKUP_PATH_EXCLUDES patterns applied (`@file` for exclude files)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub excludes: Vec<String>,
+    /// Why the path would not be backed up, or why restic could not measure it
+    pub error: Option<String>,
+}
+
+/// Totals of `run --dry-run` over all paths
+#[derive(Debug, Clone, Default, PartialEq)]
+struct DryRunTotals {
+    new_repositories: usize,
+    files_new: u64,
+    files_changed: u64,
+    bytes_added: u64,
+    /// Paths that would not be backed up
+    failed: usize,
+}
+
+impl DryRunTotals {
+    fn of(results: &[DryRunResult]) -> Self {
+        let measured = || results.iter().filter_map(|r| r.summary.as_ref());
+        Self {
+            new_repositories: results.iter().filter(|r| r.new_repository).count(),
+            files_new: measured().map(|s| s.files_new).sum(),
+            files_changed: measured().map(|s| s.files_changed).sum(),
+            bytes_added: measured().map(|s| s.data_added).sum(),
+            failed: results.iter().filter(|r| r.error.is_some()).count(),
+        }
+    }
+}
+
+/// The snapshot path of another host path among a repository's snapshots, if any
+fn foreign_owner(snapshot_paths: Vec<String>, path: &Path) -> Option<String> {
+    let native = path.to_string_lossy();
+    snapshot_paths
+        .into_iter()
+        .find(|p| p.trim_end_matches('/') != native.trim_end_matches('/'))
+}
+
+/// Refuse a repository that already holds snapshots of a colliding path
+async fn ensure_repository_owner(
+    restic_cmd: &ResticCommandExecutor,
+    path: &Path,
+    hostname: &str,
+    repo_subpath: &str,
+) -> Result<(), BackupServiceError> {
+    match foreign_owner(restic_cmd.snapshot_paths(hostname).await?, path) {
+        Some(owner) => Err(PathMapper::collision_error(
+            path,
+            Path::new(&owner),
+            repo_subpath,
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Category include/exclude filter applied to the prepared path list
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CategoryFilter {
+    only: Vec<String>,
+    skip: Vec<String>,
+}
+
+impl CategoryFilter {
+    /// Build from CLI values, falling back to env defaults when a flag was not given
+    pub fn from_options(options: &BackupOptions) -> Result<Self, BackupServiceError> {
+        let only = if options.only_categories.is_empty() {
+            Self::categories_from_env("BACKUP_ONLY_CATEGORIES")
+        } else {
+            options.only_categories.clone()
+        };
+        let skip = if options.skip_categories.is_empty() {
+            Self::categories_from_env("BACKUP_SKIP_CATEGORIES")
+        } else {
+            options.skip_categories.clone()
+        };
+        Self::new(only, skip)
+    }
+
+    pub fn new(only: Vec<String>, skip: Vec<String>) -> Result<Self, BackupServiceError> {
+        for category in only.iter().chain(skip.iter()) {
+            if ![CATEGORY_USER_HOME, CATEGORY_DOCKER_VOLUME, CATEGORY_SYSTEM]
+                .contains(&category.as_str())
+            {
+                return Err(BackupServiceError::ConfigurationError(format!(
+                    "Unknown backup category: {}.\n\nValid categories are: {}, {}, {}",
+                    category, CATEGORY_USER_HOME, CATEGORY_DOCKER_VOLUME, CATEGORY_SYSTEM
+                )));
+            }
+        }
+        Ok(Self { only, skip })
+    }
+
+    fn categories_from_env(key: &str) -> Vec<String> {
+        std::env::var(key)
+            .unwrap_or_default()
+            .split(',')
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect()
+    }
+
+    pub fn is_active(&self) -> bool {
+        !self.only.is_empty() || !self.skip.is_empty()
+    }
+
+    pub fn allows(&self, category: &str) -> bool {
+        (self.only.is_empty() || self.only.iter().any(|c| c == category))
+            && !self.skip.iter().any(|c| c == category)
+    }
 }
 
 /// Manages the complete backup workflow
+#[derive(Clone)]
 pub struct BackupWorkflow {
     config: Config,
     additional_paths: Vec<String>,
+    category_filter: CategoryFilter,
+    json_output: bool,
+    error_policy: ErrorPolicy,
+    include_sensitive: bool,
+    unattended: bool,
+    seed: bool,
+    docker_quiesce: QuiesceMode,
+    jobs: usize,
+    auto_unlock: Option<StaleLockPolicy>,
+    dry_run: bool,
+    post_prune: Option<PostBackupPrune>,
+    sensitive: Vec<PathBuf>,
+    path_overlap: OverlapPolicy,
+    excludes: ExcludeRules,
+    compression: CompressionRules,
+    tags: PathTags,
+    content_policy: ContentPolicy,
+    content_scanner: ContentScanner,
+    notifiers: Notifiers,
+    email: Option<EmailReporter>,
 }
 
 impl BackupWorkflow {
-    pub fn new(config: Config, additional_paths: Vec<String>) -> Result<Self, BackupServiceError> {
+    pub fn new(
+        config: Config,
+        additional_paths: Vec<String>,
+        options: BackupOptions,
+    ) -> Result<Self, BackupServiceError> {
+        let category_filter = CategoryFilter::from_options(&options)?;
+        let error_policy = ErrorPolicy::from_env(BACKUP_ERROR_POLICY_ENV_VAR)?;
+        let post_prune = PostBackupPrune::from_env(&config)?;
         Ok(Self {
             config,
             additional_paths,
+            category_filter,
+            json_output: options.json_output,
+            error_policy,
+            include_sensitive: options.include_sensitive,
+            unattended: options.unattended,
+            seed: options.seed,
+            docker_quiesce: QuiesceMode::resolve(options.docker_quiesce.as_deref())?,
+            jobs: resolve_jobs(options.jobs, BACKUP_CONCURRENCY_ENV_VAR, 1)?,
+            auto_unlock: if options.auto_unlock_stale
+                || std::env::var(AUTO_UNLOCK_STALE_ENV_VAR)
+                    .is_ok_and(|v| matches!(v.trim(), "1" | "true" | "yes"))
+            {
+                Some(StaleLockPolicy::resolve(None)?)
+            } else {
+                None
+            },
+            dry_run: options.dry_run,
+            post_prune,
+            sensitive: sensitive_paths(),
+            path_overlap: OverlapPolicy::from_env()?,
+            excludes: ExcludeRules::from_env()?,
+            compression: CompressionRules::from_env()?,
+            tags: PathTags::from_env()?,
+            content_policy: ContentPolicy::from_env()?,
+            content_scanner: ContentScanner::from_env()?,
+            notifiers: Notifiers::from_env()?,
+            email: EmailReporter::from_env()?,
         })
     }
 
-    /// Execute the complete backup workflow
+    /// Execute the complete backup workflow; failed paths or a shutdown make it an error
     pub async fn execute_backup(&self) -> Result<(), BackupServiceError> {
+        if self.dry_run {
+            return self.execute_dry_run().await;
+        }
+        let summary = self.run().await?;
+        if summary.interrupted {
+            return Err(BackupServiceError::CommandFailed(format!(
+                "Backup interrupted by shutdown after {} of {} paths",
+                summary.results.len(),
+                summary.total_paths
+            )));
+        }
+        if summary.failed_count > 0 && summary.success_count > 0 {
+            return Err(BackupServiceError::PartialBackup(format!(
+                "Backup failed for {} of {} paths",
+                summary.failed_count, summary.total_paths
+            )));
+        }
+        if summary.failed_count > 0 {
+            return Err(BackupServiceError::CommandFailed(format!(
+                "Backup failed for {} of {} paths",
+                summary.failed_count, summary.total_paths
+            )));
+        }
+        Ok(())
+    }
+
+    /// Run the backup and return the per-path results, then export its metrics when configured
+    ///
+    /// Path failures under `BACKUP_ERROR_POLICY=continue` are reported in the summary rather
+    /// than as an error; check [`BackupSummary::is_success`].
+    pub async fn run(&self) -> Result<BackupSummary, BackupServiceError> {
+        let started = Instant::now();
+        let healthcheck = Healthcheck::from_env();
+        healthcheck.ping_start(&self.config.hostname).await;
+        let mut run_metrics = RunMetrics::new(&self.config.hostname);
+        let outcome = self.run_backup(&mut run_metrics).await;
+        // Even a failed run may have added snapshots
+        scan_cache::invalidate(&self.config, &self.config.hostname);
+        if let Ok(summary) = &outcome {
+            history_workflow::record_run(&self.config.hostname, summary);
+        }
+        if mirror_workflow::mirror_after_backup()
+            && outcome.as_ref().is_ok_and(BackupSummary::is_success)
+        {
+            mirror_workflow::mirror_after_run(&self.config).await;
+        }
+        healthcheck
+            .ping_finished(
+                &self.config.hostname,
+                &outcome,
+                started.elapsed().as_secs_f64(),
+            )
+            .await;
+        if metrics::is_enabled() {
+            run_metrics.finish(
+                outcome.as_ref().is_ok_and(BackupSummary::is_success),
+                started.elapsed().as_secs_f64(),
+                metrics::previous_success(),
+            );
+            metrics::publish(run_metrics).await;
+        }
+        outcome
+    }
+
+    async fn run_backup(
+        &self,
+        run_metrics: &mut RunMetrics,
+    ) -> Result<BackupSummary, BackupServiceError> {
         let hostname = &self.config.hostname.clone();
         info!(hostname = %hostname, "Starting backup process");
+        let usage_start = ChildUsage::now();
 
         self.config.set_aws_env()?;
         validate_credentials(&self.config).await?;
 
+        let limits = ResourceLimits::from_env(ResourceLimits::profile_for("backup"))?;
+        if !limits.is_empty() {
+            info!(limits = %limits.systemd_properties().join(" "), "Running restic backups in a constrained systemd scope");
+        }
+        // Invalid tuning overrides fail here rather than on the first path
+        let tuning = BackendTuning::from_env(&self.config.restic_repo_base)?;
+        if let Some(profile) = tuning.profile {
+            info!(profile = %profile.name(), flags = %tuning.restic_args().join(" "), "Backend tuning");
+        }
+
+        // Network shares are verified (and mounted via their unit) before the paths are
+        // filtered, and units started here are stopped when the session is dropped
+        let mounts = MountSession::prepare_from_env().await?;
+
         // Phase 1: Prepare backup paths
         let all_paths = self.prepare_backup_paths().await?;
 
@@ -42,21 +570,294 @@ impl BackupWorkflow {
             warn!(
                 "No paths configured for backup. Use BACKUP_PATHS in .env or specify paths via command line."
             );
-            return Ok(());
+            return Ok(BackupSummary::default());
+        }
+
+        // Seeding reorders the paths and stops at the daily budget; the checkpoint resumes
+        let mut seed = if self.seed {
+            Some(SeedRun::start(&all_paths).await?)
+        } else {
+            None
+        };
+        let all_paths = match &seed {
+            Some(seed) => seed.order(all_paths),
+            None => all_paths,
+        };
+
+        // Phase 2: Check read access up front instead of via per-file restic warnings,
+        // and refuse paths that would share a repository with another path
+        let preflight = self.preflight(&all_paths)?;
+        let mut refused = self.check_repo_collisions(&all_paths)?;
+        // An unmounted share would otherwise give a tiny, bogus snapshot of the empty mountpoint
+        let unmounted = mounts.refusals(&all_paths);
+        if !unmounted.is_empty() && self.error_policy.is_fail_fast() {
+            return Err(BackupServiceError::CommandFailed(format!(
+                "{} backup paths are on network mounts that are not mounted",
+                unmounted.len()
+            )));
         }
+        refused.extend(unmounted);
+        for (path, access) in &preflight {
+            if let Some(root_error) = &access.root_error {
+                refused
+                    .entry(path.clone())
+                    .or_insert_with(|| format!("preflight: {}", root_error));
+            }
+        }
+
+        // Phase 3: Execute backups with progress tracking
+        let mut backup_summary = self
+            .execute_backup_operations(&all_paths, hostname, &refused, seed.as_mut())
+            .await?;
+        backup_summary.total_paths = all_paths.len();
+        backup_summary.preflight = preflight;
+        backup_summary.interrupted = shutdown::is_requested();
+        let uploaded = backup_summary
+            .results
+            .iter()
+            .filter_map(|r| r.summary.as_ref())
+            .map(|s| s.data_added_packed.unwrap_or(s.data_added))
+            .sum();
+        backup_summary.resources =
+            ResourceUsage::between(&usage_start, &ChildUsage::now(), uploaded);
+        record_metrics(run_metrics, &backup_summary);
 
-        // Phase 2: Execute backups with progress tracking
-        let backup_summary = self.execute_backup_operations(&all_paths, hostname).await?;
+        // Phase 3b: Apply the retention policy to the repositories that just got a snapshot
+        if let Some(post_prune) = &self.post_prune
+            && !backup_summary.interrupted
+        {
+            self.prune_backed_up(post_prune, &mut backup_summary.results)
+                .await;
+        }
 
-        // Phase 3: Report results
+        // Phase 3c: Check the host's repositories against REPO_MAX_* after retention ran
+        if !backup_summary.interrupted {
+            match check_host_budgets(&self.config, hostname).await {
+                Ok(alerts) => backup_summary.budget_alerts = alerts,
+                Err(e) => warn!(error = %e, "Could not check the repository budgets"),
+            }
+            run_metrics.budget_alerts = backup_summary.budget_alerts.clone();
+        }
+
+        // Phase 4: Report results
         self.report_backup_results(&backup_summary).await?;
+        if let Some(seed) = &seed
+            && seed.is_complete(&all_paths)
+        {
+            info!("Seeding complete: every path has an initial snapshot");
+        }
 
-        Ok(())
+        Ok(backup_summary)
+    }
+
+    /// `run --dry-run`: prepare, validate and preflight the paths like a backup, then let
+    /// `restic backup --dry-run` count what each path would add
+    ///
+    /// Nothing is written to the repositories: one that does not exist yet is not
+    /// initialized, its path is measured against an empty scratch repository instead.
+    /// The credential scan (BACKUP_CONTENT_POLICY) and docker quiescing only run for real.
+    pub async fn dry_run(&self) -> Result<Vec<DryRunResult>, BackupServiceError> {
+        info!(hostname = %self.config.hostname, "Starting backup dry run");
+        self.config.set_aws_env()?;
+        validate_credentials(&self.config).await?;
+        let mounts = MountSession::prepare_from_env().await?;
+
+        let all_paths = self.prepare_backup_paths().await?;
+        let preflight = self.preflight(&all_paths)?;
+        let mut refused = self.check_repo_collisions(&all_paths)?;
+        refused.extend(mounts.refusals(&all_paths));
+        for (path, access) in &preflight {
+            if let Some(root_error) = &access.root_error {
+                refused
+                    .entry(path.clone())
+                    .or_insert_with(|| format!("preflight: {}", root_error));
+            }
+        }
+
+        let scratch = std::env::temp_dir().join(format!("rbs-dry-run-{}", std::process::id()));
+        let mut results = Vec::with_capacity(all_paths.len());
+        for (idx, path) in all_paths.iter().enumerate() {
+            if shutdown::is_requested() {
+                warn!("Shutdown requested, stopping the dry run");
+                break;
+            }
+            let result = self.dry_run_outcome(path, &refused, &scratch).await;
+            let progress = format!("({}/{})", idx + 1, all_paths.len());
+            match (&result.summary, &result.error) {
+                (Some(summary), _) => info!(
+                    progress = %progress,
+                    path = %result.path,
+                    new_repository = %result.new_repository,
+                    files_new = %summary.files_new,
+                    files_changed = %summary.files_changed,
+                    files_unmodified = %summary.files_unmodified,
+                    would_add = %format_bytes(summary.data_added)?,
+                    "Would back up path"
+                ),
+                (None, Some(error)) => {
+                    error!(progress = %progress, path = %result.path, error = %error, "Path would not be backed up")
+                }
+                (None, None) => {
+                    warn!(progress = %progress, path = %result.path, "restic reported no summary")
+                }
+            }
+            results.push(result);
+        }
+        let _ = std::fs::remove_dir_all(&scratch);
+        Ok(results)
+    }
+
+    /// One path's dry run result; a refusal or failure becomes its `error`
+    async fn dry_run_outcome(
+        &self,
+        path: &Path,
+        refused: &BTreeMap<String, String>,
+        scratch: &Path,
+    ) -> DryRunResult {
+        let name = path.display().to_string();
+        let outcome = match refused.get(&name) {
+            Some(reason) => Err(BackupServiceError::CommandFailed(reason.clone())),
+            None => self.dry_run_path(path, scratch).await,
+        };
+        outcome.unwrap_or_else(|e| DryRunResult {
+            path: name,
+            error: Some(e.to_string()),
+            ..DryRunResult::default()
+        })
+    }
+
+    /// Measure one path with `restic backup --dry-run`
+    async fn dry_run_path(
+        &self,
+        path: &Path,
+        scratch: &Path,
+    ) -> Result<DryRunResult, BackupServiceError> {
+        if !path.exists() {
+            return Err(BackupServiceError::CommandFailed(
+                "path does not exist".to_string(),
+            ));
+        }
+        let hostname = &self.config.hostname;
+        let repo_subpath = resolve_repo_subpath(&self.config, hostname, path).await?;
+        let restic_cmd = ResticCommandExecutor::new(
+            self.config.clone(),
+            self.config.get_repo_url(&repo_subpath)?,
+        )?;
+        let new_repository = !restic_cmd.repo_exists().await?;
+        let restic_cmd = if new_repository {
+            let scratch_cmd = ResticCommandExecutor::new(
+                self.config.clone(),
+                scratch.join(&repo_subpath).display().to_string(),
+            )?;
+            scratch_cmd
+                .init_if_needed(&self.compression.init_args())
+                .await?;
+            scratch_cmd
+        } else {
+            ensure_repository_owner(&restic_cmd, path, hostname, &repo_subpath).await?;
+            restic_cmd
+        };
+
+        let (mut args, excludes) = self.path_backup_args(path);
+        args.push("--dry-run".to_string());
+        let summary = restic_cmd.backup(path, hostname, &args).await?;
+        Ok(DryRunResult {
+            path: path.display().to_string(),
+            repo_subpath: Some(repo_subpath),
+            new_repository,
+            summary,
+            excludes,
+            error: None,
+        })
+    }
+
+    /// Print the dry run per path (or as JSON); paths that would fail make it an error
+    async fn execute_dry_run(&self) -> Result<(), BackupServiceError> {
+        let results = self.dry_run().await?;
+        let totals = DryRunTotals::of(&results);
+
+        if self.json_output {
+            let output = json!({
+                "host": self.config.hostname,
+                "dry_run": true,
+                "paths": results,
+                "new_repositories": totals.new_repositories,
+                "files_new": totals.files_new,
+                "files_changed": totals.files_changed,
+                "bytes_added": totals.bytes_added,
+                "failed": totals.failed,
+            });
+            DisplayFormatter::print_json(&output)?;
+        } else {
+            info!(
+                paths = %results.len(),
+                new_repositories = %totals.new_repositories,
+                files_new = %totals.files_new,
+                files_changed = %totals.files_changed,
+                would_add = %format_bytes(totals.bytes_added)?,
+                failed = %totals.failed,
+                "Dry run: no snapshots were written"
+            );
+        }
+
+        if totals.failed == 0 {
+            Ok(())
+        } else {
+            Err(BackupServiceError::CommandFailed(format!(
+                "{} of {} paths would not be backed up",
+                totals.failed,
+                results.len()
+            )))
+        }
+    }
+
+    /// `forget --prune` each completed path's repository, one at a time
+    ///
+    /// Repositories are pruned one after another so the exclusive prune locks never queue up
+    /// behind each other. Degraded snapshots are left alone: forgetting by keep-* rules after a
+    /// suspicious snapshot could push the last good one out. Failures only warn.
+    async fn prune_backed_up(
+        &self,
+        post_prune: &PostBackupPrune,
+        results: &mut [PathBackupResult],
+    ) {
+        info!(retention = %post_prune.describe(), "Pruning the backed-up repositories");
+        for result in results
+            .iter_mut()
+            .filter(|r| r.status == BackupStatus::Completed)
+        {
+            if shutdown::is_requested() {
+                warn!("Shutdown requested, skipping the remaining post-backup prunes");
+                break;
+            }
+            let path = PathBuf::from(&result.path);
+            let outcome = async {
+                let repo_url = self.config.get_repo_url(
+                    &resolve_repo_subpath(&self.config, &self.config.hostname, &path).await?,
+                )?;
+                let restic_cmd = ResticCommandExecutor::new(self.config.clone(), repo_url)?;
+                let category = BackupRepo::new(path.clone())?.category()?.to_string();
+                post_prune.prune(&restic_cmd, &category).await
+            }
+            .await;
+            match outcome {
+                Ok(reclaimed) => {
+                    result.reclaimed_bytes = reclaimed;
+                    info!(
+                        path = %result.path,
+                        reclaimed = %format_bytes(reclaimed.unwrap_or(0)).unwrap_or_default(),
+                        "Repository pruned"
+                    );
+                }
+                Err(e) => warn!(path = %result.path, error = %e, "Post-backup prune failed"),
+            }
+        }
     }
 
     /// Phase 1: Prepare all paths to backup
     async fn prepare_backup_paths(&self) -> Result<Vec<PathBuf>, BackupServiceError> {
-        let mut all_paths: Vec<PathBuf> = self.config.backup_paths.clone();
+        // BACKUP_PATHS globs, `~` and home discovery are resolved on every run
+        let mut all_paths = PathUtilities::configured_paths(&self.config);
 
         // Add additional paths from command line
         for path in &self.additional_paths {
@@ -64,152 +865,1032 @@ impl BackupWorkflow {
         }
 
         // Discover and add docker volumes
-        let docker_volumes = PathUtilities::discover_docker_volumes()?;
-        all_paths.extend(docker_volumes);
+        if self.category_filter.allows(CATEGORY_DOCKER_VOLUME) {
+            let docker_volumes = PathUtilities::discover_docker_volumes()?;
+            all_paths.extend(docker_volumes);
+        }
+
+        // Sensitive paths only run when opted in; otherwise they are dropped
+        let all_paths = self.apply_sensitive_selection(all_paths).await?;
+
+        // Apply --only / --skip category selection
+        let all_paths = self.filter_by_category(all_paths)?;
 
         // Validate and filter paths
-        let valid_paths = PathUtilities::validate_and_filter_paths(all_paths)?;
+        let valid_paths = PathUtilities::validate_and_filter_paths(
+            all_paths,
+            self.path_overlap,
+            &self.sensitive,
+        )?;
 
         Ok(valid_paths)
     }
 
-    /// Phase 2: Execute backup operations with progress tracking
+    /// Add the sensitive paths when this run opts in (flag or prompt), otherwise drop them
+    async fn apply_sensitive_selection(
+        &self,
+        mut paths: Vec<PathBuf>,
+    ) -> Result<Vec<PathBuf>, BackupServiceError> {
+        let present: Vec<&PathBuf> = self.sensitive.iter().filter(|p| p.exists()).collect();
+        if present.is_empty() {
+            return Ok(paths);
+        }
+
+        let include = if self.include_sensitive {
+            true
+        } else if std::io::stdin().is_terminal() && !self.json_output && !self.unattended {
+            let list: Vec<String> = present.iter().map(|p| p.display().to_string()).collect();
+            confirm_action(
+                &t_args("prompt-include-sensitive", &[("paths", list.join(", "))]),
+                false,
+            )
+            .await?
+        } else {
+            false
+        };
+
+        if include {
+            for path in present {
+                if !paths.contains(path) {
+                    paths.push(path.clone());
+                }
+            }
+            info!(sensitive = %self.sensitive.len(), "Including sensitive paths in this run");
+            return Ok(paths);
+        }
+
+        paths.retain(|path| {
+            let sensitive = is_sensitive(path, &self.sensitive);
+            if sensitive {
+                info!(path = %path.display(), "Sensitive path, skipping (use --include-sensitive)");
+            }
+            !sensitive
+        });
+        Ok(paths)
+    }
+
+    /// Drop paths whose category is excluded for this run
+    fn filter_by_category(&self, paths: Vec<PathBuf>) -> Result<Vec<PathBuf>, BackupServiceError> {
+        if !self.category_filter.is_active() {
+            return Ok(paths);
+        }
+
+        let mut kept = Vec::new();
+        for path in paths {
+            let category = BackupRepo::new(path.clone())?.category()?;
+            if self.category_filter.allows(category) {
+                kept.push(path);
+            } else {
+                info!(path = %path.display(), category = %category, "Category not selected for this run, skipping");
+            }
+        }
+        Ok(kept)
+    }
+
+    /// Phase 2: Sample-read every path and report permission problems in one section
+    ///
+    /// Paths whose root cannot be read are failed without running restic; unreadable
+    /// entries below a readable root only warn, as restic still backs up the rest.
+    fn preflight(
+        &self,
+        all_paths: &[PathBuf],
+    ) -> Result<BTreeMap<String, PathAccess>, BackupServiceError> {
+        let sample = sample_size();
+        if sample == 0 {
+            return Ok(BTreeMap::new());
+        }
+
+        let problems: BTreeMap<String, PathAccess> = all_paths
+            .iter()
+            .map(|path| (path.display().to_string(), check_path_access(path, sample)))
+            .filter(|(_, access)| !access.is_ok())
+            .collect();
+        if problems.is_empty() {
+            return Ok(problems);
+        }
+
+        warn!("{}", t("backup-preflight-header"));
+        for (path, access) in &problems {
+            if let Some(root_error) = &access.root_error {
+                error!(path = %path, error = %root_error, "Path is not readable, it will not be backed up");
+                continue;
+            }
+            warn!(
+                path = %path,
+                unreadable = %access.unreadable.len(),
+                sampled = %access.sampled,
+                "Some entries are not readable and will be missing from the snapshot"
+            );
+            for issue in access.unreadable.iter().take(5) {
+                warn!(entry = %issue.path, error = %issue.error, "Unreadable entry");
+            }
+        }
+        warn!("{}", t("backup-preflight-hint"));
+
+        let blocked = problems.values().filter(|a| a.root_unreadable()).count();
+        if blocked > 0 && self.error_policy.is_fail_fast() {
+            return Err(BackupServiceError::CommandFailed(format!(
+                "Preflight found {} unreadable backup paths",
+                blocked
+            )));
+        }
+        Ok(problems)
+    }
+
+    /// Paths whose repository name collides with an earlier path's, with the reason
+    ///
+    /// With REPO_PATH_ENCODING=1, flattening `/` to `_` maps e.g. `/home/u/a_b` and
+    /// `/home/u/a/b` to the same repository; the first path keeps it and the others are
+    /// refused.
+    fn check_repo_collisions(
+        &self,
+        all_paths: &[PathBuf],
+    ) -> Result<BTreeMap<String, String>, BackupServiceError> {
+        let mut refused = BTreeMap::new();
+        for (owner, path, subpath) in PathMapper::find_collisions(all_paths)? {
+            let reason = PathMapper::collision_error(&path, &owner, &subpath);
+            error!(path = %path.display(), owner = %owner.display(), repository = %subpath, "{}", reason);
+            if self.error_policy.is_fail_fast() {
+                return Err(reason);
+            }
+            refused.insert(path.display().to_string(), reason.to_string());
+        }
+        Ok(refused)
+    }
+
+    /// Phase 3: Execute backup operations with progress tracking
+    ///
+    /// Up to `jobs` paths run at once; each one is summarized as soon as it finishes and
+    /// the results keep the order of `all_paths`.
     async fn execute_backup_operations(
         &self,
         all_paths: &[PathBuf],
         hostname: &str,
+        refused: &BTreeMap<String, String>,
+        mut seed: Option<&mut SeedRun>,
     ) -> Result<BackupSummary, BackupServiceError> {
+        let total = all_paths.len();
+        let jobs = self.concurrency(total, seed.is_some());
+        let workflow = Arc::new(self.clone());
         let mut success_count = 0;
         let mut skip_count = 0;
+        let mut degraded_count = 0;
+        let mut failed_count = 0;
+        let mut results = Vec::new();
+        let mut tasks = JoinSet::new();
+        let mut next = 0;
+        let mut stopped = false;
 
-        for (idx, path) in all_paths.iter().enumerate() {
-            info!(
-                progress = format!("({}/{})", idx + 1, all_paths.len()),
-                path = %path.display(),
-                "Starting backup"
-            );
-
-            let success = self.execute_single_backup(path, hostname).await?;
-
-            if success {
-                success_count += 1;
-                info!(
-                    progress = format!("({}/{})", idx + 1, all_paths.len()),
-                    path = %path.display(),
-                    "Backup completed successfully"
-                );
-            } else {
-                skip_count += 1;
+        loop {
+            while !stopped && next < total && tasks.len() < jobs {
+                let path = &all_paths[next];
+                // A shutdown request lets the running paths finish, then stops the run
+                if shutdown::is_requested() {
+                    warn!(
+                        remaining = %(total - next),
+                        "Shutdown requested, skipping remaining paths"
+                    );
+                    skip_count += total - next;
+                    stopped = true;
+                    break;
+                }
+                if let Some(seed) = seed.as_deref()
+                    && !seed.may_start(path)
+                {
+                    info!(
+                        remaining = %(total - next),
+                        uploaded_today = %format_bytes(seed.uploaded_today())?,
+                        "Daily seed budget reached, remaining paths resume on the next run"
+                    );
+                    skip_count += total - next;
+                    stopped = true;
+                    break;
+                }
+                clear_status_line();
                 info!(
-                    progress = format!("({}/{})", idx + 1, all_paths.len()),
+                    progress = format!("({}/{})", next + 1, total),
                     path = %path.display(),
-                    "Backup skipped"
+                    "Starting backup"
                 );
+
+                let idx = next;
+                let path = path.clone();
+                let refusal = refused.get(&path.display().to_string()).cloned();
+                let workflow = Arc::clone(&workflow);
+                let hostname = hostname.to_string();
+                tasks.spawn(async move {
+                    let result = match refusal {
+                        Some(reason) => Ok(PathBackupResult::failed(
+                            &path,
+                            &BackupServiceError::CommandFailed(reason),
+                        )),
+                        None => workflow.execute_unlocking(&path, &hostname).await,
+                    };
+                    (idx, path, result)
+                });
+                next += 1;
+            }
+
+            let Some(joined) = tasks.join_next().await else {
+                break;
+            };
+            let (idx, path, result) = joined.map_err(|e| {
+                BackupServiceError::CommandFailed(format!("Task join error: {}", e))
+            })?;
+            // Fail-fast aborts the run here; continue records the failure and moves on
+            let result = match result {
+                Ok(result) => result,
+                Err(e) if self.error_policy.is_fail_fast() => {
+                    tasks.abort_all();
+                    return Err(e);
+                }
+                Err(e) => PathBackupResult::failed(&path, &e),
+            };
+            if let Some(seed) = seed.as_deref_mut() {
+                seed.finish_path(&path, &result);
+            }
+
+            // Counted in completion order, so the counter stays monotonic with parallel jobs
+            let progress = format!("({}/{})", results.len() + 1, total);
+            clear_status_line();
+            match result.status {
+                BackupStatus::Completed | BackupStatus::Degraded => {
+                    success_count += 1;
+                    if result.status == BackupStatus::Degraded {
+                        degraded_count += 1;
+                    }
+                    let summary = result.summary.clone().unwrap_or_default();
+                    info!(
+                        progress = %progress,
+                        path = %path.display(),
+                        snapshot_id = %summary.snapshot_id.as_deref().unwrap_or("unknown"),
+                        files_new = %summary.files_new,
+                        files_changed = %summary.files_changed,
+                        files_unmodified = %summary.files_unmodified,
+                        data_added = %format_bytes(summary.data_added)?,
+                        duration_secs = %summary.duration_secs.map(|d| format!("{:.1}", d)).unwrap_or_default(),
+                        "Backup completed successfully"
+                    );
+                }
+                BackupStatus::Skipped => {
+                    skip_count += 1;
+                    info!(
+                        progress = %progress,
+                        path = %path.display(),
+                        "Backup skipped"
+                    );
+                }
+                BackupStatus::Failed => {
+                    failed_count += 1;
+                    error!(
+                        progress = %progress,
+                        path = %path.display(),
+                        error = %result.error.as_deref().unwrap_or_default(),
+                        "Backup failed, continuing with remaining paths"
+                    );
+                }
             }
+            results.push((idx, result));
         }
+        results.sort_by_key(|(idx, _)| *idx);
 
         Ok(BackupSummary {
             success_count,
             skip_count,
+            degraded_count,
+            failed_count,
+            results: results.into_iter().map(|(_, result)| result).collect(),
+            ..BackupSummary::default()
         })
     }
 
+    /// Paths backed up at once, capped at the path count
+    ///
+    /// Seeding budgets uploads path by path and a credential prompt needs the terminal,
+    /// so both fall back to one path at a time.
+    fn concurrency(&self, total: usize, seeding: bool) -> usize {
+        let prompts = self.content_policy == ContentPolicy::Confirm
+            && std::io::stdin().is_terminal()
+            && !self.json_output
+            && !self.unattended;
+        if self.jobs > 1 && (seeding || prompts) {
+            info!(
+                jobs = %self.jobs,
+                "Backing up one path at a time (seeding or interactive credential prompts)"
+            );
+            return 1;
+        }
+        self.jobs.min(total.max(1))
+    }
+
+    /// Execute backup for a single path, unlocking stale locks once if enabled
+    async fn execute_unlocking(
+        &self,
+        path: &Path,
+        hostname: &str,
+    ) -> Result<PathBackupResult, BackupServiceError> {
+        let result = self.execute_single_backup(path, hostname).await;
+        let (Err(BackupServiceError::RepositoryLocked(_)), Some(policy)) =
+            (&result, &self.auto_unlock)
+        else {
+            return result;
+        };
+
+        let repo_url = self.config.get_repo_url(
+            &resolve_repo_subpath(&self.config, &self.config.hostname, path).await?,
+        )?;
+        let restic_cmd = ResticCommandExecutor::new(self.config.clone(), repo_url)?;
+        let (locks, unlocked) = unlock_stale(&restic_cmd, policy, false).await?;
+        if !unlocked {
+            for reason in locks.iter().filter_map(|l| l.kept_because.as_ref()) {
+                warn!(path = %path.display(), reason = %reason, "Repository lock is not stale, leaving it in place");
+            }
+            return result;
+        }
+        warn!(
+            path = %path.display(),
+            locks = %locks.len(),
+            "Removed stale repository locks, retrying the backup"
+        );
+        self.execute_single_backup(path, hostname).await
+    }
+
     /// Execute backup for a single path
     async fn execute_single_backup(
         &self,
         path: &Path,
         hostname: &str,
-    ) -> Result<bool, BackupServiceError> {
+    ) -> Result<PathBackupResult, BackupServiceError> {
         // Validate path exists (redundant check for safety)
         if !path.exists() {
             warn!(path = %path.display(), "Path does not exist, skipping");
-            return Ok(false);
+            return Ok(PathBackupResult::skipped(path));
         }
 
-        let repo_subpath = PathMapper::path_to_repo_subpath(path)?;
+        let started = Instant::now();
+        let repo_subpath = resolve_repo_subpath(&self.config, &self.config.hostname, path).await?;
         let repo_url = self.config.get_repo_url(&repo_subpath)?;
         let restic_cmd = ResticCommandExecutor::new(self.config.clone(), repo_url)?;
 
         // Initialize repository if needed
-        restic_cmd.init_if_needed().await?;
+        restic_cmd
+            .init_if_needed(&self.compression.init_args())
+            .await?;
 
-        // Run backup with live output
-        let output = restic_cmd.backup(path, hostname, true).await?;
+        // A repository written by a colliding path in an earlier run must not be mixed into
+        ensure_repository_owner(&restic_cmd, path, hostname, &repo_subpath).await?;
+        let setup_secs = started.elapsed().as_secs_f64();
 
-        // For live output mode, empty string means success (no exception thrown)
-        if output.is_empty() {
-            // Live output mode - backup succeeded if no error was thrown
-            info!(path = %path.display(), "Backup completed");
-            Ok(true)
+        let (mut extra_args, excludes) = self.path_backup_args(path);
+        let (content_findings, content_excludes) =
+            self.check_contents(&restic_cmd, path, hostname).await?;
+        extra_args.extend(content_excludes);
+
+        // Containers writing to a docker volume are quiesced for the restic run only
+        let quiesced = if BackupRepo::new(path.to_path_buf())?.category()? == CATEGORY_DOCKER_VOLUME
+        {
+            QuiescedContainers::quiesce(&DockerCli, path, self.docker_quiesce).await?
         } else {
-            // Parse backup output for non-live mode
-            if output.contains("snapshot") && output.contains("saved") {
-                let snapshot_id = self.extract_snapshot_id(&output);
-                let has_warnings = output.contains("at least one source file could not be read");
+            QuiescedContainers::default()
+        };
 
-                if has_warnings {
-                    warn!(
-                        path = %path.display(),
-                        snapshot_id = %snapshot_id.as_deref().unwrap_or("unknown"),
-                        "Backed up with some files skipped due to I/O errors"
-                    );
-                } else {
-                    info!(
-                        path = %path.display(),
-                        snapshot_id = %snapshot_id.as_deref().unwrap_or("unknown"),
-                        "Backup completed"
-                    );
+        // Run backup, streaming restic's JSON progress; a missing summary is read back
+        // from the saved snapshot
+        let summary = restic_cmd.backup(path, hostname, &extra_args).await;
+        let quiesced_containers = quiesced.containers().to_vec();
+        let not_resumed = quiesced.resume(&DockerCli).await;
+        let summary = summary?;
+        // A service left down is worse than a missed backup; fail the path so the run alerts
+        if !not_resumed.is_empty() {
+            return Err(BackupServiceError::CommandFailed(format!(
+                "Snapshot saved, but containers were not resumed: {}",
+                not_resumed.join(", ")
+            )));
+        }
+        let backup_secs = started.elapsed().as_secs_f64() - setup_secs;
+        let mut result = self
+            .inspect_snapshot(&restic_cmd, path, hostname, summary)
+            .await;
+
+        if metrics::is_enabled() && result.status != BackupStatus::Skipped {
+            match restic_cmd.snapshots().await {
+                Ok(snapshots) => result.snapshot_count = Some(snapshots.len()),
+                Err(e) => {
+                    warn!(path = %path.display(), error = %e, "Could not count snapshots for metrics")
                 }
-                Ok(true)
-            } else {
-                warn!(path = %path.display(), "Failed to backup");
-                Ok(false)
             }
         }
+
+        let total_secs = started.elapsed().as_secs_f64();
+        result.excludes = excludes;
+        result.content_findings = content_findings;
+        result.quiesced_containers = quiesced_containers;
+        result.timing = Some(PathTiming {
+            setup_secs,
+            backup_secs,
+            verify_secs: total_secs - setup_secs - backup_secs,
+            total_secs,
+        });
+        Ok(result)
     }
 
-    /// Phase 3: Report backup results
+    /// restic `backup` arguments of a path, plus its exclude patterns
+    fn path_backup_args(&self, path: &Path) -> (Vec<String>, Vec<String>) {
+        // Tag sensitive snapshots; keep sensitive data out of the repositories of parent paths
+        let mut extra_args: Vec<String> = Vec::new();
+        if is_sensitive(path, &self.sensitive) {
+            extra_args.extend(["--tag".to_string(), SENSITIVE_TAG.to_string()]);
+        }
+        for nested in nested_sensitive(path, &self.sensitive) {
+            extra_args.extend(["--exclude".to_string(), nested.display().to_string()]);
+        }
+        let excludes = self.excludes.for_path(path).to_vec();
+        extra_args.extend(exclude_args(&excludes));
+        extra_args.extend(self.compression.backup_args(path));
+        extra_args.extend(self.tags.backup_args(path));
+        (extra_args, excludes)
+    }
+
+    /// Scan new and changed files for credentials and apply BACKUP_CONTENT_POLICY
+    ///
+    /// Returns the findings and the `--exclude` arguments that keep them out of the snapshot.
+    async fn check_contents(
+        &self,
+        restic_cmd: &ResticCommandExecutor,
+        path: &Path,
+        hostname: &str,
+    ) -> Result<(Vec<ContentFinding>, Vec<String>), BackupServiceError> {
+        if self.content_policy == ContentPolicy::Off {
+            return Ok((Vec::new(), Vec::new()));
+        }
+
+        // Files unchanged since the previous snapshot were checked by an earlier run
+        let since = restic_cmd
+            .recent_snapshots(hostname, path, 1)
+            .await
+            .ok()
+            .and_then(|snapshots| {
+                let time = snapshots.first()?["time"].as_str()?.to_string();
+                chrono::DateTime::parse_from_rfc3339(&time).ok()
+            })
+            .map(std::time::SystemTime::from);
+        let findings = self.content_scanner.scan(path, since);
+        if findings.is_empty() {
+            return Ok((findings, Vec::new()));
+        }
+
+        warn!(path = %path.display(), "{}", t("backup-content-header"));
+        for finding in &findings {
+            warn!(file = %finding.path, reason = %finding.reason, "Possible credential");
+        }
+        let exclude = match self.content_policy {
+            ContentPolicy::Off | ContentPolicy::Warn => false,
+            ContentPolicy::Exclude => true,
+            ContentPolicy::Confirm
+                if std::io::stdin().is_terminal() && !self.json_output && !self.unattended =>
+            {
+                !confirm_action(
+                    &t_args(
+                        "prompt-include-credentials",
+                        &[
+                            ("count", findings.len().to_string()),
+                            ("path", path.display().to_string()),
+                        ],
+                    ),
+                    false,
+                )
+                .await?
+            }
+            ContentPolicy::Confirm => {
+                warn!(path = %path.display(), "Nobody can confirm in an unattended run, excluding the possible credentials");
+                true
+            }
+        };
+        if !exclude {
+            return Ok((findings, Vec::new()));
+        }
+
+        info!(path = %path.display(), excluded = %findings.len(), "Possible credentials excluded from the snapshot");
+        let args = findings
+            .iter()
+            .flat_map(|f| ["--exclude".to_string(), exclude_pattern(&f.path)])
+            .collect();
+        Ok((findings, args))
+    }
+
+    /// Read the new snapshot's summary and compare it with the previous one
+    ///
+    /// An unreadable history never fails the backup; the result simply lacks the extra detail.
+    async fn inspect_snapshot(
+        &self,
+        restic_cmd: &ResticCommandExecutor,
+        path: &Path,
+        hostname: &str,
+        parsed_summary: Option<ResticSummary>,
+    ) -> PathBackupResult {
+        let mut result = PathBackupResult {
+            path: path.display().to_string(),
+            status: BackupStatus::Completed,
+            degradation: None,
+            summary: parsed_summary,
+            error: None,
+            timing: None,
+            excludes: Vec::new(),
+            snapshot_count: None,
+            content_findings: Vec::new(),
+            quiesced_containers: Vec::new(),
+            reclaimed_bytes: None,
+        };
+
+        let snapshots = match restic_cmd.recent_snapshots(hostname, path, 2).await {
+            Ok(snapshots) => snapshots,
+            Err(e) => {
+                warn!(path = %path.display(), error = %e, "Could not read snapshot stats, skipping empty-snapshot check");
+                return result;
+            }
+        };
+        let Some(latest) = snapshots.first() else {
+            return result;
+        };
+        if result.summary.is_none() {
+            result.summary = ResticSummary::from_json(latest);
+        }
+
+        let mut counts = Vec::new();
+        for snapshot in &snapshots {
+            match restic_cmd.snapshot_file_count(snapshot).await {
+                Ok(count) => counts.push(count),
+                Err(e) => {
+                    warn!(path = %path.display(), error = %e, "Could not read snapshot stats, skipping empty-snapshot check");
+                    return result;
+                }
+            }
+        }
+
+        result.degradation = assess_snapshot(counts[0], counts.get(1).copied(), min_file_percent());
+        match &result.degradation {
+            Some(SnapshotDegradation::Empty) => {
+                warn!(
+                    path = %path.display(),
+                    "Snapshot contains no files - is the source mounted?"
+                );
+            }
+            Some(SnapshotDegradation::Shrunk { previous, current }) => {
+                warn!(
+                    path = %path.display(),
+                    previous_files = %previous,
+                    current_files = %current,
+                    "Snapshot has far fewer files than the previous one - is the source complete?"
+                );
+            }
+            None => {}
+        }
+        if result.degradation.is_some() {
+            result.status = BackupStatus::Degraded;
+        }
+        result
+    }
+
+    /// Phase 4: Report backup results
     async fn report_backup_results(
         &self,
         summary: &BackupSummary,
     ) -> Result<(), BackupServiceError> {
-        if summary.success_count == 0 && summary.skip_count > 0 {
-            error!(
-                success_count = %summary.success_count,
-                skip_count = %summary.skip_count,
-                "BACKUP FAILED: No data was backed up! Please check the errors above"
-            );
-        } else if summary.skip_count > 0 {
-            warn!(
-                success_count = %summary.success_count,
-                skip_count = %summary.skip_count,
-                "Backup partially completed"
-            );
-        } else {
-            info!(
-                success_count = %summary.success_count,
-                "Backup completed successfully"
-            );
+        let (severity, headline) =
+            if summary.success_count == 0 && summary.skip_count + summary.failed_count > 0 {
+                error!(
+                    success_count = %summary.success_count,
+                    skip_count = %summary.skip_count,
+                    failed_count = %summary.failed_count,
+                    "{}",
+                    t("backup-failed")
+                );
+                (Severity::Failure, t("backup-failed"))
+            } else if summary.degraded_count > 0 {
+                error!(
+                    success_count = %summary.success_count,
+                    degraded_count = %summary.degraded_count,
+                    skip_count = %summary.skip_count,
+                    "{}",
+                    t("backup-degraded")
+                );
+                (Severity::Failure, t("backup-degraded"))
+            } else if summary.skip_count + summary.failed_count > 0 {
+                warn!(
+                    success_count = %summary.success_count,
+                    skip_count = %summary.skip_count,
+                    failed_count = %summary.failed_count,
+                    "{}",
+                    t("backup-partial")
+                );
+                (Severity::Warning, t("backup-partial"))
+            } else if !summary.budget_alerts.is_empty() {
+                warn!(
+                    success_count = %summary.success_count,
+                    budget_alerts = %summary.budget_alerts.len(),
+                    "{}",
+                    t("backup-budget-exceeded")
+                );
+                (Severity::Warning, t("backup-budget-exceeded"))
+            } else {
+                info!(
+                    success_count = %summary.success_count,
+                    "{}",
+                    t("backup-success")
+                );
+                (Severity::Success, t("backup-success"))
+            };
+        self.notify(summary, severity, &headline).await;
+        if let Some(email) = &self.email {
+            email
+                .report(&self.config.hostname, summary, severity, &headline)
+                .await;
+        }
+
+        let slowest = slowest_paths(&summary.results, slowest_paths_count());
+        if !slowest.is_empty() {
+            info!("{}", t("backup-slowest-header"));
+            for result in &slowest {
+                let timing = result.timing.clone().unwrap_or_default();
+                info!(
+                    path = %result.path,
+                    total_secs = %format!("{:.1}", timing.total_secs),
+                    setup_secs = %format!("{:.1}", timing.setup_secs),
+                    backup_secs = %format!("{:.1}", timing.backup_secs),
+                    verify_secs = %format!("{:.1}", timing.verify_secs),
+                    "Path timing"
+                );
+            }
+        }
+
+        let excluded: Vec<&PathBackupResult> = summary
+            .results
+            .iter()
+            .filter(|r| !r.excludes.is_empty())
+            .collect();
+        if !excluded.is_empty() {
+            info!("{}", t("backup-excludes-header"));
+            for result in excluded {
+                info!(path = %result.path, excludes = %result.excludes.join(", "), "Excludes applied");
+            }
+        }
+
+        let resources = &summary.resources;
+        info!("{}", t("backup-resources-header"));
+        info!(
+            cpu_secs = %format!("{:.1}", resources.cpu_secs()),
+            cpu_user_secs = %format!("{:.1}", resources.cpu_user_secs),
+            cpu_system_secs = %format!("{:.1}", resources.cpu_system_secs),
+            peak_memory = %format_bytes(resources.peak_memory_bytes)?,
+            disk_read = %format_bytes(resources.disk_read_bytes)?,
+            uploaded = %format_bytes(resources.uploaded_bytes)?,
+            "Restic resource usage"
+        );
+
+        let reclaimed = self.post_prune.as_ref().map(|_| {
+            summary
+                .results
+                .iter()
+                .filter_map(|r| r.reclaimed_bytes)
+                .sum::<u64>()
+        });
+        if let Some(reclaimed) = reclaimed {
+            info!(reclaimed = %format_bytes(reclaimed)?, "Space reclaimed by post-backup prune");
+        }
+
+        if self.json_output {
+            let output = json!({
+                "hostname": self.config.hostname,
+                "success_count": summary.success_count,
+                "skip_count": summary.skip_count,
+                "degraded_count": summary.degraded_count,
+                "failed_count": summary.failed_count,
+                "results": summary.results,
+                "preflight": summary.preflight,
+                "slowest": slowest.iter().map(|r| &r.path).collect::<Vec<_>>(),
+                "resources": summary.resources,
+                "reclaimed_bytes": reclaimed,
+                "budget_alerts": summary.budget_alerts,
+            });
+            DisplayFormatter::print_json(&output)?;
         }
 
         Ok(())
     }
 
-    /// Extract snapshot ID from backup output
-    fn extract_snapshot_id(&self, output: &str) -> Option<String> {
-        output
-            .lines()
-            .find(|line| line.contains("snapshot") && line.contains("saved"))
-            .and_then(|line| line.split_whitespace().nth(1))
-            .map(|s| s.to_string())
+    /// Send the run's outcome to the configured notification channels
+    async fn notify(&self, summary: &BackupSummary, severity: Severity, headline: &str) {
+        if !self.notifiers.wants(severity) {
+            return;
+        }
+        let hostname = &self.config.hostname;
+        let mut message = format!(
+            "{} paths, {} completed, {} degraded, {} skipped, {} failed\n",
+            summary.total_paths,
+            summary.success_count - summary.degraded_count,
+            summary.degraded_count,
+            summary.skip_count,
+            summary.failed_count
+        );
+        for line in summary.problem_lines() {
+            message.push_str(&line);
+            message.push('\n');
+        }
+        self.notifiers
+            .dispatch(&Notification {
+                kind: "backup",
+                host: hostname.clone(),
+                severity,
+                title: format!("{}: {}", hostname, headline),
+                message,
+                details: json!({
+                    "success_count": summary.success_count,
+                    "skip_count": summary.skip_count,
+                    "degraded_count": summary.degraded_count,
+                    "failed_count": summary.failed_count,
+                    "interrupted": summary.interrupted,
+                    "results": summary.results,
+                    "budget_alerts": summary.budget_alerts,
+                }),
+            })
+            .await;
     }
 }
 
+/// Copy a finished run's counts and per-path results into its metrics
+fn record_metrics(run_metrics: &mut RunMetrics, summary: &BackupSummary) {
+    run_metrics.completed = summary.success_count - summary.degraded_count;
+    run_metrics.degraded = summary.degraded_count;
+    run_metrics.skipped = summary.skip_count;
+    run_metrics.failed = summary.failed_count;
+    run_metrics.uploaded_bytes = summary.resources.uploaded_bytes;
+    run_metrics.paths = summary
+        .results
+        .iter()
+        .map(|result| PathMetrics {
+            path: result.path.clone(),
+            status: result.status.label(),
+            data_added_bytes: result.summary.as_ref().map_or(0, |s| s.data_added),
+            duration_secs: result.timing.as_ref().map_or(0.0, |t| t.total_secs),
+            snapshot_count: result.snapshot_count,
+        })
+        .collect();
+    run_metrics.data_added_bytes = run_metrics.paths.iter().map(|p| p.data_added_bytes).sum();
+}
+
 /// Simplified public interface that maintains API compatibility
 pub async fn execute_backup_workflow(
     config: Config,
     additional_paths: Vec<String>,
+    options: BackupOptions,
 ) -> Result<(), BackupServiceError> {
-    let workflow = BackupWorkflow::new(config, additional_paths)?;
+    let workflow = BackupWorkflow::new(config, additional_paths, options)?;
     workflow.execute_backup().await
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::budgets::BudgetKind;
+
+    fn categories(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_category_filter_only() -> Result<(), BackupServiceError> {
+        let filter = CategoryFilter::new(categories(&["user_home", "docker_volume"]), vec![])?;
+        assert!(filter.is_active());
+        assert!(filter.allows("user_home"));
+        assert!(filter.allows("docker_volume"));
+        assert!(!filter.allows("system"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_category_filter_skip_wins_over_only() -> Result<(), BackupServiceError> {
+        let filter = CategoryFilter::new(
+            categories(&["user_home", "system"]),
+            categories(&["system"]),
+        )?;
+        assert!(filter.allows("user_home"));
+        assert!(!filter.allows("system"));
+        assert!(!filter.allows("docker_volume"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_category_filter_default_allows_everything() -> Result<(), BackupServiceError> {
+        let filter = CategoryFilter::new(vec![], vec![])?;
+        assert!(!filter.is_active());
+        assert!(filter.allows("user_home"));
+        assert!(filter.allows("docker_volume"));
+        assert!(filter.allows("system"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_foreign_owner() {
+        let path = Path::new("/home/tim/my_docs");
+        assert_eq!(foreign_owner(vec![], path), None);
+        assert_eq!(
+            foreign_owner(vec!["/home/tim/my_docs/".to_string()], path),
+            None
+        );
+        assert_eq!(
+            foreign_owner(
+                vec![
+                    "/home/tim/my_docs".to_string(),
+                    "/home/tim/my/docs".to_string()
+                ],
+                path
+            )
+            .as_deref(),
+            Some("/home/tim/my/docs")
+        );
+    }
+
+    #[test]
+    fn test_dry_run_totals() {
+        let measured =
+            |path: &str, new_repository, files_new, files_changed, data_added| DryRunResult {
+                path: path.to_string(),
+                new_repository,
+                summary: Some(ResticSummary {
+                    files_new,
+                    files_changed,
+                    data_added,
+                    ..ResticSummary::default()
+                }),
+                ..DryRunResult::default()
+            };
+        let results = vec![
+            measured("/etc", false, 2, 5, 4096),
+            measured("/home/tim", true, 100, 0, 1_000_000),
+            DryRunResult {
+                path: "/srv/missing".to_string(),
+                error: Some("path does not exist".to_string()),
+                ..DryRunResult::default()
+            },
+            // Measured without a summary: neither counted nor failed
+            DryRunResult {
+                path: "/var/lib/app".to_string(),
+                ..DryRunResult::default()
+            },
+        ];
+        assert_eq!(
+            DryRunTotals::of(&results),
+            DryRunTotals {
+                new_repositories: 1,
+                files_new: 102,
+                files_changed: 5,
+                bytes_added: 1_004_096,
+                failed: 1,
+            }
+        );
+        assert_eq!(DryRunTotals::of(&[]), DryRunTotals::default());
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_refused_and_missing_paths_report_errors() -> Result<(), BackupServiceError>
+    {
+        let dir = tempfile::tempdir()?;
+        let config = Config {
+            restic_password: "test".to_string(),
+            restic_repo_base: dir.path().join("repos").display().to_string(),
+            aws_access_key_id: String::new(),
+            aws_secret_access_key: String::new(),
+            aws_default_region: String::new(),
+            aws_s3_endpoint: String::new(),
+            backup_paths: vec![],
+            hostname: "test-host".to_string(),
+        };
+        let workflow = BackupWorkflow::new(config, vec![], BackupOptions::default())?;
+        let scratch = dir.path().join("scratch");
+
+        let refused_path = dir.path().join("my_docs");
+        std::fs::create_dir(&refused_path)?;
+        let refused = BTreeMap::from([(
+            refused_path.display().to_string(),
+            "maps to the same repository as /home/tim/my/docs".to_string(),
+        )]);
+        let result = workflow
+            .dry_run_outcome(&refused_path, &refused, &scratch)
+            .await;
+        assert_eq!(result.path, refused_path.display().to_string());
+        assert_eq!(
+            result.error.as_deref(),
+            Some("Command execution failed: maps to the same repository as /home/tim/my/docs")
+        );
+        assert!(result.summary.is_none());
+
+        let missing = dir.path().join("missing");
+        let result = workflow
+            .dry_run_outcome(&missing, &BTreeMap::new(), &scratch)
+            .await;
+        assert_eq!(result.path, missing.display().to_string());
+        assert!(
+            result
+                .error
+                .as_ref()
+                .is_some_and(|e| e.contains("path does not exist"))
+        );
+        assert!(!result.new_repository && result.repo_subpath.is_none());
+        assert_eq!(
+            DryRunTotals::of(&[result]),
+            DryRunTotals {
+                failed: 1,
+                ..DryRunTotals::default()
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_assess_empty_snapshot() {
+        assert_eq!(
+            assess_snapshot(0, None, 50),
+            Some(SnapshotDegradation::Empty)
+        );
+        assert_eq!(
+            assess_snapshot(0, Some(0), 50),
+            Some(SnapshotDegradation::Empty)
+        );
+    }
+
+    #[test]
+    fn test_problem_lines_include_budget_alerts() {
+        let summary = BackupSummary {
+            results: vec![PathBackupResult {
+                status: BackupStatus::Failed,
+                error: Some("repository is locked".to_string()),
+                ..PathBackupResult::skipped(Path::new("/srv"))
+            }],
+            budget_alerts: vec![BudgetAlert {
+                path: "/home/tim".into(),
+                repo_subpath: "user_home/tim".to_string(),
+                kind: BudgetKind::SnapshotCount,
+                actual: 120,
+                limit: 100,
+            }],
+            ..BackupSummary::default()
+        };
+        assert_eq!(
+            summary.problem_lines(),
+            [
+                "failed /srv: repository is locked",
+                "budget /home/tim: 120 snapshots exceed the budget of 100"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_slowest_paths_order_and_limit() {
+        let timed = |path: &str, total_secs: Option<f64>| PathBackupResult {
+            timing: total_secs.map(|total_secs| PathTiming {
+                total_secs,
+                ..Default::default()
+            }),
+            ..PathBackupResult::skipped(Path::new(path))
+        };
+        let results = vec![
+            timed("/etc", Some(3.0)),
+            timed("/home/tim", Some(4200.5)),
+            timed("/missing", None),
+            timed("/var/lib/docker", Some(60.0)),
+        ];
+
+        let slowest: Vec<&str> = slowest_paths(&results, 2)
+            .iter()
+            .map(|r| r.path.as_str())
+            .collect();
+        assert_eq!(slowest, vec!["/home/tim", "/var/lib/docker"]);
+        assert_eq!(slowest_paths(&results, 10).len(), 3);
+        assert!(slowest_paths(&results, 0).is_empty());
+    }
+
+    #[test]
+    fn test_assess_shrunk_snapshot() {
+        assert_eq!(
+            assess_snapshot(120, Some(10_000), 50),
+            Some(SnapshotDegradation::Shrunk {
+                previous: 10_000,
+                current: 120
+            })
+        );
+        // Exactly at the threshold is still healthy
+        assert_eq!(assess_snapshot(5_000, Some(10_000), 50), None);
+        assert_eq!(assess_snapshot(10_500, Some(10_000), 50), None);
+        // First snapshot of a path has nothing to compare against
+        assert_eq!(assess_snapshot(3, None, 50), None);
+        // A threshold of 0 disables the comparison
+        assert_eq!(assess_snapshot(1, Some(10_000), 0), None);
+    }
+
+    #[test]
+    fn test_category_filter_rejects_unknown_category() {
+        let result = CategoryFilter::new(categories(&["user-home"]), vec![]);
+        assert!(result.is_err());
+        assert!(result.unwrap_err().to_string().contains("user-home"));
+    }
+}
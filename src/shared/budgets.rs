@@ -0,0 +1,277 @@
+use crate::config::Config;
+use crate::errors::BackupServiceError;
+use crate::i18n::t_args;
+use crate::shared::commands::ResticCommandExecutor;
+use crate::shared::operations::{RepositoryData, RepositoryOperations};
+use crate::utils::{format_bytes, parse_size};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tracing::warn;
+
+/// Per-repository thresholds that indicate broken retention or runaway data
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BudgetThresholds {
+    pub max_snapshots: Option<usize>,
+    pub max_size_bytes: Option<u64>,
+    pub max_files: Option<u64>,
+}
+
+/// The kind of budget a repository exceeded
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BudgetKind {
+    SnapshotCount,
+    Size,
+    FileCount,
+}
+
+impl BudgetKind {
+    /// Serialized name, also the `kind` label of the budget metric
+    pub fn label(&self) -> &'static str {
+        match self {
+            BudgetKind::SnapshotCount => "snapshot_count",
+            BudgetKind::Size => "size",
+            BudgetKind::FileCount => "file_count",
+        }
+    }
+}
+
+/// A single budget violation for one repository
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BudgetAlert {
+    pub path: PathBuf,
+    pub repo_subpath: String,
+    pub kind: BudgetKind,
+    pub actual: u64,
+    pub limit: u64,
+}
+
+impl BudgetAlert {
+    pub fn describe(&self) -> String {
+        let (key, actual, limit) = match self.kind {
+            BudgetKind::SnapshotCount => (
+                "budget-snapshot-count",
+                self.actual.to_string(),
+                self.limit.to_string(),
+            ),
+            BudgetKind::Size => (
+                "budget-size",
+                format_bytes(self.actual).unwrap_or_default(),
+                format_bytes(self.limit).unwrap_or_default(),
+            ),
+            BudgetKind::FileCount => (
+                "budget-file-count",
+                self.actual.to_string(),
+                self.limit.to_string(),
+            ),
+        };
+        t_args(key, &[("actual", actual), ("limit", limit)])
+    }
+}
+
+impl BudgetThresholds {
+    /// Load thresholds from REPO_MAX_SNAPSHOTS, REPO_MAX_SIZE and REPO_MAX_FILES
+    pub fn from_env() -> Result<Self, BackupServiceError> {
+        let max_snapshots = Self::parse_count("REPO_MAX_SNAPSHOTS")?.map(|n| n as usize);
+        let max_files = Self::parse_count("REPO_MAX_FILES")?;
+        let max_size_bytes = match std::env::var("REPO_MAX_SIZE") {
+            Ok(v) if !v.trim().is_empty() => Some(parse_size(&v)?),
+            _ => None,
+        };
+
+        Ok(Self {
+            max_snapshots,
+            max_size_bytes,
+            max_files,
+        })
+    }
+
+    fn parse_count(key: &str) -> Result<Option<u64>, BackupServiceError> {
+        match std::env::var(key) {
+            Ok(v) if !v.trim().is_empty() => v.trim().parse().map(Some).map_err(|_| {
+                BackupServiceError::ConfigurationError(format!(
+                    "Invalid {}: '{}' is not a whole number",
+                    key, v
+                ))
+            }),
+            _ => Ok(None),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.max_snapshots.is_none() && self.max_size_bytes.is_none() && self.max_files.is_none()
+    }
+
+    /// Size and file budgets need `restic stats`; snapshot counts are already known
+    fn needs_stats(&self) -> bool {
+        self.max_size_bytes.is_some() || self.max_files.is_some()
+    }
+
+    /// Check the values known for one repository against the thresholds
+    pub fn evaluate(
+        &self,
+        repo: &RepositoryData,
+        size_bytes: Option<u64>,
+        file_count: Option<u64>,
+    ) -> Vec<BudgetAlert> {
+        let mut alerts = Vec::new();
+        let mut push = |kind: BudgetKind, actual: u64, limit: u64| {
+            alerts.push(BudgetAlert {
+                path: repo.info.native_path.clone(),
+                repo_subpath: repo.info.repo_subpath.clone(),
+                kind,
+                actual,
+                limit,
+            })
+        };
+
+        if let Some(limit) = self.max_snapshots
+            && repo.snapshot_count > limit
+        {
+            push(
+                BudgetKind::SnapshotCount,
+                repo.snapshot_count as u64,
+                limit as u64,
+            );
+        }
+        if let (Some(limit), Some(actual)) = (self.max_size_bytes, size_bytes)
+            && actual > limit
+        {
+            push(BudgetKind::Size, actual, limit);
+        }
+        if let (Some(limit), Some(actual)) = (self.max_files, file_count)
+            && actual > limit
+        {
+            push(BudgetKind::FileCount, actual, limit);
+        }
+
+        alerts
+    }
+}
+
+/// Check every repository of a host against the configured budgets, logging each violation
+pub async fn check_repository_budgets(
+    config: &Config,
+    hostname: &str,
+    repo_data: &[RepositoryData],
+) -> Result<Vec<BudgetAlert>, BackupServiceError> {
+    let thresholds = BudgetThresholds::from_env()?;
+    if thresholds.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut alerts = Vec::new();
+    for repo in repo_data {
+        let (size_bytes, file_count) = if thresholds.needs_stats() {
+            let repo_url = config.get_repo_url_for_host(hostname, &repo.info.repo_subpath)?;
+            let restic_cmd = ResticCommandExecutor::new(config.clone(), repo_url)?;
+            match restic_cmd.latest_stats("restore-size").await {
+                Ok(stats) => (
+                    stats["total_size"].as_u64(),
+                    stats["total_file_count"].as_u64(),
+                ),
+                Err(e) => {
+                    warn!(path = %repo.info.native_path.display(), error = %e, "Could not read stats for budget check");
+                    (None, None)
+                }
+            }
+        } else {
+            (None, None)
+        };
+
+        for alert in thresholds.evaluate(repo, size_bytes, file_count) {
+            warn!(
+                path = %alert.path.display(),
+                "Repository budget exceeded: {}",
+                alert.describe()
+            );
+            alerts.push(alert);
+        }
+    }
+
+    Ok(alerts)
+}
+
+/// After a backup run: rescan the host and check its repositories against the budgets;
+/// without configured budgets nothing is scanned
+pub async fn check_host_budgets(
+    config: &Config,
+    hostname: &str,
+) -> Result<Vec<BudgetAlert>, BackupServiceError> {
+    if BudgetThresholds::from_env()?.is_empty() {
+        return Ok(Vec::new());
+    }
+    let scan = RepositoryOperations::new(config.clone())?
+        .scan_repositories_cached(hostname, true)
+        .await?;
+    check_repository_budgets(config, hostname, &scan.repos).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::operations::RepositoryInfo;
+
+    fn repo_with_count(count: usize) -> RepositoryData {
+        RepositoryData {
+            info: RepositoryInfo {
+                native_path: PathBuf::from("/var/log/app"),
+                repo_subpath: "system/var_log_app".to_string(),
+                category: "system".to_string(),
+            },
+            snapshots: vec![],
+            snapshot_count: count,
+        }
+    }
+
+    #[test]
+    fn test_evaluate_snapshot_count() {
+        let thresholds = BudgetThresholds {
+            max_snapshots: Some(100),
+            ..Default::default()
+        };
+
+        assert!(
+            thresholds
+                .evaluate(&repo_with_count(100), None, None)
+                .is_empty()
+        );
+
+        let alerts = thresholds.evaluate(&repo_with_count(101), None, None);
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].kind, BudgetKind::SnapshotCount);
+        assert_eq!(alerts[0].actual, 101);
+        assert_eq!(
+            serde_json::to_value(&alerts[0].kind).unwrap(),
+            alerts[0].kind.label()
+        );
+    }
+
+    #[test]
+    fn test_evaluate_size_and_files() {
+        let thresholds = BudgetThresholds {
+            max_snapshots: None,
+            max_size_bytes: Some(1024),
+            max_files: Some(400_000),
+        };
+
+        let alerts = thresholds.evaluate(&repo_with_count(5), Some(2048), Some(400_001));
+        assert_eq!(alerts.len(), 2);
+        assert_eq!(alerts[0].kind, BudgetKind::Size);
+        assert_eq!(alerts[1].kind, BudgetKind::FileCount);
+        assert!(alerts[1].describe().contains("400001 files"));
+
+        // Unknown stats never trigger alerts
+        assert!(
+            thresholds
+                .evaluate(&repo_with_count(5), None, None)
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_thresholds_empty() {
+        assert!(BudgetThresholds::default().is_empty());
+        assert!(!BudgetThresholds::default().needs_stats());
+    }
+}
@@ -0,0 +1,392 @@
+use crate::config::Config;
+use crate::errors::BackupServiceError;
+use crate::shared::commands::ResticCommandExecutor;
+use crate::shared::display::DisplayFormatter;
+use crate::shared::operations::{DiscoveryFailure, RepositoryOperations};
+use crate::utils::{parse_size, validate_credentials};
+use serde::Serialize;
+use serde_json::{Value, json};
+use tracing::{error, info, warn};
+
+/// `check` options
+#[derive(Debug, Clone, Default)]
+pub struct CheckOptions {
+    /// Passed to restic as --read-data-subset (`1/5`, `10%`, `2G`)
+    pub read_data_subset: Option<String>,
+    /// Every host below the repository base instead of one
+    pub all_hosts: bool,
+    pub json_output: bool,
+}
+
+/// Outcome of `restic check` for one repository
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CheckResult {
+    pub repo_subpath: String,
+    pub passed: bool,
+    pub error: Option<String>,
+}
+
+/// Validate a --read-data-subset value the way restic interprets it
+pub fn validate_subset(value: &str) -> Result<String, BackupServiceError> {
+    let value = value.trim();
+    let valid = if let Some((n, total)) = value.split_once('/') {
+        match (n.parse::<u32>(), total.parse::<u32>()) {
+            (Ok(n), Ok(total)) => n >= 1 && n <= total,
+            _ => false,
+        }
+    } else if let Some(percent) = value.strip_suffix('%') {
+        percent.parse::<f64>().is_ok_and(|p| p > 0.0 && p <= 100.0)
+    } else {
+        parse_size(value).is_ok_and(|size| size > 0)
+    };
+
+    if valid {
+        Ok(value.to_string())
+    } else {
+        Err(BackupServiceError::ConfigurationError(format!(
+            "Invalid --read-data-subset: {}.\n\nUse n/t (e.g. 1/5), a percentage (10%) or a size (2G)",
+            value
+        )))
+    }
+}
+
+/// Extra `restic check` arguments for the options
+fn check_args(options: &CheckOptions) -> Result<Vec<String>, BackupServiceError> {
+    Ok(match &options.read_data_subset {
+        Some(subset) => vec!["--read-data-subset".to_string(), validate_subset(subset)?],
+        None => Vec::new(),
+    })
+}
+
+/// `restic check` results of one host
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HostCheck {
+    pub host: String,
+    pub passed: usize,
+    pub failed: usize,
+    pub repositories: Vec<CheckResult>,
+    pub discovery_failures: Vec<DiscoveryFailure>,
+    /// Why the host could not be checked at all (`--all-hosts` goes on with the next one)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl HostCheck {
+    /// A host whose repositories could not even be listed
+    fn unchecked(hostname: &str, error: &BackupServiceError) -> Self {
+        Self {
+            host: hostname.to_string(),
+            passed: 0,
+            failed: 0,
+            repositories: Vec::new(),
+            discovery_failures: Vec::new(),
+            error: Some(error.to_string()),
+        }
+    }
+}
+
+/// Totals over the checked hosts
+#[derive(Debug, Clone, Default, PartialEq)]
+struct CheckTotals {
+    checked: usize,
+    failed: usize,
+    discovery_failures: usize,
+    /// Hosts that could not be checked
+    failed_hosts: Vec<String>,
+}
+
+impl CheckTotals {
+    fn of(checks: &[HostCheck]) -> Self {
+        Self {
+            checked: checks.iter().map(|c| c.repositories.len()).sum(),
+            failed: checks.iter().map(|c| c.failed).sum(),
+            discovery_failures: checks.iter().map(|c| c.discovery_failures.len()).sum(),
+            failed_hosts: checks
+                .iter()
+                .filter(|c| c.error.is_some())
+                .map(|c| c.host.clone())
+                .collect(),
+        }
+    }
+}
+
+/// Check every repository of one host, one after another
+async fn check_host(
+    config: &Config,
+    hostname: &str,
+    args: &[String],
+) -> Result<HostCheck, BackupServiceError> {
+    let operations = RepositoryOperations::new(config.clone())?;
+    let discovery = operations.discover_all_repositories(hostname).await?;
+    for failure in &discovery.failures {
+        error!(failure = %failure, "Repository discovery incomplete");
+    }
+    let repos = discovery.repos;
+    if repos.is_empty() && discovery.failures.is_empty() {
+        warn!(hostname = %hostname, "No repositories found for host");
+    }
+
+    let mut results = Vec::with_capacity(repos.len());
+    for (idx, repo) in repos.iter().enumerate() {
+        info!(
+            progress = format!("({}/{})", idx + 1, repos.len()),
+            repo_subpath = %repo.repo_subpath,
+            "Checking repository"
+        );
+        let repo_url = config.get_repo_url_for_host(hostname, &repo.repo_subpath)?;
+        let restic_cmd = ResticCommandExecutor::new(config.clone(), repo_url)?;
+        let result = match restic_cmd.check(args).await {
+            Ok(_) => CheckResult {
+                repo_subpath: repo.repo_subpath.clone(),
+                passed: true,
+                error: None,
+            },
+            Err(e) => {
+                error!(repo_subpath = %repo.repo_subpath, error = %e, "Check failed");
+                CheckResult {
+                    repo_subpath: repo.repo_subpath.clone(),
+                    passed: false,
+                    error: Some(e.to_string()),
+                }
+            }
+        };
+        results.push(result);
+    }
+
+    let failed = results.iter().filter(|r| !r.passed).count();
+    Ok(HostCheck {
+        host: hostname.to_string(),
+        passed: results.len() - failed,
+        failed,
+        repositories: results,
+        discovery_failures: discovery.failures,
+        error: None,
+    })
+}
+
+fn log_host_summary(check: &HostCheck) {
+    info!(hostname = %check.host, "Check summary:");
+    if let Some(e) = &check.error {
+        info!("  FAILED  host not checked: {}", e);
+    }
+    for result in &check.repositories {
+        match &result.error {
+            None => info!("  ok      {}", result.repo_subpath),
+            Some(e) => info!("  FAILED  {}: {}", result.repo_subpath, e),
+        }
+    }
+}
+
+/// Verify every repository of a host (or, with `all_hosts`, of every host) with `restic check`
+pub async fn execute_check_workflow(
+    config: Config,
+    host: Option<String>,
+    options: CheckOptions,
+) -> Result<(), BackupServiceError> {
+    let args = check_args(&options)?;
+
+    config.set_aws_env()?;
+    validate_credentials(&config).await?;
+
+    let hosts = if options.all_hosts {
+        RepositoryOperations::new(config.clone())?
+            .get_available_hosts()
+            .await?
+    } else {
+        vec![host.unwrap_or_else(|| config.hostname.clone())]
+    };
+
+    let mut checks = Vec::with_capacity(hosts.len());
+    for hostname in &hosts {
+        info!(
+            hostname = %hostname,
+            read_data_subset = %options.read_data_subset.as_deref().unwrap_or("none"),
+            "Starting repository check"
+        );
+        let check = match check_host(&config, hostname, &args).await {
+            Ok(check) => check,
+            // One unreachable host must not keep the others unchecked
+            Err(e) if options.all_hosts => {
+                error!(hostname = %hostname, error = %e, "Check failed for host");
+                HostCheck::unchecked(hostname, &e)
+            }
+            Err(e) => return Err(e),
+        };
+        if !options.json_output {
+            log_host_summary(&check);
+        }
+        checks.push(check);
+    }
+
+    let totals = CheckTotals::of(&checks);
+    if options.json_output {
+        DisplayFormatter::print_json(&check_report(&options, &checks, &totals))?;
+    } else if options.all_hosts {
+        info!(
+            hosts = %checks.len(),
+            passed = %(totals.checked - totals.failed),
+            failed = %totals.failed,
+            failed_hosts = %totals.failed_hosts.len(),
+            "Check of all hosts finished"
+        );
+    }
+
+    if !totals.failed_hosts.is_empty() {
+        return Err(BackupServiceError::CommandFailed(format!(
+            "Check failed on {} of {} hosts: {}{}",
+            totals.failed_hosts.len(),
+            checks.len(),
+            totals.failed_hosts.join(", "),
+            match totals.failed {
+                0 => String::new(),
+                failed => format!("; {} of {} repositories failed", failed, totals.checked),
+            }
+        )));
+    }
+    if totals.failed > 0 {
+        return Err(BackupServiceError::CommandFailed(format!(
+            "Check failed for {} of {} repositories",
+            totals.failed, totals.checked
+        )));
+    }
+    if totals.discovery_failures > 0 {
+        return Err(BackupServiceError::CommandFailed(format!(
+            "Checked {} repositories, but {} part(s) of the repository tree could not be listed",
+            totals.checked, totals.discovery_failures
+        )));
+    }
+
+    info!(repo_count = %totals.checked, "All repositories passed the check");
+    Ok(())
+}
+
+/// The `--json` document: one host's results, or every host's with totals for `--all-hosts`
+fn check_report(options: &CheckOptions, checks: &[HostCheck], totals: &CheckTotals) -> Value {
+    match (options.all_hosts, checks.first()) {
+        (false, Some(check)) => json!({
+            "host": check.host,
+            "read_data_subset": options.read_data_subset,
+            "passed": check.passed,
+            "failed": check.failed,
+            "repositories": check.repositories,
+            "discovery_failures": check.discovery_failures,
+        }),
+        _ => json!({
+            "read_data_subset": options.read_data_subset,
+            "hosts": checks,
+            "totals": {
+                "hosts": checks.len(),
+                "passed": totals.checked - totals.failed,
+                "failed": totals.failed,
+                "failed_hosts": totals.failed_hosts,
+            },
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_subset() -> Result<(), BackupServiceError> {
+        assert_eq!(validate_subset("1/5")?, "1/5");
+        assert_eq!(validate_subset(" 10% ")?, "10%");
+        assert_eq!(validate_subset("2G")?, "2G");
+        for invalid in ["0/5", "6/5", "0%", "150%", "half", ""] {
+            assert!(validate_subset(invalid).is_err(), "{}", invalid);
+        }
+        Ok(())
+    }
+
+    fn repo(repo_subpath: &str, error: Option<&str>) -> CheckResult {
+        CheckResult {
+            repo_subpath: repo_subpath.to_string(),
+            passed: error.is_none(),
+            error: error.map(str::to_string),
+        }
+    }
+
+    fn host(host: &str, repositories: Vec<CheckResult>) -> HostCheck {
+        let failed = repositories.iter().filter(|r| !r.passed).count();
+        HostCheck {
+            host: host.to_string(),
+            passed: repositories.len() - failed,
+            failed,
+            repositories,
+            discovery_failures: Vec::new(),
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_all_hosts_report_groups_per_host() {
+        let checks = vec![
+            host(
+                "nas",
+                vec![
+                    repo("system/etc", None),
+                    repo("user_home/tim", Some("pack 3f9a is damaged")),
+                ],
+            ),
+            HostCheck::unchecked(
+                "offline",
+                &BackupServiceError::CommandFailed("ssh: connection refused".to_string()),
+            ),
+            host("web1", vec![repo("docker_volume/db", None)]),
+        ];
+        let totals = CheckTotals::of(&checks);
+        assert_eq!(
+            totals,
+            CheckTotals {
+                checked: 3,
+                failed: 1,
+                discovery_failures: 0,
+                failed_hosts: vec!["offline".to_string()],
+            }
+        );
+
+        let options = CheckOptions {
+            all_hosts: true,
+            read_data_subset: Some("1/5".to_string()),
+            json_output: true,
+        };
+        let report = check_report(&options, &checks, &totals);
+        assert_eq!(report["read_data_subset"], "1/5");
+        assert_eq!(report["hosts"][0]["host"], "nas");
+        assert_eq!(report["hosts"][0]["passed"], 1);
+        assert_eq!(
+            report["hosts"][0]["repositories"][1]["error"],
+            "pack 3f9a is damaged"
+        );
+        assert_eq!(report["hosts"][1]["host"], "offline");
+        assert!(
+            report["hosts"][1]["error"]
+                .as_str()
+                .is_some_and(|e| e.contains("connection refused"))
+        );
+        assert_eq!(report["hosts"][1]["repositories"], json!([]));
+        assert_eq!(
+            report["hosts"][2]["repositories"][0]["repo_subpath"],
+            "docker_volume/db"
+        );
+        // Only unchecked hosts carry an error
+        assert!(report["hosts"][0].get("error").is_none());
+        assert_eq!(
+            report["totals"],
+            json!({"hosts": 3, "passed": 2, "failed": 1, "failed_hosts": ["offline"]})
+        );
+    }
+
+    #[test]
+    fn test_single_host_report() {
+        let checks = vec![host("nas", vec![repo("system/etc", None)])];
+        let totals = CheckTotals::of(&checks);
+        assert_eq!(totals.checked, 1);
+        assert!(totals.failed_hosts.is_empty());
+        let report = check_report(&CheckOptions::default(), &checks, &totals);
+        assert_eq!(report["host"], "nas");
+        assert_eq!(report["passed"], 1);
+        assert!(report.get("totals").is_none());
+    }
+}
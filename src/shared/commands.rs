@@ -1,13 +1,135 @@
 use crate::config::Config;
 use crate::errors::BackupServiceError;
+use crate::shared::backend_tuning::BackendTuning;
+use crate::shared::backup_progress::{BackupProgress, BackupStatusLine, clear_status_line};
+use crate::shared::backup_summary::ResticSummary;
+use crate::shared::dependencies::restic_binary;
+use crate::shared::faults;
+use crate::shared::resource_limits::ResourceLimits;
+use crate::shared::retry::RetryPolicy;
+use crate::shared::secret_source::{PASSWORD_COMMAND_ENV_VAR, PASSWORD_FILE_ENV_VAR};
+use crate::shared::shutdown;
 use serde_json::Value;
+use std::future::Future;
 use std::path::Path;
-use std::process::Command;
-use tracing::{debug, info};
+use std::process::{Output, Stdio};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::Command;
+use tracing::{debug, info, warn};
 
-/// Unified command executor for AWS CLI and restic commands
+/// restic's exit code when the snapshot was saved but some source files were unreadable
+const RESTIC_EXIT_PARTIAL: i32 = 3;
+
+/// Seconds a metadata command (snapshots, stats, ls, ...) may run before it is killed; 0 disables
+pub const COMMAND_TIMEOUT_ENV_VAR: &str = "RESTIC_COMMAND_TIMEOUT";
+const DEFAULT_COMMAND_TIMEOUT_SECS: u64 = 1800;
+/// Upper bound in seconds for every restic command, long-running ones included, and every
+/// S3 request (unset: none)
+pub const GLOBAL_TIMEOUT_ENV_VAR: &str = "COMMAND_TIMEOUT_SECS";
+
+/// Subcommands that move or scan whole snapshots and may run for hours; never timed out
+const LONG_RUNNING_SUBCOMMANDS: &[&str] = &[
+    "backup", "restore", "copy", "prune", "forget", "check", "rewrite", "find", "dump",
+];
+
+/// How long one invocation of `subcommand` may run: the shorter of RESTIC_COMMAND_TIMEOUT
+/// (default 30 min, metadata commands only) and COMMAND_TIMEOUT_SECS
+pub fn command_timeout(subcommand: &str) -> Result<Option<Duration>, BackupServiceError> {
+    command_timeout_from_lookup(subcommand, |name| std::env::var(name).ok())
+}
+
+/// COMMAND_TIMEOUT_SECS, the bound for any single command or S3 request
+pub fn global_timeout() -> Result<Option<Duration>, BackupServiceError> {
+    timeout_setting(GLOBAL_TIMEOUT_ENV_VAR, None, |name| {
+        std::env::var(name).ok()
+    })
+}
+
+fn command_timeout_from_lookup(
+    subcommand: &str,
+    lookup: impl Fn(&str) -> Option<String>,
+) -> Result<Option<Duration>, BackupServiceError> {
+    let global = timeout_setting(GLOBAL_TIMEOUT_ENV_VAR, None, &lookup)?;
+    if LONG_RUNNING_SUBCOMMANDS.contains(&subcommand) {
+        return Ok(global);
+    }
+    let own = timeout_setting(
+        COMMAND_TIMEOUT_ENV_VAR,
+        Some(DEFAULT_COMMAND_TIMEOUT_SECS),
+        &lookup,
+    )?;
+    Ok(own.into_iter().chain(global).min())
+}
+
+/// Seconds from `name`, or `default` when unset; 0 disables the timeout
+fn timeout_setting(
+    name: &str,
+    default: Option<u64>,
+    lookup: impl Fn(&str) -> Option<String>,
+) -> Result<Option<Duration>, BackupServiceError> {
+    let secs = match lookup(name)
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+    {
+        None => default,
+        Some(value) => Some(value.parse::<u64>().map_err(|_| {
+            BackupServiceError::ConfigurationError(format!(
+                "Invalid {}: {}.\n\nUse a number of seconds, or 0 to disable the timeout",
+                name, value
+            ))
+        })?),
+    };
+    Ok(secs.filter(|secs| *secs > 0).map(Duration::from_secs))
+}
+
+/// Await a child process, giving up once `timeout` has passed
+///
+/// Commands are spawned with `kill_on_drop`, so the child of a timed-out future is killed.
+pub async fn with_timeout<T>(
+    future: impl Future<Output = T>,
+    timeout: Option<Duration>,
+    context: &str,
+) -> Result<T, BackupServiceError> {
+    let Some(timeout) = timeout else {
+        return Ok(future.await);
+    };
+    tokio::time::timeout(timeout, future).await.map_err(|_| {
+        BackupServiceError::CommandFailed(format!(
+            "{} timed out after {}s (raise {} or {} if it needs longer)",
+            context,
+            timeout.as_secs(),
+            COMMAND_TIMEOUT_ENV_VAR,
+            GLOBAL_TIMEOUT_ENV_VAR
+        ))
+    })
+}
+
+/// Spawn `command` and wait for it within `timeout`, tracked so Ctrl-C can interrupt it
+///
+/// With `capture` stdout and stderr are collected, otherwise they are inherited.
+async fn run_tracked(
+    command: &mut Command,
+    capture: bool,
+    timeout: Option<Duration>,
+    context: &str,
+) -> Result<std::io::Result<Output>, BackupServiceError> {
+    if capture {
+        command.stdout(Stdio::piped()).stderr(Stdio::piped());
+    }
+    let child = match command.spawn() {
+        Ok(child) => child,
+        Err(e) => return Ok(Err(e)),
+    };
+    let _tracked = shutdown::track_child(child.id());
+    with_timeout(child.wait_with_output(), timeout, context).await
+}
+
+/// Command executor for restic (S3 access goes through `shared::s3`)
 pub struct CommandExecutor {
     config: Config,
+    /// Password of the `--from-repo` repository for `restic copy`
+    from_password: Option<String>,
 }
 
 /// Restic command wrapper using the unified executor
@@ -16,38 +138,41 @@ pub struct ResticCommandExecutor {
     repo_url: String,
 }
 
-/// S3 command wrapper using the unified executor
-pub struct S3CommandExecutor {
-    executor: CommandExecutor,
-}
-
 impl CommandExecutor {
     pub fn new(config: Config) -> Result<Self, BackupServiceError> {
-        Ok(Self { config })
+        Ok(Self {
+            config,
+            from_password: None,
+        })
     }
 
-    /// Execute AWS S3 command with proper credentials and error handling
-    pub async fn execute_aws_command(
+    /// `restic --repo <url>` with the backend tuning flags, credentials and `args`
+    fn restic_command(
         &self,
+        limits: &ResourceLimits,
+        repo_url: &str,
         args: &[&str],
-        context: &str,
-    ) -> Result<String, BackupServiceError> {
-        debug!(args = ?args, context = %context, "Executing AWS command");
-
-        let output = Command::new("aws")
+    ) -> Result<Command, BackupServiceError> {
+        let tuning = BackendTuning::from_env(&self.config.restic_repo_base)?;
+        let mut command = limits.command(&restic_binary());
+        command
+            .args(["--repo", repo_url])
+            .args(tuning.restic_args())
             .args(args)
             .env("AWS_ACCESS_KEY_ID", &self.config.aws_access_key_id)
             .env("AWS_SECRET_ACCESS_KEY", &self.config.aws_secret_access_key)
             .env("AWS_DEFAULT_REGION", &self.config.aws_default_region)
-            .output()
-            .map_err(|_| BackupServiceError::aws_command_failed())?;
-
-        if output.status.success() {
-            Ok(String::from_utf8_lossy(&output.stdout).to_string())
-        } else {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            Err(BackupServiceError::from_stderr(&stderr, context))
+            .env("AWS_S3_ENDPOINT", &self.config.aws_s3_endpoint)
+            .env("RESTIC_PASSWORD", &self.config.restic_password)
+            // The password is already resolved; restic must not read the sources again
+            .env_remove(PASSWORD_FILE_ENV_VAR)
+            .env_remove(PASSWORD_COMMAND_ENV_VAR);
+        if let Some(password) = &self.from_password {
+            command.env("RESTIC_FROM_PASSWORD", password);
         }
+        let mut command = Command::from(command);
+        command.kill_on_drop(true);
+        Ok(command)
     }
 
     /// Execute restic command with repository URL and proper environment
@@ -60,50 +185,169 @@ impl CommandExecutor {
     ) -> Result<String, BackupServiceError> {
         debug!(repo_url = %repo_url, args = ?args, context = %context, show_live_output = %show_live_output, "Executing restic command");
 
+        let fault = faults::active_fault();
+
+        // Optionally confine restic to a systemd scope so it cannot starve other workloads
+        let subcommand = args.first().copied().unwrap_or_default();
+        let limits = ResourceLimits::from_env(ResourceLimits::profile_for(subcommand))?;
+        let timeout = command_timeout(subcommand)?;
+        let timeout_context = format!("restic {}", context);
+
         if show_live_output {
+            if let Some(result) = fault.and_then(|f| faults::inject_restic_fault(f, context)) {
+                return result;
+            }
             // For operations like restore where we want to see live progress
-            let status = Command::new("restic")
-                .args(["--repo", repo_url])
-                .args(args)
-                .env("AWS_ACCESS_KEY_ID", &self.config.aws_access_key_id)
-                .env("AWS_SECRET_ACCESS_KEY", &self.config.aws_secret_access_key)
-                .env("AWS_DEFAULT_REGION", &self.config.aws_default_region)
-                .env("AWS_S3_ENDPOINT", &self.config.aws_s3_endpoint)
-                .env("RESTIC_PASSWORD", &self.config.restic_password)
-                .status()
-                .map_err(|_| BackupServiceError::restic_command_failed())?;
-
-            if status.success() {
+            let mut command = self.restic_command(&limits, repo_url, args)?;
+            let output = run_tracked(&mut command, false, timeout, &timeout_context)
+                .await?
+                .map_err(|e| limits.spawn_error(e))?;
+
+            if output.status.success() {
                 Ok(String::new()) // Return empty string for live output mode
             } else {
                 Err(BackupServiceError::restic_command_failed())
             }
         } else {
-            // Original behavior for operations where we need to capture output
-            let output = Command::new("restic")
-                .args(["--repo", repo_url])
-                .args(args)
-                .env("AWS_ACCESS_KEY_ID", &self.config.aws_access_key_id)
-                .env("AWS_SECRET_ACCESS_KEY", &self.config.aws_secret_access_key)
-                .env("AWS_DEFAULT_REGION", &self.config.aws_default_region)
-                .env("AWS_S3_ENDPOINT", &self.config.aws_s3_endpoint)
-                .env("RESTIC_PASSWORD", &self.config.restic_password)
-                .output()
-                .map_err(|_| BackupServiceError::restic_command_failed())?;
+            // Captured output: transient failures (network, throttling) are retried
+            let retry = RetryPolicy::from_env()?;
+            let mut attempt = 1;
+            loop {
+                // An injected fault stands in for the attempt, so it is retried like a real one
+                let error = match fault.and_then(|f| faults::inject_restic_fault(f, context)) {
+                    Some(Ok(output)) => return Ok(output),
+                    Some(Err(error)) => error,
+                    None => {
+                        let mut command = self.restic_command(&limits, repo_url, args)?;
+                        let output = run_tracked(&mut command, true, timeout, &timeout_context)
+                            .await?
+                            .map_err(|e| limits.spawn_error(e))?;
 
-            if output.status.success() {
-                Ok(String::from_utf8_lossy(&output.stdout).to_string())
-            } else {
-                let stderr = String::from_utf8_lossy(&output.stderr);
-                Err(BackupServiceError::from_stderr(&stderr, repo_url))
+                        if output.status.success() {
+                            let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+                            return Ok(faults::apply_output_fault(fault, stdout));
+                        }
+                        let stderr = String::from_utf8_lossy(&output.stderr);
+                        BackupServiceError::from_stderr(&stderr, repo_url)
+                    }
+                };
+                if !retry.should_retry(&error, attempt) {
+                    return Err(error);
+                }
+                retry.backoff(attempt, context, &error).await;
+                attempt += 1;
             }
         }
     }
 
-    /// Get S3 endpoint URL for AWS commands
-    pub fn get_s3_endpoint_args(&self) -> Result<Vec<String>, BackupServiceError> {
-        let endpoint = self.config.s3_endpoint()?;
-        Ok(vec!["--endpoint-url".to_string(), endpoint])
+    /// Run a restic command, handing each stdout line to `on_line` as it arrives
+    ///
+    /// stderr is forwarded live and kept for error classification; JSON error messages
+    /// (`--json` mode) are logged as warnings instead. Exit code 3 (snapshot saved, some
+    /// files unreadable) is returned as success with a warning. Network and throttling
+    /// failures are retried with backoff (`RetryPolicy`).
+    pub async fn execute_restic_streaming(
+        &self,
+        repo_url: &str,
+        args: &[&str],
+        context: &str,
+        on_line: &mut (dyn FnMut(&str) + Send),
+    ) -> Result<(), BackupServiceError> {
+        debug!(repo_url = %repo_url, args = ?args, context = %context, "Executing restic command (streaming)");
+
+        let fault = faults::active_fault();
+        let limits = ResourceLimits::from_env(
+            args.first()
+                .and_then(|subcommand| ResourceLimits::profile_for(subcommand)),
+        )?;
+        let timeout = command_timeout(args.first().copied().unwrap_or_default())?;
+        let timeout_context = format!("restic {}", context);
+        let retry = RetryPolicy::from_env()?;
+        let mut attempt = 1;
+        loop {
+            // An injected fault stands in for the attempt, so it is retried like a real one
+            let result = match fault.and_then(|f| faults::inject_restic_fault(f, context)) {
+                Some(result) => result.map(|_| ()),
+                None => {
+                    let run = self.stream_once(&limits, repo_url, args, context, on_line);
+                    with_timeout(run, timeout, &timeout_context)
+                        .await
+                        .and_then(|r| r)
+                }
+            };
+            match result {
+                Err(error) if retry.should_retry(&error, attempt) => {
+                    retry.backoff(attempt, context, &error).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// One run of a streaming restic command
+    async fn stream_once(
+        &self,
+        limits: &ResourceLimits,
+        repo_url: &str,
+        args: &[&str],
+        context: &str,
+        on_line: &mut (dyn FnMut(&str) + Send),
+    ) -> Result<(), BackupServiceError> {
+        let mut command = self.restic_command(limits, repo_url, args)?;
+        let mut child = command
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| limits.spawn_error(e))?;
+        let _tracked = shutdown::track_child(child.id());
+
+        let stderr = child.stderr.take().map(BufReader::new);
+        let stderr_task = tokio::spawn(async move {
+            let mut collected = String::new();
+            let Some(stderr) = stderr else {
+                return collected;
+            };
+            let mut lines = stderr.lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                match serde_json::from_str::<Value>(&line) {
+                    Ok(message) if message["message_type"] == "error" => {
+                        warn!(
+                            item = %message["item"].as_str().unwrap_or_default(),
+                            during = %message["during"].as_str().unwrap_or_default(),
+                            error = %message["error"]["message"].as_str().unwrap_or_default(),
+                            "restic could not read an item"
+                        );
+                    }
+                    _ => {
+                        clear_status_line();
+                        eprintln!("{}", line)
+                    }
+                }
+                collected.push_str(&line);
+                collected.push('\n');
+            }
+            collected
+        });
+
+        if let Some(stdout) = child.stdout.take() {
+            let mut lines = BufReader::new(stdout).lines();
+            while let Some(line) = lines.next_line().await? {
+                on_line(&line);
+            }
+        }
+        let status = child.wait().await?;
+        let stderr = stderr_task.await.unwrap_or_default();
+
+        match status.code() {
+            Some(0) => Ok(()),
+            Some(RESTIC_EXIT_PARTIAL) => {
+                warn!(context = %context, "Snapshot saved, but at least one source file could not be read");
+                Ok(())
+            }
+            _ if stderr.trim().is_empty() => Err(BackupServiceError::restic_command_failed()),
+            _ => Err(BackupServiceError::from_stderr(&stderr, repo_url)),
+        }
     }
 }
 
@@ -129,24 +373,148 @@ pub async fn check_restic_repository_exists(
     }
 }
 
+/// Directory names below `path` on an SFTP backend host, listed with `ssh <target> ls -1p`
+///
+/// A missing directory lists as empty, like a missing S3 prefix. restic itself reaches the
+/// host through its own sftp/ssh call, so both share the user's ssh configuration.
+pub async fn list_remote_directories(
+    target: &str,
+    port: Option<u16>,
+    path: &str,
+) -> Result<Vec<String>, BackupServiceError> {
+    let context = format!("sftp:{}:{}", target, path);
+    let mut command = Command::new("ssh");
+    command.kill_on_drop(true).args(["-o", "BatchMode=yes"]);
+    if let Some(port) = port {
+        command.args(["-p", &port.to_string()]);
+    }
+    // The remote shell parses the command line, so the path is single-quoted
+    command
+        .arg(target)
+        .arg(format!("ls -1p -- {}", shell_quote(path)));
+    let output = run_tracked(&mut command, true, command_timeout("ls")?, &context)
+        .await?
+        .map_err(|e| {
+            BackupServiceError::CommandNotFound(format!("Failed to execute ssh: {}", e))
+        })?;
+
+    if output.status.success() {
+        return Ok(directory_entries(&String::from_utf8_lossy(&output.stdout)));
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if stderr.contains("No such file or directory") {
+        return Ok(Vec::new());
+    }
+    Err(BackupServiceError::CommandFailed(format!(
+        "Listing {} failed: {}",
+        context,
+        stderr.trim()
+    )))
+}
+
+/// `ssh <target> rm -rf` of a directory; removing a tree can take as long as a prune
+pub async fn remove_remote_directory(
+    target: &str,
+    port: Option<u16>,
+    path: &str,
+) -> Result<(), BackupServiceError> {
+    let context = format!("sftp:{}:{}", target, path);
+    let mut command = Command::new("ssh");
+    command.kill_on_drop(true).args(["-o", "BatchMode=yes"]);
+    if let Some(port) = port {
+        command.args(["-p", &port.to_string()]);
+    }
+    command
+        .arg(target)
+        .arg(format!("rm -rf -- {}", shell_quote(path)));
+    let output = run_tracked(&mut command, true, command_timeout("prune")?, &context)
+        .await?
+        .map_err(|e| {
+            BackupServiceError::CommandNotFound(format!("Failed to execute ssh: {}", e))
+        })?;
+
+    if output.status.success() {
+        return Ok(());
+    }
+    Err(BackupServiceError::CommandFailed(format!(
+        "Removing {} failed: {}",
+        context,
+        String::from_utf8_lossy(&output.stderr).trim()
+    )))
+}
+
+/// Directories (`name/`) from `ls -1p` output
+fn directory_entries(listing: &str) -> Vec<String> {
+    listing
+        .lines()
+        .filter_map(|line| line.strip_suffix('/'))
+        .filter(|name| !name.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
 impl ResticCommandExecutor {
     pub fn new(config: Config, repo_url: String) -> Result<Self, BackupServiceError> {
         let executor = CommandExecutor::new(config)?;
         Ok(Self { executor, repo_url })
     }
 
-    /// Initialize repository if needed
-    pub async fn init_if_needed(&self) -> Result<(), BackupServiceError> {
+    /// Executor for a copy target: `from_password` opens the `--from-repo` source
+    pub fn with_from_password(mut self, from_password: &str) -> Self {
+        self.executor.from_password = Some(from_password.to_string());
+        self
+    }
+
+    /// Initialize the repository if needed, with the chunker parameters of `from_repo` so
+    /// snapshots copied from there deduplicate as well as in the source
+    pub async fn init_copy_target_if_needed(
+        &self,
+        from_repo: &str,
+    ) -> Result<(), BackupServiceError> {
         if !self.repo_exists().await? {
-            info!(repo_url = %self.repo_url, "Initializing repository");
+            info!(repo_url = %self.repo_url, "Initializing mirror repository");
             self.executor
                 .execute_restic_command(
                     &self.repo_url,
-                    &["init"],
-                    "repository initialization",
+                    &["init", "--from-repo", from_repo, "--copy-chunker-params"],
+                    "mirror repository initialization",
                     false,
                 )
                 .await?;
+        }
+        Ok(())
+    }
+
+    /// Copy the given snapshots of `from_repo` into this repository
+    pub async fn copy_from(
+        &self,
+        from_repo: &str,
+        snapshot_ids: &[String],
+    ) -> Result<(), BackupServiceError> {
+        // Keep the argument list well below ARG_MAX for long histories
+        for chunk in snapshot_ids.chunks(200) {
+            let mut args = vec!["copy", "--from-repo", from_repo];
+            args.extend(chunk.iter().map(|s| s.as_str()));
+            self.executor
+                .execute_restic_command(&self.repo_url, &args, "copy", false)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Initialize repository if needed, passing `init_args` (e.g. the repository version)
+    pub async fn init_if_needed(&self, init_args: &[String]) -> Result<(), BackupServiceError> {
+        if !self.repo_exists().await? {
+            info!(repo_url = %self.repo_url, "Initializing repository");
+            let mut args = vec!["init"];
+            args.extend(init_args.iter().map(String::as_str));
+            self.executor
+                .execute_restic_command(&self.repo_url, &args, "repository initialization", false)
+                .await?;
             info!("Repository initialized");
         }
         Ok(())
@@ -157,13 +525,16 @@ impl ResticCommandExecutor {
         check_restic_repository_exists(&self.executor.config, &self.repo_url).await
     }
 
-    /// Run backup with exact parameters
+    /// Run backup with exact parameters plus caller-specific tags/excludes
+    ///
+    /// restic reports in `--json` mode: status lines drive the path's progress display and
+    /// the final summary message is returned (None if restic did not print one).
     pub async fn backup(
         &self,
         path: &Path,
         hostname: &str,
-        show_live_output: bool,
-    ) -> Result<String, BackupServiceError> {
+        extra_args: &[String],
+    ) -> Result<Option<ResticSummary>, BackupServiceError> {
         let path_str = path.to_string_lossy();
         let tag = determine_backup_tag(path)?;
         let mut args: Vec<String> = vec![
@@ -173,6 +544,7 @@ impl ResticCommandExecutor {
             hostname.to_string(),
             "--tag".to_string(),
             tag.to_string(),
+            "--json".to_string(),
         ];
 
         // Append official restic exclude options if provided via environment
@@ -198,19 +570,148 @@ impl ResticCommandExecutor {
             args.push("--exclude-larger-than".to_string());
             args.push(sz);
         }
+        args.extend(extra_args.iter().cloned());
 
         let arg_refs: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
 
-        self.executor
-            .execute_restic_command(
+        let mut progress = BackupProgress::new(&path_str);
+        let mut summary = None;
+        // Lines that are not JSON (e.g. a wrapper script's output) are parsed as text
+        let mut text = String::new();
+        let result = self
+            .executor
+            .execute_restic_streaming(
                 &self.repo_url,
                 &arg_refs,
                 &format!("backup {}", path_str),
-                show_live_output,
+                &mut |line| {
+                    let Ok(message) = serde_json::from_str::<Value>(line) else {
+                        text.push_str(line);
+                        text.push('\n');
+                        return;
+                    };
+                    if let Some(status) = BackupStatusLine::from_json(&message) {
+                        progress.update(&status);
+                    } else if message["message_type"] == "summary" {
+                        summary = ResticSummary::from_json(&message);
+                    }
+                },
             )
+            .await;
+        progress.finish();
+        result.map(|()| summary.or_else(|| ResticSummary::from_text(&text)))
+    }
+
+    /// Prune unreferenced data with the given tuning flags (live output)
+    pub async fn prune(
+        &self,
+        extra_args: &[String],
+        show_live_output: bool,
+    ) -> Result<String, BackupServiceError> {
+        let mut args = vec!["prune"];
+        args.extend(extra_args.iter().map(|s| s.as_str()));
+
+        self.executor
+            .execute_restic_command(&self.repo_url, &args, "prune", show_live_output)
+            .await
+    }
+
+    /// Apply keep-* rules with `forget --prune`, passing prune tuning through (live output)
+    pub async fn forget_prune(
+        &self,
+        forget_args: &[String],
+        prune_args: &[String],
+        show_live_output: bool,
+    ) -> Result<String, BackupServiceError> {
+        let mut args = vec!["forget"];
+        args.extend(forget_args.iter().map(|s| s.as_str()));
+        args.push("--prune");
+        args.extend(prune_args.iter().map(|s| s.as_str()));
+
+        self.executor
+            .execute_restic_command(&self.repo_url, &args, "forget --prune", show_live_output)
+            .await
+    }
+
+    /// Verify repository structure (and optionally a subset of pack data)
+    pub async fn check(&self, extra_args: &[String]) -> Result<String, BackupServiceError> {
+        let mut args = vec!["check"];
+        args.extend(extra_args.iter().map(|s| s.as_str()));
+
+        self.executor
+            .execute_restic_command(&self.repo_url, &args, "check", false)
             .await
     }
 
+    /// Forget explicit snapshot IDs (decided client-side by retention rules)
+    pub async fn forget_ids(&self, ids: &[String]) -> Result<(), BackupServiceError> {
+        // Keep the argument list well below ARG_MAX for long histories
+        for chunk in ids.chunks(200) {
+            let mut args = vec!["forget"];
+            args.extend(chunk.iter().map(|s| s.as_str()));
+            self.executor
+                .execute_restic_command(&self.repo_url, &args, "forget", false)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Put every snapshot under `new_host` (`restic rewrite --new-host`, restic >= 0.17)
+    ///
+    /// Only the snapshot metadata changes; `--forget` drops the originals, so each snapshot
+    /// is kept once, with the new hostname.
+    pub async fn rewrite_host(&self, new_host: &str) -> Result<(), BackupServiceError> {
+        self.executor
+            .execute_restic_command(
+                &self.repo_url,
+                &["rewrite", "--new-host", new_host, "--forget"],
+                "rewrite --new-host",
+                false,
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Locks currently stored in the repository, as raw `restic cat lock` JSON
+    pub async fn locks(&self) -> Result<Vec<(String, Value)>, BackupServiceError> {
+        // Listing must not take a lock of its own, or it would always find one
+        let output = self
+            .executor
+            .execute_restic_command(
+                &self.repo_url,
+                &["list", "locks", "--no-lock"],
+                "lock listing",
+                false,
+            )
+            .await?;
+        let mut locks = Vec::new();
+        for id in output.lines().map(str::trim).filter(|id| !id.is_empty()) {
+            // A lock released between listing and reading is simply gone
+            match self
+                .executor
+                .execute_restic_command(
+                    &self.repo_url,
+                    &["cat", "lock", id, "--no-lock"],
+                    "lock details",
+                    false,
+                )
+                .await
+            {
+                Ok(lock) => locks.push((id.to_string(), serde_json::from_str(&lock)?)),
+                Err(e) => debug!(lock_id = %id, error = %e, "Lock vanished while reading it"),
+            }
+        }
+        Ok(locks)
+    }
+
+    /// Remove the locks restic itself considers stale
+    pub async fn unlock(&self) -> Result<(), BackupServiceError> {
+        self.executor
+            .execute_restic_command(&self.repo_url, &["unlock"], "unlock", false)
+            .await
+            .map(|_| ())
+    }
+
     /// Get snapshots as JSON
     pub async fn snapshots(&self) -> Result<Vec<Value>, BackupServiceError> {
         let args = vec!["snapshots", "--json"];
@@ -224,20 +725,274 @@ impl ResticCommandExecutor {
         Ok(snapshots)
     }
 
-    /// Restore snapshot
+    /// Keys of the repository (`key list --json`); `current` marks the one that opened it
+    pub async fn keys(&self) -> Result<Vec<Value>, BackupServiceError> {
+        let output = self
+            .executor
+            .execute_restic_command(
+                &self.repo_url,
+                &["key", "list", "--json"],
+                "key listing",
+                false,
+            )
+            .await?;
+        Ok(serde_json::from_str(&output)?)
+    }
+
+    /// Add a key for the password stored in `new_password_file`
+    pub async fn add_key(&self, new_password_file: &Path) -> Result<(), BackupServiceError> {
+        let file = new_password_file.to_string_lossy();
+        self.executor
+            .execute_restic_command(
+                &self.repo_url,
+                &["key", "add", "--new-password-file", &file],
+                "key add",
+                false,
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Remove a key by ID; restic refuses to remove the key that opened the repository
+    pub async fn remove_key(&self, key_id: &str) -> Result<(), BackupServiceError> {
+        self.executor
+            .execute_restic_command(
+                &self.repo_url,
+                &["key", "remove", key_id],
+                "key remove",
+                false,
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Most recent snapshots of a path for a host, newest first
+    pub async fn recent_snapshots(
+        &self,
+        hostname: &str,
+        path: &Path,
+        count: usize,
+    ) -> Result<Vec<Value>, BackupServiceError> {
+        let path_str = path.to_string_lossy();
+        let latest = count.to_string();
+        let output = self
+            .executor
+            .execute_restic_command(
+                &self.repo_url,
+                &[
+                    "snapshots",
+                    "--json",
+                    "--host",
+                    hostname,
+                    "--path",
+                    &path_str,
+                    "--latest",
+                    &latest,
+                ],
+                &format!("recent snapshots for {}", path_str),
+                false,
+            )
+            .await?;
+
+        let mut snapshots: Vec<Value> = serde_json::from_str(&output)?;
+        snapshots.sort_by(|a, b| b["time"].as_str().cmp(&a["time"].as_str()));
+        snapshots.truncate(count);
+        Ok(snapshots)
+    }
+
+    /// Distinct source paths this host has snapshots of in the repository
+    pub async fn snapshot_paths(&self, hostname: &str) -> Result<Vec<String>, BackupServiceError> {
+        // --latest keeps one snapshot per host and path set, so this stays cheap
+        let output = self
+            .executor
+            .execute_restic_command(
+                &self.repo_url,
+                &["snapshots", "--json", "--host", hostname, "--latest", "1"],
+                "snapshot paths listing",
+                false,
+            )
+            .await?;
+
+        let snapshots: Vec<Value> = serde_json::from_str(&output)?;
+        let mut paths: Vec<String> = snapshots
+            .iter()
+            .filter_map(|s| s["paths"].as_array())
+            .flatten()
+            .filter_map(|p| p.as_str().map(str::to_string))
+            .collect();
+        paths.sort();
+        paths.dedup();
+        Ok(paths)
+    }
+
+    /// Number of files in a snapshot from its summary, or via `stats` for older repositories
+    pub async fn snapshot_file_count(&self, snapshot: &Value) -> Result<u64, BackupServiceError> {
+        // restic >= 0.17 records a summary in the snapshot; older repos need a stats call
+        if let Some(files) = snapshot["summary"]["total_files_processed"].as_u64() {
+            return Ok(files);
+        }
+        let id = snapshot["short_id"].as_str().unwrap_or("latest");
+        let output = self
+            .executor
+            .execute_restic_command(
+                &self.repo_url,
+                &["stats", id, "--mode", "restore-size", "--json"],
+                &format!("stats for snapshot {}", id),
+                false,
+            )
+            .await?;
+        let stats: Value = serde_json::from_str(&output)?;
+        Ok(stats["total_file_count"].as_u64().unwrap_or(0))
+    }
+
+    /// `restore --dry-run` summary of what restoring into `target` would write (restic >= 0.17)
+    ///
+    /// Files already identical in `target` count as skipped, so against the original
+    /// location the summary tells how much a copy back would replace.
+    pub async fn restore_dry_run(
+        &self,
+        snapshot_id: &str,
+        path: &str,
+        target: &str,
+        extra_args: &[String],
+    ) -> Result<Value, BackupServiceError> {
+        let mut args = vec![
+            "restore",
+            snapshot_id,
+            "--path",
+            path,
+            "--target",
+            target,
+            "--dry-run",
+            "--json",
+        ];
+        args.extend(extra_args.iter().map(|s| s.as_str()));
+
+        let output = self
+            .executor
+            .execute_restic_command(
+                &self.repo_url,
+                &args,
+                &format!("restore --dry-run {} to {}", snapshot_id, target),
+                false,
+            )
+            .await?;
+        output
+            .lines()
+            .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+            .find(|message| message["message_type"] == "summary")
+            .ok_or_else(|| {
+                BackupServiceError::CommandFailed(
+                    "restic printed no dry-run summary (restore --dry-run needs restic 0.17 or newer)"
+                        .to_string(),
+                )
+            })
+    }
+
+    /// Restore snapshot, appending any extra restic flags (e.g. `--limit-download`)
     pub async fn restore(
         &self,
         snapshot_id: &str,
         path: &str,
         target: &str,
+        extra_args: &[String],
+        live_output: bool,
     ) -> Result<String, BackupServiceError> {
+        let mut args = vec!["restore", snapshot_id, "--path", path, "--target", target];
+        args.extend(extra_args.iter().map(|s| s.as_str()));
+
         self.executor
             .execute_restic_command(
                 &self.repo_url,
-                &["restore", snapshot_id, "--path", path, "--target", target],
+                &args,
                 &format!("restore {} to {}", snapshot_id, target),
-                true, // Enable live output for restore operations
+                live_output,
+            )
+            .await
+    }
+
+    /// Get stats for the latest snapshot in the given mode (`restore-size`, `raw-data`, ...)
+    pub async fn latest_stats(&self, mode: &str) -> Result<Value, BackupServiceError> {
+        self.snapshot_stats("latest", mode).await
+    }
+
+    /// Get stats for one snapshot (ID or `latest`) in the given mode
+    pub async fn snapshot_stats(
+        &self,
+        snapshot_id: &str,
+        mode: &str,
+    ) -> Result<Value, BackupServiceError> {
+        let output = self
+            .executor
+            .execute_restic_command(
+                &self.repo_url,
+                &["stats", snapshot_id, "--mode", mode, "--json"],
+                &format!("stats ({})", mode),
+                false,
+            )
+            .await?;
+
+        Ok(serde_json::from_str(&output).unwrap_or_default())
+    }
+
+    /// Get stats over every snapshot of the repository (`raw-data` is its stored size)
+    pub async fn repository_stats(&self, mode: &str) -> Result<Value, BackupServiceError> {
+        let output = self
+            .executor
+            .execute_restic_command(
+                &self.repo_url,
+                &["stats", "--mode", mode, "--json"],
+                &format!("repository stats ({})", mode),
+                false,
             )
+            .await?;
+
+        Ok(serde_json::from_str(&output).unwrap_or_default())
+    }
+
+    /// `restic find --json`: entries matching any of the patterns, grouped per snapshot
+    ///
+    /// Without `snapshot_ids` every snapshot of the repository is searched.
+    pub async fn find(
+        &self,
+        patterns: &[String],
+        snapshot_ids: &[String],
+        ignore_case: bool,
+    ) -> Result<Vec<Value>, BackupServiceError> {
+        let mut args = vec!["find", "--json"];
+        if ignore_case {
+            args.push("--ignore-case");
+        }
+        for id in snapshot_ids {
+            args.extend(["--snapshot", id.as_str()]);
+        }
+        args.extend(patterns.iter().map(String::as_str));
+
+        let output = self
+            .executor
+            .execute_restic_command(&self.repo_url, &args, "file search", false)
+            .await?;
+        if output.trim().is_empty() {
+            return Ok(Vec::new());
+        }
+        Ok(serde_json::from_str(&output)?)
+    }
+
+    /// `restic ls --json` of one directory in a snapshot (NDJSON: the snapshot, then its nodes);
+    /// `recursive` includes everything below the directory, not just its entries
+    pub async fn ls(
+        &self,
+        snapshot: &str,
+        dir: &str,
+        recursive: bool,
+    ) -> Result<String, BackupServiceError> {
+        let mut args = vec!["ls", "--json"];
+        if recursive {
+            args.push("--recursive");
+        }
+        args.extend([snapshot, dir]);
+        self.executor
+            .execute_restic_command(&self.repo_url, &args, &format!("listing {}", dir), false)
             .await
     }
 
@@ -277,48 +1032,60 @@ pub fn determine_backup_tag(path: &Path) -> Result<&'static str, BackupServiceEr
     Ok(tag)
 }
 
-impl S3CommandExecutor {
-    pub fn new(config: Config) -> Result<Self, BackupServiceError> {
-        let executor = CommandExecutor::new(config)?;
-        Ok(Self { executor })
-    }
-
-    /// List S3 directories with proper error handling
-    pub async fn list_directories(&self, s3_path: &str) -> Result<Vec<String>, BackupServiceError> {
-        let s3_bucket = self.executor.config.s3_bucket()?;
-        let full_path = if s3_path.is_empty() {
-            format!("s3://{}/", s3_bucket)
-        } else {
-            format!("s3://{}/{}/", s3_bucket, s3_path)
-        };
-
-        let mut args = vec!["s3", "ls", &full_path];
-        let endpoint_args = self.executor.get_s3_endpoint_args()?;
-        args.extend(endpoint_args.iter().map(|s| s.as_str()));
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        let output = self.executor.execute_aws_command(&args, &full_path).await?;
+    #[test]
+    fn test_remote_listing_keeps_directories() {
+        let listing = "docker_volume/\nsystem/\nuser_home/\nREADME\nconfig\n";
+        assert_eq!(
+            directory_entries(listing),
+            vec!["docker_volume", "system", "user_home"]
+        );
+        assert_eq!(shell_quote("/srv/it's here"), "'/srv/it'\\''s here'");
+    }
 
-        let dirs: Vec<String> = output
-            .lines()
-            .filter(|line| line.contains("PRE"))
-            .map(|line| {
-                // Extract directory name after "PRE " prefix, preserving spaces
-                if let Some(start) = line.find("PRE ") {
-                    let dir_name = &line[start + 4..]; // Skip "PRE "
-                    dir_name.trim_end_matches('/').to_string()
-                } else {
-                    String::new()
-                }
-            })
-            .filter(|d| !d.is_empty())
-            .collect();
+    #[test]
+    fn test_command_timeout() -> Result<(), BackupServiceError> {
+        let unset = |_: &str| None;
+        assert_eq!(
+            command_timeout_from_lookup("snapshots", unset)?,
+            Some(Duration::from_secs(DEFAULT_COMMAND_TIMEOUT_SECS))
+        );
+        assert_eq!(command_timeout_from_lookup("backup", unset)?, None);
 
-        Ok(dirs)
-    }
+        let set = |own: &'static str, global: &'static str| {
+            move |name: &str| match name {
+                COMMAND_TIMEOUT_ENV_VAR => Some(own.to_string()),
+                GLOBAL_TIMEOUT_ENV_VAR => Some(global.to_string()),
+                _ => None,
+            }
+        };
+        assert_eq!(
+            command_timeout_from_lookup("stats", set("120", ""))?,
+            Some(Duration::from_secs(120))
+        );
+        assert_eq!(command_timeout_from_lookup("stats", set("0", ""))?, None);
+        assert_eq!(
+            command_timeout_from_lookup("restore", set("120", ""))?,
+            None
+        );
+        assert!(command_timeout_from_lookup("stats", set("2m", "")).is_err());
 
-    /// Get available hosts from S3 bucket
-    pub async fn get_hosts(&self) -> Result<Vec<String>, BackupServiceError> {
-        let base_path = self.executor.config.s3_base_path()?;
-        self.list_directories(&base_path).await
+        // The global bound caps everything, long-running commands included
+        assert_eq!(
+            command_timeout_from_lookup("restore", set("120", "7200"))?,
+            Some(Duration::from_secs(7200))
+        );
+        assert_eq!(
+            command_timeout_from_lookup("stats", set("120", "60"))?,
+            Some(Duration::from_secs(60))
+        );
+        assert_eq!(
+            command_timeout_from_lookup("stats", set("0", "60"))?,
+            Some(Duration::from_secs(60))
+        );
+        Ok(())
     }
 }
@@ -0,0 +1,161 @@
+use crate::errors::BackupServiceError;
+use std::path::{Path, PathBuf};
+
+/// restic's compression mode for backups: `auto` (restic's default), `off` or `max`
+///
+/// restic reads the same variable itself, so prune repacks and copies follow it as well.
+pub const COMPRESSION_ENV_VAR: &str = "RESTIC_COMPRESSION";
+/// Env var with per-path overrides: `path=max,path=off`
+pub const PATH_COMPRESSION_ENV_VAR: &str = "BACKUP_PATH_COMPRESSION";
+
+/// How hard restic compresses new data (repository format 2)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    /// Fast compression, restic's default
+    Auto,
+    Off,
+    /// Slower backups, smaller uploads; pays off for text-heavy paths such as logs
+    Max,
+}
+
+impl Compression {
+    pub fn parse(value: &str) -> Result<Self, BackupServiceError> {
+        match value.trim().to_lowercase().as_str() {
+            "auto" => Ok(Compression::Auto),
+            "off" => Ok(Compression::Off),
+            "max" => Ok(Compression::Max),
+            other => Err(BackupServiceError::ConfigurationError(format!(
+                "Invalid compression mode: {}.\n\nUse auto, off or max",
+                other
+            ))),
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Compression::Auto => "auto",
+            Compression::Off => "off",
+            Compression::Max => "max",
+        }
+    }
+}
+
+/// Compression mode for each backup path
+///
+/// A per-path entry applies to that path and everything below it (the longest matching
+/// entry wins); paths without one use RESTIC_COMPRESSION, else restic's default.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CompressionRules {
+    global: Option<Compression>,
+    per_path: Vec<(PathBuf, Compression)>,
+}
+
+impl CompressionRules {
+    pub fn from_env() -> Result<Self, BackupServiceError> {
+        Self::parse(
+            &std::env::var(COMPRESSION_ENV_VAR).unwrap_or_default(),
+            &std::env::var(PATH_COMPRESSION_ENV_VAR).unwrap_or_default(),
+        )
+    }
+
+    pub fn parse(global: &str, per_path: &str) -> Result<Self, BackupServiceError> {
+        let global = match global.trim() {
+            "" => None,
+            mode => Some(Compression::parse(mode).map_err(|_| {
+                BackupServiceError::ConfigurationError(format!(
+                    "Invalid {}: {}.\n\nUse auto, off or max",
+                    COMPRESSION_ENV_VAR, mode
+                ))
+            })?),
+        };
+        let mut rules = Self {
+            global,
+            per_path: Vec::new(),
+        };
+        for entry in per_path.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let Some((path, mode)) = entry.split_once('=') else {
+                return Err(invalid(entry, "expected path=mode"));
+            };
+            let path = match path.trim() {
+                "/" => "/",
+                other => other.trim_end_matches('/'),
+            };
+            if !path.starts_with('/') {
+                return Err(invalid(entry, "the path must be absolute"));
+            }
+            let mode =
+                Compression::parse(mode).map_err(|_| invalid(entry, "use auto, off or max"))?;
+            rules.per_path.push((PathBuf::from(path), mode));
+        }
+        Ok(rules)
+    }
+
+    /// Mode for a backup path: its most specific override, else the global one
+    pub fn for_path(&self, path: &Path) -> Option<Compression> {
+        self.per_path
+            .iter()
+            .filter(|(prefix, _)| path.starts_with(prefix))
+            .max_by_key(|(prefix, _)| prefix.components().count())
+            .map(|(_, mode)| *mode)
+            .or(self.global)
+    }
+
+    /// `restic backup` arguments for a path; none leaves the mode to restic
+    pub fn backup_args(&self, path: &Path) -> Vec<String> {
+        self.for_path(path)
+            .map(|mode| vec!["--compression".to_string(), mode.as_str().to_string()])
+            .unwrap_or_default()
+    }
+
+    /// `restic init` arguments: compression needs repository format 2, so ask for it
+    /// explicitly when any path compresses
+    pub fn init_args(&self) -> Vec<String> {
+        let compresses = self
+            .global
+            .iter()
+            .chain(self.per_path.iter().map(|(_, mode)| mode))
+            .any(|mode| *mode != Compression::Off);
+        if compresses {
+            vec!["--repository-version".to_string(), "2".to_string()]
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+fn invalid(entry: &str, reason: &str) -> BackupServiceError {
+    BackupServiceError::ConfigurationError(format!(
+        "Invalid {} entry '{}': {}.\n\nExample: {}=\"/var/log=max,/srv/media=off\"",
+        PATH_COMPRESSION_ENV_VAR, entry, reason, PATH_COMPRESSION_ENV_VAR
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compression_rules() -> Result<(), BackupServiceError> {
+        let rules = CompressionRules::parse("auto", "/var/log=max, /srv/media/=off")?;
+        assert_eq!(
+            rules.backup_args(Path::new("/var/log/nginx")),
+            ["--compression", "max"]
+        );
+        assert_eq!(
+            rules.for_path(Path::new("/srv/media")),
+            Some(Compression::Off)
+        );
+        assert_eq!(rules.for_path(Path::new("/etc")), Some(Compression::Auto));
+        assert_eq!(rules.init_args(), ["--repository-version", "2"]);
+
+        let unset = CompressionRules::parse("", "")?;
+        assert!(unset.backup_args(Path::new("/etc")).is_empty());
+        assert!(unset.init_args().is_empty());
+        assert!(CompressionRules::parse("off", "")?.init_args().is_empty());
+
+        assert!(CompressionRules::parse("fast", "").is_err());
+        assert!(CompressionRules::parse("", "/var/log=zstd").is_err());
+        assert!(CompressionRules::parse("", "var/log=max").is_err());
+        Ok(())
+    }
+}
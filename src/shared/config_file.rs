@@ -0,0 +1,537 @@
+use crate::errors::BackupServiceError;
+use crate::shared::secret_source::{
+    PASSWORD_COMMAND_ENV_VAR, PASSWORD_FILE_ENV_VAR, PASSWORD_KEYRING_ENV_VAR,
+};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use toml::{Table, Value};
+use tracing::{debug, info};
+
+/// Path of the TOML settings file; without it `./config.toml`, then
+/// `/etc/restic-backup/config.toml` is read when present
+pub const CONFIG_FILE_ENV_VAR: &str = "RBS_CONFIG_FILE";
+const DEFAULT_CONFIG_FILES: &[&str] = &["config.toml", "/etc/restic-backup/config.toml"];
+/// Named section of `[profiles]` applied on top of the environment (`--profile`)
+pub const PROFILE_ENV_VAR: &str = "RBS_PROFILE";
+
+/// Password sources; a profile naming one replaces whichever the environment set
+const PASSWORD_SETTINGS: &[&str] = &[
+    "RESTIC_PASSWORD",
+    PASSWORD_FILE_ENV_VAR,
+    PASSWORD_COMMAND_ENV_VAR,
+    PASSWORD_KEYRING_ENV_VAR,
+];
+
+/// Credentials stay in the env or secrets file, which are kept unreadable for other users
+const SECRET_SETTINGS: &[&str] = &[
+    "RESTIC_PASSWORD",
+    "AWS_ACCESS_KEY_ID",
+    "AWS_SECRET_ACCESS_KEY",
+    "NOTIFY_NTFY_TOKEN",
+    "MIRROR_PASSWORD",
+    "REPORT_SMTP_PASSWORD",
+];
+
+/// Settings whose value is a `key=value` list, so a TOML table of that name is the value
+/// rather than a section of further settings
+const MAP_SETTINGS: &[&str] = &[
+    "BACKUP_NETWORK_MOUNTS",
+    "BACKUP_PATH_COMPRESSION",
+    "BACKUP_PATH_EXCLUDES",
+    "BACKUP_PATH_TAGS",
+    "HOST_BASE_PATHS",
+    "RESTORE_VERIFY_PROBES",
+    "RETENTION_POLICY",
+    "RETENTION_POLICY_DOCKER_VOLUME",
+    "RETENTION_POLICY_SYSTEM",
+    "RETENTION_POLICY_USER_HOME",
+    "RETENTION_RULES",
+];
+
+/// Map settings whose entries hold lists themselves and are therefore split on `;`
+const SEMICOLON_MAP_SETTINGS: &[&str] = &["BACKUP_PATH_EXCLUDES", "BACKUP_PATH_TAGS"];
+
+/// A parsed settings file: the shared settings and each profile's, as env vars
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConfigFile {
+    pub settings: BTreeMap<String, String>,
+    pub profiles: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+impl ConfigFile {
+    /// Settings of one `[profiles.<name>]` section
+    pub fn profile(
+        &self,
+        name: &str,
+        source: &Path,
+    ) -> Result<&BTreeMap<String, String>, BackupServiceError> {
+        self.profiles.get(name).ok_or_else(|| {
+            let known: Vec<&str> = self.profiles.keys().map(String::as_str).collect();
+            BackupServiceError::ConfigurationError(format!(
+                "Unknown profile {} in {}.\n\nDefined profiles: {}",
+                name,
+                source.display(),
+                if known.is_empty() {
+                    "none, add a [profiles.<name>] section".to_string()
+                } else {
+                    known.join(", ")
+                }
+            ))
+        })
+    }
+}
+
+/// Turn a TOML settings file into the env vars it stands for
+///
+/// Keys are the env var names in any case; a section prefixes its keys with its name, so
+/// `[notify] ntfy_url` is `NOTIFY_NTFY_URL`. Arrays become comma-separated lists and a
+/// table named after a map setting becomes its `key=value` entries. `[profiles.<name>]`
+/// sections follow the same rules.
+pub fn parse(content: &str, source: &Path) -> Result<ConfigFile, BackupServiceError> {
+    let mut table: Table = content.parse().map_err(|e| {
+        BackupServiceError::ConfigurationError(format!(
+            "Invalid config file {}: {}",
+            source.display(),
+            e
+        ))
+    })?;
+    let mut file = ConfigFile::default();
+    match table.remove("profiles") {
+        None => {}
+        Some(Value::Table(profiles)) => {
+            for (name, profile) in profiles {
+                let Value::Table(profile) = profile else {
+                    return Err(BackupServiceError::ConfigurationError(format!(
+                        "Invalid profile {} in {}: expected a [profiles.{}] section",
+                        name,
+                        source.display(),
+                        name
+                    )));
+                };
+                let mut settings = BTreeMap::new();
+                flatten("", &profile, &mut settings, source)?;
+                file.profiles.insert(name, settings);
+            }
+        }
+        Some(_) => {
+            return Err(BackupServiceError::ConfigurationError(format!(
+                "Invalid profiles in {}: expected [profiles.<name>] sections",
+                source.display()
+            )));
+        }
+    }
+    flatten("", &table, &mut file.settings, source)?;
+    Ok(file)
+}
+
+fn flatten(
+    prefix: &str,
+    table: &Table,
+    settings: &mut BTreeMap<String, String>,
+    source: &Path,
+) -> Result<(), BackupServiceError> {
+    for (key, value) in table {
+        let name = format!("{}{}", prefix, key.to_uppercase().replace(['-', '.'], "_"));
+        let invalid = |reason: &str| {
+            BackupServiceError::ConfigurationError(format!(
+                "Invalid setting {} in {}: {}",
+                name,
+                source.display(),
+                reason
+            ))
+        };
+        if SECRET_SETTINGS.contains(&name.as_str()) {
+            return Err(BackupServiceError::ConfigurationError(format!(
+                "{} must not be set in {}.\n\nKeep credentials in the env file or BACKUP_SECRETS_FILE, readable only by the service user",
+                name,
+                source.display()
+            )));
+        }
+        let rendered = match value {
+            Value::Table(inner) if MAP_SETTINGS.contains(&name.as_str()) => {
+                let separator = if SEMICOLON_MAP_SETTINGS.contains(&name.as_str()) {
+                    ";"
+                } else {
+                    ","
+                };
+                inner
+                    .iter()
+                    .map(|(k, v)| {
+                        Ok(format!(
+                            "{}={}",
+                            k,
+                            list(v).ok_or_else(|| invalid(
+                                "map values must be strings, numbers or lists"
+                            ))?
+                        ))
+                    })
+                    .collect::<Result<Vec<_>, BackupServiceError>>()?
+                    .join(separator)
+            }
+            Value::Table(inner) => {
+                flatten(&format!("{}_", name), inner, settings, source)?;
+                continue;
+            }
+            value => list(value)
+                .ok_or_else(|| invalid("expected a string, number, boolean or list of them"))?,
+        };
+        if settings.insert(name.clone(), rendered).is_some() {
+            return Err(invalid("set more than once"));
+        }
+    }
+    Ok(())
+}
+
+/// A scalar as its env text, an array of scalars comma-separated
+fn list(value: &Value) -> Option<String> {
+    match value {
+        Value::Array(items) => items
+            .iter()
+            .map(|item| match item {
+                Value::Array(_) => None,
+                item => scalar(item),
+            })
+            .collect::<Option<Vec<_>>>()
+            .map(|items| items.join(",")),
+        value => scalar(value),
+    }
+}
+
+fn scalar(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Integer(n) => Some(n.to_string()),
+        Value::Float(n) => Some(n.to_string()),
+        Value::Boolean(b) => Some(b.to_string()),
+        Value::Datetime(d) => Some(d.to_string()),
+        Value::Array(_) | Value::Table(_) => None,
+    }
+}
+
+/// The settings file in use: RBS_CONFIG_FILE, which must exist, else the first default found
+pub fn locate() -> Result<Option<PathBuf>, BackupServiceError> {
+    if let Some(path) = std::env::var(CONFIG_FILE_ENV_VAR)
+        .ok()
+        .filter(|v| !v.trim().is_empty())
+    {
+        let path = PathBuf::from(path.trim());
+        if !path.is_file() {
+            return Err(BackupServiceError::ConfigurationError(format!(
+                "{} points to {}, which does not exist.\n\nCreate the file or unset {}",
+                CONFIG_FILE_ENV_VAR,
+                path.display(),
+                CONFIG_FILE_ENV_VAR
+            )));
+        }
+        return Ok(Some(path));
+    }
+    Ok(DEFAULT_CONFIG_FILES
+        .iter()
+        .map(PathBuf::from)
+        .find(|path| path.is_file()))
+}
+
+/// `KEY=value` lines of an env file, taken literally; blank lines and `#` comments are skipped
+pub fn read_env_file(path: &Path) -> std::io::Result<Vec<(String, String)>> {
+    let content = std::fs::read_to_string(path)?;
+    Ok(content
+        .lines()
+        .filter(|line| {
+            let lead = line.trim_start();
+            !lead.is_empty() && !lead.starts_with('#')
+        })
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| {
+            (
+                key.trim().to_string(),
+                value.strip_suffix('\r').unwrap_or(value).to_string(),
+            )
+        })
+        .collect())
+}
+
+/// The selected profile (RBS_PROFILE, set by `--profile`), if any
+pub fn active_profile() -> Option<String> {
+    std::env::var(PROFILE_ENV_VAR)
+        .ok()
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+/// Environment changes of a settings file: variables removed first, then the ones set
+#[derive(Debug, Default, PartialEq)]
+struct EnvChanges {
+    removed: Vec<String>,
+    set: BTreeMap<String, String>,
+}
+
+/// What `apply` does to the environment, `is_set` telling which variables it already has
+///
+/// The profile's settings (and its secrets file's) are always set; when they name a
+/// password source, every other source is removed, including the file's shared one.
+/// Shared settings only fill in what is still unset.
+fn env_changes(
+    file: &ConfigFile,
+    profile: Option<&str>,
+    path: &Path,
+    is_set: impl Fn(&str) -> bool,
+) -> Result<EnvChanges, BackupServiceError> {
+    let mut changes = EnvChanges::default();
+    if let Some(name) = profile {
+        let mut overrides = file.profile(name, path)?.clone();
+        if let Some(secrets_file) = overrides.get("BACKUP_SECRETS_FILE").cloned() {
+            let secrets = read_env_file(Path::new(&secrets_file)).map_err(|e| {
+                BackupServiceError::ConfigurationError(format!(
+                    "Cannot read secrets file {} of profile {}: {}.\n\nCheck the path and that the current user may read it",
+                    secrets_file, name, e
+                ))
+            })?;
+            for (key, value) in secrets {
+                overrides.entry(key).or_insert(value);
+            }
+        }
+        if overrides
+            .keys()
+            .any(|key| PASSWORD_SETTINGS.contains(&key.as_str()))
+        {
+            changes.removed = PASSWORD_SETTINGS.iter().map(|k| k.to_string()).collect();
+        }
+        changes.set = overrides;
+    }
+    for (name, value) in &file.settings {
+        if !changes.set.contains_key(name) && !changes.removed.contains(name) && !is_set(name) {
+            changes.set.insert(name.clone(), value.clone());
+        }
+    }
+    Ok(changes)
+}
+
+/// Export the settings file's values, with the selected profile's on top
+///
+/// Precedence: command-line flags, then the profile (RBS_PROFILE / `--profile`), then the
+/// process environment and the env files (loaded into it at startup), then the shared
+/// settings of this file. A profile's `backup_secrets_file` is read as well, so each
+/// profile can bring its own credentials.
+pub fn apply() -> Result<Option<PathBuf>, BackupServiceError> {
+    let profile = active_profile();
+    let Some(path) = locate()? else {
+        return match profile {
+            Some(name) => Err(BackupServiceError::ConfigurationError(format!(
+                "Profile {} was selected, but there is no config file.\n\nDefine [profiles.{}] in ./config.toml, /etc/restic-backup/config.toml or the file named by {}",
+                name, name, CONFIG_FILE_ENV_VAR
+            ))),
+            None => Ok(None),
+        };
+    };
+    let content = std::fs::read_to_string(&path).map_err(|e| {
+        BackupServiceError::ConfigurationError(format!(
+            "Cannot read config file {}: {}.\n\nCheck the path and that the current user may read it",
+            path.display(),
+            e
+        ))
+    })?;
+    let file = parse(&content, &path)?;
+
+    let changes = env_changes(&file, profile.as_deref(), &path, |name| {
+        std::env::var_os(name).is_some()
+    })?;
+    // SAFETY: Called while loading the configuration, before any work is spawned.
+    unsafe {
+        for key in &changes.removed {
+            std::env::remove_var(key);
+        }
+        for (key, value) in &changes.set {
+            std::env::set_var(key, value);
+        }
+    }
+    if let Some(name) = &profile {
+        info!(profile = %name, file = %path.display(), "Using profile");
+    }
+    debug!(file = %path.display(), "Loaded config file");
+    Ok(Some(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_config_file() -> Result<(), BackupServiceError> {
+        let source = Path::new("config.toml");
+        let file = parse(
+            r#"
+                restic_repo_base = "s3:https://s3.example.com/backups"
+                backup_paths = ["/home", "/etc"]
+
+                [backup]
+                concurrency = 2
+                auto-unlock-stale = true
+
+                [backup_path_excludes]
+                "/home/tim" = ["node_modules", "*.iso"]
+                "/srv/media" = "@/etc/restic/media.exclude"
+
+                [retention.policy]
+                daily = 7
+                weekly = 4
+
+                [report]
+                email_to = "ops@example.com"
+
+                [profiles.work]
+                restic_repo_base = "sftp:backup@office-nas:/srv/restic"
+                backup_paths = ["/home/tim/work"]
+                backup_secrets_file = "/etc/restic-backup/work.env"
+
+                [profiles.work.retention]
+                policy = "daily=14"
+
+                [profiles.personal]
+                backup_paths = ["/home/tim/photos"]
+            "#,
+            source,
+        )?;
+        let settings = &file.settings;
+        assert_eq!(settings["BACKUP_PATHS"], "/home,/etc");
+        assert_eq!(settings["BACKUP_CONCURRENCY"], "2");
+        assert_eq!(settings["BACKUP_AUTO_UNLOCK_STALE"], "true");
+        assert_eq!(
+            settings["BACKUP_PATH_EXCLUDES"],
+            "/home/tim=node_modules,*.iso;/srv/media=@/etc/restic/media.exclude"
+        );
+        assert_eq!(settings["RETENTION_POLICY"], "daily=7,weekly=4");
+        assert_eq!(settings["REPORT_EMAIL_TO"], "ops@example.com");
+        assert_eq!(settings.len(), 7);
+
+        let work = file.profile("work", source)?;
+        assert_eq!(
+            work["RESTIC_REPO_BASE"],
+            "sftp:backup@office-nas:/srv/restic"
+        );
+        assert_eq!(work["BACKUP_PATHS"], "/home/tim/work");
+        assert_eq!(work["RETENTION_POLICY"], "daily=14");
+        assert_eq!(file.profiles.len(), 2);
+        assert!(file.profile("laptop", source).is_err());
+
+        assert!(parse("restic_password = \"hunter2\"", source).is_err());
+        assert!(parse("[profiles.work]\naws_secret_access_key = \"x\"", source).is_err());
+        assert!(parse("profiles = \"work\"", source).is_err());
+        assert!(parse("backup_paths = \"/a\"\n[backup]\npaths = \"/b\"", source).is_err());
+        assert!(parse("backup_paths = [[\"/a\"]]", source).is_err());
+        assert!(parse("backup_paths = ", source).is_err());
+
+        let dir = tempfile::tempdir()?;
+        let env_file = dir.path().join("work.env");
+        std::fs::write(
+            &env_file,
+            "# work repository\nRESTIC_PASSWORD=a=b\r\n\nAWS_ACCESS_KEY_ID=AK\n",
+        )?;
+        assert_eq!(
+            read_env_file(&env_file)?,
+            vec![
+                ("RESTIC_PASSWORD".to_string(), "a=b".to_string()),
+                ("AWS_ACCESS_KEY_ID".to_string(), "AK".to_string()),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_profile_precedence() -> Result<(), BackupServiceError> {
+        let source = Path::new("config.toml");
+        let file = parse(
+            r#"
+                restic_repo_base = "s3:https://s3.example.com/backups"
+                backup_paths = ["/home"]
+                hostname = "laptop"
+
+                [profiles.work]
+                restic_repo_base = "sftp:backup@office-nas:/srv/restic"
+            "#,
+            source,
+        )?;
+        // BACKUP_PATHS comes from the environment (or an env file)
+        let is_set = |name: &str| name == "BACKUP_PATHS";
+
+        let shared = env_changes(&file, None, source, is_set)?;
+        assert!(shared.removed.is_empty());
+        assert_eq!(
+            shared.set,
+            BTreeMap::from([
+                ("HOSTNAME".to_string(), "laptop".to_string()),
+                (
+                    "RESTIC_REPO_BASE".to_string(),
+                    "s3:https://s3.example.com/backups".to_string()
+                ),
+            ])
+        );
+
+        // The profile wins over the shared settings; the environment still beats those
+        let work = env_changes(&file, Some("work"), source, is_set)?;
+        assert_eq!(
+            work.set["RESTIC_REPO_BASE"],
+            "sftp:backup@office-nas:/srv/restic"
+        );
+        assert_eq!(work.set["HOSTNAME"], "laptop");
+        assert!(!work.set.contains_key("BACKUP_PATHS"));
+        // Set by the environment, but the profile names it: the profile wins
+        let work = env_changes(&file, Some("work"), source, |name| {
+            name == "RESTIC_REPO_BASE"
+        })?;
+        assert_eq!(
+            work.set["RESTIC_REPO_BASE"],
+            "sftp:backup@office-nas:/srv/restic"
+        );
+        assert!(env_changes(&file, Some("home"), source, is_set).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_profile_password_replaces_other_sources() -> Result<(), BackupServiceError> {
+        let dir = tempfile::tempdir()?;
+        let secrets = dir.path().join("work.env");
+        std::fs::write(&secrets, "RESTIC_PASSWORD=work-secret\n")?;
+        let source = Path::new("config.toml");
+        let file = parse(
+            &format!(
+                r#"
+                    restic_password_command = "pass show restic/home"
+
+                    [profiles.work]
+                    backup_secrets_file = "{}"
+
+                    [profiles.personal]
+                    restic_password_file = "/etc/restic-backup/personal.key"
+
+                    [profiles.plain]
+                    hostname = "nas"
+                "#,
+                secrets.display()
+            ),
+            source,
+        )?;
+        let removed: Vec<String> = PASSWORD_SETTINGS.iter().map(|k| k.to_string()).collect();
+        // The environment has a keyring password
+        let is_set = |name: &str| name == PASSWORD_KEYRING_ENV_VAR;
+
+        // A secrets file password removes the environment's and the shared command
+        let work = env_changes(&file, Some("work"), source, is_set)?;
+        assert_eq!(work.removed, removed);
+        assert_eq!(work.set["RESTIC_PASSWORD"], "work-secret");
+        assert!(!work.set.contains_key(PASSWORD_COMMAND_ENV_VAR));
+
+        let personal = env_changes(&file, Some("personal"), source, is_set)?;
+        assert_eq!(personal.removed, removed);
+        assert_eq!(
+            personal.set[PASSWORD_FILE_ENV_VAR],
+            "/etc/restic-backup/personal.key"
+        );
+        assert!(!personal.set.contains_key(PASSWORD_COMMAND_ENV_VAR));
+
+        // A profile without a password keeps the existing sources
+        let plain = env_changes(&file, Some("plain"), source, is_set)?;
+        assert!(plain.removed.is_empty());
+        assert_eq!(plain.set[PASSWORD_COMMAND_ENV_VAR], "pass show restic/home");
+        Ok(())
+    }
+}
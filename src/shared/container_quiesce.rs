@@ -0,0 +1,364 @@
+use crate::errors::BackupServiceError;
+use crate::shared::commands::with_timeout;
+use crate::shared::container_verify::{docker_async, volume_names};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Duration;
+use tracing::{error, info};
+
+/// Env var selecting what happens to containers using a volume while it is backed up
+pub const DOCKER_QUIESCE_ENV_VAR: &str = "BACKUP_DOCKER_QUIESCE";
+
+/// How running containers are kept from writing to a docker volume during its backup
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum QuiesceMode {
+    /// Back up the live volume (default)
+    #[default]
+    Off,
+    /// `docker pause`: processes are frozen, nothing is flushed, resuming is instant
+    Pause,
+    /// `docker stop`: clean shutdown (databases flush to disk), restarted afterwards
+    Stop,
+}
+
+impl QuiesceMode {
+    pub fn parse(value: &str) -> Result<Self, BackupServiceError> {
+        match value.trim().to_lowercase().as_str() {
+            "" | "off" | "none" => Ok(QuiesceMode::Off),
+            "pause" => Ok(QuiesceMode::Pause),
+            "stop" => Ok(QuiesceMode::Stop),
+            _ => Err(BackupServiceError::ConfigurationError(format!(
+                "Unknown docker quiesce mode: {}.\n\nValid modes for --docker-quiesce and {} are: off, pause, stop",
+                value, DOCKER_QUIESCE_ENV_VAR
+            ))),
+        }
+    }
+
+    /// `--docker-quiesce` when given, else BACKUP_DOCKER_QUIESCE
+    pub fn resolve(flag: Option<&str>) -> Result<Self, BackupServiceError> {
+        match flag {
+            Some(value) => Self::parse(value),
+            None => Self::parse(&std::env::var(DOCKER_QUIESCE_ENV_VAR).unwrap_or_default()),
+        }
+    }
+
+    /// docker subcommands that quiesce and resume a container
+    fn commands(&self) -> Option<(&'static str, &'static str)> {
+        match self {
+            QuiesceMode::Off => None,
+            QuiesceMode::Pause => Some(("pause", "unpause")),
+            QuiesceMode::Stop => Some(("stop", "start")),
+        }
+    }
+}
+
+/// Seconds one docker call may take; `docker stop` itself gives a container 10s
+const DOCKER_TIMEOUT_SECS: u64 = 120;
+
+/// The docker calls quiescing makes, so tests can stand in for the daemon
+pub trait DockerClient: Sync {
+    /// Run `docker <args>` and return its stdout
+    fn run(&self, args: &[&str])
+    -> impl Future<Output = Result<String, BackupServiceError>> + Send;
+}
+
+/// The `docker` CLI, like `container_verify`
+pub struct DockerCli;
+
+impl DockerClient for DockerCli {
+    async fn run(&self, args: &[&str]) -> Result<String, BackupServiceError> {
+        with_timeout(
+            docker_async(args),
+            Some(Duration::from_secs(DOCKER_TIMEOUT_SECS)),
+            &format!("docker {}", args.first().copied().unwrap_or_default()),
+        )
+        .await?
+    }
+}
+
+/// Containers paused or stopped right now, with the subcommand that resumes them
+///
+/// An interrupt that exits at once skips every `Drop`; `resume_registered` runs first.
+static QUIESCED: Mutex<Vec<(String, &'static str)>> = Mutex::new(Vec::new());
+
+fn register(name: &str, resume_command: &'static str) {
+    if let Ok(mut quiesced) = QUIESCED.lock() {
+        quiesced.push((name.to_string(), resume_command));
+    }
+}
+
+fn unregister(name: &str) {
+    if let Ok(mut quiesced) = QUIESCED.lock()
+        && let Some(index) = quiesced.iter().position(|(n, _)| n == name)
+    {
+        quiesced.remove(index);
+    }
+}
+
+/// Resume every container still registered as quiesced; used before exiting on Ctrl-C
+pub async fn resume_registered() {
+    let quiesced = QUIESCED
+        .lock()
+        .map(|mut q| std::mem::take(&mut *q))
+        .unwrap_or_default();
+    for (name, resume_command) in quiesced {
+        match DockerCli.run(&[resume_command, &name]).await {
+            Ok(_) => info!(container = %name, action = %resume_command, "Resumed container"),
+            Err(e) => {
+                error!(container = %name, error = %e, "Could not resume container after the volume backup")
+            }
+        }
+    }
+}
+
+/// Names of the running containers that mount the volume
+async fn running_containers_using(
+    docker: &impl DockerClient,
+    volume: &str,
+) -> Result<Vec<String>, BackupServiceError> {
+    let filter = format!("volume={}", volume);
+    Ok(docker
+        .run(&[
+            "ps",
+            "--filter",
+            &filter,
+            "--filter",
+            "status=running",
+            "--format",
+            "{{.Names}}",
+        ])
+        .await?
+        .lines()
+        .map(str::to_string)
+        .collect())
+}
+
+/// Containers paused or stopped for one volume's backup
+///
+/// They are resumed by `resume`. Until then they are registered for `resume_registered`,
+/// and dropping the set early (an error, a cancelled backup) resumes them in the
+/// background, so nothing ever leaves a service down.
+#[derive(Debug, Default)]
+pub struct QuiescedContainers {
+    resume_command: &'static str,
+    containers: Vec<String>,
+}
+
+impl QuiescedContainers {
+    /// Quiesce every running container using the docker volume at `path`
+    ///
+    /// Paths that are not a single volume (e.g. the whole volumes directory) are left alone.
+    pub async fn quiesce(
+        docker: &impl DockerClient,
+        path: &Path,
+        mode: QuiesceMode,
+    ) -> Result<Self, BackupServiceError> {
+        let Some((quiesce_command, resume_command)) = mode.commands() else {
+            return Ok(Self::default());
+        };
+        let Some(volume) = volume_names(&[path.to_path_buf()]).into_iter().next() else {
+            return Ok(Self::default());
+        };
+
+        let mut quiesced = Self {
+            resume_command,
+            containers: Vec::new(),
+        };
+        for name in running_containers_using(docker, &volume).await? {
+            info!(container = %name, volume = %volume, action = %quiesce_command, "Quiescing container for the volume backup");
+            // Registered first: an interrupt during the call must still resume it
+            register(&name, resume_command);
+            if let Err(e) = docker.run(&[quiesce_command, &name]).await {
+                unregister(&name);
+                // The containers handled so far come back before the error is reported
+                quiesced.resume(docker).await;
+                return Err(e);
+            }
+            quiesced.containers.push(name);
+        }
+        Ok(quiesced)
+    }
+
+    /// Containers currently paused or stopped
+    pub fn containers(&self) -> &[String] {
+        &self.containers
+    }
+
+    /// Resume the containers; returns the ones that did not come back
+    pub async fn resume(mut self, docker: &impl DockerClient) -> Vec<String> {
+        let mut failed = Vec::new();
+        for name in std::mem::take(&mut self.containers) {
+            match docker.run(&[self.resume_command, &name]).await {
+                Ok(_) => {
+                    info!(container = %name, action = %self.resume_command, "Resumed container")
+                }
+                Err(e) => {
+                    error!(container = %name, error = %e, "Could not resume container after the volume backup");
+                    failed.push(name.clone());
+                }
+            }
+            unregister(&name);
+        }
+        failed
+    }
+}
+
+impl Drop for QuiescedContainers {
+    fn drop(&mut self) {
+        if self.containers.is_empty() {
+            return;
+        }
+        if let Ok(handle) = tokio::runtime::Handle::try_current() {
+            let containers = std::mem::take(&mut self.containers);
+            let resume_command = self.resume_command;
+            handle.spawn(async move {
+                Self {
+                    resume_command,
+                    containers,
+                }
+                .resume(&DockerCli)
+                .await
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Records every docker call; `ps` lists `running`, and `fail` makes one call fail
+    #[derive(Default)]
+    struct FakeDocker {
+        running: Vec<&'static str>,
+        fail: Option<&'static str>,
+        calls: Mutex<Vec<String>>,
+    }
+
+    impl DockerClient for FakeDocker {
+        async fn run(&self, args: &[&str]) -> Result<String, BackupServiceError> {
+            let call = args.join(" ");
+            self.calls.lock().unwrap().push(call.clone());
+            if self.fail == Some(call.as_str()) {
+                return Err(BackupServiceError::CommandFailed(format!(
+                    "docker {} failed",
+                    call
+                )));
+            }
+            Ok(match args.first() {
+                Some(&"ps") => self.running.join("\n"),
+                _ => String::new(),
+            })
+        }
+    }
+
+    impl FakeDocker {
+        fn calls(&self) -> Vec<String> {
+            self.calls.lock().unwrap().clone()
+        }
+    }
+
+    fn registered(name: &str) -> bool {
+        QUIESCED.lock().unwrap().iter().any(|(n, _)| n == name)
+    }
+
+    #[test]
+    fn test_quiesce_mode() -> Result<(), BackupServiceError> {
+        assert_eq!(QuiesceMode::parse("")?, QuiesceMode::Off);
+        assert_eq!(QuiesceMode::parse("Pause")?, QuiesceMode::Pause);
+        assert_eq!(QuiesceMode::resolve(Some("stop"))?, QuiesceMode::Stop);
+        assert!(QuiesceMode::parse("freeze").is_err());
+        assert_eq!(QuiesceMode::Stop.commands(), Some(("stop", "start")));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_quiesce_off_and_non_volume_paths_touch_nothing() -> Result<(), BackupServiceError>
+    {
+        let docker = FakeDocker {
+            running: vec!["off-app"],
+            ..FakeDocker::default()
+        };
+        let path = Path::new("/mnt/docker-data/volumes/nextcloud_db");
+        let quiesced = QuiescedContainers::quiesce(&docker, path, QuiesceMode::Off).await?;
+        assert!(quiesced.resume(&docker).await.is_empty());
+        let volumes_dir = Path::new("/mnt/docker-data/volumes");
+        let quiesced =
+            QuiescedContainers::quiesce(&docker, volumes_dir, QuiesceMode::Pause).await?;
+        assert!(quiesced.resume(&docker).await.is_empty());
+        assert!(docker.calls().is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_quiesce_pause_and_resume() -> Result<(), BackupServiceError> {
+        let docker = FakeDocker {
+            running: vec!["pause-db", "pause-app"],
+            ..FakeDocker::default()
+        };
+        let path = Path::new("/mnt/docker-data/volumes/nextcloud_db");
+        let quiesced = QuiescedContainers::quiesce(&docker, path, QuiesceMode::Pause).await?;
+        assert_eq!(quiesced.containers(), ["pause-db", "pause-app"]);
+        // Registered so an interrupt that exits at once still unpauses them
+        assert!(registered("pause-db") && registered("pause-app"));
+
+        assert!(quiesced.resume(&docker).await.is_empty());
+        assert!(!registered("pause-db") && !registered("pause-app"));
+        assert_eq!(
+            docker.calls(),
+            [
+                "ps --filter volume=nextcloud_db --filter status=running --format {{.Names}}",
+                "pause pause-db",
+                "pause pause-app",
+                "unpause pause-db",
+                "unpause pause-app",
+            ]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_quiesce_stop_reports_containers_not_started() -> Result<(), BackupServiceError> {
+        let docker = FakeDocker {
+            running: vec!["stop-db", "stop-app"],
+            fail: Some("start stop-app"),
+            ..FakeDocker::default()
+        };
+        let path = Path::new("/mnt/docker-data/volumes/gitea_data");
+        let quiesced = QuiescedContainers::quiesce(&docker, path, QuiesceMode::Stop).await?;
+        assert_eq!(quiesced.containers(), ["stop-db", "stop-app"]);
+
+        assert_eq!(quiesced.resume(&docker).await, ["stop-app"]);
+        assert!(!registered("stop-db") && !registered("stop-app"));
+        assert_eq!(
+            docker.calls()[1..],
+            [
+                "stop stop-db",
+                "stop stop-app",
+                "start stop-db",
+                "start stop-app"
+            ]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_failed_quiesce_resumes_the_containers_handled_so_far() {
+        let docker = FakeDocker {
+            running: vec!["fail-db", "fail-app"],
+            fail: Some("pause fail-app"),
+            ..FakeDocker::default()
+        };
+        let path = Path::new("/mnt/docker-data/volumes/immich_db");
+        assert!(
+            QuiescedContainers::quiesce(&docker, path, QuiesceMode::Pause)
+                .await
+                .is_err()
+        );
+        assert!(!registered("fail-db") && !registered("fail-app"));
+        assert_eq!(
+            docker.calls()[1..],
+            ["pause fail-db", "pause fail-app", "unpause fail-db"]
+        );
+    }
+}
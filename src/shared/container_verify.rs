@@ -0,0 +1,324 @@
+use crate::errors::BackupServiceError;
+use crate::shared::constants::DOCKER_VOLUMES_DIR;
+use crate::shared::http;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::process::Output;
+use std::time::{Duration, Instant};
+use tracing::{error, info, warn};
+
+/// Env var with HTTP probes for containers without a Docker healthcheck: `name=url,name=url`
+pub const VERIFY_PROBES_ENV_VAR: &str = "RESTORE_VERIFY_PROBES";
+/// Env var with the seconds a container gets to come up (default 120)
+pub const VERIFY_TIMEOUT_ENV_VAR: &str = "RESTORE_VERIFY_TIMEOUT";
+
+const DEFAULT_VERIFY_TIMEOUT_SECS: u64 = 120;
+/// Without healthcheck or probe, a container that still runs after this long counts as up
+const GRACE_PERIOD: Duration = Duration::from_secs(15);
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// How a container came up after its volumes were restored
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum ContainerHealth {
+    /// Docker healthcheck reported healthy
+    Healthy,
+    /// The configured HTTP probe answered with a success status
+    ProbeOk {
+        url: String,
+    },
+    /// No healthcheck or probe; still running after the grace period
+    Running,
+    Unhealthy {
+        reason: String,
+    },
+}
+
+impl ContainerHealth {
+    pub fn is_ok(&self) -> bool {
+        !matches!(self, ContainerHealth::Unhealthy { .. })
+    }
+}
+
+/// Docker volume names of restored paths (`<volumes dir>/<name>/...`)
+pub fn volume_names(paths: &[PathBuf]) -> Vec<String> {
+    let mut names: Vec<String> = paths
+        .iter()
+        .filter_map(|p| p.strip_prefix(DOCKER_VOLUMES_DIR).ok())
+        .filter_map(|rest| rest.components().next())
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+pub fn parse_probes(value: &str) -> Result<BTreeMap<String, String>, BackupServiceError> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|e| !e.is_empty())
+        .map(|entry| match entry.split_once('=') {
+            Some((name, url)) if !name.trim().is_empty() && url.trim().starts_with("http") => {
+                Ok((name.trim().to_string(), url.trim().to_string()))
+            }
+            _ => Err(BackupServiceError::ConfigurationError(format!(
+                "Invalid {} entry: {}.\n\nUse container=url, e.g. {}=\"nextcloud=http://localhost:8080/status.php\"",
+                VERIFY_PROBES_ENV_VAR, entry, VERIFY_PROBES_ENV_VAR
+            ))),
+        })
+        .collect()
+}
+
+fn verify_timeout() -> Duration {
+    Duration::from_secs(
+        std::env::var(VERIFY_TIMEOUT_ENV_VAR)
+            .ok()
+            .and_then(|v| v.trim().parse().ok())
+            .unwrap_or(DEFAULT_VERIFY_TIMEOUT_SECS),
+    )
+}
+
+/// Verdict from one `docker inspect` `.State`, or None while still starting
+///
+/// `waited_past_grace` only matters for containers without a healthcheck or probe.
+pub fn assess_state(
+    state: &Value,
+    has_probe: bool,
+    waited_past_grace: bool,
+) -> Option<ContainerHealth> {
+    match state["Status"].as_str().unwrap_or_default() {
+        "exited" | "dead" => {
+            return Some(ContainerHealth::Unhealthy {
+                reason: format!(
+                    "exited with code {}",
+                    state["ExitCode"].as_i64().unwrap_or(-1)
+                ),
+            });
+        }
+        "running" => {}
+        // created, restarting, paused: keep waiting until the timeout
+        _ => return None,
+    }
+    match state["Health"]["Status"].as_str() {
+        Some("healthy") => Some(ContainerHealth::Healthy),
+        Some("unhealthy") => Some(ContainerHealth::Unhealthy {
+            reason: state["Health"]["Log"]
+                .as_array()
+                .and_then(|log| log.last())
+                .and_then(|last| last["Output"].as_str())
+                .map(|out| format!("healthcheck failed: {}", out.trim()))
+                .unwrap_or_else(|| "healthcheck failed".to_string()),
+        }),
+        Some(_) => None,
+        None if !has_probe && waited_past_grace => Some(ContainerHealth::Running),
+        None => None,
+    }
+}
+
+/// Run docker and return its stdout, without blocking the runtime
+pub(crate) async fn docker_async(args: &[&str]) -> Result<String, BackupServiceError> {
+    let output = tokio::process::Command::new("docker")
+        .args(args)
+        .kill_on_drop(true)
+        .output()
+        .await
+        .map_err(|_| BackupServiceError::CommandNotFound("Failed to execute docker".to_string()))?;
+    docker_stdout(args, output)
+}
+
+fn docker_stdout(args: &[&str], output: Output) -> Result<String, BackupServiceError> {
+    if !output.status.success() {
+        return Err(BackupServiceError::CommandFailed(format!(
+            "docker {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Names of all containers (running or not) that mount the volume
+async fn containers_using(volume: &str) -> Result<Vec<String>, BackupServiceError> {
+    let filter = format!("volume={}", volume);
+    Ok(
+        docker_async(&["ps", "-a", "--filter", &filter, "--format", "{{.Names}}"])
+            .await?
+            .lines()
+            .map(str::to_string)
+            .collect(),
+    )
+}
+
+async fn http_probe(url: &str) -> bool {
+    // Polled in a loop, so each probe gets a short bound and no retries of its own
+    let args = [
+        "--output",
+        "/dev/null",
+        "--max-time",
+        "5",
+        "--retry",
+        "0",
+        url,
+    ]
+    .map(String::from);
+    http::curl(&args, "", "Container health probe")
+        .await
+        .is_ok_and(|output| output.status.success())
+}
+
+/// (Re)start a container and wait until it is healthy, fails, or the timeout passes
+async fn verify_container(name: &str, probe: Option<&str>, timeout: Duration) -> ContainerHealth {
+    if let Err(e) = docker_async(&["restart", name]).await {
+        return ContainerHealth::Unhealthy {
+            reason: e.to_string(),
+        };
+    }
+    let started = Instant::now();
+    loop {
+        tokio::time::sleep(POLL_INTERVAL).await;
+        let state = docker_async(&["inspect", "--format", "{{json .State}}", name])
+            .await
+            .ok()
+            .and_then(|out| serde_json::from_str::<Value>(&out).ok())
+            .unwrap_or_default();
+        if let Some(verdict) =
+            assess_state(&state, probe.is_some(), started.elapsed() >= GRACE_PERIOD)
+        {
+            return verdict;
+        }
+        if let Some(url) = probe
+            && state["Status"] == "running"
+            && http_probe(url).await
+        {
+            return ContainerHealth::ProbeOk {
+                url: url.to_string(),
+            };
+        }
+        if started.elapsed() >= timeout {
+            let status = state["Status"].as_str().unwrap_or("unknown");
+            return ContainerHealth::Unhealthy {
+                reason: match probe {
+                    Some(url) => format!(
+                        "{} did not answer within {}s ({})",
+                        url,
+                        timeout.as_secs(),
+                        status
+                    ),
+                    None => format!("not healthy within {}s ({})", timeout.as_secs(), status),
+                },
+            };
+        }
+    }
+}
+
+/// Start the containers using restored docker volumes and check they come up
+///
+/// Returns (container, result) pairs; restores without docker volumes return nothing.
+pub async fn verify_restored_volumes(
+    restored: &[PathBuf],
+) -> Result<Vec<(String, ContainerHealth)>, BackupServiceError> {
+    let volumes = volume_names(restored);
+    if volumes.is_empty() {
+        info!("No docker volumes restored, skipping container verification");
+        return Ok(Vec::new());
+    }
+    let probes = parse_probes(&std::env::var(VERIFY_PROBES_ENV_VAR).unwrap_or_default())?;
+    let timeout = verify_timeout();
+
+    let mut containers: Vec<String> = Vec::new();
+    for volume in &volumes {
+        let users = containers_using(volume).await?;
+        if users.is_empty() {
+            warn!(volume = %volume, "No container uses this restored volume");
+        }
+        containers.extend(users);
+    }
+    containers.sort();
+    containers.dedup();
+
+    let mut results = Vec::new();
+    for name in containers {
+        info!(container = %name, "Starting container to verify restored data");
+        let health = verify_container(&name, probes.get(&name).map(String::as_str), timeout).await;
+        match &health {
+            ContainerHealth::Unhealthy { reason } => {
+                error!(container = %name, reason = %reason, "Container did not come up with the restored data")
+            }
+            ok => {
+                info!(container = %name, result = ?ok, "Container came up with the restored data")
+            }
+        }
+        results.push((name, health));
+    }
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_volume_names() {
+        let paths = vec![
+            PathBuf::from("/mnt/docker-data/volumes/nextcloud_db/_data"),
+            PathBuf::from("/mnt/docker-data/volumes/nextcloud_db"),
+            PathBuf::from("/mnt/docker-data/volumes/gitea"),
+            PathBuf::from("/home/tim"),
+        ];
+        assert_eq!(volume_names(&paths), vec!["gitea", "nextcloud_db"]);
+    }
+
+    #[test]
+    fn test_assess_state() {
+        let exited = json!({"Status": "exited", "ExitCode": 1});
+        assert_eq!(
+            assess_state(&exited, false, false),
+            Some(ContainerHealth::Unhealthy {
+                reason: "exited with code 1".to_string()
+            })
+        );
+
+        let starting = json!({"Status": "running", "Health": {"Status": "starting"}});
+        assert_eq!(assess_state(&starting, false, true), None);
+        let healthy = json!({"Status": "running", "Health": {"Status": "healthy"}});
+        assert_eq!(
+            assess_state(&healthy, true, false),
+            Some(ContainerHealth::Healthy)
+        );
+        let unhealthy = json!({"Status": "running", "Health": {"Status": "unhealthy", "Log": [{"Output": "db missing\n"}]}});
+        assert_eq!(
+            assess_state(&unhealthy, false, false),
+            Some(ContainerHealth::Unhealthy {
+                reason: "healthcheck failed: db missing".to_string()
+            })
+        );
+
+        // No healthcheck: running counts after the grace period unless a probe decides
+        let running = json!({"Status": "running"});
+        assert_eq!(assess_state(&running, false, false), None);
+        assert_eq!(
+            assess_state(&running, false, true),
+            Some(ContainerHealth::Running)
+        );
+        assert_eq!(assess_state(&running, true, true), None);
+        assert_eq!(
+            assess_state(&json!({"Status": "restarting"}), false, true),
+            None
+        );
+    }
+
+    #[test]
+    fn test_parse_probes() -> Result<(), BackupServiceError> {
+        let probes =
+            parse_probes("nextcloud=http://localhost:8080/status.php, gitea = https://git.local/")?;
+        assert_eq!(probes["nextcloud"], "http://localhost:8080/status.php");
+        assert_eq!(probes["gitea"], "https://git.local/");
+        assert!(parse_probes("nextcloud").is_err());
+        assert!(parse_probes("nextcloud=localhost:8080").is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,488 @@
+use crate::config::Config;
+use crate::errors::BackupServiceError;
+use crate::shared::commands::{command_timeout, with_timeout};
+use crate::shared::constants::{CATEGORY_DOCKER_VOLUME, CATEGORY_SYSTEM, CATEGORY_USER_HOME};
+use crate::shared::display::DisplayFormatter;
+use crate::shared::operations::RepositoryOperations;
+use crate::shared::paths::{PathMapper, PathUtilities};
+use crate::shared::sensitive_paths::sensitive_paths;
+use crate::utils::{format_bytes, parse_size, validate_credentials};
+use serde::Serialize;
+use serde_json::json;
+use std::cell::RefCell;
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tokio::process::Command;
+use tracing::{error, info};
+
+/// System directories checked when COVERAGE_SYSTEM_PATHS is not set
+const DEFAULT_SYSTEM_PATHS: &[&str] = &[
+    "/etc",
+    "/root",
+    "/srv",
+    "/opt",
+    "/usr/local",
+    "/var/lib",
+    "/var/www",
+];
+
+/// Unprotected directories smaller than this are not listed (COVERAGE_MIN_SIZE overrides)
+const DEFAULT_MIN_GAP_SIZE: u64 = 1 << 30;
+
+/// `report coverage` options
+#[derive(Debug, Clone, Default)]
+pub struct CoverageOptions {
+    /// Smallest unprotected directory worth listing (e.g. 500M)
+    pub min_size: Option<String>,
+    pub json_output: bool,
+}
+
+/// How well a directory on disk is protected
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CoverageStatus {
+    /// A repository exists for the directory or one of its parents
+    BackedUp,
+    /// Listed in the configuration, but no repository exists yet
+    ConfiguredOnly,
+    /// Some subdirectories are backed up, others are not
+    Partial,
+    Unprotected,
+}
+
+impl CoverageStatus {
+    fn label(&self) -> &'static str {
+        match self {
+            CoverageStatus::BackedUp => "backed up",
+            CoverageStatus::ConfiguredOnly => "configured",
+            CoverageStatus::Partial => "partial",
+            CoverageStatus::Unprotected => "unprotected",
+        }
+    }
+}
+
+/// One top-level directory that should normally be protected
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CoverageEntry {
+    pub path: PathBuf,
+    pub category: &'static str,
+    pub status: CoverageStatus,
+    /// Bytes on disk (None when `du` could not read it)
+    pub size: Option<u64>,
+    /// Bytes of it that are backed up
+    pub covered: u64,
+}
+
+/// A directory without any backup
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CoverageGap {
+    pub path: PathBuf,
+    pub size: u64,
+    /// Configured but never backed up (first run pending or failing)
+    pub configured: bool,
+}
+
+/// What is configured and what actually has a repository
+#[derive(Debug, Clone, Default)]
+pub struct Protection {
+    /// Repository subpaths that exist for the host
+    pub repos: BTreeSet<String>,
+    /// Configured, docker volume and sensitive paths
+    pub configured: Vec<PathBuf>,
+}
+
+impl Protection {
+    /// Whether a repository exists for `path` or a parent of it
+    pub fn is_backed_up(&self, path: &Path) -> bool {
+        path.ancestors().any(|ancestor| {
+            PathMapper::candidate_subpaths(ancestor)
+                .is_ok_and(|candidates| candidates.iter().any(|s| self.repos.contains(s)))
+        })
+    }
+
+    pub fn is_configured(&self, path: &Path) -> bool {
+        self.configured.iter().any(|c| path.starts_with(c))
+    }
+
+    /// Configured paths strictly inside `path`
+    fn configured_inside<'a>(&'a self, path: &'a Path) -> impl Iterator<Item = &'a PathBuf> {
+        self.configured
+            .iter()
+            .filter(move |c| c.starts_with(path) && c.as_path() != path)
+    }
+}
+
+/// Classify one root; partially protected roots are broken down by immediate subdirectory
+pub fn assess_root(
+    root: &Path,
+    category: &'static str,
+    protection: &Protection,
+    size_of: &dyn Fn(&Path) -> Option<u64>,
+    children_of: &dyn Fn(&Path) -> Vec<PathBuf>,
+) -> (CoverageEntry, Vec<CoverageGap>) {
+    let size = size_of(root);
+    let mut entry = CoverageEntry {
+        path: root.to_path_buf(),
+        category,
+        status: CoverageStatus::Unprotected,
+        size,
+        covered: 0,
+    };
+
+    if protection.is_backed_up(root) {
+        entry.status = CoverageStatus::BackedUp;
+        entry.covered = size.unwrap_or_default();
+        return (entry, Vec::new());
+    }
+    if protection.is_configured(root) {
+        entry.status = CoverageStatus::ConfiguredOnly;
+        let gap = CoverageGap {
+            path: root.to_path_buf(),
+            size: size.unwrap_or_default(),
+            configured: true,
+        };
+        return (entry, vec![gap]);
+    }
+
+    let mut gaps = Vec::new();
+    for child in children_of(root) {
+        if protection.is_backed_up(&child) {
+            entry.covered += size_of(&child).unwrap_or_default();
+            continue;
+        }
+        // Deeper configured paths: count what is backed up, don't report the child itself
+        let inside: Vec<&PathBuf> = protection.configured_inside(&child).collect();
+        if !inside.is_empty() {
+            entry.covered += inside
+                .iter()
+                .filter(|p| protection.is_backed_up(p))
+                .filter_map(|p| size_of(p))
+                .sum::<u64>();
+            continue;
+        }
+        gaps.push(CoverageGap {
+            size: size_of(&child).unwrap_or_default(),
+            configured: protection.is_configured(&child),
+            path: child,
+        });
+    }
+
+    if entry.covered > 0 {
+        entry.status = CoverageStatus::Partial;
+        (entry, gaps)
+    } else {
+        let gap = CoverageGap {
+            path: root.to_path_buf(),
+            size: size.unwrap_or_default(),
+            configured: false,
+        };
+        (entry, vec![gap])
+    }
+}
+
+/// Percentage of the assessed bytes that are backed up
+pub fn coverage_score(entries: &[CoverageEntry]) -> f64 {
+    let total: u64 = entries.iter().filter_map(|e| e.size).sum();
+    let covered: u64 = entries.iter().map(|e| e.covered).sum();
+    if total == 0 {
+        return 100.0;
+    }
+    covered.min(total) as f64 * 100.0 / total as f64
+}
+
+/// Apparent size via `du -sbx` (stays on the directory's filesystem), bounded like a
+/// restic metadata command
+pub async fn disk_usage(path: &Path) -> Option<u64> {
+    let child = Command::new("du")
+        .arg("-sbx")
+        .arg(path)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()
+        .ok()?;
+    let context = format!("du -sbx {}", path.display());
+    let output = with_timeout(
+        child.wait_with_output(),
+        command_timeout("du").ok()?,
+        &context,
+    )
+    .await
+    .ok()?
+    .ok()?;
+    // du exits non-zero on unreadable subdirectories but still prints a total
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .last()?
+        .split_whitespace()
+        .next()?
+        .parse()
+        .ok()
+}
+
+fn subdirectories(path: &Path) -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = std::fs::read_dir(path)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok().map(|e| e.path()))
+                .filter(|p| p.is_dir() && !p.is_symlink())
+                .collect()
+        })
+        .unwrap_or_default();
+    dirs.sort();
+    dirs
+}
+
+/// Directories that exist on this machine and would normally need a backup
+fn candidate_roots() -> Result<Vec<(PathBuf, &'static str)>, BackupServiceError> {
+    let mut roots: Vec<(PathBuf, &'static str)> = subdirectories(Path::new("/home"))
+        .into_iter()
+        .map(|p| (p, CATEGORY_USER_HOME))
+        .collect();
+    roots.extend(
+        PathUtilities::discover_docker_volumes()?
+            .into_iter()
+            .map(|p| (p, CATEGORY_DOCKER_VOLUME)),
+    );
+
+    let system: Vec<PathBuf> = match std::env::var("COVERAGE_SYSTEM_PATHS") {
+        Ok(v) if !v.trim().is_empty() => v
+            .split(',')
+            .map(str::trim)
+            .filter(|p| !p.is_empty())
+            .map(|p| PathBuf::from(p.trim_end_matches('/')))
+            .collect(),
+        _ => DEFAULT_SYSTEM_PATHS.iter().map(PathBuf::from).collect(),
+    };
+    roots.extend(
+        system
+            .into_iter()
+            .filter(|p| p.is_dir())
+            .map(|p| (p, CATEGORY_SYSTEM)),
+    );
+    Ok(roots)
+}
+
+fn min_gap_size(options: &CoverageOptions) -> Result<u64, BackupServiceError> {
+    let env = std::env::var("COVERAGE_MIN_SIZE")
+        .ok()
+        .filter(|v| !v.trim().is_empty());
+    match options.min_size.as_ref().or(env.as_ref()) {
+        Some(value) => parse_size(value),
+        None => Ok(DEFAULT_MIN_GAP_SIZE),
+    }
+}
+
+/// `report coverage`: compare what is on disk with what is configured and backed up
+pub async fn execute_coverage(
+    config: Config,
+    options: CoverageOptions,
+) -> Result<(), BackupServiceError> {
+    let min_size = min_gap_size(&options)?;
+    config.set_aws_env()?;
+    validate_credentials(&config).await?;
+
+    let operations = RepositoryOperations::new(config.clone())?;
+    let discovery = operations
+        .discover_all_repositories(&config.hostname)
+        .await?;
+    for failure in &discovery.failures {
+        error!(failure = %failure, "Repository discovery incomplete; coverage may be understated");
+    }
+
+    let mut configured = PathUtilities::configured_paths(&config);
+    configured.extend(PathUtilities::discover_docker_volumes()?);
+    configured.extend(sensitive_paths());
+    let protection = Protection {
+        repos: discovery
+            .repos
+            .into_iter()
+            .map(|r| r.repo_subpath)
+            .collect(),
+        configured,
+    };
+
+    info!("Measuring directories on disk...");
+    let mut entries = Vec::new();
+    let mut gaps = Vec::new();
+    for (root, category) in candidate_roots()? {
+        // The walk does not depend on sizes: a first pass collects the paths to measure,
+        // du runs for them, and the second pass classifies with the results
+        let wanted = RefCell::new(Vec::new());
+        let record = |p: &Path| {
+            wanted.borrow_mut().push(p.to_path_buf());
+            None
+        };
+        assess_root(&root, category, &protection, &record, &subdirectories);
+        let mut sizes = HashMap::new();
+        for path in wanted.into_inner() {
+            if let Some(size) = disk_usage(&path).await {
+                sizes.insert(path, size);
+            }
+        }
+        let size_of = |p: &Path| sizes.get(p).copied();
+        let (entry, root_gaps) =
+            assess_root(&root, category, &protection, &size_of, &subdirectories);
+        entries.push(entry);
+        gaps.extend(root_gaps);
+    }
+    gaps.retain(|g| g.size >= min_size);
+    gaps.sort_by_key(|g| std::cmp::Reverse(g.size));
+    let score = coverage_score(&entries);
+
+    if options.json_output {
+        let output = json!({
+            "host": config.hostname,
+            "score": (score * 10.0).round() / 10.0,
+            "min_gap_size": min_size,
+            "directories": entries,
+            "gaps": gaps,
+            "discovery_failures": discovery.failures,
+        });
+        DisplayFormatter::print_json(&output)?;
+        return Ok(());
+    }
+
+    let total: u64 = entries.iter().filter_map(|e| e.size).sum();
+    let covered: u64 = entries.iter().map(|e| e.covered).sum();
+    info!(
+        "Coverage for {}: {:.1}% ({} of {} backed up)",
+        config.hostname,
+        score,
+        format_bytes(covered.min(total))?,
+        format_bytes(total)?
+    );
+    for entry in &entries {
+        let size = match entry.size {
+            Some(size) => format_bytes(size)?,
+            None => "?".to_string(),
+        };
+        info!(
+            "  {:<12} {:>10}  {}",
+            entry.status.label(),
+            size,
+            entry.path.display()
+        );
+    }
+
+    if gaps.is_empty() {
+        info!(
+            "No unprotected directories over {}",
+            format_bytes(min_size)?
+        );
+    } else {
+        info!("Unprotected directories over {}:", format_bytes(min_size)?);
+        for gap in &gaps {
+            let note = if gap.configured {
+                " (configured, no repository yet)"
+            } else {
+                ""
+            };
+            info!(
+                "  {:>10}  {}{}",
+                format_bytes(gap.size)?,
+                gap.path.display(),
+                note
+            );
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sizes() -> HashMap<PathBuf, u64> {
+        [
+            ("/home/alice", 100),
+            ("/home/alice/photos", 60),
+            ("/home/alice/code", 30),
+            ("/home/alice/.cache", 10),
+            ("/etc", 5),
+            ("/srv", 200),
+        ]
+        .into_iter()
+        .map(|(p, s)| (PathBuf::from(p), s))
+        .collect()
+    }
+
+    fn assess(root: &str, protection: &Protection) -> (CoverageEntry, Vec<CoverageGap>) {
+        let sizes = sizes();
+        let size_of = |p: &Path| sizes.get(p).copied();
+        let children_of = |p: &Path| {
+            let mut children: Vec<PathBuf> = sizes
+                .keys()
+                .filter(|c| c.parent() == Some(p))
+                .cloned()
+                .collect();
+            children.sort();
+            children
+        };
+        assess_root(
+            Path::new(root),
+            CATEGORY_SYSTEM,
+            protection,
+            &size_of,
+            &children_of,
+        )
+    }
+
+    fn protection() -> Protection {
+        Protection {
+            repos: ["user_home/alice/photos", "system/etc"]
+                .into_iter()
+                .map(String::from)
+                .collect(),
+            configured: vec![
+                PathBuf::from("/etc"),
+                PathBuf::from("/home/alice/photos"),
+                PathBuf::from("/srv"),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_partial_home_lists_uncovered_subdirectories() {
+        let (entry, gaps) = assess("/home/alice", &protection());
+        assert_eq!(entry.status, CoverageStatus::Partial);
+        assert_eq!(entry.covered, 60);
+        let gap_paths: Vec<&Path> = gaps.iter().map(|g| g.path.as_path()).collect();
+        assert_eq!(
+            gap_paths,
+            vec![
+                Path::new("/home/alice/.cache"),
+                Path::new("/home/alice/code")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_backed_up_configured_and_unprotected_roots() {
+        let (etc, gaps) = assess("/etc", &protection());
+        assert_eq!(etc.status, CoverageStatus::BackedUp);
+        assert_eq!(etc.covered, 5);
+        assert!(gaps.is_empty());
+
+        let (srv, gaps) = assess("/srv", &protection());
+        assert_eq!(srv.status, CoverageStatus::ConfiguredOnly);
+        assert!(gaps[0].configured);
+
+        let (home, gaps) = assess("/home/alice", &Protection::default());
+        assert_eq!(home.status, CoverageStatus::Unprotected);
+        assert_eq!(gaps.len(), 1);
+        assert_eq!(gaps[0].size, 100);
+    }
+
+    #[test]
+    fn test_score_weights_by_size() {
+        let protection = protection();
+        let entries: Vec<CoverageEntry> = ["/home/alice", "/etc", "/srv"]
+            .iter()
+            .map(|root| assess(root, &protection).0)
+            .collect();
+        // 60 + 5 backed up of 100 + 5 + 200
+        assert!((coverage_score(&entries) - 65.0 * 100.0 / 305.0).abs() < 1e-9);
+    }
+}
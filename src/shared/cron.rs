@@ -0,0 +1,228 @@
+use crate::errors::BackupServiceError;
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveTime, TimeZone, Timelike};
+use std::collections::BTreeSet;
+
+/// Days searched for the next match before giving up (covers Feb 29 schedules)
+const SEARCH_DAYS: i64 = 366 * 8;
+
+/// A standard five-field cron expression: minute hour day-of-month month day-of-week
+///
+/// Fields accept `*`, values, ranges (`1-5`), lists (`1,15`) and steps (`*/15`, `8-18/2`);
+/// day-of-week is 0-7 with 0 and 7 both Sunday. As in cron, when both day fields are
+/// restricted a day matches if either does. `@hourly`, `@daily`, `@weekly`, `@monthly`
+/// and `@yearly` are accepted as shorthands.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CronSchedule {
+    minutes: BTreeSet<u32>,
+    hours: BTreeSet<u32>,
+    days_of_month: BTreeSet<u32>,
+    months: BTreeSet<u32>,
+    days_of_week: BTreeSet<u32>,
+    dom_restricted: bool,
+    dow_restricted: bool,
+}
+
+impl CronSchedule {
+    pub fn parse(expression: &str) -> Result<Self, BackupServiceError> {
+        let expanded = match expression.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            other => other,
+        };
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        let [minute, hour, dom, month, dow] = fields[..] else {
+            return Err(invalid(expression, "expected 5 fields"));
+        };
+
+        let mut days_of_week = parse_field(dow, 0, 7).map_err(|e| invalid(expression, &e))?;
+        if days_of_week.remove(&7) {
+            days_of_week.insert(0);
+        }
+        Ok(Self {
+            minutes: parse_field(minute, 0, 59).map_err(|e| invalid(expression, &e))?,
+            hours: parse_field(hour, 0, 23).map_err(|e| invalid(expression, &e))?,
+            days_of_month: parse_field(dom, 1, 31).map_err(|e| invalid(expression, &e))?,
+            months: parse_field(month, 1, 12).map_err(|e| invalid(expression, &e))?,
+            days_of_week,
+            dom_restricted: dom != "*",
+            dow_restricted: dow != "*",
+        })
+    }
+
+    fn day_matches(&self, date: NaiveDate) -> bool {
+        if !self.months.contains(&date.month()) {
+            return false;
+        }
+        let dom = self.days_of_month.contains(&date.day());
+        let dow = self
+            .days_of_week
+            .contains(&date.weekday().num_days_from_sunday());
+        match (self.dom_restricted, self.dow_restricted) {
+            (true, true) => dom || dow,
+            (true, false) => dom,
+            (false, true) => dow,
+            (false, false) => true,
+        }
+    }
+
+    /// First matching minute strictly after `after`, in `after`'s time zone
+    ///
+    /// Wall-clock times skipped by a DST change are passed over; repeated ones fire once.
+    pub fn next_after<Tz: TimeZone>(&self, after: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+        let zone = after.timezone();
+        let local = after.naive_local();
+        let start_date = local.date();
+        let start_minute = local.hour() * 60 + local.minute();
+
+        for offset in 0..SEARCH_DAYS {
+            let date = start_date + Duration::days(offset);
+            if !self.day_matches(date) {
+                continue;
+            }
+            for &hour in &self.hours {
+                for &minute in &self.minutes {
+                    if offset == 0 && hour * 60 + minute <= start_minute {
+                        continue;
+                    }
+                    let time = NaiveTime::from_hms_opt(hour, minute, 0)?;
+                    if let Some(candidate) =
+                        zone.from_local_datetime(&date.and_time(time)).earliest()
+                        && candidate > *after
+                    {
+                        return Some(candidate);
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+fn invalid(expression: &str, reason: &str) -> BackupServiceError {
+    BackupServiceError::ConfigurationError(format!(
+        "Invalid cron expression '{}': {}.\n\nUse five fields (minute hour day-of-month month day-of-week), \
+        e.g. \"0 3 * * *\" for 03:00 daily or \"30 */6 * * 1-5\" every 6 hours on weekdays",
+        expression, reason
+    ))
+}
+
+fn parse_field(field: &str, min: u32, max: u32) -> Result<BTreeSet<u32>, String> {
+    let mut values = BTreeSet::new();
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (
+                range,
+                step.parse::<u32>()
+                    .ok()
+                    .filter(|s| *s > 0)
+                    .ok_or_else(|| format!("invalid step in '{}'", part))?,
+            ),
+            None => (part, 1),
+        };
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((a, b)) = range.split_once('-') {
+            (parse_value(a, part)?, parse_value(b, part)?)
+        } else {
+            let value = parse_value(range, part)?;
+            // `5/15` means from 5 to the end of the range in steps of 15
+            (value, if step > 1 { max } else { value })
+        };
+        if start < min || end > max || start > end {
+            return Err(format!("'{}' is outside {}-{}", part, min, max));
+        }
+        values.extend((start..=end).step_by(step as usize));
+    }
+    Ok(values)
+}
+
+fn parse_value(value: &str, part: &str) -> Result<u32, String> {
+    value
+        .parse()
+        .map_err(|_| format!("'{}' is not a number or range", part))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{FixedOffset, Utc};
+
+    fn at(value: &str) -> DateTime<FixedOffset> {
+        DateTime::parse_from_rfc3339(value).unwrap()
+    }
+
+    fn next(expression: &str, after: &str) -> Result<String, BackupServiceError> {
+        Ok(CronSchedule::parse(expression)?
+            .next_after(&at(after))
+            .map(|t| t.to_rfc3339())
+            .unwrap_or_default())
+    }
+
+    #[test]
+    fn test_daily_and_stepped_schedules() -> Result<(), BackupServiceError> {
+        assert_eq!(
+            next("0 3 * * *", "2025-01-15T02:59:00+01:00")?,
+            "2025-01-15T03:00:00+01:00"
+        );
+        // Exactly on a match: the next one, not the same minute
+        assert_eq!(
+            next("0 3 * * *", "2025-01-15T03:00:00+01:00")?,
+            "2025-01-16T03:00:00+01:00"
+        );
+        assert_eq!(
+            next("*/15 8-18/2 * * *", "2025-01-15T09:50:00+00:00")?,
+            "2025-01-15T10:00:00+00:00"
+        );
+        assert_eq!(
+            next("@monthly", "2025-01-31T12:00:00+00:00")?,
+            "2025-02-01T00:00:00+00:00"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_day_fields() -> Result<(), BackupServiceError> {
+        // 2025-01-15 is a Wednesday; 1-5 = Monday-Friday, 7 = Sunday
+        assert_eq!(
+            next("30 22 * * 1-5", "2025-01-17T23:00:00+00:00")?,
+            "2025-01-20T22:30:00+00:00"
+        );
+        assert_eq!(
+            next("0 0 * * 7", "2025-01-15T00:00:00+00:00")?,
+            "2025-01-19T00:00:00+00:00"
+        );
+        // Both day fields restricted: either matches (the 1st, or any Sunday)
+        assert_eq!(
+            next("0 0 1 * 0", "2025-01-15T00:00:00+00:00")?,
+            "2025-01-19T00:00:00+00:00"
+        );
+        assert_eq!(
+            next("0 12 29 2 *", "2025-01-01T00:00:00+00:00")?,
+            "2028-02-29T12:00:00+00:00"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_expressions() {
+        for expression in [
+            "0 3 * *",
+            "60 * * * *",
+            "* * 0 * *",
+            "*/0 * * * *",
+            "a * * * *",
+            "5-1 * * * *",
+        ] {
+            assert!(CronSchedule::parse(expression).is_err(), "{}", expression);
+        }
+        assert!(
+            CronSchedule::parse("0 3 * * *")
+                .unwrap()
+                .next_after(&Utc::now())
+                .is_some()
+        );
+    }
+}
@@ -0,0 +1,142 @@
+use crate::config::Config;
+use crate::errors::BackupServiceError;
+use crate::shared::backup_workflow::{BackupOptions, BackupWorkflow};
+use crate::shared::cron::CronSchedule;
+use crate::shared::instance_lock::InstanceLock;
+use crate::shared::logs_workflow;
+use crate::shared::metrics;
+use crate::shared::shutdown;
+use crate::shared::timestamps::format_local;
+use chrono::{Local, Utc};
+use tokio::signal::unix::{SignalKind, signal};
+use tracing::{error, info, warn};
+
+/// Env var with the daemon's cron expression (`0 3 * * *`)
+pub const BACKUP_SCHEDULE_ENV_VAR: &str = "BACKUP_SCHEDULE";
+
+/// `daemon` options
+#[derive(Debug, Clone, Default)]
+pub struct DaemonOptions {
+    /// Cron expression (`--schedule`, default BACKUP_SCHEDULE)
+    pub schedule: Option<String>,
+    /// Serve the latest run's metrics on this address (`--metrics-listen`, default METRICS_LISTEN)
+    pub metrics_listen: Option<String>,
+    /// Options for every scheduled run
+    pub backup: BackupOptions,
+}
+
+fn resolve_schedule(options: &DaemonOptions) -> Result<CronSchedule, BackupServiceError> {
+    let env = std::env::var(BACKUP_SCHEDULE_ENV_VAR)
+        .ok()
+        .filter(|v| !v.trim().is_empty());
+    match options.schedule.as_ref().or(env.as_ref()) {
+        Some(expression) => CronSchedule::parse(expression),
+        None => Err(BackupServiceError::ConfigurationError(format!(
+            "The daemon needs a schedule.\n\nPass --schedule \"0 3 * * *\" or set {}",
+            BACKUP_SCHEDULE_ENV_VAR
+        ))),
+    }
+}
+
+/// Keep running and back up on the cron schedule until SIGTERM/SIGINT
+///
+/// Runs never overlap: the next run is planned only after the current one finished (slots
+/// that passed meanwhile are skipped), and the `run` instance lock makes a slot a no-op
+/// while a manual or timer-triggered `run` is active. The first signal during a run lets
+/// the path being backed up finish and skips the rest; a second one stops immediately.
+pub async fn execute_daemon(
+    mut config: Config,
+    options: DaemonOptions,
+) -> Result<(), BackupServiceError> {
+    let schedule = resolve_schedule(&options)?;
+    let mut sigterm = signal(SignalKind::terminate())?;
+    let mut sigint = signal(SignalKind::interrupt())?;
+    if let Some(addr) = metrics::resolve_listen(options.metrics_listen.clone()) {
+        metrics::serve(&addr).await?;
+    }
+    info!(hostname = %config.hostname, "Backup daemon started");
+
+    let mut run = 0u64;
+    loop {
+        let now = Local::now();
+        let Some(next) = schedule.next_after(&now) else {
+            return Err(BackupServiceError::ConfigurationError(
+                "The schedule never matches a date".to_string(),
+            ));
+        };
+        info!(next_run = %format_local(next.with_timezone(&Utc)), "Waiting for next scheduled backup");
+
+        let wait = (next - now).to_std().unwrap_or_default();
+        tokio::select! {
+            _ = tokio::time::sleep(wait) => {}
+            _ = sigterm.recv() => break,
+            _ = sigint.recv() => break,
+        }
+
+        run += 1;
+        // The process outlives many daily log files, so retention is applied per run too
+        logs_workflow::cleanup_log_files(&logs_workflow::log_dir());
+        let lock = match InstanceLock::acquire("run")? {
+            Ok(lock) => lock,
+            Err(holder) => {
+                warn!(run = %run, holder = %holder.describe(Utc::now()), "Another backup is still running, skipping this slot");
+                continue;
+            }
+        };
+
+        // Rotated provider credentials are picked up; a provider outage keeps the last ones
+        if let Err(e) = config.reload_secrets().await {
+            warn!(run = %run, error = %e, "Could not refresh secrets, using the previous ones");
+        }
+        info!(run = %run, "Scheduled backup starting");
+        let started = std::time::Instant::now();
+        let workflow = BackupWorkflow::new(config.clone(), Vec::new(), options.backup.clone())?;
+        // restic runs as a blocking child process, so the run gets its own task and the
+        // signal handlers stay responsive on this one
+        let mut task = tokio::spawn(async move { workflow.execute_backup().await });
+        let result = tokio::select! {
+            joined = &mut task => joined,
+            _ = sigterm.recv() => wait_after_signal(&mut task, &mut sigterm, &mut sigint).await?,
+            _ = sigint.recv() => wait_after_signal(&mut task, &mut sigterm, &mut sigint).await?,
+        };
+        drop(lock);
+
+        let duration_secs = format!("{:.1}", started.elapsed().as_secs_f64());
+        match result {
+            Ok(Ok(())) => {
+                info!(run = %run, duration_secs = %duration_secs, "Scheduled backup completed")
+            }
+            Ok(Err(e)) => {
+                error!(run = %run, duration_secs = %duration_secs, error = %e, "Scheduled backup failed")
+            }
+            Err(e) => error!(run = %run, error = %e, "Scheduled backup task aborted"),
+        }
+        if shutdown::is_requested() {
+            break;
+        }
+    }
+
+    info!(runs = %run, "Backup daemon stopped");
+    Ok(())
+}
+
+/// First signal during a run: let the current path finish; a second one aborts
+async fn wait_after_signal<T>(
+    task: &mut tokio::task::JoinHandle<T>,
+    sigterm: &mut tokio::signal::unix::Signal,
+    sigint: &mut tokio::signal::unix::Signal,
+) -> Result<Result<T, tokio::task::JoinError>, BackupServiceError> {
+    shutdown::request();
+    warn!("Shutdown requested, finishing the current path (signal again to stop now)");
+    tokio::select! {
+        joined = &mut *task => Ok(joined),
+        _ = sigterm.recv() => Err(forced_shutdown()),
+        _ = sigint.recv() => Err(forced_shutdown()),
+    }
+}
+
+fn forced_shutdown() -> BackupServiceError {
+    BackupServiceError::CommandFailed(
+        "Daemon stopped during a backup; the interrupted path has no new snapshot".to_string(),
+    )
+}
@@ -0,0 +1,220 @@
+use crate::config::Config;
+use crate::errors::BackupServiceError;
+use crate::i18n::{t, t_args};
+use crate::shared::commands::ResticCommandExecutor;
+use crate::shared::display::DisplayFormatter;
+use crate::shared::operations::RepositoryOperations;
+use crate::shared::repo_store::RepoStore;
+use crate::shared::scan_cache;
+use crate::shared::ui::confirm_destructive;
+use crate::utils::{format_bytes, validate_credentials};
+use serde_json::json;
+use tracing::{error, info};
+
+/// `delete-host` options
+#[derive(Debug, Clone, Default)]
+pub struct DeleteHostOptions {
+    /// List what would be removed and stop
+    pub dry_run: bool,
+    /// `restic forget` every snapshot and prune each repository instead of deleting the
+    /// host's directory from the storage
+    pub forget_only: bool,
+    pub assume_yes: bool,
+    /// Hostname repeated as a safeguard for --yes
+    pub confirm: Option<String>,
+    pub json_output: bool,
+}
+
+impl DeleteHostOptions {
+    fn mode(&self) -> &'static str {
+        if self.forget_only { "forget" } else { "delete" }
+    }
+
+    /// Completes "About to … all repositories of host X" in the confirmation prompt
+    fn operation(&self) -> &'static str {
+        if self.forget_only {
+            "forget and prune"
+        } else {
+            "permanently delete"
+        }
+    }
+}
+
+/// Forget every snapshot of one repository, then prune it so its data is released
+async fn forget_repository(
+    config: &Config,
+    hostname: &str,
+    repo_subpath: &str,
+    show_live_output: bool,
+) -> Result<usize, BackupServiceError> {
+    let repo_url = config.get_repo_url_for_host(hostname, repo_subpath)?;
+    let restic_cmd = ResticCommandExecutor::new(config.clone(), repo_url)?;
+    let ids: Vec<String> = restic_cmd
+        .snapshots()
+        .await?
+        .iter()
+        .filter_map(|s| s["id"].as_str().map(str::to_string))
+        .collect();
+    if !ids.is_empty() {
+        restic_cmd.forget_ids(&ids).await?;
+    }
+    restic_cmd.prune(&[], show_live_output).await?;
+    Ok(ids.len())
+}
+
+/// Remove every repository of a decommissioned host
+pub async fn execute_delete_host_workflow(
+    config: Config,
+    hostname: String,
+    options: DeleteHostOptions,
+) -> Result<(), BackupServiceError> {
+    config.set_aws_env()?;
+    validate_credentials(&config).await?;
+
+    let operations = RepositoryOperations::new(config.clone())?;
+    if !operations.get_available_hosts().await?.contains(&hostname) {
+        return Err(BackupServiceError::ConfigurationError(format!(
+            "{}\n\n{}",
+            t_args("delete-host-not-found", &[("host", hostname.clone())]),
+            t("delete-host-not-found-hint")
+        )));
+    }
+    let discovery = operations.discover_all_repositories(&hostname).await?;
+    for failure in &discovery.failures {
+        error!(failure = %failure, "Repository discovery incomplete");
+    }
+    let repositories: Vec<String> = discovery
+        .repos
+        .into_iter()
+        .map(|r| r.repo_subpath)
+        .collect();
+    let store_path = config.host_store_path(&hostname)?;
+    let store = RepoStore::new(config.clone())?;
+
+    if options.dry_run {
+        // Deletion removes the whole directory, so its size is what gets freed
+        let usage = if options.forget_only {
+            None
+        } else {
+            store.usage(&store_path).await?
+        };
+        if options.json_output {
+            let output = json!({
+                "host": hostname,
+                "dry_run": true,
+                "mode": options.mode(),
+                "store_path": store_path,
+                "repositories": repositories,
+                "objects": usage.map(|(objects, _)| objects),
+                "bytes": usage.map(|(_, bytes)| bytes),
+                "discovery_errors": discovery.failures,
+            });
+            DisplayFormatter::print_json(&output)?;
+        } else {
+            for repo_subpath in &repositories {
+                info!(repo_subpath = %repo_subpath, mode = %options.mode(), "Would remove repository");
+            }
+            info!(
+                host = %hostname,
+                store_path = %store_path,
+                repositories = %repositories.len(),
+                objects = %usage.map(|(objects, _)| objects.to_string()).unwrap_or_default(),
+                size = %usage.map(|(_, bytes)| format_bytes(bytes)).transpose()?.unwrap_or_default(),
+                "Dry run: nothing was removed"
+            );
+        }
+        return Ok(());
+    }
+
+    confirm_destructive(
+        options.operation(),
+        &hostname,
+        options.assume_yes,
+        options.confirm.as_deref(),
+    )?;
+
+    let mut failed = Vec::new();
+    if options.forget_only {
+        // An incomplete discovery would leave snapshots behind unnoticed
+        failed.extend(discovery.failures.iter().map(|f| f.scope.clone()));
+        for (idx, repo_subpath) in repositories.iter().enumerate() {
+            match forget_repository(&config, &hostname, repo_subpath, !options.json_output).await {
+                Ok(snapshots) => info!(
+                    progress = format!("({}/{})", idx + 1, repositories.len()),
+                    repo_subpath = %repo_subpath,
+                    snapshots = %snapshots,
+                    "Forgot and pruned repository"
+                ),
+                Err(e) => {
+                    error!(repo_subpath = %repo_subpath, error = %e, "Forgetting repository failed");
+                    failed.push(repo_subpath.clone());
+                }
+            }
+        }
+    } else {
+        store.delete_tree(&store_path).await?;
+        info!(host = %hostname, store_path = %store_path, "Deleted all repositories of host");
+    }
+    scan_cache::invalidate(&config, &hostname);
+
+    if options.json_output {
+        let output = json!({
+            "host": hostname,
+            "dry_run": false,
+            "mode": options.mode(),
+            "store_path": store_path,
+            "repositories": repositories,
+            "failed": failed,
+        });
+        DisplayFormatter::print_json(&output)?;
+    }
+    if failed.is_empty() {
+        Ok(())
+    } else {
+        Err(BackupServiceError::CommandFailed(t_args(
+            "delete-host-not-forgotten",
+            &[
+                ("count", failed.len().to_string()),
+                ("host", hostname),
+                ("repositories", failed.join(", ")),
+            ],
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_unknown_host_is_refused() -> Result<(), BackupServiceError> {
+        let root = tempfile::tempdir()?;
+        std::fs::create_dir_all(root.path().join("web10/system/etc"))?;
+        let config = Config {
+            restic_password: "test".to_string(),
+            restic_repo_base: root.path().display().to_string(),
+            aws_access_key_id: String::new(),
+            aws_secret_access_key: String::new(),
+            aws_default_region: String::new(),
+            aws_s3_endpoint: String::new(),
+            backup_paths: vec![],
+            hostname: "web10".to_string(),
+        };
+        let options = DeleteHostOptions {
+            assume_yes: true,
+            confirm: Some("web1".to_string()),
+            ..DeleteHostOptions::default()
+        };
+
+        let err = execute_delete_host_workflow(config, "web1".to_string(), options)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, BackupServiceError::ConfigurationError(_)));
+        assert!(
+            err.to_string()
+                .contains("No repositories found for host web1")
+        );
+        assert!(root.path().join("web10/system/etc").is_dir());
+        Ok(())
+    }
+}
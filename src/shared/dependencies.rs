@@ -0,0 +1,460 @@
+use crate::errors::BackupServiceError;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tracing::{debug, warn};
+
+/// Oldest restic with repository format v2 (compression) and `--read-data-subset` sizes
+pub const MIN_RESTIC_VERSION: Version = Version(0, 16, 0);
+
+/// Env var with the restic binary to run (a name looked up in PATH, or a path)
+pub const RESTIC_BIN_ENV_VAR: &str = "RESTIC_BIN";
+
+/// RESTIC_BIN, else `restic` from PATH
+pub fn restic_binary() -> String {
+    std::env::var(RESTIC_BIN_ENV_VAR)
+        .ok()
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+        .unwrap_or_else(|| "restic".to_string())
+}
+
+/// Major, minor and patch of a tool's `--version` output
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Version(pub u32, pub u32, pub u32);
+
+impl std::fmt::Display for Version {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}.{}", self.0, self.1, self.2)
+    }
+}
+
+/// First dotted number in a version banner (`restic 0.16.4 compiled with go1.21`, `OpenSSH_9.6p1`)
+pub fn parse_version(output: &str) -> Option<Version> {
+    output
+        .split(|c: char| !c.is_ascii_digit() && c != '.')
+        .map(|token| token.trim_matches('.'))
+        .find(|token| token.contains('.'))
+        .and_then(|token| {
+            let mut parts = token.split('.').map(|p| p.parse::<u32>().ok());
+            Some(Version(
+                parts.next()??,
+                parts.next()??,
+                parts.next().flatten().unwrap_or(0),
+            ))
+        })
+}
+
+/// An external program a command spawns
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dependency {
+    /// Binary name, or a path (e.g. a custom FLEET_SSH_COMMAND)
+    pub binary: String,
+    pub min_version: Option<Version>,
+    /// Arguments printing the version banner; None skips the version check
+    version_args: Option<&'static [&'static str]>,
+    /// Refuse to run below `min_version` instead of only warning
+    pub enforce_min_version: bool,
+    /// What the tool is needed for, shown next to the install hint
+    pub purpose: &'static str,
+}
+
+impl Dependency {
+    pub fn restic() -> Self {
+        Self {
+            binary: restic_binary(),
+            min_version: Some(MIN_RESTIC_VERSION),
+            version_args: Some(&["version"]),
+            // Older releases fail with cryptic flag errors (`--retry-lock`, compression)
+            enforce_min_version: true,
+            purpose: "backups, restores and repository maintenance",
+        }
+    }
+
+    pub fn docker(purpose: &'static str) -> Self {
+        Self {
+            binary: "docker".to_string(),
+            min_version: None,
+            version_args: None,
+            enforce_min_version: false,
+            purpose,
+        }
+    }
+
+    pub fn curl(purpose: &'static str) -> Self {
+        Self {
+            binary: "curl".to_string(),
+            min_version: None,
+            version_args: None,
+            enforce_min_version: false,
+            purpose,
+        }
+    }
+
+    pub fn bzip2() -> Self {
+        Self {
+            binary: "bzip2".to_string(),
+            min_version: None,
+            version_args: None,
+            enforce_min_version: false,
+            purpose: "unpacking the restic release binary",
+        }
+    }
+
+    pub fn sendmail() -> Self {
+        Self {
+            binary: "sendmail".to_string(),
+            min_version: None,
+            version_args: None,
+            enforce_min_version: false,
+            purpose: "mailing reports to REPORT_EMAIL_TO",
+        }
+    }
+
+    pub fn minisign() -> Self {
+        Self {
+            binary: "minisign".to_string(),
+            min_version: None,
+            version_args: None,
+            enforce_min_version: false,
+            purpose: "verifying release signatures (RBS_RELEASE_PUBKEY)",
+        }
+    }
+
+    pub fn sops() -> Self {
+        Self {
+            binary: "sops".to_string(),
+            min_version: None,
+            version_args: None,
+            enforce_min_version: false,
+            purpose: "decrypting SOPS_SECRETS_FILE (SECRET_PROVIDER=sops)",
+        }
+    }
+
+    /// Keyring client: `secret-tool` (libsecret) on Linux, `security` on macOS
+    pub fn keyring() -> Self {
+        Self {
+            binary: if cfg!(target_os = "macos") {
+                "security"
+            } else {
+                "secret-tool"
+            }
+            .to_string(),
+            min_version: None,
+            version_args: None,
+            enforce_min_version: false,
+            purpose: "reading the repository password from RESTIC_PASSWORD_KEYRING",
+        }
+    }
+
+    pub fn systemctl() -> Self {
+        Self {
+            binary: "systemctl".to_string(),
+            min_version: None,
+            version_args: None,
+            enforce_min_version: false,
+            purpose: "starting mount units from BACKUP_NETWORK_MOUNTS",
+        }
+    }
+
+    /// AWS CLI; never required, `doctor` only reports it
+    pub fn aws_cli() -> Self {
+        Self {
+            binary: "aws".to_string(),
+            min_version: None,
+            version_args: Some(&["--version"]),
+            enforce_min_version: false,
+            purpose: "inspecting the S3 bucket by hand (optional)",
+        }
+    }
+
+    pub fn sftp_ssh() -> Self {
+        Self {
+            binary: "ssh".to_string(),
+            min_version: None,
+            version_args: None,
+            enforce_min_version: false,
+            purpose: "listing repositories on the SFTP backend (RESTIC_REPO_BASE=sftp:...)",
+        }
+    }
+
+    /// The program of FLEET_SSH_COMMAND (default `ssh`)
+    pub fn ssh() -> Self {
+        let command = std::env::var("FLEET_SSH_COMMAND").unwrap_or_default();
+        Self {
+            binary: command
+                .split_whitespace()
+                .next()
+                .unwrap_or("ssh")
+                .to_string(),
+            min_version: None,
+            version_args: None,
+            enforce_min_version: false,
+            purpose: "running fleet commands on other hosts",
+        }
+    }
+
+    /// Install command for this system
+    pub fn install_hint(&self) -> String {
+        let distro = Distro::detect();
+        distro.install_hint(&self.package(distro))
+    }
+
+    /// Package providing the binary on `distro`
+    fn package(&self, distro: Distro) -> String {
+        // RESTIC_BIN may name any file; the package is restic all the same
+        if self.binary == restic_binary() {
+            return "restic".to_string();
+        }
+        let name = Path::new(&self.binary)
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| self.binary.clone());
+        let package = match (name.as_str(), distro) {
+            ("docker", Distro::Debian) => "docker.io",
+            ("docker", Distro::Fedora) => "moby-engine",
+            ("ssh", Distro::Debian) => "openssh-client",
+            ("ssh", Distro::Fedora | Distro::Suse) => "openssh-clients",
+            ("ssh", _) => "openssh",
+            ("sendmail", Distro::Fedora | Distro::Alpine | Distro::NixOS) => "msmtp",
+            ("sendmail", _) => "msmtp-mta",
+            ("systemctl", _) => "systemd",
+            ("aws", Distro::NixOS) => "awscli2",
+            ("aws", _) => "awscli",
+            _ => return name,
+        };
+        package.to_string()
+    }
+}
+
+/// Package manager family from /etc/os-release
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Distro {
+    Debian,
+    Fedora,
+    Arch,
+    Alpine,
+    Suse,
+    NixOS,
+    Unknown,
+}
+
+impl Distro {
+    pub fn detect() -> Self {
+        std::fs::read_to_string("/etc/os-release")
+            .map(|content| Self::from_os_release(&content))
+            .unwrap_or(Distro::Unknown)
+    }
+
+    /// Match `ID`, then each `ID_LIKE` entry (e.g. Ubuntu is `ID_LIKE=debian`)
+    pub fn from_os_release(content: &str) -> Self {
+        let field = |key: &str| {
+            content
+                .lines()
+                .find_map(|line| line.strip_prefix(key)?.strip_prefix('='))
+                .map(|v| v.trim().trim_matches('"').to_lowercase())
+                .unwrap_or_default()
+        };
+        let id = field("ID");
+        let like = field("ID_LIKE");
+        std::iter::once(id.as_str())
+            .chain(like.split_whitespace())
+            .find_map(|id| match id {
+                "debian" | "ubuntu" => Some(Distro::Debian),
+                "fedora" | "rhel" | "centos" => Some(Distro::Fedora),
+                "arch" => Some(Distro::Arch),
+                "alpine" => Some(Distro::Alpine),
+                "suse" | "opensuse" | "opensuse-leap" | "opensuse-tumbleweed" => Some(Distro::Suse),
+                "nixos" => Some(Distro::NixOS),
+                _ => None,
+            })
+            .unwrap_or(Distro::Unknown)
+    }
+
+    pub fn install_hint(self, package: &str) -> String {
+        match self {
+            Distro::Debian => format!("sudo apt install {}", package),
+            Distro::Fedora => format!("sudo dnf install {}", package),
+            Distro::Arch => format!("sudo pacman -S {}", package),
+            Distro::Alpine => format!("sudo apk add {}", package),
+            Distro::Suse => format!("sudo zypper install {}", package),
+            Distro::NixOS => format!(
+                "add pkgs.{} to environment.systemPackages (or nix-shell -p {})",
+                package, package
+            ),
+            Distro::Unknown => format!("install {} with your package manager", package),
+        }
+    }
+}
+
+/// Result of looking up one dependency
+#[derive(Debug, Clone, PartialEq)]
+pub enum DependencyStatus {
+    Ok,
+    Missing,
+    Outdated { found: Version },
+}
+
+/// Resolve a binary name against PATH; names containing `/` are taken as paths
+fn find_binary(binary: &str) -> Option<PathBuf> {
+    if binary.contains('/') {
+        let path = PathBuf::from(binary);
+        return path.is_file().then_some(path);
+    }
+    std::env::split_paths(&std::env::var_os("PATH")?)
+        .map(|dir| dir.join(binary))
+        .find(|candidate| candidate.is_file())
+}
+
+/// Classify a dependency from its PATH lookup and version banner
+pub fn assess(dep: &Dependency, found: bool, banner: Option<&str>) -> DependencyStatus {
+    if !found {
+        return DependencyStatus::Missing;
+    }
+    match (dep.min_version, banner.and_then(parse_version)) {
+        (Some(min), Some(version)) if version < min => {
+            DependencyStatus::Outdated { found: version }
+        }
+        _ => DependencyStatus::Ok,
+    }
+}
+
+/// Look a dependency up in PATH and read its version banner
+pub fn probe(dep: &Dependency) -> (DependencyStatus, Option<Version>) {
+    let Some(path) = find_binary(&dep.binary) else {
+        return (DependencyStatus::Missing, None);
+    };
+    let banner = dep.version_args.and_then(|args| {
+        let output = Command::new(&path).args(args).output().ok()?;
+        Some(format!(
+            "{}{}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        ))
+    });
+    debug!(binary = %path.display(), version = ?banner.as_deref().map(str::trim), "Found dependency");
+    (
+        assess(dep, true, banner.as_deref()),
+        banner.as_deref().and_then(parse_version),
+    )
+}
+
+/// Check the external programs a command needs before it starts
+///
+/// Every missing program is reported at once, with the install command for this
+/// system. restic below [`MIN_RESTIC_VERSION`] is refused; other programs older than
+/// their minimum version only warn, since most commands still work with them.
+pub fn ensure_available(deps: &[Dependency]) -> Result<(), BackupServiceError> {
+    let distro = Distro::detect();
+    let mut missing = Vec::new();
+    let mut unsupported = None;
+    for dep in deps {
+        match probe(dep).0 {
+            DependencyStatus::Ok => {}
+            DependencyStatus::Missing => missing.push(dep),
+            DependencyStatus::Outdated { found } if dep.enforce_min_version => {
+                unsupported = Some((dep, found));
+            }
+            DependencyStatus::Outdated { found } => warn!(
+                binary = %dep.binary,
+                found = %found,
+                required = %dep.min_version.unwrap_or(found),
+                install = %distro.install_hint(&dep.package(distro)),
+                "Outdated dependency, some features may fail"
+            ),
+        }
+    }
+    if missing.is_empty() {
+        return match unsupported {
+            Some((dep, found)) => Err(BackupServiceError::UnsupportedResticVersion {
+                binary: dep.binary.clone(),
+                found: found.to_string(),
+                required: dep.min_version.unwrap_or(found).to_string(),
+            }),
+            None => Ok(()),
+        };
+    }
+
+    let names: Vec<&str> = missing.iter().map(|d| d.binary.as_str()).collect();
+    let details: Vec<String> = missing
+        .iter()
+        .map(|dep| {
+            let version = dep
+                .min_version
+                .map(|v| format!(" >= {}", v))
+                .unwrap_or_default();
+            format!(
+                "{}{} (needed for {}): {}",
+                dep.binary,
+                version,
+                dep.purpose,
+                distro.install_hint(&dep.package(distro))
+            )
+        })
+        .collect();
+    Err(BackupServiceError::CommandNotFound(format!(
+        "Missing external dependencies: {}\n\n{}",
+        names.join(", "),
+        details.join("\n")
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(
+            parse_version("restic 0.16.4 compiled with go1.21.6 on linux/amd64"),
+            Some(Version(0, 16, 4))
+        );
+        assert_eq!(
+            parse_version("Docker version 24.0.7, build afdd53b"),
+            Some(Version(24, 0, 7))
+        );
+        assert_eq!(
+            parse_version("OpenSSH_9.6p1, OpenSSL 3.0.13"),
+            Some(Version(9, 6, 0))
+        );
+        assert_eq!(parse_version("no version here"), None);
+    }
+
+    #[test]
+    fn test_assess() {
+        let restic = Dependency::restic();
+        assert_eq!(assess(&restic, false, None), DependencyStatus::Missing);
+        assert_eq!(
+            assess(&restic, true, Some("restic 0.12.1 compiled with go1.16")),
+            DependencyStatus::Outdated {
+                found: Version(0, 12, 1)
+            }
+        );
+        assert_eq!(
+            assess(&restic, true, Some("restic 0.17.3")),
+            DependencyStatus::Ok
+        );
+        // An unreadable banner does not block the command
+        assert_eq!(assess(&restic, true, Some("garbage")), DependencyStatus::Ok);
+        assert!(restic.enforce_min_version && !Dependency::curl("tests").enforce_min_version);
+    }
+
+    #[test]
+    fn test_distro_and_install_hint() {
+        let ubuntu = "NAME=\"Ubuntu\"\nID=ubuntu\nID_LIKE=debian\n";
+        assert_eq!(Distro::from_os_release(ubuntu), Distro::Debian);
+        let rocky = "ID=\"rocky\"\nID_LIKE=\"rhel centos fedora\"\n";
+        assert_eq!(Distro::from_os_release(rocky), Distro::Fedora);
+        assert_eq!(Distro::from_os_release("ID=nixos\n"), Distro::NixOS);
+        assert_eq!(Distro::from_os_release("ID=gentoo\n"), Distro::Unknown);
+
+        let ssh = Dependency::ssh();
+        assert_eq!(
+            Distro::Debian.install_hint(&ssh.package(Distro::Debian)),
+            "sudo apt install openssh-client"
+        );
+        assert_eq!(
+            Distro::Fedora.install_hint(&Dependency::docker("tests").package(Distro::Fedora)),
+            "sudo dnf install moby-engine"
+        );
+    }
+}
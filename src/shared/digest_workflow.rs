@@ -0,0 +1,430 @@
+use crate::config::Config;
+use crate::errors::BackupServiceError;
+use crate::shared::commands::with_timeout;
+use crate::shared::display::DisplayFormatter;
+use crate::shared::http;
+use crate::shared::operations::{DiscoveryFailure, RepositoryData, RepositoryOperations};
+use crate::utils::{format_bytes, parse_size, validate_credentials};
+use chrono::{DateTime, Duration, NaiveDate, SecondsFormat, Utc};
+use serde::Serialize;
+use serde_json::json;
+use std::collections::BTreeSet;
+use std::process::Stdio;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use tracing::info;
+
+/// Env var with an incoming-webhook URL (Slack, Mattermost, Teams, ...) the digest is posted to
+pub const DIGEST_WEBHOOK_ENV_VAR: &str = "REPORT_WEBHOOK_URL";
+/// Env var with comma-separated recipients the digest is mailed to via `sendmail -t`
+pub const DIGEST_EMAIL_ENV_VAR: &str = "REPORT_EMAIL_TO";
+
+/// Snapshots adding more than this are listed as notable (DIGEST_LARGE_CHANGE overrides)
+const DEFAULT_LARGE_CHANGE: u64 = 1 << 30;
+/// Seconds sendmail may take to accept the message
+const SENDMAIL_TIMEOUT_SECS: u64 = 60;
+
+/// `report digest` options
+#[derive(Debug, Clone, Default)]
+pub struct DigestOptions {
+    /// Hosts to include (default: every host in the bucket)
+    pub hosts: Vec<String>,
+    /// Length of the reporting window in days
+    pub days: u32,
+    pub json_output: bool,
+    /// Deliver to REPORT_WEBHOOK_URL and/or REPORT_EMAIL_TO instead of only printing
+    pub send: bool,
+}
+
+/// Something in the window worth a human's attention
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum NotableChange {
+    /// First snapshot of this path was taken in the window
+    NewPath { path: String },
+    /// No snapshot in the window although the path has older ones (failing or removed)
+    Missed {
+        path: String,
+        /// RFC 3339 time of the newest snapshot
+        last_backup: Option<String>,
+    },
+    /// A single snapshot added more than the large-change threshold
+    LargeAddition {
+        path: String,
+        snapshot: String,
+        data_added: u64,
+    },
+}
+
+impl NotableChange {
+    pub fn describe(&self) -> Result<String, BackupServiceError> {
+        Ok(match self {
+            NotableChange::NewPath { path } => format!("new path {}", path),
+            NotableChange::Missed { path, last_backup } => match last_backup {
+                Some(time) => format!("no backup of {} (last {})", path, time),
+                None => format!("no backup of {}", path),
+            },
+            NotableChange::LargeAddition {
+                path,
+                snapshot,
+                data_added,
+            } => format!(
+                "{} added {} to {}",
+                snapshot,
+                format_bytes(*data_added)?,
+                path
+            ),
+        })
+    }
+}
+
+/// One host's activity over the reporting window
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct HostDigest {
+    pub host: String,
+    pub repositories: usize,
+    /// Snapshots taken in the window, over all paths
+    pub snapshots: usize,
+    /// Days in the window with at least one snapshot
+    pub active_days: usize,
+    /// Bytes added to the repositories in the window (from snapshot summaries)
+    pub data_added: u64,
+    pub files_new: u64,
+    pub files_changed: u64,
+    pub notable: Vec<NotableChange>,
+    /// Repositories that could not be read while building the digest
+    pub failures: Vec<DiscoveryFailure>,
+}
+
+impl HostDigest {
+    pub fn needs_attention(&self) -> bool {
+        !self.failures.is_empty()
+            || self
+                .notable
+                .iter()
+                .any(|n| matches!(n, NotableChange::Missed { .. }))
+    }
+}
+
+/// Large-change threshold from DIGEST_LARGE_CHANGE
+fn large_change_threshold() -> Result<u64, BackupServiceError> {
+    match std::env::var("DIGEST_LARGE_CHANGE") {
+        Ok(v) if !v.trim().is_empty() => parse_size(&v),
+        _ => Ok(DEFAULT_LARGE_CHANGE),
+    }
+}
+
+/// Aggregate a host's repositories over the window starting at `since`
+pub fn summarize_host(
+    host: &str,
+    repos: &[RepositoryData],
+    since: DateTime<Utc>,
+    large_change: u64,
+) -> HostDigest {
+    let mut digest = HostDigest {
+        host: host.to_string(),
+        repositories: repos.len(),
+        ..Default::default()
+    };
+    let mut days: BTreeSet<NaiveDate> = BTreeSet::new();
+
+    for repo in repos {
+        let path = repo.info.native_path.display().to_string();
+        let in_window: Vec<_> = repo.snapshots.iter().filter(|s| s.time >= since).collect();
+
+        if in_window.is_empty() {
+            digest.notable.push(NotableChange::Missed {
+                path,
+                last_backup: repo
+                    .snapshots
+                    .iter()
+                    .map(|s| s.time)
+                    .max()
+                    .map(|t| t.to_rfc3339_opts(SecondsFormat::Secs, true)),
+            });
+            continue;
+        }
+        if in_window.len() == repo.snapshots.len() {
+            digest
+                .notable
+                .push(NotableChange::NewPath { path: path.clone() });
+        }
+
+        for snapshot in in_window {
+            digest.snapshots += 1;
+            days.insert(snapshot.time.date_naive());
+            let Some(summary) = &snapshot.summary else {
+                continue;
+            };
+            digest.data_added += summary.data_added;
+            digest.files_new += summary.files_new;
+            digest.files_changed += summary.files_changed;
+            if summary.data_added > large_change {
+                digest.notable.push(NotableChange::LargeAddition {
+                    path: path.clone(),
+                    snapshot: snapshot.id.clone(),
+                    data_added: summary.data_added,
+                });
+            }
+        }
+    }
+
+    digest.active_days = days.len();
+    digest
+}
+
+/// Plain-text digest, one block per host, suitable for chat and email
+pub fn render_text(
+    digests: &[HostDigest],
+    since: DateTime<Utc>,
+    until: DateTime<Utc>,
+) -> Result<String, BackupServiceError> {
+    let attention = digests.iter().filter(|d| d.needs_attention()).count();
+    let mut text = format!(
+        "Backup digest {} - {}: {} hosts, {} need attention\n",
+        since.format("%Y-%m-%d"),
+        until.format("%Y-%m-%d"),
+        digests.len(),
+        attention
+    );
+
+    for digest in digests {
+        let marker = if digest.needs_attention() { "!" } else { "-" };
+        text.push_str(&format!(
+            "\n{} {}: {} snapshots on {} days across {} paths, {} added ({} new / {} changed files)\n",
+            marker,
+            digest.host,
+            digest.snapshots,
+            digest.active_days,
+            digest.repositories,
+            format_bytes(digest.data_added)?,
+            digest.files_new,
+            digest.files_changed
+        ));
+        for change in &digest.notable {
+            text.push_str(&format!("    {}\n", change.describe()?));
+        }
+        for failure in &digest.failures {
+            text.push_str(&format!("    unreadable: {}\n", failure));
+        }
+    }
+    Ok(text)
+}
+
+/// Post `{"text": ...}`, the payload incoming webhooks of the common chat tools accept
+pub async fn send_webhook(url: &str, text: &str) -> Result<(), BackupServiceError> {
+    let body = serde_json::to_string(&json!({ "text": text }))?;
+    let headers = ["Content-Type: application/json".to_string()];
+    http::post(url, &headers, &body, "Posting the digest to the webhook").await
+}
+
+/// Mail `text` to comma-separated recipients via `sendmail -t`
+pub async fn send_email(
+    recipients: &str,
+    subject: &str,
+    text: &str,
+) -> Result<(), BackupServiceError> {
+    let mut child = Command::new("sendmail")
+        .arg("-t")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|_| {
+            BackupServiceError::CommandNotFound("Failed to execute sendmail".to_string())
+        })?;
+    let message = format!(
+        "To: {}\nSubject: {}\nContent-Type: text/plain; charset=utf-8\n\n{}",
+        recipients, subject, text
+    );
+    let stdin = child.stdin.take();
+    let output = with_timeout(
+        async move {
+            if let Some(mut stdin) = stdin {
+                stdin.write_all(message.as_bytes()).await?;
+            }
+            child.wait_with_output().await
+        },
+        Some(std::time::Duration::from_secs(SENDMAIL_TIMEOUT_SECS)),
+        "sendmail",
+    )
+    .await??;
+    if !output.status.success() {
+        return Err(BackupServiceError::CommandFailed(format!(
+            "Mailing the digest failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(())
+}
+
+/// `report digest`: one summary of the window's backups per host instead of per-run noise
+pub async fn execute_digest(
+    config: Config,
+    options: DigestOptions,
+) -> Result<(), BackupServiceError> {
+    config.set_aws_env()?;
+    validate_credentials(&config).await?;
+
+    let until = Utc::now();
+    let since = until - Duration::days(i64::from(options.days.max(1)));
+    let large_change = large_change_threshold()?;
+    let operations = RepositoryOperations::new(config.clone())?;
+
+    let hosts = if options.hosts.is_empty() {
+        operations.get_available_hosts().await?
+    } else {
+        options.hosts.clone()
+    };
+
+    let mut digests = Vec::new();
+    for host in &hosts {
+        info!(host = %host, "Collecting snapshots for digest");
+        let scan = operations.collect_backup_data(host).await?;
+        let mut digest = summarize_host(host, &scan.repos, since, large_change);
+        digest.failures = scan.failures;
+        digests.push(digest);
+    }
+
+    let text = render_text(&digests, since, until)?;
+    if options.json_output {
+        let output = json!({
+            "since": since.to_rfc3339(),
+            "until": until.to_rfc3339(),
+            "hosts": digests,
+        });
+        DisplayFormatter::print_json(&output)?;
+    } else {
+        for line in text.lines() {
+            info!("{}", line);
+        }
+    }
+
+    if !options.send {
+        return Ok(());
+    }
+
+    let webhook = std::env::var(DIGEST_WEBHOOK_ENV_VAR)
+        .ok()
+        .filter(|v| !v.trim().is_empty());
+    let email = std::env::var(DIGEST_EMAIL_ENV_VAR)
+        .ok()
+        .filter(|v| !v.trim().is_empty());
+    if webhook.is_none() && email.is_none() {
+        return Err(BackupServiceError::ConfigurationError(format!(
+            "--send needs a delivery target.\n\nSet {} to an incoming-webhook URL and/or {} to email recipients",
+            DIGEST_WEBHOOK_ENV_VAR, DIGEST_EMAIL_ENV_VAR
+        )));
+    }
+    if let Some(url) = webhook {
+        send_webhook(&url, &text).await?;
+        info!("Digest posted to webhook");
+    }
+    if let Some(recipients) = email {
+        let subject = text.lines().next().unwrap_or("Backup digest");
+        send_email(&recipients, subject, &text).await?;
+        info!(recipients = %recipients, "Digest mailed");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::backup_summary::ResticSummary;
+    use crate::shared::operations::{RepositoryInfo, SnapshotInfo};
+    use std::path::PathBuf;
+
+    fn time(value: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(value)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn repo(path: &str, snapshots: &[(&str, &str, u64)]) -> RepositoryData {
+        RepositoryData {
+            info: RepositoryInfo {
+                native_path: PathBuf::from(path),
+                repo_subpath: format!("system/{}", path.trim_start_matches('/')),
+                category: "system".to_string(),
+            },
+            snapshots: snapshots
+                .iter()
+                .map(|(t, id, added)| SnapshotInfo {
+                    time: time(t),
+                    path: PathBuf::from(path),
+                    id: id.to_string(),
+                    tags: Vec::new(),
+                    summary: Some(ResticSummary {
+                        files_new: 1,
+                        data_added: *added,
+                        ..Default::default()
+                    }),
+                })
+                .collect(),
+            snapshot_count: snapshots.len(),
+        }
+    }
+
+    #[test]
+    fn test_summarize_week() {
+        let repos = vec![
+            repo(
+                "/etc",
+                &[
+                    ("2025-01-01T02:00:00Z", "old", 10),
+                    ("2025-01-10T02:00:00Z", "a1", 100),
+                    ("2025-01-11T02:00:00Z", "a2", 2_000),
+                ],
+            ),
+            repo("/srv/new", &[("2025-01-11T02:05:00Z", "n1", 50)]),
+            repo("/srv/gone", &[("2025-01-02T02:00:00Z", "g1", 5)]),
+        ];
+        let digest = summarize_host("web1", &repos, time("2025-01-08T00:00:00Z"), 1_000);
+
+        assert_eq!(digest.repositories, 3);
+        assert_eq!(digest.snapshots, 3);
+        assert_eq!(digest.active_days, 2);
+        assert_eq!(digest.data_added, 2_150);
+        assert_eq!(digest.files_new, 3);
+        assert_eq!(
+            digest.notable,
+            vec![
+                NotableChange::LargeAddition {
+                    path: "/etc".to_string(),
+                    snapshot: "a2".to_string(),
+                    data_added: 2_000,
+                },
+                NotableChange::NewPath {
+                    path: "/srv/new".to_string()
+                },
+                NotableChange::Missed {
+                    path: "/srv/gone".to_string(),
+                    last_backup: Some("2025-01-02T02:00:00Z".to_string()),
+                },
+            ]
+        );
+        assert!(digest.needs_attention());
+    }
+
+    #[test]
+    fn test_render_marks_hosts_needing_attention() -> Result<(), BackupServiceError> {
+        let quiet = summarize_host(
+            "nas",
+            &[repo("/etc", &[("2025-01-10T02:00:00Z", "a1", 100)])],
+            time("2025-01-01T00:00:00Z"),
+            1_000,
+        );
+        let text = render_text(
+            &[quiet],
+            time("2025-01-08T00:00:00Z"),
+            time("2025-01-15T00:00:00Z"),
+        )?;
+        assert!(
+            text.starts_with("Backup digest 2025-01-08 - 2025-01-15: 1 hosts, 0 need attention")
+        );
+        assert!(text.contains("- nas: 1 snapshots on 1 days across 1 paths"));
+        Ok(())
+    }
+}
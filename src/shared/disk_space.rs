@@ -0,0 +1,55 @@
+use crate::errors::BackupServiceError;
+use std::ffi::CString;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+
+/// `path` itself or its closest existing parent, so targets can be checked before creation
+fn existing_ancestor(path: &Path) -> Option<&Path> {
+    path.ancestors().find(|p| p.exists())
+}
+
+/// Bytes an unprivileged user can still write on the filesystem holding `path`
+pub fn available_bytes(path: &Path) -> Result<u64, BackupServiceError> {
+    let existing = existing_ancestor(path).unwrap_or(Path::new("/"));
+    let c_path = CString::new(existing.as_os_str().as_bytes()).map_err(|_| {
+        BackupServiceError::ConfigurationError(format!(
+            "Path contains a NUL byte: {}",
+            path.display()
+        ))
+    })?;
+    // SAFETY: statvfs only writes into the zero-initialised struct we pass in
+    let stat = unsafe {
+        let mut stat: libc::statvfs = std::mem::zeroed();
+        if libc::statvfs(c_path.as_ptr(), &mut stat) != 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        stat
+    };
+    Ok((stat.f_bavail as u64).saturating_mul(stat.f_frsize as u64))
+}
+
+/// Whether two paths (or their closest existing parents) live on the same filesystem
+pub fn same_filesystem(a: &Path, b: &Path) -> bool {
+    let device = |path: &Path| {
+        existing_ancestor(path)
+            .and_then(|p| p.metadata().ok())
+            .map(|m| m.dev())
+    };
+    matches!((device(a), device(b)), (Some(a), Some(b)) if a == b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_paths_not_created_yet() -> Result<(), BackupServiceError> {
+        let dir = tempfile::tempdir()?;
+        let missing = dir.path().join("restore/target");
+        assert!(available_bytes(&missing)? > 0);
+        assert!(same_filesystem(dir.path(), &missing));
+        assert!(!same_filesystem(dir.path(), Path::new("/proc")));
+        Ok(())
+    }
+}
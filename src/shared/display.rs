@@ -1,9 +1,22 @@
 use crate::errors::BackupServiceError;
+use crate::i18n::{t, t_args};
 use crate::repository::BackupRepo;
+use crate::shared::backup_progress::format_eta;
+use crate::shared::budgets::BudgetAlert;
 use crate::shared::constants::{CATEGORY_DOCKER_VOLUME, CATEGORY_SYSTEM, CATEGORY_USER_HOME};
-use crate::shared::operations::SnapshotInfo;
+use crate::shared::doctor_workflow::{CheckStatus, DoctorCheck};
+use crate::shared::find_workflow::FindMatch;
+use crate::shared::history_workflow::PathTrend;
+use crate::shared::ls_workflow::{LsEntry, LsSnapshot};
+use crate::shared::operations::{DiscoveryFailure, SnapshotInfo};
+use crate::shared::snapshots_workflow::SnapshotRow;
+use crate::shared::stats_workflow::{RepoStats, StatsTotals};
+use crate::shared::status_workflow::PathStatus;
+use crate::shared::timestamps::format_local;
+use crate::utils::format_bytes;
 use std::collections::HashMap;
-use tracing::info;
+use std::io::IsTerminal;
+use tracing::{info, warn};
 
 /// Display formatter for backup summaries and listings
 pub struct DisplayFormatter;
@@ -20,11 +33,30 @@ impl DisplayFormatter {
         Ok(())
     }
 
+    /// Display parts of the repository tree that could not be listed (partial results)
+    pub fn display_discovery_failures(
+        failures: &[DiscoveryFailure],
+    ) -> Result<(), BackupServiceError> {
+        if failures.is_empty() {
+            return Ok(());
+        }
+
+        let header = t("list-incomplete-header");
+        warn!("{}", header);
+        warn!("{}", "=".repeat(header.chars().count()));
+        for failure in failures {
+            warn!("  {}", failure);
+        }
+        info!("");
+        Ok(())
+    }
+
     /// Display backup paths summary section
     pub fn display_backup_paths_summary(repos: &[BackupRepo]) -> Result<(), BackupServiceError> {
         info!("");
-        info!("BACKUP PATHS SUMMARY:");
-        info!("====================");
+        let header = t("list-paths-header");
+        info!("{}", header);
+        info!("{}", "=".repeat(header.chars().count()));
 
         // Group by category
         let categories = Self::group_repos_by_category(repos)?;
@@ -40,11 +72,12 @@ impl DisplayFormatter {
     /// Display snapshot timeline section
     pub fn display_snapshot_timeline(snapshots: &[SnapshotInfo]) -> Result<(), BackupServiceError> {
         info!("");
-        info!("SNAPSHOT TIMELINE:");
-        info!("==================");
+        let header = t("list-timeline-header");
+        info!("{}", header);
+        info!("{}", "=".repeat(header.chars().count()));
 
         if snapshots.is_empty() {
-            info!("No snapshots found");
+            info!("{}", t("list-no-snapshots"));
             return Ok(());
         }
 
@@ -73,9 +106,15 @@ impl DisplayFormatter {
         let user_repos = categories.get(CATEGORY_USER_HOME).unwrap_or(&empty_vec);
 
         info!("");
-        info!("User Home ({} paths):", user_repos.len());
+        info!(
+            "{}",
+            t_args(
+                "list-category-user-home",
+                &[("count", user_repos.len().to_string())]
+            )
+        );
         if user_repos.is_empty() {
-            info!("  None");
+            info!("  {}", t("list-none"));
         } else {
             for repo in user_repos {
                 Self::display_repo_entry(repo)?;
@@ -93,9 +132,15 @@ impl DisplayFormatter {
         let docker_repos = categories.get(CATEGORY_DOCKER_VOLUME).unwrap_or(&empty_vec);
 
         info!("");
-        info!("Docker Volumes ({} paths):", docker_repos.len());
+        info!(
+            "{}",
+            t_args(
+                "list-category-docker",
+                &[("count", docker_repos.len().to_string())]
+            )
+        );
         if docker_repos.is_empty() {
-            info!("  None");
+            info!("  {}", t("list-none"));
         } else {
             for repo in docker_repos {
                 Self::display_repo_entry(repo)?;
@@ -113,9 +158,15 @@ impl DisplayFormatter {
         let system_repos = categories.get(CATEGORY_SYSTEM).unwrap_or(&empty_vec);
 
         info!("");
-        info!("System ({} paths):", system_repos.len());
+        info!(
+            "{}",
+            t_args(
+                "list-category-system",
+                &[("count", system_repos.len().to_string())]
+            )
+        );
         if system_repos.is_empty() {
-            info!("  None");
+            info!("  {}", t("list-none"));
         } else {
             for repo in system_repos {
                 Self::display_repo_entry(repo)?;
@@ -158,8 +209,12 @@ impl DisplayFormatter {
 
         for time in times.iter().take(20) {
             if let Some(snaps) = timeline.get(time) {
+                // Grouped by UTC minute; shown in local time with the offset
+                let label = snaps
+                    .first()
+                    .map_or_else(|| time.clone(), |s| format_local(s.time));
                 info!("");
-                info!("{}:", time);
+                info!("{}:", label);
                 for snap in snaps {
                     Self::display_snapshot_entry(snap)?;
                 }
@@ -168,9 +223,371 @@ impl DisplayFormatter {
 
         if times.len() > 20 {
             info!("");
-            info!("... and {} more time points", times.len() - 20);
+            info!(
+                "{}",
+                t_args(
+                    "list-more-time-points",
+                    &[("count", (times.len() - 20).to_string())]
+                )
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Display the filtered snapshot list of `snapshots`, newest first
+    pub fn display_snapshot_table(rows: &[SnapshotRow]) -> Result<(), BackupServiceError> {
+        info!("");
+        let header = t("snapshots-header");
+        info!("{}", header);
+        info!("{}", "=".repeat(header.chars().count()));
+
+        if rows.is_empty() {
+            info!("{}", t("list-no-snapshots"));
+            return Ok(());
+        }
+
+        for row in rows {
+            info!(
+                "  {:<23} {:<9} {:<14} {:<50} {}",
+                format_local(row.time),
+                row.id,
+                row.category,
+                row.path,
+                row.tags.join(",")
+            );
+        }
+        info!("");
+        info!(
+            "{}",
+            t_args("snapshots-count", &[("count", rows.len().to_string())])
+        );
+        Ok(())
+    }
+
+    /// Display `find` matches, newest snapshot first
+    pub fn display_find_results(rows: &[FindMatch]) -> Result<(), BackupServiceError> {
+        info!("");
+        let header = t("find-header");
+        info!("{}", header);
+        info!("{}", "=".repeat(header.chars().count()));
+
+        if rows.is_empty() {
+            info!("{}", t("find-no-matches"));
+            return Ok(());
+        }
+
+        for row in rows {
+            let size = match row.size {
+                Some(size) => format_bytes(size)?,
+                None => row.kind.clone(),
+            };
+            info!(
+                "  {:<23} {:<9} {:<30} {:>10}  {}",
+                format_local(row.time),
+                row.snapshot_id,
+                row.repo_subpath,
+                size,
+                row.path
+            );
+        }
+        let mut snapshots: Vec<_> = rows.iter().map(|r| &r.snapshot_id).collect();
+        snapshots.sort();
+        snapshots.dedup();
+        info!("");
+        info!(
+            "{}",
+            t_args(
+                "find-count",
+                &[
+                    ("count", rows.len().to_string()),
+                    ("snapshots", snapshots.len().to_string())
+                ]
+            )
+        );
+        Ok(())
+    }
+
+    /// Display the size of every repository, largest first, and the host total
+    pub fn display_stats_table(
+        repos: &[RepoStats],
+        totals: &StatsTotals,
+    ) -> Result<(), BackupServiceError> {
+        info!("");
+        let header = t("stats-header");
+        info!("{}", header);
+        info!("{}", "=".repeat(header.chars().count()));
+
+        if repos.is_empty() {
+            info!("{}", t("stats-no-repositories"));
+            return Ok(());
+        }
+
+        for repo in repos {
+            info!(
+                "  {:>10} {:>10} {:>10} {:>5}  {}",
+                format_bytes(repo.raw_data_bytes)?,
+                format_bytes(repo.restore_size_bytes)?,
+                repo.file_count,
+                repo.snapshot_count,
+                repo.path.display()
+            );
         }
+        info!("");
+        info!(
+            "{}",
+            t_args(
+                "stats-total",
+                &[
+                    ("repos", totals.repositories.to_string()),
+                    ("raw", format_bytes(totals.raw_data_bytes)?),
+                    ("restore", format_bytes(totals.restore_size_bytes)?),
+                    ("files", totals.file_count.to_string()),
+                ]
+            )
+        );
+        Ok(())
+    }
+
+    /// Doctor report: one pass/warn/fail line per check, colored on a terminal, hints below
+    pub fn display_doctor_report(checks: &[DoctorCheck]) -> Result<(), BackupServiceError> {
+        let color = std::io::stdout().is_terminal();
+        info!("");
+        let header = t("doctor-header");
+        info!("{}", header);
+        info!("{}", "=".repeat(header.chars().count()));
+        for check in checks {
+            let (label, code) = match check.status {
+                CheckStatus::Pass => ("PASS", "32"),
+                CheckStatus::Warn => ("WARN", "33"),
+                CheckStatus::Fail => ("FAIL", "31"),
+            };
+            let label = if color {
+                format!("\x1b[{}m{}\x1b[0m", code, label)
+            } else {
+                label.to_string()
+            };
+            info!("  [{}] {:<16} {}", label, check.name, check.detail);
+            if let Some(hint) = &check.hint {
+                info!("         {:<16} {}: {}", "", t("hint-prefix"), hint);
+            }
+        }
+        let count = |status| checks.iter().filter(|c| c.status == status).count();
+        info!("");
+        info!(
+            "{}",
+            t_args(
+                "doctor-summary",
+                &[
+                    ("passed", count(CheckStatus::Pass).to_string()),
+                    ("warnings", count(CheckStatus::Warn).to_string()),
+                    ("failed", count(CheckStatus::Fail).to_string()),
+                ]
+            )
+        );
+        Ok(())
+    }
+
+    /// Per-path trends from the backup history, regressions marked with `!`
+    pub fn display_history(
+        file: &std::path::Path,
+        trends: &[PathTrend],
+    ) -> Result<(), BackupServiceError> {
+        let local = |time: &str| {
+            chrono::DateTime::parse_from_rfc3339(time)
+                .map(|t| format_local(t.with_timezone(&chrono::Utc)))
+                .unwrap_or_else(|_| time.to_string())
+        };
+        info!("");
+        let header = t_args("history-header", &[("file", file.display().to_string())]);
+        info!("{}", header);
+        info!("{}", "=".repeat(header.chars().count()));
+        if trends.is_empty() {
+            info!("{}", t("history-empty"));
+            return Ok(());
+        }
+        for trend in trends {
+            info!("");
+            info!("{} {}", trend.host, trend.path);
+            info!(
+                "  {}",
+                t_args(
+                    "history-runs",
+                    &[
+                        ("runs", trend.runs.to_string()),
+                        ("failures", trend.failures.to_string()),
+                        ("status", trend.last_status.clone()),
+                        ("time", local(&trend.last_run)),
+                    ]
+                )
+            );
+            if trend.last_status != "completed" {
+                match &trend.last_success {
+                    Some(time) => info!(
+                        "  {}",
+                        t_args("history-last-success", &[("time", local(time))])
+                    ),
+                    None => info!("  {}", t("history-never-succeeded")),
+                }
+            }
+            info!(
+                "  {}",
+                t_args(
+                    "history-growth",
+                    &[
+                        ("total", format_bytes(trend.bytes_added)?),
+                        (
+                            "average",
+                            format_bytes(trend.avg_bytes_added.unwrap_or(0.0) as u64)?
+                        ),
+                        (
+                            "duration",
+                            format_eta(trend.avg_duration_secs.unwrap_or(0.0).round() as u64)
+                        ),
+                    ]
+                )
+            );
+            for regression in &trend.regressions {
+                warn!("  ! {}", regression);
+            }
+        }
+        info!("");
+        Ok(())
+    }
+
+    /// One block per path: last success and its age, delay, and the last run when it failed
+    pub fn display_status(
+        host: &str,
+        max_age: &str,
+        statuses: &[PathStatus],
+        budget_alerts: &[BudgetAlert],
+    ) -> Result<(), BackupServiceError> {
+        let local = |time: &str| {
+            chrono::DateTime::parse_from_rfc3339(time)
+                .map(|t| format_local(t.with_timezone(&chrono::Utc)))
+                .unwrap_or_else(|_| time.to_string())
+        };
+        info!("");
+        let header = t_args(
+            "status-header",
+            &[("host", host.to_string()), ("max_age", max_age.to_string())],
+        );
+        info!("{}", header);
+        info!("{}", "=".repeat(header.chars().count()));
+        for status in statuses {
+            info!("");
+            info!("{}", status.path);
+            let line = match (&status.last_success, status.age_secs) {
+                (Some(time), Some(age)) => {
+                    let key = if status.stale {
+                        "status-stale"
+                    } else {
+                        "status-ok"
+                    };
+                    t_args(
+                        key,
+                        &[
+                            ("time", local(time)),
+                            ("age", format_eta(age.max(0) as u64)),
+                        ],
+                    )
+                }
+                _ => t("status-never"),
+            };
+            if status.stale {
+                warn!("  {}", line);
+            } else {
+                info!("  {}", line);
+            }
+            if status.behind_secs > 0 {
+                info!(
+                    "  {}",
+                    t_args(
+                        "status-behind",
+                        &[("behind", format_eta(status.behind_secs as u64))]
+                    )
+                );
+            }
+            if let (Some(run_status), Some(time)) = (&status.last_status, &status.last_run)
+                && status.last_run != status.last_success
+            {
+                info!(
+                    "  {}",
+                    t_args(
+                        "status-last-run",
+                        &[("status", run_status.clone()), ("time", local(time))]
+                    )
+                );
+            }
+            for warning in &status.warnings {
+                warn!("  ! {}", warning);
+            }
+        }
+        if !budget_alerts.is_empty() {
+            info!("");
+            warn!("{}", t("status-budget-header"));
+            for alert in budget_alerts {
+                warn!("  {}: {}", alert.path.display(), alert.describe());
+            }
+        }
+        info!("");
+        Ok(())
+    }
+
+    /// Snapshot contents below a directory as an indented tree, directories marked with `/`
+    pub fn display_ls(
+        dir: &std::path::Path,
+        snapshot: Option<&LsSnapshot>,
+        entries: &[LsEntry],
+    ) -> Result<(), BackupServiceError> {
+        info!("");
+        let header = match snapshot {
+            Some(snapshot) => t_args(
+                "ls-header",
+                &[
+                    ("path", dir.display().to_string()),
+                    ("snapshot", snapshot.id.clone()),
+                    ("time", format_local(snapshot.time)),
+                ],
+            ),
+            None => dir.display().to_string(),
+        };
+        info!("{}", header);
+        info!("{}", "=".repeat(header.chars().count()));
+
+        if entries.is_empty() {
+            info!("{}", t("ls-empty"));
+            return Ok(());
+        }
+
+        for entry in entries {
+            let name = entry.path.rsplit('/').next().unwrap_or(&entry.path);
+            let indent = "  ".repeat(entry.depth);
+            match entry.kind.as_str() {
+                "dir" => info!("{}{}/", indent, name),
+                _ => {
+                    let size = entry
+                        .size
+                        .map(format_bytes)
+                        .transpose()?
+                        .unwrap_or_default();
+                    info!("{}{:<40} {:>10}", indent, name, size);
+                }
+            }
+        }
+        info!("");
+        info!(
+            "{}",
+            t_args("ls-count", &[("count", entries.len().to_string())])
+        );
+        Ok(())
+    }
 
+    /// Print a command result as JSON on stdout, bare so scripts can parse it
+    ///
+    /// In JSON mode log lines go to stderr (see `--json`), leaving stdout to the result.
+    pub fn print_json(output: &impl serde::Serialize) -> Result<(), BackupServiceError> {
+        println!("{}", serde_json::to_string_pretty(output)?);
         Ok(())
     }
 
@@ -197,6 +614,8 @@ mod tests {
             time,
             path: PathBuf::from(path),
             id: id.to_string(),
+            tags: Vec::new(),
+            summary: None,
         }
     }
 
@@ -0,0 +1,501 @@
+use crate::config::{Config, RepoBackend};
+use crate::errors::BackupServiceError;
+use crate::shared::commands::ResticCommandExecutor;
+use crate::shared::dependencies::{Dependency, DependencyStatus, probe};
+use crate::shared::display::DisplayFormatter;
+use crate::shared::http;
+use crate::shared::operations::{DEFAULT_SCAN_JOBS, RepositoryOperations, SCAN_JOBS_ENV_VAR};
+use crate::shared::repo_store::RepoStore;
+use crate::shared::unlock_workflow::{RepoLock, StaleLockPolicy, unlock_stale};
+use crate::utils::resolve_jobs;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::json;
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+/// Clock offset at which S3 rejects requests (RequestTimeTooSkewed)
+const SKEW_FAIL_SECS: i64 = 15 * 60;
+/// Clock offset worth a warning: lock ages and snapshot times drift with it
+const SKEW_WARN_SECS: i64 = 60;
+
+/// `doctor` options
+#[derive(Debug, Clone, Default)]
+pub struct DoctorOptions {
+    pub json_output: bool,
+}
+
+/// Outcome of one check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+/// One line of the doctor report
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DoctorCheck {
+    pub name: String,
+    pub status: CheckStatus,
+    pub detail: String,
+    /// What to do about a warning or failure
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hint: Option<String>,
+}
+
+impl DoctorCheck {
+    fn pass(name: &str, detail: String) -> Self {
+        Self {
+            name: name.to_string(),
+            status: CheckStatus::Pass,
+            detail,
+            hint: None,
+        }
+    }
+
+    fn warn(name: &str, detail: String, hint: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            status: CheckStatus::Warn,
+            detail,
+            hint: Some(hint.into()),
+        }
+    }
+
+    fn fail(name: &str, detail: String, hint: Option<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            status: CheckStatus::Fail,
+            detail,
+            hint,
+        }
+    }
+}
+
+/// Report a binary: missing fails when `required`, otherwise only warns
+pub fn binary_check(
+    dep: &Dependency,
+    status: DependencyStatus,
+    version: Option<String>,
+    required: bool,
+) -> DoctorCheck {
+    let version = version.unwrap_or_else(|| "unknown version".to_string());
+    match status {
+        DependencyStatus::Ok => DoctorCheck::pass(&dep.binary, version),
+        DependencyStatus::Outdated { found } if dep.enforce_min_version => DoctorCheck::fail(
+            &dep.binary,
+            format!(
+                "{} is older than {}, the oldest supported release",
+                found,
+                dep.min_version.unwrap_or(found)
+            ),
+            Some(format!(
+                "{}, or `restic-backup-service self install-restic`",
+                dep.install_hint()
+            )),
+        ),
+        DependencyStatus::Outdated { found } => DoctorCheck::warn(
+            &dep.binary,
+            format!(
+                "{} is older than {}",
+                found,
+                dep.min_version.unwrap_or(found)
+            ),
+            format!("Some features may fail; upgrade: {}", dep.install_hint()),
+        ),
+        DependencyStatus::Missing if required => DoctorCheck::fail(
+            &dep.binary,
+            format!("not found in PATH (needed for {})", dep.purpose),
+            Some(dep.install_hint()),
+        ),
+        DependencyStatus::Missing => DoctorCheck::warn(
+            &dep.binary,
+            format!("not found in PATH (only for {})", dep.purpose),
+            dep.install_hint(),
+        ),
+    }
+}
+
+/// Judge the offset between this machine and the storage (positive: local clock ahead)
+pub fn clock_check(skew_secs: i64) -> DoctorCheck {
+    let detail = format!("local clock differs from the storage by {}s", skew_secs);
+    let hint = "Sync time, e.g. `timedatectl set-ntp true`";
+    match skew_secs.abs() {
+        secs if secs >= SKEW_FAIL_SECS => DoctorCheck::fail("clock", detail, Some(hint.into())),
+        secs if secs >= SKEW_WARN_SECS => DoctorCheck::warn("clock", detail, hint),
+        _ => DoctorCheck::pass("clock", detail),
+    }
+}
+
+/// The `Date:` header of an HTTP response head
+pub fn parse_date_header(headers: &str) -> Option<DateTime<Utc>> {
+    headers.lines().find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        if !name.trim().eq_ignore_ascii_case("date") {
+            return None;
+        }
+        DateTime::parse_from_rfc2822(value.trim())
+            .ok()
+            .map(|date| date.with_timezone(&Utc))
+    })
+}
+
+/// Compare the local clock with the `Date` header of the S3 endpoint
+async fn s3_clock_check(config: &Config) -> DoctorCheck {
+    let endpoint = match config.s3_endpoint() {
+        Ok(endpoint) if !endpoint.is_empty() => endpoint,
+        _ => {
+            return DoctorCheck::warn(
+                "clock",
+                "no S3 endpoint to compare with".to_string(),
+                "Set AWS_S3_ENDPOINT or use an s3:https://... RESTIC_REPO_BASE",
+            );
+        }
+    };
+    let sent = Utc::now();
+    // Any response carries a Date header, an anonymous 403 included
+    let args = ["--no-fail", "--head", "--max-time", "10", &endpoint].map(String::from);
+    let output = http::curl(&args, "", "Clock check").await;
+    let received = Utc::now();
+    let server = output
+        .ok()
+        .and_then(|o| parse_date_header(&String::from_utf8_lossy(&o.stdout)));
+    match server {
+        // The header is truncated to seconds; measure against the middle of the request
+        Some(server) => clock_check((sent + (received - sent) / 2 - server).num_seconds()),
+        None => DoctorCheck::warn(
+            "clock",
+            format!("could not read the time of {}", endpoint),
+            "Install curl or check that the endpoint answers HTTP requests",
+        ),
+    }
+}
+
+/// What opening one repository found
+struct RepoProbe {
+    repo_subpath: String,
+    result: Result<Vec<RepoLock>, BackupServiceError>,
+}
+
+/// Open a repository and read its locks
+async fn probe_repository(
+    config: Config,
+    hostname: String,
+    repo_subpath: String,
+    policy: StaleLockPolicy,
+) -> RepoProbe {
+    let result = async {
+        let repo_url = config.get_repo_url_for_host(&hostname, &repo_subpath)?;
+        let restic_cmd = ResticCommandExecutor::new(config, repo_url)?;
+        if !restic_cmd.repo_exists().await? {
+            return Err(BackupServiceError::RepositoryNotFound(repo_subpath.clone()));
+        }
+        // A dry run only classifies the locks
+        Ok(unlock_stale(&restic_cmd, &policy, true).await?.0)
+    }
+    .await;
+    RepoProbe {
+        repo_subpath,
+        result,
+    }
+}
+
+/// Sum up the repository probes as the `repositories` and `locks` checks
+fn repository_checks(hostname: &str, mut probes: Vec<RepoProbe>) -> Vec<DoctorCheck> {
+    probes.sort_by(|a, b| a.repo_subpath.cmp(&b.repo_subpath));
+    let failed: Vec<(&str, &BackupServiceError)> = probes
+        .iter()
+        .filter_map(|p| {
+            p.result
+                .as_ref()
+                .err()
+                .map(|e| (p.repo_subpath.as_str(), e))
+        })
+        .collect();
+    let repositories = if let Some((_, first)) = failed.first() {
+        let names: Vec<String> = failed
+            .iter()
+            .map(|(subpath, e)| format!("{} ({})", subpath, e))
+            .collect();
+        DoctorCheck::fail(
+            "repositories",
+            format!(
+                "{} of {} cannot be opened: {}",
+                failed.len(),
+                probes.len(),
+                names.join(", ")
+            ),
+            first.hint(),
+        )
+    } else {
+        DoctorCheck::pass("repositories", format!("{} open", probes.len()))
+    };
+
+    let locked: Vec<(&str, &Vec<RepoLock>)> = probes
+        .iter()
+        .filter_map(|p| match &p.result {
+            Ok(locks) if !locks.is_empty() => Some((p.repo_subpath.as_str(), locks)),
+            _ => None,
+        })
+        .collect();
+    let locks = if locked.is_empty() {
+        DoctorCheck::pass("locks", "no locks".to_string())
+    } else {
+        let names: Vec<&str> = locked.iter().map(|(subpath, _)| *subpath).collect();
+        let held = locked
+            .iter()
+            .any(|(_, locks)| locks.iter().any(|l| l.kept_because.is_some()));
+        let (detail, hint) = if held {
+            (
+                "held by a running or recent operation",
+                format!(
+                    "Wait for it to finish; `restic-backup-service unlock -H {} --dry-run` shows who holds them",
+                    hostname
+                ),
+            )
+        } else {
+            (
+                "stale",
+                format!(
+                    "Remove them with `restic-backup-service unlock -H {}`",
+                    hostname
+                ),
+            )
+        };
+        DoctorCheck::warn("locks", format!("{}: {}", names.join(", "), detail), hint)
+    };
+    vec![repositories, locks]
+}
+
+/// Check binaries, credentials, storage, every repository, locks and the clock
+pub async fn execute_doctor_workflow(
+    config: Config,
+    host: Option<String>,
+    options: DoctorOptions,
+) -> Result<(), BackupServiceError> {
+    let hostname = host.unwrap_or_else(|| config.hostname.clone());
+    let backend = config.backend()?;
+    let mut checks = Vec::new();
+
+    let restic = Dependency::restic();
+    let (status, version) = probe(&restic);
+    checks.push(binary_check(
+        &restic,
+        status,
+        version.map(|v| v.to_string()),
+        true,
+    ));
+    if backend == RepoBackend::S3 {
+        let aws = Dependency::aws_cli();
+        let (status, version) = probe(&aws);
+        checks.push(binary_check(
+            &aws,
+            status,
+            version.map(|v| v.to_string()),
+            false,
+        ));
+    }
+
+    config.set_aws_env()?;
+    let store = RepoStore::new(config.clone())?;
+    let reachable = match store.validate().await {
+        Ok(()) => {
+            checks.push(DoctorCheck::pass(
+                "credentials",
+                "accepted by the storage".to_string(),
+            ));
+            true
+        }
+        Err(e) => {
+            checks.push(DoctorCheck::fail("credentials", e.to_string(), e.hint()));
+            false
+        }
+    };
+
+    if reachable {
+        let operations = RepositoryOperations::new(config.clone())?;
+        match operations.get_available_hosts().await {
+            Ok(hosts) if hosts.contains(&hostname) => checks.push(DoctorCheck::pass(
+                "repository base",
+                format!(
+                    "{} reachable, {} hosts",
+                    config.restic_repo_base,
+                    hosts.len()
+                ),
+            )),
+            Ok(hosts) => checks.push(DoctorCheck::warn(
+                "repository base",
+                format!(
+                    "{} reachable, but host {} has no backups ({} other hosts)",
+                    config.restic_repo_base,
+                    hostname,
+                    hosts.len()
+                ),
+                "Run a backup first, or pass -H with one of `restic-backup-service hosts`",
+            )),
+            Err(e) => checks.push(DoctorCheck::fail(
+                "repository base",
+                e.to_string(),
+                e.hint(),
+            )),
+        }
+
+        match operations.discover_all_repositories(&hostname).await {
+            Ok(discovery) => {
+                if !discovery.failures.is_empty() {
+                    let scopes: Vec<String> =
+                        discovery.failures.iter().map(|f| f.to_string()).collect();
+                    checks.push(DoctorCheck::warn(
+                        "discovery",
+                        scopes.join("; "),
+                        "Parts of the repository tree could not be listed; check the storage permissions",
+                    ));
+                }
+                let policy = StaleLockPolicy::resolve(None)?;
+                let permits = Arc::new(Semaphore::new(resolve_jobs(
+                    None,
+                    SCAN_JOBS_ENV_VAR,
+                    DEFAULT_SCAN_JOBS,
+                )?));
+                let mut tasks = JoinSet::new();
+                for repo in discovery.repos {
+                    let probe = probe_repository(
+                        config.clone(),
+                        hostname.clone(),
+                        repo.repo_subpath,
+                        policy.clone(),
+                    );
+                    let permits = Arc::clone(&permits);
+                    tasks.spawn(async move {
+                        let _permit = permits.acquire_owned().await;
+                        probe.await
+                    });
+                }
+                let mut probes = Vec::new();
+                while let Some(joined) = tasks.join_next().await {
+                    probes.push(joined.map_err(|e| {
+                        BackupServiceError::CommandFailed(format!("Task join error: {}", e))
+                    })?);
+                }
+                checks.extend(repository_checks(&hostname, probes));
+            }
+            Err(e) => checks.push(DoctorCheck::fail("repositories", e.to_string(), e.hint())),
+        }
+    }
+
+    if backend == RepoBackend::S3 {
+        checks.push(s3_clock_check(&config).await);
+    }
+
+    let count = |status| checks.iter().filter(|c| c.status == status).count();
+    let (passed, warnings, failed) = (
+        count(CheckStatus::Pass),
+        count(CheckStatus::Warn),
+        count(CheckStatus::Fail),
+    );
+    if options.json_output {
+        let output = json!({
+            "host": hostname,
+            "checks": checks,
+            "passed": passed,
+            "warnings": warnings,
+            "failed": failed,
+        });
+        DisplayFormatter::print_json(&output)?;
+    } else {
+        DisplayFormatter::display_doctor_report(&checks)?;
+    }
+
+    if failed > 0 {
+        return Err(BackupServiceError::CommandFailed(format!(
+            "{} of {} doctor checks failed",
+            failed,
+            checks.len()
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clock_check() {
+        assert_eq!(clock_check(3).status, CheckStatus::Pass);
+        assert_eq!(clock_check(-120).status, CheckStatus::Warn);
+        assert_eq!(clock_check(SKEW_FAIL_SECS).status, CheckStatus::Fail);
+
+        let headers =
+            "HTTP/1.1 403 Forbidden\r\ndate: Sun, 12 Jan 2025 03:00:00 GMT\r\nServer: AmazonS3\r\n";
+        assert_eq!(
+            parse_date_header(headers).map(|d| d.to_rfc3339()),
+            Some("2025-01-12T03:00:00+00:00".to_string())
+        );
+        assert_eq!(parse_date_header("HTTP/1.1 200 OK\r\n"), None);
+    }
+
+    #[test]
+    fn test_repository_checks() {
+        let lock = |kept: Option<&str>| RepoLock {
+            id: "4f1c2a9e".to_string(),
+            time: Utc::now(),
+            exclusive: false,
+            hostname: "web1".to_string(),
+            username: "root".to_string(),
+            pid: 42,
+            kept_because: kept.map(str::to_string),
+        };
+        let probe = |subpath: &str, result| RepoProbe {
+            repo_subpath: subpath.to_string(),
+            result,
+        };
+
+        let clean = repository_checks(
+            "web1",
+            vec![
+                probe("system/etc", Ok(Vec::new())),
+                probe("user_home/tim", Ok(Vec::new())),
+            ],
+        );
+        assert_eq!(clean[0].status, CheckStatus::Pass);
+        assert_eq!(clean[0].detail, "2 open");
+        assert_eq!(clean[1].status, CheckStatus::Pass);
+
+        let checks = repository_checks(
+            "web1",
+            vec![
+                probe("user_home/tim", Ok(vec![lock(None)])),
+                probe(
+                    "system/etc",
+                    Err(BackupServiceError::WrongPassword("system/etc".to_string())),
+                ),
+            ],
+        );
+        assert_eq!(checks[0].status, CheckStatus::Fail);
+        assert!(
+            checks[0]
+                .detail
+                .starts_with("1 of 2 cannot be opened: system/etc")
+        );
+        assert!(checks[0].hint.is_some());
+        assert_eq!(checks[1].status, CheckStatus::Warn);
+        assert_eq!(checks[1].detail, "user_home/tim: stale");
+
+        let held = repository_checks(
+            "web1",
+            vec![probe(
+                "user_home/tim",
+                Ok(vec![lock(None), lock(Some("process 42 is running"))]),
+            )],
+        );
+        assert!(
+            held[1]
+                .detail
+                .ends_with("held by a running or recent operation")
+        );
+    }
+}
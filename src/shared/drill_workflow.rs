@@ -0,0 +1,397 @@
+use crate::config::Config;
+use crate::errors::BackupServiceError;
+use crate::shared::commands::ResticCommandExecutor;
+use crate::shared::display::DisplayFormatter;
+use crate::shared::logs_workflow::log_dir;
+use crate::shared::metrics::{self, DrillMetrics};
+use crate::shared::operations::{RepositoryOperations, SnapshotCollector, UnscannedRepository};
+use crate::utils::{format_bytes, validate_credentials};
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use tracing::{error, info, warn};
+
+/// Env var with the number of repositories restored per drill (default 3)
+pub const DRILL_SAMPLE_ENV_VAR: &str = "DRILL_SAMPLE";
+/// Env var overriding where drills restore to (default: /tmp/restic/drill)
+pub const DRILL_SCRATCH_ENV_VAR: &str = "DRILL_SCRATCH_DIR";
+/// Env var overriding the drill history (default: `<RBS_LOG_DIR>/drill-history.jsonl`)
+pub const DRILL_HISTORY_ENV_VAR: &str = "DRILL_HISTORY_FILE";
+
+const DEFAULT_SAMPLE: usize = 3;
+const DEFAULT_SCRATCH_DIR: &str = "/tmp/restic/drill";
+
+/// `drill` options
+#[derive(Debug, Clone, Default)]
+pub struct DrillOptions {
+    /// Repositories to restore; DRILL_SAMPLE, else 3
+    pub sample: Option<usize>,
+    /// Scratch directory instead of DRILL_SCRATCH_DIR
+    pub scratch_dir: Option<PathBuf>,
+    /// Leave the restored files in place for inspection
+    pub keep: bool,
+    pub json_output: bool,
+}
+
+/// Outcome of restoring and verifying one repository's latest snapshot
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DrillResult {
+    pub repo_subpath: String,
+    pub snapshot_id: String,
+    pub snapshot_time: String,
+    /// Files the snapshot recorded (restic >= 0.17 keeps a summary with the snapshot)
+    pub files_expected: Option<u64>,
+    pub files_restored: u64,
+    pub bytes_restored: u64,
+    pub duration_secs: f64,
+    pub passed: bool,
+    pub error: Option<String>,
+}
+
+/// One line of the drill history
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DrillRecord {
+    /// RFC 3339
+    pub finished_at: String,
+    pub host: String,
+    pub passed: bool,
+    pub repositories: Vec<DrillResult>,
+}
+
+pub fn drill_history_file() -> PathBuf {
+    std::env::var(DRILL_HISTORY_ENV_VAR)
+        .ok()
+        .filter(|v| !v.trim().is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| log_dir().join("drill-history.jsonl"))
+}
+
+fn sample_size(flag: Option<usize>) -> Result<usize, BackupServiceError> {
+    let sample = match flag {
+        Some(n) => n,
+        None => match std::env::var(DRILL_SAMPLE_ENV_VAR) {
+            Ok(v) if !v.trim().is_empty() => v.trim().parse().map_err(|_| {
+                BackupServiceError::ConfigurationError(format!(
+                    "Invalid {}: {}.\n\nExpected the number of repositories per drill, e.g. 3",
+                    DRILL_SAMPLE_ENV_VAR, v
+                ))
+            })?,
+            _ => DEFAULT_SAMPLE,
+        },
+    };
+    if sample == 0 {
+        return Err(BackupServiceError::ConfigurationError(
+            "A drill needs at least one repository.\n\nPass --sample 1 or more".to_string(),
+        ));
+    }
+    Ok(sample)
+}
+
+fn scratch_dir(flag: Option<PathBuf>) -> PathBuf {
+    flag.or_else(|| {
+        std::env::var(DRILL_SCRATCH_ENV_VAR)
+            .ok()
+            .filter(|v| !v.trim().is_empty())
+            .map(PathBuf::from)
+    })
+    .unwrap_or_else(|| PathBuf::from(DEFAULT_SCRATCH_DIR))
+}
+
+/// Repositories in random order; drills walk it until enough have snapshots
+fn shuffle(mut repos: Vec<UnscannedRepository>) -> Vec<UnscannedRepository> {
+    // RandomState is seeded per process, so every drill picks a different sample
+    let state = RandomState::new();
+    repos.sort_by_key(|repo| state.hash_one(&repo.repo_subpath));
+    repos
+}
+
+/// Non-directory entries and their bytes below `dir` (symlinks are counted, not followed)
+fn count_restored(dir: &Path) -> Result<(u64, u64), BackupServiceError> {
+    let mut files = 0;
+    let mut bytes = 0;
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        let metadata = std::fs::symlink_metadata(&current)?;
+        if metadata.is_dir() {
+            for entry in std::fs::read_dir(&current)? {
+                pending.push(entry?.path());
+            }
+        } else {
+            files += 1;
+            bytes += metadata.len();
+        }
+    }
+    Ok((files, bytes))
+}
+
+/// Compare what landed on disk with what the snapshot recorded
+fn verify_counts(expected: Option<u64>, restored: u64) -> Result<(), String> {
+    match expected {
+        Some(expected) if expected != restored => Err(format!(
+            "snapshot records {} files, {} were restored",
+            expected, restored
+        )),
+        None if restored == 0 => Err("nothing was restored".to_string()),
+        _ => Ok(()),
+    }
+}
+
+/// Append one record; the history is JSON lines so a broken write never corrupts older drills
+fn append_history(file: &Path, record: &DrillRecord) -> Result<(), BackupServiceError> {
+    if let Some(dir) = file.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut history = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(file)?;
+    writeln!(history, "{}", serde_json::to_string(record)?)?;
+    Ok(())
+}
+
+/// Unix time of the last drill of `host` in which every repository passed
+fn last_success(history: &str, host: &str) -> Option<i64> {
+    history
+        .lines()
+        .filter_map(|line| serde_json::from_str::<DrillRecord>(line).ok())
+        .filter(|record| record.passed && record.host == host)
+        .filter_map(|record| DateTime::parse_from_rfc3339(&record.finished_at).ok())
+        .map(|time| time.timestamp())
+        .max()
+}
+
+/// Restore a random sample of a host's repositories to scratch space and verify them
+pub async fn execute_drill_workflow(
+    config: Config,
+    host: Option<String>,
+    options: DrillOptions,
+) -> Result<(), BackupServiceError> {
+    let hostname = host.unwrap_or_else(|| config.hostname.clone());
+    let sample = sample_size(options.sample)?;
+    let run_dir = scratch_dir(options.scratch_dir.clone()).join(format!(
+        "{}-{}",
+        hostname,
+        Utc::now().format("%Y%m%dT%H%M%S")
+    ));
+
+    config.set_aws_env()?;
+    validate_credentials(&config).await?;
+
+    info!(hostname = %hostname, sample = %sample, scratch = %run_dir.display(), "Starting restore drill");
+    let started = Instant::now();
+
+    let operations = RepositoryOperations::new(config.clone())?;
+    let discovery = operations.discover_all_repositories(&hostname).await?;
+    for failure in &discovery.failures {
+        warn!(failure = %failure, "Repository discovery incomplete; drilling the rest");
+    }
+    if discovery.repos.is_empty() {
+        warn!(hostname = %hostname, "No repositories found for host");
+        return Ok(());
+    }
+
+    let collector = SnapshotCollector::new(config.clone(), &hostname)?;
+    let mut results = Vec::new();
+    for repo in shuffle(discovery.repos) {
+        if results.len() == sample {
+            break;
+        }
+        let snapshots = match collector.get_snapshots(&repo.repo_subpath).await {
+            Ok((_, snapshots)) => snapshots,
+            Err(e) => {
+                warn!(repo_subpath = %repo.repo_subpath, error = %e, "Cannot list snapshots, picking another repository");
+                continue;
+            }
+        };
+        let Some(latest) = snapshots.into_iter().max_by_key(|s| s.time) else {
+            continue;
+        };
+
+        info!(
+            progress = format!("({}/{})", results.len() + 1, sample),
+            repo_subpath = %repo.repo_subpath,
+            snapshot = %latest.id,
+            "Restoring for drill"
+        );
+        let target = run_dir.join(repo.repo_subpath.replace('/', "_"));
+        let repo_started = Instant::now();
+        let files_expected = latest.summary.as_ref().map(|s| s.total_files_processed);
+        let mut result = DrillResult {
+            repo_subpath: repo.repo_subpath.clone(),
+            snapshot_id: latest.id.clone(),
+            snapshot_time: latest.time.to_rfc3339_opts(SecondsFormat::Secs, true),
+            files_expected,
+            files_restored: 0,
+            bytes_restored: 0,
+            duration_secs: 0.0,
+            passed: false,
+            error: None,
+        };
+
+        let repo_url = config.get_repo_url_for_host(&hostname, &repo.repo_subpath)?;
+        let restic_cmd = ResticCommandExecutor::new(config.clone(), repo_url)?;
+        std::fs::create_dir_all(&target)?;
+        let target_str = target.to_string_lossy();
+        let path_str = latest.path.to_string_lossy();
+        // --verify reads every restored file back and compares it with the repository
+        let restored = restic_cmd
+            .restore(
+                &latest.id,
+                &path_str,
+                &target_str,
+                &["--verify".to_string()],
+                false,
+            )
+            .await;
+        let outcome = match restored {
+            Ok(_) => match count_restored(&target) {
+                Ok((files, bytes)) => {
+                    result.files_restored = files;
+                    result.bytes_restored = bytes;
+                    verify_counts(files_expected, files)
+                }
+                Err(e) => Err(format!("cannot read restored files ({})", e)),
+            },
+            Err(e) => Err(e.to_string()),
+        };
+        result.duration_secs = repo_started.elapsed().as_secs_f64();
+        match outcome {
+            Ok(()) => {
+                result.passed = true;
+                info!(
+                    repo_subpath = %result.repo_subpath,
+                    files = %result.files_restored,
+                    size = %format_bytes(result.bytes_restored)?,
+                    "Drill restore verified"
+                );
+            }
+            Err(e) => {
+                error!(repo_subpath = %result.repo_subpath, error = %e, "Drill restore failed");
+                result.error = Some(e);
+            }
+        }
+        if !options.keep
+            && let Err(e) = std::fs::remove_dir_all(&target)
+        {
+            warn!(path = %target.display(), error = %e, "Could not remove drill restore");
+        }
+        results.push(result);
+    }
+    if !options.keep {
+        let _ = std::fs::remove_dir(&run_dir);
+    }
+
+    if results.is_empty() {
+        warn!(hostname = %hostname, "No repository of the host has a snapshot to drill");
+        return Ok(());
+    }
+
+    let failed = results.iter().filter(|r| !r.passed).count();
+    let finished_at = Utc::now();
+    let record = DrillRecord {
+        finished_at: finished_at.to_rfc3339_opts(SecondsFormat::Secs, true),
+        host: hostname.clone(),
+        passed: failed == 0,
+        repositories: results,
+    };
+    let history_file = drill_history_file();
+    if let Err(e) = append_history(&history_file, &record) {
+        warn!(path = %history_file.display(), error = %e, "Could not record the drill");
+    }
+    let history = std::fs::read_to_string(&history_file).unwrap_or_default();
+    metrics::publish_drill(&DrillMetrics {
+        hostname: hostname.clone(),
+        finished_at: finished_at.timestamp(),
+        succeeded: record.passed,
+        duration_secs: started.elapsed().as_secs_f64(),
+        passed: record.repositories.len() - failed,
+        failed,
+        last_success: last_success(&history, &hostname),
+    })
+    .await;
+
+    if options.json_output {
+        let output = json!({
+            "host": hostname,
+            "finished_at": record.finished_at,
+            "passed": record.repositories.len() - failed,
+            "failed": failed,
+            "repositories": record.repositories,
+            "discovery_failures": discovery.failures,
+        });
+        DisplayFormatter::print_json(&output)?;
+    } else {
+        info!("Drill summary:");
+        for result in &record.repositories {
+            match &result.error {
+                None => info!(
+                    "  ok      {} ({}, {} files)",
+                    result.repo_subpath, result.snapshot_id, result.files_restored
+                ),
+                Some(e) => info!("  FAILED  {}: {}", result.repo_subpath, e),
+            }
+        }
+    }
+
+    if failed > 0 {
+        return Err(BackupServiceError::CommandFailed(format!(
+            "Restore drill failed for {} of {} repositories",
+            failed,
+            record.repositories.len()
+        )));
+    }
+    info!(repo_count = %record.repositories.len(), "All drilled repositories restored and verified");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_count_and_verify_restored_files() -> Result<(), BackupServiceError> {
+        let root = tempfile::tempdir()?;
+        let restored = root.path().join("home/tim");
+        std::fs::create_dir_all(restored.join("docs"))?;
+        std::fs::write(restored.join("notes.txt"), "hello")?;
+        std::fs::write(restored.join("docs/a.md"), "abc")?;
+        std::os::unix::fs::symlink("notes.txt", restored.join("link"))?;
+
+        let (files, bytes) = count_restored(root.path())?;
+        assert_eq!(files, 3);
+        assert!(bytes >= 8);
+
+        assert!(verify_counts(Some(3), files).is_ok());
+        assert!(verify_counts(Some(4), files).is_err());
+        assert!(verify_counts(None, files).is_ok());
+        assert!(verify_counts(None, 0).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_history_round_trip() -> Result<(), BackupServiceError> {
+        let dir = tempfile::tempdir()?;
+        let file = dir.path().join("drill-history.jsonl");
+        let record = |finished_at: &str, host: &str, passed: bool| DrillRecord {
+            finished_at: finished_at.to_string(),
+            host: host.to_string(),
+            passed,
+            repositories: Vec::new(),
+        };
+        append_history(&file, &record("2025-01-10T03:00:00Z", "nas", true))?;
+        append_history(&file, &record("2025-01-17T03:00:00Z", "nas", false))?;
+        append_history(&file, &record("2025-01-18T03:00:00Z", "web1", true))?;
+
+        let history = std::fs::read_to_string(&file)?;
+        assert_eq!(history.lines().count(), 3);
+        // The failed drill does not move the last success
+        assert_eq!(last_success(&history, "nas"), Some(1_736_478_000));
+        assert_eq!(last_success(&history, "db1"), None);
+        Ok(())
+    }
+}
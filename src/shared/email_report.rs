@@ -0,0 +1,367 @@
+use crate::errors::BackupServiceError;
+use crate::shared::backup_workflow::{BackupStatus, BackupSummary};
+use crate::shared::notify::{NotifyOn, Severity};
+use crate::utils::format_bytes;
+use lettre::message::Mailbox;
+use lettre::message::header::ContentType;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// SMTP server the run reports are sent through; unset disables the reports
+pub const SMTP_HOST_ENV_VAR: &str = "REPORT_SMTP_HOST";
+/// Port (default 587 with STARTTLS, 465 with TLS, 25 without)
+pub const SMTP_PORT_ENV_VAR: &str = "REPORT_SMTP_PORT";
+/// `starttls` (default), `tls` or `none`
+pub const SMTP_TLS_ENV_VAR: &str = "REPORT_SMTP_TLS";
+pub const SMTP_USERNAME_ENV_VAR: &str = "REPORT_SMTP_USERNAME";
+pub const SMTP_PASSWORD_ENV_VAR: &str = "REPORT_SMTP_PASSWORD";
+/// Sender address, e.g. `Backups <backup@example.com>`
+pub const SMTP_FROM_ENV_VAR: &str = "REPORT_SMTP_FROM";
+/// Comma-separated recipients
+pub const SMTP_TO_ENV_VAR: &str = "REPORT_SMTP_TO";
+/// `always` or `failure` (default): which runs are reported
+pub const SMTP_ON_ENV_VAR: &str = "REPORT_SMTP_ON";
+
+/// Give up on an unresponsive mail server instead of holding up the run
+const SMTP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How the connection to the mail server is secured
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SmtpTls {
+    StartTls,
+    /// TLS from the first byte (SMTPS)
+    Tls,
+    /// Plain text, for a relay on localhost
+    None,
+}
+
+impl SmtpTls {
+    fn parse(value: &str) -> Result<Self, BackupServiceError> {
+        match value.trim().to_lowercase().as_str() {
+            "" | "starttls" => Ok(SmtpTls::StartTls),
+            "tls" | "smtps" => Ok(SmtpTls::Tls),
+            "none" | "off" => Ok(SmtpTls::None),
+            other => Err(BackupServiceError::ConfigurationError(format!(
+                "Invalid {}: {}.\n\nUse starttls, tls or none",
+                SMTP_TLS_ENV_VAR, other
+            ))),
+        }
+    }
+
+    fn default_port(self) -> u16 {
+        match self {
+            SmtpTls::StartTls => 587,
+            SmtpTls::Tls => 465,
+            SmtpTls::None => 25,
+        }
+    }
+}
+
+/// Mails a summary of every backup run (or only the unsuccessful ones) over SMTP
+///
+/// No `Debug`: it holds the SMTP password.
+#[derive(Clone)]
+pub struct EmailReporter {
+    host: String,
+    port: u16,
+    tls: SmtpTls,
+    credentials: Option<(String, String)>,
+    from: Mailbox,
+    to: Vec<Mailbox>,
+    on: NotifyOn,
+}
+
+impl EmailReporter {
+    /// `None` unless REPORT_SMTP_HOST is set
+    pub fn from_env() -> Result<Option<Self>, BackupServiceError> {
+        Self::from_lookup(|name| std::env::var(name).ok())
+    }
+
+    fn from_lookup(
+        lookup: impl Fn(&str) -> Option<String>,
+    ) -> Result<Option<Self>, BackupServiceError> {
+        let value = |name: &str| {
+            lookup(name)
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+        };
+        let Some(host) = value(SMTP_HOST_ENV_VAR) else {
+            return Ok(None);
+        };
+        let required = |name: &str| {
+            value(name).ok_or_else(|| {
+                BackupServiceError::ConfigurationError(format!(
+                    "{} is set, but {} is missing.\n\nSet the sender and the recipients of the run reports",
+                    SMTP_HOST_ENV_VAR, name
+                ))
+            })
+        };
+        let mailbox = |name: &str, address: &str| {
+            address.trim().parse::<Mailbox>().map_err(|e| {
+                BackupServiceError::ConfigurationError(format!(
+                    "Invalid {}: {} ({}).\n\nUse an address like backup@example.com or Backups <backup@example.com>",
+                    name, address, e
+                ))
+            })
+        };
+
+        let tls = SmtpTls::parse(&value(SMTP_TLS_ENV_VAR).unwrap_or_default())?;
+        let port = match value(SMTP_PORT_ENV_VAR) {
+            None => tls.default_port(),
+            Some(port) => port.parse().map_err(|_| {
+                BackupServiceError::ConfigurationError(format!(
+                    "Invalid {}: {}.\n\nUse a port number such as 587",
+                    SMTP_PORT_ENV_VAR, port
+                ))
+            })?,
+        };
+        let credentials = match (value(SMTP_USERNAME_ENV_VAR), value(SMTP_PASSWORD_ENV_VAR)) {
+            (Some(username), Some(password)) => Some((username, password)),
+            (None, None) => None,
+            _ => {
+                return Err(BackupServiceError::ConfigurationError(format!(
+                    "Only one of {} and {} is set.\n\nSet both to log in, or neither for an open relay",
+                    SMTP_USERNAME_ENV_VAR, SMTP_PASSWORD_ENV_VAR
+                )));
+            }
+        };
+        let from = mailbox(SMTP_FROM_ENV_VAR, &required(SMTP_FROM_ENV_VAR)?)?;
+        let to = required(SMTP_TO_ENV_VAR)?
+            .split(',')
+            .filter(|address| !address.trim().is_empty())
+            .map(|address| mailbox(SMTP_TO_ENV_VAR, address))
+            .collect::<Result<Vec<_>, _>>()?;
+        let on = NotifyOn::parse(&value(SMTP_ON_ENV_VAR).unwrap_or_default()).map_err(|_| {
+            BackupServiceError::ConfigurationError(format!(
+                "Invalid {}.\n\nUse always or failure",
+                SMTP_ON_ENV_VAR
+            ))
+        })?;
+
+        Ok(Some(Self {
+            host,
+            port,
+            tls,
+            credentials,
+            from,
+            to,
+            on,
+        }))
+    }
+
+    /// Mail the run's summary unless REPORT_SMTP_ON filters the outcome out; failures only warn
+    pub async fn report(
+        &self,
+        hostname: &str,
+        summary: &BackupSummary,
+        severity: Severity,
+        headline: &str,
+    ) {
+        if !self.on.wants(severity) {
+            return;
+        }
+        let subject = format!("{}: {}", hostname, headline);
+        match self.send(&subject, format_report(summary)).await {
+            Ok(()) => info!(recipients = %self.to.len(), "Sent backup report email"),
+            Err(e) => warn!(host = %self.host, error = %e, "Could not send backup report email"),
+        }
+    }
+
+    async fn send(&self, subject: &str, body: String) -> Result<(), BackupServiceError> {
+        let failed = |e: &dyn std::fmt::Display| {
+            BackupServiceError::CommandFailed(format!("Sending the report email failed: {}", e))
+        };
+        let mut message = Message::builder()
+            .from(self.from.clone())
+            .subject(subject)
+            .header(ContentType::TEXT_PLAIN);
+        for to in &self.to {
+            message = message.to(to.clone());
+        }
+        let message = message.body(body).map_err(|e| failed(&e))?;
+
+        let mut transport = match self.tls {
+            SmtpTls::StartTls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&self.host)
+                .map_err(|e| failed(&e))?,
+            SmtpTls::Tls => {
+                AsyncSmtpTransport::<Tokio1Executor>::relay(&self.host).map_err(|e| failed(&e))?
+            }
+            SmtpTls::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&self.host),
+        }
+        .port(self.port)
+        .timeout(Some(SMTP_TIMEOUT));
+        if let Some((username, password)) = &self.credentials {
+            transport = transport.credentials(Credentials::new(username.clone(), password.clone()));
+        }
+        transport
+            .build()
+            .send(message)
+            .await
+            .map_err(|e| failed(&e))?;
+        Ok(())
+    }
+}
+
+/// Plain-text report: the counts, one line per path with size and duration, then the errors
+pub fn format_report(summary: &BackupSummary) -> String {
+    let mut text = format!(
+        "{} paths: {} completed, {} degraded, {} skipped, {} failed\n",
+        summary.total_paths,
+        summary.success_count - summary.degraded_count,
+        summary.degraded_count,
+        summary.skip_count,
+        summary.failed_count
+    );
+    if summary.interrupted {
+        text.push_str("The run was interrupted before every path was backed up.\n");
+    }
+    let uploaded = format_bytes(summary.resources.uploaded_bytes).unwrap_or_default();
+    text.push_str(&format!("Uploaded: {}\n\n", uploaded));
+
+    text.push_str(&format!(
+        "{:<10} {:>10} {:>10} {:>9}  {}\n",
+        "STATUS", "ADDED", "FILES", "TIME", "PATH"
+    ));
+    for result in &summary.results {
+        let (added, files) = match &result.summary {
+            Some(s) => (
+                format_bytes(s.data_added).unwrap_or_default(),
+                s.total_files_processed.to_string(),
+            ),
+            None => ("-".to_string(), "-".to_string()),
+        };
+        let time = result
+            .timing
+            .as_ref()
+            .map(|t| format!("{:.1}s", t.total_secs))
+            .unwrap_or_else(|| "-".to_string());
+        text.push_str(&format!(
+            "{:<10} {:>10} {:>10} {:>9}  {}\n",
+            result.status.label(),
+            added,
+            files,
+            time,
+            result.path
+        ));
+    }
+
+    let problems: Vec<String> = summary
+        .results
+        .iter()
+        .filter(|r| r.status != BackupStatus::Completed)
+        .filter_map(|r| {
+            let reason = r.error.clone().or_else(|| {
+                r.degradation
+                    .as_ref()
+                    .map(|d| serde_json::to_string(d).unwrap_or_default())
+            })?;
+            Some(format!("  {} {}: {}", r.status.label(), r.path, reason))
+        })
+        .collect();
+    if !problems.is_empty() {
+        text.push_str("\nErrors:\n");
+        for line in problems {
+            text.push_str(&line);
+            text.push('\n');
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::backup_summary::ResticSummary;
+    use crate::shared::backup_workflow::{PathBackupResult, PathTiming};
+    use std::collections::HashMap;
+
+    fn lookup(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        move |name| vars.get(name).cloned()
+    }
+
+    #[test]
+    fn test_email_reporter_from_env() -> Result<(), BackupServiceError> {
+        assert!(EmailReporter::from_lookup(lookup(&[]))?.is_none());
+
+        let reporter = EmailReporter::from_lookup(lookup(&[
+            ("REPORT_SMTP_HOST", "smtp.example.com"),
+            ("REPORT_SMTP_TLS", "tls"),
+            ("REPORT_SMTP_FROM", "Backups <backup@example.com>"),
+            ("REPORT_SMTP_TO", "ops@example.com, tim@example.com"),
+        ]))?
+        .unwrap();
+        assert_eq!(reporter.port, 465);
+        assert_eq!(reporter.to.len(), 2);
+        assert!(reporter.credentials.is_none());
+        assert!(!reporter.on.wants(Severity::Success));
+
+        let missing_to = lookup(&[
+            ("REPORT_SMTP_HOST", "smtp.example.com"),
+            ("REPORT_SMTP_FROM", "backup@example.com"),
+        ]);
+        assert!(EmailReporter::from_lookup(missing_to).is_err());
+        let half_login = lookup(&[
+            ("REPORT_SMTP_HOST", "smtp.example.com"),
+            ("REPORT_SMTP_FROM", "backup@example.com"),
+            ("REPORT_SMTP_TO", "ops@example.com"),
+            ("REPORT_SMTP_USERNAME", "backup"),
+        ]);
+        assert!(EmailReporter::from_lookup(half_login).is_err());
+        Ok(())
+    }
+
+    fn result(path: &str, status: BackupStatus) -> PathBackupResult {
+        PathBackupResult {
+            path: path.to_string(),
+            status,
+            degradation: None,
+            summary: None,
+            error: None,
+            timing: None,
+            excludes: Vec::new(),
+            snapshot_count: None,
+            content_findings: Vec::new(),
+            quiesced_containers: Vec::new(),
+            reclaimed_bytes: None,
+        }
+    }
+
+    #[test]
+    fn test_format_report() {
+        let completed = PathBackupResult {
+            summary: Some(ResticSummary {
+                data_added: 2048,
+                total_files_processed: 120,
+                ..Default::default()
+            }),
+            timing: Some(PathTiming {
+                total_secs: 12.34,
+                ..Default::default()
+            }),
+            ..result("/home/tim", BackupStatus::Completed)
+        };
+        let failed = PathBackupResult {
+            error: Some("repository is locked".to_string()),
+            ..result("/srv/data", BackupStatus::Failed)
+        };
+        let summary = BackupSummary {
+            total_paths: 2,
+            success_count: 1,
+            failed_count: 1,
+            results: vec![completed, failed],
+            ..Default::default()
+        };
+
+        let text = format_report(&summary);
+        assert!(text.starts_with("2 paths: 1 completed, 0 degraded, 0 skipped, 1 failed\n"));
+        assert!(text.contains("completed     2.00 KB        120     12.3s  /home/tim\n"));
+        assert!(text.contains("failed              -          -         -  /srv/data\n"));
+        assert!(text.contains("\nErrors:\n  failed /srv/data: repository is locked\n"));
+    }
+}
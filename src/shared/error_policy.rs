@@ -0,0 +1,55 @@
+use crate::errors::BackupServiceError;
+
+/// Env var controlling whether a failing path aborts the whole backup run
+pub const BACKUP_ERROR_POLICY_ENV_VAR: &str = "BACKUP_ERROR_POLICY";
+
+/// Env var controlling whether S3/snapshot discovery errors abort list/restore/prune
+pub const DISCOVERY_ERROR_POLICY_ENV_VAR: &str = "DISCOVERY_ERROR_POLICY";
+
+/// How strictly a multi-item operation reacts to a failing item
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ErrorPolicy {
+    /// Log the error and carry on with the remaining items
+    #[default]
+    Continue,
+    /// Abort on the first error (CI-like usage)
+    FailFast,
+}
+
+impl ErrorPolicy {
+    pub fn parse(value: &str) -> Result<Self, BackupServiceError> {
+        match value.trim().to_lowercase().as_str() {
+            "continue" | "warn" => Ok(ErrorPolicy::Continue),
+            "fail-fast" | "fail_fast" | "fail" => Ok(ErrorPolicy::FailFast),
+            other => Err(BackupServiceError::ConfigurationError(format!(
+                "Unknown error policy: {}.\n\nValid policies are: continue, fail-fast",
+                other
+            ))),
+        }
+    }
+
+    /// Read a policy from the given env var, defaulting to `continue`
+    pub fn from_env(key: &str) -> Result<Self, BackupServiceError> {
+        match std::env::var(key) {
+            Ok(v) if !v.trim().is_empty() => Self::parse(&v),
+            _ => Ok(ErrorPolicy::default()),
+        }
+    }
+
+    pub fn is_fail_fast(&self) -> bool {
+        *self == ErrorPolicy::FailFast
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_policy_parse() -> Result<(), BackupServiceError> {
+        assert_eq!(ErrorPolicy::parse("continue")?, ErrorPolicy::Continue);
+        assert_eq!(ErrorPolicy::parse(" Fail-Fast ")?, ErrorPolicy::FailFast);
+        assert!(ErrorPolicy::parse("strict").is_err());
+        Ok(())
+    }
+}
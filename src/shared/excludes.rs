@@ -0,0 +1,142 @@
+use crate::errors::BackupServiceError;
+use std::path::{Path, PathBuf};
+
+/// Env var with restic exclude patterns (comma-separated) applied to every backup path
+pub const EXCLUDES_ENV_VAR: &str = "BACKUP_EXCLUDES";
+/// Env var with per-path overrides: `path=pattern,pattern;path=@/exclude/file`
+pub const PATH_EXCLUDES_ENV_VAR: &str = "BACKUP_PATH_EXCLUDES";
+
+/// Exclude patterns for each backup path
+///
+/// A per-path entry replaces BACKUP_EXCLUDES for that path and everything below it (the
+/// longest matching entry wins); an empty entry (`/srv/www=`) backs a path up unfiltered.
+/// Entries starting with `@` name an exclude file instead of a pattern.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExcludeRules {
+    global: Vec<String>,
+    per_path: Vec<(PathBuf, Vec<String>)>,
+}
+
+impl ExcludeRules {
+    pub fn from_env() -> Result<Self, BackupServiceError> {
+        Self::parse(
+            &std::env::var(EXCLUDES_ENV_VAR).unwrap_or_default(),
+            &std::env::var(PATH_EXCLUDES_ENV_VAR).unwrap_or_default(),
+        )
+    }
+
+    pub fn parse(global: &str, per_path: &str) -> Result<Self, BackupServiceError> {
+        let mut rules = Self {
+            global: split_patterns(global),
+            per_path: Vec::new(),
+        };
+        for entry in per_path.split(';').map(str::trim).filter(|e| !e.is_empty()) {
+            let Some((path, patterns)) = entry.split_once('=') else {
+                return Err(invalid(entry, "expected path=pattern,pattern"));
+            };
+            let path = match path.trim() {
+                "/" => "/",
+                other => other.trim_end_matches('/'),
+            };
+            if !path.starts_with('/') {
+                return Err(invalid(entry, "the path must be absolute"));
+            }
+            rules
+                .per_path
+                .push((PathBuf::from(path), split_patterns(patterns)));
+        }
+        Ok(rules)
+    }
+
+    /// Patterns for a backup path: its most specific override, else the global list
+    pub fn for_path(&self, path: &Path) -> &[String] {
+        self.per_path
+            .iter()
+            .filter(|(prefix, _)| path.starts_with(prefix))
+            .max_by_key(|(prefix, _)| prefix.components().count())
+            .map_or(&self.global, |(_, patterns)| patterns)
+    }
+}
+
+fn split_patterns(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn invalid(entry: &str, reason: &str) -> BackupServiceError {
+    BackupServiceError::ConfigurationError(format!(
+        "Invalid {} entry '{}': {}.\n\nExample: {}=\"/home/tim=node_modules,.cache;/srv/games=@/etc/games.exclude\"",
+        PATH_EXCLUDES_ENV_VAR, entry, reason, PATH_EXCLUDES_ENV_VAR
+    ))
+}
+
+/// restic arguments for the patterns: `--exclude P`, or `--exclude-file F` for `@F`
+pub fn exclude_args(patterns: &[String]) -> Vec<String> {
+    patterns
+        .iter()
+        .flat_map(|pattern| match pattern.strip_prefix('@') {
+            Some(file) => ["--exclude-file".to_string(), file.to_string()],
+            None => ["--exclude".to_string(), pattern.clone()],
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_most_specific_override_wins() -> Result<(), BackupServiceError> {
+        let rules = ExcludeRules::parse(
+            "node_modules, .cache",
+            "/home/tim=**/shadercache,@/etc/tim.exclude; /home/tim/www/=;",
+        )?;
+        assert_eq!(
+            rules.for_path(Path::new("/etc")),
+            ["node_modules", ".cache"]
+        );
+        assert_eq!(
+            rules.for_path(Path::new("/home/tim")),
+            ["**/shadercache", "@/etc/tim.exclude"]
+        );
+        assert_eq!(
+            rules.for_path(Path::new("/home/tim/games")),
+            ["**/shadercache", "@/etc/tim.exclude"]
+        );
+        assert!(rules.for_path(Path::new("/home/tim/www")).is_empty());
+        // Component-wise: /home/timothy is not below /home/tim
+        assert_eq!(
+            rules.for_path(Path::new("/home/timothy")),
+            ["node_modules", ".cache"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_entries() {
+        assert!(ExcludeRules::parse("", "/home/tim").is_err());
+        assert!(ExcludeRules::parse("", "home/tim=.cache").is_err());
+        assert_eq!(
+            ExcludeRules::parse("", "").ok(),
+            Some(ExcludeRules::default())
+        );
+    }
+
+    #[test]
+    fn test_exclude_args() {
+        let patterns = vec!["node_modules".to_string(), "@/etc/x.exclude".to_string()];
+        assert_eq!(
+            exclude_args(&patterns),
+            [
+                "--exclude",
+                "node_modules",
+                "--exclude-file",
+                "/etc/x.exclude"
+            ]
+        );
+    }
+}
@@ -0,0 +1,178 @@
+use crate::errors::BackupServiceError;
+use tracing::warn;
+
+/// Env var holding the active fault (set from the hidden `--inject-fault` flag)
+pub const FAULT_ENV_VAR: &str = "RBS_INJECT_FAULT";
+
+/// Env var that must be set to `1` before any fault is honored
+pub const FAULT_GATE_ENV_VAR: &str = "RBS_ALLOW_FAULT_INJECTION";
+
+/// Simulated failure shapes injected at the executor layer (developer/testing only)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FaultKind {
+    /// S3 rejects requests with a throttling response
+    S3Throttle,
+    /// restic exits non-zero with a fatal error
+    ResticFailure,
+    /// restic cannot reach the repository (transient, so it is retried)
+    ResticNetwork,
+    /// Commands succeed but stdout is truncated mid-stream
+    PartialOutput,
+}
+
+impl FaultKind {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "s3-throttle" => Some(FaultKind::S3Throttle),
+            "restic-failure" => Some(FaultKind::ResticFailure),
+            "restic-network" => Some(FaultKind::ResticNetwork),
+            "partial-output" => Some(FaultKind::PartialOutput),
+            _ => None,
+        }
+    }
+
+    /// Stderr emitted by the simulated failure, fed through the normal classification path
+    pub fn simulated_stderr(&self) -> &'static str {
+        match self {
+            FaultKind::S3Throttle => {
+                "An error occurred (SlowDown) when calling the ListObjectsV2 operation: Please reduce your request rate."
+            }
+            FaultKind::ResticFailure => {
+                "Fatal: unable to save snapshot: simulated restic failure (fault injection)"
+            }
+            FaultKind::ResticNetwork => {
+                "Fatal: unable to open repository: dial tcp 192.0.2.1:443: connect: connection refused (fault injection)"
+            }
+            FaultKind::PartialOutput => "",
+        }
+    }
+}
+
+/// Read the active fault, honoring it only when the gate env var is set
+pub fn active_fault() -> Option<FaultKind> {
+    let value = std::env::var(FAULT_ENV_VAR).ok()?;
+    if std::env::var(FAULT_GATE_ENV_VAR).ok().as_deref() != Some("1") {
+        return None;
+    }
+    FaultKind::parse(&value)
+}
+
+/// Truncate output to roughly half its length on a char boundary
+fn truncate_output(output: &str) -> String {
+    let mut cut = output.len() / 2;
+    while !output.is_char_boundary(cut) {
+        cut -= 1;
+    }
+    output[..cut].to_string()
+}
+
+/// Apply an injected fault to an S3 request; `None` means send the request normally
+pub fn inject_aws_fault(
+    fault: FaultKind,
+    context: &str,
+) -> Option<Result<String, BackupServiceError>> {
+    match fault {
+        FaultKind::S3Throttle => {
+            warn!(context = %context, "Fault injection: simulating S3 throttling");
+            Some(Err(BackupServiceError::from_stderr(
+                fault.simulated_stderr(),
+                context,
+            )))
+        }
+        FaultKind::ResticFailure | FaultKind::ResticNetwork | FaultKind::PartialOutput => None,
+    }
+}
+
+/// Apply an injected fault to a restic command; `None` means run the command normally
+pub fn inject_restic_fault(
+    fault: FaultKind,
+    context: &str,
+) -> Option<Result<String, BackupServiceError>> {
+    match fault {
+        FaultKind::ResticFailure | FaultKind::ResticNetwork => {
+            warn!(context = %context, fault = ?fault, "Fault injection: simulating restic failure");
+            Some(Err(BackupServiceError::from_stderr(
+                fault.simulated_stderr(),
+                context,
+            )))
+        }
+        FaultKind::S3Throttle | FaultKind::PartialOutput => None,
+    }
+}
+
+/// Post-process captured stdout for the partial-output fault
+pub fn apply_output_fault(fault: Option<FaultKind>, output: String) -> String {
+    match fault {
+        Some(FaultKind::PartialOutput) => {
+            warn!("Fault injection: truncating command output");
+            truncate_output(&output)
+        }
+        _ => output,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fault_kind_parsing() {
+        assert_eq!(FaultKind::parse("s3-throttle"), Some(FaultKind::S3Throttle));
+        assert_eq!(
+            FaultKind::parse(" Restic-Failure "),
+            Some(FaultKind::ResticFailure)
+        );
+        assert_eq!(
+            FaultKind::parse("partial-output"),
+            Some(FaultKind::PartialOutput)
+        );
+        assert_eq!(FaultKind::parse("unknown"), None);
+    }
+
+    #[test]
+    fn test_restic_failure_classification() {
+        let result = inject_restic_fault(FaultKind::ResticFailure, "backup /data");
+        assert!(matches!(
+            result,
+            Some(Err(BackupServiceError::CommandFailed(_)))
+        ));
+
+        // restic faults never short-circuit AWS commands
+        assert!(inject_aws_fault(FaultKind::ResticFailure, "s3://bucket/").is_none());
+
+        // An unreachable repository is classified as transient, so it goes through retries
+        let err = inject_restic_fault(FaultKind::ResticNetwork, "snapshots")
+            .unwrap()
+            .unwrap_err();
+        assert!(matches!(err, BackupServiceError::NetworkError));
+        assert!(err.is_retryable());
+    }
+
+    #[test]
+    fn test_s3_throttle_is_not_misclassified() {
+        let result = inject_aws_fault(FaultKind::S3Throttle, "s3://bucket/");
+        let err = result.unwrap().unwrap_err();
+        assert!(matches!(err, BackupServiceError::Throttled));
+        assert!(inject_restic_fault(FaultKind::S3Throttle, "snapshots").is_none());
+    }
+
+    #[test]
+    fn test_partial_output_truncates_json() {
+        let output = r#"[{"time":"2025-01-15T10:30:00Z","short_id":"abc123"}]"#.to_string();
+        let truncated = apply_output_fault(Some(FaultKind::PartialOutput), output.clone());
+        assert!(truncated.len() < output.len());
+        assert!(serde_json::from_str::<serde_json::Value>(&truncated).is_err());
+
+        assert_eq!(apply_output_fault(None, output.clone()), output);
+        assert_eq!(
+            apply_output_fault(Some(FaultKind::S3Throttle), output.clone()),
+            output
+        );
+    }
+
+    #[test]
+    fn test_truncate_output_respects_char_boundaries() {
+        let truncated = truncate_output("ääää");
+        assert_eq!(truncated, "ää");
+    }
+}
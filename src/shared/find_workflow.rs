@@ -0,0 +1,244 @@
+use crate::config::Config;
+use crate::errors::BackupServiceError;
+use crate::shared::commands::ResticCommandExecutor;
+use crate::shared::display::DisplayFormatter;
+use crate::shared::error_policy::{DISCOVERY_ERROR_POLICY_ENV_VAR, ErrorPolicy};
+use crate::shared::operations::{
+    DEFAULT_SCAN_JOBS, DiscoveryFailure, RepositoryOperations, SCAN_JOBS_ENV_VAR,
+    SnapshotCollector, SnapshotInfo,
+};
+use crate::shared::snapshot_filter::{SnapshotFilter, serialize_time};
+use crate::utils::{resolve_jobs, validate_credentials};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::{Value, json};
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use tracing::{info, warn};
+
+/// `find` options
+#[derive(Debug, Clone, Default)]
+pub struct FindOptions {
+    /// restic patterns; an entry matching any of them is reported
+    pub patterns: Vec<String>,
+    /// Which snapshots are searched
+    pub filter: SnapshotFilter,
+    pub ignore_case: bool,
+    pub json_output: bool,
+}
+
+/// One file or directory found in one snapshot
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FindMatch {
+    /// When the snapshot holding the entry was taken
+    #[serde(serialize_with = "serialize_time")]
+    pub time: DateTime<Utc>,
+    pub snapshot_id: String,
+    pub repo_subpath: String,
+    pub path: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+    /// Modification time as restic reports it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mtime: Option<String>,
+}
+
+/// Turn `restic find --json` output into rows, dating each by its snapshot
+///
+/// restic reports full snapshot IDs; `snapshots` carries the short ones.
+pub fn parse_find_output(
+    repo_subpath: &str,
+    snapshots: &[SnapshotInfo],
+    found: &[Value],
+) -> Vec<FindMatch> {
+    let mut rows = Vec::new();
+    for group in found {
+        let full_id = group["snapshot"].as_str().unwrap_or_default();
+        let Some(snapshot) = snapshots
+            .iter()
+            .find(|s| !full_id.is_empty() && full_id.starts_with(&s.id))
+        else {
+            continue;
+        };
+        for entry in group["matches"].as_array().into_iter().flatten() {
+            let Some(path) = entry["path"].as_str() else {
+                continue;
+            };
+            rows.push(FindMatch {
+                time: snapshot.time,
+                snapshot_id: snapshot.id.clone(),
+                repo_subpath: repo_subpath.to_string(),
+                path: path.to_string(),
+                kind: entry["type"].as_str().unwrap_or("file").to_string(),
+                size: entry["size"].as_u64(),
+                mtime: entry["mtime"].as_str().map(str::to_string),
+            });
+        }
+    }
+    rows
+}
+
+/// Search one repository's snapshots that pass the filter
+async fn find_in_repository(
+    config: Config,
+    collector: SnapshotCollector,
+    hostname: String,
+    repo_subpath: String,
+    category: String,
+    options: FindOptions,
+) -> Result<Vec<FindMatch>, BackupServiceError> {
+    let (_, snapshots) = collector.get_snapshots(&repo_subpath).await?;
+    let searched: Vec<SnapshotInfo> = snapshots
+        .into_iter()
+        .filter(|s| options.filter.matches(s, &category))
+        .collect();
+    if searched.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let repo_url = config.get_repo_url_for_host(&hostname, &repo_subpath)?;
+    let restic_cmd = ResticCommandExecutor::new(config, repo_url)?;
+    // An unfiltered search covers every snapshot, no need to list them
+    let ids: Vec<String> = if options.filter == SnapshotFilter::default() {
+        Vec::new()
+    } else {
+        searched.iter().map(|s| s.id.clone()).collect()
+    };
+    let found = restic_cmd
+        .find(&options.patterns, &ids, options.ignore_case)
+        .await?;
+    Ok(parse_find_output(&repo_subpath, &searched, &found))
+}
+
+/// Search every repository of a host for files matching the patterns
+pub async fn execute_find_workflow(
+    config: Config,
+    host: Option<String>,
+    options: FindOptions,
+) -> Result<(), BackupServiceError> {
+    let hostname = host.unwrap_or_else(|| config.hostname.clone());
+    config.set_aws_env()?;
+    validate_credentials(&config).await?;
+
+    let operations = RepositoryOperations::new(config.clone())?;
+    let discovery = operations.discover_all_repositories(&hostname).await?;
+    let mut failures = discovery.failures;
+    // Repositories of unselected categories are never opened
+    let repos: Vec<_> = discovery
+        .repos
+        .into_iter()
+        .filter(|r| options.filter.wants_category(&r.category))
+        .collect();
+    if !options.json_output {
+        info!(
+            hostname = %hostname,
+            repo_count = %repos.len(),
+            patterns = %options.patterns.join(", "),
+            "Searching snapshots"
+        );
+    }
+
+    let collector = SnapshotCollector::new(config.clone(), &hostname)?;
+    let permits = Arc::new(Semaphore::new(resolve_jobs(
+        None,
+        SCAN_JOBS_ENV_VAR,
+        DEFAULT_SCAN_JOBS,
+    )?));
+    let mut tasks = JoinSet::new();
+    for repo in repos {
+        let search = find_in_repository(
+            config.clone(),
+            collector.clone(),
+            hostname.clone(),
+            repo.repo_subpath.clone(),
+            repo.category,
+            options.clone(),
+        );
+        let permits = Arc::clone(&permits);
+        tasks.spawn(async move {
+            let _permit = permits.acquire_owned().await;
+            (repo.repo_subpath, search.await)
+        });
+    }
+
+    let policy = ErrorPolicy::from_env(DISCOVERY_ERROR_POLICY_ENV_VAR)?;
+    let mut rows = Vec::new();
+    while let Some(joined) = tasks.join_next().await {
+        let (repo_subpath, result) = joined
+            .map_err(|e| BackupServiceError::CommandFailed(format!("Task join error: {}", e)))?;
+        match result {
+            Ok(found) => rows.extend(found),
+            Err(e) if policy.is_fail_fast() => return Err(e),
+            Err(e) => {
+                warn!(repo_subpath = %repo_subpath, error = %e, "Failed to search repository");
+                failures.push(DiscoveryFailure {
+                    scope: repo_subpath,
+                    message: format!("failed to search ({})", e),
+                });
+            }
+        }
+    }
+    rows.sort_by(|a, b| {
+        b.time
+            .cmp(&a.time)
+            .then_with(|| a.repo_subpath.cmp(&b.repo_subpath))
+            .then_with(|| a.path.cmp(&b.path))
+    });
+
+    if options.json_output {
+        let output = json!({
+            "host": hostname,
+            "patterns": options.patterns,
+            "filter": options.filter,
+            "matches": rows,
+            "discovery_errors": failures,
+        });
+        DisplayFormatter::print_json(&output)?;
+    } else {
+        DisplayFormatter::display_find_results(&rows)?;
+        DisplayFormatter::display_discovery_failures(&failures)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_parse_find_output() {
+        let snapshots = vec![SnapshotInfo {
+            time: "2025-01-12T03:00:00Z".parse().unwrap(),
+            path: PathBuf::from("/home/tim"),
+            id: "4f1c2a9e".to_string(),
+            tags: Vec::new(),
+            summary: None,
+        }];
+        let found: Vec<Value> = serde_json::from_str(
+            r#"[
+                {"hits": 2, "snapshot": "4f1c2a9e7b0d3c51e2f6a8b9c0d1e2f3a4b5c6d7e8f90a1b2c3d4e5f6a7b8c9d",
+                 "matches": [
+                    {"path": "/home/tim/Documents/report.docx", "type": "file", "size": 18231,
+                     "mtime": "2024-12-02T10:15:00+01:00"},
+                    {"path": "/home/tim/Documents/old", "type": "dir", "mtime": "2024-11-30T08:00:00+01:00"}
+                 ]},
+                {"hits": 1, "snapshot": "deadbeef00", "matches": [{"path": "/x", "type": "file"}]}
+            ]"#,
+        )
+        .unwrap();
+
+        let rows = parse_find_output("user_home/tim", &snapshots, &found);
+        // Groups of snapshots outside the searched set are dropped
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].snapshot_id, "4f1c2a9e");
+        assert_eq!(rows[0].time, snapshots[0].time);
+        assert_eq!(rows[0].size, Some(18231));
+        assert_eq!(rows[1].kind, "dir");
+        assert_eq!(rows[1].size, None);
+        assert!(parse_find_output("user_home/tim", &snapshots, &[]).is_empty());
+    }
+}
@@ -0,0 +1,385 @@
+use crate::config::Config;
+use crate::errors::BackupServiceError;
+use crate::shared::commands::{command_timeout, with_timeout};
+use crate::shared::prune_workflow::{PruneOptions, execute_prune_workflow};
+use crate::shared::retention::RetentionPolicy;
+use crate::shared::shutdown;
+use crate::shared::ui::{confirm_destructive, ensure_host_not_protected, protected_hosts};
+use std::collections::{BTreeMap, BTreeSet};
+use tokio::process::Command;
+use tracing::{error, info, warn};
+
+/// Env var prefix for host groups: `HOST_GROUP_SERVERS=nas,web1`
+const GROUP_PREFIX: &str = "HOST_GROUP_";
+/// Suffix for a group's inherited retention: `HOST_GROUP_SERVERS_RETENTION=daily=7,weekly=4`
+const RETENTION_SUFFIX: &str = "_RETENTION";
+/// Suffix for a group's backup schedule: `HOST_GROUP_SERVERS_SCHEDULE=*-*-* 02:00`
+const SCHEDULE_SUFFIX: &str = "_SCHEDULE";
+
+/// Named set of hosts with policies inherited by its members
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HostGroup {
+    pub name: String,
+    pub members: Vec<String>,
+    pub retention: RetentionPolicy,
+    pub schedule: Option<String>,
+}
+
+/// All host groups defined in the environment, keyed by lowercase name
+#[derive(Debug, Clone, Default)]
+pub struct HostGroups {
+    groups: BTreeMap<String, HostGroup>,
+}
+
+impl HostGroups {
+    pub fn from_env() -> Result<Self, BackupServiceError> {
+        Self::from_vars(std::env::vars())
+    }
+
+    /// Build groups from `HOST_GROUP_<NAME>[_RETENTION|_SCHEDULE]` key/value pairs
+    pub fn from_vars(
+        vars: impl IntoIterator<Item = (String, String)>,
+    ) -> Result<Self, BackupServiceError> {
+        let mut groups: BTreeMap<String, HostGroup> = BTreeMap::new();
+
+        for (key, value) in vars {
+            let Some(rest) = key.strip_prefix(GROUP_PREFIX) else {
+                continue;
+            };
+            let (name, field) = if let Some(name) = rest.strip_suffix(RETENTION_SUFFIX) {
+                (name, RETENTION_SUFFIX)
+            } else if let Some(name) = rest.strip_suffix(SCHEDULE_SUFFIX) {
+                (name, SCHEDULE_SUFFIX)
+            } else {
+                (rest, "")
+            };
+            if name.is_empty() {
+                continue;
+            }
+
+            let name = name.to_lowercase();
+            let group = groups.entry(name.clone()).or_insert_with(|| HostGroup {
+                name,
+                ..Default::default()
+            });
+            match field {
+                RETENTION_SUFFIX => group.retention = RetentionPolicy::parse(&value)?,
+                SCHEDULE_SUFFIX => {
+                    group.schedule = Some(value.trim().to_string()).filter(|s| !s.is_empty())
+                }
+                _ => {
+                    group.members = value
+                        .split(',')
+                        .map(|h| h.trim().to_string())
+                        .filter(|h| !h.is_empty())
+                        .collect()
+                }
+            }
+        }
+
+        Ok(Self { groups })
+    }
+
+    pub fn get(&self, name: &str) -> Result<&HostGroup, BackupServiceError> {
+        self.groups.get(&name.to_lowercase()).ok_or_else(|| {
+            let known: Vec<&str> = self.groups.keys().map(|k| k.as_str()).collect();
+            BackupServiceError::ConfigurationError(format!(
+                "Unknown host group: {}.\n\nDefined groups: {}\nDefine one with {}<NAME>=host1,host2",
+                name,
+                if known.is_empty() {
+                    "(none)".to_string()
+                } else {
+                    known.join(", ")
+                },
+                GROUP_PREFIX
+            ))
+        })
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &HostGroup> {
+        self.groups.values()
+    }
+
+    /// Groups a host belongs to, in name order
+    pub fn groups_of(&self, host: &str) -> Vec<&HostGroup> {
+        self.groups
+            .values()
+            .filter(|g| g.members.iter().any(|m| m == host))
+            .collect()
+    }
+
+    /// Retention inherited from the first group of a host that defines one
+    pub fn retention_for(&self, host: &str) -> Option<&RetentionPolicy> {
+        self.groups_of(host)
+            .into_iter()
+            .map(|g| &g.retention)
+            .find(|r| !r.is_empty())
+    }
+
+    /// Schedule inherited from the first group of a host that defines one
+    pub fn schedule_for(&self, host: &str) -> Option<&str> {
+        self.groups_of(host)
+            .into_iter()
+            .find_map(|g| g.schedule.as_deref())
+    }
+}
+
+/// Resolve the members to act on, optionally narrowed with `--host`
+fn target_members(
+    group: &HostGroup,
+    only_hosts: &[String],
+) -> Result<Vec<String>, BackupServiceError> {
+    if group.members.is_empty() {
+        return Err(BackupServiceError::ConfigurationError(format!(
+            "Host group '{}' has no members",
+            group.name
+        )));
+    }
+    if only_hosts.is_empty() {
+        return Ok(group.members.clone());
+    }
+    for host in only_hosts {
+        if !group.members.contains(host) {
+            return Err(BackupServiceError::ConfigurationError(format!(
+                "Host '{}' is not a member of group '{}'",
+                host, group.name
+            )));
+        }
+    }
+    Ok(only_hosts.to_vec())
+}
+
+/// Print all groups with members and their inherited policies
+pub fn execute_fleet_groups() -> Result<(), BackupServiceError> {
+    let groups = HostGroups::from_env()?;
+    if groups.iter().next().is_none() {
+        warn!(
+            "No host groups defined. Add {}<NAME>=host1,host2 to the env file.",
+            GROUP_PREFIX
+        );
+        return Ok(());
+    }
+
+    for group in groups.iter() {
+        info!("{} ({} hosts)", group.name, group.members.len());
+        info!("  members:   {}", group.members.join(", "));
+        if let Some(schedule) = &group.schedule {
+            info!("  schedule:  {}", schedule);
+        }
+        if !group.retention.is_empty() {
+            info!("  retention: {}", group.retention.to_args().join(" "));
+        }
+    }
+
+    // Effective policies after inheritance (first group defining a value wins)
+    let hosts: BTreeSet<&String> = groups.iter().flat_map(|g| &g.members).collect();
+    info!("");
+    for host in hosts {
+        info!(
+            "{}: schedule={} retention={}",
+            host,
+            groups.schedule_for(host).unwrap_or("-"),
+            groups
+                .retention_for(host)
+                .map(|r| r.to_args().join(" "))
+                .unwrap_or_else(|| "-".to_string())
+        );
+    }
+    Ok(())
+}
+
+/// Run `restic-backup-service run` on every member over SSH, continuing past failures
+///
+/// Each ssh session is tracked like a restic child, so Ctrl-C interrupts the remote run and
+/// no further host is started.
+pub async fn execute_fleet_run(
+    group_name: &str,
+    only_hosts: &[String],
+    extra_args: &[String],
+) -> Result<(), BackupServiceError> {
+    let groups = HostGroups::from_env()?;
+    let group = groups.get(group_name)?;
+    let members = target_members(group, only_hosts)?;
+
+    let ssh = std::env::var("FLEET_SSH_COMMAND").unwrap_or_else(|_| "ssh".to_string());
+    let remote = std::env::var("FLEET_REMOTE_COMMAND")
+        .unwrap_or_else(|_| "restic-backup-service".to_string());
+
+    let mut failed = Vec::new();
+    for (idx, host) in members.iter().enumerate() {
+        if shutdown::is_requested() {
+            warn!(host = %host, "Interrupted, not starting further remote backups");
+            failed.push(host.clone());
+            continue;
+        }
+        info!(
+            progress = format!("({}/{})", idx + 1, members.len()),
+            host = %host,
+            group = %group.name,
+            "Starting remote backup"
+        );
+        let mut child = Command::new(&ssh)
+            .arg(host)
+            .arg(&remote)
+            .arg("run")
+            .args(extra_args)
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|_| {
+                BackupServiceError::CommandNotFound(format!("Failed to execute {}", ssh))
+            })?;
+        let _tracked = shutdown::track_child(child.id());
+        let context = format!("Remote backup on {}", host);
+        let status = match with_timeout(child.wait(), command_timeout("backup")?, &context).await {
+            Ok(status) => status?,
+            Err(e) => {
+                error!(host = %host, error = %e, "Remote backup failed");
+                failed.push(host.clone());
+                continue;
+            }
+        };
+
+        if status.success() {
+            info!(host = %host, "Remote backup completed");
+        } else {
+            error!(host = %host, exit_code = ?status.code(), "Remote backup failed");
+            failed.push(host.clone());
+        }
+    }
+
+    if !failed.is_empty() {
+        return Err(BackupServiceError::CommandFailed(format!(
+            "Backup failed on {} of {} hosts in group '{}': {}",
+            failed.len(),
+            members.len(),
+            group.name,
+            failed.join(", ")
+        )));
+    }
+    info!(group = %group.name, host_count = %members.len(), "Fleet backup completed");
+    Ok(())
+}
+
+/// Prune every member's repositories, inheriting the group's retention unless overridden
+pub async fn execute_fleet_prune(
+    config: Config,
+    group_name: &str,
+    only_hosts: &[String],
+    options: PruneOptions,
+) -> Result<(), BackupServiceError> {
+    let groups = HostGroups::from_env()?;
+    let group = groups.get(group_name)?;
+    let members = target_members(group, only_hosts)?;
+
+    // Refuse up front rather than halfway through the group
+    let protected = protected_hosts();
+    for host in &members {
+        ensure_host_not_protected("prune", host, &protected)?;
+    }
+    confirm_destructive(
+        "prune group",
+        &group.name,
+        options.assume_yes,
+        options.confirm.as_deref(),
+    )?;
+
+    let mut failed = Vec::new();
+    for host in &members {
+        let retention = if options.retention.is_empty() {
+            group.retention.clone()
+        } else {
+            options.retention.clone()
+        };
+        let host_options = PruneOptions {
+            retention,
+            // The group was confirmed as a whole above
+            assume_yes: true,
+            confirm: Some(host.clone()),
+            ..options.clone()
+        };
+        if let Err(e) =
+            execute_prune_workflow(config.clone(), Some(host.clone()), host_options).await
+        {
+            error!(host = %host, error = %e, "Prune failed for host");
+            failed.push(host.clone());
+        }
+    }
+
+    if !failed.is_empty() {
+        return Err(BackupServiceError::CommandFailed(format!(
+            "Prune failed on {} of {} hosts in group '{}': {}",
+            failed.len(),
+            members.len(),
+            group.name,
+            failed.join(", ")
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_groups_from_vars() -> Result<(), BackupServiceError> {
+        let groups = HostGroups::from_vars(vars(&[
+            ("HOST_GROUP_SERVERS", "nas, web1,db1"),
+            ("HOST_GROUP_SERVERS_RETENTION", "daily=7,weekly=4"),
+            ("HOST_GROUP_SERVERS_SCHEDULE", "*-*-* 02:00"),
+            ("HOST_GROUP_LAPTOPS", "tim-laptop"),
+            ("BACKUP_PATHS", "/etc"),
+        ]))?;
+
+        let servers = groups.get("servers")?;
+        assert_eq!(servers.members, vec!["nas", "web1", "db1"]);
+        assert_eq!(servers.retention.keep_daily, Some(7));
+        assert_eq!(servers.schedule.as_deref(), Some("*-*-* 02:00"));
+        assert_eq!(groups.get("LAPTOPS")?.members, vec!["tim-laptop"]);
+        assert_eq!(groups.iter().count(), 2);
+
+        let err = groups.get("desktops").unwrap_err();
+        assert!(err.to_string().contains("laptops, servers"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_members_inherit_group_policies() -> Result<(), BackupServiceError> {
+        let groups = HostGroups::from_vars(vars(&[
+            ("HOST_GROUP_ALL", "nas,tim-laptop"),
+            ("HOST_GROUP_SERVERS", "nas"),
+            ("HOST_GROUP_SERVERS_RETENTION", "daily=14"),
+            ("HOST_GROUP_SERVERS_SCHEDULE", "hourly"),
+        ]))?;
+
+        assert_eq!(
+            groups.retention_for("nas").and_then(|r| r.keep_daily),
+            Some(14)
+        );
+        assert_eq!(groups.schedule_for("nas"), Some("hourly"));
+        assert_eq!(groups.retention_for("tim-laptop"), None);
+        assert_eq!(groups.schedule_for("unknown"), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_target_members() -> Result<(), BackupServiceError> {
+        let group = HostGroup {
+            name: "servers".to_string(),
+            members: vec!["nas".to_string(), "web1".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(target_members(&group, &[])?, vec!["nas", "web1"]);
+        assert_eq!(target_members(&group, &["web1".to_string()])?, vec!["web1"]);
+        assert!(target_members(&group, &["laptop".to_string()]).is_err());
+        assert!(target_members(&HostGroup::default(), &[]).is_err());
+        Ok(())
+    }
+}
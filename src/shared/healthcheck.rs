@@ -0,0 +1,179 @@
+use crate::errors::BackupServiceError;
+use crate::shared::backup_workflow::BackupSummary;
+use crate::shared::http;
+use tracing::{info, warn};
+
+/// Healthchecks.io-style check URL: `<url>/start` before a run, `<url>` or `<url>/fail` after
+pub const HEALTHCHECK_URL_ENV_VAR: &str = "HEALTHCHECK_URL";
+/// Per-event URLs for services with another scheme; each wins over the derived one
+pub const HEALTHCHECK_START_URL_ENV_VAR: &str = "HEALTHCHECK_START_URL";
+pub const HEALTHCHECK_SUCCESS_URL_ENV_VAR: &str = "HEALTHCHECK_SUCCESS_URL";
+pub const HEALTHCHECK_FAIL_URL_ENV_VAR: &str = "HEALTHCHECK_FAIL_URL";
+
+/// healthchecks.io keeps the first 100 kB of a ping body
+const MAX_BODY_BYTES: usize = 100_000;
+
+/// Where the start, success and failure of a backup run are reported
+///
+/// The monitoring side alerts when no success ping arrives in time, so a machine that
+/// stops backing up entirely is noticed, not just runs that fail.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Healthcheck {
+    pub start: Option<String>,
+    pub success: Option<String>,
+    pub fail: Option<String>,
+}
+
+impl Healthcheck {
+    pub fn from_env() -> Self {
+        Self::from_lookup(|name| std::env::var(name).ok())
+    }
+
+    fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Self {
+        let value = |name: &str| {
+            lookup(name)
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+        };
+        let base = value(HEALTHCHECK_URL_ENV_VAR).map(|url| url.trim_end_matches('/').to_string());
+        let derived = |suffix: &str| base.as_ref().map(|url| format!("{}{}", url, suffix));
+        Self {
+            start: value(HEALTHCHECK_START_URL_ENV_VAR).or_else(|| derived("/start")),
+            success: value(HEALTHCHECK_SUCCESS_URL_ENV_VAR).or_else(|| derived("")),
+            fail: value(HEALTHCHECK_FAIL_URL_ENV_VAR).or_else(|| derived("/fail")),
+        }
+    }
+
+    pub fn is_configured(&self) -> bool {
+        self.start.is_some() || self.success.is_some() || self.fail.is_some()
+    }
+
+    /// Signal that a run started, so the service can measure its duration
+    pub async fn ping_start(&self, hostname: &str) {
+        if let Some(url) = &self.start {
+            send(url, &format!("{}: backup started", hostname), "start").await;
+        }
+    }
+
+    /// Report how the run ended, with the summary as the ping body
+    pub async fn ping_finished(
+        &self,
+        hostname: &str,
+        outcome: &Result<BackupSummary, BackupServiceError>,
+        duration_secs: f64,
+    ) {
+        let succeeded = outcome.as_ref().is_ok_and(BackupSummary::is_success);
+        let (url, event) = if succeeded {
+            (&self.success, "success")
+        } else {
+            (&self.fail, "fail")
+        };
+        if let Some(url) = url {
+            send(url, &run_report(hostname, outcome, duration_secs), event).await;
+        }
+    }
+}
+
+/// Plain-text summary of a run: one headline, then every path that did not complete cleanly
+pub fn run_report(
+    hostname: &str,
+    outcome: &Result<BackupSummary, BackupServiceError>,
+    duration_secs: f64,
+) -> String {
+    let summary = match outcome {
+        Ok(summary) => summary,
+        Err(e) => {
+            return format!(
+                "{}: backup failed after {:.0}s before finishing its paths: {}\n",
+                hostname, duration_secs, e
+            );
+        }
+    };
+
+    let mut report = format!(
+        "{}: {} paths, {} completed, {} degraded, {} skipped, {} failed in {:.0}s{}\n",
+        hostname,
+        summary.total_paths,
+        summary.success_count - summary.degraded_count,
+        summary.degraded_count,
+        summary.skip_count,
+        summary.failed_count,
+        duration_secs,
+        if summary.interrupted {
+            " (interrupted by shutdown)"
+        } else {
+            ""
+        }
+    );
+    for line in summary.problem_lines() {
+        report.push_str(&line);
+        report.push('\n');
+    }
+    report
+}
+
+/// POST the body to a ping URL; a failed ping is logged and never fails the backup
+async fn send(url: &str, body: &str, event: &str) {
+    let mut body = body.to_string();
+    if body.len() > MAX_BODY_BYTES {
+        let mut end = MAX_BODY_BYTES;
+        while !body.is_char_boundary(end) {
+            end -= 1;
+        }
+        body.truncate(end);
+    }
+    match http::post(url, &[], &body, "Healthcheck ping").await {
+        Ok(()) => info!(event = %event, "Sent healthcheck ping"),
+        Err(e) => warn!(event = %event, error = %e, "Could not send healthcheck ping"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn lookup(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        move |name| vars.get(name).cloned()
+    }
+
+    #[test]
+    fn test_healthcheck_urls_and_report() {
+        assert!(!Healthcheck::from_lookup(lookup(&[])).is_configured());
+
+        let check = Healthcheck::from_lookup(lookup(&[
+            ("HEALTHCHECK_URL", "https://hc-ping.com/5d1c/"),
+            (
+                "HEALTHCHECK_FAIL_URL",
+                "https://alerts.example/backup-failed",
+            ),
+        ]));
+        assert_eq!(
+            check.start.as_deref(),
+            Some("https://hc-ping.com/5d1c/start")
+        );
+        assert_eq!(check.success.as_deref(), Some("https://hc-ping.com/5d1c"));
+        assert_eq!(
+            check.fail.as_deref(),
+            Some("https://alerts.example/backup-failed")
+        );
+
+        let summary = BackupSummary {
+            total_paths: 3,
+            success_count: 2,
+            degraded_count: 1,
+            failed_count: 1,
+            ..BackupSummary::default()
+        };
+        assert_eq!(
+            run_report("web1", &Ok(summary), 312.4),
+            "web1: 3 paths, 1 completed, 1 degraded, 0 skipped, 1 failed in 312s\n"
+        );
+        let failed = run_report("web1", &Err(BackupServiceError::NetworkError), 2.0);
+        assert!(failed.starts_with("web1: backup failed after 2s"));
+    }
+}
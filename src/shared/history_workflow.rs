@@ -0,0 +1,394 @@
+use crate::errors::BackupServiceError;
+use crate::shared::backup_workflow::{BackupSummary, PathBackupResult, SnapshotDegradation};
+use crate::shared::budgets::BudgetAlert;
+use crate::shared::display::DisplayFormatter;
+use crate::shared::logs_workflow::log_dir;
+use chrono::{SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+/// Env var overriding the backup history (default: `<RBS_LOG_DIR>/backup-history.jsonl`)
+pub const BACKUP_HISTORY_ENV_VAR: &str = "BACKUP_HISTORY_FILE";
+
+/// Runs per path the trends are computed over unless --runs is given
+const DEFAULT_HISTORY_RUNS: usize = 30;
+/// Runs compared against the ones before them to spot regressions
+const RECENT_RUNS: usize = 3;
+/// How much worse the recent average must be before it is flagged
+const REGRESSION_FACTOR: f64 = 1.5;
+
+/// `history` options
+#[derive(Debug, Clone, Default)]
+pub struct HistoryOptions {
+    /// Only runs of this host; every host in the file when unset
+    pub host: Option<String>,
+    /// Only this backup path
+    pub path: Option<String>,
+    /// Most recent runs per path to look at (default 30)
+    pub runs: Option<usize>,
+    pub json_output: bool,
+}
+
+/// Outcome of one path in a recorded run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoryPath {
+    pub path: String,
+    /// completed, degraded, skipped or failed
+    pub status: String,
+    pub snapshot_id: Option<String>,
+    /// Bytes added to the repository (after deduplication)
+    pub bytes_added: Option<u64>,
+    pub duration_secs: Option<f64>,
+    /// Degradation, unreadable entries and credential-looking files
+    #[serde(default)]
+    pub warnings: Vec<String>,
+    pub error: Option<String>,
+}
+
+/// One line of the backup history
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoryRecord {
+    /// RFC 3339
+    pub finished_at: String,
+    pub host: String,
+    pub interrupted: bool,
+    pub paths: Vec<HistoryPath>,
+    /// REPO_MAX_* budgets exceeded after the run (`status` reports the newest)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub budget_alerts: Vec<BudgetAlert>,
+}
+
+/// Trends of one path over its recorded runs
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PathTrend {
+    pub host: String,
+    pub path: String,
+    pub runs: usize,
+    pub failures: usize,
+    /// Failed runs in a row up to the newest one
+    pub failure_streak: usize,
+    pub last_status: String,
+    pub last_run: String,
+    /// Newest run that completed (degraded counts: a snapshot was saved)
+    pub last_success: Option<String>,
+    pub last_snapshot_id: Option<String>,
+    /// Sum of bytes added over the runs looked at
+    pub bytes_added: u64,
+    pub avg_bytes_added: Option<f64>,
+    pub avg_duration_secs: Option<f64>,
+    pub warnings: usize,
+    /// Human-readable signs that the path is getting worse
+    pub regressions: Vec<String>,
+}
+
+pub fn history_file() -> PathBuf {
+    std::env::var(BACKUP_HISTORY_ENV_VAR)
+        .ok()
+        .filter(|v| !v.trim().is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| log_dir().join("backup-history.jsonl"))
+}
+
+fn history_path(result: &PathBackupResult, summary: &BackupSummary) -> HistoryPath {
+    let mut warnings = Vec::new();
+    match &result.degradation {
+        Some(SnapshotDegradation::Empty) => warnings.push("empty snapshot".to_string()),
+        Some(SnapshotDegradation::Shrunk { previous, current }) => warnings.push(format!(
+            "file count dropped from {} to {}",
+            previous, current
+        )),
+        None => {}
+    }
+    if let Some(access) = summary.preflight.get(&result.path)
+        && !access.unreadable.is_empty()
+    {
+        warnings.push(format!("{} unreadable entries", access.unreadable.len()));
+    }
+    if !result.content_findings.is_empty() {
+        warnings.push(format!(
+            "{} files look like credentials",
+            result.content_findings.len()
+        ));
+    }
+    HistoryPath {
+        path: result.path.clone(),
+        status: result.status.label().to_string(),
+        snapshot_id: result.summary.as_ref().and_then(|s| s.snapshot_id.clone()),
+        bytes_added: result.summary.as_ref().map(|s| s.data_added),
+        duration_secs: result.timing.as_ref().map(|t| t.total_secs),
+        warnings,
+        error: result.error.clone(),
+    }
+}
+
+pub fn history_record(host: &str, summary: &BackupSummary) -> HistoryRecord {
+    HistoryRecord {
+        finished_at: Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+        host: host.to_string(),
+        interrupted: summary.interrupted,
+        paths: summary
+            .results
+            .iter()
+            .map(|result| history_path(result, summary))
+            .collect(),
+        budget_alerts: summary.budget_alerts.clone(),
+    }
+}
+
+fn append_history(file: &Path, record: &HistoryRecord) -> Result<(), BackupServiceError> {
+    if let Some(dir) = file.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut history = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(file)?;
+    writeln!(history, "{}", serde_json::to_string(record)?)?;
+    Ok(())
+}
+
+/// Append a finished run to the history; a run without results is not recorded and
+/// failures only warn
+pub fn record_run(host: &str, summary: &BackupSummary) {
+    if summary.results.is_empty() {
+        return;
+    }
+    let file = history_file();
+    if let Err(e) = append_history(&file, &history_record(host, summary)) {
+        warn!(file = %file.display(), error = %e, "Could not record the run in the backup history");
+    }
+}
+
+/// Records in file order (oldest first); unparsable lines are skipped
+fn parse_history(content: &str) -> Vec<HistoryRecord> {
+    content
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
+
+/// Every record in `file`; a missing file is an empty history
+pub fn read_history(file: &Path) -> Result<Vec<HistoryRecord>, BackupServiceError> {
+    match std::fs::read_to_string(file) {
+        Ok(content) => Ok(parse_history(&content)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn average(values: &[f64]) -> Option<f64> {
+    (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64)
+}
+
+/// "duration up 80%"-style note when the newest runs average REGRESSION_FACTOR times the
+/// runs before them
+fn regression(what: &str, values: &[f64]) -> Option<String> {
+    if values.len() < RECENT_RUNS * 2 {
+        return None;
+    }
+    let (earlier, recent) = values.split_at(values.len() - RECENT_RUNS);
+    let (earlier, recent) = (average(earlier)?, average(recent)?);
+    (earlier > 0.0 && recent >= earlier * REGRESSION_FACTOR).then(|| {
+        format!(
+            "{} up {:.0}% over the last {} runs",
+            what,
+            (recent / earlier - 1.0) * 100.0,
+            RECENT_RUNS
+        )
+    })
+}
+
+/// Per-path trends over the newest `runs` runs of each path, sorted by host and path
+pub fn path_trends(
+    records: &[HistoryRecord],
+    host: Option<&str>,
+    path: Option<&str>,
+    runs: usize,
+) -> Vec<PathTrend> {
+    // Keyed by host and path, so the trends come out sorted
+    let mut per_path: BTreeMap<(String, String), Vec<(&str, &HistoryPath)>> = BTreeMap::new();
+    for record in records {
+        if host.is_some_and(|h| h != record.host) {
+            continue;
+        }
+        for entry in &record.paths {
+            if path.is_some_and(|p| p != entry.path) {
+                continue;
+            }
+            per_path
+                .entry((record.host.clone(), entry.path.clone()))
+                .or_default()
+                .push((record.finished_at.as_str(), entry));
+        }
+    }
+
+    per_path
+        .into_iter()
+        .map(|((host, path), entries)| {
+            let entries = &entries[entries.len().saturating_sub(runs)..];
+            let failed = |entry: &HistoryPath| entry.status == "failed";
+            let succeeded =
+                |entry: &HistoryPath| matches!(entry.status.as_str(), "completed" | "degraded");
+            let (newest_time, newest) = entries[entries.len() - 1];
+            let added: Vec<f64> = entries
+                .iter()
+                .filter_map(|(_, e)| e.bytes_added)
+                .map(|b| b as f64)
+                .collect();
+            let durations: Vec<f64> = entries
+                .iter()
+                .filter(|(_, e)| succeeded(e))
+                .filter_map(|(_, e)| e.duration_secs)
+                .collect();
+            let failure_streak = entries.iter().rev().take_while(|(_, e)| failed(e)).count();
+
+            let mut regressions = Vec::new();
+            if failure_streak > 1 {
+                regressions.push(format!("failed {} runs in a row", failure_streak));
+            }
+            regressions.extend(regression("duration", &durations));
+            regressions.extend(regression("bytes added", &added));
+            let recent_warnings = entries
+                .iter()
+                .rev()
+                .take(RECENT_RUNS)
+                .filter(|(_, e)| !e.warnings.is_empty())
+                .count();
+            if entries.len() >= RECENT_RUNS && recent_warnings == RECENT_RUNS {
+                regressions.push(format!("warnings in each of the last {} runs", RECENT_RUNS));
+            }
+
+            PathTrend {
+                host,
+                path,
+                runs: entries.len(),
+                failures: entries.iter().filter(|(_, e)| failed(e)).count(),
+                failure_streak,
+                last_status: newest.status.clone(),
+                last_run: newest_time.to_string(),
+                last_success: entries
+                    .iter()
+                    .rev()
+                    .find(|(_, e)| succeeded(e))
+                    .map(|(time, _)| time.to_string()),
+                last_snapshot_id: entries
+                    .iter()
+                    .rev()
+                    .find_map(|(_, e)| e.snapshot_id.clone()),
+                bytes_added: added.iter().sum::<f64>() as u64,
+                avg_bytes_added: average(&added),
+                avg_duration_secs: average(&durations),
+                warnings: entries.iter().map(|(_, e)| e.warnings.len()).sum(),
+                regressions,
+            }
+        })
+        .collect()
+}
+
+/// Show per-path trends from the local backup history
+pub async fn execute_history_workflow(options: HistoryOptions) -> Result<(), BackupServiceError> {
+    let runs = options.runs.unwrap_or(DEFAULT_HISTORY_RUNS);
+    if runs == 0 {
+        return Err(BackupServiceError::ConfigurationError(
+            "--runs must be at least 1.\n\nPass --runs 10 to look at the last ten runs of each path"
+                .to_string(),
+        ));
+    }
+    let file = history_file();
+    let trends = path_trends(
+        &read_history(&file)?,
+        options.host.as_deref(),
+        options.path.as_deref(),
+        runs,
+    );
+
+    if options.json_output {
+        let output = json!({
+            "file": file,
+            "runs": runs,
+            "paths": trends,
+        });
+        DisplayFormatter::print_json(&output)?;
+    } else {
+        DisplayFormatter::display_history(&file, &trends)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(time: &str, status: &str, bytes: u64, secs: f64) -> HistoryRecord {
+        HistoryRecord {
+            finished_at: time.to_string(),
+            host: "web1".to_string(),
+            interrupted: false,
+            paths: vec![HistoryPath {
+                path: "/home/tim".to_string(),
+                status: status.to_string(),
+                snapshot_id: (status != "failed").then(|| format!("id-{}", time)),
+                bytes_added: (status != "failed").then_some(bytes),
+                duration_secs: Some(secs),
+                warnings: Vec::new(),
+                error: (status == "failed").then(|| "repository is locked".to_string()),
+            }],
+            budget_alerts: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_history_round_trip_and_trends() -> Result<(), BackupServiceError> {
+        let dir = tempfile::tempdir()?;
+        let file = dir.path().join("backup-history.jsonl");
+        let records = [
+            run("2025-01-10T03:00:00Z", "completed", 100, 60.0),
+            run("2025-01-11T03:00:00Z", "completed", 100, 60.0),
+            run("2025-01-12T03:00:00Z", "completed", 100, 60.0),
+            run("2025-01-13T03:00:00Z", "completed", 100, 120.0),
+            run("2025-01-14T03:00:00Z", "completed", 100, 120.0),
+            run("2025-01-15T03:00:00Z", "degraded", 100, 120.0),
+        ];
+        for record in &records {
+            append_history(&file, record)?;
+        }
+        let parsed = parse_history(&std::fs::read_to_string(&file)?);
+        assert_eq!(parsed, records);
+
+        let trends = path_trends(&parsed, None, None, 30);
+        assert_eq!(trends.len(), 1);
+        assert_eq!(trends[0].runs, 6);
+        assert_eq!(trends[0].bytes_added, 600);
+        assert_eq!(trends[0].avg_duration_secs, Some(90.0));
+        assert_eq!(
+            trends[0].last_snapshot_id.as_deref(),
+            Some("id-2025-01-15T03:00:00Z")
+        );
+        assert_eq!(
+            trends[0].regressions,
+            ["duration up 100% over the last 3 runs"]
+        );
+
+        let mut failing = parsed.clone();
+        failing.push(run("2025-01-16T03:00:00Z", "failed", 0, 5.0));
+        failing.push(run("2025-01-17T03:00:00Z", "failed", 0, 5.0));
+        // Only the newest four runs: the slow-down is no longer visible
+        let trends = path_trends(&failing, Some("web1"), Some("/home/tim"), 4);
+        assert_eq!(trends[0].runs, 4);
+        assert_eq!(trends[0].failures, 2);
+        assert_eq!(trends[0].failure_streak, 2);
+        assert_eq!(trends[0].last_status, "failed");
+        assert_eq!(
+            trends[0].last_success.as_deref(),
+            Some("2025-01-15T03:00:00Z")
+        );
+        assert_eq!(trends[0].regressions, ["failed 2 runs in a row"]);
+
+        assert!(path_trends(&failing, Some("db1"), None, 30).is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,88 @@
+use crate::errors::BackupServiceError;
+use crate::shared::commands::{global_timeout, with_timeout};
+use std::process::{Output, Stdio};
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+/// Seconds curl may spend on one attempt, connecting included
+const ATTEMPT_TIMEOUT_SECS: u64 = 20;
+/// Attempts after the first; curl only retries timeouts and transient HTTP errors
+const RETRIES: u64 = 2;
+
+/// Bound for the whole request: every attempt plus curl's back-off between them (1s, 2s),
+/// capped by COMMAND_TIMEOUT_SECS
+fn request_timeout() -> Result<Option<Duration>, BackupServiceError> {
+    let attempts = Duration::from_secs((ATTEMPT_TIMEOUT_SECS + 2) * (RETRIES + 1));
+    Ok(Some(attempts).into_iter().chain(global_timeout()?).min())
+}
+
+/// Run curl with the shared timeout and retry policy, `input` piped on stdin
+///
+/// `args` follow `--fail --silent --show-error` and the per-attempt and retry flags, so a
+/// caller can override them (curl takes the last value, `--no-fail` drops `--fail`). The
+/// output is returned whatever curl's exit status, so callers map failures to their own
+/// errors. `context` names the request in a timeout error.
+pub async fn curl(
+    args: &[String],
+    input: &str,
+    context: &str,
+) -> Result<Output, BackupServiceError> {
+    let mut child = Command::new("curl")
+        .args(["--fail", "--silent", "--show-error", "--max-time"])
+        .arg(ATTEMPT_TIMEOUT_SECS.to_string())
+        .arg("--retry")
+        .arg(RETRIES.to_string())
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|_| BackupServiceError::CommandNotFound("Failed to execute curl".to_string()))?;
+    let stdin = child.stdin.take();
+    let output = with_timeout(
+        async move {
+            if let Some(mut stdin) = stdin {
+                stdin.write_all(input.as_bytes()).await?;
+            }
+            child.wait_with_output().await
+        },
+        request_timeout()?,
+        context,
+    )
+    .await??;
+    Ok(output)
+}
+
+/// POST `body` to `url`, the body on stdin so it never shows up in `ps`
+///
+/// Used for every webhook, ping and push the service sends. `context` names the request
+/// in the error, e.g. "Healthcheck ping".
+pub async fn post(
+    url: &str,
+    headers: &[String],
+    body: &str,
+    context: &str,
+) -> Result<(), BackupServiceError> {
+    let mut args = Vec::new();
+    for header in headers {
+        args.extend(["--header".to_string(), header.clone()]);
+    }
+    args.extend([
+        "--data-binary".to_string(),
+        "@-".to_string(),
+        "--output".to_string(),
+        "/dev/null".to_string(),
+        url.to_string(),
+    ]);
+    let output = curl(&args, body, context).await?;
+    if !output.status.success() {
+        return Err(BackupServiceError::CommandFailed(format!(
+            "{} failed: {}",
+            context,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(())
+}
@@ -0,0 +1,194 @@
+use crate::errors::BackupServiceError;
+use crate::i18n::{t, t_args};
+use crate::shared::config_file;
+use crate::shared::logs_workflow::log_dir;
+use chrono::{DateTime, Utc};
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+/// Env var overriding where instance lock files live (default: the log directory,
+/// which the systemd unit and the CLI wrapper share despite PrivateTmp)
+pub const LOCK_DIR_ENV_VAR: &str = "RBS_LOCK_DIR";
+
+/// Exit code of a run skipped because another one holds the lock (EX_TEMPFAIL)
+pub const ALREADY_RUNNING_EXIT_CODE: i32 = 75;
+
+/// Who holds a lock, as recorded in the lock file
+#[derive(Debug, Clone, PartialEq)]
+pub struct LockHolder {
+    pub pid: Option<u32>,
+    pub started: Option<DateTime<Utc>>,
+}
+
+impl LockHolder {
+    fn parse(contents: &str) -> Self {
+        let mut lines = contents.lines();
+        Self {
+            pid: lines.next().and_then(|l| l.trim().parse().ok()),
+            started: lines
+                .next()
+                .and_then(|l| DateTime::parse_from_rfc3339(l.trim()).ok())
+                .map(|t| t.with_timezone(&Utc)),
+        }
+    }
+
+    /// "pid 1234, started 00:12 ago" (hours:minutes)
+    pub fn describe(&self, now: DateTime<Utc>) -> String {
+        let pid = self.pid.map_or_else(
+            || t("lock-holder-unknown-pid"),
+            |p| t_args("lock-holder-pid", &[("pid", p.to_string())]),
+        );
+        match self.started {
+            Some(started) => {
+                let minutes = (now - started).num_minutes().max(0);
+                t_args(
+                    "lock-holder-started",
+                    &[
+                        ("holder", pid),
+                        (
+                            "elapsed",
+                            format!("{:02}:{:02}", minutes / 60, minutes % 60),
+                        ),
+                    ],
+                )
+            }
+            None => pid,
+        }
+    }
+}
+
+/// Exclusive per-operation lock held for the lifetime of the value
+///
+/// Backed by `flock`, so a crashed run never leaves a stale lock behind.
+#[derive(Debug)]
+pub struct InstanceLock {
+    _file: File,
+    path: PathBuf,
+}
+
+impl InstanceLock {
+    pub fn lock_dir() -> PathBuf {
+        std::env::var(LOCK_DIR_ENV_VAR)
+            .ok()
+            .filter(|v| !v.trim().is_empty())
+            .map(PathBuf::from)
+            .unwrap_or_else(log_dir)
+    }
+
+    /// Take the lock for `operation`, or return the current holder
+    ///
+    /// Each profile (`--profile`) backs up its own repository base, so it locks separately.
+    pub fn acquire(operation: &str) -> Result<Result<Self, LockHolder>, BackupServiceError> {
+        let name = lock_name(operation, config_file::active_profile().as_deref());
+        Self::acquire_in(&Self::lock_dir(), &name)
+    }
+
+    pub fn acquire_in(
+        dir: &Path,
+        operation: &str,
+    ) -> Result<Result<Self, LockHolder>, BackupServiceError> {
+        std::fs::create_dir_all(dir)?;
+        let path = dir.join(format!(".restic-backup-{}.lock", operation));
+        // No truncate on open: the holder's details must survive a failed attempt
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)?;
+
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => {
+                let mut contents = String::new();
+                file.read_to_string(&mut contents)?;
+                return Ok(Err(LockHolder::parse(&contents)));
+            }
+            Err(TryLockError::Error(e)) => return Err(e.into()),
+        }
+
+        file.set_len(0)?;
+        file.seek(SeekFrom::Start(0))?;
+        writeln!(file, "{}\n{}", std::process::id(), Utc::now().to_rfc3339())?;
+        file.flush()?;
+        Ok(Ok(Self { _file: file, path }))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+/// `operation`, suffixed with the profile if one is selected
+fn lock_name(operation: &str, profile: Option<&str>) -> String {
+    match profile {
+        None => operation.to_string(),
+        Some(profile) => {
+            // Profile names are TOML keys and may hold anything; the file name may not
+            let profile: String = profile
+                .chars()
+                .map(|c| {
+                    if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                        c
+                    } else {
+                        '_'
+                    }
+                })
+                .collect();
+            format!("{}-{}", operation, profile)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_second_acquire_reports_holder() -> Result<(), BackupServiceError> {
+        let dir = tempfile::tempdir()?;
+        let first = InstanceLock::acquire_in(dir.path(), "run")?.expect("first run locks");
+
+        let holder = InstanceLock::acquire_in(dir.path(), "run")?.unwrap_err();
+        assert_eq!(holder.pid, Some(std::process::id()));
+        assert!(holder.started.is_some());
+
+        // Other operations are independent
+        assert!(InstanceLock::acquire_in(dir.path(), "prune")?.is_ok());
+
+        drop(first);
+        assert!(InstanceLock::acquire_in(dir.path(), "run")?.is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn test_profiles_lock_separately() -> Result<(), BackupServiceError> {
+        assert_eq!(lock_name("run", None), "run");
+        assert_eq!(lock_name("run", Some("work")), "run-work");
+        assert_eq!(lock_name("run", Some("../x y")), "run-___x_y");
+
+        let dir = tempfile::tempdir()?;
+        let _default = InstanceLock::acquire_in(dir.path(), &lock_name("run", None))?
+            .expect("default profile locks");
+        let work = InstanceLock::acquire_in(dir.path(), &lock_name("run", Some("work")))?
+            .expect("a profile does not wait for another");
+        assert_eq!(work.path(), dir.path().join(".restic-backup-run-work.lock"));
+        assert!(InstanceLock::acquire_in(dir.path(), &lock_name("run", Some("work")))?.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_holder_description() {
+        let started = DateTime::parse_from_rfc3339("2025-01-15T10:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let now = started + chrono::Duration::minutes(72);
+        let holder = LockHolder {
+            pid: Some(4242),
+            started: Some(started),
+        };
+        assert_eq!(holder.describe(now), "pid 4242, started 01:12 ago");
+        assert_eq!(LockHolder::parse("garbage").describe(now), "unknown pid");
+    }
+}
@@ -0,0 +1,395 @@
+use crate::config::Config;
+use crate::errors::BackupServiceError;
+use crate::shared::commands::ResticCommandExecutor;
+use crate::shared::display::DisplayFormatter;
+use crate::shared::operations::RepositoryOperations;
+use crate::shared::ui::confirm_destructive;
+use crate::utils::validate_credentials;
+use serde::Serialize;
+use serde_json::{Value, json};
+use std::path::{Path, PathBuf};
+use tracing::{error, info, warn};
+
+/// `key` options shared by every action
+#[derive(Debug, Clone, Default)]
+pub struct KeyOptions {
+    /// Host whose repositories to change (default: this host)
+    pub host: Option<String>,
+    pub assume_yes: bool,
+    /// Hostname repeated as a safeguard for --yes
+    pub confirm: Option<String>,
+    pub json_output: bool,
+}
+
+/// `key` action applied to every repository of a host
+#[derive(Debug, Clone, PartialEq)]
+pub enum KeyAction {
+    List,
+    /// Add a key for the password in the file
+    Add {
+        new_password_file: PathBuf,
+    },
+    /// Remove the key the password in the file opens
+    Remove {
+        password_file: PathBuf,
+    },
+    /// Add the new key everywhere, verify it everywhere, then remove the old key
+    Rotate {
+        new_password_file: PathBuf,
+    },
+}
+
+impl KeyAction {
+    fn name(&self) -> &'static str {
+        match self {
+            KeyAction::List => "list",
+            KeyAction::Add { .. } => "add",
+            KeyAction::Remove { .. } => "remove",
+            KeyAction::Rotate { .. } => "rotate",
+        }
+    }
+}
+
+/// Outcome of a key action on one repository
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct KeyResult {
+    pub repo_subpath: String,
+    /// Keys after the action (`list`), as reported by restic
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub keys: Vec<Value>,
+    /// Key added by `add`/`rotate`
+    pub added: Option<String>,
+    /// Key removed by `remove`/`rotate`
+    pub removed: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Password from a file, trimmed like restic reads `--password-file`
+fn read_password_file(file: &Path) -> Result<String, BackupServiceError> {
+    let password = std::fs::read_to_string(file).map_err(|e| {
+        BackupServiceError::ConfigurationError(format!(
+            "Cannot read password file {}: {}.\n\nCheck the path and that the current user may read it",
+            file.display(),
+            e
+        ))
+    })?;
+    let password = password.trim().to_string();
+    if password.is_empty() {
+        return Err(BackupServiceError::ConfigurationError(format!(
+            "Password file {} is empty",
+            file.display()
+        )));
+    }
+    Ok(password)
+}
+
+/// ID of the key marked `current`, i.e. the one the listing password opened
+pub fn current_key_id(keys: &[Value]) -> Option<String> {
+    keys.iter()
+        .find(|k| k["current"].as_bool() == Some(true))
+        .and_then(|k| k["id"].as_str())
+        .map(str::to_string)
+}
+
+/// The same repository opened with another password
+fn with_password(
+    config: &Config,
+    repo_url: &str,
+    password: &str,
+) -> Result<ResticCommandExecutor, BackupServiceError> {
+    let mut config = config.clone();
+    config.restic_password = password.to_string();
+    ResticCommandExecutor::new(config, repo_url.to_string())
+}
+
+/// The key the new password opens, unless it existed before `key add`
+fn added_key_id(before: &[Value], after: &[Value]) -> Option<String> {
+    current_key_id(after).filter(|id| !before.iter().any(|k| k["id"].as_str() == Some(id.as_str())))
+}
+
+/// Add the new key unless it already opens the repository; returns (old key, new key)
+async fn add_key(
+    config: &Config,
+    repo_url: &str,
+    new_password_file: &Path,
+    new_password: &str,
+) -> Result<(Option<String>, Option<String>), BackupServiceError> {
+    let current = ResticCommandExecutor::new(config.clone(), repo_url.to_string())?;
+    let new = with_password(config, repo_url, new_password)?;
+    // A rerun after an interrupted rotation finds the new key in place, and maybe the old
+    // one already removed
+    if let Ok(keys) = new.keys().await
+        && let Some(id) = current_key_id(&keys)
+    {
+        let old_key = current.keys().await.ok().and_then(|k| current_key_id(&k));
+        return Ok((old_key, Some(id)));
+    }
+    let before = current.keys().await?;
+    let old_key = current_key_id(&before).ok_or_else(|| {
+        BackupServiceError::CommandFailed("restic marked no key as current".to_string())
+    })?;
+    current.add_key(new_password_file).await?;
+    let after = new.keys().await?;
+    Ok((Some(old_key), added_key_id(&before, &after)))
+}
+
+/// Apply a key action to every repository of a host
+///
+/// `rotate` is two-phase: the new key is added to every repository and verified by opening
+/// each one with the new password; only when that worked everywhere are the old keys
+/// removed, so a failure never leaves a repository that neither password opens.
+pub async fn execute_key_workflow(
+    config: Config,
+    action: KeyAction,
+    options: KeyOptions,
+) -> Result<(), BackupServiceError> {
+    let hostname = options
+        .host
+        .clone()
+        .unwrap_or_else(|| config.hostname.clone());
+    let password = match &action {
+        KeyAction::List => None,
+        KeyAction::Add { new_password_file } | KeyAction::Rotate { new_password_file } => {
+            Some(read_password_file(new_password_file)?)
+        }
+        KeyAction::Remove { password_file } => Some(read_password_file(password_file)?),
+    };
+    if password.as_deref() == Some(config.restic_password.as_str()) {
+        return Err(BackupServiceError::ConfigurationError(
+            "The password file holds the current repository password.\n\nPass the new (add, rotate) or the retired (remove) password"
+                .to_string(),
+        ));
+    }
+    config.set_aws_env()?;
+    validate_credentials(&config).await?;
+
+    let operations = RepositoryOperations::new(config.clone())?;
+    let discovery = operations.discover_all_repositories(&hostname).await?;
+    for failure in &discovery.failures {
+        error!(failure = %failure, "Repository discovery incomplete");
+    }
+    let mut failed: Vec<String> = discovery.failures.iter().map(|f| f.scope.clone()).collect();
+    let repositories: Vec<(String, String)> = discovery
+        .repos
+        .iter()
+        .map(|r| {
+            Ok((
+                r.repo_subpath.clone(),
+                config.get_repo_url_for_host(&hostname, &r.repo_subpath)?,
+            ))
+        })
+        .collect::<Result<_, BackupServiceError>>()?;
+    match &action {
+        KeyAction::Remove { .. } => {
+            confirm_destructive(
+                "remove a key from",
+                &hostname,
+                options.assume_yes,
+                options.confirm.as_deref(),
+            )?;
+        }
+        KeyAction::Rotate { .. } => {
+            // Old keys of repositories discovery missed would stay valid
+            if !failed.is_empty() {
+                return Err(BackupServiceError::CommandFailed(format!(
+                    "Not rotating: discovery failed for {}",
+                    failed.join(", ")
+                )));
+            }
+            confirm_destructive(
+                "rotate the password of",
+                &hostname,
+                options.assume_yes,
+                options.confirm.as_deref(),
+            )?;
+        }
+        _ => {}
+    }
+
+    let mut results: Vec<KeyResult> = Vec::with_capacity(repositories.len());
+    let mut old_keys: Vec<Option<String>> = Vec::with_capacity(repositories.len());
+    for (idx, (repo_subpath, repo_url)) in repositories.iter().enumerate() {
+        let progress = format!("({}/{})", idx + 1, repositories.len());
+        let mut result = KeyResult {
+            repo_subpath: repo_subpath.clone(),
+            ..KeyResult::default()
+        };
+        // The key that opened the repository before a rotation
+        let outcome: Result<Option<String>, BackupServiceError> = async {
+            match &action {
+                KeyAction::List => {
+                    result.keys = ResticCommandExecutor::new(config.clone(), repo_url.clone())?
+                        .keys()
+                        .await?;
+                }
+                KeyAction::Add { new_password_file } | KeyAction::Rotate { new_password_file } => {
+                    let new_password = password.as_deref().unwrap_or_default();
+                    let (old_key, added) =
+                        add_key(&config, repo_url, new_password_file, new_password).await?;
+                    result.added = added;
+                    return Ok(old_key);
+                }
+                KeyAction::Remove { .. } => {
+                    let retired = password.as_deref().unwrap_or_default();
+                    let key =
+                        current_key_id(&with_password(&config, repo_url, retired)?.keys().await?)
+                            .ok_or_else(|| {
+                            BackupServiceError::CommandFailed(
+                                "restic marked no key as current".to_string(),
+                            )
+                        })?;
+                    ResticCommandExecutor::new(config.clone(), repo_url.clone())?
+                        .remove_key(&key)
+                        .await?;
+                    result.removed = Some(key);
+                }
+            }
+            Ok(None)
+        }
+        .await;
+        match outcome {
+            Ok(old_key) => {
+                info!(progress = %progress, repo_subpath = %repo_subpath, action = %action.name(), "Key action applied");
+                old_keys.push(old_key);
+            }
+            Err(e) => {
+                error!(progress = %progress, repo_subpath = %repo_subpath, action = %action.name(), error = %e, "Key action failed");
+                old_keys.push(None);
+                result.error = Some(e.to_string());
+                failed.push(repo_subpath.clone());
+            }
+        }
+        results.push(result);
+    }
+
+    if let KeyAction::Rotate { .. } = &action {
+        if failed.is_empty() {
+            remove_old_keys(
+                &config,
+                password.as_deref().unwrap_or_default(),
+                &repositories,
+                &old_keys,
+                &mut results,
+                &mut failed,
+            )
+            .await;
+        } else {
+            warn!(
+                failed = %failed.len(),
+                "Not all repositories accept the new key; the old keys were kept everywhere"
+            );
+        }
+    }
+
+    if options.json_output {
+        let output = json!({
+            "host": hostname,
+            "action": action.name(),
+            "repositories": results,
+            "failed": failed,
+        });
+        DisplayFormatter::print_json(&output)?;
+    } else if action == KeyAction::List {
+        for result in &results {
+            for key in &result.keys {
+                info!(
+                    repo_subpath = %result.repo_subpath,
+                    id = %key["id"].as_str().unwrap_or_default(),
+                    user = %key["userName"].as_str().unwrap_or_default(),
+                    host = %key["hostName"].as_str().unwrap_or_default(),
+                    created = %key["created"].as_str().unwrap_or_default(),
+                    current = %key["current"].as_bool().unwrap_or(false),
+                    "Key"
+                );
+            }
+        }
+    }
+
+    if failed.is_empty() {
+        if matches!(action, KeyAction::Rotate { .. }) {
+            info!(
+                host = %hostname,
+                repositories = %results.len(),
+                "Password rotated; store the new password as RESTIC_PASSWORD before the next run"
+            );
+        }
+        Ok(())
+    } else {
+        Err(BackupServiceError::CommandFailed(format!(
+            "key {} failed for {} of host {}: {}",
+            action.name(),
+            failed.len(),
+            hostname,
+            failed.join(", ")
+        )))
+    }
+}
+
+/// Second phase of `rotate`: verify the new password everywhere, then remove the old keys
+async fn remove_old_keys(
+    config: &Config,
+    new_password: &str,
+    repositories: &[(String, String)],
+    old_keys: &[Option<String>],
+    results: &mut [KeyResult],
+    failed: &mut Vec<String>,
+) {
+    for (repo_subpath, repo_url) in repositories {
+        let verified = match with_password(config, repo_url, new_password) {
+            Ok(new) => new.keys().await.map(|_| ()),
+            Err(e) => Err(e),
+        };
+        if let Err(e) = verified {
+            error!(repo_subpath = %repo_subpath, error = %e, "New password does not open the repository");
+            failed.push(repo_subpath.clone());
+        }
+    }
+    if !failed.is_empty() {
+        warn!("Verification failed; the old keys were kept everywhere");
+        return;
+    }
+    info!(repositories = %repositories.len(), "New password verified everywhere, removing the old keys");
+
+    for (((repo_subpath, repo_url), old_key), result) in
+        repositories.iter().zip(old_keys).zip(results.iter_mut())
+    {
+        let Some(old_key) = old_key else { continue };
+        let outcome = match with_password(config, repo_url, new_password) {
+            Ok(new) => new.remove_key(old_key).await,
+            Err(e) => Err(e),
+        };
+        match outcome {
+            Ok(()) => result.removed = Some(old_key.clone()),
+            Err(e) => {
+                error!(repo_subpath = %repo_subpath, error = %e, "Removing the old key failed");
+                result.error = Some(e.to_string());
+                failed.push(repo_subpath.clone());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_ids() {
+        let before = vec![
+            json!({"current": true, "id": "0a1b2c3d", "userName": "root"}),
+            json!({"current": false, "id": "9f8e7d6c", "userName": "tim"}),
+        ];
+        assert_eq!(current_key_id(&before).as_deref(), Some("0a1b2c3d"));
+        assert_eq!(current_key_id(&[json!({"id": "x"})]), None);
+
+        let after = vec![
+            json!({"current": false, "id": "0a1b2c3d"}),
+            json!({"current": false, "id": "9f8e7d6c"}),
+            json!({"current": true, "id": "5e6f7a8b"}),
+        ];
+        assert_eq!(added_key_id(&before, &after).as_deref(), Some("5e6f7a8b"));
+        // Opening with a password that already had a key adds nothing
+        let reopened = vec![json!({"current": true, "id": "9f8e7d6c"})];
+        assert_eq!(added_key_id(&before, &reopened), None);
+    }
+}
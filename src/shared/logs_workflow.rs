@@ -0,0 +1,468 @@
+use crate::errors::BackupServiceError;
+use crate::utils::parse_size;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use tracing::{Level, info, warn};
+
+/// Env var overriding where the rolling log files are written
+pub const LOG_DIR_ENV_VAR: &str = "RBS_LOG_DIR";
+/// Same as RBS_LOG_DIR, which wins when both are set
+pub const LOG_DIR_ALIAS_ENV_VAR: &str = "LOG_DIR";
+/// Last resort when neither XDG_STATE_HOME nor HOME is set
+pub const DEFAULT_LOG_DIR: &str = "./logs";
+/// Days a rolled log file is kept; 0 keeps them forever
+pub const LOG_RETENTION_DAYS_ENV_VAR: &str = "LOG_RETENTION_DAYS";
+const DEFAULT_LOG_RETENTION_DAYS: i64 = 30;
+/// Cap on the total size of the log files (e.g. 500M); the oldest rolled files go first
+pub const LOG_MAX_SIZE_ENV_VAR: &str = "LOG_MAX_SIZE";
+/// Daily files are named `<prefix>.YYYY-MM-DD`
+pub const LOG_FILE_PREFIX: &str = "restic-backup.log";
+
+/// Lines printed when neither --since nor --lines is given
+const DEFAULT_TAIL_LINES: usize = 100;
+
+/// How often `--follow` checks for new lines or a rotated file
+const FOLLOW_POLL_MS: u64 = 500;
+
+/// Set while a full-screen view owns the terminal; log lines then only reach the file
+static CONSOLE_MUTED: AtomicBool = AtomicBool::new(false);
+
+pub fn mute_console(muted: bool) {
+    CONSOLE_MUTED.store(muted, Ordering::Relaxed);
+}
+
+pub fn console_muted() -> bool {
+    CONSOLE_MUTED.load(Ordering::Relaxed)
+}
+
+/// `logs` options
+#[derive(Debug, Clone, Default)]
+pub struct LogsOptions {
+    pub follow: bool,
+    /// Only lines newer than this (e.g. 30m, 1h, 2d)
+    pub since: Option<String>,
+    /// Minimum level to show (trace, debug, info, warn, error)
+    pub level: Option<String>,
+    /// Show at most this many lines from the end
+    pub lines: Option<usize>,
+}
+
+/// Where the rolling log files (and the state files kept next to them) live
+pub fn log_dir() -> PathBuf {
+    resolve_log_dir(|name| std::env::var(name).ok().filter(|v| !v.trim().is_empty()))
+}
+
+/// RBS_LOG_DIR, then LOG_DIR, then the XDG state directory, so the location no longer
+/// depends on the working directory
+fn resolve_log_dir(var: impl Fn(&str) -> Option<String>) -> PathBuf {
+    if let Some(dir) = var(LOG_DIR_ENV_VAR).or_else(|| var(LOG_DIR_ALIAS_ENV_VAR)) {
+        return PathBuf::from(dir);
+    }
+    if let Some(state) = var("XDG_STATE_HOME") {
+        return Path::new(&state).join("restic-backup-service/logs");
+    }
+    if let Some(home) = var("HOME") {
+        return Path::new(&home).join(".local/state/restic-backup-service/logs");
+    }
+    PathBuf::from(DEFAULT_LOG_DIR)
+}
+
+/// Day a rolled file was written, from its `.YYYY-MM-DD` suffix
+fn log_file_date(path: &Path) -> Option<NaiveDate> {
+    let name = path.file_name()?.to_str()?;
+    let suffix = name.strip_prefix(LOG_FILE_PREFIX)?.strip_prefix('.')?;
+    NaiveDate::parse_from_str(suffix, "%Y-%m-%d").ok()
+}
+
+/// Rolled files to delete, given `(path, day, size)` oldest first: those older than
+/// `retention_days`, then the oldest remaining until the rest fit in `max_bytes`. The file
+/// for `today` is still being written and always stays.
+pub fn files_to_prune(
+    files: &[(PathBuf, NaiveDate, u64)],
+    today: NaiveDate,
+    retention_days: i64,
+    max_bytes: Option<u64>,
+) -> Vec<PathBuf> {
+    let mut doomed = Vec::new();
+    let mut total: u64 = files.iter().map(|(_, _, size)| size).sum();
+    for (path, day, size) in files {
+        if *day >= today {
+            continue;
+        }
+        let expired = retention_days > 0 && (today - *day).num_days() > retention_days;
+        let over_cap = max_bytes.is_some_and(|max| total > max);
+        if expired || over_cap {
+            doomed.push(path.clone());
+            total -= size;
+        }
+    }
+    doomed
+}
+
+/// Delete rolled log files past LOG_RETENTION_DAYS (default 30) or beyond LOG_MAX_SIZE;
+/// returns the deleted paths
+pub fn prune_log_files(dir: &Path) -> Result<Vec<PathBuf>, BackupServiceError> {
+    let retention_days = match std::env::var(LOG_RETENTION_DAYS_ENV_VAR) {
+        Ok(value) if !value.trim().is_empty() => value
+            .trim()
+            .parse::<i64>()
+            .ok()
+            .filter(|d| *d >= 0)
+            .ok_or_else(|| {
+                BackupServiceError::ConfigurationError(format!(
+                    "Invalid {}: {}.\n\nUse a number of days, or 0 to keep log files forever",
+                    LOG_RETENTION_DAYS_ENV_VAR, value
+                ))
+            })?,
+        _ => DEFAULT_LOG_RETENTION_DAYS,
+    };
+    let max_bytes = match std::env::var(LOG_MAX_SIZE_ENV_VAR) {
+        Ok(value) if !value.trim().is_empty() => Some(parse_size(&value)?),
+        _ => None,
+    };
+
+    let mut files = Vec::new();
+    for path in log_files(dir)? {
+        let Some(day) = log_file_date(&path) else {
+            continue;
+        };
+        let size = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+        files.push((path, day, size));
+    }
+    let doomed = files_to_prune(&files, Utc::now().date_naive(), retention_days, max_bytes);
+    for path in &doomed {
+        std::fs::remove_file(path)?;
+    }
+    Ok(doomed)
+}
+
+/// `prune_log_files` for startup and the daemon loop: problems only warn
+pub fn cleanup_log_files(dir: &Path) {
+    match prune_log_files(dir) {
+        Ok(deleted) if !deleted.is_empty() => {
+            info!(count = deleted.len(), dir = %dir.display(), "Removed old log files")
+        }
+        Ok(_) => {}
+        Err(e) => warn!(error = %e, dir = %dir.display(), "Could not clean up old log files"),
+    }
+}
+
+/// Rolling log files in `dir`, oldest first (the date suffix sorts chronologically)
+pub fn log_files(dir: &Path) -> Result<Vec<PathBuf>, BackupServiceError> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(dir)
+        .map_err(|e| {
+            BackupServiceError::ConfigurationError(format!(
+                "Cannot read log directory {}: {}.\n\nSet {} to the service's log directory (the NixOS module uses /var/log/restic-backup)",
+                dir.display(),
+                e,
+                LOG_DIR_ENV_VAR
+            ))
+        })?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with(LOG_FILE_PREFIX))
+        })
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+/// Parse `30s`, `15m`, `1h`, `2d` or `1w`
+pub fn parse_since(value: &str) -> Result<Duration, BackupServiceError> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (amount, unit) = value.split_at(split);
+    let amount: i64 = amount.parse().map_err(|_| invalid_since(value))?;
+    match unit {
+        "s" => Ok(Duration::seconds(amount)),
+        "m" => Ok(Duration::minutes(amount)),
+        "h" | "" => Ok(Duration::hours(amount)),
+        "d" => Ok(Duration::days(amount)),
+        "w" => Ok(Duration::weeks(amount)),
+        _ => Err(invalid_since(value)),
+    }
+}
+
+fn invalid_since(value: &str) -> BackupServiceError {
+    BackupServiceError::ConfigurationError(format!(
+        "Invalid --since value: {}.\n\nUse a number with a unit: 30s, 15m, 1h, 2d, 1w",
+        value
+    ))
+}
+
+pub fn parse_level(value: &str) -> Result<Level, BackupServiceError> {
+    value.trim().parse::<Level>().map_err(|_| {
+        BackupServiceError::ConfigurationError(format!(
+            "Invalid --level value: {}.\n\nValid levels are: trace, debug, info, warn, error",
+            value
+        ))
+    })
+}
+
+/// Remove the terminal color codes tracing writes into the files as well
+pub fn strip_ansi(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c == '\u{1b}' {
+            // CSI sequence: ESC [ params final-byte
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// Timestamp and level of a `tracing_subscriber::fmt` line; None for continuation lines
+pub fn parse_line(line: &str) -> Option<(DateTime<Utc>, Level)> {
+    let mut words = line.split_whitespace();
+    let timestamp = DateTime::parse_from_rfc3339(words.next()?).ok()?;
+    let level = words.next()?.parse::<Level>().ok()?;
+    Some((timestamp.with_timezone(&Utc), level))
+}
+
+/// Decides which lines to show; continuation lines follow their entry
+struct LineFilter {
+    since: Option<DateTime<Utc>>,
+    // tracing orders levels by verbosity: TRACE > DEBUG > ... > ERROR
+    min_level: Option<Level>,
+    showing: bool,
+}
+
+impl LineFilter {
+    fn accepts(&mut self, line: &str) -> bool {
+        if let Some((timestamp, level)) = parse_line(line) {
+            self.showing = self.since.is_none_or(|since| timestamp >= since)
+                && self.min_level.is_none_or(|min| level <= min);
+        }
+        self.showing
+    }
+}
+
+/// Print matching lines of `file` from `offset`; returns the new offset
+fn print_new_lines(
+    file: &Path,
+    offset: u64,
+    filter: &mut LineFilter,
+) -> Result<u64, BackupServiceError> {
+    let mut reader = BufReader::new(File::open(file)?);
+    reader.seek(SeekFrom::Start(offset))?;
+    let mut offset = offset;
+    let mut line = String::new();
+    loop {
+        line.clear();
+        let read = reader.read_line(&mut line)?;
+        // Only consume complete lines; a partial one is re-read on the next poll
+        if read == 0 || !line.ends_with('\n') {
+            return Ok(offset);
+        }
+        offset += read as u64;
+        let clean = strip_ansi(line.trim_end());
+        if filter.accepts(&clean) {
+            println!("{}", clean);
+        }
+    }
+}
+
+/// Print (and optionally follow) the rolling service logs
+pub async fn execute_logs(options: LogsOptions) -> Result<(), BackupServiceError> {
+    let dir = log_dir();
+    let since = options
+        .since
+        .as_deref()
+        .map(parse_since)
+        .transpose()?
+        .map(|d| Utc::now() - d);
+    let min_level = options.level.as_deref().map(parse_level).transpose()?;
+    let mut filter = LineFilter {
+        since,
+        min_level,
+        showing: true,
+    };
+
+    let files = log_files(&dir)?;
+    if files.is_empty() && !options.follow {
+        println!("No log files found in {}", dir.display());
+        return Ok(());
+    }
+
+    // Without --since, only the tail of the newest file is of interest
+    let tail = options
+        .lines
+        .or(since.is_none().then_some(DEFAULT_TAIL_LINES));
+    let selected: Vec<PathBuf> = match since {
+        Some(_) => files.clone(),
+        None => files.last().cloned().into_iter().collect(),
+    };
+
+    let mut matching = Vec::new();
+    for file in &selected {
+        for line in BufReader::new(File::open(file)?).lines() {
+            let clean = strip_ansi(line?.trim_end());
+            if filter.accepts(&clean) {
+                matching.push(clean);
+            }
+        }
+    }
+    let skip = tail.map_or(0, |n| matching.len().saturating_sub(n));
+    for line in &matching[skip..] {
+        println!("{}", line);
+    }
+
+    if !options.follow {
+        return Ok(());
+    }
+
+    // Follow the newest file, switching over when the appender rotates at midnight
+    let mut current = files.last().cloned();
+    let mut offset = match &current {
+        Some(file) => std::fs::metadata(file)?.len(),
+        None => 0,
+    };
+    loop {
+        tokio::time::sleep(std::time::Duration::from_millis(FOLLOW_POLL_MS)).await;
+        if let Some(file) = &current {
+            offset = print_new_lines(file, offset, &mut filter)?;
+        }
+        let newest = log_files(&dir)?.pop();
+        if newest.is_some() && newest != current {
+            if let Some(file) = &current {
+                print_new_lines(file, offset, &mut filter)?;
+            }
+            current = newest;
+            offset = 0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_since() -> Result<(), BackupServiceError> {
+        assert_eq!(parse_since("30m")?, Duration::minutes(30));
+        assert_eq!(parse_since("1h")?, Duration::hours(1));
+        assert_eq!(parse_since("2d")?, Duration::days(2));
+        assert_eq!(parse_since("6")?, Duration::hours(6));
+        assert!(parse_since("1y").is_err());
+        assert!(parse_since("h").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_colored_line() {
+        let raw = "\u{1b}[2m2025-01-15T10:30:00.123456Z\u{1b}[0m \u{1b}[33m WARN\u{1b}[0m \
+                   \u{1b}[2mrestic_backup_service::shared::backup_workflow\u{1b}[0m\u{1b}[2m:\u{1b}[0m Path does not exist";
+        let clean = strip_ansi(raw);
+        assert!(clean.starts_with("2025-01-15T10:30:00.123456Z  WARN restic_backup_service"));
+
+        let (timestamp, level) = parse_line(&clean).unwrap();
+        assert_eq!(timestamp.to_rfc3339(), "2025-01-15T10:30:00.123456+00:00");
+        assert_eq!(level, Level::WARN);
+        assert_eq!(parse_line("  continuation of a multi-line message"), None);
+    }
+
+    #[test]
+    fn test_filter_by_level_keeps_continuations() {
+        let mut filter = LineFilter {
+            since: None,
+            min_level: Some(Level::WARN),
+            showing: true,
+        };
+        assert!(!filter.accepts("2025-01-15T10:30:00Z  INFO x: started"));
+        assert!(!filter.accepts("  detail of the info line"));
+        assert!(filter.accepts("2025-01-15T10:31:00Z ERROR x: failed"));
+        assert!(filter.accepts("  detail of the error line"));
+        assert!(filter.accepts("2025-01-15T10:32:00Z  WARN x: degraded"));
+    }
+
+    #[test]
+    fn test_log_dir_and_pruning() {
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                vars.iter()
+                    .find(|(k, _)| *k == name)
+                    .map(|(_, v)| v.to_string())
+            }
+        };
+        assert_eq!(
+            resolve_log_dir(env(&[("LOG_DIR", "/srv/logs"), ("HOME", "/home/tim")])),
+            PathBuf::from("/srv/logs")
+        );
+        assert_eq!(
+            resolve_log_dir(env(&[
+                ("RBS_LOG_DIR", "/var/log/rbs"),
+                ("LOG_DIR", "/srv/logs")
+            ])),
+            PathBuf::from("/var/log/rbs")
+        );
+        assert_eq!(
+            resolve_log_dir(env(&[("HOME", "/home/tim")])),
+            PathBuf::from("/home/tim/.local/state/restic-backup-service/logs")
+        );
+        assert_eq!(resolve_log_dir(env(&[])), PathBuf::from("./logs"));
+        assert_eq!(
+            log_file_date(Path::new("/x/restic-backup.log.2025-01-15")),
+            NaiveDate::from_ymd_opt(2025, 1, 15)
+        );
+        assert_eq!(log_file_date(Path::new("/x/restic-backup.log")), None);
+
+        let day = |d: u32| NaiveDate::from_ymd_opt(2025, 1, d).unwrap();
+        let files: Vec<(PathBuf, NaiveDate, u64)> = [1, 10, 14, 15]
+            .into_iter()
+            .map(|d| (PathBuf::from(format!("f{}", d)), day(d), 100))
+            .collect();
+        // With 7 days kept only the file from the 1st has expired
+        assert_eq!(
+            files_to_prune(&files, day(15), 7, None),
+            ["f1"].map(PathBuf::from)
+        );
+        assert!(files_to_prune(&files, day(15), 0, None).is_empty());
+        // Over the cap the oldest go first, but today's file stays even when alone too big
+        assert_eq!(
+            files_to_prune(&files, day(15), 0, Some(250)),
+            ["f1", "f10"].map(PathBuf::from)
+        );
+        assert_eq!(
+            files_to_prune(&files, day(15), 0, Some(50)),
+            ["f1", "f10", "f14"].map(PathBuf::from)
+        );
+    }
+
+    #[test]
+    fn test_log_files_sorted_by_date() -> Result<(), BackupServiceError> {
+        let dir = tempfile::tempdir()?;
+        for name in [
+            "restic-backup.log.2025-01-16",
+            "restic-backup.log.2025-01-15",
+            "other.txt",
+        ] {
+            std::fs::write(dir.path().join(name), "")?;
+        }
+        let names: Vec<String> = log_files(dir.path())?
+            .iter()
+            .map(|p| p.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                "restic-backup.log.2025-01-15",
+                "restic-backup.log.2025-01-16"
+            ]
+        );
+        Ok(())
+    }
+}
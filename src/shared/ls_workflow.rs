@@ -0,0 +1,249 @@
+use crate::config::Config;
+use crate::errors::BackupServiceError;
+use crate::shared::commands::ResticCommandExecutor;
+use crate::shared::content_policy::wildcard_match;
+use crate::shared::display::DisplayFormatter;
+use crate::shared::operations::RepositoryOperations;
+use crate::shared::paths::PathMapper;
+use crate::shared::snapshot_filter::serialize_time;
+use crate::utils::validate_credentials;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::{Value, json};
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+/// `ls` options
+#[derive(Debug, Clone)]
+pub struct LsOptions {
+    /// Snapshot ID (short or full); the newest snapshot when unset
+    pub snapshot: Option<String>,
+    /// Levels below the listed directory to show; 0 shows the whole tree
+    pub depth: usize,
+    /// Only entries whose name matches one of these (`*` and `?`)
+    pub globs: Vec<String>,
+    pub json_output: bool,
+}
+
+impl Default for LsOptions {
+    fn default() -> Self {
+        Self {
+            snapshot: None,
+            depth: 1,
+            globs: Vec::new(),
+            json_output: false,
+        }
+    }
+}
+
+/// The snapshot a listing was read from
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LsSnapshot {
+    pub id: String,
+    #[serde(serialize_with = "serialize_time")]
+    pub time: DateTime<Utc>,
+}
+
+/// One file, directory or link in the listing
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LsEntry {
+    pub path: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+    /// Levels below the listed directory (1 = its direct entries)
+    pub depth: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+    /// Modification time as restic reports it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mtime: Option<String>,
+}
+
+/// Turn `restic ls --json` output into the snapshot and the entries below `dir` that are
+/// within `depth` and match a glob
+pub fn parse_ls_output(
+    output: &str,
+    dir: &Path,
+    depth: usize,
+    globs: &[String],
+) -> (Option<LsSnapshot>, Vec<LsEntry>) {
+    let mut snapshot = None;
+    let mut entries = Vec::new();
+    for line in output.lines() {
+        let Ok(value) = serde_json::from_str::<Value>(line) else {
+            continue;
+        };
+        // restic >= 0.17 names the kind in message_type, older versions in struct_type
+        let kind = value["message_type"]
+            .as_str()
+            .or_else(|| value["struct_type"].as_str());
+        match kind {
+            Some("snapshot") => {
+                snapshot = value["time"]
+                    .as_str()
+                    .and_then(|t| t.parse().ok())
+                    .map(|time| LsSnapshot {
+                        id: value["short_id"]
+                            .as_str()
+                            .or_else(|| value["id"].as_str().map(|id| &id[..id.len().min(8)]))
+                            .unwrap_or_default()
+                            .to_string(),
+                        time,
+                    });
+            }
+            Some("node") => {
+                let Some(path) = value["path"].as_str() else {
+                    continue;
+                };
+                let Ok(relative) = Path::new(path).strip_prefix(dir) else {
+                    continue;
+                };
+                let level = relative.components().count();
+                if level == 0 || (depth > 0 && level > depth) {
+                    continue;
+                }
+                let name = value["name"].as_str().unwrap_or_default();
+                if !globs.is_empty() && !globs.iter().any(|glob| wildcard_match(glob, name)) {
+                    continue;
+                }
+                entries.push(LsEntry {
+                    path: path.to_string(),
+                    kind: value["type"].as_str().unwrap_or("file").to_string(),
+                    depth: level,
+                    size: value["size"].as_u64(),
+                    mtime: value["mtime"].as_str().map(str::to_string),
+                });
+            }
+            _ => {}
+        }
+    }
+    entries.sort_by(|a, b| a.path.cmp(&b.path));
+    (snapshot, entries)
+}
+
+/// The repository holding `path`: the backed-up path at or above it with a repository
+fn owning_repository(path: &Path, repo_subpaths: &[String]) -> Option<(PathBuf, String)> {
+    path.ancestors().find_map(|ancestor| {
+        let subpath = PathMapper::candidate_subpaths(ancestor)
+            .ok()?
+            .into_iter()
+            .find(|subpath| repo_subpaths.contains(subpath))?;
+        Some((ancestor.to_path_buf(), subpath))
+    })
+}
+
+/// Show the files of a snapshot below `path`
+pub async fn execute_ls_workflow(
+    config: Config,
+    host: Option<String>,
+    path: String,
+    options: LsOptions,
+) -> Result<(), BackupServiceError> {
+    let hostname = host.unwrap_or_else(|| config.hostname.clone());
+    let dir = match path.trim_end_matches('/') {
+        "" => PathBuf::from("/"),
+        trimmed => PathBuf::from(trimmed),
+    };
+    if !dir.is_absolute() {
+        return Err(BackupServiceError::ConfigurationError(format!(
+            "Path must be absolute: {}.\n\nGive the path as it was backed up, e.g. /home/tim/Documents",
+            path
+        )));
+    }
+    config.set_aws_env()?;
+    validate_credentials(&config).await?;
+
+    let operations = RepositoryOperations::new(config.clone())?;
+    let discovery = operations.discover_all_repositories(&hostname).await?;
+    let repo_subpaths: Vec<String> = discovery
+        .repos
+        .into_iter()
+        .map(|r| r.repo_subpath)
+        .collect();
+    let Some((backed_up, repo_subpath)) = owning_repository(&dir, &repo_subpaths) else {
+        return Err(BackupServiceError::ConfigurationError(format!(
+            "No backup of host {} contains {}.\n\nRun `restic-backup-service list -H {}` to see the backed-up paths",
+            hostname,
+            dir.display(),
+            hostname
+        )));
+    };
+
+    let snapshot = options.snapshot.as_deref().unwrap_or("latest");
+    if !options.json_output {
+        info!(
+            path = %dir.display(),
+            repo_subpath = %repo_subpath,
+            snapshot = %snapshot,
+            "Listing snapshot contents"
+        );
+    }
+    let repo_url = config.get_repo_url_for_host(&hostname, &repo_subpath)?;
+    let restic_cmd = ResticCommandExecutor::new(config, repo_url)?;
+    let output = restic_cmd
+        .ls(snapshot, &dir.to_string_lossy(), options.depth != 1)
+        .await?;
+    let (listed, entries) = parse_ls_output(&output, &dir, options.depth, &options.globs);
+
+    if options.json_output {
+        let output = json!({
+            "host": hostname,
+            "path": dir,
+            "backup_path": backed_up,
+            "repo_subpath": repo_subpath,
+            "snapshot": listed,
+            "depth": options.depth,
+            "globs": options.globs,
+            "entries": entries,
+        });
+        DisplayFormatter::print_json(&output)?;
+    } else {
+        DisplayFormatter::display_ls(&dir, listed.as_ref(), &entries)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LS_OUTPUT: &str = r#"{"time":"2025-01-12T03:00:00Z","paths":["/home/tim"],"hostname":"web1","id":"4f1c2a9e7b0d3c51","short_id":"4f1c2a9e","struct_type":"snapshot"}
+{"name":"Documents","type":"dir","path":"/home/tim/Documents","mtime":"2024-12-02T10:15:00+01:00","struct_type":"node"}
+{"name":"report.docx","type":"file","path":"/home/tim/Documents/report.docx","size":18231,"mtime":"2024-12-02T10:15:00+01:00","struct_type":"node"}
+{"name":"2023","type":"dir","path":"/home/tim/Documents/2023","struct_type":"node"}
+{"name":"taxes.pdf","type":"file","path":"/home/tim/Documents/2023/taxes.pdf","size":90211,"struct_type":"node"}"#;
+
+    #[test]
+    fn test_parse_ls_output() {
+        let dir = Path::new("/home/tim/Documents");
+        let (snapshot, entries) = parse_ls_output(LS_OUTPUT, dir, 1, &[]);
+        let snapshot = snapshot.unwrap();
+        assert_eq!(snapshot.id, "4f1c2a9e");
+        assert_eq!(snapshot.time.to_rfc3339(), "2025-01-12T03:00:00+00:00");
+        // The directory itself is not an entry of its listing
+        let paths: Vec<_> = entries.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            [
+                "/home/tim/Documents/2023",
+                "/home/tim/Documents/report.docx"
+            ]
+        );
+        assert_eq!(entries[1].size, Some(18231));
+
+        let (_, whole_tree) = parse_ls_output(LS_OUTPUT, dir, 0, &[]);
+        assert_eq!(whole_tree.len(), 3);
+        assert_eq!(whole_tree[1].depth, 2);
+
+        let (_, pdfs) = parse_ls_output(LS_OUTPUT, dir, 0, &["*.pdf".to_string()]);
+        assert_eq!(pdfs.len(), 1);
+        assert_eq!(pdfs[0].path, "/home/tim/Documents/2023/taxes.pdf");
+
+        let repos = vec!["user_home/tim".to_string(), "system/etc".to_string()];
+        assert_eq!(
+            owning_repository(dir, &repos),
+            Some((PathBuf::from("/home/tim"), "user_home/tim".to_string()))
+        );
+        assert_eq!(owning_repository(Path::new("/srv/www"), &repos), None);
+    }
+}
@@ -0,0 +1,555 @@
+use crate::errors::BackupServiceError;
+use crate::shared::budgets::BudgetAlert;
+use crate::shared::http;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tracing::{info, warn};
+
+/// File for node_exporter's textfile collector, rewritten after every run
+pub const METRICS_TEXTFILE_ENV_VAR: &str = "METRICS_TEXTFILE";
+/// Prometheus Pushgateway base URL the metrics are pushed to after every run
+pub const METRICS_PUSHGATEWAY_ENV_VAR: &str = "METRICS_PUSHGATEWAY_URL";
+/// Address the daemon serves `/metrics` on (`--metrics-listen`)
+pub const METRICS_LISTEN_ENV_VAR: &str = "METRICS_LISTEN";
+
+const LAST_SUCCESS_METRIC: &str = "restic_backup_last_success_timestamp_seconds";
+
+/// Result of the latest run, served by the daemon's listener
+static LATEST: Mutex<Option<RunMetrics>> = Mutex::new(None);
+/// Whether this process serves `/metrics`
+static LISTENING: AtomicBool = AtomicBool::new(false);
+
+fn env_value(key: &str) -> Option<String> {
+    std::env::var(key)
+        .ok()
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+/// Whether any metrics sink is configured; per-repository snapshot counts cost an extra
+/// restic call per path, so they are only read when something consumes them
+pub fn is_enabled() -> bool {
+    env_value(METRICS_TEXTFILE_ENV_VAR).is_some()
+        || env_value(METRICS_PUSHGATEWAY_ENV_VAR).is_some()
+        || LISTENING.load(Ordering::SeqCst)
+}
+
+/// One backed-up path of a run
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathMetrics {
+    pub path: String,
+    /// `completed`, `degraded`, `skipped` or `failed`
+    pub status: &'static str,
+    pub data_added_bytes: u64,
+    pub duration_secs: f64,
+    /// Snapshots in the path's repository after the run (all hosts)
+    pub snapshot_count: Option<usize>,
+}
+
+/// Everything exported about one backup run
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RunMetrics {
+    pub hostname: String,
+    /// Unix time the run finished
+    pub finished_at: i64,
+    /// The run ended without an error (skipped and degraded paths still count as success)
+    pub succeeded: bool,
+    pub duration_secs: f64,
+    pub completed: usize,
+    pub degraded: usize,
+    pub skipped: usize,
+    pub failed: usize,
+    pub data_added_bytes: u64,
+    pub uploaded_bytes: u64,
+    /// Unix time of the last successful run, carried over from earlier runs after a failure
+    pub last_success: Option<i64>,
+    pub paths: Vec<PathMetrics>,
+    /// REPO_MAX_* budgets the host's repositories exceed after the run
+    pub budget_alerts: Vec<BudgetAlert>,
+}
+
+impl RunMetrics {
+    pub fn new(hostname: &str) -> Self {
+        Self {
+            hostname: hostname.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Stamp the run's end; a failed run keeps the previous success timestamp
+    pub fn finish(&mut self, succeeded: bool, duration_secs: f64, previous_success: Option<i64>) {
+        self.finished_at = chrono::Utc::now().timestamp();
+        self.succeeded = succeeded;
+        self.duration_secs = duration_secs;
+        self.last_success = if succeeded {
+            Some(self.finished_at)
+        } else {
+            previous_success
+        };
+    }
+}
+
+/// Quote a label value per the Prometheus text format
+fn label_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Append one gauge with its samples; a gauge without samples is left out
+fn write_gauge(out: &mut String, name: &str, help: &str, samples: Vec<(String, String)>) {
+    if samples.is_empty() {
+        return;
+    }
+    out.push_str(&format!(
+        "# HELP {} {}\n# TYPE {} gauge\n",
+        name, help, name
+    ));
+    for (labels, value) in samples {
+        out.push_str(&format!("{}{{{}}} {}\n", name, labels, value));
+    }
+}
+
+/// Render the run in the Prometheus text exposition format
+pub fn render(metrics: &RunMetrics) -> String {
+    let host = format!("host=\"{}\"", label_value(&metrics.hostname));
+    let mut out = String::new();
+    let mut gauge = |name: &str, help: &str, samples: Vec<(String, String)>| {
+        write_gauge(&mut out, name, help, samples)
+    };
+
+    gauge(
+        "restic_backup_last_run_timestamp_seconds",
+        "Unix time the last backup run finished.",
+        vec![(host.clone(), metrics.finished_at.to_string())],
+    );
+    gauge(
+        "restic_backup_last_run_success",
+        "1 if the last backup run ended without an error.",
+        vec![(host.clone(), u8::from(metrics.succeeded).to_string())],
+    );
+    gauge(
+        LAST_SUCCESS_METRIC,
+        "Unix time of the last backup run that ended without an error.",
+        metrics
+            .last_success
+            .map(|ts| (host.clone(), ts.to_string()))
+            .into_iter()
+            .collect(),
+    );
+    gauge(
+        "restic_backup_last_run_duration_seconds",
+        "Wall-clock duration of the last backup run.",
+        vec![(host.clone(), format!("{:.3}", metrics.duration_secs))],
+    );
+    gauge(
+        "restic_backup_paths",
+        "Paths of the last backup run by outcome.",
+        [
+            ("completed", metrics.completed),
+            ("degraded", metrics.degraded),
+            ("skipped", metrics.skipped),
+            ("failed", metrics.failed),
+        ]
+        .iter()
+        .map(|(status, count)| (format!("{},status=\"{}\"", host, status), count.to_string()))
+        .collect(),
+    );
+    gauge(
+        "restic_backup_data_added_bytes",
+        "Bytes added to the repositories by the last backup run (uncompressed).",
+        vec![(host.clone(), metrics.data_added_bytes.to_string())],
+    );
+    gauge(
+        "restic_backup_uploaded_bytes",
+        "Bytes stored in the repositories by the last backup run (compressed).",
+        vec![(host.clone(), metrics.uploaded_bytes.to_string())],
+    );
+
+    let path_labels = |p: &PathMetrics| format!("{},path=\"{}\"", host, label_value(&p.path));
+    gauge(
+        "restic_backup_path_success",
+        "1 if the path got a snapshot in the last backup run (degraded included).",
+        metrics
+            .paths
+            .iter()
+            .map(|p| {
+                let ok = matches!(p.status, "completed" | "degraded");
+                (path_labels(p), u8::from(ok).to_string())
+            })
+            .collect(),
+    );
+    gauge(
+        "restic_backup_path_data_added_bytes",
+        "Bytes the last backup run added for the path.",
+        metrics
+            .paths
+            .iter()
+            .map(|p| (path_labels(p), p.data_added_bytes.to_string()))
+            .collect(),
+    );
+    gauge(
+        "restic_backup_path_duration_seconds",
+        "Wall-clock duration of the path's backup in the last run.",
+        metrics
+            .paths
+            .iter()
+            .map(|p| (path_labels(p), format!("{:.3}", p.duration_secs)))
+            .collect(),
+    );
+    gauge(
+        "restic_backup_repository_snapshots",
+        "Snapshots in the path's repository after the last backup run.",
+        metrics
+            .paths
+            .iter()
+            .filter_map(|p| Some((path_labels(p), p.snapshot_count?.to_string())))
+            .collect(),
+    );
+    gauge(
+        "restic_backup_repository_budget_exceeded",
+        "1 per repository and REPO_MAX_* budget kind it exceeded after the last backup run.",
+        metrics
+            .budget_alerts
+            .iter()
+            .map(|a| {
+                let labels = format!(
+                    "{},path=\"{}\",kind=\"{}\"",
+                    host,
+                    label_value(&a.path.display().to_string()),
+                    a.kind.label()
+                );
+                (labels, "1".to_string())
+            })
+            .collect(),
+    );
+    out
+}
+
+/// Everything exported about one restore drill
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DrillMetrics {
+    pub hostname: String,
+    /// Unix time the drill finished
+    pub finished_at: i64,
+    pub succeeded: bool,
+    pub duration_secs: f64,
+    pub passed: usize,
+    pub failed: usize,
+    /// Unix time of the last drill in which every repository passed
+    pub last_success: Option<i64>,
+}
+
+/// Render a drill in the Prometheus text exposition format
+pub fn render_drill(metrics: &DrillMetrics) -> String {
+    let host = format!("host=\"{}\"", label_value(&metrics.hostname));
+    let mut out = String::new();
+    let mut gauge = |name: &str, help: &str, samples: Vec<(String, String)>| {
+        write_gauge(&mut out, name, help, samples)
+    };
+
+    gauge(
+        "restic_backup_drill_last_run_timestamp_seconds",
+        "Unix time the last restore drill finished.",
+        vec![(host.clone(), metrics.finished_at.to_string())],
+    );
+    gauge(
+        "restic_backup_drill_last_run_success",
+        "1 if every repository of the last restore drill restored and verified.",
+        vec![(host.clone(), u8::from(metrics.succeeded).to_string())],
+    );
+    gauge(
+        "restic_backup_drill_last_success_timestamp_seconds",
+        "Unix time of the last restore drill in which every repository passed.",
+        metrics
+            .last_success
+            .map(|ts| (host.clone(), ts.to_string()))
+            .into_iter()
+            .collect(),
+    );
+    gauge(
+        "restic_backup_drill_last_run_duration_seconds",
+        "Wall-clock duration of the last restore drill.",
+        vec![(host.clone(), format!("{:.3}", metrics.duration_secs))],
+    );
+    gauge(
+        "restic_backup_drill_repositories",
+        "Repositories of the last restore drill by outcome.",
+        [("passed", metrics.passed), ("failed", metrics.failed)]
+            .iter()
+            .map(|(status, count)| (format!("{},status=\"{}\"", host, status), count.to_string()))
+            .collect(),
+    );
+    out
+}
+
+/// Last-success timestamp from an earlier rendering (e.g. the previous textfile)
+pub fn parse_last_success(text: &str) -> Option<i64> {
+    text.lines()
+        .filter(|line| !line.starts_with('#'))
+        .find(|line| {
+            line.strip_prefix(LAST_SUCCESS_METRIC)
+                .is_some_and(|rest| rest.starts_with(['{', ' ']))
+        })
+        .and_then(|line| line.rsplit(' ').next()?.parse().ok())
+}
+
+/// When the previous run of this host succeeded: from memory in the daemon, else the textfile
+pub fn previous_success() -> Option<i64> {
+    let latest = LATEST.lock().unwrap_or_else(|e| e.into_inner());
+    latest.as_ref().and_then(|m| m.last_success).or_else(|| {
+        let path = env_value(METRICS_TEXTFILE_ENV_VAR)?;
+        parse_last_success(&std::fs::read_to_string(path).ok()?)
+    })
+}
+
+/// Write via a temporary file so node_exporter never reads a half-written file
+fn write_textfile(path: &Path, text: &str) -> Result<(), BackupServiceError> {
+    let mut tmp = PathBuf::from(path);
+    tmp.set_extension("prom.tmp");
+    std::fs::write(&tmp, text)?;
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+/// Pushgateway grouping URL for this host
+pub fn pushgateway_url(base: &str, hostname: &str) -> String {
+    grouping_url(base, "restic_backup", hostname)
+}
+
+fn grouping_url(base: &str, job: &str, hostname: &str) -> String {
+    format!(
+        "{}/metrics/job/{}/instance/{}",
+        base.trim_end_matches('/'),
+        job,
+        hostname
+    )
+}
+
+/// Drills get their own textfile next to METRICS_TEXTFILE (`<name>-drill.prom`), so a
+/// backup run rewriting its file never drops them
+pub fn drill_textfile(path: &Path) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    path.with_file_name(format!("{}-drill.prom", stem))
+}
+
+/// Export a finished run to every configured sink
+///
+/// Metrics never fail the backup: export errors are logged and the run result stands.
+pub async fn publish(metrics: RunMetrics) {
+    let text = render(&metrics);
+    if let Some(path) = env_value(METRICS_TEXTFILE_ENV_VAR) {
+        match write_textfile(Path::new(&path), &text) {
+            Ok(()) => info!(path = %path, "Wrote backup metrics"),
+            Err(e) => warn!(path = %path, error = %e, "Could not write backup metrics"),
+        }
+    }
+    if let Some(base) = env_value(METRICS_PUSHGATEWAY_ENV_VAR) {
+        let url = pushgateway_url(&base, &metrics.hostname);
+        match push(&url, &text).await {
+            Ok(()) => info!(url = %url, "Pushed backup metrics"),
+            Err(e) => warn!(url = %url, error = %e, "Could not push backup metrics"),
+        }
+    }
+    *LATEST.lock().unwrap_or_else(|e| e.into_inner()) = Some(metrics);
+}
+
+/// Export a finished drill to the textfile and Pushgateway sinks (never fails the drill)
+pub async fn publish_drill(metrics: &DrillMetrics) {
+    let text = render_drill(metrics);
+    if let Some(path) = env_value(METRICS_TEXTFILE_ENV_VAR) {
+        let path = drill_textfile(Path::new(&path));
+        match write_textfile(&path, &text) {
+            Ok(()) => info!(path = %path.display(), "Wrote drill metrics"),
+            Err(e) => warn!(path = %path.display(), error = %e, "Could not write drill metrics"),
+        }
+    }
+    if let Some(base) = env_value(METRICS_PUSHGATEWAY_ENV_VAR) {
+        let url = grouping_url(&base, "restic_backup_drill", &metrics.hostname);
+        match push(&url, &text).await {
+            Ok(()) => info!(url = %url, "Pushed drill metrics"),
+            Err(e) => warn!(url = %url, error = %e, "Could not push drill metrics"),
+        }
+    }
+}
+
+/// POST replaces only the pushed metric names, so a last-success timestamp that a
+/// failed run omits keeps its previous value on the gateway
+async fn push(url: &str, text: &str) -> Result<(), BackupServiceError> {
+    http::post(url, &[], text, "Pushing metrics to the Pushgateway").await
+}
+
+/// Listen address from `--metrics-listen`, falling back to METRICS_LISTEN
+pub fn resolve_listen(flag: Option<String>) -> Option<String> {
+    flag.filter(|v| !v.trim().is_empty())
+        .or_else(|| env_value(METRICS_LISTEN_ENV_VAR))
+}
+
+/// Serve the latest run on `GET /metrics` until the process exits
+///
+/// Before the first run finishes the response is empty, which Prometheus accepts.
+pub async fn serve(addr: &str) -> Result<(), BackupServiceError> {
+    let listener = TcpListener::bind(addr).await.map_err(|e| {
+        BackupServiceError::ConfigurationError(format!(
+            "Cannot listen for metrics on {}: {}\n\nPass a free address to --metrics-listen or {}, e.g. 0.0.0.0:9099",
+            addr, e, METRICS_LISTEN_ENV_VAR
+        ))
+    })?;
+    LISTENING.store(true, Ordering::SeqCst);
+    info!(address = %addr, "Serving backup metrics on /metrics");
+
+    tokio::spawn(async move {
+        loop {
+            let Ok((mut stream, _)) = listener.accept().await else {
+                continue;
+            };
+            tokio::spawn(async move {
+                let mut buf = [0u8; 1024];
+                let Ok(n) = stream.read(&mut buf).await else {
+                    return;
+                };
+                let request = String::from_utf8_lossy(&buf[..n]);
+                let response = match request.split_whitespace().nth(1) {
+                    Some("/metrics") => {
+                        let body = LATEST
+                            .lock()
+                            .unwrap_or_else(|e| e.into_inner())
+                            .as_ref()
+                            .map(render)
+                            .unwrap_or_default();
+                        format!(
+                            "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                            body.len(),
+                            body
+                        )
+                    }
+                    _ => "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                        .to_string(),
+                };
+                let _ = stream.write_all(response.as_bytes()).await;
+            });
+        }
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::budgets::BudgetKind;
+
+    fn sample() -> RunMetrics {
+        RunMetrics {
+            hostname: "nas".to_string(),
+            finished_at: 1_760_000_000,
+            succeeded: true,
+            duration_secs: 12.5,
+            completed: 1,
+            failed: 1,
+            data_added_bytes: 2048,
+            uploaded_bytes: 1024,
+            last_success: Some(1_760_000_000),
+            paths: vec![
+                PathMetrics {
+                    path: "/home/tim".to_string(),
+                    status: "completed",
+                    data_added_bytes: 2048,
+                    duration_secs: 10.0,
+                    snapshot_count: Some(42),
+                },
+                PathMetrics {
+                    path: "/srv/\"odd\"".to_string(),
+                    status: "failed",
+                    data_added_bytes: 0,
+                    duration_secs: 0.0,
+                    snapshot_count: None,
+                },
+            ],
+            budget_alerts: vec![BudgetAlert {
+                path: "/home/tim".into(),
+                repo_subpath: "user_home/tim".to_string(),
+                kind: BudgetKind::SnapshotCount,
+                actual: 120,
+                limit: 100,
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_render_exposition_format() {
+        let text = render(&sample());
+        assert!(text.contains("# TYPE restic_backup_last_run_success gauge\n"));
+        assert!(text.contains("restic_backup_last_run_success{host=\"nas\"} 1\n"));
+        assert!(text.contains("restic_backup_paths{host=\"nas\",status=\"failed\"} 1\n"));
+        assert!(
+            text.contains(
+                "restic_backup_repository_snapshots{host=\"nas\",path=\"/home/tim\"} 42\n"
+            )
+        );
+        assert!(text.contains("path=\"/srv/\\\"odd\\\"\"} 0\n"));
+        // Paths without a snapshot count are left out rather than reported as 0
+        assert_eq!(
+            text.matches("restic_backup_repository_snapshots{").count(),
+            1
+        );
+        assert!(text.contains(
+            "restic_backup_repository_budget_exceeded{host=\"nas\",path=\"/home/tim\",kind=\"snapshot_count\"} 1\n"
+        ));
+    }
+
+    #[test]
+    fn test_failed_run_carries_last_success() {
+        let previous = render(&sample());
+        assert_eq!(parse_last_success(&previous), Some(1_760_000_000));
+
+        let mut failed = RunMetrics::new("nas");
+        failed.finish(false, 1.0, parse_last_success(&previous));
+        assert!(!failed.succeeded);
+        assert_eq!(failed.last_success, Some(1_760_000_000));
+
+        // Without an earlier success the metric is omitted
+        failed.last_success = None;
+        assert!(!render(&failed).contains(LAST_SUCCESS_METRIC));
+        assert_eq!(parse_last_success(&render(&failed)), None);
+    }
+
+    #[test]
+    fn test_pushgateway_url() {
+        assert_eq!(
+            pushgateway_url("http://gw:9091/", "nas"),
+            "http://gw:9091/metrics/job/restic_backup/instance/nas"
+        );
+    }
+
+    #[test]
+    fn test_render_drill() {
+        let text = render_drill(&DrillMetrics {
+            hostname: "nas".to_string(),
+            finished_at: 1_760_000_000,
+            succeeded: false,
+            passed: 2,
+            failed: 1,
+            last_success: Some(1_759_000_000),
+            ..Default::default()
+        });
+        assert!(text.contains("restic_backup_drill_last_run_success{host=\"nas\"} 0\n"));
+        assert!(text.contains(
+            "restic_backup_drill_last_success_timestamp_seconds{host=\"nas\"} 1759000000\n"
+        ));
+        assert!(
+            text.contains("restic_backup_drill_repositories{host=\"nas\",status=\"failed\"} 1\n")
+        );
+        assert_eq!(
+            drill_textfile(Path::new("/var/lib/node_exporter/restic-backup.prom")),
+            PathBuf::from("/var/lib/node_exporter/restic-backup-drill.prom")
+        );
+    }
+}
@@ -0,0 +1,274 @@
+use crate::config::Config;
+use crate::errors::BackupServiceError;
+use crate::shared::commands::ResticCommandExecutor;
+use crate::shared::display::DisplayFormatter;
+use crate::shared::operations::RepositoryOperations;
+use crate::shared::repo_store::RepoStore;
+use crate::shared::scan_cache;
+use crate::shared::ui::confirm_destructive;
+use crate::utils::{format_bytes, validate_credentials};
+use serde_json::json;
+use tracing::{error, info};
+
+/// `migrate-host` options
+#[derive(Debug, Clone, Default)]
+pub struct MigrateHostOptions {
+    /// List what would be copied and stop
+    pub dry_run: bool,
+    /// Remove the old host's repositories once everything was copied
+    pub move_source: bool,
+    pub assume_yes: bool,
+    /// Old hostname repeated as a safeguard for --move --yes
+    pub confirm: Option<String>,
+    pub json_output: bool,
+}
+
+fn method(store: &RepoStore) -> &'static str {
+    if store.copies_server_side() {
+        "server-side"
+    } else {
+        "restic-copy"
+    }
+}
+
+/// The new name becomes a directory below the repository base
+fn validate_new_hostname(old: &str, new: &str) -> Result<(), BackupServiceError> {
+    if new.is_empty() || new.starts_with('.') || new.contains('/') {
+        return Err(BackupServiceError::ConfigurationError(format!(
+            "Invalid new hostname: '{}'.\n\nUse the plain hostname, without slashes or a leading dot",
+            new
+        )));
+    }
+    if old == new {
+        return Err(BackupServiceError::ConfigurationError(format!(
+            "Old and new hostname are both {}",
+            old
+        )));
+    }
+    Ok(())
+}
+
+/// Copy every snapshot of one repository into the same path below the new host
+async fn copy_repository(
+    config: &Config,
+    old: &str,
+    new: &str,
+    repo_subpath: &str,
+) -> Result<usize, BackupServiceError> {
+    let source_url = config.get_repo_url_for_host(old, repo_subpath)?;
+    let target_url = config.get_repo_url_for_host(new, repo_subpath)?;
+    let ids: Vec<String> = ResticCommandExecutor::new(config.clone(), source_url.clone())?
+        .snapshots()
+        .await?
+        .iter()
+        .filter_map(|s| s["id"].as_str().map(str::to_string))
+        .collect();
+    let target = ResticCommandExecutor::new(config.clone(), target_url)?
+        .with_from_password(&config.restic_password);
+    target.init_copy_target_if_needed(&source_url).await?;
+    if !ids.is_empty() {
+        target.copy_from(&source_url, &ids).await?;
+    }
+    Ok(ids.len())
+}
+
+/// Give the copied snapshots of one repository the new hostname
+///
+/// Backups and retention select snapshots by host: left under the old name, the copies
+/// would never be a parent snapshot nor be thinned by `forget`.
+async fn rewrite_repository(
+    config: &Config,
+    new: &str,
+    repo_subpath: &str,
+) -> Result<(), BackupServiceError> {
+    let repo_url = config.get_repo_url_for_host(new, repo_subpath)?;
+    ResticCommandExecutor::new(config.clone(), repo_url)?
+        .rewrite_host(new)
+        .await
+}
+
+/// Relocate every repository of a renamed host below its new hostname
+///
+/// S3 copies the objects server-side; other backends `restic copy` each repository.
+/// Every copied repository then gets `restic rewrite --new-host`, so the snapshots carry
+/// the new hostname like the backups that follow.
+pub async fn execute_migrate_host_workflow(
+    config: Config,
+    old: String,
+    new: String,
+    options: MigrateHostOptions,
+) -> Result<(), BackupServiceError> {
+    validate_new_hostname(&old, &new)?;
+    config.set_aws_env()?;
+    validate_credentials(&config).await?;
+
+    let operations = RepositoryOperations::new(config.clone())?;
+    let hosts = operations.get_available_hosts().await?;
+    if !hosts.contains(&old) {
+        return Err(BackupServiceError::ConfigurationError(format!(
+            "No repositories found for host {}.\n\nRun `restic-backup-service hosts` to see the hosts in the repository base",
+            old
+        )));
+    }
+    if hosts.contains(&new) {
+        return Err(BackupServiceError::ConfigurationError(format!(
+            "Host {} already has repositories.\n\nMigrating into an existing host would mix both histories; delete or rename it first",
+            new
+        )));
+    }
+    let discovery = operations.discover_all_repositories(&old).await?;
+    for failure in &discovery.failures {
+        error!(failure = %failure, "Repository discovery incomplete");
+    }
+    let repositories: Vec<String> = discovery
+        .repos
+        .into_iter()
+        .map(|r| r.repo_subpath)
+        .collect();
+    let from_path = config.host_store_path(&old)?;
+    let to_path = config.host_store_path(&new)?;
+    let store = RepoStore::new(config.clone())?;
+
+    if options.dry_run {
+        let usage = store.usage(&from_path).await?;
+        if options.json_output {
+            let output = json!({
+                "host": old,
+                "new_host": new,
+                "dry_run": true,
+                "method": method(&store),
+                "move": options.move_source,
+                "from_path": from_path,
+                "to_path": to_path,
+                "repositories": repositories,
+                "objects": usage.map(|(objects, _)| objects),
+                "bytes": usage.map(|(_, bytes)| bytes),
+                "discovery_errors": discovery.failures,
+            });
+            DisplayFormatter::print_json(&output)?;
+        } else {
+            for repo_subpath in &repositories {
+                info!(repo_subpath = %repo_subpath, method = %method(&store), "Would copy repository");
+            }
+            info!(
+                host = %old,
+                new_host = %new,
+                to_path = %to_path,
+                repositories = %repositories.len(),
+                objects = %usage.map(|(objects, _)| objects.to_string()).unwrap_or_default(),
+                size = %usage.map(|(_, bytes)| format_bytes(bytes)).transpose()?.unwrap_or_default(),
+                "Dry run: nothing was copied"
+            );
+        }
+        return Ok(());
+    }
+
+    if options.move_source {
+        confirm_destructive("move", &old, options.assume_yes, options.confirm.as_deref())?;
+    }
+
+    // An incomplete discovery may leave repositories behind, so the source must stay
+    let mut failed: Vec<String> = discovery.failures.iter().map(|f| f.scope.clone()).collect();
+    let mut copied = json!({});
+    if store.copies_server_side() {
+        let (objects, bytes) = store.copy_tree(&from_path, &to_path).await?;
+        // Both trees must match the copy: a backup writing to the old host meanwhile would
+        // otherwise be lost by --move
+        let expected = Some((objects, bytes));
+        if store.usage(&from_path).await? != expected || store.usage(&to_path).await? != expected {
+            failed.push(to_path.clone());
+        }
+        info!(host = %old, new_host = %new, objects = %objects, size = %format_bytes(bytes)?, "Copied repositories server-side");
+        copied = json!({ "objects": objects, "bytes": bytes });
+    } else {
+        for (idx, repo_subpath) in repositories.iter().enumerate() {
+            match copy_repository(&config, &old, &new, repo_subpath).await {
+                Ok(snapshots) => {
+                    info!(
+                        progress = format!("({}/{})", idx + 1, repositories.len()),
+                        repo_subpath = %repo_subpath,
+                        snapshots = %snapshots,
+                        "Copied repository"
+                    );
+                    copied[repo_subpath.as_str()] = json!(snapshots);
+                }
+                Err(e) => {
+                    error!(repo_subpath = %repo_subpath, error = %e, "Copying repository failed");
+                    failed.push(repo_subpath.clone());
+                }
+            }
+        }
+    }
+
+    // After the S3 comparison: the rewrite adds and removes snapshot files in the new tree
+    let copied_repositories: Vec<&String> = repositories
+        .iter()
+        .filter(|r| !failed.contains(r))
+        .collect();
+    for repo_subpath in copied_repositories {
+        match rewrite_repository(&config, &new, repo_subpath).await {
+            Ok(()) => {
+                info!(repo_subpath = %repo_subpath, new_host = %new, "Moved snapshots to the new hostname")
+            }
+            Err(e) => {
+                error!(repo_subpath = %repo_subpath, error = %e, "Rewriting the hostname failed");
+                failed.push(repo_subpath.clone());
+            }
+        }
+    }
+
+    let remove_source = options.move_source && failed.is_empty();
+    if remove_source {
+        store.delete_tree(&from_path).await?;
+        info!(host = %old, store_path = %from_path, "Removed the old host's repositories");
+    }
+    scan_cache::invalidate(&config, &old);
+    scan_cache::invalidate(&config, &new);
+
+    if options.json_output {
+        let output = json!({
+            "host": old,
+            "new_host": new,
+            "dry_run": false,
+            "method": method(&store),
+            "from_path": from_path,
+            "to_path": to_path,
+            "repositories": repositories,
+            "copied": copied,
+            "source_removed": remove_source,
+            "failed": failed,
+        });
+        DisplayFormatter::print_json(&output)?;
+    }
+    if failed.is_empty() {
+        Ok(())
+    } else {
+        Err(BackupServiceError::CommandFailed(format!(
+            "Migrating host {} to {} is incomplete{}: {}",
+            old,
+            new,
+            if options.move_source {
+                ", the old repositories were kept"
+            } else {
+                ""
+            },
+            failed.join(", ")
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_new_hostname() {
+        assert!(validate_new_hostname("web1", "web2").is_ok());
+        for new in ["", "web1", ".hidden", "legacy/web2"] {
+            assert!(matches!(
+                validate_new_hostname("web1", new),
+                Err(BackupServiceError::ConfigurationError(_))
+            ));
+        }
+    }
+}
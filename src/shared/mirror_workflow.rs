@@ -0,0 +1,334 @@
+use crate::config::{Config, RepoBackend};
+use crate::errors::BackupServiceError;
+use crate::shared::commands::ResticCommandExecutor;
+use crate::shared::display::DisplayFormatter;
+use crate::shared::logs_workflow::log_dir;
+use crate::shared::operations::{DiscoveryFailure, RepositoryOperations};
+use crate::utils::validate_credentials;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use tracing::{error, info, warn};
+
+/// Repository base of the secondary copy; same layout as RESTIC_REPO_BASE below it
+pub const MIRROR_REPO_BASE_ENV_VAR: &str = "MIRROR_REPO_BASE";
+/// Password of the mirror repositories (default: the primary password)
+pub const MIRROR_PASSWORD_ENV_VAR: &str = "MIRROR_PASSWORD";
+/// `true` makes `run` mirror the host's repositories after a successful backup
+pub const MIRROR_AFTER_BACKUP_ENV_VAR: &str = "MIRROR_AFTER_BACKUP";
+/// Record of copied snapshots (default: `<RBS_LOG_DIR>/mirror-state.json`)
+pub const MIRROR_STATE_ENV_VAR: &str = "MIRROR_STATE_FILE";
+
+pub fn mirror_state_file() -> PathBuf {
+    std::env::var(MIRROR_STATE_ENV_VAR)
+        .ok()
+        .filter(|v| !v.trim().is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| log_dir().join("mirror-state.json"))
+}
+
+pub fn mirror_after_backup() -> bool {
+    std::env::var(MIRROR_AFTER_BACKUP_ENV_VAR)
+        .is_ok_and(|v| matches!(v.trim(), "1" | "true" | "yes"))
+}
+
+/// The primary configuration pointed at the mirror: its base and password, same credentials
+///
+/// restic reads one set of AWS_* variables for both repositories of a copy, so an S3 mirror
+/// must accept the primary's keys; other providers are reached over SFTP or a local mount.
+pub fn mirror_config(config: &Config) -> Result<Config, BackupServiceError> {
+    let base = std::env::var(MIRROR_REPO_BASE_ENV_VAR)
+        .ok()
+        .map(|v| v.trim().trim_end_matches('/').to_string())
+        .filter(|v| !v.is_empty())
+        .ok_or_else(|| {
+            BackupServiceError::ConfigurationError(format!(
+                "{} is not set.\n\nSet it to the secondary repository base, e.g. {}=sftp:backup@offsite:/srv/restic",
+                MIRROR_REPO_BASE_ENV_VAR, MIRROR_REPO_BASE_ENV_VAR
+            ))
+        })?;
+    RepoBackend::parse(&base)?;
+    if base == config.restic_repo_base.trim().trim_end_matches('/') {
+        return Err(BackupServiceError::ConfigurationError(format!(
+            "{} is the primary repository base.\n\nPoint it at a different bucket, server or directory",
+            MIRROR_REPO_BASE_ENV_VAR
+        )));
+    }
+    let password = std::env::var(MIRROR_PASSWORD_ENV_VAR)
+        .ok()
+        .filter(|v| !v.trim().is_empty())
+        .unwrap_or_else(|| config.restic_password.clone());
+    Ok(Config {
+        restic_repo_base: base,
+        restic_password: password,
+        ..config.clone()
+    })
+}
+
+/// Snapshots already copied, per mirror repository URL, so reruns only copy new ones
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MirrorState {
+    pub copied: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl MirrorState {
+    pub fn load(file: &Path) -> Result<Self, BackupServiceError> {
+        if !file.exists() {
+            return Ok(Self::default());
+        }
+        Ok(serde_json::from_str(&std::fs::read_to_string(file)?)?)
+    }
+
+    /// Write via a temporary file so an interrupted run never leaves a truncated record
+    pub fn save(&self, file: &Path) -> Result<(), BackupServiceError> {
+        if let Some(dir) = file.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let tmp = file.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&tmp, file)?;
+        Ok(())
+    }
+
+    /// Snapshot IDs of `snapshots` not yet copied to `mirror_url`
+    pub fn pending(&self, mirror_url: &str, snapshots: &[Value]) -> Vec<String> {
+        let copied = self.copied.get(mirror_url);
+        snapshots
+            .iter()
+            .filter_map(|s| s["id"].as_str())
+            .filter(|id| !copied.is_some_and(|c| c.contains(*id)))
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// Record a finished copy; snapshots the primary no longer has are dropped from the record
+    pub fn record(&mut self, mirror_url: &str, snapshots: &[Value], copied: &[String]) {
+        let present: BTreeSet<&str> = snapshots.iter().filter_map(|s| s["id"].as_str()).collect();
+        let entry = self.copied.entry(mirror_url.to_string()).or_default();
+        entry.retain(|id| present.contains(id.as_str()));
+        entry.extend(copied.iter().cloned());
+    }
+}
+
+/// `mirror` options
+#[derive(Debug, Clone, Default)]
+pub struct MirrorOptions {
+    /// List what would be copied without touching the mirror
+    pub dry_run: bool,
+    pub json_output: bool,
+}
+
+/// Outcome of mirroring one repository
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MirrorResult {
+    pub repo_subpath: String,
+    /// Snapshots copied in this run (or that would be, with --dry-run)
+    pub copied: usize,
+    pub already_mirrored: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Every repository's result plus the parts of the repository tree that could not be listed
+#[derive(Debug, Clone, Default)]
+pub struct MirrorRun {
+    pub results: Vec<MirrorResult>,
+    pub discovery_failures: Vec<DiscoveryFailure>,
+}
+
+impl MirrorRun {
+    /// An error when any repository failed or was possibly missed
+    pub fn check(&self) -> Result<(), BackupServiceError> {
+        let failed = self.results.iter().filter(|r| r.error.is_some()).count();
+        if failed > 0 || !self.discovery_failures.is_empty() {
+            return Err(BackupServiceError::CommandFailed(format!(
+                "Mirroring failed for {} of {} repositories ({} discovery failures)",
+                failed,
+                self.results.len(),
+                self.discovery_failures.len()
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Copy one repository's new snapshots to its mirror
+async fn mirror_repository(
+    config: &Config,
+    mirror: &Config,
+    state: &mut MirrorState,
+    hostname: &str,
+    repo_subpath: &str,
+    dry_run: bool,
+) -> Result<MirrorResult, BackupServiceError> {
+    let source_url = config.get_repo_url_for_host(hostname, repo_subpath)?;
+    let mirror_url = mirror.get_repo_url_for_host(hostname, repo_subpath)?;
+    let source = ResticCommandExecutor::new(config.clone(), source_url.clone())?;
+    let snapshots = source.snapshots().await?;
+    let pending = state.pending(&mirror_url, &snapshots);
+    let result = MirrorResult {
+        repo_subpath: repo_subpath.to_string(),
+        copied: pending.len(),
+        already_mirrored: snapshots.len() - pending.len(),
+        error: None,
+    };
+    if dry_run || pending.is_empty() {
+        return Ok(result);
+    }
+
+    let target = ResticCommandExecutor::new(mirror.clone(), mirror_url.clone())?
+        .with_from_password(&config.restic_password);
+    target.init_copy_target_if_needed(&source_url).await?;
+    target.copy_from(&source_url, &pending).await?;
+    state.record(&mirror_url, &snapshots, &pending);
+    Ok(result)
+}
+
+/// Replicate every repository of a host to the mirror with `restic copy`
+///
+/// A failing repository does not stop the others; see [`MirrorRun::check`]. The state file
+/// is saved after each repository.
+pub async fn mirror_host(
+    config: &Config,
+    hostname: &str,
+    dry_run: bool,
+) -> Result<MirrorRun, BackupServiceError> {
+    let mirror = mirror_config(config)?;
+    let state_file = mirror_state_file();
+    let mut state = MirrorState::load(&state_file)?;
+
+    let operations = RepositoryOperations::new(config.clone())?;
+    let discovery = operations.discover_all_repositories(hostname).await?;
+    for failure in &discovery.failures {
+        error!(failure = %failure, "Repository discovery incomplete");
+    }
+    info!(
+        hostname = %hostname,
+        repo_count = %discovery.repos.len(),
+        mirror = %mirror.restic_repo_base,
+        "Mirroring repositories"
+    );
+
+    let mut results = Vec::with_capacity(discovery.repos.len());
+    for (idx, repo) in discovery.repos.iter().enumerate() {
+        info!(
+            progress = format!("({}/{})", idx + 1, discovery.repos.len()),
+            repo_subpath = %repo.repo_subpath,
+            "Mirroring repository"
+        );
+        let outcome = mirror_repository(
+            config,
+            &mirror,
+            &mut state,
+            hostname,
+            &repo.repo_subpath,
+            dry_run,
+        )
+        .await;
+        match outcome {
+            Ok(result) => {
+                if !dry_run {
+                    state.save(&state_file)?;
+                }
+                results.push(result);
+            }
+            Err(e) => {
+                error!(repo_subpath = %repo.repo_subpath, error = %e, "Mirroring failed");
+                results.push(MirrorResult {
+                    repo_subpath: repo.repo_subpath.clone(),
+                    copied: 0,
+                    already_mirrored: 0,
+                    error: Some(e.to_string()),
+                });
+            }
+        }
+    }
+
+    Ok(MirrorRun {
+        results,
+        discovery_failures: discovery.failures,
+    })
+}
+
+/// `mirror`: copy a host's new snapshots to MIRROR_REPO_BASE and report per repository
+pub async fn execute_mirror_workflow(
+    config: Config,
+    host: Option<String>,
+    options: MirrorOptions,
+) -> Result<(), BackupServiceError> {
+    let hostname = host.unwrap_or_else(|| config.hostname.clone());
+    config.set_aws_env()?;
+    validate_credentials(&config).await?;
+
+    let run = mirror_host(&config, &hostname, options.dry_run).await?;
+    let copied: usize = run.results.iter().map(|r| r.copied).sum();
+    if options.json_output {
+        let output = json!({
+            "host": hostname,
+            "dry_run": options.dry_run,
+            "copied": copied,
+            "repositories": run.results,
+            "discovery_failures": run.discovery_failures,
+        });
+        DisplayFormatter::print_json(&output)?;
+    } else {
+        info!("Mirror summary:");
+        for result in &run.results {
+            if let Some(e) = &result.error {
+                info!("  FAILED  {}: {}", result.repo_subpath, e);
+                continue;
+            }
+            info!(
+                "  {:>4} {}  {} ({} already mirrored)",
+                result.copied,
+                if options.dry_run { "to copy" } else { "copied" },
+                result.repo_subpath,
+                result.already_mirrored
+            );
+        }
+        if copied == 0 && run.check().is_ok() {
+            info!("Mirror is up to date");
+        }
+    }
+    run.check()
+}
+
+/// `run` post-backup step; a failed mirror is logged but does not fail the backup
+pub async fn mirror_after_run(config: &Config) {
+    let outcome = mirror_host(config, &config.hostname, false).await;
+    if let Err(e) = outcome.and_then(|run| run.check()) {
+        warn!(error = %e, "Post-backup mirror incomplete");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mirror_state() -> Result<(), BackupServiceError> {
+        let url = "sftp:backup@offsite:/srv/restic/web1/system/etc";
+        let snapshots: Vec<Value> = vec![json!({"id": "aaa"}), json!({"id": "bbb"})];
+        let mut state = MirrorState::default();
+        assert_eq!(state.pending(url, &snapshots), vec!["aaa", "bbb"]);
+
+        state.record(url, &snapshots, &["aaa".to_string(), "bbb".to_string()]);
+        let snapshots: Vec<Value> = vec![json!({"id": "bbb"}), json!({"id": "ccc"})];
+        assert_eq!(state.pending(url, &snapshots), vec!["ccc"]);
+        assert_eq!(state.pending("other", &snapshots).len(), 2);
+
+        // Forgotten snapshots leave the record
+        state.record(url, &snapshots, &["ccc".to_string()]);
+        assert_eq!(
+            state.copied[url].iter().collect::<Vec<_>>(),
+            vec!["bbb", "ccc"]
+        );
+
+        let dir = tempfile::tempdir()?;
+        let file = dir.path().join("state/mirror-state.json");
+        state.save(&file)?;
+        assert_eq!(MirrorState::load(&file)?, state);
+        Ok(())
+    }
+}
@@ -1,8 +1,71 @@
+pub mod backend_tuning;
+pub mod backup_progress;
+pub mod backup_summary;
 pub mod backup_workflow;
+pub mod budgets;
+pub mod check_workflow;
 pub mod commands;
+pub mod compression;
+pub mod config_file;
 pub mod constants;
+pub mod container_quiesce;
+pub mod container_verify;
+pub mod content_policy;
+pub mod coverage_workflow;
+pub mod cron;
+pub mod daemon_workflow;
+pub mod delete_host_workflow;
+pub mod dependencies;
+pub mod digest_workflow;
+pub mod disk_space;
 pub mod display;
+pub mod doctor_workflow;
+pub mod drill_workflow;
+pub mod email_report;
+pub mod error_policy;
+pub mod excludes;
+pub mod faults;
+pub mod find_workflow;
+pub mod fleet_workflow;
+pub mod healthcheck;
+pub mod history_workflow;
+pub mod http;
+pub mod instance_lock;
+pub mod key_workflow;
+pub mod logs_workflow;
+pub mod ls_workflow;
+pub mod metrics;
+pub mod migrate_host_workflow;
+pub mod mirror_workflow;
+pub mod network_mounts;
+pub mod notify;
 pub mod operations;
+pub mod path_tags;
 pub mod paths;
+pub mod permissions_workflow;
+pub mod preflight;
+pub mod prune_workflow;
+pub mod repo_store;
+pub mod resource_limits;
+pub mod resource_usage;
+pub mod restore_transcript;
 pub mod restore_workflow;
+pub mod retention;
+pub mod retention_rules;
+pub mod retry;
+pub mod s3;
+pub mod scan_cache;
+pub mod secret_source;
+pub mod secrets;
+pub mod seed;
+pub mod self_update_workflow;
+pub mod sensitive_paths;
+pub mod shutdown;
+pub mod snapshot_filter;
+pub mod snapshots_workflow;
+pub mod stats_workflow;
+pub mod status_workflow;
+pub mod timestamps;
+pub mod tui_workflow;
 pub mod ui;
+pub mod unlock_workflow;
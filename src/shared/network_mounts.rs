@@ -0,0 +1,306 @@
+use crate::errors::BackupServiceError;
+use crate::shared::commands::with_timeout;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::process::Command;
+use tracing::{error, info, warn};
+
+/// Env var declaring network mounts backup paths live on: `/mnt/nas=mnt-nas.mount,/mnt/share`
+pub const NETWORK_MOUNTS_ENV_VAR: &str = "BACKUP_NETWORK_MOUNTS";
+
+/// Filesystem types that count as a mounted network share
+const NETWORK_FS_TYPES: &[&str] = &[
+    "nfs",
+    "nfs4",
+    "cifs",
+    "smb3",
+    "smbfs",
+    "fuse.sshfs",
+    "glusterfs",
+    "ceph",
+];
+
+/// How long `systemctl start` may wait for a share to mount (systemd's default job timeout)
+const UNIT_START_TIMEOUT_SECS: u64 = 90;
+
+/// A mountpoint that must be mounted before paths at or below it are backed up
+#[derive(Debug, Clone, PartialEq)]
+pub struct NetworkMount {
+    pub mountpoint: PathBuf,
+    /// systemd unit started when the share is not mounted and stopped after the run
+    pub unit: Option<String>,
+}
+
+pub fn parse_network_mounts(value: &str) -> Result<Vec<NetworkMount>, BackupServiceError> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|e| !e.is_empty())
+        .map(|entry| {
+            let (mountpoint, unit) = match entry.split_once('=') {
+                Some((path, unit)) => (path.trim(), Some(unit.trim().to_string())),
+                None => (entry, None),
+            };
+            if !mountpoint.starts_with('/') || unit.as_ref().is_some_and(|u| u.is_empty()) {
+                return Err(BackupServiceError::ConfigurationError(format!(
+                    "Invalid {} entry: {}.\n\nUse an absolute mountpoint, optionally with the systemd unit \
+                    that mounts it, e.g. {}=/mnt/nas=mnt-nas.mount,/mnt/share",
+                    NETWORK_MOUNTS_ENV_VAR, entry, NETWORK_MOUNTS_ENV_VAR
+                )));
+            }
+            Ok(NetworkMount {
+                mountpoint: PathBuf::from(mountpoint),
+                unit,
+            })
+        })
+        .collect()
+}
+
+/// One line of /proc/mounts
+#[derive(Debug, Clone, PartialEq)]
+pub struct MountEntry {
+    pub source: String,
+    pub mountpoint: PathBuf,
+    pub fstype: String,
+}
+
+/// Undo the kernel's octal escapes (`\040` for a space) in /proc/mounts fields
+fn unescape_mount_field(field: &str) -> String {
+    let bytes = field.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'\\'
+            && let Some(code) = field
+                .get(i + 1..i + 4)
+                .and_then(|oct| u8::from_str_radix(oct, 8).ok())
+        {
+            out.push(code);
+            i += 4;
+            continue;
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).to_string()
+}
+
+pub fn parse_proc_mounts(content: &str) -> Vec<MountEntry> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            Some(MountEntry {
+                source: unescape_mount_field(fields.next()?),
+                mountpoint: PathBuf::from(unescape_mount_field(fields.next()?)),
+                fstype: fields.next()?.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// What is mounted at `mountpoint`: the last entry wins, as later mounts stack on top
+pub fn mounted_at<'a>(entries: &'a [MountEntry], mountpoint: &Path) -> Option<&'a MountEntry> {
+    entries.iter().rev().find(|e| e.mountpoint == mountpoint)
+}
+
+fn read_mounts() -> Vec<MountEntry> {
+    std::fs::read_to_string("/proc/mounts")
+        .map(|content| parse_proc_mounts(&content))
+        .unwrap_or_default()
+}
+
+/// The share's mount entry, triggering a systemd automount (autofs placeholder) on the way
+fn current_mount(mountpoint: &Path) -> Option<MountEntry> {
+    let entry = mounted_at(&read_mounts(), mountpoint).cloned()?;
+    if entry.fstype != "autofs" {
+        return Some(entry);
+    }
+    // Listing the directory makes the kernel ask systemd to mount the share
+    let _ = std::fs::read_dir(mountpoint);
+    mounted_at(&read_mounts(), mountpoint)
+        .filter(|e| e.fstype != "autofs")
+        .cloned()
+}
+
+async fn start_unit(unit: &str) -> Result<(), BackupServiceError> {
+    let child = Command::new("systemctl")
+        .args(["start", unit])
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|_| {
+            BackupServiceError::CommandNotFound("Failed to execute systemctl".to_string())
+        })?;
+    let output = with_timeout(
+        child.wait_with_output(),
+        Some(Duration::from_secs(UNIT_START_TIMEOUT_SECS)),
+        &format!("systemctl start {}", unit),
+    )
+    .await??;
+    if !output.status.success() {
+        return Err(BackupServiceError::CommandFailed(format!(
+            "systemctl start {} failed: {}",
+            unit,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(())
+}
+
+/// Declared mounts checked (and mounted) for one backup run
+///
+/// Units this run started are stopped again when the session is dropped, so an
+/// aborted run does not leave the share mounted either.
+#[derive(Debug, Default)]
+pub struct MountSession {
+    /// Mountpoint -> why it is not available
+    unavailable: BTreeMap<PathBuf, String>,
+    started_units: Vec<String>,
+}
+
+impl MountSession {
+    /// Verify every mount from BACKUP_NETWORK_MOUNTS, starting its unit if needed
+    pub async fn prepare_from_env() -> Result<Self, BackupServiceError> {
+        let mounts =
+            parse_network_mounts(&std::env::var(NETWORK_MOUNTS_ENV_VAR).unwrap_or_default())?;
+        Ok(Self::prepare(&mounts).await)
+    }
+
+    pub async fn prepare(mounts: &[NetworkMount]) -> Self {
+        let mut session = Self::default();
+        for mount in mounts {
+            let mut entry = current_mount(&mount.mountpoint);
+            if entry.is_none()
+                && let Some(unit) = &mount.unit
+            {
+                info!(mountpoint = %mount.mountpoint.display(), unit = %unit, "Network mount not mounted, starting unit");
+                match start_unit(unit).await {
+                    Ok(()) => {
+                        session.started_units.push(unit.clone());
+                        entry = current_mount(&mount.mountpoint);
+                    }
+                    Err(e) => warn!(unit = %unit, error = %e, "Could not start mount unit"),
+                }
+            }
+
+            match entry {
+                Some(entry) => {
+                    if !NETWORK_FS_TYPES.contains(&entry.fstype.as_str()) {
+                        warn!(mountpoint = %mount.mountpoint.display(), fstype = %entry.fstype, "Declared network mount has a local filesystem type");
+                    }
+                    info!(mountpoint = %mount.mountpoint.display(), source = %entry.source, fstype = %entry.fstype, "Network mount available");
+                }
+                None => {
+                    let reason = format!(
+                        "network mount {} is not mounted{}",
+                        mount.mountpoint.display(),
+                        match &mount.unit {
+                            Some(unit) => format!(" (after starting {})", unit),
+                            None => String::new(),
+                        }
+                    );
+                    error!(mountpoint = %mount.mountpoint.display(), "Network mount is not mounted, paths on it will not be backed up");
+                    session.unavailable.insert(mount.mountpoint.clone(), reason);
+                }
+            }
+        }
+        session
+    }
+
+    /// Backup paths on an unavailable mount, with the reason they are refused
+    pub fn refusals(&self, paths: &[PathBuf]) -> BTreeMap<String, String> {
+        paths
+            .iter()
+            .filter_map(|path| {
+                self.unavailable
+                    .iter()
+                    .find(|(mountpoint, _)| path.starts_with(mountpoint))
+                    .map(|(_, reason)| (path.display().to_string(), reason.clone()))
+            })
+            .collect()
+    }
+}
+
+impl Drop for MountSession {
+    /// Drop cannot wait, so the stop is only queued (`--no-block`); tokio reaps the child
+    fn drop(&mut self) {
+        for unit in self.started_units.iter().rev() {
+            match Command::new("systemctl")
+                .args(["stop", "--no-block", unit])
+                .spawn()
+            {
+                Ok(_) => info!(unit = %unit, "Unmounting network mount started for this run"),
+                Err(e) => warn!(unit = %unit, error = %e, "Could not stop mount unit"),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_network_mounts() -> Result<(), BackupServiceError> {
+        let mounts = parse_network_mounts("/mnt/nas/=mnt-nas.mount, /mnt/share")?;
+        assert_eq!(
+            mounts,
+            vec![
+                NetworkMount {
+                    mountpoint: PathBuf::from("/mnt/nas"),
+                    unit: Some("mnt-nas.mount".to_string()),
+                },
+                NetworkMount {
+                    mountpoint: PathBuf::from("/mnt/share"),
+                    unit: None,
+                },
+            ]
+        );
+        assert!(parse_network_mounts("mnt/nas").is_err());
+        assert!(parse_network_mounts("/mnt/nas=").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_proc_mounts_stacking_and_escapes() {
+        let content = "\
+/dev/sda1 / ext4 rw,relatime 0 0
+systemd-1 /mnt/nas autofs rw,relatime 0 0
+nas:/export /mnt/nas nfs4 rw,relatime 0 0
+//srv/share /mnt/my\\040share cifs rw 0 0
+";
+        let entries = parse_proc_mounts(content);
+        assert_eq!(
+            mounted_at(&entries, Path::new("/mnt/nas")).map(|e| e.fstype.as_str()),
+            Some("nfs4")
+        );
+        assert_eq!(
+            mounted_at(&entries, Path::new("/mnt/my share")).map(|e| e.source.as_str()),
+            Some("//srv/share")
+        );
+        assert!(mounted_at(&entries, Path::new("/mnt/other")).is_none());
+    }
+
+    #[test]
+    fn test_refusals() {
+        let session = MountSession {
+            unavailable: BTreeMap::from([(
+                PathBuf::from("/mnt/nas"),
+                "network mount /mnt/nas is not mounted".to_string(),
+            )]),
+            started_units: Vec::new(),
+        };
+        let refused = session.refusals(&[
+            PathBuf::from("/mnt/nas/photos"),
+            PathBuf::from("/mnt/nas"),
+            PathBuf::from("/mnt/nas2"),
+            PathBuf::from("/home/tim"),
+        ]);
+        assert_eq!(refused.len(), 2);
+        assert!(refused.contains_key("/mnt/nas/photos"));
+        assert!(!refused.contains_key("/home/tim"));
+    }
+}
@@ -0,0 +1,321 @@
+use crate::errors::BackupServiceError;
+use crate::shared::http;
+use serde::Serialize;
+use serde_json::{Value, json};
+use std::sync::Arc;
+use tracing::{info, warn};
+
+/// Generic webhook receiving every notification as a JSON document
+pub const NOTIFY_WEBHOOK_URL_ENV_VAR: &str = "NOTIFY_WEBHOOK_URL";
+/// ntfy topic URL, e.g. https://ntfy.sh/my-backups
+pub const NOTIFY_NTFY_URL_ENV_VAR: &str = "NOTIFY_NTFY_URL";
+/// Access token for a protected ntfy topic
+pub const NOTIFY_NTFY_TOKEN_ENV_VAR: &str = "NOTIFY_NTFY_TOKEN";
+/// Slack or Discord incoming webhook
+pub const NOTIFY_CHAT_WEBHOOK_URL_ENV_VAR: &str = "NOTIFY_CHAT_WEBHOOK_URL";
+/// `always` or `failure` (default): which outcomes are sent
+pub const NOTIFY_ON_ENV_VAR: &str = "NOTIFY_ON";
+
+/// Discord rejects messages longer than this
+const MAX_CHAT_CHARS: usize = 2000;
+
+/// How a backup run or restore session ended
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Success,
+    /// Finished, but some paths or repositories did not
+    Warning,
+    Failure,
+}
+
+impl Severity {
+    fn ntfy_priority(self) -> &'static str {
+        match self {
+            Severity::Success => "default",
+            Severity::Warning => "high",
+            Severity::Failure => "urgent",
+        }
+    }
+
+    fn ntfy_tag(self) -> &'static str {
+        match self {
+            Severity::Success => "white_check_mark",
+            Severity::Warning => "warning",
+            Severity::Failure => "rotating_light",
+        }
+    }
+}
+
+/// Which outcomes are worth a notification
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum NotifyOn {
+    Always,
+    /// Anything but a clean success
+    #[default]
+    Failure,
+}
+
+impl NotifyOn {
+    pub fn parse(value: &str) -> Result<Self, BackupServiceError> {
+        match value.trim().to_lowercase().as_str() {
+            "" | "failure" | "failures" => Ok(NotifyOn::Failure),
+            "always" => Ok(NotifyOn::Always),
+            other => Err(BackupServiceError::ConfigurationError(format!(
+                "Invalid {}: {}.\n\nUse always or failure",
+                NOTIFY_ON_ENV_VAR, other
+            ))),
+        }
+    }
+
+    pub fn wants(self, severity: Severity) -> bool {
+        self == NotifyOn::Always || severity != Severity::Success
+    }
+}
+
+/// What happened, in a form every channel can render
+#[derive(Debug, Clone, Serialize)]
+pub struct Notification {
+    /// `backup` or `restore`
+    pub kind: &'static str,
+    pub host: String,
+    pub severity: Severity,
+    pub title: String,
+    /// Plain text, one line per problem
+    pub message: String,
+    /// Machine-readable details for the generic webhook
+    pub details: Value,
+}
+
+/// The HTTP POST a channel delivers a notification with
+#[derive(Debug, Clone, PartialEq)]
+pub struct Request {
+    pub url: String,
+    pub headers: Vec<String>,
+    pub body: String,
+}
+
+/// A channel notifications are delivered to
+pub trait Notifier {
+    /// Channel name for logs
+    fn name(&self) -> &'static str;
+    fn request(&self, notification: &Notification) -> Result<Request, BackupServiceError>;
+}
+
+/// POSTs the notification as JSON, for custom receivers
+pub struct WebhookNotifier {
+    pub url: String,
+}
+
+impl Notifier for WebhookNotifier {
+    fn name(&self) -> &'static str {
+        "webhook"
+    }
+
+    fn request(&self, notification: &Notification) -> Result<Request, BackupServiceError> {
+        Ok(Request {
+            url: self.url.clone(),
+            headers: vec![json_header()],
+            body: serde_json::to_string(notification)?,
+        })
+    }
+}
+
+/// Publishes to an ntfy topic, with the severity as priority and tag
+pub struct NtfyNotifier {
+    pub url: String,
+    pub token: Option<String>,
+}
+
+impl Notifier for NtfyNotifier {
+    fn name(&self) -> &'static str {
+        "ntfy"
+    }
+
+    fn request(&self, notification: &Notification) -> Result<Request, BackupServiceError> {
+        let mut headers = vec![
+            format!("Title: {}", notification.title),
+            format!("Priority: {}", notification.severity.ntfy_priority()),
+            format!("Tags: {}", notification.severity.ntfy_tag()),
+        ];
+        if let Some(token) = &self.token {
+            headers.push(format!("Authorization: Bearer {}", token));
+        }
+        Ok(Request {
+            url: self.url.clone(),
+            headers,
+            body: notification.message.clone(),
+        })
+    }
+}
+
+/// Incoming webhook of Slack (`text`) or Discord (`content`); each ignores the other's field
+pub struct ChatNotifier {
+    pub url: String,
+}
+
+impl Notifier for ChatNotifier {
+    fn name(&self) -> &'static str {
+        "chat"
+    }
+
+    fn request(&self, notification: &Notification) -> Result<Request, BackupServiceError> {
+        let text = chat_text(notification);
+        Ok(Request {
+            url: self.url.clone(),
+            headers: vec![json_header()],
+            body: serde_json::to_string(&json!({ "text": text, "content": text }))?,
+        })
+    }
+}
+
+fn chat_text(notification: &Notification) -> String {
+    let text = format!("{}\n{}", notification.title, notification.message);
+    let text = text.trim_end();
+    match text.char_indices().nth(MAX_CHAT_CHARS) {
+        Some((end, _)) => text[..end].to_string(),
+        None => text.to_string(),
+    }
+}
+
+/// Every configured channel; delivery failures are logged and never fail the operation
+#[derive(Clone, Default)]
+pub struct Notifiers {
+    channels: Vec<Arc<dyn Notifier + Send + Sync>>,
+    on: NotifyOn,
+}
+
+impl Notifiers {
+    pub fn from_env() -> Result<Self, BackupServiceError> {
+        Self::from_lookup(|name| std::env::var(name).ok())
+    }
+
+    fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Result<Self, BackupServiceError> {
+        let value = |name: &str| {
+            lookup(name)
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+        };
+        let mut channels: Vec<Arc<dyn Notifier + Send + Sync>> = Vec::new();
+        if let Some(url) = value(NOTIFY_WEBHOOK_URL_ENV_VAR) {
+            channels.push(Arc::new(WebhookNotifier { url }));
+        }
+        if let Some(url) = value(NOTIFY_NTFY_URL_ENV_VAR) {
+            channels.push(Arc::new(NtfyNotifier {
+                url,
+                token: value(NOTIFY_NTFY_TOKEN_ENV_VAR),
+            }));
+        }
+        if let Some(url) = value(NOTIFY_CHAT_WEBHOOK_URL_ENV_VAR) {
+            channels.push(Arc::new(ChatNotifier { url }));
+        }
+        let on = NotifyOn::parse(&value(NOTIFY_ON_ENV_VAR).unwrap_or_default())?;
+        Ok(Self { channels, on })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.channels.is_empty()
+    }
+
+    /// Whether a notification of this severity goes out at all
+    pub fn wants(&self, severity: Severity) -> bool {
+        !self.is_empty() && self.on.wants(severity)
+    }
+
+    /// Send to every channel, unless NOTIFY_ON filters the severity out
+    pub async fn dispatch(&self, notification: &Notification) {
+        if !self.wants(notification.severity) {
+            return;
+        }
+        for channel in &self.channels {
+            let sent = match channel.request(notification) {
+                Ok(request) => {
+                    http::post(
+                        &request.url,
+                        &request.headers,
+                        &request.body,
+                        "Sending the notification",
+                    )
+                    .await
+                }
+                Err(e) => Err(e),
+            };
+            match sent {
+                Ok(()) => {
+                    info!(channel = %channel.name(), kind = %notification.kind, "Sent notification")
+                }
+                Err(e) => {
+                    warn!(channel = %channel.name(), error = %e, "Could not send notification")
+                }
+            }
+        }
+    }
+}
+
+fn json_header() -> String {
+    "Content-Type: application/json".to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn lookup(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        move |name| vars.get(name).cloned()
+    }
+
+    #[test]
+    fn test_notifiers_from_env() -> Result<(), BackupServiceError> {
+        let none = Notifiers::from_lookup(lookup(&[]))?;
+        assert!(none.is_empty());
+        assert!(!none.wants(Severity::Failure));
+
+        let failures = Notifiers::from_lookup(lookup(&[
+            ("NOTIFY_NTFY_URL", "https://ntfy.sh/backups"),
+            (
+                "NOTIFY_CHAT_WEBHOOK_URL",
+                "https://hooks.slack.com/services/T/B/x",
+            ),
+        ]))?;
+        assert_eq!(failures.channels.len(), 2);
+        assert!(!failures.wants(Severity::Success));
+        assert!(failures.wants(Severity::Warning));
+
+        let always = Notifiers::from_lookup(lookup(&[
+            ("NOTIFY_WEBHOOK_URL", "https://example.com/hook"),
+            ("NOTIFY_ON", "Always"),
+        ]))?;
+        assert!(always.wants(Severity::Success));
+        assert!(Notifiers::from_lookup(lookup(&[("NOTIFY_ON", "sometimes")])).is_err());
+
+        let notification = Notification {
+            kind: "backup",
+            host: "web1".to_string(),
+            severity: Severity::Failure,
+            title: "web1: backup failed".to_string(),
+            message: "x".repeat(3000),
+            details: Value::Null,
+        };
+        assert_eq!(chat_text(&notification).chars().count(), MAX_CHAT_CHARS);
+
+        let ntfy = NtfyNotifier {
+            url: "https://ntfy.sh/backups".to_string(),
+            token: Some("tk_x".to_string()),
+        };
+        let request = ntfy.request(&notification)?;
+        assert_eq!(request.url, "https://ntfy.sh/backups");
+        assert_eq!(request.body, notification.message);
+        assert!(request.headers.contains(&"Priority: urgent".to_string()));
+        assert!(
+            request
+                .headers
+                .contains(&"Authorization: Bearer tk_x".to_string())
+        );
+        Ok(())
+    }
+}
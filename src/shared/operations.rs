@@ -1,24 +1,65 @@
 use crate::config::Config;
 use crate::errors::BackupServiceError;
 use crate::repository::BackupRepo;
-use crate::shared::commands::{ResticCommandExecutor, S3CommandExecutor};
+use crate::shared::backup_summary::ResticSummary;
+use crate::shared::backup_workflow::CategoryFilter;
+use crate::shared::commands::ResticCommandExecutor;
 use crate::shared::constants::{CATEGORY_DOCKER_VOLUME, CATEGORY_SYSTEM, CATEGORY_USER_HOME};
+use crate::shared::error_policy::{DISCOVERY_ERROR_POLICY_ENV_VAR, ErrorPolicy};
+use crate::shared::repo_store::RepoStore;
+use crate::shared::scan_cache;
+use crate::shared::timestamps::format_local;
+use crate::utils::resolve_jobs;
 use chrono::{DateTime, Utc};
+use serde::Serialize;
 use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::{
     Arc, Mutex,
     atomic::{AtomicUsize, Ordering},
 };
+use tokio::sync::Semaphore;
 use tracing::{info, warn};
 
-// Repository discovered from S3 but not yet scanned for snapshots
+/// Repositories whose snapshots are listed at once during a scan
+pub const SCAN_JOBS_ENV_VAR: &str = "SCAN_JOBS";
+pub const DEFAULT_SCAN_JOBS: usize = 8;
+
+// Repository discovered in the store but not yet scanned for snapshots
 #[derive(Debug, Clone)]
 pub struct UnscannedRepository {
     pub repo_subpath: String,
     pub category: String,
 }
 
+// Part of the repository tree that could not be read during discovery
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DiscoveryFailure {
+    /// Category, category/user, or repository subpath that failed
+    pub scope: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for DiscoveryFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.scope, self.message)
+    }
+}
+
+// Repositories found in the store plus the parts of the tree that failed to list
+#[derive(Debug, Clone, Default)]
+pub struct RepositoryDiscovery {
+    pub repos: Vec<UnscannedRepository>,
+    pub failures: Vec<DiscoveryFailure>,
+}
+
+// Scanned repositories plus everything that failed along the way (partial results)
+#[derive(Debug, Clone, Default)]
+pub struct ScanResult {
+    pub repos: Vec<RepositoryData>,
+    pub failures: Vec<DiscoveryFailure>,
+}
+
 // Repository metadata information (after scanning snapshots for actual path)
 #[derive(Debug, Clone)]
 pub struct RepositoryInfo {
@@ -33,6 +74,9 @@ pub struct SnapshotInfo {
     pub time: DateTime<Utc>,
     pub path: PathBuf,
     pub id: String,
+    pub tags: Vec<String>,
+    /// Backup statistics stored with the snapshot (restic >= 0.17)
+    pub summary: Option<ResticSummary>,
 }
 
 // Combined repository information with snapshot data
@@ -61,7 +105,10 @@ pub struct SnapshotItem {
 // Main repository operations manager with scanning capabilities
 pub struct RepositoryOperations {
     config: Config,
-    s3_executor: S3CommandExecutor,
+    store: RepoStore,
+    discovery_policy: ErrorPolicy,
+    /// Categories whose prefixes are listed at all (`--category`); empty means every one
+    categories: CategoryFilter,
 }
 
 // Collects snapshot data from restic repositories
@@ -74,63 +121,142 @@ pub struct SnapshotCollector {
 
 impl RepositoryOperations {
     pub fn new(config: Config) -> Result<Self, BackupServiceError> {
-        let s3_executor = S3CommandExecutor::new(config.clone())?;
+        let store = RepoStore::new(config.clone())?;
+        let discovery_policy = ErrorPolicy::from_env(DISCOVERY_ERROR_POLICY_ENV_VAR)?;
         Ok(Self {
             config,
-            s3_executor,
+            store,
+            discovery_policy,
+            categories: CategoryFilter::default(),
         })
     }
 
+    /// Only discover and scan repositories of these categories
+    pub fn with_categories(mut self, categories: Vec<String>) -> Result<Self, BackupServiceError> {
+        self.categories = CategoryFilter::new(categories, Vec::new())?;
+        Ok(self)
+    }
+
     // Main entrypoint to collect all repository data for a hostname
     pub async fn collect_backup_data(
         &self,
         hostname: &str,
-    ) -> Result<Vec<RepositoryData>, BackupServiceError> {
+    ) -> Result<ScanResult, BackupServiceError> {
         self.scan_repositories(hostname).await
     }
 
-    // Construct S3 path with optional base path prefix
-    fn build_s3_path(&self, hostname: &str, category: &str) -> Result<String, BackupServiceError> {
-        let base_path = self.config.s3_base_path()?;
-        if base_path.is_empty() {
-            Ok(format!("{}/{}", hostname, category))
-        } else {
-            Ok(format!("{}/{}/{}", base_path, hostname, category))
+    // Construct the store path of a host's category, honouring the base path prefix
+    fn build_store_path(
+        &self,
+        hostname: &str,
+        category: &str,
+    ) -> Result<String, BackupServiceError> {
+        Ok(format!(
+            "{}/{}",
+            self.config.host_store_path(hostname)?,
+            category
+        ))
+    }
+
+    // List directories on the repository backend (S3, SFTP or local)
+    pub async fn list_dirs(&self, path: &str) -> Result<Vec<String>, BackupServiceError> {
+        self.store.list_directories(path).await
+    }
+
+    // List directories; under DISCOVERY_ERROR_POLICY=continue a failure is recorded, not fatal
+    async fn list_dirs_or_record(
+        &self,
+        path: &str,
+        scope: &str,
+        failures: &mut Vec<DiscoveryFailure>,
+    ) -> Result<Vec<String>, BackupServiceError> {
+        match self.list_dirs(path).await {
+            Ok(dirs) => Ok(dirs),
+            Err(e) if self.discovery_policy.is_fail_fast() => Err(e),
+            Err(e) => {
+                warn!(scope = %scope, error = %e, "Failed to list repositories");
+                failures.push(DiscoveryFailure {
+                    scope: scope.to_string(),
+                    message: format!("failed to list ({})", e),
+                });
+                Ok(Vec::new())
+            }
         }
     }
 
-    // List S3 directories using shared S3CommandExecutor
-    pub async fn list_s3_dirs(&self, s3_path: &str) -> Result<Vec<String>, BackupServiceError> {
-        self.s3_executor.list_directories(s3_path).await
+    /// `scan_repositories`, answered from the local scan cache while it is fresh
+    ///
+    /// Only complete scans are cached; `refresh` rescans and replaces the cached one. A
+    /// category-filtered scan reads a cached full scan but is never stored itself.
+    pub async fn scan_repositories_cached(
+        &self,
+        hostname: &str,
+        refresh: bool,
+    ) -> Result<ScanResult, BackupServiceError> {
+        if !refresh
+            && let Some((scanned_at, mut repos)) = scan_cache::lookup(&self.config, hostname)
+        {
+            repos.retain(|r| self.categories.allows(&r.info.category));
+            info!(
+                host = %hostname,
+                scanned_at = %format_local(scanned_at),
+                repo_count = %repos.len(),
+                "Using cached repository scan (--refresh to rescan)"
+            );
+            return Ok(ScanResult {
+                repos,
+                failures: Vec::new(),
+            });
+        }
+        let scan = self.scan_repositories(hostname).await?;
+        if scan.failures.is_empty() && !self.categories.is_active() {
+            scan_cache::store(&self.config, hostname, &scan.repos);
+        }
+        Ok(scan)
     }
 
     /// Scan and collect all repositories for a hostname with true parallelization
     pub async fn scan_repositories(
         &self,
         hostname: &str,
-    ) -> Result<Vec<RepositoryData>, BackupServiceError> {
-        let all_repo_infos = self.discover_all_repositories(hostname).await?;
+    ) -> Result<ScanResult, BackupServiceError> {
+        let RepositoryDiscovery {
+            repos: all_repo_infos,
+            mut failures,
+        } = self.discover_all_repositories(hostname).await?;
         let total_repos = all_repo_infos.len();
         let counter = Arc::new(AtomicUsize::new(0));
 
         if total_repos == 0 {
             info!("Scanning completed!");
-            return Ok(Vec::new());
+            return Ok(ScanResult {
+                repos: Vec::new(),
+                failures,
+            });
         }
 
-        info!("Found {} repositories to check", total_repos);
+        // Unbounded, a few hundred repositories trip the backend's rate limits
+        let jobs = resolve_jobs(None, SCAN_JOBS_ENV_VAR, DEFAULT_SCAN_JOBS)?;
+        info!(
+            "Found {} repositories to check ({} at a time)",
+            total_repos, jobs
+        );
 
         let snapshot_collector = SnapshotCollector::new(self.config.clone(), hostname)?;
+        let permits = Arc::new(Semaphore::new(jobs));
 
         // Parallel execution: spawn concurrent tasks for repository checking
         let mut tasks = Vec::new();
 
         for unscanned_repo in all_repo_infos {
             let snapshot_collector = snapshot_collector.clone();
+            let discovery_policy = self.discovery_policy;
             let counter_clone = counter.clone();
+            let permits = Arc::clone(&permits);
 
-            // Each repository is checked concurrently using tokio::spawn
+            // Each repository is checked on its own task once a permit is free
             let task = tokio::spawn(async move {
+                let _permit = permits.acquire_owned().await;
                 let current = counter_clone.fetch_add(1, Ordering::SeqCst) + 1;
                 let repo_subpath = &unscanned_repo.repo_subpath;
 
@@ -156,25 +282,29 @@ impl RepositoryOperations {
                                 category: unscanned_repo.category,
                             };
 
-                            Ok::<Option<RepositoryData>, BackupServiceError>(Some(RepositoryData {
+                            Ok(Ok(Some(RepositoryData {
                                 info: repo_info,
                                 snapshots,
                                 snapshot_count: count,
-                            }))
+                            })))
                         } else {
                             warn!(
                                 "({}/{}) - No snapshots found for repo: {}",
                                 current, total_repos, repo_subpath
                             );
-                            Ok::<Option<RepositoryData>, BackupServiceError>(None)
+                            Ok(Ok(None))
                         }
                     }
+                    Err(e) if discovery_policy.is_fail_fast() => Err(e),
                     Err(e) => {
                         warn!(
                             "({}/{}) - Failed to get snapshots for repo '{}': {}",
                             current, total_repos, repo_subpath, e
                         );
-                        Ok::<Option<RepositoryData>, BackupServiceError>(None)
+                        Ok::<_, BackupServiceError>(Err(DiscoveryFailure {
+                            scope: repo_subpath.clone(),
+                            message: format!("failed to read snapshots ({})", e),
+                        }))
                     }
                 }
             });
@@ -182,10 +312,14 @@ impl RepositoryOperations {
             tasks.push(task);
         }
 
-        let mut results = Vec::new();
+        let mut repos = Vec::new();
         for task in tasks {
             match task.await {
-                Ok(result) => results.push(result?),
+                Ok(result) => match result? {
+                    Ok(Some(repo)) => repos.push(repo),
+                    Ok(None) => {}
+                    Err(failure) => failures.push(failure),
+                },
                 Err(join_error) => {
                     return Err(BackupServiceError::CommandFailed(format!(
                         "Task join error: {}",
@@ -194,32 +328,28 @@ impl RepositoryOperations {
                 }
             }
         }
-        let repos: Vec<RepositoryData> = results.into_iter().flatten().collect();
-
         info!("Scanning completed!");
-        Ok(repos)
+        Ok(ScanResult { repos, failures })
     }
 
-    async fn discover_all_repositories(
+    /// Discover repository subpaths for a host from the store without querying snapshots
+    pub async fn discover_all_repositories(
         &self,
         hostname: &str,
-    ) -> Result<Vec<UnscannedRepository>, BackupServiceError> {
-        let mut all_repos = Vec::new();
+    ) -> Result<RepositoryDiscovery, BackupServiceError> {
+        let mut discovery = RepositoryDiscovery::default();
 
-        all_repos.extend(
-            self.discover_repositories_by_category(hostname, "user_home")
-                .await?,
-        );
-        all_repos.extend(
-            self.discover_repositories_by_category(hostname, "docker_volume")
-                .await?,
-        );
-        all_repos.extend(
-            self.discover_repositories_by_category(hostname, "system")
-                .await?,
-        );
+        for category in [CATEGORY_USER_HOME, CATEGORY_DOCKER_VOLUME, CATEGORY_SYSTEM] {
+            if !self.categories.allows(category) {
+                continue;
+            }
+            let repos = self
+                .discover_repositories_by_category(hostname, category, &mut discovery.failures)
+                .await?;
+            discovery.repos.extend(repos);
+        }
 
-        Ok(all_repos)
+        Ok(discovery)
     }
 
     // Unified repository discovery for all categories
@@ -227,21 +357,26 @@ impl RepositoryOperations {
         &self,
         hostname: &str,
         category: &str,
+        failures: &mut Vec<DiscoveryFailure>,
     ) -> Result<Vec<UnscannedRepository>, BackupServiceError> {
-        let category_path = self.build_s3_path(hostname, category)?;
+        let category_path = self.build_store_path(hostname, category)?;
         info!("Scanning {} directories...", category);
 
         let mut repos = Vec::new();
 
         match category {
-            "user_home" => {
-                repos.extend(self.discover_user_home_repositories(&category_path).await?)
-            }
+            "user_home" => repos.extend(
+                self.discover_user_home_repositories(&category_path, failures)
+                    .await?,
+            ),
             "docker_volume" => repos.extend(
-                self.discover_docker_volume_repositories(&category_path)
+                self.discover_docker_volume_repositories(&category_path, failures)
+                    .await?,
+            ),
+            "system" => repos.extend(
+                self.discover_system_repositories(&category_path, failures)
                     .await?,
             ),
-            "system" => repos.extend(self.discover_system_repositories(&category_path).await?),
             _ => {
                 return Err(BackupServiceError::ConfigurationError(format!(
                     "Unknown repository category: {}",
@@ -256,23 +391,25 @@ impl RepositoryOperations {
     async fn discover_user_home_repositories(
         &self,
         category_path: &str,
+        failures: &mut Vec<DiscoveryFailure>,
     ) -> Result<Vec<UnscannedRepository>, BackupServiceError> {
         let mut repos = Vec::new();
 
-        if let Ok(users) = self.list_s3_dirs(category_path).await {
-            for user in users {
-                info!("Processing user: {}", user);
-                let user_path = format!("{}/{}", category_path, user);
-
-                if let Ok(subdirs) = self.list_s3_dirs(&user_path).await {
-                    for subdir in subdirs {
-                        let repo_subpath = format!("user_home/{}/{}", user, subdir);
-
-                        repos.push(
-                            self.create_unscanned_repository(repo_subpath, CATEGORY_USER_HOME),
-                        );
-                    }
-                }
+        let users = self
+            .list_dirs_or_record(category_path, CATEGORY_USER_HOME, failures)
+            .await?;
+        for user in users {
+            info!("Processing user: {}", user);
+            let user_path = format!("{}/{}", category_path, user);
+            let scope = format!("{}/{}", CATEGORY_USER_HOME, user);
+
+            let subdirs = self
+                .list_dirs_or_record(&user_path, &scope, failures)
+                .await?;
+            for subdir in subdirs {
+                let repo_subpath = format!("user_home/{}/{}", user, subdir);
+
+                repos.push(self.create_unscanned_repository(repo_subpath, CATEGORY_USER_HOME));
             }
         }
 
@@ -282,15 +419,17 @@ impl RepositoryOperations {
     async fn discover_docker_volume_repositories(
         &self,
         category_path: &str,
+        failures: &mut Vec<DiscoveryFailure>,
     ) -> Result<Vec<UnscannedRepository>, BackupServiceError> {
         let mut repos = Vec::new();
 
-        if let Ok(volumes) = self.list_s3_dirs(category_path).await {
-            for volume in volumes {
-                let repo_subpath = format!("docker_volume/{}", volume);
+        let volumes = self
+            .list_dirs_or_record(category_path, CATEGORY_DOCKER_VOLUME, failures)
+            .await?;
+        for volume in volumes {
+            let repo_subpath = format!("docker_volume/{}", volume);
 
-                repos.push(self.create_unscanned_repository(repo_subpath, CATEGORY_DOCKER_VOLUME));
-            }
+            repos.push(self.create_unscanned_repository(repo_subpath, CATEGORY_DOCKER_VOLUME));
         }
 
         Ok(repos)
@@ -299,15 +438,17 @@ impl RepositoryOperations {
     async fn discover_system_repositories(
         &self,
         category_path: &str,
+        failures: &mut Vec<DiscoveryFailure>,
     ) -> Result<Vec<UnscannedRepository>, BackupServiceError> {
         let mut repos = Vec::new();
 
-        if let Ok(paths) = self.list_s3_dirs(category_path).await {
-            for path in paths {
-                let repo_subpath = format!("system/{}", path);
+        let paths = self
+            .list_dirs_or_record(category_path, CATEGORY_SYSTEM, failures)
+            .await?;
+        for path in paths {
+            let repo_subpath = format!("system/{}", path);
 
-                repos.push(self.create_unscanned_repository(repo_subpath, CATEGORY_SYSTEM));
-            }
+            repos.push(self.create_unscanned_repository(repo_subpath, CATEGORY_SYSTEM));
         }
 
         Ok(repos)
@@ -324,9 +465,9 @@ impl RepositoryOperations {
         }
     }
 
-    // Get available hosts from S3 storage
+    // Get available hosts from the repository store
     pub async fn get_available_hosts(&self) -> Result<Vec<String>, BackupServiceError> {
-        self.s3_executor.get_hosts().await
+        self.store.get_hosts().await
     }
 
     // Convert repository data to BackupRepo format
@@ -434,10 +575,20 @@ impl SnapshotCollector {
             .filter_map(|s| {
                 let time = s["time"].as_str()?.parse::<DateTime<Utc>>().ok()?;
                 let id = s["short_id"].as_str()?.to_string();
+                let tags = s["tags"]
+                    .as_array()
+                    .map(|tags| {
+                        tags.iter()
+                            .filter_map(|t| t.as_str().map(str::to_string))
+                            .collect()
+                    })
+                    .unwrap_or_default();
                 Some(SnapshotInfo {
                     time,
                     path: actual_native_path.clone(),
                     id,
+                    tags,
+                    summary: ResticSummary::from_json(&s),
                 })
             })
             .collect();
@@ -469,6 +620,8 @@ mod tests {
             time,
             path: PathBuf::from(path),
             id: id.to_string(),
+            tags: Vec::new(),
+            summary: None,
         }
     }
 
@@ -1025,4 +1178,63 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_category_filtered_discovery() -> Result<(), BackupServiceError> {
+        use crate::config::Config;
+
+        let root = tempfile::tempdir()?;
+        for dir in [
+            "web1/user_home/tim/Documents",
+            "web1/docker_volume/db",
+            "web1/system/etc",
+        ] {
+            std::fs::create_dir_all(root.path().join(dir))?;
+        }
+        let config = Config {
+            restic_password: "test".to_string(),
+            restic_repo_base: root.path().display().to_string(),
+            aws_access_key_id: String::new(),
+            aws_secret_access_key: String::new(),
+            aws_default_region: "auto".to_string(),
+            aws_s3_endpoint: String::new(),
+            backup_paths: vec![],
+            hostname: "web1".to_string(),
+        };
+
+        let all = RepositoryOperations::new(config.clone())?
+            .discover_all_repositories("web1")
+            .await?;
+        assert_eq!(all.repos.len(), 3);
+
+        let filtered = RepositoryOperations::new(config.clone())?
+            .with_categories(vec![CATEGORY_DOCKER_VOLUME.to_string()])?
+            .discover_all_repositories("web1")
+            .await?;
+        let subpaths: Vec<&str> = filtered
+            .repos
+            .iter()
+            .map(|r| r.repo_subpath.as_str())
+            .collect();
+        assert_eq!(subpaths, ["docker_volume/db"]);
+
+        assert!(
+            RepositoryOperations::new(config)?
+                .with_categories(vec!["volumes".to_string()])
+                .is_err()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_discovery_failure_display() {
+        let failure = DiscoveryFailure {
+            scope: CATEGORY_USER_HOME.to_string(),
+            message: format!("failed to list ({})", BackupServiceError::NetworkError),
+        };
+        assert_eq!(
+            failure.to_string(),
+            "user_home: failed to list (Network error: Cannot connect to repository)"
+        );
+    }
 }
@@ -0,0 +1,165 @@
+use crate::errors::BackupServiceError;
+use crate::shared::operations::RepositoryData;
+use std::path::{Path, PathBuf};
+
+/// Env var with extra snapshot tags per path: `path=tag,tag;path=tag`
+pub const PATH_TAGS_ENV_VAR: &str = "BACKUP_PATH_TAGS";
+
+/// Extra `restic backup --tag` values for each backup path, on top of the built-in
+/// user-path/docker-volume/system-path tag
+///
+/// Unlike excludes, tags add up: a path gets the tags of every entry at or above it, so
+/// `/home=household;/home/tim=critical` tags /home/tim/Documents with both.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PathTags {
+    per_path: Vec<(PathBuf, Vec<String>)>,
+}
+
+impl PathTags {
+    pub fn from_env() -> Result<Self, BackupServiceError> {
+        Self::parse(&std::env::var(PATH_TAGS_ENV_VAR).unwrap_or_default())
+    }
+
+    pub fn parse(value: &str) -> Result<Self, BackupServiceError> {
+        let mut rules = Self::default();
+        for entry in value.split(';').map(str::trim).filter(|e| !e.is_empty()) {
+            let Some((path, tags)) = entry.split_once('=') else {
+                return Err(invalid(entry, "expected path=tag,tag"));
+            };
+            let path = match path.trim() {
+                "/" => "/",
+                other => other.trim_end_matches('/'),
+            };
+            if !path.starts_with('/') {
+                return Err(invalid(entry, "the path must be absolute"));
+            }
+            let tags: Vec<String> = tags
+                .split(',')
+                .map(str::trim)
+                .filter(|t| !t.is_empty())
+                .map(str::to_string)
+                .collect();
+            if tags.is_empty() {
+                return Err(invalid(entry, "name at least one tag"));
+            }
+            if let Some(tag) = tags.iter().find(|t| t.contains(char::is_whitespace)) {
+                return Err(invalid(
+                    entry,
+                    &format!("tag '{}' must not contain whitespace", tag),
+                ));
+            }
+            rules.per_path.push((PathBuf::from(path), tags));
+        }
+        Ok(rules)
+    }
+
+    /// Tags of every entry at or above a backup path, sorted and without duplicates
+    pub fn for_path(&self, path: &Path) -> Vec<String> {
+        let mut tags: Vec<String> = self
+            .per_path
+            .iter()
+            .filter(|(prefix, _)| path.starts_with(prefix))
+            .flat_map(|(_, tags)| tags.iter().cloned())
+            .collect();
+        tags.sort();
+        tags.dedup();
+        tags
+    }
+
+    /// `restic backup` arguments for a path: one `--tag` per tag
+    pub fn backup_args(&self, path: &Path) -> Vec<String> {
+        self.for_path(path)
+            .into_iter()
+            .flat_map(|tag| ["--tag".to_string(), tag])
+            .collect()
+    }
+}
+
+fn invalid(entry: &str, reason: &str) -> BackupServiceError {
+    BackupServiceError::ConfigurationError(format!(
+        "Invalid {} entry '{}': {}.\n\nExample: {}=\"/home/tim=critical;/srv/games=games,large\"",
+        PATH_TAGS_ENV_VAR, entry, reason, PATH_TAGS_ENV_VAR
+    ))
+}
+
+/// Keep only snapshots carrying every one of `tags` (`--tag` of list and restore), dropping
+/// repositories left without any; no tags keeps everything
+pub fn retain_tagged(repos: Vec<RepositoryData>, tags: &[String]) -> Vec<RepositoryData> {
+    if tags.is_empty() {
+        return repos;
+    }
+    repos
+        .into_iter()
+        .filter_map(|mut repo| {
+            repo.snapshots
+                .retain(|s| tags.iter().all(|tag| s.tags.contains(tag)));
+            repo.snapshot_count = repo.snapshots.len();
+            (!repo.snapshots.is_empty()).then_some(repo)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::operations::{RepositoryInfo, SnapshotInfo};
+    use chrono::Utc;
+
+    #[test]
+    fn test_path_tags() -> Result<(), BackupServiceError> {
+        let rules = PathTags::parse("/home=household; /home/tim/=critical,household;/srv=games")?;
+        assert_eq!(
+            rules.backup_args(Path::new("/home/tim/Documents")),
+            ["--tag", "critical", "--tag", "household"]
+        );
+        assert_eq!(rules.for_path(Path::new("/home/anna")), ["household"]);
+        assert!(rules.for_path(Path::new("/etc")).is_empty());
+        assert!(rules.for_path(Path::new("/srvdata")).is_empty());
+
+        assert!(
+            PathTags::parse("")?
+                .backup_args(Path::new("/etc"))
+                .is_empty()
+        );
+        assert!(PathTags::parse("home/tim=critical").is_err());
+        assert!(PathTags::parse("/home/tim=").is_err());
+        assert!(PathTags::parse("/home/tim=very important").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_retain_tagged() {
+        let snapshot = |id: &str, tags: &[&str]| SnapshotInfo {
+            time: Utc::now(),
+            path: PathBuf::from("/home/tim"),
+            id: id.to_string(),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            summary: None,
+        };
+        let repo = |subpath: &str, snapshots: Vec<SnapshotInfo>| RepositoryData {
+            info: RepositoryInfo {
+                native_path: PathBuf::from("/home/tim"),
+                repo_subpath: subpath.to_string(),
+                category: "user_home".to_string(),
+            },
+            snapshot_count: snapshots.len(),
+            snapshots,
+        };
+        let repos = vec![
+            repo(
+                "user_home/tim",
+                vec![
+                    snapshot("a1", &["user-path", "critical"]),
+                    snapshot("b2", &["user-path"]),
+                ],
+            ),
+            repo("user_home/anna", vec![snapshot("c3", &["user-path"])]),
+        ];
+
+        let tagged = retain_tagged(repos.clone(), &["critical".to_string()]);
+        assert_eq!(tagged.len(), 1);
+        assert_eq!(tagged[0].snapshot_count, 1);
+        assert_eq!(tagged[0].snapshots[0].id, "a1");
+        assert_eq!(retain_tagged(repos, &[]).len(), 2);
+    }
+}
@@ -1,10 +1,69 @@
+use crate::config::Config;
 use crate::errors::BackupServiceError;
+use crate::shared::commands::check_restic_repository_exists;
 use crate::shared::constants::{
     DOCKER_BACKING_FS_BLOCK_DEV, DOCKER_METADATA_DB, DOCKER_VOLUMES_DIR,
+    DOCKER_VOLUMES_DIR_WITH_SLASH,
 };
-use std::path::{Path, PathBuf};
+use crate::shared::content_policy::wildcard_match;
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
 use tracing::{info, warn};
 
+/// Env var that adds every `/home/<user>` directory to the backup paths
+pub const DISCOVER_HOMES_ENV_VAR: &str = "BACKUP_DISCOVER_HOMES";
+/// Users left out of home discovery (names or `*`/`?` patterns, comma-separated)
+pub const HOME_EXCLUDE_ENV_VAR: &str = "BACKUP_HOME_EXCLUDE";
+const HOME_ROOT: &str = "/home";
+/// Env var choosing what happens to a backup path nested below another one
+pub const PATH_OVERLAP_ENV_VAR: &str = "BACKUP_PATH_OVERLAP";
+
+/// What to do with a backup path that lies below another backup path
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OverlapPolicy {
+    /// Back up both and warn that the nested data is stored twice (default)
+    #[default]
+    Warn,
+    /// Drop the nested path; the parent's snapshot already contains it
+    SkipNested,
+    /// Back up both without a warning, e.g. for a separate history of the nested path
+    Keep,
+}
+
+impl OverlapPolicy {
+    pub fn parse(value: &str) -> Result<Self, BackupServiceError> {
+        match value.trim().to_lowercase().as_str() {
+            "" | "warn" => Ok(OverlapPolicy::Warn),
+            "skip-nested" | "skip" => Ok(OverlapPolicy::SkipNested),
+            "keep" | "keep-both" => Ok(OverlapPolicy::Keep),
+            _ => Err(BackupServiceError::ConfigurationError(format!(
+                "Unknown path overlap policy: {}.\n\nValid values for {} are: warn, skip-nested, keep",
+                value, PATH_OVERLAP_ENV_VAR
+            ))),
+        }
+    }
+
+    pub fn from_env() -> Result<Self, BackupServiceError> {
+        Self::parse(&std::env::var(PATH_OVERLAP_ENV_VAR).unwrap_or_default())
+    }
+}
+
+/// `(nested, parent)` for every path below another path of the list, with the closest
+/// parent; paths in `separate` (sensitive paths, which their parents exclude) never count
+pub fn find_overlaps(paths: &[PathBuf], separate: &[PathBuf]) -> Vec<(PathBuf, PathBuf)> {
+    paths
+        .iter()
+        .filter(|path| !separate.contains(path))
+        .filter_map(|path| {
+            paths
+                .iter()
+                .filter(|parent| *parent != path && path.starts_with(parent))
+                .max_by_key(|parent| parent.components().count())
+                .map(|parent| (path.clone(), parent.clone()))
+        })
+        .collect()
+}
+
 /// Docker volume discovery and validation utilities
 pub struct PathUtilities;
 
@@ -36,11 +95,50 @@ impl PathUtilities {
         Ok(volumes)
     }
 
-    /// Validate that paths exist and are accessible
+    /// BACKUP_PATHS with `~` and globs expanded, plus the discovered home directories when
+    /// BACKUP_DISCOVER_HOMES is on; duplicates are dropped, the first occurrence wins
+    pub fn configured_paths(config: &Config) -> Vec<PathBuf> {
+        let home = std::env::var_os("HOME").map(PathBuf::from);
+        let mut paths = Vec::new();
+        for entry in &config.backup_paths {
+            let expanded = expand_backup_path(entry, home.as_deref());
+            if expanded.is_empty() {
+                warn!(pattern = %entry.display(), "Backup path pattern matched nothing");
+            }
+            paths.extend(expanded);
+        }
+
+        let discover = std::env::var(DISCOVER_HOMES_ENV_VAR)
+            .is_ok_and(|v| matches!(v.trim(), "1" | "true" | "yes"));
+        if discover {
+            let exclude: Vec<String> = std::env::var(HOME_EXCLUDE_ENV_VAR)
+                .unwrap_or_default()
+                .split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect();
+            let homes = discover_home_dirs(Path::new(HOME_ROOT), &exclude);
+            info!(homes = %homes.len(), "Discovered home directories");
+            paths.extend(homes);
+        }
+
+        let mut unique = Vec::new();
+        for path in paths {
+            if !unique.contains(&path) {
+                unique.push(path);
+            }
+        }
+        unique
+    }
+
+    /// Validate that paths exist and are accessible, drop duplicates and apply the overlap
+    /// policy to paths nested below another one (except those in `separate`)
     pub fn validate_and_filter_paths(
         paths: Vec<PathBuf>,
+        overlap: OverlapPolicy,
+        separate: &[PathBuf],
     ) -> Result<Vec<PathBuf>, BackupServiceError> {
-        let mut valid_paths = Vec::new();
+        let mut valid_paths: Vec<PathBuf> = Vec::new();
         let mut skip_count = 0;
 
         for path in paths {
@@ -49,6 +147,9 @@ impl PathUtilities {
                 skip_count += 1;
                 continue;
             }
+            if valid_paths.contains(&path) {
+                continue;
+            }
 
             valid_paths.push(path);
         }
@@ -57,48 +158,345 @@ impl PathUtilities {
             info!(skip_count = %skip_count, "Skipped non-existent paths");
         }
 
+        for (nested, parent) in find_overlaps(&valid_paths, separate) {
+            match overlap {
+                OverlapPolicy::Warn => warn!(
+                    path = %nested.display(),
+                    parent = %parent.display(),
+                    "Path is also backed up as part of its parent (set BACKUP_PATH_OVERLAP=skip-nested to skip it)"
+                ),
+                OverlapPolicy::SkipNested => {
+                    info!(path = %nested.display(), parent = %parent.display(), "Skipping path nested below another backup path");
+                    valid_paths.retain(|p| *p != nested);
+                }
+                OverlapPolicy::Keep => {}
+            }
+        }
+
         Ok(valid_paths)
     }
 }
 
+fn has_glob(name: &str) -> bool {
+    name.contains(['*', '?'])
+}
+
+/// `~` and `~/x` resolve against `home`, `~user/x` against `/home/user`
+fn expand_tilde(path: &str, home: Option<&Path>) -> Option<PathBuf> {
+    let rest = path.strip_prefix('~')?;
+    let (user, tail) = rest.split_once('/').unwrap_or((rest, ""));
+    let base = if user.is_empty() {
+        home?.to_path_buf()
+    } else {
+        Path::new(HOME_ROOT).join(user)
+    };
+    Some(if tail.is_empty() {
+        base
+    } else {
+        base.join(tail)
+    })
+}
+
+/// One BACKUP_PATHS entry as the paths it stands for
+///
+/// Components with `*` or `?` are matched against the directory listing, sorted; hidden
+/// entries only match a pattern starting with a dot. Entries without a glob are returned
+/// as they are, so a missing path is still reported by `validate_and_filter_paths`.
+pub fn expand_backup_path(entry: &Path, home: Option<&Path>) -> Vec<PathBuf> {
+    let raw = entry.to_string_lossy();
+    let path = match expand_tilde(&raw, home) {
+        Some(path) => path,
+        None if raw.starts_with('~') => {
+            warn!(path = %raw, "HOME is not set, cannot expand ~");
+            return Vec::new();
+        }
+        None => entry.to_path_buf(),
+    };
+    if !has_glob(&path.to_string_lossy()) {
+        return vec![path];
+    }
+
+    let mut matches = vec![PathBuf::new()];
+    for component in path.components() {
+        let pattern = match component {
+            Component::Normal(name) if has_glob(&name.to_string_lossy()) => {
+                name.to_string_lossy().to_string()
+            }
+            other => {
+                for m in &mut matches {
+                    m.push(other);
+                }
+                continue;
+            }
+        };
+        matches = matches
+            .into_iter()
+            .flat_map(|base| {
+                let mut names: Vec<String> = std::fs::read_dir(&base)
+                    .into_iter()
+                    .flatten()
+                    .filter_map(Result::ok)
+                    .map(|e| e.file_name().to_string_lossy().to_string())
+                    .filter(|name| !name.starts_with('.') || pattern.starts_with('.'))
+                    .filter(|name| wildcard_match(&pattern, name))
+                    .collect();
+                names.sort();
+                names.into_iter().map(move |name| base.join(name))
+            })
+            .collect();
+    }
+    // Literal components after a glob may not exist below every match
+    matches.retain(|m| m.exists());
+    matches
+}
+
+/// User directories below `root`, sorted; dot entries, `lost+found`, symlinks and
+/// `exclude` matches are skipped
+pub fn discover_home_dirs(root: &Path, exclude: &[String]) -> Vec<PathBuf> {
+    let mut homes: Vec<PathBuf> = std::fs::read_dir(root)
+        .into_iter()
+        .flatten()
+        .filter_map(Result::ok)
+        .filter(|e| e.file_type().is_ok_and(|t| t.is_dir()))
+        .filter(|e| {
+            let name = e.file_name().to_string_lossy().to_string();
+            !name.starts_with('.')
+                && name != "lost+found"
+                && !exclude.iter().any(|pattern| wildcard_match(pattern, &name))
+        })
+        .map(|e| e.path())
+        .collect();
+    homes.sort();
+    homes
+}
+
+/// Env var selecting how new repository names are encoded: `2` (default) or `1`
+pub const PATH_ENCODING_ENV_VAR: &str = "REPO_PATH_ENCODING";
+
+/// How the part of a path below its category root is flattened into one directory name
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PathEncoding {
+    /// Version 1: `/` becomes `_`, so `/var/log_app` and `/var/log/app` share a name and a
+    /// name cannot be mapped back to its path
+    Legacy,
+    /// Version 2: `~`, `%` and `_` inside a component are hex-escaped behind `~` (`~7E`,
+    /// `~25`, `~5F`) before `/` becomes `_`. Every name maps back to exactly one path, and
+    /// paths without these characters keep their version 1 name. `~` is unreserved in URLs,
+    /// so restic's URL parsing of `s3:https://…` repositories keeps the name as written
+    #[default]
+    Escaped,
+}
+
+impl PathEncoding {
+    pub fn parse(value: &str) -> Result<Self, BackupServiceError> {
+        match value.trim().to_lowercase().as_str() {
+            "1" | "v1" | "legacy" => Ok(PathEncoding::Legacy),
+            "" | "2" | "v2" | "escaped" => Ok(PathEncoding::Escaped),
+            _ => Err(BackupServiceError::ConfigurationError(format!(
+                "Unknown repository path encoding: {}.\n\nValid values for {} are: 1 (legacy), 2",
+                value, PATH_ENCODING_ENV_VAR
+            ))),
+        }
+    }
+
+    pub fn from_env() -> Result<Self, BackupServiceError> {
+        Self::parse(&std::env::var(PATH_ENCODING_ENV_VAR).unwrap_or_default())
+    }
+
+    /// Flatten path components into one directory name
+    fn flatten(&self, components: &[&str]) -> String {
+        match self {
+            PathEncoding::Legacy => components.join("_"),
+            PathEncoding::Escaped => components
+                .iter()
+                .map(|c| {
+                    c.replace('~', "~7E")
+                        .replace('%', "~25")
+                        .replace('_', "~5F")
+                })
+                .collect::<Vec<_>>()
+                .join("_"),
+        }
+    }
+}
+
+/// Components of a version 2 name, or None when it is not valid version 2 encoding
+fn unflatten_escaped(name: &str) -> Option<Vec<String>> {
+    name.split('_')
+        .map(|component| {
+            let mut decoded = String::new();
+            let mut rest = component;
+            while let Some(idx) = rest.find('~') {
+                decoded.push_str(&rest[..idx]);
+                match rest.get(idx + 1..idx + 3) {
+                    Some("7E") => decoded.push('~'),
+                    Some("25") => decoded.push('%'),
+                    Some("5F") => decoded.push('_'),
+                    _ => return None,
+                }
+                rest = &rest[idx + 3..];
+            }
+            decoded.push_str(rest);
+            (!decoded.is_empty()).then_some(decoded)
+        })
+        .collect()
+}
+
+/// Repository subpath `path` is backed up to on `hostname`
+///
+/// A repository created under the other encoding (see `PathMapper::candidate_subpaths`)
+/// keeps being used while none exists under the configured one, so switching encodings
+/// never splits a path's history.
+pub async fn resolve_repo_subpath(
+    config: &Config,
+    hostname: &str,
+    path: &Path,
+) -> Result<String, BackupServiceError> {
+    let candidates = PathMapper::candidate_subpaths(path)?;
+    let [preferred, others @ ..] = candidates.as_slice() else {
+        return PathMapper::path_to_repo_subpath(path);
+    };
+    if others.is_empty()
+        || check_restic_repository_exists(
+            config,
+            &config.get_repo_url_for_host(hostname, preferred)?,
+        )
+        .await?
+    {
+        return Ok(preferred.clone());
+    }
+    for other in others {
+        let url = config.get_repo_url_for_host(hostname, other)?;
+        if check_restic_repository_exists(config, &url).await? {
+            warn!(
+                path = %path.display(),
+                repository = %other,
+                preferred = %preferred,
+                "Using the repository named under the other path encoding"
+            );
+            return Ok(other.clone());
+        }
+    }
+    Ok(preferred.clone())
+}
+
 /// Path mapping utilities (extracted from helpers.rs PathMapper)
 pub struct PathMapper;
 
 impl PathMapper {
-    /// Convert native filesystem path to repository subpath
+    /// Convert native filesystem path to repository subpath (REPO_PATH_ENCODING)
     pub fn path_to_repo_subpath(path: &Path) -> Result<String, BackupServiceError> {
+        Ok(Self::encode(path, PathEncoding::from_env()?))
+    }
+
+    /// Repository subpath of a path in the given encoding
+    pub fn encode(path: &Path, encoding: PathEncoding) -> String {
         let path_str = path.to_string_lossy();
+        let parts = |rest: &str| -> Vec<String> {
+            rest.split('/')
+                .filter(|p| !p.is_empty())
+                .map(str::to_string)
+                .collect()
+        };
+        let flatten = |parts: &[String]| {
+            encoding.flatten(&parts.iter().map(String::as_str).collect::<Vec<_>>())
+        };
 
-        let result = if let Some(stripped) = path_str.strip_prefix("/home/") {
-            let parts: Vec<&str> = stripped.split('/').collect();
-            if parts.is_empty() {
-                "user_home".to_string()
-            } else {
-                let username = parts[0];
-                if parts.len() == 1 {
-                    format!("user_home/{}", username)
-                } else {
-                    let subdir = parts[1..].join("_");
-                    format!("user_home/{}/{}", username, subdir)
+        if let Some(stripped) = path_str.strip_prefix("/home/") {
+            match parts(stripped).split_first() {
+                None => "user_home".to_string(),
+                Some((username, [])) => format!("user_home/{}", username),
+                Some((username, subdir)) => {
+                    format!("user_home/{}/{}", username, flatten(subdir))
                 }
             }
-        } else if let Some(stripped) = path_str.strip_prefix("/mnt/docker-data/volumes/") {
-            let volume_path = stripped;
-            if volume_path.is_empty() {
-                "docker_volume".to_string()
-            } else {
-                format!("docker_volume/{}", volume_path.replace('/', "_"))
+        } else if let Some(stripped) = path_str.strip_prefix(DOCKER_VOLUMES_DIR_WITH_SLASH) {
+            match parts(stripped).as_slice() {
+                [] => "docker_volume".to_string(),
+                volume => format!("docker_volume/{}", flatten(volume)),
             }
         } else {
-            let system_path = path_str.trim_start_matches('/');
-            if system_path.is_empty() {
-                "system".to_string()
-            } else {
-                format!("system/{}", system_path.replace('/', "_"))
+            match parts(&path_str).as_slice() {
+                [] => "system".to_string(),
+                system => format!("system/{}", flatten(system)),
             }
+        }
+    }
+
+    /// Native path of a version 2 repository subpath; None for names version 2 cannot
+    /// produce (a version 1 name whose path contained `_` reads differently)
+    pub fn repo_subpath_to_path(subpath: &str) -> Option<PathBuf> {
+        let (category, rest) = subpath.split_once('/')?;
+        let (root, name) = match category {
+            "user_home" => {
+                let (user, name) = rest.split_once('/')?;
+                (Path::new("/home").join(user), name)
+            }
+            "docker_volume" => (PathBuf::from(DOCKER_VOLUMES_DIR), rest),
+            "system" => (PathBuf::from("/"), rest),
+            _ => return None,
         };
+        Some(
+            unflatten_escaped(name)?
+                .iter()
+                .fold(root, |path, c| path.join(c)),
+        )
+    }
+
+    /// Subpaths the repository of `path` may live under: the configured encoding first,
+    /// then the other one when it differs (repositories created before a switch)
+    pub fn candidate_subpaths(path: &Path) -> Result<Vec<String>, BackupServiceError> {
+        let preferred = PathEncoding::from_env()?;
+        let mut candidates = vec![Self::encode(path, preferred)];
+        for encoding in [PathEncoding::Escaped, PathEncoding::Legacy] {
+            let subpath = Self::encode(path, encoding);
+            if !candidates.contains(&subpath) {
+                candidates.push(subpath);
+            }
+        }
+        Ok(candidates)
+    }
+
+    /// Paths that flatten to the repo subpath of an earlier path in the list
+    ///
+    /// Returns `(earlier, colliding, subpath)`; the earlier path keeps the repository.
+    pub fn find_collisions(
+        paths: &[PathBuf],
+    ) -> Result<Vec<(PathBuf, PathBuf, String)>, BackupServiceError> {
+        Ok(Self::find_collisions_with(paths, PathEncoding::from_env()?))
+    }
+
+    fn find_collisions_with(
+        paths: &[PathBuf],
+        encoding: PathEncoding,
+    ) -> Vec<(PathBuf, PathBuf, String)> {
+        let mut owners: HashMap<String, &PathBuf> = HashMap::new();
+        let mut collisions = Vec::new();
+        for path in paths {
+            let subpath = Self::encode(path, encoding);
+            match owners.get(&subpath) {
+                Some(owner) if *owner != path => {
+                    collisions.push(((*owner).clone(), path.clone(), subpath));
+                }
+                Some(_) => {}
+                None => {
+                    owners.insert(subpath, path);
+                }
+            }
+        }
+        collisions
+    }
 
-        Ok(result)
+    /// Error refusing to back up `path` into the repository `owner` maps to
+    pub fn collision_error(path: &Path, owner: &Path, subpath: &str) -> BackupServiceError {
+        BackupServiceError::ConfigurationError(format!(
+            "{} maps to repository '{}', which also holds snapshots of {}.\n\n\
+            Backing it up would mix both paths' snapshots in one repository.\n\
+            Rename one of the directories, or back up their common parent instead",
+            path.display(),
+            subpath,
+            owner.display()
+        ))
     }
 }
 
@@ -157,6 +555,68 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_flattening_collisions_are_detected() -> Result<(), BackupServiceError> {
+        let paths = vec![
+            PathBuf::from("/home/u/a_b"),
+            PathBuf::from("/home/u/other"),
+            PathBuf::from("/home/u/a/b"),
+            PathBuf::from("/home/u/a_b"),
+        ];
+        let collisions = PathMapper::find_collisions_with(&paths, PathEncoding::Legacy);
+        assert_eq!(
+            collisions,
+            vec![(
+                PathBuf::from("/home/u/a_b"),
+                PathBuf::from("/home/u/a/b"),
+                "user_home/u/a_b".to_string()
+            )]
+        );
+        // Version 2 names keep them apart
+        assert!(PathMapper::find_collisions_with(&paths, PathEncoding::Escaped).is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_escaped_encoding_round_trip() -> Result<(), BackupServiceError> {
+        let cases = [
+            ("/var/log_app", "system/var_log~5Fapp"),
+            ("/var/log/app", "system/var_log_app"),
+            ("/home/tim/100%_done/x", "user_home/tim/100~25~5Fdone_x"),
+            ("/srv/~old/a~5F", "system/srv_~7Eold_a~7E5F"),
+            (
+                "/mnt/docker-data/volumes/project_db_data",
+                "docker_volume/project~5Fdb~5Fdata",
+            ),
+        ];
+        for (native, subpath) in cases {
+            assert_eq!(
+                PathMapper::encode(Path::new(native), PathEncoding::Escaped),
+                subpath
+            );
+            assert_eq!(
+                PathMapper::repo_subpath_to_path(subpath),
+                Some(PathBuf::from(native))
+            );
+        }
+        // Paths without `_` or `%` keep their version 1 name
+        let plain = Path::new("/home/user/.local/share/My Documents");
+        assert_eq!(
+            PathMapper::encode(plain, PathEncoding::Escaped),
+            PathMapper::encode(plain, PathEncoding::Legacy)
+        );
+        // Only the legacy name of a path with `_` differs, and it is the fallback candidate
+        assert_eq!(
+            PathMapper::encode(Path::new("/var/log_app"), PathEncoding::Legacy),
+            "system/var_log_app"
+        );
+        assert_eq!(PathMapper::repo_subpath_to_path("system/a~zz"), None);
+        assert_eq!(PathMapper::repo_subpath_to_path("system/a__b"), None);
+        assert_eq!(PathEncoding::parse("1")?, PathEncoding::Legacy);
+        assert!(PathEncoding::parse("3").is_err());
+        Ok(())
+    }
+
     #[test]
     fn test_validate_and_filter_paths_logic() -> Result<(), BackupServiceError> {
         let test_paths = vec![
@@ -164,8 +624,97 @@ mod tests {
             PathBuf::from("/nonexistent/path2"),
         ];
 
-        let result = PathUtilities::validate_and_filter_paths(test_paths)?;
+        let result =
+            PathUtilities::validate_and_filter_paths(test_paths, OverlapPolicy::Warn, &[])?;
         assert_eq!(result.len(), 0); // All paths should be filtered out
         Ok(())
     }
+
+    #[test]
+    fn test_overlapping_paths() -> Result<(), BackupServiceError> {
+        let root = tempfile::tempdir()?;
+        for dir in ["tim/Projects/app", "tim/.ssh", "timo"] {
+            std::fs::create_dir_all(root.path().join(dir))?;
+        }
+        let path = |p: &str| root.path().join(p);
+        let paths = vec![
+            path("tim"),
+            path("tim/Projects"),
+            path("tim/Projects/app"),
+            path("tim/.ssh"),
+            path("timo"),
+            path("tim"),
+        ];
+        let separate = [path("tim/.ssh")];
+
+        // The closest parent is named; `timo` is not below `tim`
+        assert_eq!(
+            find_overlaps(&paths[..5], &separate),
+            [
+                (path("tim/Projects"), path("tim")),
+                (path("tim/Projects/app"), path("tim/Projects")),
+            ]
+        );
+        let kept = PathUtilities::validate_and_filter_paths(
+            paths.clone(),
+            OverlapPolicy::SkipNested,
+            &separate,
+        )?;
+        assert_eq!(kept, [path("tim"), path("tim/.ssh"), path("timo")]);
+        let kept = PathUtilities::validate_and_filter_paths(paths, OverlapPolicy::Keep, &separate)?;
+        assert_eq!(kept.len(), 5);
+
+        assert_eq!(
+            OverlapPolicy::parse("Skip-Nested")?,
+            OverlapPolicy::SkipNested
+        );
+        assert!(OverlapPolicy::parse("merge").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_backup_path_expansion() -> Result<(), BackupServiceError> {
+        let root = tempfile::tempdir()?;
+        for dir in [
+            "alice/Documents",
+            "bob/Documents",
+            "carol",
+            ".snapshots/Documents",
+        ] {
+            std::fs::create_dir_all(root.path().join(dir))?;
+        }
+        std::fs::create_dir(root.path().join("lost+found"))?;
+        std::os::unix::fs::symlink(root.path().join("alice"), root.path().join("alias"))?;
+
+        let pattern = root.path().join("*/Documents");
+        assert_eq!(
+            expand_backup_path(&pattern, None),
+            [
+                root.path().join("alias/Documents"),
+                root.path().join("alice/Documents"),
+                root.path().join("bob/Documents"),
+            ]
+        );
+        assert!(expand_backup_path(&root.path().join("*/Music"), None).is_empty());
+        // Without a glob the entry stays as it is, even when missing
+        let missing = root.path().join("dave");
+        assert_eq!(expand_backup_path(&missing, None), [missing]);
+
+        let home = Path::new("/root");
+        assert_eq!(
+            expand_backup_path(Path::new("~/Projects"), Some(home)),
+            [PathBuf::from("/root/Projects")]
+        );
+        assert_eq!(
+            expand_backup_path(Path::new("~tim/Projects"), None),
+            [PathBuf::from("/home/tim/Projects")]
+        );
+        assert!(expand_backup_path(Path::new("~"), None).is_empty());
+
+        assert_eq!(
+            discover_home_dirs(root.path(), &["c*".to_string()]),
+            [root.path().join("alice"), root.path().join("bob")]
+        );
+        Ok(())
+    }
 }
@@ -0,0 +1,322 @@
+use crate::config::{Config, RepoBackend};
+use crate::errors::BackupServiceError;
+use crate::shared::display::DisplayFormatter;
+use crate::shared::s3::S3Client;
+use serde::Serialize;
+use serde_json::json;
+use std::collections::BTreeMap;
+use tracing::{error, info, warn};
+
+/// S3 capability a restic operation may need
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Permission {
+    List,
+    Get,
+    Put,
+    /// Delete below `*/locks/*` (restic removes its own lock files)
+    DeleteLocks,
+    /// Delete anywhere else (data, index, snapshots): only forget/prune need this
+    DeleteData,
+}
+
+impl Permission {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Permission::List => "list",
+            Permission::Get => "get",
+            Permission::Put => "put",
+            Permission::DeleteLocks => "delete (locks)",
+            Permission::DeleteData => "delete (data)",
+        }
+    }
+}
+
+/// Which operations a key is meant for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyProfile {
+    /// Backup hosts: run, list, restore; should not be able to destroy data
+    AppendOnly,
+    /// Maintenance keys that also forget and prune
+    Admin,
+}
+
+impl KeyProfile {
+    pub fn parse(value: &str) -> Result<Self, BackupServiceError> {
+        match value.trim().to_lowercase().as_str() {
+            "append-only" | "append_only" | "backup" => Ok(KeyProfile::AppendOnly),
+            "admin" | "full" | "prune" => Ok(KeyProfile::Admin),
+            other => Err(BackupServiceError::ConfigurationError(format!(
+                "Unknown permission profile: {}.\n\nValid profiles are: append-only, admin",
+                other
+            ))),
+        }
+    }
+
+    fn operations(&self) -> &'static [&'static str] {
+        match self {
+            KeyProfile::AppendOnly => &["run", "list", "restore", "check"],
+            KeyProfile::Admin => &["run", "list", "restore", "check", "prune"],
+        }
+    }
+}
+
+/// Permissions each command needs (restic takes a lock for every repository access)
+pub fn required_permissions(operation: &str) -> &'static [Permission] {
+    use Permission::*;
+    match operation {
+        "run" => &[List, Get, Put, DeleteLocks],
+        "list" | "restore" | "check" => &[List, Get, Put, DeleteLocks],
+        "prune" => &[List, Get, Put, DeleteLocks, DeleteData],
+        _ => &[],
+    }
+}
+
+/// Probe outcome: Some(granted) or None when it could not be determined
+pub type ProbeResults = BTreeMap<Permission, Option<bool>>;
+
+/// Comparison of probed permissions with a key profile
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PermissionReport {
+    /// Operation -> permissions it needs but the key lacks
+    pub missing: BTreeMap<String, Vec<Permission>>,
+    /// Granted permissions no operation of the profile needs
+    pub excess: Vec<Permission>,
+    pub undetermined: Vec<Permission>,
+}
+
+impl PermissionReport {
+    pub fn is_ok(&self) -> bool {
+        self.missing.is_empty() && self.excess.is_empty()
+    }
+}
+
+pub fn assess(probes: &ProbeResults, profile: KeyProfile) -> PermissionReport {
+    let mut missing = BTreeMap::new();
+    for operation in profile.operations() {
+        let lacking: Vec<Permission> = required_permissions(operation)
+            .iter()
+            .copied()
+            .filter(|p| probes.get(p).copied().flatten() == Some(false))
+            .collect();
+        if !lacking.is_empty() {
+            missing.insert(operation.to_string(), lacking);
+        }
+    }
+
+    let needed: Vec<Permission> = profile
+        .operations()
+        .iter()
+        .flat_map(|op| required_permissions(op).iter().copied())
+        .collect();
+    let excess = probes
+        .iter()
+        .filter(|(p, granted)| **granted == Some(true) && !needed.contains(p))
+        .map(|(p, _)| *p)
+        .collect();
+    let undetermined = probes
+        .iter()
+        .filter(|(_, granted)| granted.is_none())
+        .map(|(p, _)| *p)
+        .collect();
+
+    PermissionReport {
+        missing,
+        excess,
+        undetermined,
+    }
+}
+
+/// Map a probe call to granted / denied, passing through unrelated failures
+fn granted(result: Result<(), BackupServiceError>) -> Result<bool, BackupServiceError> {
+    match result {
+        Ok(()) => Ok(true),
+        Err(BackupServiceError::AuthenticationFailed) => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+/// Probe list/get/put/delete with throwaway objects under `<base>/.permission-probe/`
+async fn probe_permissions(config: &Config) -> Result<ProbeResults, BackupServiceError> {
+    let s3 = S3Client::new(config.clone())?;
+    let base = config.s3_base_path()?;
+    let base = if base.is_empty() {
+        String::new()
+    } else {
+        format!("{}/", base.trim_end_matches('/'))
+    };
+    let id = format!("{}-{}", config.hostname, std::process::id());
+    let data_key = format!("{}.permission-probe/data/{}", base, id);
+    let lock_key = format!("{}.permission-probe/locks/{}", base, id);
+    let body = b"restic-backup-service permission probe\n";
+
+    let mut probes = ProbeResults::new();
+
+    let listed = match s3.first_key(&base).await {
+        Ok(key) => {
+            probes.insert(Permission::List, Some(true));
+            key
+        }
+        Err(BackupServiceError::AuthenticationFailed) => {
+            probes.insert(Permission::List, Some(false));
+            None
+        }
+        Err(e) => return Err(e),
+    };
+
+    let put = granted(s3.put_object(&data_key, body).await)?;
+    probes.insert(Permission::Put, Some(put));
+
+    // Read back our probe, or fall back to any existing object
+    let readable = if put { Some(data_key.clone()) } else { listed };
+    let get = match readable {
+        Some(key) => Some(granted(s3.get_object_head_byte(&key).await)?),
+        None => None,
+    };
+    probes.insert(Permission::Get, get);
+
+    // S3 checks permissions before existence, so deletes are meaningful even without a put
+    let delete_data = granted(s3.delete_object(&data_key).await)?;
+    probes.insert(Permission::DeleteData, Some(delete_data));
+    if put && !delete_data {
+        warn!(key = %data_key, "Probe object could not be removed; delete it with an admin key");
+    }
+
+    let lock_put = granted(s3.put_object(&lock_key, body).await)?;
+    let delete_locks = granted(s3.delete_object(&lock_key).await)?;
+    probes.insert(Permission::DeleteLocks, Some(delete_locks));
+    if lock_put && !delete_locks {
+        warn!(key = %lock_key, "Probe lock could not be removed; delete it with an admin key");
+    }
+
+    Ok(probes)
+}
+
+/// `permissions check`: probe the configured key and compare with what the profile needs
+pub async fn execute_permissions_check(
+    config: Config,
+    profile: KeyProfile,
+    json_output: bool,
+) -> Result<(), BackupServiceError> {
+    let backend = config.backend()?;
+    if backend != RepoBackend::S3 {
+        return Err(BackupServiceError::ConfigurationError(format!(
+            "permissions check probes S3 keys, but RESTIC_REPO_BASE uses the {} backend.\n\nCheck the ssh user's or the directory's permissions instead",
+            backend.name()
+        )));
+    }
+    config.set_aws_env()?;
+    info!(bucket = %config.s3_bucket()?, "Probing effective S3 permissions (writes and deletes probe objects)");
+
+    let probes = probe_permissions(&config).await?;
+    let report = assess(&probes, profile);
+
+    if json_output {
+        let output = json!({
+            "probes": probes
+                .iter()
+                .map(|(p, g)| (p.as_str(), *g))
+                .collect::<BTreeMap<_, _>>(),
+            "report": report,
+        });
+        DisplayFormatter::print_json(&output)?;
+    } else {
+        for (permission, granted) in &probes {
+            let state = match granted {
+                Some(true) => "granted",
+                Some(false) => "denied",
+                None => "unknown",
+            };
+            info!("  {:<16} {}", permission.as_str(), state);
+        }
+        for (operation, lacking) in &report.missing {
+            let names: Vec<&str> = lacking.iter().map(|p| p.as_str()).collect();
+            error!(operation = %operation, missing = %names.join(", "), "Key is under-privileged");
+        }
+        for permission in &report.excess {
+            warn!(
+                permission = %permission.as_str(),
+                "Key is over-privileged: a compromised host could delete backups. Restrict DeleteObject to */locks/* for append-only keys"
+            );
+        }
+        if !report.undetermined.is_empty() {
+            warn!("Some permissions could not be determined (empty bucket and no put access)");
+        }
+        if report.is_ok() {
+            info!("Key permissions match the profile");
+        }
+    }
+
+    if !report.missing.is_empty() {
+        return Err(BackupServiceError::ConfigurationError(
+            "The configured key lacks permissions needed by some operations.\n\nSee the report above"
+                .to_string(),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn probes(granted: &[(Permission, Option<bool>)]) -> ProbeResults {
+        granted.iter().copied().collect()
+    }
+
+    #[test]
+    fn test_append_only_key_with_delete_is_over_privileged() {
+        use Permission::*;
+        let report = assess(
+            &probes(&[
+                (List, Some(true)),
+                (Get, Some(true)),
+                (Put, Some(true)),
+                (DeleteLocks, Some(true)),
+                (DeleteData, Some(true)),
+            ]),
+            KeyProfile::AppendOnly,
+        );
+        assert!(report.missing.is_empty());
+        assert_eq!(report.excess, vec![DeleteData]);
+
+        let admin = assess(
+            &probes(&[
+                (List, Some(true)),
+                (Get, Some(true)),
+                (Put, Some(true)),
+                (DeleteLocks, Some(true)),
+                (DeleteData, Some(true)),
+            ]),
+            KeyProfile::Admin,
+        );
+        assert!(admin.is_ok());
+    }
+
+    #[test]
+    fn test_missing_lock_delete_breaks_every_operation() {
+        use Permission::*;
+        let report = assess(
+            &probes(&[
+                (List, Some(true)),
+                (Get, None),
+                (Put, Some(true)),
+                (DeleteLocks, Some(false)),
+                (DeleteData, Some(false)),
+            ]),
+            KeyProfile::AppendOnly,
+        );
+        assert_eq!(report.missing.len(), 4);
+        assert_eq!(report.missing["run"], vec![DeleteLocks]);
+        assert!(report.excess.is_empty());
+        assert_eq!(report.undetermined, vec![Get]);
+    }
+
+    #[test]
+    fn test_profile_parse() -> Result<(), BackupServiceError> {
+        assert_eq!(KeyProfile::parse("append-only")?, KeyProfile::AppendOnly);
+        assert_eq!(KeyProfile::parse("Admin")?, KeyProfile::Admin);
+        assert!(KeyProfile::parse("root").is_err());
+        Ok(())
+    }
+}
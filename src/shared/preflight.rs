@@ -0,0 +1,134 @@
+use serde::Serialize;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+/// Root entries sampled per path; enough to catch a wrongly-owned tree without walking it
+const DEFAULT_SAMPLE_SIZE: usize = 64;
+
+/// A permission problem found before restic runs
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AccessIssue {
+    pub path: String,
+    pub error: String,
+}
+
+/// Preflight outcome for one backup path
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct PathAccess {
+    /// The path itself cannot be read (restic would back up nothing)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub root_error: Option<String>,
+    /// Sampled entries directly below the path that cannot be opened
+    pub unreadable: Vec<AccessIssue>,
+    /// Number of root entries sampled
+    pub sampled: usize,
+}
+
+impl PathAccess {
+    pub fn is_ok(&self) -> bool {
+        self.root_error.is_none() && self.unreadable.is_empty()
+    }
+
+    pub fn root_unreadable(&self) -> bool {
+        self.root_error.is_some()
+    }
+}
+
+/// Sample size from BACKUP_PREFLIGHT_SAMPLE (0 disables the preflight)
+pub fn sample_size() -> usize {
+    std::env::var("BACKUP_PREFLIGHT_SAMPLE")
+        .ok()
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(DEFAULT_SAMPLE_SIZE)
+}
+
+/// Try to open a file or list a directory, the same access restic needs
+fn probe(path: &Path) -> Result<(), std::io::Error> {
+    let metadata = fs::symlink_metadata(path)?;
+    if metadata.is_dir() {
+        fs::read_dir(path).map(|_| ())
+    } else if metadata.is_file() {
+        fs::File::open(path).map(|_| ())
+    } else {
+        // Symlinks, sockets and devices are stored by restic without being opened
+        Ok(())
+    }
+}
+
+fn describe(error: &std::io::Error) -> String {
+    match error.kind() {
+        ErrorKind::PermissionDenied => "permission denied".to_string(),
+        _ => error.to_string(),
+    }
+}
+
+/// Check that `path` and a sample of its root entries can be read by this process
+pub fn check_path_access(path: &Path, sample: usize) -> PathAccess {
+    let mut access = PathAccess::default();
+    if let Err(e) = probe(path) {
+        access.root_error = Some(describe(&e));
+        return access;
+    }
+
+    let Ok(entries) = fs::read_dir(path) else {
+        return access;
+    };
+    let mut sampled: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .take(sample)
+        .collect();
+    sampled.sort();
+    access.sampled = sampled.len();
+
+    for entry in sampled {
+        if let Err(e) = probe(&entry) {
+            access.unreadable.push(AccessIssue {
+                path: entry.display().to_string(),
+                error: describe(&e),
+            });
+        }
+    }
+    access
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    #[test]
+    fn test_readable_tree_passes() -> Result<(), std::io::Error> {
+        let dir = tempfile::tempdir()?;
+        fs::write(dir.path().join("a.txt"), b"a")?;
+        fs::create_dir(dir.path().join("sub"))?;
+
+        let access = check_path_access(dir.path(), 64);
+        assert!(access.is_ok());
+        assert_eq!(access.sampled, 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_missing_root_is_reported() {
+        let access = check_path_access(Path::new("/nonexistent/preflight/path"), 64);
+        assert!(access.root_unreadable());
+    }
+
+    #[test]
+    fn test_unreadable_entries_are_reported() -> Result<(), std::io::Error> {
+        let dir = tempfile::tempdir()?;
+        let secret = dir.path().join("secret");
+        fs::write(&secret, b"s")?;
+        fs::set_permissions(&secret, fs::Permissions::from_mode(0o000))?;
+
+        let access = check_path_access(dir.path(), 64);
+        // root bypasses file permissions, so only assert when the probe actually fails
+        if fs::File::open(&secret).is_err() {
+            assert_eq!(access.unreadable.len(), 1);
+            assert_eq!(access.unreadable[0].error, "permission denied");
+        }
+        fs::set_permissions(&secret, fs::Permissions::from_mode(0o644))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,651 @@
+use crate::config::Config;
+use crate::errors::BackupServiceError;
+use crate::shared::backup_summary::parse_restic_bytes;
+use crate::shared::commands::ResticCommandExecutor;
+use crate::shared::fleet_workflow::HostGroups;
+use crate::shared::operations::{RepositoryOperations, UnscannedRepository};
+use crate::shared::retention::{
+    CategoryRetention, GroupBy, RepoLayout, RetentionPolicy, TagSelection,
+};
+use crate::shared::retention_rules::{RetentionRules, Rule, RuleSnapshot};
+use crate::shared::scan_cache;
+use crate::shared::ui::{confirm_destructive, ensure_host_not_protected, protected_hosts};
+use crate::utils::{resolve_jobs, validate_credentials};
+use chrono::Local;
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use tracing::{error, info, warn};
+
+/// Repositories pruned at once unless --jobs / PRUNE_JOBS say otherwise
+const DEFAULT_PRUNE_JOBS: usize = 4;
+
+/// Run `forget --prune` on every repository `run` just backed up
+pub const PRUNE_AFTER_BACKUP_ENV_VAR: &str = "PRUNE_AFTER_BACKUP";
+
+pub fn prune_after_backup() -> bool {
+    std::env::var(PRUNE_AFTER_BACKUP_ENV_VAR)
+        .is_ok_and(|v| matches!(v.trim(), "1" | "true" | "yes"))
+}
+
+/// Storage cost model presets for `restic prune` repacking
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrunePreset {
+    /// Cloudflare R2: free egress, billed writes; tolerate more unused space to avoid rewrites
+    R2,
+    /// AWS S3 and similar: billed egress; avoid downloading packs just to repack them
+    S3,
+    /// Local disk / NAS: rewrites are cheap, compact aggressively
+    Local,
+}
+
+impl PrunePreset {
+    pub fn parse(value: &str) -> Result<Self, BackupServiceError> {
+        match value.trim().to_lowercase().as_str() {
+            "r2" => Ok(PrunePreset::R2),
+            "s3" => Ok(PrunePreset::S3),
+            "local" => Ok(PrunePreset::Local),
+            other => Err(BackupServiceError::ConfigurationError(format!(
+                "Unknown prune preset: {}.\n\nValid presets are: r2, s3, local",
+                other
+            ))),
+        }
+    }
+
+    /// Pick a preset from the repository base URL
+    pub fn detect(repo_base: &str) -> Self {
+        if repo_base.contains("r2.cloudflarestorage.com") {
+            PrunePreset::R2
+        } else if repo_base.starts_with("s3:") {
+            PrunePreset::S3
+        } else {
+            PrunePreset::Local
+        }
+    }
+
+    pub fn max_unused(&self) -> &'static str {
+        match self {
+            PrunePreset::R2 => "15%",
+            PrunePreset::S3 => "10%",
+            PrunePreset::Local => "2%",
+        }
+    }
+
+    pub fn repack_cacheable_only(&self) -> bool {
+        matches!(self, PrunePreset::S3)
+    }
+}
+
+/// Resolved `restic prune` tuning flags
+#[derive(Debug, Clone, PartialEq)]
+pub struct PruneTuning {
+    pub max_unused: String,
+    pub repack_cacheable_only: bool,
+}
+
+/// Prune tuning as given on the command line; unset values fall back to env, then preset
+#[derive(Debug, Clone, Default)]
+pub struct PruneOptions {
+    pub preset: Option<String>,
+    pub max_unused: Option<String>,
+    pub repack_cacheable_only: Option<bool>,
+    /// Skip the interactive confirmation (requires `confirm`)
+    pub assume_yes: bool,
+    /// Hostname repeated for non-interactive confirmation
+    pub confirm: Option<String>,
+    /// keep-* rules; when set, snapshots are forgotten before pruning
+    pub retention: RetentionPolicy,
+    /// Override for `forget --group-by` (must include `paths`)
+    pub group_by: Option<String>,
+    /// Client-side retention rules (`--rules`, default RETENTION_RULES); exclusive with keep-*
+    pub rules: Option<String>,
+    /// Only forget among snapshots carrying all of these tags (`--tag`)
+    pub tags: Vec<String>,
+    /// Never forget snapshots carrying any of these tags (`--keep-tag`, plus RETENTION_KEEP_TAGS)
+    pub keep_tags: Vec<String>,
+    /// Show what would be forgotten/pruned without changing the repositories
+    pub dry_run: bool,
+    /// Repositories pruned in parallel (`--jobs`, default PRUNE_JOBS or 4)
+    pub jobs: Option<usize>,
+    /// Every host below the repository base, one after another (PROTECT_HOSTS are skipped)
+    pub all_hosts: bool,
+}
+
+impl PruneTuning {
+    /// Resolve CLI options > PRUNE_* env vars > preset (explicit or detected from repo base)
+    pub fn resolve(options: &PruneOptions, repo_base: &str) -> Result<Self, BackupServiceError> {
+        let env_preset = std::env::var("PRUNE_PRESET")
+            .ok()
+            .filter(|v| !v.trim().is_empty());
+        let preset = match options.preset.as_ref().or(env_preset.as_ref()) {
+            Some(name) => PrunePreset::parse(name)?,
+            None => PrunePreset::detect(repo_base),
+        };
+
+        let max_unused = options
+            .max_unused
+            .clone()
+            .or_else(|| {
+                std::env::var("PRUNE_MAX_UNUSED")
+                    .ok()
+                    .filter(|v| !v.trim().is_empty())
+            })
+            .unwrap_or_else(|| preset.max_unused().to_string());
+
+        let repack_cacheable_only = match options.repack_cacheable_only {
+            Some(value) => value,
+            None => match std::env::var("PRUNE_REPACK_CACHEABLE_ONLY") {
+                Ok(v) if !v.trim().is_empty() => matches!(v.trim(), "1" | "true" | "yes"),
+                _ => preset.repack_cacheable_only(),
+            },
+        };
+
+        Ok(Self {
+            max_unused,
+            repack_cacheable_only,
+        })
+    }
+
+    pub fn to_args(&self) -> Vec<String> {
+        let mut args = vec!["--max-unused".to_string(), self.max_unused.clone()];
+        if self.repack_cacheable_only {
+            args.push("--repack-cacheable-only".to_string());
+        }
+        args
+    }
+}
+
+/// Word typed (or passed to `--confirm`) to prune every host at once
+pub const ALL_HOSTS_CONFIRMATION: &str = "all-hosts";
+
+/// Prune every repository of a host (or, with `all_hosts`, of every host) with the resolved
+/// tuning
+pub async fn execute_prune_workflow(
+    config: Config,
+    host: Option<String>,
+    options: PruneOptions,
+) -> Result<(), BackupServiceError> {
+    if options.all_hosts {
+        return prune_all_hosts(config, options).await;
+    }
+    let hostname = host.unwrap_or_else(|| config.hostname.clone());
+    prune_host(config, hostname, options).await
+}
+
+/// Prune the hosts one after another; a failing host does not stop the others
+async fn prune_all_hosts(config: Config, options: PruneOptions) -> Result<(), BackupServiceError> {
+    config.set_aws_env()?;
+    validate_credentials(&config).await?;
+    let protected = protected_hosts();
+    let (skipped, hosts): (Vec<String>, Vec<String>) = RepositoryOperations::new(config.clone())?
+        .get_available_hosts()
+        .await?
+        .into_iter()
+        .partition(|host| protected.contains(host));
+    for host in &skipped {
+        warn!(host = %host, "Skipping host listed in PROTECT_HOSTS");
+    }
+    if hosts.is_empty() {
+        warn!("No hosts to prune");
+        return Ok(());
+    }
+    // Confirmed once for all hosts rather than once per host
+    if !options.dry_run {
+        confirm_destructive(
+            "prune every host",
+            ALL_HOSTS_CONFIRMATION,
+            options.assume_yes,
+            options.confirm.as_deref(),
+        )?;
+    }
+
+    let mut failed = Vec::new();
+    for (idx, host) in hosts.iter().enumerate() {
+        info!(
+            progress = format!("({}/{})", idx + 1, hosts.len()),
+            host = %host,
+            "Pruning host"
+        );
+        let host_options = PruneOptions {
+            assume_yes: true,
+            confirm: Some(host.clone()),
+            all_hosts: false,
+            ..options.clone()
+        };
+        if let Err(e) = prune_host(config.clone(), host.clone(), host_options).await {
+            error!(host = %host, error = %e, "Prune failed for host");
+            failed.push(host.clone());
+        }
+    }
+    info!(
+        hosts = %hosts.len(),
+        failed = %failed.len(),
+        skipped = %skipped.len(),
+        "Prune of all hosts finished"
+    );
+
+    if !failed.is_empty() {
+        return Err(BackupServiceError::CommandFailed(format!(
+            "Prune failed on {} of {} hosts: {}",
+            failed.len(),
+            hosts.len(),
+            failed.join(", ")
+        )));
+    }
+    Ok(())
+}
+
+async fn prune_host(
+    config: Config,
+    hostname: String,
+    options: PruneOptions,
+) -> Result<(), BackupServiceError> {
+    let tags = TagSelection::resolve(options.tags.clone(), options.keep_tags.clone());
+    let rules = resolve_rules(&options)?.map(|mut rules| {
+        rules.rules.extend(tags.keep.iter().cloned().map(Rule::Tag));
+        rules
+    });
+    let retention = if options.retention.is_empty() && rules.is_none() {
+        configured_retention(&hostname)?
+    } else {
+        CategoryRetention::uniform(options.retention.clone())
+    };
+    let tuning = PruneTuning::resolve(&options, &config.restic_repo_base)?;
+    let mut prune_args = tuning.to_args();
+    if options.dry_run {
+        prune_args.push("--dry-run".to_string());
+    }
+    let group_by = GroupBy::resolve(options.group_by.as_deref(), RepoLayout::from_env()?)?;
+    if !options.dry_run {
+        confirm_destructive(
+            "prune",
+            &hostname,
+            options.assume_yes,
+            options.confirm.as_deref(),
+        )?;
+    }
+
+    config.set_aws_env()?;
+    validate_credentials(&config).await?;
+
+    info!(
+        hostname = %hostname,
+        max_unused = %tuning.max_unused,
+        repack_cacheable_only = %tuning.repack_cacheable_only,
+        retention = %retention.describe(),
+        rules = %rules.as_ref().map(RetentionRules::describe).unwrap_or_default(),
+        group_by = %group_by.as_arg(),
+        tags = %tags.only.join(","),
+        keep_tags = %tags.keep.join(","),
+        dry_run = %options.dry_run,
+        "Starting prune"
+    );
+
+    let operations = RepositoryOperations::new(config.clone())?;
+    let discovery = operations.discover_all_repositories(&hostname).await?;
+    for failure in &discovery.failures {
+        error!(failure = %failure, "Repository discovery incomplete");
+    }
+    let repos = discovery.repos;
+    if repos.is_empty() && discovery.failures.is_empty() {
+        warn!(hostname = %hostname, "No repositories found for host");
+        return Ok(());
+    }
+
+    let jobs =
+        resolve_jobs(options.jobs, "PRUNE_JOBS", DEFAULT_PRUNE_JOBS)?.min(repos.len().max(1));
+    let plan = Arc::new(PrunePlan {
+        config: config.clone(),
+        hostname: hostname.clone(),
+        rules,
+        retention,
+        group_by,
+        tags,
+        prune_args,
+        dry_run: options.dry_run,
+        // Interleaved restic progress from parallel jobs is unreadable
+        live_output: jobs == 1,
+    });
+    info!(repo_count = %repos.len(), jobs = %jobs, "Pruning repositories");
+
+    let permits = Arc::new(Semaphore::new(jobs));
+    let mut tasks = JoinSet::new();
+    for repo in repos.iter().cloned() {
+        let plan = Arc::clone(&plan);
+        let permits = Arc::clone(&permits);
+        tasks.spawn(async move {
+            let _permit = permits.acquire_owned().await;
+            info!(repo_subpath = %repo.repo_subpath, "Pruning repository");
+            let result = plan.prune_repository(&repo).await;
+            PruneOutcome {
+                repo_subpath: repo.repo_subpath,
+                result: result.map_err(|e| e.to_string()),
+            }
+        });
+    }
+
+    let mut outcomes = Vec::with_capacity(repos.len());
+    while let Some(joined) = tasks.join_next().await {
+        let outcome = joined.map_err(|e| {
+            BackupServiceError::CommandFailed(format!("Prune task panicked: {}", e))
+        })?;
+        match &outcome.result {
+            Ok(_) => info!(
+                progress = format!("({}/{})", outcomes.len() + 1, repos.len()),
+                repo_subpath = %outcome.repo_subpath,
+                "Repository pruned"
+            ),
+            Err(e) => error!(repo_subpath = %outcome.repo_subpath, error = %e, "Prune failed"),
+        }
+        outcomes.push(outcome);
+    }
+    outcomes.sort_by(|a, b| a.repo_subpath.cmp(&b.repo_subpath));
+    log_summary(&outcomes, plan.dry_run);
+    if !plan.dry_run {
+        scan_cache::invalidate(&config, &hostname);
+    }
+
+    let failed = outcomes.iter().filter(|o| o.result.is_err()).count();
+    if failed > 0 {
+        return Err(BackupServiceError::CommandFailed(format!(
+            "Prune failed for {} of {} repositories",
+            failed,
+            repos.len()
+        )));
+    }
+    if !discovery.failures.is_empty() {
+        return Err(BackupServiceError::CommandFailed(format!(
+            "Pruned {} repositories, but {} part(s) of the repository tree could not be listed",
+            repos.len(),
+            discovery.failures.len()
+        )));
+    }
+
+    info!(repo_count = %repos.len(), "Prune completed successfully");
+    Ok(())
+}
+
+/// RETENTION_POLICY_<CATEGORY> per repository, else the host group's retention, then
+/// RETENTION_POLICY; used when no keep-* flags or rules are given
+fn configured_retention(hostname: &str) -> Result<CategoryRetention, BackupServiceError> {
+    CategoryRetention::from_env(match HostGroups::from_env()?.retention_for(hostname) {
+        Some(inherited) => inherited.clone(),
+        None => RetentionPolicy::from_env()?,
+    })
+}
+
+/// Space freed by a prune: the `total prune: N blobs / 1.234 MiB` line of restic's report
+pub fn reclaimed_bytes(output: &str) -> Option<u64> {
+    output.lines().find_map(|line| {
+        let (_, size) = line.trim().strip_prefix("total prune:")?.split_once('/')?;
+        let (value, unit) = size.trim().split_once(' ')?;
+        parse_restic_bytes(value, unit.trim())
+    })
+}
+
+/// `forget --prune` after a backup run (PRUNE_AFTER_BACKUP), with the configured keep-* policy
+/// and prune tuning
+///
+/// Client-side RETENTION_RULES are not applied here; they stay with the `prune` command.
+#[derive(Debug, Clone)]
+pub struct PostBackupPrune {
+    retention: CategoryRetention,
+    group_by: GroupBy,
+    tags: TagSelection,
+    prune_args: Vec<String>,
+}
+
+impl PostBackupPrune {
+    /// `None` unless PRUNE_AFTER_BACKUP is set; a protected host or a missing policy is an error
+    pub fn from_env(config: &Config) -> Result<Option<Self>, BackupServiceError> {
+        if !prune_after_backup() {
+            return Ok(None);
+        }
+        ensure_host_not_protected("prune after backup", &config.hostname, &protected_hosts())?;
+        let retention = configured_retention(&config.hostname)?;
+        if retention.describe().is_empty() {
+            return Err(BackupServiceError::ConfigurationError(format!(
+                "{} is set, but no keep-* policy is configured.\n\nSet RETENTION_POLICY, e.g. RETENTION_POLICY=daily=7,weekly=4",
+                PRUNE_AFTER_BACKUP_ENV_VAR
+            )));
+        }
+        let tuning = PruneTuning::resolve(&PruneOptions::default(), &config.restic_repo_base)?;
+        Ok(Some(Self {
+            retention,
+            group_by: GroupBy::resolve(None, RepoLayout::from_env()?)?,
+            tags: TagSelection::resolve(Vec::new(), Vec::new()),
+            prune_args: tuning.to_args(),
+        }))
+    }
+
+    pub fn describe(&self) -> String {
+        self.retention.describe()
+    }
+
+    /// Forget and prune one repository; `None` when its category has no policy (nothing to do)
+    /// or restic's report gave no size
+    pub async fn prune(
+        &self,
+        restic_cmd: &ResticCommandExecutor,
+        category: &str,
+    ) -> Result<Option<u64>, BackupServiceError> {
+        let retention = self.retention.for_category(category);
+        if retention.is_empty() {
+            return Ok(None);
+        }
+        let mut forget_args = retention.forget_args(&self.group_by);
+        forget_args.extend(self.tags.forget_args());
+        let output = restic_cmd
+            .forget_prune(&forget_args, &self.prune_args, false)
+            .await?;
+        Ok(reclaimed_bytes(&output))
+    }
+}
+
+/// Settings shared by every repository's prune job
+struct PrunePlan {
+    config: Config,
+    hostname: String,
+    rules: Option<RetentionRules>,
+    retention: CategoryRetention,
+    group_by: GroupBy,
+    tags: TagSelection,
+    prune_args: Vec<String>,
+    dry_run: bool,
+    live_output: bool,
+}
+
+/// Result of one repository's prune; `Some(n)` counts snapshots forgotten by rules
+struct PruneOutcome {
+    repo_subpath: String,
+    result: Result<Option<usize>, String>,
+}
+
+impl PrunePlan {
+    async fn prune_repository(
+        &self,
+        repo: &UnscannedRepository,
+    ) -> Result<Option<usize>, BackupServiceError> {
+        let repo_url = self
+            .config
+            .get_repo_url_for_host(&self.hostname, &repo.repo_subpath)?;
+        let restic_cmd = ResticCommandExecutor::new(self.config.clone(), repo_url)?;
+        let retention = self.retention.for_category(&repo.category);
+        match &self.rules {
+            Some(rules) => apply_rules(&restic_cmd, rules, self, &repo.repo_subpath)
+                .await
+                .map(Some),
+            None if retention.is_empty() => restic_cmd
+                .prune(&self.prune_args, self.live_output)
+                .await
+                .map(|_| None),
+            None => {
+                let mut forget_args = retention.forget_args(&self.group_by);
+                forget_args.extend(self.tags.forget_args());
+                restic_cmd
+                    .forget_prune(&forget_args, &self.prune_args, self.live_output)
+                    .await
+                    .map(|_| None)
+            }
+        }
+    }
+}
+
+/// End-of-run table: one line per repository, then the totals
+fn log_summary(outcomes: &[PruneOutcome], dry_run: bool) {
+    let verb = if dry_run { "would forget" } else { "forgot" };
+    info!("Prune summary:");
+    for outcome in outcomes {
+        match &outcome.result {
+            Ok(Some(n)) => info!(
+                "  ok      {} ({} {} snapshots)",
+                outcome.repo_subpath, verb, n
+            ),
+            Ok(None) => info!("  ok      {}", outcome.repo_subpath),
+            Err(e) => info!("  FAILED  {}: {}", outcome.repo_subpath, e),
+        }
+    }
+    let failed = outcomes.iter().filter(|o| o.result.is_err()).count();
+    info!(
+        "  {} repositories, {} succeeded, {} failed",
+        outcomes.len(),
+        outcomes.len() - failed,
+        failed
+    );
+}
+
+/// Rules from --rules, or RETENTION_RULES when no keep-* flags were given
+fn resolve_rules(options: &PruneOptions) -> Result<Option<RetentionRules>, BackupServiceError> {
+    if options.rules.is_some() && !options.retention.is_empty() {
+        return Err(BackupServiceError::ConfigurationError(
+            "--rules cannot be combined with --keep-* flags.\n\n\
+            Express the keep-* rules inside --rules instead (e.g. daily=7,first-monthly=all)"
+                .to_string(),
+        ));
+    }
+    let env_rules = std::env::var("RETENTION_RULES")
+        .ok()
+        .filter(|v| !v.trim().is_empty() && options.retention.is_empty());
+    match options.rules.clone().or(env_rules) {
+        Some(spec) => {
+            let rules = RetentionRules::parse(&spec)?;
+            Ok((!rules.is_empty()).then_some(rules))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Evaluate the rules against one repository, forget the rejected snapshots, then prune
+async fn apply_rules(
+    restic_cmd: &ResticCommandExecutor,
+    rules: &RetentionRules,
+    plan: &PrunePlan,
+    repo_subpath: &str,
+) -> Result<usize, BackupServiceError> {
+    let snapshots: Vec<RuleSnapshot> = restic_cmd
+        .snapshots()
+        .await?
+        .iter()
+        .filter_map(|s| RuleSnapshot::from_json(s, &plan.group_by))
+        .filter(|s| plan.tags.considers(&s.tags))
+        .collect();
+    let decision = rules.evaluate(&snapshots, Local::now());
+    info!(
+        repo_subpath = %repo_subpath,
+        keep = %decision.keep.len(),
+        forget = %decision.forget.len(),
+        "Retention rules evaluated"
+    );
+
+    if plan.dry_run {
+        for (id, reasons) in &decision.keep {
+            info!(
+                "  {} keep   {} ({})",
+                repo_subpath,
+                short_id(id),
+                reasons.join(", ")
+            );
+        }
+        for id in &decision.forget {
+            info!("  {} forget {}", repo_subpath, short_id(id));
+        }
+        return Ok(decision.forget.len());
+    }
+
+    if !decision.forget.is_empty() {
+        restic_cmd.forget_ids(&decision.forget).await?;
+    }
+    restic_cmd.prune(&plan.prune_args, plan.live_output).await?;
+    Ok(decision.forget.len())
+}
+
+fn short_id(id: &str) -> &str {
+    id.get(..8).unwrap_or(id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preset_detection() {
+        assert_eq!(
+            PrunePreset::detect("s3:https://abc.r2.cloudflarestorage.com/restic"),
+            PrunePreset::R2
+        );
+        assert_eq!(
+            PrunePreset::detect("s3:https://s3.amazonaws.com/bucket"),
+            PrunePreset::S3
+        );
+        assert_eq!(PrunePreset::detect("/srv/restic"), PrunePreset::Local);
+    }
+
+    #[test]
+    fn test_preset_parse() -> Result<(), BackupServiceError> {
+        assert_eq!(PrunePreset::parse("R2")?, PrunePreset::R2);
+        assert_eq!(PrunePreset::parse("local")?, PrunePreset::Local);
+        assert!(PrunePreset::parse("glacier").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_tuning_cli_overrides_preset() -> Result<(), BackupServiceError> {
+        let options = PruneOptions {
+            preset: Some("s3".to_string()),
+            max_unused: Some("unlimited".to_string()),
+            repack_cacheable_only: Some(false),
+            ..Default::default()
+        };
+        let tuning = PruneTuning::resolve(&options, "s3:https://s3.amazonaws.com/bucket")?;
+        assert_eq!(tuning.max_unused, "unlimited");
+        assert!(!tuning.repack_cacheable_only);
+        assert_eq!(tuning.to_args(), vec!["--max-unused", "unlimited"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_tuning_args_from_preset() {
+        let tuning = PruneTuning {
+            max_unused: PrunePreset::S3.max_unused().to_string(),
+            repack_cacheable_only: PrunePreset::S3.repack_cacheable_only(),
+        };
+        assert_eq!(
+            tuning.to_args(),
+            vec!["--max-unused", "10%", "--repack-cacheable-only"]
+        );
+    }
+
+    #[test]
+    fn test_reclaimed_bytes() {
+        let output = "\
+collecting packs for deletion and repacking
+to repack:           120 blobs / 1.500 MiB
+this removes:         40 blobs / 512.000 KiB
+to delete:            12 blobs / 2.000 MiB
+total prune:          52 blobs / 2.500 MiB
+remaining:          4000 blobs / 1.200 GiB
+";
+        assert_eq!(reclaimed_bytes(output), Some(2_621_440));
+        assert_eq!(reclaimed_bytes("no snapshots were removed"), None);
+    }
+}
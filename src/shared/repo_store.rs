@@ -0,0 +1,311 @@
+use crate::config::{Config, HostBasePaths, RepoBackend};
+use crate::errors::BackupServiceError;
+use crate::shared::commands::{list_remote_directories, remove_remote_directory};
+use crate::shared::s3::S3Client;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+/// How the repository tree is listed on each backend
+enum StoreBackend {
+    S3(S3Client),
+    /// `ssh <target> ls`; restic reaches the same host over sftp
+    Sftp {
+        target: String,
+        port: Option<u16>,
+    },
+    /// Plain readdir below `/`
+    Local,
+}
+
+/// Repository discovery on the backend of RESTIC_REPO_BASE
+///
+/// Paths are relative to the storage root: the bucket on S3, `/` on SFTP and local
+/// backends (see `Config::host_store_path`).
+pub struct RepoStore {
+    config: Config,
+    backend: StoreBackend,
+}
+
+impl RepoStore {
+    pub fn new(config: Config) -> Result<Self, BackupServiceError> {
+        let backend = match config.backend()? {
+            RepoBackend::S3 => StoreBackend::S3(S3Client::new(config.clone())?),
+            RepoBackend::Sftp { target, port, .. } => StoreBackend::Sftp { target, port },
+            RepoBackend::Local { .. } => StoreBackend::Local,
+        };
+        Ok(Self { config, backend })
+    }
+
+    /// Directory names directly below a path; a missing directory lists as empty
+    pub async fn list_directories(&self, path: &str) -> Result<Vec<String>, BackupServiceError> {
+        match &self.backend {
+            StoreBackend::S3(s3) => s3.list_directories(path).await,
+            StoreBackend::Sftp { target, port } => {
+                let mut dirs = list_remote_directories(target, *port, &from_root(path)).await?;
+                dirs.sort();
+                Ok(dirs)
+            }
+            StoreBackend::Local => list_local_directories(Path::new(&from_root(path))),
+        }
+    }
+
+    /// Check that the storage answers with the configured credentials
+    pub async fn validate(&self) -> Result<(), BackupServiceError> {
+        match &self.backend {
+            StoreBackend::S3(s3) => s3.first_key("").await.map(|_| ()),
+            _ => self
+                .list_directories(&self.config.store_base_path()?)
+                .await
+                .map(|_| ()),
+        }
+    }
+
+    /// Objects and bytes below a path; only S3 lists them, other backends report None
+    pub async fn usage(&self, path: &str) -> Result<Option<(usize, u64)>, BackupServiceError> {
+        match &self.backend {
+            StoreBackend::S3(s3) => {
+                let objects = s3.list_objects(&tree_prefix(path)).await?;
+                Ok(Some((
+                    objects.len(),
+                    objects.iter().map(|(_, size)| size).sum(),
+                )))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Remove a path and everything below it; a missing path counts as removed
+    pub async fn delete_tree(&self, path: &str) -> Result<(), BackupServiceError> {
+        if path.trim_matches('/').is_empty() {
+            return Err(BackupServiceError::ConfigurationError(
+                "Refusing to delete the storage root".to_string(),
+            ));
+        }
+        match &self.backend {
+            StoreBackend::S3(s3) => {
+                let keys: Vec<String> = s3
+                    .list_objects(&tree_prefix(path))
+                    .await?
+                    .into_iter()
+                    .map(|(key, _)| key)
+                    .collect();
+                s3.delete_objects(&keys).await
+            }
+            StoreBackend::Sftp { target, port } => {
+                remove_remote_directory(target, *port, &from_root(path)).await
+            }
+            StoreBackend::Local => {
+                // A host's tree can hold millions of pack files; keep the runtime free
+                let path = from_root(path);
+                let removal = {
+                    let path = path.clone();
+                    tokio::task::spawn_blocking(move || std::fs::remove_dir_all(path)).await
+                };
+                match removal {
+                    Err(e) => Err(BackupServiceError::CommandFailed(format!(
+                        "Removing {} failed: {}",
+                        path, e
+                    ))),
+                    Ok(Err(e)) if e.kind() != std::io::ErrorKind::NotFound => {
+                        Err(BackupServiceError::CommandFailed(format!(
+                            "Removing {} failed: {}",
+                            path, e
+                        )))
+                    }
+                    Ok(_) => Ok(()),
+                }
+            }
+        }
+    }
+
+    /// Whether [`Self::copy_tree`] is available: only S3 copies objects server-side
+    pub fn copies_server_side(&self) -> bool {
+        matches!(self.backend, StoreBackend::S3(_))
+    }
+
+    /// Copy every object below `from` to the same relative key below `to` without
+    /// downloading it; returns the objects and bytes copied
+    pub async fn copy_tree(
+        &self,
+        from: &str,
+        to: &str,
+    ) -> Result<(usize, u64), BackupServiceError> {
+        let StoreBackend::S3(s3) = &self.backend else {
+            return Err(BackupServiceError::ConfigurationError(
+                "Server-side copies need an S3 repository base".to_string(),
+            ));
+        };
+        let (from, to) = (tree_prefix(from), tree_prefix(to));
+        let objects = s3.list_objects(&from).await?;
+        let mut bytes = 0;
+        for (key, size) in &objects {
+            if let Some(target) = relocate_key(key, &from, &to) {
+                s3.copy_object(key, &target).await?;
+                bytes += size;
+            }
+        }
+        Ok((objects.len(), bytes))
+    }
+
+    /// Hosts that have a directory below the repository base
+    pub async fn get_hosts(&self) -> Result<Vec<String>, BackupServiceError> {
+        let config = &self.config;
+        let base_path = config.store_base_path()?;
+        let base_paths = HostBasePaths::from_env()?;
+        // Directories holding mapped hosts (e.g. `legacy`) are not hosts themselves
+        let containers: BTreeSet<&str> = base_paths
+            .iter()
+            .filter(|(_, prefix)| !prefix.starts_with('/'))
+            .filter_map(|(_, prefix)| prefix.split('/').next())
+            .collect();
+        // Dot-prefixed entries are tool metadata (e.g. `.permission-probe`), not hosts
+        let mut hosts: Vec<String> = self
+            .list_directories(&base_path)
+            .await?
+            .into_iter()
+            .filter(|h| !h.starts_with('.') && !containers.contains(h.as_str()))
+            .collect();
+
+        // Mapped hosts are listed when their directory exists below their prefix
+        let mut listings: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for (host, _) in base_paths.iter() {
+            let host_path = config.host_store_path_with(&base_paths, host)?;
+            let parent = host_path
+                .rsplit_once('/')
+                .map(|(parent, _)| parent.to_string())
+                .unwrap_or_default();
+            if !listings.contains_key(&parent) {
+                let dirs = self.list_directories(&parent).await?;
+                listings.insert(parent.clone(), dirs);
+            }
+            if listings[&parent].iter().any(|d| d == host) && !hosts.iter().any(|h| h == host) {
+                hosts.push(host.to_string());
+            }
+        }
+        hosts.sort();
+        Ok(hosts)
+    }
+}
+
+/// Filesystem path of a store path on SFTP and local backends
+fn from_root(path: &str) -> String {
+    format!("/{}", path.trim_matches('/'))
+}
+
+/// Key prefix of everything below a path, so `web1` does not match `web10`
+fn tree_prefix(path: &str) -> String {
+    format!("{}/", path.trim_matches('/'))
+}
+
+/// `key` moved from one tree prefix to another
+fn relocate_key(key: &str, from_prefix: &str, to_prefix: &str) -> Option<String> {
+    key.strip_prefix(from_prefix)
+        .map(|rest| format!("{}{}", to_prefix, rest))
+}
+
+fn list_local_directories(path: &Path) -> Result<Vec<String>, BackupServiceError> {
+    let entries = match std::fs::read_dir(path) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => {
+            return Err(BackupServiceError::CommandFailed(format!(
+                "Listing {} failed: {}",
+                path.display(),
+                e
+            )));
+        }
+    };
+    let mut dirs: Vec<String> = entries
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_ok_and(|t| t.is_dir()))
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .collect();
+    dirs.sort();
+    Ok(dirs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_local_listing() -> Result<(), BackupServiceError> {
+        let root = tempfile::tempdir()?;
+        for dir in ["web1", "nas", ".permission-probe"] {
+            std::fs::create_dir(root.path().join(dir))?;
+        }
+        std::fs::write(root.path().join("config"), "")?;
+
+        assert_eq!(
+            list_local_directories(root.path())?,
+            vec![".permission-probe", "nas", "web1"]
+        );
+        assert!(list_local_directories(&root.path().join("missing"))?.is_empty());
+        assert_eq!(from_root("srv/restic/web1"), "/srv/restic/web1");
+        assert_eq!(tree_prefix("restic/web1/"), "restic/web1/");
+        assert_eq!(
+            relocate_key(
+                "restic/web1/etc/data/3f/3f9a",
+                "restic/web1/",
+                "restic/web2/"
+            )
+            .as_deref(),
+            Some("restic/web2/etc/data/3f/3f9a")
+        );
+        assert_eq!(
+            relocate_key("restic/web10/config", "restic/web1/", "x/"),
+            None
+        );
+        Ok(())
+    }
+
+    /// Config whose repository base is a local directory
+    fn local_config(base: &Path) -> Config {
+        Config {
+            restic_password: "test".to_string(),
+            restic_repo_base: base.display().to_string(),
+            aws_access_key_id: String::new(),
+            aws_secret_access_key: String::new(),
+            aws_default_region: String::new(),
+            aws_s3_endpoint: String::new(),
+            backup_paths: vec![],
+            hostname: "web1".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_local_delete_tree() -> Result<(), BackupServiceError> {
+        let root = tempfile::tempdir()?;
+        for dir in ["web1/user_home/tim/data", "web10/system/etc/data"] {
+            std::fs::create_dir_all(root.path().join(dir))?;
+        }
+        std::fs::write(root.path().join("web1/user_home/tim/config"), "")?;
+        let config = local_config(root.path());
+        let store = RepoStore::new(config.clone())?;
+
+        store.delete_tree(&config.host_store_path("web1")?).await?;
+        assert!(!root.path().join("web1").exists());
+        // The sibling whose name starts with the same characters is left alone
+        assert!(root.path().join("web10/system/etc/data").is_dir());
+        assert_eq!(store.get_hosts().await?, ["web10"]);
+
+        // Deleting again finds nothing and still succeeds
+        store.delete_tree(&config.host_store_path("web1")?).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_delete_tree_refuses_the_storage_root() -> Result<(), BackupServiceError> {
+        let root = tempfile::tempdir()?;
+        let store = RepoStore::new(local_config(root.path()))?;
+        for path in ["", "/", "//"] {
+            let err = store.delete_tree(path).await.unwrap_err();
+            assert!(
+                err.to_string()
+                    .contains("Refusing to delete the storage root")
+            );
+        }
+        assert!(root.path().is_dir());
+        Ok(())
+    }
+}
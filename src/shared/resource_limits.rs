@@ -0,0 +1,208 @@
+use crate::errors::BackupServiceError;
+use crate::utils::parse_size;
+use std::process::Command;
+
+/// Prefix of the limit env vars: `RESTIC_SCOPE_<LIMIT>` applies to every restic call,
+/// `RESTIC_SCOPE_<PROFILE>_<LIMIT>` overrides it for one profile
+const ENV_PREFIX: &str = "RESTIC_SCOPE";
+
+/// Limits and the systemd property each one maps to
+const CPU_QUOTA: &str = "CPU_QUOTA";
+const IO_WEIGHT: &str = "IO_WEIGHT";
+const MEMORY_MAX: &str = "MEMORY_MAX";
+
+/// CPU/IO/memory limits for restic child processes, applied via a transient systemd scope
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ResourceLimits {
+    /// Percent of one CPU (200 = two full cores)
+    pub cpu_quota: Option<u32>,
+    /// Relative IO weight, 1-10000 (systemd default 100)
+    pub io_weight: Option<u32>,
+    /// Hard memory limit in bytes; restic is killed when it exceeds it
+    pub memory_max: Option<u64>,
+}
+
+impl ResourceLimits {
+    /// Limit profile of a restic subcommand; other subcommands only get the global limits
+    pub fn profile_for(subcommand: &str) -> Option<&'static str> {
+        match subcommand {
+            "backup" => Some("backup"),
+            "restore" => Some("restore"),
+            "prune" | "forget" => Some("prune"),
+            _ => None,
+        }
+    }
+
+    /// Limits for `profile` from the environment
+    pub fn from_env(profile: Option<&str>) -> Result<Self, BackupServiceError> {
+        Self::from_lookup(profile, |name| std::env::var(name).ok())
+    }
+
+    fn from_lookup(
+        profile: Option<&str>,
+        lookup: impl Fn(&str) -> Option<String>,
+    ) -> Result<Self, BackupServiceError> {
+        // The profile-specific value wins over the global one; "none" clears it for the profile
+        let value = |limit: &str| {
+            profile
+                .and_then(|p| lookup(&format!("{}_{}_{}", ENV_PREFIX, p.to_uppercase(), limit)))
+                .or_else(|| lookup(&format!("{}_{}", ENV_PREFIX, limit)))
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty() && v != "none")
+        };
+
+        let cpu_quota = value(CPU_QUOTA)
+            .map(|v| match v.trim_end_matches('%').parse::<u32>() {
+                Ok(percent) if percent > 0 => Ok(percent),
+                _ => Err(invalid_limit(CPU_QUOTA, &v, "a percentage such as 50%")),
+            })
+            .transpose()?;
+        let io_weight = value(IO_WEIGHT)
+            .map(|v| match v.parse::<u32>() {
+                Ok(weight) if (1..=10000).contains(&weight) => Ok(weight),
+                _ => Err(invalid_limit(IO_WEIGHT, &v, "a weight between 1 and 10000")),
+            })
+            .transpose()?;
+        let memory_max = value(MEMORY_MAX)
+            .map(|v| parse_size(&v).map_err(|_| invalid_limit(MEMORY_MAX, &v, "a size such as 2G")))
+            .transpose()?;
+
+        Ok(Self {
+            cpu_quota,
+            io_weight,
+            memory_max,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cpu_quota.is_none() && self.io_weight.is_none() && self.memory_max.is_none()
+    }
+
+    /// `systemd-run -p` properties for these limits
+    pub fn systemd_properties(&self) -> Vec<String> {
+        let mut properties = Vec::new();
+        if let Some(percent) = self.cpu_quota {
+            properties.push(format!("CPUQuota={}%", percent));
+        }
+        if let Some(weight) = self.io_weight {
+            properties.push(format!("IOWeight={}", weight));
+        }
+        if let Some(bytes) = self.memory_max {
+            properties.push(format!("MemoryMax={}", bytes));
+        }
+        properties
+    }
+
+    /// `program`, wrapped in `systemd-run --scope` when any limit is set
+    pub fn command(&self, program: &str) -> Command {
+        if self.is_empty() {
+            return Command::new(program);
+        }
+        let mut command = Command::new("systemd-run");
+        command.args(["--scope", "--quiet", "--collect"]);
+        for property in self.systemd_properties() {
+            command.args(["-p", &property]);
+        }
+        command.args(["--", program]);
+        command
+    }
+
+    /// Error for a command that could not be spawned under these limits
+    pub fn spawn_error(&self, error: std::io::Error) -> BackupServiceError {
+        if self.is_empty() || error.kind() != std::io::ErrorKind::NotFound {
+            return BackupServiceError::restic_command_failed();
+        }
+        BackupServiceError::ConfigurationError(format!(
+            "Cannot start restic in a systemd scope: {}.\n\n\
+            {}_* limits need systemd-run (and root or a user manager); unset them to run restic directly",
+            error, ENV_PREFIX
+        ))
+    }
+}
+
+fn invalid_limit(limit: &str, value: &str, expected: &str) -> BackupServiceError {
+    BackupServiceError::ConfigurationError(format!(
+        "Invalid {} limit: {}.\n\nExpected {} in {}_{} or {}_<PROFILE>_{}",
+        limit, value, expected, ENV_PREFIX, limit, ENV_PREFIX, limit
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn lookup(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        move |name| vars.get(name).cloned()
+    }
+
+    #[test]
+    fn test_profile_overrides_global_limits() -> Result<(), BackupServiceError> {
+        let vars = lookup(&[
+            ("RESTIC_SCOPE_CPU_QUOTA", "50%"),
+            ("RESTIC_SCOPE_IO_WEIGHT", "20"),
+            ("RESTIC_SCOPE_BACKUP_CPU_QUOTA", "25"),
+            ("RESTIC_SCOPE_BACKUP_MEMORY_MAX", "2G"),
+            ("RESTIC_SCOPE_RESTORE_IO_WEIGHT", "none"),
+        ]);
+
+        let backup = ResourceLimits::from_lookup(Some("backup"), &vars)?;
+        assert_eq!(
+            backup.systemd_properties(),
+            vec!["CPUQuota=25%", "IOWeight=20", "MemoryMax=2147483648"]
+        );
+
+        // "none" lifts a global limit for one profile
+        let restore = ResourceLimits::from_lookup(Some("restore"), &vars)?;
+        assert_eq!(restore.io_weight, None);
+        assert_eq!(restore.cpu_quota, Some(50));
+
+        let other = ResourceLimits::from_lookup(None, lookup(&[]))?;
+        assert!(other.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_scope_command_line() -> Result<(), BackupServiceError> {
+        let limits = ResourceLimits::from_lookup(
+            Some("prune"),
+            lookup(&[("RESTIC_SCOPE_PRUNE_IO_WEIGHT", "10")]),
+        )?;
+        let command = limits.command("restic");
+        assert_eq!(command.get_program(), "systemd-run");
+        let args: Vec<_> = command.get_args().collect();
+        assert_eq!(
+            args,
+            vec![
+                "--scope",
+                "--quiet",
+                "--collect",
+                "-p",
+                "IOWeight=10",
+                "--",
+                "restic"
+            ]
+        );
+
+        assert_eq!(
+            ResourceLimits::default().command("restic").get_program(),
+            "restic"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_limits_are_rejected() {
+        for (name, value) in [
+            ("RESTIC_SCOPE_IO_WEIGHT", "0"),
+            ("RESTIC_SCOPE_CPU_QUOTA", "half"),
+            ("RESTIC_SCOPE_MEMORY_MAX", "lots"),
+        ] {
+            assert!(ResourceLimits::from_lookup(None, lookup(&[(name, value)])).is_err());
+        }
+    }
+}
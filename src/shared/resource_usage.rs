@@ -0,0 +1,99 @@
+use serde::Serialize;
+
+/// Cumulative `getrusage(RUSAGE_CHILDREN)` counters of every reaped child (restic, docker, ...)
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ChildUsage {
+    user_secs: f64,
+    system_secs: f64,
+    /// Largest resident set of any single child, in KiB (Linux reports `ru_maxrss` in KiB)
+    max_rss_kib: u64,
+    /// Blocks of 512 bytes read from storage (page-cache hits are not counted)
+    in_blocks: u64,
+}
+
+impl ChildUsage {
+    pub fn now() -> Self {
+        // SAFETY: getrusage only writes into the zero-initialised struct we pass in
+        let usage = unsafe {
+            let mut usage: libc::rusage = std::mem::zeroed();
+            if libc::getrusage(libc::RUSAGE_CHILDREN, &mut usage) != 0 {
+                return Self::default();
+            }
+            usage
+        };
+        let secs = |tv: libc::timeval| tv.tv_sec as f64 + tv.tv_usec as f64 / 1_000_000.0;
+        Self {
+            user_secs: secs(usage.ru_utime),
+            system_secs: secs(usage.ru_stime),
+            max_rss_kib: usage.ru_maxrss.max(0) as u64,
+            in_blocks: usage.ru_inblock.max(0) as u64,
+        }
+    }
+}
+
+/// What the external processes of one run consumed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct ResourceUsage {
+    pub cpu_user_secs: f64,
+    pub cpu_system_secs: f64,
+    /// Peak RSS of the largest child; the kernel keeps one maximum per process, so in daemon
+    /// mode this is the peak since the daemon started
+    pub peak_memory_bytes: u64,
+    pub disk_read_bytes: u64,
+    /// Compressed bytes restic stored in the repositories
+    pub uploaded_bytes: u64,
+}
+
+impl ResourceUsage {
+    /// Difference between two counter readings, plus the bytes the run uploaded
+    pub fn between(start: &ChildUsage, end: &ChildUsage, uploaded_bytes: u64) -> Self {
+        Self {
+            cpu_user_secs: (end.user_secs - start.user_secs).max(0.0),
+            cpu_system_secs: (end.system_secs - start.system_secs).max(0.0),
+            peak_memory_bytes: end.max_rss_kib * 1024,
+            disk_read_bytes: end.in_blocks.saturating_sub(start.in_blocks) * 512,
+            uploaded_bytes,
+        }
+    }
+
+    pub fn cpu_secs(&self) -> f64 {
+        self.cpu_user_secs + self.cpu_system_secs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_usage_between_readings() {
+        let start = ChildUsage {
+            user_secs: 10.0,
+            system_secs: 2.0,
+            max_rss_kib: 100_000,
+            in_blocks: 1_000,
+        };
+        let end = ChildUsage {
+            user_secs: 70.5,
+            system_secs: 6.5,
+            max_rss_kib: 512_000,
+            in_blocks: 3_048,
+        };
+        let usage = ResourceUsage::between(&start, &end, 4096);
+        assert_eq!(usage.cpu_user_secs, 60.5);
+        assert_eq!(usage.cpu_system_secs, 4.5);
+        assert_eq!(usage.cpu_secs(), 65.0);
+        assert_eq!(usage.peak_memory_bytes, 512_000 * 1024);
+        assert_eq!(usage.disk_read_bytes, 2_048 * 512);
+        assert_eq!(usage.uploaded_bytes, 4096);
+    }
+
+    #[test]
+    fn test_child_usage_counts_reaped_children() {
+        let before = ChildUsage::now();
+        std::process::Command::new("true").status().unwrap();
+        let after = ChildUsage::now();
+        assert!(after.user_secs + after.system_secs >= before.user_secs + before.system_secs);
+        assert!(after.max_rss_kib > 0);
+    }
+}
@@ -0,0 +1,201 @@
+use crate::errors::BackupServiceError;
+use crate::shared::digest_workflow::{
+    DIGEST_EMAIL_ENV_VAR, DIGEST_WEBHOOK_ENV_VAR, send_email, send_webhook,
+};
+use crate::shared::logs_workflow::log_dir;
+use crate::shared::timestamps::format_local;
+use chrono::{DateTime, Local, Utc};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tracing::{info, warn};
+
+/// Env var overriding where restore transcripts are written (default: `<RBS_LOG_DIR>/restore-transcripts`)
+pub const TRANSCRIPT_DIR_ENV_VAR: &str = "RESTORE_TRANSCRIPT_DIR";
+/// Env var that, when true, also sends each transcript to REPORT_WEBHOOK_URL / REPORT_EMAIL_TO
+pub const TRANSCRIPT_NOTIFY_ENV_VAR: &str = "RESTORE_TRANSCRIPT_NOTIFY";
+
+pub fn transcript_dir() -> PathBuf {
+    std::env::var(TRANSCRIPT_DIR_ENV_VAR)
+        .ok()
+        .filter(|v| !v.trim().is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| log_dir().join("restore-transcripts"))
+}
+
+/// One line of the record
+#[derive(Debug, Clone, PartialEq)]
+pub struct TranscriptEntry {
+    pub time: DateTime<Utc>,
+    /// Short category: selection, snapshot, action, overwrite, ...
+    pub kind: &'static str,
+    pub detail: String,
+}
+
+/// Record of one interactive restore session, for post-incident review
+///
+/// Workflow phases take `&self`, so entries are collected behind a mutex.
+#[derive(Debug)]
+pub struct RestoreTranscript {
+    started: DateTime<Utc>,
+    operator: String,
+    entries: Mutex<Vec<TranscriptEntry>>,
+}
+
+impl Default for RestoreTranscript {
+    fn default() -> Self {
+        Self::new(Utc::now(), operator())
+    }
+}
+
+/// Who ran the restore: the invoking user behind sudo, else the current user
+fn operator() -> String {
+    ["SUDO_USER", "USER", "LOGNAME"]
+        .iter()
+        .find_map(|key| std::env::var(key).ok().filter(|v| !v.is_empty()))
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+impl RestoreTranscript {
+    pub fn new(started: DateTime<Utc>, operator: String) -> Self {
+        Self {
+            started,
+            operator,
+            entries: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn record(&self, kind: &'static str, detail: impl Into<String>) {
+        self.record_at(Utc::now(), kind, detail);
+    }
+
+    pub fn record_at(&self, time: DateTime<Utc>, kind: &'static str, detail: impl Into<String>) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.push(TranscriptEntry {
+                time,
+                kind,
+                detail: detail.into(),
+            });
+        }
+    }
+
+    pub fn entries(&self) -> Vec<TranscriptEntry> {
+        self.entries.lock().map(|e| e.clone()).unwrap_or_default()
+    }
+
+    /// Plain-text transcript; times are local, like the rest of the restore output
+    pub fn render(&self, outcome: &str) -> String {
+        let mut text = format!(
+            "Restore transcript\nstarted:  {}\noperator: {}\nmachine:  {}\noutcome:  {}\n\n",
+            format_local(self.started),
+            self.operator,
+            hostname::get()
+                .map(|h| h.to_string_lossy().to_string())
+                .unwrap_or_else(|_| "unknown".to_string()),
+            outcome
+        );
+        for entry in self.entries() {
+            text.push_str(&format!(
+                "{}  {:<10} {}\n",
+                format_local(entry.time),
+                entry.kind,
+                entry.detail
+            ));
+        }
+        text
+    }
+
+    /// Write to `dir` as `restore-<local start time>.log`; never overwrites an earlier session
+    pub fn write_to(&self, dir: &Path, outcome: &str) -> Result<PathBuf, BackupServiceError> {
+        fs::create_dir_all(dir)?;
+        let stamp = self.started.with_timezone(&Local).format("%Y%m%d-%H%M%S");
+        let mut path = dir.join(format!("restore-{}.log", stamp));
+        let mut n = 1;
+        while path.exists() {
+            n += 1;
+            path = dir.join(format!("restore-{}-{}.log", stamp, n));
+        }
+        fs::write(&path, self.render(outcome))?;
+        Ok(path)
+    }
+
+    /// Write the transcript and optionally send it; failures here never fail the restore
+    pub async fn finish(&self, outcome: &str) {
+        let text = self.render(outcome);
+        match self.write_to(&transcript_dir(), outcome) {
+            Ok(path) => info!(transcript = %path.display(), "Restore transcript written"),
+            Err(e) => warn!(error = %e, "Could not write restore transcript"),
+        }
+
+        let notify = std::env::var(TRANSCRIPT_NOTIFY_ENV_VAR)
+            .is_ok_and(|v| matches!(v.trim(), "1" | "true" | "yes"));
+        if !notify {
+            return;
+        }
+        let subject = format!(
+            "Restore transcript ({}, {})",
+            self.operator,
+            format_local(self.started)
+        );
+        let webhook = std::env::var(DIGEST_WEBHOOK_ENV_VAR)
+            .ok()
+            .filter(|v| !v.trim().is_empty());
+        let email = std::env::var(DIGEST_EMAIL_ENV_VAR)
+            .ok()
+            .filter(|v| !v.trim().is_empty());
+        if webhook.is_none() && email.is_none() {
+            warn!(
+                "{} is set but neither {} nor {} is configured",
+                TRANSCRIPT_NOTIFY_ENV_VAR, DIGEST_WEBHOOK_ENV_VAR, DIGEST_EMAIL_ENV_VAR
+            );
+        }
+        if let Some(url) = webhook
+            && let Err(e) = send_webhook(&url, &format!("{}\n\n{}", subject, text)).await
+        {
+            warn!(error = %e, "Could not post restore transcript");
+        }
+        if let Some(recipients) = email
+            && let Err(e) = send_email(&recipients, &subject, &text).await
+        {
+            warn!(error = %e, "Could not mail restore transcript");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(value: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(value)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_render_lists_entries_in_order() {
+        let transcript = RestoreTranscript::new(at("2025-01-15T10:00:00Z"), "alice".to_string());
+        transcript.record_at(at("2025-01-15T10:00:05Z"), "host", "web1");
+        transcript.record_at(at("2025-01-15T10:01:00Z"), "overwrite", "/etc/nginx");
+
+        let text = transcript.render("completed");
+        assert!(text.contains("operator: alice\n"));
+        assert!(text.contains("outcome:  completed\n"));
+        let host = text.find("host       web1").unwrap();
+        let overwrite = text.find("overwrite  /etc/nginx").unwrap();
+        assert!(host < overwrite);
+    }
+
+    #[test]
+    fn test_write_never_overwrites_an_earlier_session() -> Result<(), BackupServiceError> {
+        let dir = tempfile::tempdir()?;
+        let transcript = RestoreTranscript::new(at("2025-01-15T10:00:00Z"), "alice".to_string());
+        let first = transcript.write_to(dir.path(), "cancelled")?;
+        let second = transcript.write_to(dir.path(), "completed")?;
+
+        assert_ne!(first, second);
+        assert!(fs::read_to_string(&first)?.contains("outcome:  cancelled"));
+        assert!(fs::read_to_string(&second)?.contains("outcome:  completed"));
+        Ok(())
+    }
+}
@@ -1,23 +1,522 @@
 use crate::config::Config;
 use crate::errors::BackupServiceError;
-use crate::shared::commands::{ResticCommandExecutor, S3CommandExecutor};
-use crate::shared::operations::{RepositoryOperations, RepositorySelectionItem};
+use crate::i18n::{t, t_args};
+use crate::shared::commands::ResticCommandExecutor;
+use crate::shared::container_verify::{
+    ContainerHealth, VERIFY_PROBES_ENV_VAR, parse_probes, verify_restored_volumes,
+};
+use crate::shared::disk_space::{available_bytes, same_filesystem};
+use crate::shared::display::DisplayFormatter;
+use crate::shared::notify::{Notification, Notifiers, Severity};
+use crate::shared::operations::{RepositoryOperations, RepositorySelectionItem, SnapshotItem};
+use crate::shared::path_tags::retain_tagged;
+use crate::shared::repo_store::RepoStore;
+use crate::shared::restore_transcript::RestoreTranscript;
+use crate::shared::shutdown;
+use crate::shared::timestamps::format_local;
 use crate::shared::ui::{
     HostSelection, RepositorySelection, TimestampSelection, confirm_action, select_host,
-    select_repositories, select_timestamp,
+    select_item, select_repositories, select_timestamp,
 };
-use crate::utils::validate_credentials;
-use chrono::{DateTime, Duration, Utc};
+use crate::utils::{format_bytes, parse_size, resolve_jobs, validate_credentials};
+use chrono::{DateTime, Duration, Local, NaiveTime, TimeZone, Utc};
+use serde::Serialize;
+use serde_json::json;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
 use tracing::{error, info, warn};
 
+/// Repositories restored in parallel (`--jobs` overrides it)
+pub const RESTORE_JOBS_ENV_VAR: &str = "RESTORE_JOBS";
+const DEFAULT_RESTORE_JOBS: usize = 4;
+
+/// Optional restore tuning beyond host/path/timestamp selection
+#[derive(Debug, Clone, Default)]
+pub struct RestoreOptions {
+    /// Download bandwidth cap passed to restic as `--limit-download` (KiB/s)
+    pub limit_download: Option<u32>,
+    /// Start time (`HH:MM` local, or RFC 3339) to wait for before restoring
+    pub start_at: Option<String>,
+    /// Only download the selected snapshots into the prefetch staging area
+    pub prefetch: bool,
+    /// After copy/move, start the containers of restored docker volumes and check their health
+    pub verify_containers: bool,
+    /// Never prompt: default host/timestamp, clear the target, apply `action` (`--yes`)
+    pub assume_yes: bool,
+    /// What to do with the restored files (`--action`); prompts when unset unless `assume_yes`
+    pub action: Option<String>,
+    /// Directory restic restores into instead of RESTORE_STAGING_DIR (`--target`)
+    pub target: Option<PathBuf>,
+    /// Delete the contents of a non-empty `target` first (`--wipe-target`); without it such
+    /// a target is refused. The staging directory is the tool's own and always cleared.
+    pub wipe_target: bool,
+    /// Repositories restored in parallel (`--jobs`, default RESTORE_JOBS or 4)
+    pub jobs: Option<usize>,
+    /// Print the result of every repository as JSON at the end; needs `assume_yes`
+    pub json_output: bool,
+    /// Only restore snapshot entries matching these patterns (`--include`, restic syntax)
+    pub include: Vec<String>,
+    /// Leave out snapshot entries matching these patterns (`--exclude`)
+    pub exclude: Vec<String>,
+    /// Only report what each repository would restore and where; nothing is written
+    pub dry_run: bool,
+    /// Rescan the repositories instead of using the scan cache (`--refresh`)
+    pub refresh: bool,
+    /// Only offer snapshots carrying all of these tags (`--tag`)
+    pub tags: Vec<String>,
+    /// Only scan repositories of these categories (`--category`)
+    pub categories: Vec<String>,
+    /// Restore just this file or directory (`--file`, its path in the snapshot)
+    pub file: Option<PathBuf>,
+    /// Where `file` is placed instead of its original path (`--to`)
+    pub file_to: Option<PathBuf>,
+    /// What copy/move do with existing content (`--overwrite`, default replace)
+    pub overwrite: Option<String>,
+}
+
+/// What copy/move do with content already at a destination
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OverwritePolicy {
+    /// Delete it first (the destination ends up exactly as restored)
+    #[default]
+    Replace,
+    /// Keep existing files and only add the missing ones
+    Skip,
+    /// Rename it to `<name>.pre-restore-<timestamp>` next to the restored data
+    KeepBoth,
+}
+
+impl OverwritePolicy {
+    pub fn parse(value: &str) -> Result<Self, BackupServiceError> {
+        match value.trim().to_lowercase().as_str() {
+            "replace" => Ok(OverwritePolicy::Replace),
+            "skip" => Ok(OverwritePolicy::Skip),
+            "keep-both" | "keep_both" => Ok(OverwritePolicy::KeepBoth),
+            other => Err(BackupServiceError::ConfigurationError(format!(
+                "Unknown overwrite policy: {}.\n\nValid policies are: replace, skip, keep-both",
+                other
+            ))),
+        }
+    }
+}
+
+/// Where `--overwrite keep-both` moves existing content: `<name>.pre-restore-<timestamp>`
+fn pre_restore_path<Tz: TimeZone>(dst: &Path, now: DateTime<Tz>) -> PathBuf
+where
+    Tz::Offset: std::fmt::Display,
+{
+    let name = dst
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    dst.with_file_name(format!(
+        "{}.pre-restore-{}",
+        name,
+        now.format("%Y%m%d-%H%M%S")
+    ))
+}
+
+/// What happens to the restored files once they are staged
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PostRestoreAction {
+    Copy,
+    Move,
+    Leave,
+}
+
+impl PostRestoreAction {
+    pub fn parse(value: &str) -> Result<Self, BackupServiceError> {
+        match value.trim().to_lowercase().as_str() {
+            "copy" => Ok(PostRestoreAction::Copy),
+            "move" => Ok(PostRestoreAction::Move),
+            "leave" | "none" => Ok(PostRestoreAction::Leave),
+            other => Err(BackupServiceError::ConfigurationError(format!(
+                "Unknown restore action: {}.\n\nValid actions are: copy, move, leave",
+                other
+            ))),
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            PostRestoreAction::Copy => "copy",
+            PostRestoreAction::Move => "move",
+            PostRestoreAction::Leave => "leave",
+        }
+    }
+
+    /// Index into the prompt's action list (copy, move, leave)
+    fn index(self) -> usize {
+        match self {
+            PostRestoreAction::Copy => 0,
+            PostRestoreAction::Move => 1,
+            PostRestoreAction::Leave => 2,
+        }
+    }
+}
+
+/// Default staging root for `restore --prefetch` (disk-backed, unlike /tmp on many systems)
+const DEFAULT_PREFETCH_DIR: &str = "/var/tmp/restic/prefetch";
+
+/// Staging root from RESTORE_PREFETCH_DIR
+pub fn prefetch_dir() -> PathBuf {
+    std::env::var("RESTORE_PREFETCH_DIR")
+        .ok()
+        .filter(|v| !v.trim().is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_PREFETCH_DIR))
+}
+
+/// Default restore staging directory (files land here before copy/move)
+const DEFAULT_STAGING_DIR: &str = "/tmp/restic/interactive";
+
+/// Staging directory from RESTORE_STAGING_DIR
+pub fn staging_dir() -> PathBuf {
+    std::env::var("RESTORE_STAGING_DIR")
+        .ok()
+        .filter(|v| !v.trim().is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_STAGING_DIR))
+}
+
+/// Staging size cap from RESTORE_STAGING_MAX_SIZE (e.g. 50G); None means unlimited
+pub fn staging_max_size() -> Result<Option<u64>, BackupServiceError> {
+    match std::env::var("RESTORE_STAGING_MAX_SIZE") {
+        Ok(v) if !v.trim().is_empty() => Ok(Some(parse_size(&v)?)),
+        _ => Ok(None),
+    }
+}
+
+/// Group repositories (by index) into consecutive chunks that each fit `cap`
+///
+/// Returns the index of the first repository that alone exceeds the cap.
+pub fn plan_staging_chunks(sizes: &[u64], cap: u64) -> Result<Vec<Vec<usize>>, usize> {
+    let mut chunks: Vec<Vec<usize>> = Vec::new();
+    let mut current: Vec<usize> = Vec::new();
+    let mut current_size = 0;
+    for (idx, &size) in sizes.iter().enumerate() {
+        if size > cap {
+            return Err(idx);
+        }
+        if current_size + size > cap && !current.is_empty() {
+            chunks.push(std::mem::take(&mut current));
+            current_size = 0;
+        }
+        current.push(idx);
+        current_size += size;
+    }
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+    Ok(chunks)
+}
+
+/// Split the selection into chunks fitting RESTORE_STAGING_MAX_SIZE
+///
+/// A repository larger than the cap on its own is refused, since it could never be staged.
+fn plan_chunks(
+    selected_repos: &[RepositorySelectionItem],
+    sizes: &[u64],
+    cap: u64,
+) -> Result<Vec<Vec<usize>>, BackupServiceError> {
+    info!(max_size = %format_bytes(cap)?, "Restore staging size cap");
+    plan_staging_chunks(sizes, cap).map_err(|idx| {
+        let size = format_bytes(sizes[idx]).unwrap_or_else(|_| sizes[idx].to_string());
+        let cap = format_bytes(cap).unwrap_or_else(|_| cap.to_string());
+        BackupServiceError::ConfigurationError(format!(
+            "{} needs {} in the staging directory, more than RESTORE_STAGING_MAX_SIZE ({}).\n\n\
+            Raise the cap or point RESTORE_STAGING_DIR at a larger filesystem",
+            selected_repos[idx].path.display(),
+            size,
+            cap
+        ))
+    })
+}
+
+/// `--include`/`--exclude` patterns as restic flags; `--file` is one more include
+fn filter_args(options: &RestoreOptions) -> Vec<String> {
+    let file = options
+        .file
+        .iter()
+        .map(|f| ("--include", f.to_string_lossy().into_owned()));
+    let include = options.include.iter().map(|p| ("--include", p.clone()));
+    let exclude = options.exclude.iter().map(|p| ("--exclude", p.clone()));
+    file.chain(include)
+        .chain(exclude)
+        .flat_map(|(flag, pattern)| [flag.to_string(), pattern])
+        .collect()
+}
+
+/// Whether only part of each snapshot is restored
+fn is_filtered(options: &RestoreOptions) -> bool {
+    options.file.is_some() || !options.include.is_empty() || !options.exclude.is_empty()
+}
+
+/// Whether restic's own output goes straight to the terminal
+///
+/// Only for a single job: interleaved progress from parallel jobs is unreadable. Never with
+/// `--json`, where stdout carries nothing but the result document.
+fn live_restic_output(options: &RestoreOptions, jobs: usize) -> bool {
+    jobs == 1 && !options.json_output
+}
+
+/// The repository whose backed-up path holds `file` (the deepest one if several do)
+fn file_repository<'a>(
+    repos: &'a [RepositorySelectionItem],
+    file: &Path,
+) -> Option<&'a RepositorySelectionItem> {
+    repos
+        .iter()
+        .filter(|r| file.starts_with(&r.path))
+        .max_by_key(|r| r.path.components().count())
+}
+
+fn filtered_move_error() -> BackupServiceError {
+    BackupServiceError::ConfigurationError(
+        "Moving a filtered restore would replace the original directories with the selected files only.\n\n\
+        Use --action copy --overwrite skip to add the files next to the existing ones, or leave them in the target"
+            .to_string(),
+    )
+}
+
+/// Refuse to start when the largest chunk does not fit on the target's filesystem
+fn check_free_space(
+    dest_dir: &Path,
+    sizes: &[u64],
+    chunks: &[Vec<usize>],
+) -> Result<(), BackupServiceError> {
+    let needed = largest_chunk_size(sizes, chunks);
+    let available = available_bytes(dest_dir)?;
+    if needed > available {
+        return Err(BackupServiceError::ConfigurationError(format!(
+            "{} has {} free, the restore needs {}.\n\n\
+            Free up space, restore elsewhere with --target, or set RESTORE_STAGING_MAX_SIZE to restore in chunks",
+            dest_dir.display(),
+            format_bytes(available)?,
+            format_bytes(needed)?
+        )));
+    }
+    info!(
+        target = %dest_dir.display(),
+        needed = %format_bytes(needed)?,
+        available = %format_bytes(available)?,
+        "Free space checked"
+    );
+    Ok(())
+}
+
+/// Bytes staged at once: the whole selection, or its largest chunk
+fn largest_chunk_size(sizes: &[u64], chunks: &[Vec<usize>]) -> u64 {
+    chunks
+        .iter()
+        .map(|chunk| chunk.iter().map(|&i| sizes[i]).sum())
+        .max()
+        .unwrap_or(0)
+}
+
+/// Where one snapshot of a host is staged; complete once the marker exists
+fn prefetch_staging(root: &Path, host: &str, snapshot_id: &str) -> (PathBuf, PathBuf) {
+    let staging = root.join(host).join(snapshot_id);
+    let marker = root.join(host).join(format!("{}.complete", snapshot_id));
+    (staging, marker)
+}
+
+/// Snapshot to restore for a chosen time: the latest in the 5-minute window, else the closest before it
+pub fn select_snapshot<'a>(
+    repo: &'a RepositorySelectionItem,
+    selected_timestamp: &DateTime<Utc>,
+) -> Option<&'a SnapshotItem> {
+    let window_end = *selected_timestamp + Duration::minutes(5);
+    repo.snapshots
+        .iter()
+        .filter(|s| s.time >= *selected_timestamp && s.time < window_end)
+        .max_by_key(|s| s.time)
+        .or_else(|| {
+            repo.snapshots
+                .iter()
+                .filter(|s| s.time < *selected_timestamp)
+                .max_by_key(|s| s.time)
+        })
+}
+
+/// How the restore of one repository ended
+#[derive(Debug, Clone, PartialEq)]
+enum RepoRestoreStatus {
+    Restored,
+    /// Snapshot of an empty volume: only directories were restored
+    Empty,
+    /// Moved into place from `restore --prefetch` data instead of downloaded
+    Prefetched,
+    /// No snapshot at or before the selected time
+    Skipped,
+    Failed(String),
+}
+
+impl RepoRestoreStatus {
+    fn label(&self) -> &'static str {
+        match self {
+            RepoRestoreStatus::Restored => "restored",
+            RepoRestoreStatus::Empty => "restored (empty)",
+            RepoRestoreStatus::Prefetched => "restored (prefetched)",
+            RepoRestoreStatus::Skipped => "skipped",
+            RepoRestoreStatus::Failed(_) => "failed",
+        }
+    }
+
+    /// Machine-readable status for `restore --json`
+    fn code(&self) -> &'static str {
+        match self {
+            RepoRestoreStatus::Restored => "restored",
+            RepoRestoreStatus::Empty => "empty",
+            RepoRestoreStatus::Prefetched => "prefetched",
+            RepoRestoreStatus::Skipped => "skipped",
+            RepoRestoreStatus::Failed(_) => "failed",
+        }
+    }
+}
+
+struct RepoRestoreOutcome {
+    path: PathBuf,
+    snapshot: Option<SnapshotItem>,
+    status: RepoRestoreStatus,
+}
+
+/// One repository in the `restore --json` result
+#[derive(Debug, Clone, Serialize)]
+struct RepoRestoreResult {
+    path: String,
+    status: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    snapshot_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    snapshot_time: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl From<&RepoRestoreOutcome> for RepoRestoreResult {
+    fn from(outcome: &RepoRestoreOutcome) -> Self {
+        Self {
+            path: outcome.path.display().to_string(),
+            status: outcome.status.code(),
+            snapshot_id: outcome.snapshot.as_ref().map(|s| s.id.clone()),
+            snapshot_time: outcome.snapshot.as_ref().map(|s| s.time.to_rfc3339()),
+            error: match &outcome.status {
+                RepoRestoreStatus::Failed(e) => Some(e.clone()),
+                _ => None,
+            },
+        }
+    }
+}
+
+/// One repository in the `restore --dry-run` preview
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+struct DryRunPreview {
+    path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    snapshot_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    snapshot_time: Option<String>,
+    /// Where the files would be staged before copy/move
+    staged_at: String,
+    total_files: u64,
+    total_bytes: u64,
+    /// Files that differ from the original location, i.e. what copying back would replace
+    changed_files: u64,
+    changed_bytes: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl DryRunPreview {
+    /// Fill the counts from a `restic restore --dry-run --json` summary message
+    fn with_summary(mut self, summary: &serde_json::Value) -> Self {
+        let count = |field: &str| summary[field].as_u64().unwrap_or(0);
+        self.total_files = count("total_files");
+        self.total_bytes = count("total_bytes");
+        self.changed_files = count("files_restored");
+        self.changed_bytes = count("bytes_restored");
+        self
+    }
+}
+
+/// One repository's `restic restore`, owned so it can run on its own task
+struct RepoRestoreJob {
+    restic_cmd: ResticCommandExecutor,
+    path: PathBuf,
+    snapshot: SnapshotItem,
+    dest_dir: PathBuf,
+    extra_args: Vec<String>,
+    live_output: bool,
+}
+
+impl RepoRestoreJob {
+    async fn run(self) -> RepoRestoreOutcome {
+        // Jobs still waiting for a permit when Ctrl-C arrived are not started
+        if shutdown::is_requested() {
+            return RepoRestoreOutcome {
+                path: self.path,
+                snapshot: Some(self.snapshot),
+                status: RepoRestoreStatus::Failed("not started, restore interrupted".to_string()),
+            };
+        }
+        info!(
+            path = %self.path.display(),
+            snapshot_id = %self.snapshot.id,
+            timestamp = %format_local(self.snapshot.time),
+            "Found snapshot, starting restore"
+        );
+        let result = self
+            .restic_cmd
+            .restore(
+                &self.snapshot.id,
+                &self.path.to_string_lossy(),
+                &self.dest_dir.to_string_lossy(),
+                &self.extra_args,
+                self.live_output,
+            )
+            .await;
+
+        let status = match result {
+            Err(e) => RepoRestoreStatus::Failed(e.to_string()),
+            // Check if the restoration was empty (like old script detection)
+            Ok(output) => {
+                let restored_path = self
+                    .dest_dir
+                    .join(self.path.strip_prefix("/").unwrap_or(&self.path));
+                let is_empty = fs::read_dir(&restored_path)
+                    .map(|mut entries| entries.next().is_none())
+                    .unwrap_or(true);
+                if is_empty && output.contains("0 B") {
+                    RepoRestoreStatus::Empty
+                } else {
+                    RepoRestoreStatus::Restored
+                }
+            }
+        };
+        RepoRestoreOutcome {
+            path: self.path,
+            snapshot: Some(self.snapshot),
+            status,
+        }
+    }
+}
+
 /// Manage the entire restore workflow
 pub struct RestoreWorkflow {
     config: Config,
     host_opt: Option<String>,
     path_opt: Option<String>,
     timestamp_opt: Option<String>,
+    options: RestoreOptions,
+    action: Option<PostRestoreAction>,
+    overwrite: OverwritePolicy,
+    transcript: RestoreTranscript,
+    results: Mutex<Vec<RepoRestoreResult>>,
+    notifiers: Notifiers,
 }
 
 impl RestoreWorkflow {
@@ -26,17 +525,209 @@ impl RestoreWorkflow {
         host_opt: Option<String>,
         path_opt: Option<String>,
         timestamp_opt: Option<String>,
+        options: RestoreOptions,
     ) -> Result<Self, BackupServiceError> {
+        // Reject malformed start times before any prompts are shown
+        if let Some(spec) = &options.start_at {
+            resolve_start_time(spec, Local::now())?;
+        }
+        if options.verify_containers {
+            parse_probes(&std::env::var(VERIFY_PROBES_ENV_VAR).unwrap_or_default())?;
+        }
+        let action = options
+            .action
+            .as_deref()
+            .map(PostRestoreAction::parse)
+            .transpose()?;
+        let overwrite = options
+            .overwrite
+            .as_deref()
+            .map(OverwritePolicy::parse)
+            .transpose()?
+            .unwrap_or_default();
+        if options.dry_run && options.prefetch {
+            return Err(BackupServiceError::ConfigurationError(
+                "restore --dry-run cannot be combined with --prefetch.\n\n\
+                Preview first with --dry-run, then run --prefetch on its own"
+                    .to_string(),
+            ));
+        }
+        if options.json_output && !options.assume_yes {
+            return Err(BackupServiceError::ConfigurationError(
+                "restore --json only works without prompts.\n\n\
+                Add --yes and --path, e.g. restore --json --yes --path /home/tim --action copy"
+                    .to_string(),
+            ));
+        }
+        // Moving replaces the original directory, which would drop every file left out
+        // (a single --file is placed on its own instead)
+        if action == Some(PostRestoreAction::Move)
+            && is_filtered(&options)
+            && options.file.is_none()
+        {
+            return Err(filtered_move_error());
+        }
+        if let Some(file) = &options.file
+            && !file.is_absolute()
+        {
+            return Err(BackupServiceError::ConfigurationError(format!(
+                "--file must be an absolute path: {}.\n\nGive the path as it was backed up, e.g. --file /home/tim/Documents/report.docx",
+                file.display()
+            )));
+        }
+        if options.file_to.is_some() && options.file.is_none() {
+            return Err(BackupServiceError::ConfigurationError(
+                "--to only applies to a single-file restore.\n\nName the file with --file, or use --target for whole repositories"
+                    .to_string(),
+            ));
+        }
+        // Host and timestamp have unattended defaults; the paths do not
+        if options.assume_yes && path_opt.is_none() && options.file.is_none() {
+            return Err(BackupServiceError::ConfigurationError(
+                "restore --yes needs the path to restore.\n\n\
+                Pass --path, e.g. restore --yes --path /home/tim --action copy"
+                    .to_string(),
+            ));
+        }
+        // Without a prompt, the current host and the latest snapshot are restored
+        let (host_opt, timestamp_opt) = if options.assume_yes {
+            (
+                host_opt.or_else(|| Some(config.hostname.clone())),
+                timestamp_opt.or_else(|| Some("now".to_string())),
+            )
+        } else {
+            (host_opt, timestamp_opt)
+        };
+
         Ok(Self {
             config,
             host_opt,
             path_opt,
             timestamp_opt,
+            options,
+            action,
+            overwrite,
+            transcript: RestoreTranscript::default(),
+            results: Mutex::new(Vec::new()),
+            notifiers: Notifiers::from_env()?,
         })
     }
 
-    /// Execute the complete interactive restore workflow
+    /// Execute the complete interactive restore workflow, leaving a transcript of the session
     pub async fn execute_interactive_restore(&self) -> Result<(), BackupServiceError> {
+        let result = self.run_session().await;
+        // A preview leaves no transcript and sends no notification
+        if self.options.dry_run {
+            return result;
+        }
+        let cancelled = self
+            .transcript
+            .entries()
+            .iter()
+            .any(|e| e.kind == "cancelled");
+        let outcome = match &result {
+            Err(e) => format!("failed: {}", e),
+            Ok(()) if cancelled => "cancelled".to_string(),
+            Ok(()) => "completed".to_string(),
+        };
+        self.transcript.finish(&outcome).await;
+        if !cancelled {
+            self.notify(&result).await;
+        }
+        if self.options.json_output {
+            self.print_result(&result, cancelled)?;
+        }
+        result
+    }
+
+    /// Send the session outcome to the configured notification channels
+    async fn notify(&self, result: &Result<(), BackupServiceError>) {
+        let repositories = self.results.lock().map(|r| r.clone()).unwrap_or_default();
+        let failed: Vec<&RepoRestoreResult> = repositories
+            .iter()
+            .filter(|r| r.status == "failed")
+            .collect();
+        let severity = match result {
+            Err(_) => Severity::Failure,
+            Ok(()) if !failed.is_empty() => Severity::Warning,
+            Ok(()) => Severity::Success,
+        };
+        if !self.notifiers.wants(severity) {
+            return;
+        }
+        let host = self
+            .host_opt
+            .clone()
+            .unwrap_or_else(|| self.config.hostname.clone());
+        let title = match result {
+            Err(_) => format!("{}: restore failed", host),
+            Ok(()) if !failed.is_empty() => format!("{}: restore partially failed", host),
+            Ok(()) => format!("{}: restore completed", host),
+        };
+        let restored = repositories
+            .iter()
+            .filter(|r| !matches!(r.status, "failed" | "skipped"))
+            .count();
+        let mut message = format!(
+            "{} of {} repositories restored to {}\n",
+            restored,
+            repositories.len(),
+            self.dest_dir().display()
+        );
+        if let Err(e) = result {
+            message.push_str(&format!("{}\n", e));
+        }
+        for repo in &failed {
+            message.push_str(&format!(
+                "failed {}: {}\n",
+                repo.path,
+                repo.error.as_deref().unwrap_or_default()
+            ));
+        }
+        self.notifiers
+            .dispatch(&Notification {
+                kind: "restore",
+                host,
+                severity,
+                title,
+                message,
+                details: json!({
+                    "destination": self.dest_dir().display().to_string(),
+                    "action": self.action.unwrap_or(PostRestoreAction::Leave).as_str(),
+                    "error": result.as_ref().err().map(|e| e.to_string()),
+                    "repositories": repositories,
+                }),
+            })
+            .await;
+    }
+
+    /// `restore --json`: the session outcome and every repository's result on stdout
+    fn print_result(
+        &self,
+        result: &Result<(), BackupServiceError>,
+        cancelled: bool,
+    ) -> Result<(), BackupServiceError> {
+        let repositories = self.results.lock().map(|r| r.clone()).unwrap_or_default();
+        let output = json!({
+            "host": self.host_opt,
+            "path": self.path_opt,
+            "destination": self.dest_dir().display().to_string(),
+            "action": self.action.unwrap_or(PostRestoreAction::Leave).as_str(),
+            "prefetch": self.options.prefetch,
+            "include": self.options.include,
+            "exclude": self.options.exclude,
+            "outcome": match result {
+                Err(_) => "failed",
+                Ok(()) if cancelled => "cancelled",
+                Ok(()) => "completed",
+            },
+            "error": result.as_ref().err().map(|e| e.to_string()),
+            "repositories": repositories,
+        });
+        DisplayFormatter::print_json(&output)
+    }
+
+    async fn run_session(&self) -> Result<(), BackupServiceError> {
         self.config.set_aws_env()?;
         info!("Restic Interactive Restore Tool");
 
@@ -58,6 +749,34 @@ impl RestoreWorkflow {
             .execute_timestamp_selection_phase(&repository_selection.selected_repos)
             .await?;
 
+        let filters = filter_args(&self.options);
+        if !filters.is_empty() {
+            self.transcript.record("filters", filters.join(" "));
+        }
+
+        if self.options.dry_run {
+            return self
+                .execute_dry_run_phase(
+                    &host_selection.selected_host,
+                    &repository_selection.selected_repos,
+                    &timestamp_selection.selected_timestamp,
+                )
+                .await;
+        }
+
+        // Optionally defer the download to a quieter time
+        self.wait_for_scheduled_start().await?;
+
+        if self.options.prefetch {
+            return self
+                .execute_prefetch_phase(
+                    &host_selection.selected_host,
+                    &repository_selection.selected_repos,
+                    &timestamp_selection.selected_timestamp,
+                )
+                .await;
+        }
+
         // Phase 5: Restoration
         self.execute_restoration_phase(
             &host_selection.selected_host,
@@ -69,10 +788,48 @@ impl RestoreWorkflow {
         Ok(())
     }
 
+    /// Sleep until the requested `--at` time, if any
+    async fn wait_for_scheduled_start(&self) -> Result<(), BackupServiceError> {
+        let Some(spec) = &self.options.start_at else {
+            return Ok(());
+        };
+
+        let now = Local::now();
+        let start = resolve_start_time(spec, now)?;
+        if let Ok(wait) = (start - now).to_std() {
+            info!(
+                start_at = %start.format("%Y-%m-%d %H:%M %:z"),
+                wait_minutes = %(wait.as_secs() / 60),
+                "Waiting for scheduled restore start"
+            );
+            self.transcript.record(
+                "scheduled",
+                format!("waiting until {}", start.format("%Y-%m-%d %H:%M %:z")),
+            );
+            tokio::time::sleep(wait).await;
+        }
+        Ok(())
+    }
+
+    /// Directory restic restores into: `--target`, else RESTORE_STAGING_DIR
+    fn dest_dir(&self) -> PathBuf {
+        self.options.target.clone().unwrap_or_else(staging_dir)
+    }
+
+    /// Extra restic flags applied to every restore command
+    fn restore_extra_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if let Some(limit) = self.options.limit_download {
+            args.push("--limit-download".to_string());
+            args.push(limit.to_string());
+        }
+        args.extend(filter_args(&self.options));
+        args
+    }
+
     /// Phase 1: Host selection
     async fn execute_host_selection_phase(&self) -> Result<HostSelection, BackupServiceError> {
-        let s3_executor = S3CommandExecutor::new(self.config.clone())?;
-        let hosts = s3_executor.get_hosts().await?;
+        let hosts = RepoStore::new(self.config.clone())?.get_hosts().await?;
 
         if hosts.is_empty() {
             error!("No hosts found in backup repository");
@@ -85,6 +842,8 @@ impl RestoreWorkflow {
         let host_selection = select_host(hosts, current_host, self.host_opt.clone()).await?;
 
         info!(host = %host_selection.selected_host, "Selected host");
+        self.transcript
+            .record("host", host_selection.selected_host.clone());
         Ok(host_selection)
     }
 
@@ -94,15 +853,29 @@ impl RestoreWorkflow {
         hostname: &str,
     ) -> Result<Vec<RepositorySelectionItem>, BackupServiceError> {
         info!(host = %hostname, "Querying backups");
-        let operations = RepositoryOperations::new(self.config.clone())?;
+        let operations = RepositoryOperations::new(self.config.clone())?
+            .with_categories(self.options.categories.clone())?;
 
-        let repo_infos = operations.scan_repositories(hostname).await?;
-        info!(repo_count = %repo_infos.len(), "Converting repository data for UI");
+        let scan = operations
+            .scan_repositories_cached(hostname, self.options.refresh)
+            .await?;
+        for failure in &scan.failures {
+            warn!(failure = %failure, "Some backups could not be listed");
+        }
+        info!(repo_count = %scan.repos.len(), "Converting repository data for UI");
 
-        let repos = operations.convert_to_selection_items(repo_infos)?;
+        let repos =
+            operations.convert_to_selection_items(retain_tagged(scan.repos, &self.options.tags))?;
 
         if repos.is_empty() {
             error!(host = %hostname, "No backups found for host");
+            if !scan.failures.is_empty() {
+                let details: Vec<String> = scan.failures.iter().map(|f| f.to_string()).collect();
+                return Err(BackupServiceError::CommandFailed(format!(
+                    "No backups could be listed for host:\n  {}",
+                    details.join("\n  ")
+                )));
+            }
             return Err(BackupServiceError::ConfigurationError(
                 "No backups found for host".to_string(),
             ));
@@ -119,9 +892,34 @@ impl RestoreWorkflow {
     ) -> Result<RepositorySelection, BackupServiceError> {
         info!(repo_count = %backup_data.len(), "Found repositories, starting selection phase");
 
-        let repository_selection = select_repositories(backup_data, self.path_opt.clone()).await?;
+        let repository_selection = match &self.options.file {
+            Some(file) => {
+                let repo = file_repository(&backup_data, file)
+                    .filter(|repo| {
+                        self.path_opt
+                            .as_deref()
+                            .is_none_or(|path| repo.path == Path::new(path))
+                    })
+                    .ok_or_else(|| {
+                        BackupServiceError::ConfigurationError(format!(
+                            "No backup of the selected host contains {}.\n\nCheck the path with `restic-backup-service ls`, or drop --path",
+                            file.display()
+                        ))
+                    })?;
+                RepositorySelection {
+                    selected_repos: vec![repo.clone()],
+                }
+            }
+            None => select_repositories(backup_data, self.path_opt.clone()).await?,
+        };
 
         info!(repo_count = %repository_selection.selected_repos.len(), "Selected repositories for restoration");
+        for repo in &repository_selection.selected_repos {
+            self.transcript.record(
+                "selection",
+                format!("{} ({})", repo.path.display(), repo.repo_subpath),
+            );
+        }
         Ok(repository_selection)
     }
 
@@ -133,31 +931,307 @@ impl RestoreWorkflow {
         let timestamp_selection =
             select_timestamp(selected_repos, self.timestamp_opt.clone()).await?;
 
-        info!(timestamp = %timestamp_selection.selected_timestamp.format("%Y-%m-%d %H:%M"), "🕐 Selected time window");
+        info!(timestamp = %format_local(timestamp_selection.selected_timestamp), "🕐 Selected time window");
+        self.transcript.record(
+            "timestamp",
+            format_local(timestamp_selection.selected_timestamp),
+        );
         Ok(timestamp_selection)
     }
 
-    /// Phase 5: Restoration
-    async fn execute_restoration_phase(
+    /// Phase 5 (`--prefetch`): download the selected snapshots into the staging area only
+    ///
+    /// The later restore of the same host/snapshots moves staged data into place instead of
+    /// downloading it again, so the cutover itself is short.
+    async fn execute_prefetch_phase(
+        &self,
+        selected_host: &str,
+        selected_repos: &[RepositorySelectionItem],
+        selected_timestamp: &DateTime<Utc>,
+    ) -> Result<(), BackupServiceError> {
+        let root = prefetch_dir();
+        let mut prefetched = 0;
+
+        for (idx, repo) in selected_repos.iter().enumerate() {
+            let Some(snapshot) = select_snapshot(repo, selected_timestamp) else {
+                warn!(path = %repo.path.display(), "No suitable snapshots found, skipping");
+                continue;
+            };
+            let (staging, marker) = prefetch_staging(&root, selected_host, &snapshot.id);
+            if marker.exists() {
+                info!(path = %repo.path.display(), snapshot_id = %snapshot.id, "Already prefetched");
+                prefetched += 1;
+                continue;
+            }
+
+            info!(
+                progress = format!("({}/{})", idx + 1, selected_repos.len()),
+                path = %repo.path.display(),
+                snapshot_id = %snapshot.id,
+                staging = %staging.display(),
+                "Prefetching snapshot"
+            );
+            // A partial staging dir from an interrupted prefetch cannot be trusted
+            if staging.exists() {
+                fs::remove_dir_all(&staging)?;
+            }
+            fs::create_dir_all(&staging)?;
+
+            let repo_url = self
+                .config
+                .get_repo_url_for_host(selected_host, &repo.repo_subpath)?;
+            let restic_cmd = ResticCommandExecutor::new(self.config.clone(), repo_url)?;
+            restic_cmd
+                .restore(
+                    &snapshot.id,
+                    &repo.path.to_string_lossy(),
+                    &staging.to_string_lossy(),
+                    &self.restore_extra_args(),
+                    live_restic_output(&self.options, 1),
+                )
+                .await?;
+            // The filters are kept with the marker: data staged with others is not reused
+            fs::write(
+                &marker,
+                format!(
+                    "{}\n{}",
+                    Utc::now().to_rfc3339(),
+                    filter_args(&self.options).join(" ")
+                ),
+            )?;
+            self.transcript.record(
+                "prefetch",
+                format!(
+                    "{} <- {} ({}) into {}",
+                    repo.path.display(),
+                    snapshot.id,
+                    format_local(snapshot.time),
+                    staging.display()
+                ),
+            );
+            prefetched += 1;
+        }
+
+        info!(
+            prefetched = %prefetched,
+            repo_count = %selected_repos.len(),
+            staging = %root.join(selected_host).display(),
+            "Prefetch completed; run the same restore without --prefetch at cutover"
+        );
+        Ok(())
+    }
+
+    /// Phase 5 (`--dry-run`): report what every repository would restore, writing nothing
+    ///
+    /// Each snapshot is dry-run restored onto its original location, so besides the totals
+    /// the preview shows how much copying back would replace.
+    async fn execute_dry_run_phase(
         &self,
         selected_host: &str,
         selected_repos: &[RepositorySelectionItem],
         selected_timestamp: &DateTime<Utc>,
     ) -> Result<(), BackupServiceError> {
-        let dest_dir = PathBuf::from("/tmp/restic/interactive");
+        let dest_dir = self.dest_dir();
+        let mut previews = Vec::new();
 
-        if dest_dir.exists() {
-            if fs::read_dir(&dest_dir)?.next().is_some() {
-                warn!(destination = %dest_dir.display(), "Destination directory is not empty");
+        for repo in selected_repos {
+            let relative = repo.path.strip_prefix("/").unwrap_or(&repo.path);
+            let preview = DryRunPreview {
+                path: repo.path.display().to_string(),
+                staged_at: dest_dir.join(relative).display().to_string(),
+                ..DryRunPreview::default()
+            };
+            let Some(snapshot) = select_snapshot(repo, selected_timestamp) else {
+                previews.push(DryRunPreview {
+                    error: Some("no suitable snapshot".to_string()),
+                    ..preview
+                });
+                continue;
+            };
+            let preview = DryRunPreview {
+                snapshot_id: Some(snapshot.id.clone()),
+                snapshot_time: Some(snapshot.time.to_rfc3339()),
+                ..preview
+            };
 
-                if !confirm_action("Continue and clear the directory?", false).await? {
-                    error!("Operation cancelled by user");
-                    return Ok(());
+            let repo_url = self
+                .config
+                .get_repo_url_for_host(selected_host, &repo.repo_subpath)?;
+            let restic_cmd = ResticCommandExecutor::new(self.config.clone(), repo_url)?;
+            let summary = restic_cmd
+                .restore_dry_run(
+                    &snapshot.id,
+                    &repo.path.to_string_lossy(),
+                    "/",
+                    &self.restore_extra_args(),
+                )
+                .await;
+            previews.push(match summary {
+                Ok(summary) => preview.with_summary(&summary),
+                Err(e) => {
+                    warn!(path = %repo.path.display(), error = %e, "Dry run failed");
+                    DryRunPreview {
+                        error: Some(e.to_string()),
+                        ..preview
+                    }
                 }
+            });
+        }
+
+        if self.options.json_output {
+            let output = json!({
+                "host": selected_host,
+                "dry_run": true,
+                "destination": dest_dir.display().to_string(),
+                "action": self.action.map(PostRestoreAction::as_str),
+                "include": self.options.include,
+                "exclude": self.options.exclude,
+                "repositories": previews,
+            });
+            return DisplayFormatter::print_json(&output);
+        }
+
+        info!("{}", t("restore-dry-run-header"));
+        for preview in &previews {
+            let Some(snapshot_id) = &preview.snapshot_id else {
+                info!(
+                    "  {}",
+                    t_args("restore-dry-run-skipped", &[("path", preview.path.clone())])
+                );
+                continue;
+            };
+            if let Some(e) = &preview.error {
+                error!(path = %preview.path, error = %e, "Dry run failed");
+                continue;
             }
-            fs::remove_dir_all(&dest_dir)?;
+            info!(
+                "  {}",
+                t_args(
+                    "restore-dry-run-repo",
+                    &[
+                        ("path", preview.path.clone()),
+                        ("snapshot", snapshot_id.clone()),
+                        ("files", preview.total_files.to_string()),
+                        ("size", format_bytes(preview.total_bytes)?),
+                        ("changed_files", preview.changed_files.to_string()),
+                        ("changed_size", format_bytes(preview.changed_bytes)?),
+                        ("staged", preview.staged_at.clone()),
+                    ]
+                )
+            );
+        }
+        info!(
+            "  {}",
+            t_args(
+                "restore-summary-destination",
+                &[("path", dest_dir.display().to_string())]
+            )
+        );
+        info!("{}", t("restore-dry-run-nothing-written"));
+        Ok(())
+    }
+
+    /// Move a prefetched snapshot into the restore destination; false if none is staged
+    fn use_prefetched(
+        &self,
+        selected_host: &str,
+        repo: &RepositorySelectionItem,
+        snapshot_id: &str,
+        dest_dir: &Path,
+    ) -> Result<bool, BackupServiceError> {
+        let (staging, marker) = prefetch_staging(&prefetch_dir(), selected_host, snapshot_id);
+        if !marker.exists() {
+            return Ok(false);
+        }
+        let staged_filters = fs::read_to_string(&marker)?
+            .lines()
+            .nth(1)
+            .unwrap_or_default()
+            .to_string();
+        if staged_filters != filter_args(&self.options).join(" ") {
+            warn!(
+                path = %repo.path.display(),
+                snapshot_id = %snapshot_id,
+                "Prefetched data was staged with other --include/--exclude filters, downloading instead"
+            );
+            return Ok(false);
+        }
+
+        let relative = repo.path.strip_prefix("/").unwrap_or(&repo.path);
+        let src = staging.join(relative);
+        let dst = dest_dir.join(relative);
+        if let Some(parent) = dst.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        if src.exists() && fs::rename(&src, &dst).is_err() {
+            copy_recursively(&src, &dst)?;
+        }
+        fs::remove_dir_all(&staging)?;
+        fs::remove_file(&marker)?;
+        Ok(true)
+    }
+
+    /// Phase 5: Restoration
+    async fn execute_restoration_phase(
+        &self,
+        selected_host: &str,
+        selected_repos: &[RepositorySelectionItem],
+        selected_timestamp: &DateTime<Utc>,
+    ) -> Result<(), BackupServiceError> {
+        let dest_dir = self.dest_dir();
+        let cap = staging_max_size()?;
+        let sizes = match self
+            .estimate_sizes(selected_host, selected_repos, selected_timestamp)
+            .await
+        {
+            Ok(sizes) => Some(sizes),
+            // Only the staging cap depends on the estimate; the free-space check is best effort
+            Err(e) if cap.is_none() => {
+                warn!(error = %e, "Could not estimate the restore size, skipping the free-space check");
+                None
+            }
+            Err(e) => return Err(e),
+        };
+        let chunks = match (&sizes, cap) {
+            (Some(sizes), Some(cap)) => plan_chunks(selected_repos, sizes, cap)?,
+            _ => vec![(0..selected_repos.len()).collect()],
+        };
+        self.warn_same_filesystem(selected_repos, &dest_dir);
+
+        if dest_dir.exists() && fs::read_dir(&dest_dir)?.next().is_some() {
+            check_target_clearable(&self.options, &dest_dir)?;
+            warn!(destination = %dest_dir.display(), "Destination directory is not empty");
+
+            if !self.options.assume_yes
+                && !confirm_action(&t("prompt-clear-destination"), false).await?
+            {
+                error!("Operation cancelled by user");
+                self.transcript.record(
+                    "cancelled",
+                    format!("declined to clear {}", dest_dir.display()),
+                );
+                return Ok(());
+            }
+            self.transcript
+                .record("action", format!("cleared {}", dest_dir.display()));
+            empty_dir(&dest_dir)?;
         }
         fs::create_dir_all(&dest_dir)?;
+        if let Some(sizes) = &sizes {
+            check_free_space(&dest_dir, sizes, &chunks)?;
+        }
+
+        if chunks.len() > 1 {
+            return self
+                .execute_chunked_restoration(
+                    selected_host,
+                    selected_repos,
+                    selected_timestamp,
+                    &dest_dir,
+                    &chunks,
+                )
+                .await;
+        }
 
         info!(destination = %dest_dir.display(), "Restoring to destination");
 
@@ -167,12 +1241,30 @@ impl RestoreWorkflow {
 
         // Display detailed summary
         info!("");
-        info!("Restoration Summary:");
-        info!("  Successfully restored: {} repositories", restored_count);
+        info!("{}", t("restore-summary-header"));
+        info!(
+            "  {}",
+            t_args(
+                "restore-summary-restored",
+                &[("count", restored_count.to_string())]
+            )
+        );
         if skipped_count > 0 {
-            info!("  Skipped: {} repositories", skipped_count);
+            info!(
+                "  {}",
+                t_args(
+                    "restore-summary-skipped",
+                    &[("count", skipped_count.to_string())]
+                )
+            );
         }
-        info!("  Destination: {}", dest_dir.display());
+        info!(
+            "  {}",
+            t_args(
+                "restore-summary-destination",
+                &[("path", dest_dir.display().to_string())]
+            )
+        );
 
         if restored_count > 0 {
             info!("Restoration completed successfully");
@@ -185,7 +1277,10 @@ impl RestoreWorkflow {
         Ok(())
     }
 
-    /// Restore all selected repositories
+    /// Restore all selected repositories, up to `--jobs` (RESTORE_JOBS) at once
+    ///
+    /// Repositories are independent, so a failed one does not stop the others; the run
+    /// fails once every job has finished.
     async fn restore_repositories(
         &self,
         selected_host: &str,
@@ -193,90 +1288,274 @@ impl RestoreWorkflow {
         selected_timestamp: &DateTime<Utc>,
         dest_dir: &Path,
     ) -> Result<(usize, usize), BackupServiceError> {
-        let mut restored_count = 0;
-        let mut skipped_count = 0;
-
-        info!("Starting restoration process");
-
-        for (idx, repo) in selected_repos.iter().enumerate() {
-            info!(
-                path = %repo.path.display(),
-                repo_subpath = %repo.repo_subpath,
-                progress = format!("({}/{})", idx + 1, selected_repos.len()),
-                "Restoring repository"
-            );
-
-            let repo_url = self
-                .config
-                .get_repo_url_for_host(selected_host, &repo.repo_subpath)?;
-
-            let window_end = *selected_timestamp + Duration::minutes(5);
-            let best_snapshot = repo
-                .snapshots
-                .iter()
-                .filter(|s| s.time >= *selected_timestamp && s.time < window_end)
-                .max_by_key(|s| s.time)
-                .or_else(|| {
-                    repo.snapshots
-                        .iter()
-                        .filter(|s| s.time < *selected_timestamp)
-                        .max_by_key(|s| s.time)
+        let total = selected_repos.len();
+        let jobs = resolve_jobs(
+            self.options.jobs,
+            RESTORE_JOBS_ENV_VAR,
+            DEFAULT_RESTORE_JOBS,
+        )?
+        .min(total.max(1));
+        info!(repo_count = %total, jobs = %jobs, "Starting restoration process");
+
+        let mut outcomes = Vec::with_capacity(total);
+        let permits = Arc::new(Semaphore::new(jobs));
+        let mut tasks = JoinSet::new();
+        for repo in selected_repos {
+            let Some(snapshot) = select_snapshot(repo, selected_timestamp) else {
+                warn!(
+                    path = %repo.path.display(),
+                    "No suitable snapshots found, skipping"
+                );
+                self.transcript.record(
+                    "skipped",
+                    format!("{}: no suitable snapshot", repo.path.display()),
+                );
+                outcomes.push(RepoRestoreOutcome {
+                    path: repo.path.clone(),
+                    snapshot: None,
+                    status: RepoRestoreStatus::Skipped,
                 });
+                continue;
+            };
 
-            if let Some(snapshot) = best_snapshot {
+            if self.use_prefetched(selected_host, repo, &snapshot.id, dest_dir)? {
                 info!(
+                    progress = format!("({}/{})", outcomes.len() + 1, total),
                     path = %repo.path.display(),
                     snapshot_id = %snapshot.id,
-                    timestamp = %snapshot.time.format("%Y-%m-%dT%H:%M:%S"),
-                    "Found snapshot, starting restore"
+                    "Restored from prefetched data"
                 );
+                self.transcript.record(
+                    "snapshot",
+                    format!(
+                        "{} <- {} ({}, prefetched)",
+                        repo.path.display(),
+                        snapshot.id,
+                        format_local(snapshot.time)
+                    ),
+                );
+                outcomes.push(RepoRestoreOutcome {
+                    path: repo.path.clone(),
+                    snapshot: Some(snapshot.clone()),
+                    status: RepoRestoreStatus::Prefetched,
+                });
+                continue;
+            }
 
-                let restic_cmd = ResticCommandExecutor::new(self.config.clone(), repo_url)?;
-                let restore_output = restic_cmd
-                    .restore(
-                        &snapshot.id,
-                        &repo.path.to_string_lossy(),
-                        &dest_dir.to_string_lossy(),
-                    )
-                    .await?;
-
-                // Check if the restoration was empty (like old script detection)
-                let restored_path =
-                    dest_dir.join(repo.path.strip_prefix("/").unwrap_or(&repo.path));
-                let is_empty = if restored_path.exists() {
-                    std::fs::read_dir(&restored_path)
-                        .map(|mut entries| entries.next().is_none())
-                        .unwrap_or(true)
-                } else {
-                    true
-                };
+            let repo_url = self
+                .config
+                .get_repo_url_for_host(selected_host, &repo.repo_subpath)?;
+            let job = RepoRestoreJob {
+                restic_cmd: ResticCommandExecutor::new(self.config.clone(), repo_url)?,
+                path: repo.path.clone(),
+                snapshot: snapshot.clone(),
+                dest_dir: dest_dir.to_path_buf(),
+                extra_args: self.restore_extra_args(),
+                live_output: live_restic_output(&self.options, jobs),
+            };
+            let permits = Arc::clone(&permits);
+            tasks.spawn(async move {
+                let _permit = permits.acquire_owned().await;
+                job.run().await
+            });
+        }
 
-                if is_empty && restore_output.contains("0 B") {
+        while let Some(joined) = tasks.join_next().await {
+            let outcome = joined.map_err(|e| {
+                BackupServiceError::CommandFailed(format!("Restore task panicked: {}", e))
+            })?;
+            let progress = format!("({}/{})", outcomes.len() + 1, total);
+            match (&outcome.status, &outcome.snapshot) {
+                (RepoRestoreStatus::Failed(e), _) => {
+                    error!(progress = %progress, path = %outcome.path.display(), error = %e, "Restore failed");
+                    self.transcript
+                        .record("failed", format!("{}: {}", outcome.path.display(), e));
+                }
+                (status, Some(snapshot)) => {
                     info!(
-                        path = %repo.path.display(),
+                        progress = %progress,
+                        path = %outcome.path.display(),
                         snapshot_id = %snapshot.id,
-                        timestamp = %snapshot.time.format("%Y-%m-%dT%H:%M:%S"),
-                        "Restored (empty volume - directories only)"
+                        timestamp = %format_local(snapshot.time),
+                        "{}",
+                        if *status == RepoRestoreStatus::Empty {
+                            "Restored (empty volume - directories only)"
+                        } else {
+                            "Restored successfully"
+                        }
                     );
-                } else {
-                    info!(
-                        path = %repo.path.display(),
-                        snapshot_id = %snapshot.id,
-                        timestamp = %snapshot.time.format("%Y-%m-%dT%H:%M:%S"),
-                        "Restored successfully"
+                    self.transcript.record(
+                        "snapshot",
+                        format!(
+                            "{} <- {} ({}) into {}",
+                            outcome.path.display(),
+                            snapshot.id,
+                            format_local(snapshot.time),
+                            dest_dir.display()
+                        ),
                     );
                 }
-                restored_count += 1;
-            } else {
+                (_, None) => {}
+            }
+            outcomes.push(outcome);
+        }
+
+        outcomes.sort_by(|a, b| a.path.cmp(&b.path));
+        if let Ok(mut results) = self.results.lock() {
+            results.extend(outcomes.iter().map(RepoRestoreResult::from));
+        }
+        if total > 1 {
+            for outcome in &outcomes {
+                info!(path = %outcome.path.display(), status = %outcome.status.label(), "Repository restore status");
+            }
+        }
+
+        let count =
+            |f: fn(&RepoRestoreStatus) -> bool| outcomes.iter().filter(|o| f(&o.status)).count();
+        let failed = count(|s| matches!(s, RepoRestoreStatus::Failed(_)));
+        if failed > 0 {
+            return Err(BackupServiceError::CommandFailed(format!(
+                "Restore failed for {} of {} repositories",
+                failed, total
+            )));
+        }
+        let skipped = count(|s| *s == RepoRestoreStatus::Skipped);
+        Ok((total - skipped, skipped))
+    }
+
+    /// Restored size of the snapshot each repository would be restored from (0 when none)
+    ///
+    /// Sizes come from `restic stats <snapshot> --mode restore-size`.
+    async fn estimate_sizes(
+        &self,
+        selected_host: &str,
+        selected_repos: &[RepositorySelectionItem],
+        selected_timestamp: &DateTime<Utc>,
+    ) -> Result<Vec<u64>, BackupServiceError> {
+        let mut sizes = Vec::with_capacity(selected_repos.len());
+        for repo in selected_repos {
+            let size = match select_snapshot(repo, selected_timestamp) {
+                Some(snapshot) => {
+                    let repo_url = self
+                        .config
+                        .get_repo_url_for_host(selected_host, &repo.repo_subpath)?;
+                    let restic_cmd = ResticCommandExecutor::new(self.config.clone(), repo_url)?;
+                    restic_cmd
+                        .snapshot_stats(&snapshot.id, "restore-size")
+                        .await?["total_size"]
+                        .as_u64()
+                        .unwrap_or(0)
+                }
+                None => 0,
+            };
+            sizes.push(size);
+        }
+
+        info!(
+            total_size = %format_bytes(sizes.iter().sum())?,
+            "Estimated restore size"
+        );
+        Ok(sizes)
+    }
+
+    /// Warn about restored paths on the target's filesystem: placing them back with copy
+    /// needs room for both copies, and a failing disk is restored onto itself
+    fn warn_same_filesystem(&self, selected_repos: &[RepositorySelectionItem], dest_dir: &Path) {
+        for repo in selected_repos {
+            if same_filesystem(&repo.path, dest_dir) {
                 warn!(
                     path = %repo.path.display(),
-                    "No suitable snapshots found, skipping"
+                    target = %dest_dir.display(),
+                    "Restore target is on the filesystem being restored; copying back needs room for a second copy"
                 );
-                skipped_count += 1;
             }
         }
+    }
+
+    /// Restore chunk by chunk, placing each chunk and clearing the staging dir before the next
+    async fn execute_chunked_restoration(
+        &self,
+        selected_host: &str,
+        selected_repos: &[RepositorySelectionItem],
+        selected_timestamp: &DateTime<Utc>,
+        dest_dir: &Path,
+        chunks: &[Vec<usize>],
+    ) -> Result<(), BackupServiceError> {
+        warn!(
+            chunk_count = %chunks.len(),
+            "Selection exceeds RESTORE_STAGING_MAX_SIZE, restoring in chunks"
+        );
+        // Leaving files in place is not possible: staging is emptied between chunks
+        let actions = vec![t("action-copy"), t("action-move")];
+        let selection = match self.action {
+            Some(PostRestoreAction::Leave) | None if self.options.assume_yes => {
+                return Err(BackupServiceError::ConfigurationError(
+                    "A chunked restore cannot leave files in the staging directory.\n\n\
+                    Pass --action copy or --action move, or raise RESTORE_STAGING_MAX_SIZE"
+                        .to_string(),
+                ));
+            }
+            Some(PostRestoreAction::Leave) | None => {
+                select_item(&t("prompt-post-restore-chunked"), &actions, 0)?
+            }
+            Some(action) => action.index(),
+        };
+        self.transcript.record(
+            "action",
+            format!("{} per chunk ({} chunks)", actions[selection], chunks.len()),
+        );
+
+        let mut restored_total = 0;
+        let mut skipped_total = 0;
+        for (idx, chunk) in chunks.iter().enumerate() {
+            let repos: Vec<RepositorySelectionItem> =
+                chunk.iter().map(|&i| selected_repos[i].clone()).collect();
+            info!(
+                chunk = format!("({}/{})", idx + 1, chunks.len()),
+                repo_count = %repos.len(),
+                "Restoring chunk"
+            );
 
-        Ok((restored_count, skipped_count))
+            let (restored, skipped) = self
+                .restore_repositories(selected_host, &repos, selected_timestamp, dest_dir)
+                .await?;
+            restored_total += restored;
+            skipped_total += skipped;
+
+            match selection {
+                0 => {
+                    self.copy_files_to_original_locations(&repos, dest_dir)
+                        .await?
+                }
+                _ => {
+                    self.move_files_to_original_locations(&repos, dest_dir)
+                        .await?
+                }
+            }
+            // Only restored data is left: the destination was empty or wiped up front
+            empty_dir(dest_dir)?;
+        }
+        self.verify_containers(selected_repos).await?;
+
+        info!("");
+        info!("{}", t("restore-summary-header"));
+        info!(
+            "  {}",
+            t_args(
+                "restore-summary-restored",
+                &[("count", restored_total.to_string())]
+            )
+        );
+        if skipped_total > 0 {
+            info!(
+                "  {}",
+                t_args(
+                    "restore-summary-skipped",
+                    &[("count", skipped_total.to_string())]
+                )
+            );
+        }
+        Ok(())
     }
 
     /// Handle post-restoration actions
@@ -285,22 +1564,20 @@ impl RestoreWorkflow {
         selected_repos: &[RepositorySelectionItem],
         dest_dir: &Path,
     ) -> Result<(), BackupServiceError> {
-        use dialoguer::Select;
-
         info!(destination = %dest_dir.display(), "Restoration completed successfully! You can now access your restored files");
+        if let Some(file) = &self.options.file {
+            return self.place_restored_file(file, dest_dir).await;
+        }
 
         info!("");
-        let actions = vec![
-            "Copy to original location (replace existing files)",
-            "Move to original location (replace existing files)",
-            "Leave files in temporary location",
-        ];
+        let actions = vec![t("action-copy"), t("action-move"), t("action-leave")];
 
-        let selection = Select::new()
-            .with_prompt("What would you like to do with the restored files?")
-            .items(&actions)
-            .default(2)
-            .interact()?;
+        let selection = match self.action {
+            Some(action) => action.index(),
+            None if self.options.assume_yes => PostRestoreAction::Leave.index(),
+            None => select_item(&t("prompt-post-restore"), &actions, 2)?,
+        };
+        self.transcript.record("action", actions[selection].clone());
 
         match selection {
             0 => {
@@ -312,10 +1589,157 @@ impl RestoreWorkflow {
                     .await?
             }
             _ => {
+                if self.options.verify_containers {
+                    warn!("Container verification needs the files in place, skipping it");
+                }
                 info!(location = %dest_dir.display(), "Files remain at temporary location");
+                self.transcript
+                    .record("result", format!("files left at {}", dest_dir.display()));
+            }
+        }
+        if selection < 2 {
+            self.verify_containers(selected_repos).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Put a single restored file (`--file`) at `--to` or its original path
+    ///
+    /// Unlike a whole repository, only that file or directory is replaced. Interactive runs
+    /// confirm first; `--action` decides without asking and `--yes` alone leaves it staged.
+    async fn place_restored_file(
+        &self,
+        file: &Path,
+        dest_dir: &Path,
+    ) -> Result<(), BackupServiceError> {
+        let staged = dest_dir.join(file.strip_prefix("/").unwrap_or(file));
+        if !staged.exists() {
+            return Err(BackupServiceError::CommandFailed(format!(
+                "{} is not part of the selected snapshot.\n\nBrowse the snapshot with `restic-backup-service ls {}`",
+                file.display(),
+                file.parent().unwrap_or(file).display()
+            )));
+        }
+        let dst = self.options.file_to.as_deref().unwrap_or(file);
+        let action = match self.action {
+            Some(action) => action,
+            None if self.options.assume_yes => PostRestoreAction::Leave,
+            None => {
+                let replaces = dst.exists() && self.overwrite == OverwritePolicy::Replace;
+                let key = if replaces {
+                    "prompt-place-file-overwrite"
+                } else {
+                    "prompt-place-file"
+                };
+                let prompt = t_args(key, &[("path", dst.display().to_string())]);
+                if confirm_action(&prompt, !replaces).await? {
+                    PostRestoreAction::Copy
+                } else {
+                    PostRestoreAction::Leave
+                }
             }
+        };
+        self.transcript
+            .record("action", action.as_str().to_string());
+        if action == PostRestoreAction::Leave {
+            info!(location = %staged.display(), "Restored file remains at temporary location");
+            self.transcript
+                .record("result", format!("file left at {}", staged.display()));
+            return Ok(());
         }
 
+        if self.make_room(dst)? {
+            copy_recursively(&staged, dst)?;
+        } else {
+            self.merge_missing(&staged, dst)?;
+        }
+        if action == PostRestoreAction::Move {
+            remove_path(&staged)?;
+        }
+        info!(path = %dst.display(), "Restored file placed");
+        self.transcript.record(
+            "copied",
+            format!("{} -> {}", staged.display(), dst.display()),
+        );
+        Ok(())
+    }
+
+    /// Clear the way for restored data at `dst` as `--overwrite` says; `false` means skip:
+    /// the existing content stays and only missing files are added
+    fn make_room(&self, dst: &Path) -> Result<bool, BackupServiceError> {
+        if fs::symlink_metadata(dst).is_err() {
+            return Ok(true);
+        }
+        match self.overwrite {
+            OverwritePolicy::Replace => {
+                self.transcript
+                    .record("overwrite", dst.display().to_string());
+                remove_path(dst)?;
+                Ok(true)
+            }
+            OverwritePolicy::KeepBoth => {
+                let aside = pre_restore_path(dst, Local::now());
+                fs::rename(dst, &aside).map_err(|e| {
+                    BackupServiceError::CommandFailed(format!(
+                        "Failed to rename existing '{}' to '{}': {}",
+                        dst.display(),
+                        aside.display(),
+                        e
+                    ))
+                })?;
+                info!(existing = %aside.display(), "Kept existing content");
+                self.transcript
+                    .record("kept", format!("{} -> {}", dst.display(), aside.display()));
+                Ok(true)
+            }
+            OverwritePolicy::Skip => Ok(false),
+        }
+    }
+
+    /// Add the restored files that do not exist at `dst` yet (`--overwrite skip`)
+    fn merge_missing(&self, src: &Path, dst: &Path) -> Result<(), BackupServiceError> {
+        let skipped = copy_missing(src, dst)?;
+        if skipped > 0 {
+            info!(path = %dst.display(), skipped = %skipped, "Left existing files untouched");
+            self.transcript.record(
+                "skipped",
+                format!("{} existing files in {}", skipped, dst.display()),
+            );
+        }
+        Ok(())
+    }
+
+    /// Start the containers of restored docker volumes (`--verify-containers`)
+    async fn verify_containers(
+        &self,
+        selected_repos: &[RepositorySelectionItem],
+    ) -> Result<(), BackupServiceError> {
+        if !self.options.verify_containers {
+            return Ok(());
+        }
+        let paths: Vec<PathBuf> = selected_repos.iter().map(|r| r.path.clone()).collect();
+        let results = verify_restored_volumes(&paths).await?;
+        for (container, health) in &results {
+            let detail = match health {
+                ContainerHealth::Unhealthy { reason } => {
+                    format!("{}: FAILED ({})", container, reason)
+                }
+                ContainerHealth::ProbeOk { url } => format!("{}: ok ({} answered)", container, url),
+                ContainerHealth::Healthy => format!("{}: ok (healthcheck)", container),
+                ContainerHealth::Running => format!("{}: ok (running, no probe)", container),
+            };
+            self.transcript.record("verify", detail);
+        }
+
+        let failed = results.iter().filter(|(_, h)| !h.is_ok()).count();
+        if failed > 0 {
+            return Err(BackupServiceError::CommandFailed(format!(
+                "Restored files are in place, but {} of {} containers did not come up",
+                failed,
+                results.len()
+            )));
+        }
         Ok(())
     }
 
@@ -352,29 +1776,14 @@ impl RestoreWorkflow {
                 })?;
             }
 
-            // Remove existing destination if it exists
-            if dst.exists() {
-                if dst.is_dir() {
-                    fs::remove_dir_all(dst).map_err(|e| {
-                        BackupServiceError::CommandFailed(format!(
-                            "Failed to remove existing directory '{}': {}",
-                            dst.display(),
-                            e
-                        ))
-                    })?;
-                } else {
-                    fs::remove_file(dst).map_err(|e| {
-                        BackupServiceError::CommandFailed(format!(
-                            "Failed to remove existing file '{}': {}",
-                            dst.display(),
-                            e
-                        ))
-                    })?;
-                }
+            if self.make_room(dst)? {
+                copy_recursively(&src, dst)?;
+            } else {
+                self.merge_missing(&src, dst)?;
             }
-
-            copy_recursively(&src, dst)?;
             info!(path = %dst.display(), "Copied");
+            self.transcript
+                .record("copied", format!("{} -> {}", src.display(), dst.display()));
         }
 
         Ok(())
@@ -386,6 +1795,9 @@ impl RestoreWorkflow {
         selected_repos: &[RepositorySelectionItem],
         dest_dir: &Path,
     ) -> Result<(), BackupServiceError> {
+        if is_filtered(&self.options) {
+            return Err(filtered_move_error());
+        }
         info!("Moving files to original locations...");
 
         for repo in selected_repos {
@@ -413,56 +1825,122 @@ impl RestoreWorkflow {
                 })?;
             }
 
-            // Remove existing destination if it exists
-            if dst.exists() {
-                if dst.is_dir() {
-                    fs::remove_dir_all(dst).map_err(|e| {
-                        BackupServiceError::CommandFailed(format!(
-                            "Failed to remove existing directory '{}': {}",
-                            dst.display(),
-                            e
-                        ))
-                    })?;
-                } else {
-                    fs::remove_file(dst).map_err(|e| {
-                        BackupServiceError::CommandFailed(format!(
-                            "Failed to remove existing file '{}': {}",
-                            dst.display(),
-                            e
-                        ))
-                    })?;
-                }
-            }
-
-            // Try rename first, fallback to copy+delete for cross-filesystem
-            if fs::rename(&src, dst).is_err() {
+            if !self.make_room(dst)? {
+                self.merge_missing(&src, dst)?;
+                remove_path(&src)?;
+            } else if fs::rename(&src, dst).is_err() {
+                // Cross-filesystem: copy, then delete the staged copy
                 copy_recursively(&src, dst)?;
-                if src.is_dir() {
-                    fs::remove_dir_all(&src).map_err(|e| {
-                        BackupServiceError::CommandFailed(format!(
-                            "Failed to clean up source '{}': {}",
-                            src.display(),
-                            e
-                        ))
-                    })?;
-                } else {
-                    fs::remove_file(&src).map_err(|e| {
-                        BackupServiceError::CommandFailed(format!(
-                            "Failed to clean up source '{}': {}",
-                            src.display(),
-                            e
-                        ))
-                    })?;
-                }
+                remove_path(&src)?;
             }
             info!(path = %dst.display(), "Moved");
+            self.transcript
+                .record("moved", format!("{} -> {}", src.display(), dst.display()));
         }
 
-        fs::remove_dir_all(dest_dir).ok();
+        // The staging directory goes; a --target stays, emptied of the moved data's parents
+        if self.options.target.is_some() {
+            empty_dir(dest_dir).ok();
+        } else {
+            fs::remove_dir_all(dest_dir).ok();
+        }
         Ok(())
     }
 }
 
+/// Resolve `--at` into a concrete start time: `HH:MM` means the next occurrence
+/// of that local time (today or tomorrow), otherwise an RFC 3339 timestamp
+fn resolve_start_time(
+    spec: &str,
+    now: DateTime<Local>,
+) -> Result<DateTime<Local>, BackupServiceError> {
+    if let Ok(time) = NaiveTime::parse_from_str(spec.trim(), "%H:%M") {
+        let today = now.date_naive().and_time(time);
+        let candidate = if today > now.naive_local() {
+            today
+        } else {
+            today + Duration::days(1)
+        };
+        return Local
+            .from_local_datetime(&candidate)
+            .earliest()
+            .ok_or_else(|| {
+                BackupServiceError::ConfigurationError(format!(
+                    "Start time {} does not exist in the local time zone",
+                    spec
+                ))
+            });
+    }
+
+    DateTime::parse_from_rfc3339(spec.trim())
+        .map(|dt| dt.with_timezone(&Local))
+        .map_err(|_| {
+            BackupServiceError::ConfigurationError(format!(
+                "Invalid --at value '{}': expected HH:MM or an RFC 3339 timestamp",
+                spec
+            ))
+        })
+}
+
+/// A non-empty `--target` is the user's directory: it is only cleared with `--wipe-target`
+fn check_target_clearable(
+    options: &RestoreOptions,
+    dest_dir: &Path,
+) -> Result<(), BackupServiceError> {
+    if options.target.is_some() && !options.wipe_target {
+        return Err(BackupServiceError::ConfigurationError(format!(
+            "Target directory {} is not empty.\n\nRestore into an empty directory, or pass --wipe-target to delete its contents first",
+            dest_dir.display()
+        )));
+    }
+    Ok(())
+}
+
+/// Delete everything inside a directory, keeping the directory itself (it may be a mount
+/// point or carry permissions of its own)
+fn empty_dir(dir: &Path) -> Result<(), BackupServiceError> {
+    for entry in fs::read_dir(dir)? {
+        remove_path(&entry?.path())?;
+    }
+    Ok(())
+}
+
+/// Delete a file or a directory tree
+fn remove_path(path: &Path) -> Result<(), BackupServiceError> {
+    let result = if path.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    };
+    result.map_err(|e| {
+        BackupServiceError::CommandFailed(format!("Failed to remove '{}': {}", path.display(), e))
+    })
+}
+
+/// Copy what is missing at `dst`, leaving every existing entry alone; returns how many
+/// restored files were skipped
+fn copy_missing(src: &Path, dst: &Path) -> Result<usize, BackupServiceError> {
+    if fs::symlink_metadata(dst).is_err() {
+        copy_recursively(src, dst)?;
+        return Ok(0);
+    }
+    if !(src.is_dir() && dst.is_dir()) {
+        return Ok(1);
+    }
+    let mut skipped = 0;
+    for entry in fs::read_dir(src).map_err(|e| {
+        BackupServiceError::CommandFailed(format!(
+            "Failed to read directory '{}': {}",
+            src.display(),
+            e
+        ))
+    })? {
+        let entry = entry?;
+        skipped += copy_missing(&entry.path(), &dst.join(entry.file_name()))?;
+    }
+    Ok(skipped)
+}
+
 /// Recursively copy files and directories
 fn copy_recursively(src: &Path, dst: &Path) -> Result<(), BackupServiceError> {
     if src.is_dir() {
@@ -514,6 +1992,272 @@ mod tests {
     use std::fs;
     use tempfile::tempdir;
 
+    #[test]
+    fn test_resolve_start_time_later_today() -> Result<(), BackupServiceError> {
+        let now = Local.with_ymd_and_hms(2025, 1, 15, 1, 0, 0).unwrap();
+        let start = resolve_start_time("02:00", now)?;
+        assert_eq!(start, Local.with_ymd_and_hms(2025, 1, 15, 2, 0, 0).unwrap());
+        Ok(())
+    }
+
+    #[test]
+    fn test_resolve_start_time_rolls_over_to_tomorrow() -> Result<(), BackupServiceError> {
+        let now = Local.with_ymd_and_hms(2025, 1, 15, 19, 0, 0).unwrap();
+        let start = resolve_start_time("02:00", now)?;
+        assert_eq!(start, Local.with_ymd_and_hms(2025, 1, 16, 2, 0, 0).unwrap());
+        Ok(())
+    }
+
+    #[test]
+    fn test_resolve_start_time_rfc3339_and_invalid() -> Result<(), BackupServiceError> {
+        let now = Local.with_ymd_and_hms(2025, 1, 15, 19, 0, 0).unwrap();
+        let start = resolve_start_time("2025-01-16T02:30:00Z", now)?;
+        assert_eq!(
+            start.with_timezone(&Utc).to_rfc3339(),
+            "2025-01-16T02:30:00+00:00"
+        );
+
+        assert!(resolve_start_time("25:00", now).is_err());
+        assert!(resolve_start_time("tonight", now).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_select_snapshot_prefers_window_then_prior() {
+        let at = |s: &str| s.parse::<DateTime<Utc>>().unwrap();
+        let repo = RepositorySelectionItem {
+            path: PathBuf::from("/etc"),
+            repo_subpath: "system/etc".to_string(),
+            category: "system".to_string(),
+            snapshots: [
+                "2025-01-14T02:00:00Z",
+                "2025-01-15T02:00:00Z",
+                "2025-01-15T02:03:00Z",
+            ]
+            .iter()
+            .enumerate()
+            .map(|(i, t)| SnapshotItem {
+                id: format!("snap{}", i),
+                time: at(t),
+            })
+            .collect(),
+        };
+
+        let id = |ts: &str| select_snapshot(&repo, &at(ts)).map(|s| s.id.clone());
+        assert_eq!(id("2025-01-15T02:00:00Z").as_deref(), Some("snap2"));
+        assert_eq!(id("2025-01-14T12:00:00Z").as_deref(), Some("snap0"));
+        assert_eq!(id("2025-01-13T00:00:00Z"), None);
+    }
+
+    #[test]
+    fn test_post_restore_action_parse() -> Result<(), BackupServiceError> {
+        assert_eq!(PostRestoreAction::parse("Copy")?, PostRestoreAction::Copy);
+        assert_eq!(PostRestoreAction::parse(" move ")?, PostRestoreAction::Move);
+        assert_eq!(PostRestoreAction::parse("leave")?.index(), 2);
+        assert!(PostRestoreAction::parse("delete").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_plan_staging_chunks() {
+        assert_eq!(
+            plan_staging_chunks(&[10, 20, 30], 100),
+            Ok(vec![vec![0, 1, 2]])
+        );
+        assert_eq!(
+            plan_staging_chunks(&[60, 30, 20, 100, 0], 100),
+            Ok(vec![vec![0, 1], vec![2], vec![3, 4]])
+        );
+        assert_eq!(plan_staging_chunks(&[10, 101, 5], 100), Err(1));
+        assert_eq!(plan_staging_chunks(&[], 100), Ok(vec![]));
+
+        let sizes = [60, 30, 20, 100, 0];
+        assert_eq!(largest_chunk_size(&sizes, &[vec![0, 1, 2, 3, 4]]), 210);
+        assert_eq!(
+            largest_chunk_size(&sizes, &[vec![0, 1], vec![2], vec![3, 4]]),
+            100
+        );
+    }
+
+    #[test]
+    fn test_restore_filters() {
+        let options = RestoreOptions {
+            include: vec![
+                "Documents/**/*.docx".to_string(),
+                "/home/tim/.ssh".to_string(),
+            ],
+            exclude: vec!["*.tmp".to_string()],
+            ..RestoreOptions::default()
+        };
+        assert!(is_filtered(&options));
+        assert_eq!(
+            filter_args(&options),
+            [
+                "--include",
+                "Documents/**/*.docx",
+                "--include",
+                "/home/tim/.ssh",
+                "--exclude",
+                "*.tmp"
+            ]
+        );
+        assert!(!is_filtered(&RestoreOptions::default()));
+    }
+
+    #[test]
+    fn test_single_file_selects_its_repository() {
+        let repo = |path: &str, repo_subpath: &str| RepositorySelectionItem {
+            path: PathBuf::from(path),
+            repo_subpath: repo_subpath.to_string(),
+            category: "user_home".to_string(),
+            snapshots: Vec::new(),
+        };
+        let repos = vec![
+            repo("/home/tim", "user_home/tim"),
+            repo("/home/tim/Games", "user_home/tim/Games"),
+            repo("/home/anna", "user_home/anna"),
+        ];
+        let file = Path::new("/home/tim/Documents/report.docx");
+        assert_eq!(
+            file_repository(&repos, file).map(|r| r.repo_subpath.as_str()),
+            Some("user_home/tim")
+        );
+        assert_eq!(
+            file_repository(&repos, Path::new("/home/tim/Games/save.dat"))
+                .map(|r| r.repo_subpath.as_str()),
+            Some("user_home/tim/Games")
+        );
+        assert!(file_repository(&repos, Path::new("/etc/hosts")).is_none());
+
+        let options = RestoreOptions {
+            file: Some(file.to_path_buf()),
+            ..RestoreOptions::default()
+        };
+        assert!(is_filtered(&options));
+        assert_eq!(
+            filter_args(&options),
+            ["--include", "/home/tim/Documents/report.docx"]
+        );
+        assert!(filter_args(&RestoreOptions::default()).is_empty());
+    }
+
+    #[test]
+    fn test_live_restic_output_only_for_single_job_without_json() {
+        let options = RestoreOptions::default();
+        assert!(live_restic_output(&options, 1));
+        assert!(!live_restic_output(&options, 4));
+
+        let json = RestoreOptions {
+            json_output: true,
+            assume_yes: true,
+            ..RestoreOptions::default()
+        };
+        assert!(!live_restic_output(&json, 1));
+    }
+
+    #[tokio::test]
+    async fn test_json_restore_keeps_restic_output_off_stdout() -> Result<(), BackupServiceError> {
+        use crate::shared::dependencies::RESTIC_BIN_ENV_VAR;
+        use std::os::unix::fs::PermissionsExt;
+
+        // A restic that prints its usual summary line to stdout
+        let dir = tempdir()?;
+        let restic = dir.path().join("restic");
+        fs::write(&restic, "#!/bin/sh\necho 'restored 3 files, 0 B in 0:00'\n")?;
+        fs::set_permissions(&restic, fs::Permissions::from_mode(0o755))?;
+        // SAFETY: no other test reads RESTIC_BIN or spawns restic
+        unsafe { std::env::set_var(RESTIC_BIN_ENV_VAR, &restic) };
+
+        let config = Config {
+            restic_password: "test".to_string(),
+            restic_repo_base: "s3:https://test.com/bucket".to_string(),
+            aws_access_key_id: "test".to_string(),
+            aws_secret_access_key: "test".to_string(),
+            aws_default_region: "auto".to_string(),
+            aws_s3_endpoint: "https://test.com".to_string(),
+            backup_paths: vec![],
+            hostname: "test-host".to_string(),
+        };
+        let restic_cmd =
+            ResticCommandExecutor::new(config, "s3:https://test.com/bucket/h/r".to_string())?;
+        let options = RestoreOptions {
+            json_output: true,
+            assume_yes: true,
+            ..RestoreOptions::default()
+        };
+        let output = restic_cmd
+            .restore(
+                "abc123",
+                "/srv/app",
+                &dir.path().to_string_lossy(),
+                &[],
+                live_restic_output(&options, 1),
+            )
+            .await;
+        unsafe { std::env::remove_var(RESTIC_BIN_ENV_VAR) };
+
+        // Captured, so it never reaches the stdout that carries the JSON document
+        assert_eq!(output?.trim(), "restored 3 files, 0 B in 0:00");
+        Ok(())
+    }
+
+    #[test]
+    fn test_non_empty_target_needs_wipe_target() -> Result<(), BackupServiceError> {
+        let dir = tempdir()?;
+        fs::create_dir(dir.path().join("keep"))?;
+        fs::write(dir.path().join("keep/data.db"), "x")?;
+
+        let target = RestoreOptions {
+            target: Some(dir.path().to_path_buf()),
+            assume_yes: true,
+            ..RestoreOptions::default()
+        };
+        let err = check_target_clearable(&target, dir.path()).unwrap_err();
+        assert!(matches!(err, BackupServiceError::ConfigurationError(_)));
+        assert!(err.to_string().contains("--wipe-target"));
+        assert!(dir.path().join("keep/data.db").exists());
+
+        // The staging directory is ours, and --wipe-target is an explicit yes
+        assert!(check_target_clearable(&RestoreOptions::default(), dir.path()).is_ok());
+        let wipe = RestoreOptions {
+            wipe_target: true,
+            ..target
+        };
+        assert!(check_target_clearable(&wipe, dir.path()).is_ok());
+
+        empty_dir(dir.path())?;
+        assert!(dir.path().is_dir());
+        assert_eq!(fs::read_dir(dir.path())?.count(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_prefetch_staging_layout() {
+        let (staging, marker) = prefetch_staging(Path::new("/var/tmp/p"), "nas", "abc123");
+        assert_eq!(staging, PathBuf::from("/var/tmp/p/nas/abc123"));
+        assert_eq!(marker, PathBuf::from("/var/tmp/p/nas/abc123.complete"));
+    }
+
+    #[test]
+    fn test_dry_run_preview_from_summary() {
+        let summary: serde_json::Value = serde_json::from_str(
+            r#"{"message_type": "summary", "seconds_elapsed": 1, "total_files": 120,
+                "files_restored": 7, "files_skipped": 113, "total_bytes": 5242880,
+                "bytes_restored": 40960, "bytes_skipped": 5201920}"#,
+        )
+        .unwrap();
+        let preview = DryRunPreview {
+            path: "/home/tim".to_string(),
+            ..DryRunPreview::default()
+        }
+        .with_summary(&summary);
+        assert_eq!(preview.total_files, 120);
+        assert_eq!(preview.total_bytes, 5242880);
+        assert_eq!(preview.changed_files, 7);
+        assert_eq!(preview.changed_bytes, 40960);
+        assert_eq!(preview.path, "/home/tim");
+    }
+
     #[test]
     fn test_copy_recursively_basic() -> Result<(), BackupServiceError> {
         let src_dir = tempdir().unwrap();
@@ -536,6 +2280,40 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_overwrite_policies() -> Result<(), BackupServiceError> {
+        assert_eq!(
+            OverwritePolicy::parse("keep-both")?,
+            OverwritePolicy::KeepBoth
+        );
+        assert_eq!(OverwritePolicy::parse("Skip")?, OverwritePolicy::Skip);
+        assert!(OverwritePolicy::parse("merge").is_err());
+
+        let now = Local.with_ymd_and_hms(2025, 1, 15, 14, 5, 9).unwrap();
+        assert_eq!(
+            pre_restore_path(Path::new("/home/tim/Documents"), now),
+            PathBuf::from("/home/tim/Documents.pre-restore-20250115-140509")
+        );
+
+        // skip adds missing files and leaves existing ones alone
+        let src_dir = tempdir().unwrap();
+        let dst_dir = tempdir().unwrap();
+        fs::create_dir_all(src_dir.path().join("sub")).unwrap();
+        fs::write(src_dir.path().join("kept.txt"), "restored").unwrap();
+        fs::write(src_dir.path().join("sub/new.txt"), "restored").unwrap();
+        fs::write(dst_dir.path().join("kept.txt"), "current").unwrap();
+        assert_eq!(copy_missing(src_dir.path(), dst_dir.path())?, 1);
+        assert_eq!(
+            fs::read_to_string(dst_dir.path().join("kept.txt")).unwrap(),
+            "current"
+        );
+        assert_eq!(
+            fs::read_to_string(dst_dir.path().join("sub/new.txt")).unwrap(),
+            "restored"
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_copy_recursively_nested() -> Result<(), BackupServiceError> {
         let src_dir = tempdir().unwrap();
@@ -0,0 +1,415 @@
+use crate::errors::BackupServiceError;
+use crate::shared::constants::{CATEGORY_DOCKER_VOLUME, CATEGORY_SYSTEM, CATEGORY_USER_HOME};
+use std::collections::BTreeMap;
+
+/// How snapshots are spread over restic repositories
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RepoLayout {
+    /// One repository per backed-up path (current layout)
+    PerPath,
+    /// All paths of a host share one repository (proposed layout)
+    Shared,
+}
+
+impl RepoLayout {
+    pub fn parse(value: &str) -> Result<Self, BackupServiceError> {
+        match value.trim().to_lowercase().as_str() {
+            "per-path" | "per_path" => Ok(RepoLayout::PerPath),
+            "shared" => Ok(RepoLayout::Shared),
+            other => Err(BackupServiceError::ConfigurationError(format!(
+                "Unknown repository layout: {}.\n\nValid layouts are: per-path, shared",
+                other
+            ))),
+        }
+    }
+
+    /// Layout from REPO_LAYOUT, defaulting to one repository per path
+    pub fn from_env() -> Result<Self, BackupServiceError> {
+        match std::env::var("REPO_LAYOUT") {
+            Ok(v) if !v.trim().is_empty() => Self::parse(&v),
+            _ => Ok(RepoLayout::PerPath),
+        }
+    }
+}
+
+/// Snapshot grouping passed to `restic forget --group-by`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GroupBy {
+    pub host: bool,
+    pub paths: bool,
+    pub tags: bool,
+}
+
+impl GroupBy {
+    /// Parse restic's comma-separated syntax (`host,paths,tags`)
+    pub fn parse(value: &str) -> Result<Self, BackupServiceError> {
+        let mut group_by = GroupBy {
+            host: false,
+            paths: false,
+            tags: false,
+        };
+        for part in value.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            match part {
+                "host" => group_by.host = true,
+                "paths" => group_by.paths = true,
+                "tags" => group_by.tags = true,
+                other => {
+                    return Err(BackupServiceError::ConfigurationError(format!(
+                        "Unknown --group-by field: {}.\n\nValid fields are: host, paths, tags",
+                        other
+                    )));
+                }
+            }
+        }
+        Ok(group_by)
+    }
+
+    /// Grouping that keeps every path's history separate for the given layout
+    ///
+    /// In a shared repository the category tag is added as well, so a path that moved
+    /// between categories keeps two independent histories.
+    pub fn default_for(layout: RepoLayout) -> Self {
+        GroupBy {
+            host: true,
+            paths: true,
+            tags: layout == RepoLayout::Shared,
+        }
+    }
+
+    /// Resolve CLI value > RETENTION_GROUP_BY > layout default, refusing path-collapsing groupings
+    pub fn resolve(cli: Option<&str>, layout: RepoLayout) -> Result<Self, BackupServiceError> {
+        let env_value = std::env::var("RETENTION_GROUP_BY")
+            .ok()
+            .filter(|v| !v.trim().is_empty());
+        let group_by = match cli.or(env_value.as_deref()) {
+            Some(value) => Self::parse(value)?,
+            None => return Ok(Self::default_for(layout)),
+        };
+        group_by.validate(layout)?;
+        Ok(group_by)
+    }
+
+    /// Without `paths`, keep-* rules count snapshots of different paths as one history
+    pub fn validate(&self, layout: RepoLayout) -> Result<(), BackupServiceError> {
+        if !self.paths {
+            return Err(BackupServiceError::ConfigurationError(format!(
+                "--group-by {} would merge the snapshot histories of different paths.\n\n\
+                Include 'paths' (default for this layout: {})",
+                self.as_arg(),
+                Self::default_for(layout).as_arg()
+            )));
+        }
+        Ok(())
+    }
+
+    pub fn as_arg(&self) -> String {
+        [
+            (self.host, "host"),
+            (self.paths, "paths"),
+            (self.tags, "tags"),
+        ]
+        .iter()
+        .filter(|(enabled, _)| *enabled)
+        .map(|(_, name)| *name)
+        .collect::<Vec<_>>()
+        .join(",")
+    }
+}
+
+/// restic keep-* rules applied by `forget`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RetentionPolicy {
+    pub keep_last: Option<u32>,
+    pub keep_daily: Option<u32>,
+    pub keep_weekly: Option<u32>,
+    pub keep_monthly: Option<u32>,
+    pub keep_yearly: Option<u32>,
+}
+
+impl RetentionPolicy {
+    /// Parse a compact rule list such as `daily=7,weekly=4,monthly=12`
+    pub fn parse(value: &str) -> Result<Self, BackupServiceError> {
+        let mut policy = RetentionPolicy::default();
+        for part in value.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let parsed = part
+                .split_once('=')
+                .and_then(|(k, v)| Some((k.trim(), v.trim().parse::<u32>().ok()?)));
+            let slot = match parsed {
+                Some(("last", _)) => &mut policy.keep_last,
+                Some(("daily", _)) => &mut policy.keep_daily,
+                Some(("weekly", _)) => &mut policy.keep_weekly,
+                Some(("monthly", _)) => &mut policy.keep_monthly,
+                Some(("yearly", _)) => &mut policy.keep_yearly,
+                _ => {
+                    return Err(BackupServiceError::ConfigurationError(format!(
+                        "Invalid retention rule: {}.\n\nExpected e.g. daily=7,weekly=4,monthly=12 (last, daily, weekly, monthly, yearly)",
+                        part
+                    )));
+                }
+            };
+            *slot = parsed.map(|(_, n)| n);
+        }
+        Ok(policy)
+    }
+
+    /// Default policy from RETENTION_POLICY (same syntax as `parse`)
+    pub fn from_env() -> Result<Self, BackupServiceError> {
+        match std::env::var("RETENTION_POLICY") {
+            Ok(v) if !v.trim().is_empty() => Self::parse(&v),
+            _ => Ok(Self::default()),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.to_args().is_empty()
+    }
+
+    pub fn to_args(&self) -> Vec<String> {
+        [
+            ("--keep-last", self.keep_last),
+            ("--keep-daily", self.keep_daily),
+            ("--keep-weekly", self.keep_weekly),
+            ("--keep-monthly", self.keep_monthly),
+            ("--keep-yearly", self.keep_yearly),
+        ]
+        .iter()
+        .filter_map(|(flag, value)| value.map(|n| [flag.to_string(), n.to_string()]))
+        .flatten()
+        .collect()
+    }
+
+    /// Full `forget` argument list with explicit grouping
+    pub fn forget_args(&self, group_by: &GroupBy) -> Vec<String> {
+        let mut args = vec!["--group-by".to_string(), group_by.as_arg()];
+        args.extend(self.to_args());
+        args
+    }
+}
+
+/// keep-* rules per repository category, with one policy for categories without their own
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CategoryRetention {
+    pub default: RetentionPolicy,
+    pub categories: BTreeMap<String, RetentionPolicy>,
+}
+
+impl CategoryRetention {
+    /// The same policy for every category (keep-* flags on the command line)
+    pub fn uniform(policy: RetentionPolicy) -> Self {
+        Self {
+            default: policy,
+            categories: BTreeMap::new(),
+        }
+    }
+
+    /// `default` plus RETENTION_POLICY_USER_HOME / _DOCKER_VOLUME / _SYSTEM where set
+    pub fn from_env(default: RetentionPolicy) -> Result<Self, BackupServiceError> {
+        let mut retention = Self::uniform(default);
+        for category in [CATEGORY_USER_HOME, CATEGORY_DOCKER_VOLUME, CATEGORY_SYSTEM] {
+            let key = format!("RETENTION_POLICY_{}", category.to_uppercase());
+            if let Ok(spec) = std::env::var(&key)
+                && !spec.trim().is_empty()
+            {
+                retention = retention.with_category(category, &spec).map_err(|e| {
+                    BackupServiceError::ConfigurationError(format!("{}: {}", key, e))
+                })?;
+            }
+        }
+        Ok(retention)
+    }
+
+    pub fn with_category(mut self, category: &str, spec: &str) -> Result<Self, BackupServiceError> {
+        self.categories
+            .insert(category.to_string(), RetentionPolicy::parse(spec)?);
+        Ok(self)
+    }
+
+    pub fn for_category(&self, category: &str) -> &RetentionPolicy {
+        self.categories.get(category).unwrap_or(&self.default)
+    }
+
+    /// `--keep-daily 7; docker_volume: --keep-daily 3` for the start-of-run log
+    pub fn describe(&self) -> String {
+        std::iter::once(self.default.to_args().join(" "))
+            .chain(
+                self.categories.iter().map(|(category, policy)| {
+                    format!("{}: {}", category, policy.to_args().join(" "))
+                }),
+            )
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+}
+
+/// Env var with tags (comma-separated) whose snapshots `forget` never removes
+pub const KEEP_TAGS_ENV_VAR: &str = "RETENTION_KEEP_TAGS";
+
+/// Which snapshots retention looks at (`--tag`) and which it must keep (`--keep-tag`)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TagSelection {
+    /// Only snapshots carrying every one of these tags are considered; the rest stay untouched
+    pub only: Vec<String>,
+    /// Snapshots carrying any of these tags are always kept
+    pub keep: Vec<String>,
+}
+
+impl TagSelection {
+    /// CLI tags; `--keep-tag` adds to RETENTION_KEEP_TAGS rather than replacing it
+    pub fn resolve(only: Vec<String>, keep: Vec<String>) -> Self {
+        let mut all_keep: Vec<String> = std::env::var(KEEP_TAGS_ENV_VAR)
+            .unwrap_or_default()
+            .split(',')
+            .chain(keep.iter().map(String::as_str))
+            .map(str::trim)
+            .filter(|t| !t.is_empty())
+            .map(str::to_string)
+            .collect();
+        all_keep.sort();
+        all_keep.dedup();
+        Self {
+            only: only
+                .into_iter()
+                .map(|t| t.trim().to_string())
+                .filter(|t| !t.is_empty())
+                .collect(),
+            keep: all_keep,
+        }
+    }
+
+    /// `forget` arguments: `--tag a,b` (restic ANDs a comma list) and one `--keep-tag` per tag
+    pub fn forget_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if !self.only.is_empty() {
+            args.extend(["--tag".to_string(), self.only.join(",")]);
+        }
+        for tag in &self.keep {
+            args.extend(["--keep-tag".to_string(), tag.clone()]);
+        }
+        args
+    }
+
+    /// Whether retention applies to a snapshot with these tags at all
+    pub fn considers(&self, tags: &[String]) -> bool {
+        self.only.iter().all(|tag| tags.contains(tag))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layout_defaults_keep_paths_separate() {
+        assert_eq!(
+            GroupBy::default_for(RepoLayout::PerPath).as_arg(),
+            "host,paths"
+        );
+        assert_eq!(
+            GroupBy::default_for(RepoLayout::Shared).as_arg(),
+            "host,paths,tags"
+        );
+    }
+
+    #[test]
+    fn test_group_by_parse_and_normalize() -> Result<(), BackupServiceError> {
+        assert_eq!(GroupBy::parse("tags, paths")?.as_arg(), "paths,tags");
+        assert!(GroupBy::parse("host,path").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_group_by_without_paths_is_rejected() -> Result<(), BackupServiceError> {
+        let err = GroupBy::parse("host,tags")?
+            .validate(RepoLayout::Shared)
+            .unwrap_err();
+        assert!(err.to_string().contains("host,paths,tags"));
+        assert!(GroupBy::parse("")?.validate(RepoLayout::PerPath).is_err());
+        assert!(
+            GroupBy::parse("paths")?
+                .validate(RepoLayout::PerPath)
+                .is_ok()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_retention_parse() -> Result<(), BackupServiceError> {
+        let policy = RetentionPolicy::parse("daily=7, monthly=12")?;
+        assert_eq!(policy.keep_daily, Some(7));
+        assert_eq!(policy.keep_monthly, Some(12));
+        assert_eq!(policy.keep_weekly, None);
+        assert!(RetentionPolicy::parse("")?.is_empty());
+        assert!(RetentionPolicy::parse("hourly=3").is_err());
+        assert!(RetentionPolicy::parse("daily=many").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_forget_args() {
+        let policy = RetentionPolicy {
+            keep_daily: Some(7),
+            keep_monthly: Some(12),
+            ..Default::default()
+        };
+        assert!(!policy.is_empty());
+        assert!(RetentionPolicy::default().is_empty());
+        assert_eq!(
+            policy.forget_args(&GroupBy::default_for(RepoLayout::PerPath)),
+            vec![
+                "--group-by",
+                "host,paths",
+                "--keep-daily",
+                "7",
+                "--keep-monthly",
+                "12"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tag_selection_forget_args() {
+        let selection = TagSelection {
+            only: vec!["games".to_string(), "large".to_string()],
+            keep: vec!["critical".to_string()],
+        };
+        assert_eq!(
+            selection.forget_args(),
+            vec!["--tag", "games,large", "--keep-tag", "critical"]
+        );
+        assert!(selection.considers(&["large".to_string(), "games".to_string()]));
+        assert!(!selection.considers(&["games".to_string()]));
+        assert!(TagSelection::default().forget_args().is_empty());
+        assert!(TagSelection::default().considers(&[]));
+    }
+
+    #[test]
+    fn test_category_retention() -> Result<(), BackupServiceError> {
+        let retention = CategoryRetention::uniform(RetentionPolicy::parse("daily=30,monthly=12")?)
+            .with_category(CATEGORY_DOCKER_VOLUME, "daily=7")?;
+        assert_eq!(
+            retention.for_category(CATEGORY_DOCKER_VOLUME).to_args(),
+            vec!["--keep-daily", "7"]
+        );
+        assert_eq!(
+            retention.for_category(CATEGORY_USER_HOME).to_args(),
+            vec!["--keep-daily", "30", "--keep-monthly", "12"]
+        );
+        assert_eq!(
+            retention.describe(),
+            "--keep-daily 30 --keep-monthly 12; docker_volume: --keep-daily 7"
+        );
+
+        // A category policy alone leaves the other categories on plain prune
+        let only_docker =
+            CategoryRetention::default().with_category(CATEGORY_DOCKER_VOLUME, "daily=7")?;
+        assert!(!only_docker.for_category(CATEGORY_DOCKER_VOLUME).is_empty());
+        assert!(only_docker.for_category(CATEGORY_SYSTEM).is_empty());
+        assert!(
+            CategoryRetention::default()
+                .with_category(CATEGORY_SYSTEM, "hourly=1")
+                .is_err()
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,386 @@
+use crate::errors::BackupServiceError;
+use crate::shared::logs_workflow::parse_since;
+use crate::shared::retention::GroupBy;
+use chrono::{DateTime, Datelike, Duration, TimeZone, Utc};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Calendar period used by bucketed rules
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Period {
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+impl Period {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "daily" => Some(Period::Day),
+            "weekly" => Some(Period::Week),
+            "monthly" => Some(Period::Month),
+            "yearly" => Some(Period::Year),
+            _ => None,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Period::Day => "daily",
+            Period::Week => "weekly",
+            Period::Month => "monthly",
+            Period::Year => "yearly",
+        }
+    }
+
+    /// Bucket of `time` in the time zone of `zone` (restic also buckets in local time)
+    fn key<Tz: TimeZone>(&self, time: DateTime<Utc>, zone: &Tz) -> (i32, u32) {
+        let local = time.with_timezone(zone);
+        match self {
+            Period::Day => (local.year(), local.ordinal()),
+            Period::Week => {
+                let week = local.iso_week();
+                (week.year(), week.week())
+            }
+            Period::Month => (local.year(), local.month()),
+            Period::Year => (local.year(), 0),
+        }
+    }
+}
+
+/// How many periods a rule covers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Count {
+    Recent(u32),
+    Forever,
+}
+
+/// One keep rule; a snapshot is kept when any rule keeps it
+#[derive(Debug, Clone, PartialEq)]
+pub enum Rule {
+    /// `last=N`: the N newest snapshots
+    Last(u32),
+    /// `daily=N` etc.: the newest snapshot of each of the N most recent periods (restic keep-*)
+    LastPerPeriod(Period, Count),
+    /// `first-monthly=all` etc.: the oldest snapshot of each period
+    FirstPerPeriod(Period, Count),
+    /// `tag:NAME`: every snapshot carrying the tag
+    Tag(String),
+    /// `within=30d`: every snapshot younger than the duration
+    Within(Duration),
+}
+
+impl Rule {
+    fn label(&self) -> String {
+        match self {
+            Rule::Last(n) => format!("last={}", n),
+            Rule::LastPerPeriod(period, count) => {
+                format!("{}={}", period.name(), count_label(*count))
+            }
+            Rule::FirstPerPeriod(period, count) => {
+                format!("first-{}={}", period.name(), count_label(*count))
+            }
+            Rule::Tag(tag) => format!("tag:{}", tag),
+            Rule::Within(duration) => format!("within={}h", duration.num_hours()),
+        }
+    }
+}
+
+fn count_label(count: Count) -> String {
+    match count {
+        Count::Recent(n) => n.to_string(),
+        Count::Forever => "all".to_string(),
+    }
+}
+
+/// Client-side retention rules evaluated into explicit forget lists
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RetentionRules {
+    pub rules: Vec<Rule>,
+}
+
+/// A snapshot as seen by the rule engine
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuleSnapshot {
+    pub id: String,
+    pub time: DateTime<Utc>,
+    pub tags: Vec<String>,
+    /// Grouping key (host/paths/tags as selected by `--group-by`)
+    pub group: String,
+}
+
+impl RuleSnapshot {
+    /// Build from `restic snapshots --json` output
+    pub fn from_json(value: &Value, group_by: &GroupBy) -> Option<Self> {
+        let strings = |field: &str| -> Vec<String> {
+            value[field]
+                .as_array()
+                .map(|items| {
+                    items
+                        .iter()
+                        .filter_map(|v| v.as_str().map(str::to_string))
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+        let tags = strings("tags");
+        let mut group = Vec::new();
+        if group_by.host {
+            group.push(value["hostname"].as_str().unwrap_or_default().to_string());
+        }
+        if group_by.paths {
+            group.push(strings("paths").join(","));
+        }
+        if group_by.tags {
+            group.push(tags.join(","));
+        }
+        Some(Self {
+            id: value["id"].as_str()?.to_string(),
+            time: value["time"].as_str()?.parse().ok()?,
+            tags,
+            group: group.join(" "),
+        })
+    }
+}
+
+/// Outcome for one repository: what stays (with the rules that keep it) and what goes
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RetentionDecision {
+    pub keep: BTreeMap<String, Vec<String>>,
+    pub forget: Vec<String>,
+}
+
+impl RetentionRules {
+    /// Parse e.g. `first-monthly=all,tag:pre-upgrade,daily=7,within=14d`
+    pub fn parse(value: &str) -> Result<Self, BackupServiceError> {
+        let mut rules = Vec::new();
+        for part in value.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            rules.push(Self::parse_rule(part).ok_or_else(|| {
+                BackupServiceError::ConfigurationError(format!(
+                    "Invalid retention rule: {}.\n\nExpected e.g. first-monthly=all,tag:pre-upgrade,daily=7,within=14d \
+                    (last=N; daily|weekly|monthly|yearly=N|all; first-<period>=N|all; tag:NAME; within=DURATION)",
+                    part
+                ))
+            })?);
+        }
+        Ok(Self { rules })
+    }
+
+    fn parse_rule(part: &str) -> Option<Rule> {
+        if let Some(tag) = part.strip_prefix("tag:") {
+            let tag = tag.trim();
+            return (!tag.is_empty()).then(|| Rule::Tag(tag.to_string()));
+        }
+        let (name, value) = part.split_once('=')?;
+        let (name, value) = (name.trim(), value.trim());
+        let count = || match value {
+            "all" | "forever" => Some(Count::Forever),
+            n => n.parse().ok().filter(|n| *n > 0).map(Count::Recent),
+        };
+        match name {
+            "last" => value.parse().ok().filter(|n| *n > 0).map(Rule::Last),
+            "within" => parse_since(value).ok().map(Rule::Within),
+            _ => match name.strip_prefix("first-") {
+                Some(period) => Some(Rule::FirstPerPeriod(Period::parse(period)?, count()?)),
+                None => Some(Rule::LastPerPeriod(Period::parse(name)?, count()?)),
+            },
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    pub fn describe(&self) -> String {
+        self.rules
+            .iter()
+            .map(Rule::label)
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    /// Decide per group; the newest snapshot of every group is always kept
+    pub fn evaluate<Tz: TimeZone>(
+        &self,
+        snapshots: &[RuleSnapshot],
+        now: DateTime<Tz>,
+    ) -> RetentionDecision {
+        let mut groups: BTreeMap<&str, Vec<&RuleSnapshot>> = BTreeMap::new();
+        for snapshot in snapshots {
+            groups.entry(&snapshot.group).or_default().push(snapshot);
+        }
+
+        let mut decision = RetentionDecision::default();
+        for (_, mut group) in groups {
+            // Newest first, like restic
+            group.sort_by(|a, b| b.time.cmp(&a.time).then_with(|| a.id.cmp(&b.id)));
+            let mut reasons: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+            for rule in &self.rules {
+                for id in self.kept_by(rule, &group, &now) {
+                    reasons.entry(id).or_default().push(rule.label());
+                }
+            }
+            if let Some(newest) = group.first() {
+                reasons
+                    .entry(&newest.id)
+                    .or_insert_with(|| vec!["newest".to_string()]);
+            }
+
+            for snapshot in &group {
+                match reasons.remove(snapshot.id.as_str()) {
+                    Some(why) => {
+                        decision.keep.insert(snapshot.id.clone(), why);
+                    }
+                    None => decision.forget.push(snapshot.id.clone()),
+                }
+            }
+        }
+        decision
+    }
+
+    /// IDs one rule keeps within a newest-first group
+    fn kept_by<'a, Tz: TimeZone>(
+        &self,
+        rule: &Rule,
+        group: &[&'a RuleSnapshot],
+        now: &DateTime<Tz>,
+    ) -> Vec<&'a str> {
+        let zone = now.timezone();
+        match rule {
+            Rule::Last(n) => group
+                .iter()
+                .take(*n as usize)
+                .map(|s| s.id.as_str())
+                .collect(),
+            Rule::Tag(tag) => group
+                .iter()
+                .filter(|s| s.tags.iter().any(|t| t == tag))
+                .map(|s| s.id.as_str())
+                .collect(),
+            Rule::Within(duration) => {
+                let cutoff = now.with_timezone(&Utc) - *duration;
+                group
+                    .iter()
+                    .filter(|s| s.time >= cutoff)
+                    .map(|s| s.id.as_str())
+                    .collect()
+            }
+            Rule::LastPerPeriod(period, count) => {
+                let picked = pick_per_period(group.iter().copied(), *period, &zone);
+                limit(picked, *count)
+            }
+            Rule::FirstPerPeriod(period, count) => {
+                // Oldest first picks each period's first snapshot; the limit counts recent periods
+                let mut picked = pick_per_period(group.iter().rev().copied(), *period, &zone);
+                picked.reverse();
+                limit(picked, *count)
+            }
+        }
+    }
+}
+
+/// First snapshot seen per period, in iteration order
+fn pick_per_period<'a, Tz: TimeZone>(
+    snapshots: impl Iterator<Item = &'a RuleSnapshot>,
+    period: Period,
+    zone: &Tz,
+) -> Vec<&'a str> {
+    let mut seen = BTreeSet::new();
+    snapshots
+        .filter(|s| seen.insert(period.key(s.time, zone)))
+        .map(|s| s.id.as_str())
+        .collect()
+}
+
+/// Keep the first `count` entries of a newest-first list
+fn limit(picked: Vec<&str>, count: Count) -> Vec<&str> {
+    match count {
+        Count::Forever => picked,
+        Count::Recent(n) => picked.into_iter().take(n as usize).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(id: &str, time: &str, tags: &[&str]) -> RuleSnapshot {
+        RuleSnapshot {
+            id: id.to_string(),
+            time: DateTime::parse_from_rfc3339(time)
+                .unwrap()
+                .with_timezone(&Utc),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            group: "web1 /etc".to_string(),
+        }
+    }
+
+    fn now() -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2025-03-20T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn history() -> Vec<RuleSnapshot> {
+        vec![
+            snapshot("jan-02", "2025-01-02T02:00:00Z", &[]),
+            snapshot("jan-15", "2025-01-15T02:00:00Z", &["pre-upgrade"]),
+            snapshot("feb-01", "2025-02-01T02:00:00Z", &[]),
+            snapshot("feb-20", "2025-02-20T02:00:00Z", &[]),
+            snapshot("mar-18", "2025-03-18T02:00:00Z", &[]),
+            snapshot("mar-19", "2025-03-19T02:00:00Z", &[]),
+        ]
+    }
+
+    #[test]
+    fn test_first_of_month_forever_and_tagged() -> Result<(), BackupServiceError> {
+        let rules = RetentionRules::parse("first-monthly=all, tag:pre-upgrade")?;
+        let decision = rules.evaluate(&history(), now());
+
+        let kept: Vec<&str> = decision.keep.keys().map(String::as_str).collect();
+        assert_eq!(kept, vec!["feb-01", "jan-02", "jan-15", "mar-18", "mar-19"]);
+        assert_eq!(decision.keep["jan-15"], vec!["tag:pre-upgrade"]);
+        assert_eq!(decision.keep["mar-19"], vec!["newest"]);
+        assert_eq!(decision.forget, vec!["feb-20"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_restic_style_rules() -> Result<(), BackupServiceError> {
+        let decision = RetentionRules::parse("monthly=2,within=3d")?.evaluate(&history(), now());
+        // monthly=2 keeps the newest of March and February; within=3d keeps both March runs
+        assert_eq!(decision.forget, vec!["feb-01", "jan-15", "jan-02"]);
+        assert_eq!(decision.keep["feb-20"], vec!["monthly=2"]);
+        assert_eq!(decision.keep["mar-18"], vec!["within=72h"]);
+        assert_eq!(decision.keep["mar-19"], vec!["monthly=2", "within=72h"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_groups_are_decided_independently() -> Result<(), BackupServiceError> {
+        let mut other = snapshot("other", "2024-06-01T02:00:00Z", &[]);
+        other.group = "web1 /srv".to_string();
+        let mut snapshots = history();
+        snapshots.push(other);
+
+        let decision = RetentionRules::parse("last=1")?.evaluate(&snapshots, now());
+        assert!(decision.keep.contains_key("other"));
+        assert!(decision.keep.contains_key("mar-19"));
+        assert_eq!(decision.forget.len(), 5);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for spec in [
+            "hourly=3",
+            "first-monthly=0",
+            "tag:",
+            "last=all",
+            "within=soon",
+        ] {
+            assert!(RetentionRules::parse(spec).is_err(), "{}", spec);
+        }
+    }
+}
@@ -0,0 +1,141 @@
+use crate::errors::BackupServiceError;
+use crate::shared::shutdown;
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+/// Attempts per restic command, the first one included (1 disables retries)
+pub const RETRY_ATTEMPTS_ENV_VAR: &str = "RESTIC_RETRY_ATTEMPTS";
+/// Seconds before the first retry; each further retry waits twice as long
+pub const RETRY_BASE_DELAY_ENV_VAR: &str = "RESTIC_RETRY_BASE_DELAY";
+/// Upper bound in seconds for a single wait
+pub const RETRY_MAX_DELAY_ENV_VAR: &str = "RESTIC_RETRY_MAX_DELAY";
+
+const DEFAULT_ATTEMPTS: u32 = 3;
+const DEFAULT_BASE_DELAY_SECS: u64 = 2;
+const DEFAULT_MAX_DELAY_SECS: u64 = 60;
+
+/// Exponential backoff with jitter for commands that failed transiently
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: DEFAULT_ATTEMPTS,
+            base_delay: Duration::from_secs(DEFAULT_BASE_DELAY_SECS),
+            max_delay: Duration::from_secs(DEFAULT_MAX_DELAY_SECS),
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn from_env() -> Result<Self, BackupServiceError> {
+        Self::from_lookup(|name| std::env::var(name).ok())
+    }
+
+    fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Result<Self, BackupServiceError> {
+        let number = |name: &str, default: u64, min: u64| match lookup(name)
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+        {
+            None => Ok(default),
+            Some(v) => match v.trim_end_matches('s').parse::<u64>() {
+                Ok(n) if n >= min => Ok(n),
+                _ => Err(BackupServiceError::ConfigurationError(format!(
+                    "Invalid {}: {}.\n\nUse a whole number of at least {}",
+                    name, v, min
+                ))),
+            },
+        };
+        let max_attempts = number(RETRY_ATTEMPTS_ENV_VAR, DEFAULT_ATTEMPTS.into(), 1)?;
+        let base_delay = number(RETRY_BASE_DELAY_ENV_VAR, DEFAULT_BASE_DELAY_SECS, 0)?;
+        let max_delay = number(RETRY_MAX_DELAY_ENV_VAR, DEFAULT_MAX_DELAY_SECS, 0)?;
+        Ok(Self {
+            max_attempts: max_attempts.min(u32::MAX.into()) as u32,
+            base_delay: Duration::from_secs(base_delay),
+            max_delay: Duration::from_secs(max_delay.max(base_delay)),
+        })
+    }
+
+    /// Whether `error` from attempt number `attempt` (starting at 1) gets another try
+    pub fn should_retry(&self, error: &BackupServiceError, attempt: u32) -> bool {
+        attempt < self.max_attempts && error.is_retryable() && !shutdown::is_requested()
+    }
+
+    /// Wait after failed attempt `attempt`: the doubled step, capped, then jittered into
+    /// its upper half so parallel jobs hitting the same outage do not retry in lockstep
+    pub fn delay(&self, attempt: u32, jitter: f64) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        let step = self.base_delay.saturating_mul(factor).min(self.max_delay);
+        step.mul_f64(0.5 + jitter.clamp(0.0, 1.0) / 2.0)
+    }
+
+    /// Log the failure and sleep before the next attempt
+    pub async fn backoff(&self, attempt: u32, context: &str, error: &BackupServiceError) {
+        let delay = self.delay(attempt, jitter());
+        warn!(
+            context = %context,
+            attempt = %attempt,
+            max_attempts = %self.max_attempts,
+            retry_in_secs = %format!("{:.1}", delay.as_secs_f64()),
+            error = %error,
+            "Transient failure, retrying"
+        );
+        tokio::time::sleep(delay).await;
+    }
+}
+
+/// Pseudo-random fraction in [0, 1), different per call and process
+fn jitter() -> f64 {
+    let hash = RandomState::new().hash_one(Instant::now());
+    (hash >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn lookup(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        move |name| vars.get(name).cloned()
+    }
+
+    #[test]
+    fn test_should_retry() {
+        let policy = RetryPolicy::default();
+        assert!(policy.should_retry(&BackupServiceError::NetworkError, 2));
+        assert!(!policy.should_retry(&BackupServiceError::NetworkError, 3));
+        assert!(!policy.should_retry(&BackupServiceError::AuthenticationFailed, 1));
+    }
+
+    #[test]
+    fn test_backoff_delays() -> Result<(), BackupServiceError> {
+        let policy = RetryPolicy::from_lookup(lookup(&[
+            ("RESTIC_RETRY_ATTEMPTS", "5"),
+            ("RESTIC_RETRY_BASE_DELAY", "4s"),
+            ("RESTIC_RETRY_MAX_DELAY", "10"),
+        ]))?;
+        assert_eq!(policy.max_attempts, 5);
+        assert_eq!(policy.delay(1, 1.0), Duration::from_secs(4));
+        assert_eq!(policy.delay(2, 0.0), Duration::from_secs(4));
+        assert_eq!(policy.delay(2, 1.0), Duration::from_secs(8));
+        // Capped at the maximum, however many attempts
+        assert_eq!(policy.delay(3, 1.0), Duration::from_secs(10));
+        assert_eq!(policy.delay(40, 0.5), Duration::from_millis(7500));
+        assert!((0.0..1.0).contains(&jitter()));
+
+        assert!(RetryPolicy::from_lookup(lookup(&[("RESTIC_RETRY_ATTEMPTS", "0")])).is_err());
+        assert!(RetryPolicy::from_lookup(lookup(&[("RESTIC_RETRY_BASE_DELAY", "soon")])).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,503 @@
+use crate::config::Config;
+use crate::errors::BackupServiceError;
+use crate::shared::commands::global_timeout;
+use crate::shared::faults;
+use aws_sdk_s3::Client;
+use aws_sdk_s3::config::timeout::TimeoutConfig;
+use aws_sdk_s3::config::{
+    BehaviorVersion, Credentials, Region, RequestChecksumCalculation, ResponseChecksumValidation,
+};
+use aws_sdk_s3::error::{DisplayErrorContext, ProvideErrorMetadata, SdkError};
+use aws_sdk_s3::primitives::ByteStream;
+use aws_sdk_s3::types::{Delete, ObjectIdentifier};
+use tracing::debug;
+
+/// Keys per DeleteObjects request (the S3 maximum)
+pub const DELETE_BATCH_SIZE: usize = 1000;
+
+/// `x-amz-copy-source` value: `bucket/key`, URL-encoded except for the separators
+pub fn copy_source(bucket: &str, key: &str) -> String {
+    let mut encoded = format!("{}/", bucket);
+    for byte in key.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+/// Native S3 client for the configured bucket and endpoint (no `aws` CLI involved)
+pub struct S3Client {
+    client: Client,
+    bucket: String,
+}
+
+/// One ListObjectsV2 response page reduced to directory names
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ListPage {
+    pub dirs: Vec<String>,
+    pub next_token: Option<String>,
+}
+
+/// Directory names relative to `prefix` from a page's common prefixes
+pub fn list_page<'a>(
+    common_prefixes: impl IntoIterator<Item = &'a str>,
+    prefix: &str,
+    next_token: Option<String>,
+) -> ListPage {
+    let dirs = common_prefixes
+        .into_iter()
+        .map(|p| p.strip_prefix(prefix).unwrap_or(p).trim_end_matches('/'))
+        .filter(|d| !d.is_empty())
+        .map(str::to_string)
+        .collect();
+    ListPage { dirs, next_token }
+}
+
+/// Request pages until no continuation token is returned, guarding against a repeating token
+pub async fn collect_prefix_pages<F, Fut>(
+    mut fetch_page: F,
+) -> Result<Vec<String>, BackupServiceError>
+where
+    F: FnMut(Option<String>) -> Fut,
+    Fut: std::future::Future<Output = Result<ListPage, BackupServiceError>>,
+{
+    let mut dirs = Vec::new();
+    let mut token: Option<String> = None;
+    let mut pages = 0;
+
+    loop {
+        let page = fetch_page(token.clone()).await?;
+        pages += 1;
+        dirs.extend(page.dirs);
+
+        match page.next_token {
+            Some(next) if token.as_deref() == Some(next.as_str()) => {
+                return Err(BackupServiceError::CommandFailed(format!(
+                    "S3 listing returned the same continuation token twice after {} pages",
+                    pages
+                )));
+            }
+            Some(next) => token = Some(next),
+            None => break,
+        }
+    }
+
+    debug!(
+        pages = pages,
+        dirs = dirs.len(),
+        "Collected S3 prefix listing"
+    );
+    Ok(dirs)
+}
+
+/// Map an S3 error code and message onto the service's error taxonomy
+pub fn classify_s3_error(code: Option<&str>, message: &str, context: &str) -> BackupServiceError {
+    match code.unwrap_or_default() {
+        "AccessDenied"
+        | "InvalidAccessKeyId"
+        | "SignatureDoesNotMatch"
+        | "Forbidden"
+        | "Unauthorized" => BackupServiceError::AuthenticationFailed,
+        "SlowDown" | "Throttling" | "ThrottlingException" | "TooManyRequests" => {
+            BackupServiceError::Throttled
+        }
+        "RequestTimeTooSkewed" => BackupServiceError::ClockSkew,
+        "QuotaExceeded" => BackupServiceError::QuotaExceeded,
+        "NoSuchBucket" => BackupServiceError::ConfigurationError(format!(
+            "The bucket does not exist ({}).\n\nCheck the bucket in RESTIC_REPO_BASE and AWS_S3_ENDPOINT",
+            context
+        )),
+        // Anything else goes through the same text classification as restic errors
+        code => BackupServiceError::from_stderr(&format!("{} {}", code, message), context),
+    }
+}
+
+fn sdk_error<E, R>(err: SdkError<E, R>, context: &str) -> BackupServiceError
+where
+    E: ProvideErrorMetadata + std::error::Error + Send + Sync + 'static,
+    R: std::fmt::Debug,
+{
+    match &err {
+        SdkError::ServiceError(service) => {
+            let inner = service.err();
+            classify_s3_error(inner.code(), inner.message().unwrap_or_default(), context)
+        }
+        SdkError::DispatchFailure(_) | SdkError::TimeoutError(_) => {
+            match BackupServiceError::from_stderr(&DisplayErrorContext(&err).to_string(), context) {
+                BackupServiceError::CommandFailed(_) => BackupServiceError::NetworkError,
+                classified => classified,
+            }
+        }
+        _ => BackupServiceError::CommandFailed(format!(
+            "S3 request failed ({}): {}",
+            context,
+            DisplayErrorContext(&err)
+        )),
+    }
+}
+
+/// Honor `--inject-fault s3-throttle` before a request goes out
+fn injected_fault(context: &str) -> Result<(), BackupServiceError> {
+    match faults::active_fault().and_then(|f| faults::inject_aws_fault(f, context)) {
+        Some(Err(e)) => Err(e),
+        _ => Ok(()),
+    }
+}
+
+impl S3Client {
+    pub fn new(config: Config) -> Result<Self, BackupServiceError> {
+        let bucket = config.s3_bucket()?;
+        let credentials = Credentials::new(
+            &config.aws_access_key_id,
+            &config.aws_secret_access_key,
+            None,
+            None,
+            "restic-backup-service",
+        );
+        let mut timeouts = TimeoutConfig::builder();
+        if let Some(timeout) = global_timeout()? {
+            // Bounds each request including its retries, like one restic command
+            timeouts = timeouts.operation_timeout(timeout);
+        }
+        let sdk_config = aws_sdk_s3::Config::builder()
+            .behavior_version(BehaviorVersion::latest())
+            .region(Region::new(config.aws_default_region.clone()))
+            .endpoint_url(config.s3_endpoint()?)
+            .credentials_provider(credentials)
+            .timeout_config(timeouts.build())
+            // R2, MinIO and friends expect path-style requests and predate default checksums
+            .force_path_style(true)
+            .request_checksum_calculation(RequestChecksumCalculation::WhenRequired)
+            .response_checksum_validation(ResponseChecksumValidation::WhenRequired)
+            .build();
+        Ok(Self {
+            client: Client::from_conf(sdk_config),
+            bucket,
+        })
+    }
+
+    /// List S3 directories (common prefixes) below a path, following every result page
+    pub async fn list_directories(&self, s3_path: &str) -> Result<Vec<String>, BackupServiceError> {
+        let prefix = if s3_path.is_empty() {
+            String::new()
+        } else {
+            format!("{}/", s3_path.trim_end_matches('/'))
+        };
+        let context = format!("s3://{}/{}", self.bucket, prefix);
+
+        collect_prefix_pages(|token| {
+            let prefix = prefix.clone();
+            let context = context.clone();
+            async move {
+                injected_fault(&context)?;
+                let page = self
+                    .client
+                    .list_objects_v2()
+                    .bucket(&self.bucket)
+                    .prefix(&prefix)
+                    .delimiter("/")
+                    .set_continuation_token(token)
+                    .send()
+                    .await
+                    .map_err(|e| sdk_error(e, &context))?;
+                let next_token = if page.is_truncated().unwrap_or(false) {
+                    page.next_continuation_token().map(str::to_string)
+                } else {
+                    None
+                };
+                Ok(list_page(
+                    page.common_prefixes().iter().filter_map(|p| p.prefix()),
+                    &prefix,
+                    next_token,
+                ))
+            }
+        })
+        .await
+    }
+
+    /// First object key below a prefix, if any
+    pub async fn first_key(&self, prefix: &str) -> Result<Option<String>, BackupServiceError> {
+        let context = format!("list {}", prefix);
+        injected_fault(&context)?;
+        let page = self
+            .client
+            .list_objects_v2()
+            .bucket(&self.bucket)
+            .prefix(prefix)
+            .max_keys(1)
+            .send()
+            .await
+            .map_err(|e| sdk_error(e, &context))?;
+        Ok(page
+            .contents()
+            .first()
+            .and_then(|o| o.key())
+            .map(str::to_string))
+    }
+
+    pub async fn put_object(&self, key: &str, body: &[u8]) -> Result<(), BackupServiceError> {
+        let context = format!("put {}", key);
+        injected_fault(&context)?;
+        self.client
+            .put_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .body(ByteStream::from(body.to_vec()))
+            .send()
+            .await
+            .map_err(|e| sdk_error(e, &context))?;
+        Ok(())
+    }
+
+    /// Read only the first byte of an object (enough to prove read access)
+    pub async fn get_object_head_byte(&self, key: &str) -> Result<(), BackupServiceError> {
+        let context = format!("get {}", key);
+        injected_fault(&context)?;
+        let object = self
+            .client
+            .get_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .range("bytes=0-0")
+            .send()
+            .await
+            .map_err(|e| sdk_error(e, &context))?;
+        object.body.collect().await.map_err(|e| {
+            BackupServiceError::CommandFailed(format!("Reading {} failed: {}", key, e))
+        })?;
+        Ok(())
+    }
+
+    /// Server-side copy of one object within the bucket
+    pub async fn copy_object(
+        &self,
+        from_key: &str,
+        to_key: &str,
+    ) -> Result<(), BackupServiceError> {
+        let context = format!("copy {} to {}", from_key, to_key);
+        injected_fault(&context)?;
+        self.client
+            .copy_object()
+            .bucket(&self.bucket)
+            .copy_source(copy_source(&self.bucket, from_key))
+            .key(to_key)
+            .send()
+            .await
+            .map_err(|e| sdk_error(e, &context))?;
+        Ok(())
+    }
+
+    pub async fn delete_object(&self, key: &str) -> Result<(), BackupServiceError> {
+        let context = format!("delete {}", key);
+        injected_fault(&context)?;
+        self.client
+            .delete_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .send()
+            .await
+            .map_err(|e| sdk_error(e, &context))?;
+        Ok(())
+    }
+
+    /// Every object key and size below a prefix, following every result page
+    pub async fn list_objects(
+        &self,
+        prefix: &str,
+    ) -> Result<Vec<(String, u64)>, BackupServiceError> {
+        let context = format!("s3://{}/{}", self.bucket, prefix);
+        let mut objects = Vec::new();
+        let mut token: Option<String> = None;
+        loop {
+            injected_fault(&context)?;
+            let page = self
+                .client
+                .list_objects_v2()
+                .bucket(&self.bucket)
+                .prefix(prefix)
+                .set_continuation_token(token.clone())
+                .send()
+                .await
+                .map_err(|e| sdk_error(e, &context))?;
+            objects.extend(page.contents().iter().filter_map(|o| {
+                o.key()
+                    .map(|key| (key.to_string(), o.size().unwrap_or(0).max(0) as u64))
+            }));
+            match page
+                .next_continuation_token()
+                .filter(|_| page.is_truncated().unwrap_or(false))
+            {
+                Some(next) if token.as_deref() == Some(next) => {
+                    return Err(BackupServiceError::CommandFailed(format!(
+                        "S3 listing of {} returned the same continuation token twice",
+                        context
+                    )));
+                }
+                Some(next) => token = Some(next.to_string()),
+                None => break,
+            }
+        }
+        debug!(objects = objects.len(), "Collected S3 object listing");
+        Ok(objects)
+    }
+
+    /// Delete keys in DeleteObjects batches; any key the bucket refuses fails the call
+    pub async fn delete_objects(&self, keys: &[String]) -> Result<(), BackupServiceError> {
+        for batch in keys.chunks(DELETE_BATCH_SIZE) {
+            let context = format!("delete {} objects from s3://{}", batch.len(), self.bucket);
+            injected_fault(&context)?;
+            let objects = batch
+                .iter()
+                .map(|key| ObjectIdentifier::builder().key(key).build())
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| BackupServiceError::CommandFailed(format!("{}: {}", context, e)))?;
+            let delete = Delete::builder()
+                .set_objects(Some(objects))
+                .quiet(true)
+                .build()
+                .map_err(|e| BackupServiceError::CommandFailed(format!("{}: {}", context, e)))?;
+            let output = self
+                .client
+                .delete_objects()
+                .bucket(&self.bucket)
+                .delete(delete)
+                .send()
+                .await
+                .map_err(|e| sdk_error(e, &context))?;
+            if let Some(first) = output.errors().first() {
+                return Err(classify_s3_error(
+                    first.code(),
+                    first.message().unwrap_or_default(),
+                    &format!(
+                        "{} ({} keys refused, first: {})",
+                        context,
+                        output.errors().len(),
+                        first.key().unwrap_or_default()
+                    ),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Serve synthetic prefixes in ListObjectsV2-shaped pages of `page_size`
+    fn synthetic_page(
+        all: &[String],
+        prefix: &str,
+        page_size: usize,
+        token: Option<String>,
+    ) -> ListPage {
+        let start: usize = token.map(|t| t.parse().unwrap()).unwrap_or(0);
+        let end = (start + page_size).min(all.len());
+        let common: Vec<String> = all[start..end]
+            .iter()
+            .map(|d| format!("{}{}/", prefix, d))
+            .collect();
+        let next_token = (end < all.len()).then(|| end.to_string());
+        list_page(common.iter().map(String::as_str), prefix, next_token)
+    }
+
+    #[test]
+    fn test_list_page_strips_prefix() {
+        let page = list_page(
+            [
+                "base/host/user_home/tim/",
+                "base/host/user_home/PRE weird name/",
+                "base/host/user_home/ümlaut+%20&/",
+            ],
+            "base/host/user_home/",
+            None,
+        );
+        assert_eq!(page.dirs, vec!["tim", "PRE weird name", "ümlaut+%20&"]);
+        assert_eq!(page.next_token, None);
+        assert_eq!(list_page([], "x/", None), ListPage::default());
+    }
+
+    #[test]
+    fn test_copy_source_encoding() {
+        assert_eq!(
+            copy_source("backups", "restic/web1/home~5Ftim/config"),
+            "backups/restic/web1/home~5Ftim/config"
+        );
+        assert_eq!(copy_source("b", "a b+c%/ü"), "b/a%20b%2Bc%25/%C3%BC");
+    }
+
+    #[test]
+    fn test_classify_s3_error() {
+        assert!(matches!(
+            classify_s3_error(Some("AccessDenied"), "Access Denied", "list x"),
+            BackupServiceError::AuthenticationFailed
+        ));
+        assert!(matches!(
+            classify_s3_error(
+                Some("SlowDown"),
+                "Please reduce your request rate.",
+                "list x"
+            ),
+            BackupServiceError::Throttled
+        ));
+        assert!(matches!(
+            classify_s3_error(Some("RequestTimeTooSkewed"), "", "list x"),
+            BackupServiceError::ClockSkew
+        ));
+        assert!(matches!(
+            classify_s3_error(Some("NoSuchBucket"), "", "list x"),
+            BackupServiceError::ConfigurationError(_)
+        ));
+        assert!(matches!(
+            classify_s3_error(None, "something odd", "list x"),
+            BackupServiceError::CommandFailed(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_collect_thousands_of_prefixes() -> Result<(), BackupServiceError> {
+        let prefix = "base/host/docker_volume/";
+        let all: Vec<String> = (0..4321).map(|i| format!("volume {:05}", i)).collect();
+
+        let mut requests = 0;
+        let dirs = collect_prefix_pages(|token| {
+            requests += 1;
+            let page = synthetic_page(&all, prefix, 1000, token);
+            async move { Ok(page) }
+        })
+        .await?;
+
+        assert_eq!(requests, 5);
+        assert_eq!(dirs, all);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_collect_exact_page_boundary() -> Result<(), BackupServiceError> {
+        let all: Vec<String> = (0..2000).map(|i| i.to_string()).collect();
+        let dirs = collect_prefix_pages(|token| {
+            let page = synthetic_page(&all, "", 1000, token);
+            async move { Ok(page) }
+        })
+        .await?;
+        assert_eq!(dirs.len(), 2000);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_collect_rejects_repeating_token() {
+        let result = collect_prefix_pages(|_| async {
+            Ok(ListPage {
+                dirs: vec!["a".to_string()],
+                next_token: Some("same".to_string()),
+            })
+        })
+        .await;
+        assert!(result.is_err());
+    }
+}
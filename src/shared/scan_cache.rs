@@ -0,0 +1,289 @@
+use crate::config::Config;
+use crate::errors::BackupServiceError;
+use crate::shared::backup_summary::ResticSummary;
+use crate::shared::operations::{RepositoryData, RepositoryInfo, SnapshotInfo};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::{debug, warn};
+
+/// Where scan results are kept (default `$XDG_CACHE_HOME` or `~/.cache`, see `cache_file`)
+pub const SCAN_CACHE_FILE_ENV_VAR: &str = "SCAN_CACHE_FILE";
+/// Seconds a cached scan is reused (default 3600, 0 disables the cache)
+pub const SCAN_CACHE_TTL_ENV_VAR: &str = "SCAN_CACHE_TTL";
+const DEFAULT_SCAN_CACHE_TTL_SECS: u64 = 3600;
+const CACHE_FILE: &str = "restic-backup-service/scan.json";
+
+/// Cache file from SCAN_CACHE_FILE, else below XDG_CACHE_HOME or ~/.cache; none without a home
+pub fn cache_file() -> Option<PathBuf> {
+    let var = |name: &str| {
+        std::env::var(name)
+            .ok()
+            .filter(|v| !v.trim().is_empty())
+            .map(PathBuf::from)
+    };
+    var(SCAN_CACHE_FILE_ENV_VAR)
+        .or_else(|| var("XDG_CACHE_HOME").map(|dir| dir.join(CACHE_FILE)))
+        .or_else(|| var("HOME").map(|home| home.join(".cache").join(CACHE_FILE)))
+}
+
+/// How long a scan stays fresh; `None` when caching is disabled
+pub fn cache_ttl() -> Result<Option<Duration>, BackupServiceError> {
+    let secs = match std::env::var(SCAN_CACHE_TTL_ENV_VAR)
+        .ok()
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+    {
+        None => DEFAULT_SCAN_CACHE_TTL_SECS,
+        Some(value) => value.parse::<u64>().map_err(|_| {
+            BackupServiceError::ConfigurationError(format!(
+                "Invalid {}: {}.\n\nUse a number of seconds, or 0 to disable the scan cache",
+                SCAN_CACHE_TTL_ENV_VAR, value
+            ))
+        })?,
+    };
+    Ok((secs > 0).then(|| Duration::from_secs(secs)))
+}
+
+/// The cache entry of a host: its repository store, so another base or prefix never matches
+pub fn cache_key(config: &Config, hostname: &str) -> Result<String, BackupServiceError> {
+    Ok(format!(
+        "{}|{}",
+        config.restic_repo_base,
+        config.host_store_path(hostname)?
+    ))
+}
+
+/// A snapshot as stored in the cache (times as RFC 3339, like the other state files)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedSnapshot {
+    time: String,
+    path: PathBuf,
+    id: String,
+    tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    summary: Option<ResticSummary>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedRepository {
+    native_path: PathBuf,
+    repo_subpath: String,
+    category: String,
+    snapshot_count: usize,
+    snapshots: Vec<CachedSnapshot>,
+}
+
+impl From<&RepositoryData> for CachedRepository {
+    fn from(repo: &RepositoryData) -> Self {
+        Self {
+            native_path: repo.info.native_path.clone(),
+            repo_subpath: repo.info.repo_subpath.clone(),
+            category: repo.info.category.clone(),
+            snapshot_count: repo.snapshot_count,
+            snapshots: repo
+                .snapshots
+                .iter()
+                .map(|s| CachedSnapshot {
+                    time: s.time.to_rfc3339(),
+                    path: s.path.clone(),
+                    id: s.id.clone(),
+                    tags: s.tags.clone(),
+                    summary: s.summary.clone(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl CachedRepository {
+    /// Back to scan data; `None` if a stored time no longer parses
+    fn to_repository_data(&self) -> Option<RepositoryData> {
+        let snapshots = self
+            .snapshots
+            .iter()
+            .map(|s| {
+                Some(SnapshotInfo {
+                    time: parse_time(&s.time)?,
+                    path: s.path.clone(),
+                    id: s.id.clone(),
+                    tags: s.tags.clone(),
+                    summary: s.summary.clone(),
+                })
+            })
+            .collect::<Option<Vec<_>>>()?;
+        Some(RepositoryData {
+            info: RepositoryInfo {
+                native_path: self.native_path.clone(),
+                repo_subpath: self.repo_subpath.clone(),
+                category: self.category.clone(),
+            },
+            snapshots,
+            snapshot_count: self.snapshot_count,
+        })
+    }
+}
+
+fn parse_time(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .ok()
+        .map(|t| t.with_timezone(&Utc))
+}
+
+/// One complete scan of a host's repositories
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedScan {
+    /// RFC 3339
+    scanned_at: String,
+    repos: Vec<CachedRepository>,
+}
+
+impl CachedScan {
+    pub fn new(scanned_at: DateTime<Utc>, repos: &[RepositoryData]) -> Self {
+        Self {
+            scanned_at: scanned_at.to_rfc3339(),
+            repos: repos.iter().map(CachedRepository::from).collect(),
+        }
+    }
+
+    pub fn scanned_at(&self) -> Option<DateTime<Utc>> {
+        parse_time(&self.scanned_at)
+    }
+
+    pub fn is_fresh(&self, ttl: Duration, now: DateTime<Utc>) -> bool {
+        self.scanned_at()
+            .and_then(|scanned_at| (now - scanned_at).to_std().ok())
+            .is_some_and(|age| age < ttl)
+    }
+
+    /// The cached repositories; `None` if the entry is damaged and must be rescanned
+    pub fn repos(&self) -> Option<Vec<RepositoryData>> {
+        self.repos
+            .iter()
+            .map(CachedRepository::to_repository_data)
+            .collect()
+    }
+}
+
+/// Scan results per host store, reused by `list` and `restore` until they expire
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScanCache {
+    pub scans: BTreeMap<String, CachedScan>,
+}
+
+impl ScanCache {
+    /// Read the cache; a missing or unreadable file (older format) is an empty cache
+    pub fn load(file: &Path) -> Self {
+        std::fs::read_to_string(file)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    /// Write via a temporary file so concurrent readers never see a truncated cache
+    pub fn save(&self, file: &Path) -> Result<(), BackupServiceError> {
+        if let Some(dir) = file.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let tmp = file.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_string(self)?)?;
+        std::fs::rename(&tmp, file)?;
+        Ok(())
+    }
+}
+
+/// Scan time and repositories of a host's cached scan, if it is younger than the TTL
+pub fn lookup(config: &Config, hostname: &str) -> Option<(DateTime<Utc>, Vec<RepositoryData>)> {
+    let ttl = cache_ttl().ok()??;
+    let key = cache_key(config, hostname).ok()?;
+    let scan = ScanCache::load(&cache_file()?).scans.remove(&key)?;
+    if !scan.is_fresh(ttl, Utc::now()) {
+        return None;
+    }
+    Some((scan.scanned_at()?, scan.repos()?))
+}
+
+/// Remember a complete scan; failing to write the cache only costs the next rescan
+pub fn store(config: &Config, hostname: &str, repos: &[RepositoryData]) {
+    let Some(file) = cache_file() else {
+        return;
+    };
+    if !matches!(cache_ttl(), Ok(Some(_))) {
+        return;
+    }
+    let result = cache_key(config, hostname).and_then(|key| {
+        let mut cache = ScanCache::load(&file);
+        cache.scans.insert(key, CachedScan::new(Utc::now(), repos));
+        cache.save(&file)
+    });
+    if let Err(e) = result {
+        warn!(file = %file.display(), error = %e, "Could not write the scan cache");
+    }
+}
+
+/// Drop a host's cached scan after its snapshots changed (backup, prune)
+pub fn invalidate(config: &Config, hostname: &str) {
+    let (Some(file), Ok(key)) = (cache_file(), cache_key(config, hostname)) else {
+        return;
+    };
+    let mut cache = ScanCache::load(&file);
+    if cache.scans.remove(&key).is_none() {
+        return;
+    }
+    match cache.save(&file) {
+        Ok(()) => debug!(host = %hostname, "Dropped cached repository scan"),
+        Err(e) => warn!(file = %file.display(), error = %e, "Could not update the scan cache"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_scan_cache_round_trip_and_freshness() -> Result<(), BackupServiceError> {
+        let scanned_at = Utc.with_ymd_and_hms(2025, 1, 15, 9, 0, 0).unwrap();
+        let repo = RepositoryData {
+            info: RepositoryInfo {
+                native_path: PathBuf::from("/home/tim"),
+                repo_subpath: "user_home/tim".to_string(),
+                category: "user_home".to_string(),
+            },
+            snapshots: vec![SnapshotInfo {
+                time: Utc.with_ymd_and_hms(2025, 1, 15, 3, 0, 0).unwrap(),
+                path: PathBuf::from("/home/tim"),
+                id: "4f1c2a9e".to_string(),
+                tags: vec!["user-path".to_string()],
+                summary: None,
+            }],
+            snapshot_count: 1,
+        };
+        let mut cache = ScanCache::default();
+        cache.scans.insert(
+            "s3:https://s3.example/bucket|web1".to_string(),
+            CachedScan::new(scanned_at, std::slice::from_ref(&repo)),
+        );
+
+        let dir = tempdir()?;
+        let file = dir.path().join("cache/scan.json");
+        cache.save(&file)?;
+        let loaded = ScanCache::load(&file);
+        let scan = &loaded.scans["s3:https://s3.example/bucket|web1"];
+        assert_eq!(scan.scanned_at(), Some(scanned_at));
+        let repos = scan.repos().unwrap();
+        assert_eq!(repos[0].info.repo_subpath, "user_home/tim");
+        assert_eq!(repos[0].snapshots, repo.snapshots);
+
+        let ttl = Duration::from_secs(3600);
+        assert!(scan.is_fresh(ttl, scanned_at + chrono::Duration::minutes(59)));
+        assert!(!scan.is_fresh(ttl, scanned_at + chrono::Duration::minutes(61)));
+
+        std::fs::write(&file, "not json")?;
+        assert!(ScanCache::load(&file).scans.is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,198 @@
+use crate::errors::BackupServiceError;
+use crate::shared::dependencies::{Dependency, ensure_available};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use tracing::debug;
+
+/// File whose content is the repository password
+pub const PASSWORD_FILE_ENV_VAR: &str = "RESTIC_PASSWORD_FILE";
+/// Shell command printing the repository password
+pub const PASSWORD_COMMAND_ENV_VAR: &str = "RESTIC_PASSWORD_COMMAND";
+/// `<service>[:<account>]` of an OS keyring entry holding the repository password
+pub const PASSWORD_KEYRING_ENV_VAR: &str = "RESTIC_PASSWORD_KEYRING";
+const DEFAULT_KEYRING_ACCOUNT: &str = "restic";
+
+/// Where the repository password comes from when SECRET_PROVIDER does not supply it
+#[derive(Debug, Clone, PartialEq)]
+pub enum SecretSource {
+    /// RESTIC_PASSWORD in the environment or env file
+    Env(String),
+    /// RESTIC_PASSWORD_FILE
+    File(PathBuf),
+    /// RESTIC_PASSWORD_COMMAND, run with `sh -c`
+    Command(String),
+    /// RESTIC_PASSWORD_KEYRING: libsecret (`secret-tool`) on Linux, the keychain on macOS
+    Keyring { service: String, account: String },
+}
+
+impl SecretSource {
+    /// The one password source that is set, None when there is none
+    pub fn from_env() -> Result<Option<Self>, BackupServiceError> {
+        Self::from_lookup(|name| std::env::var(name).ok())
+    }
+
+    fn from_lookup(
+        lookup: impl Fn(&str) -> Option<String>,
+    ) -> Result<Option<Self>, BackupServiceError> {
+        let value = |name: &str| lookup(name).filter(|v| !v.trim().is_empty());
+        let mut sources = Vec::new();
+        if let Some(password) = value("RESTIC_PASSWORD") {
+            sources.push(("RESTIC_PASSWORD", SecretSource::Env(password)));
+        }
+        if let Some(file) = value(PASSWORD_FILE_ENV_VAR) {
+            sources.push((
+                PASSWORD_FILE_ENV_VAR,
+                SecretSource::File(PathBuf::from(file.trim())),
+            ));
+        }
+        if let Some(command) = value(PASSWORD_COMMAND_ENV_VAR) {
+            sources.push((PASSWORD_COMMAND_ENV_VAR, SecretSource::Command(command)));
+        }
+        if let Some(entry) = value(PASSWORD_KEYRING_ENV_VAR) {
+            let (service, account) = match entry.trim().split_once(':') {
+                Some((service, account)) => (service.to_string(), account.to_string()),
+                None => (
+                    entry.trim().to_string(),
+                    DEFAULT_KEYRING_ACCOUNT.to_string(),
+                ),
+            };
+            sources.push((
+                PASSWORD_KEYRING_ENV_VAR,
+                SecretSource::Keyring { service, account },
+            ));
+        }
+
+        if sources.len() > 1 {
+            let names: Vec<_> = sources.iter().map(|(name, _)| *name).collect();
+            return Err(BackupServiceError::ConfigurationError(format!(
+                "Several repository password sources are set: {}.\n\nKeep exactly one of RESTIC_PASSWORD, {}, {} and {}",
+                names.join(", "),
+                PASSWORD_FILE_ENV_VAR,
+                PASSWORD_COMMAND_ENV_VAR,
+                PASSWORD_KEYRING_ENV_VAR
+            )));
+        }
+        Ok(sources.pop().map(|(_, source)| source))
+    }
+
+    /// Where the password is read from, safe to log
+    pub fn describe(&self) -> String {
+        match self {
+            SecretSource::Env(_) => "RESTIC_PASSWORD".to_string(),
+            SecretSource::File(path) => format!("file {}", path.display()),
+            SecretSource::Command(_) => PASSWORD_COMMAND_ENV_VAR.to_string(),
+            SecretSource::Keyring { service, account } => {
+                format!("keyring entry {}:{}", service, account)
+            }
+        }
+    }
+
+    /// Read the password; surrounding whitespace is dropped, as restic does
+    pub fn resolve(&self) -> Result<String, BackupServiceError> {
+        debug!(source = %self.describe(), "Reading repository password");
+        let password = match self {
+            SecretSource::Env(password) => password.clone(),
+            SecretSource::File(path) => std::fs::read_to_string(path).map_err(|e| {
+                BackupServiceError::ConfigurationError(format!(
+                    "Cannot read {} {}: {}.\n\nCheck the path and that the current user may read it",
+                    PASSWORD_FILE_ENV_VAR,
+                    path.display(),
+                    e
+                ))
+            })?,
+            SecretSource::Command(command) => run(
+                Command::new("sh").args(["-c", command]),
+                PASSWORD_COMMAND_ENV_VAR,
+            )?,
+            SecretSource::Keyring { service, account } => {
+                ensure_available(&[Dependency::keyring()])?;
+                let mut command = if cfg!(target_os = "macos") {
+                    let mut command = Command::new("security");
+                    command.args(["find-generic-password", "-s", service, "-a", account, "-w"]);
+                    command
+                } else {
+                    let mut command = Command::new("secret-tool");
+                    command.args(["lookup", "service", service, "account", account]);
+                    command
+                };
+                run(&mut command, PASSWORD_KEYRING_ENV_VAR)?
+            }
+        };
+
+        let password = password.trim().to_string();
+        if password.is_empty() {
+            return Err(BackupServiceError::ConfigurationError(format!(
+                "The repository password from {} is empty.\n\nStore the password there or pick another source",
+                self.describe()
+            )));
+        }
+        Ok(password)
+    }
+}
+
+/// stdout of a password helper; its stderr and stdin stay attached so it can prompt
+fn run(command: &mut Command, source: &str) -> Result<String, BackupServiceError> {
+    let output = command
+        .stdin(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .output()
+        .map_err(|e| {
+            BackupServiceError::CommandNotFound(format!("Failed to run {}: {}", source, e))
+        })?;
+    if !output.status.success() {
+        return Err(BackupServiceError::ConfigurationError(format!(
+            "{} exited with {} without printing a password.\n\nRun it by hand to see why",
+            source, output.status
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn lookup(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        move |name| vars.get(name).cloned()
+    }
+
+    #[test]
+    fn test_password_sources() -> Result<(), BackupServiceError> {
+        assert_eq!(SecretSource::from_lookup(lookup(&[]))?, None);
+        assert_eq!(
+            SecretSource::from_lookup(lookup(&[("RESTIC_PASSWORD_KEYRING", "restic-backup")]))?,
+            Some(SecretSource::Keyring {
+                service: "restic-backup".to_string(),
+                account: "restic".to_string(),
+            })
+        );
+        assert!(
+            SecretSource::from_lookup(lookup(&[
+                ("RESTIC_PASSWORD", "pw"),
+                ("RESTIC_PASSWORD_FILE", "/run/secrets/restic"),
+            ]))
+            .is_err()
+        );
+
+        let dir = tempfile::tempdir()?;
+        let file = dir.path().join("password");
+        std::fs::write(&file, "from-file\n")?;
+        assert_eq!(SecretSource::File(file).resolve()?, "from-file");
+        assert_eq!(
+            SecretSource::Command("printf ' from-command\\n'".to_string()).resolve()?,
+            "from-command"
+        );
+        assert!(
+            SecretSource::Command("exit 1".to_string())
+                .resolve()
+                .is_err()
+        );
+        assert!(SecretSource::Command("true".to_string()).resolve().is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,467 @@
+use crate::errors::BackupServiceError;
+use crate::shared::commands::with_timeout;
+use crate::shared::dependencies::{Dependency, ensure_available};
+use crate::shared::http;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::process::Command;
+use tracing::{debug, info};
+
+/// Where credentials come from: `env` (default), `vault` or `sops`
+pub const SECRET_PROVIDER_ENV_VAR: &str = "SECRET_PROVIDER";
+/// Seconds fetched secrets are reused before the provider is asked again
+pub const SECRET_CACHE_TTL_ENV_VAR: &str = "SECRET_CACHE_TTL";
+const DEFAULT_CACHE_TTL_SECS: u64 = 300;
+/// Seconds `sops --decrypt` may take (a KMS or age plugin round trip)
+const SOPS_TIMEOUT_SECS: u64 = 60;
+
+/// Keys a provider may supply; anything it does not have falls back to the environment
+pub const SECRET_KEYS: &[&str] = &[
+    "RESTIC_PASSWORD",
+    "RESTIC_REPO_BASE",
+    "AWS_ACCESS_KEY_ID",
+    "AWS_SECRET_ACCESS_KEY",
+    "AWS_S3_ENDPOINT",
+];
+
+fn env_value(key: &str) -> Option<String> {
+    std::env::var(key)
+        .ok()
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+fn missing(key: &str, provider: &str, example: &str) -> BackupServiceError {
+    BackupServiceError::ConfigurationError(format!(
+        "Missing {} for {}={}.\n\nSet it in the environment or env file, e.g. {}={}",
+        key, SECRET_PROVIDER_ENV_VAR, provider, key, example
+    ))
+}
+
+/// How the agent authenticates against Vault
+#[derive(Debug, Clone, PartialEq)]
+pub enum VaultAuth {
+    /// VAULT_TOKEN
+    Token(String),
+    /// VAULT_TOKEN_FILE, re-read on every fetch (e.g. the sink of a Vault agent)
+    TokenFile(PathBuf),
+    /// VAULT_ROLE_ID with VAULT_SECRET_ID or VAULT_SECRET_ID_FILE
+    AppRole { role_id: String, secret_id: String },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct VaultSource {
+    /// VAULT_ADDR, e.g. https://vault.example.com:8200
+    pub addr: String,
+    /// VAULT_SECRET_PATH below /v1/, e.g. secret/data/restic/nas for KV v2
+    pub path: String,
+    pub namespace: Option<String>,
+    pub cacert: Option<String>,
+    pub auth: VaultAuth,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SecretProvider {
+    Env,
+    Vault(VaultSource),
+    /// SOPS_SECRETS_FILE, decrypted with the `sops` CLI
+    Sops(PathBuf),
+}
+
+impl SecretProvider {
+    pub fn from_env() -> Result<Self, BackupServiceError> {
+        let name = env_value(SECRET_PROVIDER_ENV_VAR).unwrap_or_else(|| "env".to_string());
+        match name.to_lowercase().as_str() {
+            "env" => Ok(SecretProvider::Env),
+            "vault" => Ok(SecretProvider::Vault(Self::vault_from_env()?)),
+            "sops" => env_value("SOPS_SECRETS_FILE")
+                .map(|file| SecretProvider::Sops(PathBuf::from(file)))
+                .ok_or_else(|| {
+                    missing("SOPS_SECRETS_FILE", "sops", "/etc/restic-backup.sops.yaml")
+                }),
+            other => Err(BackupServiceError::ConfigurationError(format!(
+                "Unknown {}: {}.\n\nValid providers are: env, vault, sops",
+                SECRET_PROVIDER_ENV_VAR, other
+            ))),
+        }
+    }
+
+    fn vault_from_env() -> Result<VaultSource, BackupServiceError> {
+        let addr = env_value("VAULT_ADDR")
+            .ok_or_else(|| missing("VAULT_ADDR", "vault", "https://vault.example.com:8200"))?;
+        let path = env_value("VAULT_SECRET_PATH")
+            .ok_or_else(|| missing("VAULT_SECRET_PATH", "vault", "secret/data/restic/nas"))?;
+        let auth = if let Some(role_id) = env_value("VAULT_ROLE_ID") {
+            let secret_id = match env_value("VAULT_SECRET_ID_FILE") {
+                Some(file) => std::fs::read_to_string(&file)?.trim().to_string(),
+                None => env_value("VAULT_SECRET_ID").ok_or_else(|| {
+                    missing("VAULT_SECRET_ID", "vault", "<secret id of the AppRole>")
+                })?,
+            };
+            VaultAuth::AppRole { role_id, secret_id }
+        } else if let Some(file) = env_value("VAULT_TOKEN_FILE") {
+            VaultAuth::TokenFile(PathBuf::from(file))
+        } else if let Some(token) = env_value("VAULT_TOKEN") {
+            VaultAuth::Token(token)
+        } else {
+            return Err(missing(
+                "VAULT_TOKEN, VAULT_TOKEN_FILE or VAULT_ROLE_ID",
+                "vault",
+                "<token>",
+            ));
+        };
+        Ok(VaultSource {
+            addr: addr.trim_end_matches('/').to_string(),
+            path: path.trim_matches('/').to_string(),
+            namespace: env_value("VAULT_NAMESPACE"),
+            cacert: env_value("VAULT_CACERT"),
+            auth,
+        })
+    }
+
+    /// Fetch the provider's values and how long they may be reused (a Vault lease, if any)
+    async fn fetch(
+        &self,
+    ) -> Result<(BTreeMap<String, String>, Option<Duration>), BackupServiceError> {
+        match self {
+            SecretProvider::Env => Ok((BTreeMap::new(), None)),
+            SecretProvider::Vault(source) => {
+                ensure_available(&[Dependency::curl("fetching secrets from Vault")])?;
+                let token = vault_token(source).await?;
+                let response = vault_request(
+                    source,
+                    "GET",
+                    &format!("v1/{}", source.path),
+                    Some(&token),
+                    None,
+                )
+                .await?;
+                let lease = response["lease_duration"]
+                    .as_u64()
+                    .filter(|secs| *secs > 0)
+                    .map(Duration::from_secs);
+                Ok((parse_vault_secret(&response), lease))
+            }
+            SecretProvider::Sops(file) => {
+                ensure_available(&[Dependency::sops()])?;
+                let child = Command::new("sops")
+                    .args(["--decrypt", "--output-type", "json"])
+                    .arg(file)
+                    .stdout(std::process::Stdio::piped())
+                    .stderr(std::process::Stdio::piped())
+                    .kill_on_drop(true)
+                    .spawn()
+                    .map_err(|_| {
+                        BackupServiceError::CommandNotFound("Failed to execute sops".to_string())
+                    })?;
+                let output = with_timeout(
+                    child.wait_with_output(),
+                    Some(Duration::from_secs(SOPS_TIMEOUT_SECS)),
+                    "sops --decrypt",
+                )
+                .await??;
+                if !output.status.success() {
+                    return Err(BackupServiceError::ConfigurationError(format!(
+                        "Could not decrypt {}: {}\n\nCheck that this host's age/PGP/KMS key is a recipient of the file",
+                        file.display(),
+                        String::from_utf8_lossy(&output.stderr).trim()
+                    )));
+                }
+                let document: Value = serde_json::from_slice(&output.stdout)?;
+                Ok((flat_secrets(&document), None))
+            }
+        }
+    }
+}
+
+/// Top-level scalar values of a secret document, keyed by their upper-cased names
+pub fn flat_secrets(document: &Value) -> BTreeMap<String, String> {
+    document
+        .as_object()
+        .map(|object| {
+            object
+                .iter()
+                .filter_map(|(key, value)| {
+                    let value = match value {
+                        Value::String(s) => s.clone(),
+                        Value::Number(n) => n.to_string(),
+                        _ => return None,
+                    };
+                    Some((key.to_uppercase(), value))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// KV v2 nests the secret in `data.data`, KV v1 returns it as `data`
+pub fn parse_vault_secret(response: &Value) -> BTreeMap<String, String> {
+    let data = &response["data"];
+    if data["data"].is_object() && data["metadata"].is_object() {
+        flat_secrets(&data["data"])
+    } else {
+        flat_secrets(data)
+    }
+}
+
+/// Send a request to Vault via curl; the token travels on stdin, never on the command line
+async fn vault_request(
+    source: &VaultSource,
+    method: &str,
+    api_path: &str,
+    token: Option<&str>,
+    body: Option<&str>,
+) -> Result<Value, BackupServiceError> {
+    let mut args = vec!["--request".to_string(), method.to_string()];
+    if let Some(cacert) = &source.cacert {
+        args.extend(["--cacert".to_string(), cacert.clone()]);
+    }
+    if let Some(namespace) = &source.namespace {
+        args.extend([
+            "--header".to_string(),
+            format!("X-Vault-Namespace: {}", namespace),
+        ]);
+    }
+    // One stdin: a token request reads its header from it, a login its body
+    args.push(
+        if body.is_some() {
+            "--data-binary"
+        } else {
+            "--header"
+        }
+        .to_string(),
+    );
+    args.push("@-".to_string());
+    args.push(format!("{}/{}", source.addr, api_path));
+    let input = match (body, token) {
+        (Some(body), _) => body.to_string(),
+        (None, Some(token)) => format!("X-Vault-Token: {}\n", token),
+        (None, None) => String::new(),
+    };
+    let output = http::curl(
+        &args,
+        &input,
+        &format!("Vault request {} {}", method, api_path),
+    )
+    .await?;
+    if !output.status.success() {
+        return Err(BackupServiceError::ConfigurationError(format!(
+            "Vault request {} {} failed: {}\n\nCheck VAULT_ADDR, VAULT_SECRET_PATH and that the token's policy can read the path",
+            method,
+            api_path,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(serde_json::from_slice(&output.stdout)?)
+}
+
+/// An AppRole login token and when it has to be renewed or replaced
+#[derive(Debug, Clone)]
+struct VaultToken {
+    token: String,
+    issued: Instant,
+    ttl: Duration,
+    renewable: bool,
+}
+
+/// What to do with a cached login token before using it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TokenAction {
+    Use,
+    Renew,
+    Login,
+}
+
+/// Renew once half the TTL has passed, log in again once it has expired
+pub fn token_action(age: Duration, ttl: Duration, renewable: bool) -> TokenAction {
+    if ttl.is_zero() {
+        // Tokens without a TTL never expire
+        TokenAction::Use
+    } else if age >= ttl {
+        TokenAction::Login
+    } else if age >= ttl / 2 {
+        if renewable {
+            TokenAction::Renew
+        } else {
+            TokenAction::Use
+        }
+    } else {
+        TokenAction::Use
+    }
+}
+
+static APPROLE_TOKEN: Mutex<Option<VaultToken>> = Mutex::new(None);
+
+fn token_from_auth(auth: &Value, issued: Instant) -> Option<VaultToken> {
+    Some(VaultToken {
+        token: auth["client_token"].as_str()?.to_string(),
+        issued,
+        ttl: Duration::from_secs(auth["lease_duration"].as_u64().unwrap_or(0)),
+        renewable: auth["renewable"].as_bool().unwrap_or(false),
+    })
+}
+
+async fn vault_token(source: &VaultSource) -> Result<String, BackupServiceError> {
+    let (role_id, secret_id) = match &source.auth {
+        VaultAuth::Token(token) => return Ok(token.clone()),
+        VaultAuth::TokenFile(file) => return Ok(std::fs::read_to_string(file)?.trim().to_string()),
+        VaultAuth::AppRole { role_id, secret_id } => (role_id, secret_id),
+    };
+
+    // The lock is never held across a request; concurrent refreshes just log in twice
+    let cached = APPROLE_TOKEN
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone();
+    if let Some(token) = cached {
+        match token_action(token.issued.elapsed(), token.ttl, token.renewable) {
+            TokenAction::Use => return Ok(token.token.clone()),
+            TokenAction::Renew => {
+                let issued = Instant::now();
+                match vault_request(
+                    source,
+                    "POST",
+                    "v1/auth/token/renew-self",
+                    Some(&token.token),
+                    None,
+                )
+                .await
+                .ok()
+                .and_then(|response| token_from_auth(&response["auth"], issued))
+                {
+                    Some(renewed) => {
+                        debug!(ttl_secs = %renewed.ttl.as_secs(), "Renewed Vault token");
+                        let value = renewed.token.clone();
+                        *APPROLE_TOKEN.lock().unwrap_or_else(|e| e.into_inner()) = Some(renewed);
+                        return Ok(value);
+                    }
+                    None => debug!("Vault token renewal failed, logging in again"),
+                }
+            }
+            TokenAction::Login => {}
+        }
+    }
+
+    let body = serde_json::json!({ "role_id": role_id, "secret_id": secret_id }).to_string();
+    let issued = Instant::now();
+    let response =
+        vault_request(source, "POST", "v1/auth/approle/login", None, Some(&body)).await?;
+    let token = token_from_auth(&response["auth"], issued).ok_or_else(|| {
+        BackupServiceError::ConfigurationError(
+            "Vault AppRole login returned no token.\n\nCheck VAULT_ROLE_ID and VAULT_SECRET_ID"
+                .to_string(),
+        )
+    })?;
+    info!(ttl_secs = %token.ttl.as_secs(), "Logged in to Vault via AppRole");
+    let value = token.token.clone();
+    *APPROLE_TOKEN.lock().unwrap_or_else(|e| e.into_inner()) = Some(token);
+    Ok(value)
+}
+
+#[derive(Debug, Clone)]
+struct CachedSecrets {
+    values: BTreeMap<String, String>,
+    fetched: Instant,
+    ttl: Duration,
+}
+
+static CACHE: Mutex<Option<CachedSecrets>> = Mutex::new(None);
+
+fn cache_ttl() -> Duration {
+    Duration::from_secs(
+        env_value(SECRET_CACHE_TTL_ENV_VAR)
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_CACHE_TTL_SECS),
+    )
+}
+
+/// Credentials from the configured provider, reusing them until the cache TTL (or a
+/// shorter Vault lease) runs out
+pub async fn load() -> Result<BTreeMap<String, String>, BackupServiceError> {
+    let provider = SecretProvider::from_env()?;
+    if provider == SecretProvider::Env {
+        return Ok(BTreeMap::new());
+    }
+
+    if let Some(cached) = CACHE.lock().unwrap_or_else(|e| e.into_inner()).as_ref()
+        && cached.fetched.elapsed() < cached.ttl
+    {
+        return Ok(cached.values.clone());
+    }
+
+    let (mut values, lease) = provider.fetch().await?;
+    values.retain(|key, _| SECRET_KEYS.contains(&key.as_str()));
+    let ttl = lease.map_or(cache_ttl(), |lease| lease.min(cache_ttl()));
+    info!(
+        provider = %env_value(SECRET_PROVIDER_ENV_VAR).unwrap_or_default(),
+        keys = %values.keys().cloned().collect::<Vec<_>>().join(", "),
+        "Loaded secrets"
+    );
+    *CACHE.lock().unwrap_or_else(|e| e.into_inner()) = Some(CachedSecrets {
+        values: values.clone(),
+        fetched: Instant::now(),
+        ttl,
+    });
+    Ok(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_vault_secret_kv_versions() {
+        let v2 = json!({
+            "lease_duration": 0,
+            "data": {
+                "data": { "RESTIC_PASSWORD": "pw", "aws_access_key_id": "AKIA" },
+                "metadata": { "version": 3 }
+            }
+        });
+        let secrets = parse_vault_secret(&v2);
+        assert_eq!(secrets["RESTIC_PASSWORD"], "pw");
+        assert_eq!(secrets["AWS_ACCESS_KEY_ID"], "AKIA");
+
+        let v1 = json!({ "lease_duration": 3600, "data": { "RESTIC_PASSWORD": "pw1" } });
+        assert_eq!(parse_vault_secret(&v1)["RESTIC_PASSWORD"], "pw1");
+    }
+
+    #[test]
+    fn test_flat_secrets_skips_nested_values() {
+        let document = json!({
+            "RESTIC_PASSWORD": "pw",
+            "port": 9000,
+            "sops": { "version": "3.8.1" }
+        });
+        let secrets = flat_secrets(&document);
+        assert_eq!(secrets.len(), 2);
+        assert_eq!(secrets["PORT"], "9000");
+        assert!(!secrets.contains_key("SOPS"));
+    }
+
+    #[test]
+    fn test_token_action() {
+        let hour = Duration::from_secs(3600);
+        assert_eq!(
+            token_action(Duration::from_secs(60), hour, true),
+            TokenAction::Use
+        );
+        assert_eq!(
+            token_action(Duration::from_secs(1900), hour, true),
+            TokenAction::Renew
+        );
+        assert_eq!(
+            token_action(Duration::from_secs(1900), hour, false),
+            TokenAction::Use
+        );
+        assert_eq!(token_action(hour, hour, true), TokenAction::Login);
+        assert_eq!(
+            token_action(Duration::from_secs(99_999), Duration::ZERO, false),
+            TokenAction::Use
+        );
+    }
+}
@@ -0,0 +1,276 @@
+use crate::errors::BackupServiceError;
+use crate::shared::backup_workflow::{BackupStatus, PathBackupResult};
+use crate::shared::coverage_workflow::disk_usage;
+use crate::shared::logs_workflow::log_dir;
+use crate::utils::{format_bytes, parse_size};
+use chrono::{Local, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// Env var with the bytes `run --seed` may add per day (e.g. 500G; unset = unlimited)
+pub const SEED_BUDGET_ENV_VAR: &str = "SEED_DAILY_BUDGET";
+/// Env var overriding the checkpoint file (default: `<RBS_LOG_DIR>/seed-state.json`)
+pub const SEED_STATE_ENV_VAR: &str = "SEED_STATE_FILE";
+
+pub fn seed_state_file() -> PathBuf {
+    std::env::var(SEED_STATE_ENV_VAR)
+        .ok()
+        .filter(|v| !v.trim().is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| log_dir().join("seed-state.json"))
+}
+
+fn seed_budget() -> Result<Option<u64>, BackupServiceError> {
+    match std::env::var(SEED_BUDGET_ENV_VAR) {
+        Ok(v) if !v.trim().is_empty() => Ok(Some(parse_size(&v)?)),
+        _ => Ok(None),
+    }
+}
+
+/// Checkpoint of a seeding that spans several runs
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SeedState {
+    /// Paths with an initial snapshot -> completion time (RFC 3339)
+    pub completed: BTreeMap<String, String>,
+    /// Local day the upload counter belongs to (YYYY-MM-DD)
+    pub day: String,
+    /// Bytes added on `day`, including any overrun carried over from earlier days
+    pub uploaded: u64,
+}
+
+impl SeedState {
+    pub fn load(file: &Path) -> Result<Self, BackupServiceError> {
+        if !file.exists() {
+            return Ok(Self::default());
+        }
+        Ok(serde_json::from_str(&std::fs::read_to_string(file)?)?)
+    }
+
+    /// Write via a temporary file so an interrupted run never leaves a truncated checkpoint
+    pub fn save(&self, file: &Path) -> Result<(), BackupServiceError> {
+        if let Some(dir) = file.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let tmp = file.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&tmp, file)?;
+        Ok(())
+    }
+
+    /// Move the counter to `today`; every day that passed pays one budget off it
+    ///
+    /// A path is never split, so one that overshoots the budget delays the following paths
+    /// until the overrun is paid off, keeping the long-run average within the budget.
+    pub fn roll_to(&mut self, today: NaiveDate, budget: Option<u64>) {
+        let days = match NaiveDate::parse_from_str(&self.day, "%Y-%m-%d") {
+            Ok(day) => (today - day).num_days(),
+            Err(_) => {
+                self.uploaded = 0;
+                1
+            }
+        };
+        if days > 0 {
+            self.uploaded = match budget {
+                Some(budget) => self
+                    .uploaded
+                    .saturating_sub(budget.saturating_mul(days as u64)),
+                None => 0,
+            };
+            self.day = today.format("%Y-%m-%d").to_string();
+        }
+    }
+
+    /// Whether a path of `size` bytes may start today
+    ///
+    /// Paths that fit the remaining budget start; an oversized one only starts on a day with
+    /// nothing uploaded yet, otherwise it could never be seeded. Unknown sizes (and seeded
+    /// paths, which only upload changes) start while any budget is left.
+    pub fn may_start(&self, budget: Option<u64>, size: Option<u64>) -> bool {
+        let Some(budget) = budget else {
+            return true;
+        };
+        if self.uploaded >= budget {
+            return false;
+        }
+        self.uploaded == 0 || size.is_none_or(|size| self.uploaded + size <= budget)
+    }
+
+    pub fn is_seeded(&self, path: &Path) -> bool {
+        self.completed.contains_key(&path.display().to_string())
+    }
+}
+
+/// Already seeded paths first (cheap incrementals), then the rest smallest first so as
+/// many paths as possible are protected early; paths of unknown size go last
+pub fn seed_order(
+    paths: Vec<PathBuf>,
+    state: &SeedState,
+    sizes: &HashMap<PathBuf, u64>,
+) -> Vec<PathBuf> {
+    let (mut ordered, mut pending): (Vec<PathBuf>, Vec<PathBuf>) =
+        paths.into_iter().partition(|p| state.is_seeded(p));
+    pending.sort_by_key(|p| sizes.get(p).copied().unwrap_or(u64::MAX));
+    ordered.extend(pending);
+    ordered
+}
+
+/// `run --seed`: the checkpoint plus this run's budget and path sizes
+#[derive(Debug)]
+pub struct SeedRun {
+    file: PathBuf,
+    state: SeedState,
+    budget: Option<u64>,
+    sizes: HashMap<PathBuf, u64>,
+}
+
+impl SeedRun {
+    /// Load the checkpoint, roll it to today and measure the paths still to seed
+    pub async fn start(paths: &[PathBuf]) -> Result<Self, BackupServiceError> {
+        let file = seed_state_file();
+        let budget = seed_budget()?;
+        let mut state = SeedState::load(&file)?;
+        state.roll_to(Local::now().date_naive(), budget);
+
+        let pending: Vec<&PathBuf> = paths.iter().filter(|p| !state.is_seeded(p)).collect();
+        info!(pending = %pending.len(), "Measuring paths still to seed");
+        let mut sizes: HashMap<PathBuf, u64> = HashMap::new();
+        for path in &pending {
+            if let Some(size) = disk_usage(path).await {
+                sizes.insert((*path).clone(), size);
+            }
+        }
+
+        info!(
+            seeded = %(paths.len() - pending.len()),
+            pending = %pending.len(),
+            pending_size = %format_bytes(sizes.values().sum())?,
+            daily_budget = %budget.map_or_else(|| Ok("unlimited".to_string()), format_bytes)?,
+            uploaded_today = %format_bytes(state.uploaded)?,
+            checkpoint = %file.display(),
+            "Seeding mode"
+        );
+        Ok(Self {
+            file,
+            state,
+            budget,
+            sizes,
+        })
+    }
+
+    pub fn order(&self, paths: Vec<PathBuf>) -> Vec<PathBuf> {
+        seed_order(paths, &self.state, &self.sizes)
+    }
+
+    pub fn may_start(&self, path: &Path) -> bool {
+        let size = if self.state.is_seeded(path) {
+            None
+        } else {
+            self.sizes.get(path).copied()
+        };
+        self.state.may_start(self.budget, size)
+    }
+
+    pub fn uploaded_today(&self) -> u64 {
+        self.state.uploaded
+    }
+
+    /// Count the path's upload and checkpoint it once it has a snapshot
+    pub fn finish_path(&mut self, path: &Path, result: &PathBackupResult) {
+        self.state.uploaded += result.summary.as_ref().map_or(0, |s| s.data_added);
+        if matches!(
+            result.status,
+            BackupStatus::Completed | BackupStatus::Degraded
+        ) {
+            self.state
+                .completed
+                .entry(path.display().to_string())
+                .or_insert_with(|| Utc::now().to_rfc3339());
+        }
+        if let Err(e) = self.state.save(&self.file) {
+            warn!(checkpoint = %self.file.display(), error = %e, "Could not save seed checkpoint; the next run may repeat this path");
+        }
+    }
+
+    pub fn is_complete(&self, paths: &[PathBuf]) -> bool {
+        paths.iter().all(|p| self.state.is_seeded(p))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GB: u64 = 1 << 30;
+
+    fn day(value: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_overrun_carries_into_following_days() {
+        let mut state = SeedState::default();
+        state.roll_to(day("2025-01-15"), Some(100 * GB));
+        assert!(state.may_start(Some(100 * GB), Some(250 * GB)));
+
+        state.uploaded += 250 * GB;
+        assert!(!state.may_start(Some(100 * GB), Some(GB)));
+        state.roll_to(day("2025-01-16"), Some(100 * GB));
+        assert_eq!(state.uploaded, 150 * GB);
+        assert!(!state.may_start(Some(100 * GB), None));
+        state.roll_to(day("2025-01-18"), Some(100 * GB));
+        assert_eq!(state.uploaded, 0);
+
+        // Same day again changes nothing
+        state.uploaded = 40 * GB;
+        state.roll_to(day("2025-01-18"), Some(100 * GB));
+        assert_eq!(state.uploaded, 40 * GB);
+        assert!(state.may_start(Some(100 * GB), Some(60 * GB)));
+        assert!(!state.may_start(Some(100 * GB), Some(61 * GB)));
+        assert!(state.may_start(None, Some(10_000 * GB)));
+    }
+
+    #[test]
+    fn test_seed_order() {
+        let mut state = SeedState::default();
+        state
+            .completed
+            .insert("/etc".to_string(), "2025-01-15T03:00:00+00:00".to_string());
+        let sizes: HashMap<PathBuf, u64> = [
+            (PathBuf::from("/srv/media"), 4000 * GB),
+            (PathBuf::from("/home/tim"), 80 * GB),
+        ]
+        .into_iter()
+        .collect();
+        let paths = ["/srv/media", "/mnt/unknown", "/etc", "/home/tim"]
+            .iter()
+            .map(PathBuf::from)
+            .collect();
+
+        assert_eq!(
+            seed_order(paths, &state, &sizes),
+            ["/etc", "/home/tim", "/srv/media", "/mnt/unknown"]
+                .iter()
+                .map(PathBuf::from)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_checkpoint_round_trip() -> Result<(), BackupServiceError> {
+        let dir = tempfile::tempdir()?;
+        let file = dir.path().join("state/seed-state.json");
+        assert_eq!(SeedState::load(&file)?, SeedState::default());
+
+        let mut state = SeedState::default();
+        state.roll_to(day("2025-01-15"), None);
+        state.uploaded = 7;
+        state
+            .completed
+            .insert("/etc".to_string(), Utc::now().to_rfc3339());
+        state.save(&file)?;
+        assert_eq!(SeedState::load(&file)?, state);
+        Ok(())
+    }
+}
@@ -0,0 +1,512 @@
+use crate::errors::BackupServiceError;
+use crate::shared::dependencies::{MIN_RESTIC_VERSION, Version, parse_version};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tracing::{info, warn};
+
+/// GitHub repository publishing release binaries
+pub const RELEASE_REPO: &str = "timlisemer/restic-backup-service";
+
+/// Env var overriding the release repository (forks, mirrors)
+pub const RELEASE_REPO_ENV_VAR: &str = "RBS_RELEASE_REPO";
+
+/// Env var holding a minisign public key; when set, release signatures are required.
+/// Without it only the `.sha256` from the same release is checked, which catches corrupt
+/// downloads but not a tampered release
+pub const RELEASE_PUBKEY_ENV_VAR: &str = "RBS_RELEASE_PUBKEY";
+
+const BINARY_NAME: &str = "restic-backup-service";
+
+/// restic release `self install-restic` installs unless `--version` is given
+pub const PINNED_RESTIC_VERSION: Version = Version(0, 17, 3);
+
+const RESTIC_RELEASES_URL: &str = "https://github.com/restic/restic/releases/download";
+
+/// `self install` / `self update` options
+#[derive(Debug, Clone, Default)]
+pub struct SelfUpdateOptions {
+    /// Release tag to fetch (default: latest)
+    pub version: Option<String>,
+    /// Reinstall even when the running version already matches
+    pub force: bool,
+}
+
+/// Release asset name for a target, e.g. `restic-backup-service-x86_64-linux`
+pub fn asset_name(arch: &str, os: &str) -> Result<String, BackupServiceError> {
+    let arch = match arch {
+        "x86_64" | "aarch64" => arch,
+        other => {
+            return Err(BackupServiceError::ConfigurationError(format!(
+                "No release binaries are published for architecture: {}.\n\nSupported: x86_64, aarch64",
+                other
+            )));
+        }
+    };
+    let os = match os {
+        "linux" | "macos" => os,
+        other => {
+            return Err(BackupServiceError::ConfigurationError(format!(
+                "No release binaries are published for OS: {}.\n\nSupported: linux, macos",
+                other
+            )));
+        }
+    };
+    Ok(format!("{}-{}-{}", BINARY_NAME, arch, os))
+}
+
+/// restic release asset for a target, e.g. `restic_0.17.3_linux_amd64.bz2`
+pub fn restic_asset_name(
+    version: &str,
+    arch: &str,
+    os: &str,
+) -> Result<String, BackupServiceError> {
+    let arch = match arch {
+        "x86_64" => "amd64",
+        "aarch64" => "arm64",
+        other => {
+            return Err(BackupServiceError::ConfigurationError(format!(
+                "No restic binaries are installed for architecture: {}.\n\nSupported: x86_64, aarch64",
+                other
+            )));
+        }
+    };
+    let os = match os {
+        "linux" => "linux",
+        "macos" => "darwin",
+        other => {
+            return Err(BackupServiceError::ConfigurationError(format!(
+                "No restic binaries are installed for OS: {}.\n\nSupported: linux, macos",
+                other
+            )));
+        }
+    };
+    Ok(format!("restic_{}_{}_{}.bz2", version, os, arch))
+}
+
+/// Strip a leading `v` from release tags
+pub fn normalize_version(tag: &str) -> &str {
+    tag.trim().trim_start_matches('v')
+}
+
+/// Find the digest for `asset` in `sha256sum`-style output (bare digest or `<digest>  <file>` lines)
+pub fn parse_checksum(contents: &str, asset: &str) -> Option<String> {
+    contents.lines().find_map(|line| {
+        let mut parts = line.split_whitespace();
+        let digest = parts.next()?;
+        let valid = digest.len() == 64 && digest.chars().all(|c| c.is_ascii_hexdigit());
+        match parts.next() {
+            _ if !valid => None,
+            None => Some(digest.to_lowercase()),
+            Some(file) if file.trim_start_matches('*') == asset => Some(digest.to_lowercase()),
+            Some(_) => None,
+        }
+    })
+}
+
+pub fn sha256_hex(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+fn release_repo() -> String {
+    std::env::var(RELEASE_REPO_ENV_VAR)
+        .ok()
+        .filter(|v| !v.trim().is_empty())
+        .unwrap_or_else(|| RELEASE_REPO.to_string())
+}
+
+/// Fetch a URL with curl, following redirects and failing on HTTP errors
+fn download(url: &str, dest: &Path) -> Result<(), BackupServiceError> {
+    let output = Command::new("curl")
+        .args([
+            "--fail",
+            "--silent",
+            "--show-error",
+            "--location",
+            "--output",
+        ])
+        .arg(dest)
+        .arg(url)
+        .output()
+        .map_err(|_| BackupServiceError::CommandNotFound("Failed to execute curl".to_string()))?;
+
+    if !output.status.success() {
+        return Err(BackupServiceError::CommandFailed(format!(
+            "Download of {} failed: {}",
+            url,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(())
+}
+
+/// Resolve `latest` to a concrete tag through the GitHub API
+fn resolve_tag(repo: &str, version: Option<&str>) -> Result<String, BackupServiceError> {
+    if let Some(version) = version {
+        let version = normalize_version(version);
+        return Ok(format!("v{}", version));
+    }
+
+    let output = Command::new("curl")
+        .args(["--fail", "--silent", "--show-error", "--location"])
+        .arg(format!(
+            "https://api.github.com/repos/{}/releases/latest",
+            repo
+        ))
+        .output()
+        .map_err(|_| BackupServiceError::CommandNotFound("Failed to execute curl".to_string()))?;
+    if !output.status.success() {
+        return Err(BackupServiceError::CommandFailed(format!(
+            "Could not query the latest release of {}: {}",
+            repo,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    let release: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    release["tag_name"]
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| {
+            BackupServiceError::CommandFailed(format!("No release tag found for {}", repo))
+        })
+}
+
+/// Verify the minisign signature when a public key is configured
+fn verify_signature(
+    binary: &Path,
+    signature_url: &str,
+    workdir: &Path,
+) -> Result<(), BackupServiceError> {
+    let Ok(pubkey) = std::env::var(RELEASE_PUBKEY_ENV_VAR) else {
+        warn!(
+            "{} not set, the release signature is NOT verified: the checksum comes from the same release and does not detect a tampered one",
+            RELEASE_PUBKEY_ENV_VAR
+        );
+        return Ok(());
+    };
+
+    let signature = workdir.join("binary.minisig");
+    download(signature_url, &signature)?;
+    let output = Command::new("minisign")
+        .args(["-V", "-P", pubkey.trim(), "-m"])
+        .arg(binary)
+        .arg("-x")
+        .arg(&signature)
+        .output()
+        .map_err(|_| {
+            BackupServiceError::CommandNotFound("Failed to execute minisign".to_string())
+        })?;
+    if !output.status.success() {
+        return Err(BackupServiceError::CommandFailed(format!(
+            "Signature verification failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    info!("Release signature verified");
+    Ok(())
+}
+
+/// Download and verify the release binary; returns the tag and the verified bytes
+fn fetch_release(
+    options: &SelfUpdateOptions,
+) -> Result<Option<(String, Vec<u8>)>, BackupServiceError> {
+    let repo = release_repo();
+    let asset = asset_name(std::env::consts::ARCH, std::env::consts::OS)?;
+    let tag = resolve_tag(&repo, options.version.as_deref())?;
+
+    let current = env!("CARGO_PKG_VERSION");
+    if normalize_version(&tag) == current && !options.force {
+        info!(version = %current, "Already running this release (use --force to reinstall)");
+        return Ok(None);
+    }
+
+    let base_url = format!("https://github.com/{}/releases/download/{}", repo, tag);
+    // Private (0700, random name) and removed on drop
+    let tempdir = tempfile::Builder::new()
+        .prefix("rbs-self-update-")
+        .tempdir()?;
+    let workdir = tempdir.path();
+
+    let result = (|| {
+        info!(tag = %tag, asset = %asset, "Downloading release binary");
+        let binary = workdir.join(&asset);
+        let checksum = workdir.join("binary.sha256");
+        download(&format!("{}/{}", base_url, asset), &binary)?;
+        download(&format!("{}/{}.sha256", base_url, asset), &checksum)?;
+
+        let expected =
+            parse_checksum(&std::fs::read_to_string(&checksum)?, &asset).ok_or_else(|| {
+                BackupServiceError::CommandFailed(format!("No checksum for {} in release", asset))
+            })?;
+        let data = std::fs::read(&binary)?;
+        let actual = sha256_hex(&data);
+        if actual != expected {
+            return Err(BackupServiceError::CommandFailed(format!(
+                "Checksum mismatch for {}: expected {}, got {}",
+                asset, expected, actual
+            )));
+        }
+        info!("Checksum verified");
+
+        verify_signature(&binary, &format!("{}/{}.minisig", base_url, asset), workdir)?;
+        Ok((tag.clone(), data))
+    })();
+
+    result.map(Some)
+}
+
+/// Atomically place `data` at `target`: write a sibling temp file, mark it executable, rename
+fn install_binary(data: &[u8], target: &Path) -> Result<(), BackupServiceError> {
+    use std::os::unix::fs::PermissionsExt;
+
+    if target.starts_with("/nix/store") {
+        return Err(BackupServiceError::ConfigurationError(format!(
+            "{} is managed by Nix and cannot be replaced in place.\n\nUpdate the flake input and rebuild instead.",
+            target.display()
+        )));
+    }
+
+    let dir = target.parent().ok_or_else(|| {
+        BackupServiceError::ConfigurationError(format!(
+            "Invalid install path: {}",
+            target.display()
+        ))
+    })?;
+    std::fs::create_dir_all(dir)?;
+
+    let name = target
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| BINARY_NAME.to_string());
+    let staging = dir.join(format!(".{}.new", name));
+    std::fs::write(&staging, data)?;
+    std::fs::set_permissions(&staging, std::fs::Permissions::from_mode(0o755))?;
+    if let Err(e) = std::fs::rename(&staging, target) {
+        let _ = std::fs::remove_file(&staging);
+        return Err(e.into());
+    }
+    Ok(())
+}
+
+/// `self install`: put the release binary at `path`
+pub fn execute_self_install(
+    path: PathBuf,
+    options: SelfUpdateOptions,
+) -> Result<(), BackupServiceError> {
+    // Installing to a new location always downloads, even if versions match
+    let options = SelfUpdateOptions {
+        force: true,
+        ..options
+    };
+    if let Some((tag, data)) = fetch_release(&options)? {
+        install_binary(&data, &path)?;
+        info!(tag = %tag, path = %path.display(), "Installed release binary");
+    }
+    Ok(())
+}
+
+/// restic releases are bzip2-compressed single binaries; the already verified bytes are
+/// piped through `bzip2 -dc`, so the file is never read a second time
+fn decompress_bzip2(archive: &[u8], name: &str) -> Result<Vec<u8>, BackupServiceError> {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let mut child = Command::new("bzip2")
+        .arg("-dc")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|_| BackupServiceError::CommandNotFound("Failed to execute bzip2".to_string()))?;
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    // Feed stdin from a second thread so a full stdout pipe cannot block the write
+    let output = std::thread::scope(|scope| {
+        let writer = scope.spawn(move || stdin.write_all(archive));
+        let output = child.wait_with_output();
+        // A write error (bzip2 exited early) shows up in its exit status and stderr
+        let _ = writer.join();
+        output
+    })?;
+    if !output.status.success() {
+        return Err(BackupServiceError::CommandFailed(format!(
+            "Unpacking {} failed: {}",
+            name,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(output.stdout)
+}
+
+/// `self install-restic`: put the official static restic binary at `path`
+///
+/// The download is checked against the release's SHA256SUMS; the installed binary must
+/// then report the requested version.
+pub fn execute_restic_install(
+    path: PathBuf,
+    version: Option<String>,
+) -> Result<(), BackupServiceError> {
+    let version = version
+        .as_deref()
+        .map(|v| normalize_version(v).to_string())
+        .unwrap_or_else(|| PINNED_RESTIC_VERSION.to_string());
+    match parse_version(&version) {
+        Some(parsed) if parsed >= MIN_RESTIC_VERSION => {}
+        _ => {
+            return Err(BackupServiceError::ConfigurationError(format!(
+                "Invalid restic version: {}.\n\nUse a release from {} on, e.g. {}",
+                version, MIN_RESTIC_VERSION, PINNED_RESTIC_VERSION
+            )));
+        }
+    }
+    let asset = restic_asset_name(&version, std::env::consts::ARCH, std::env::consts::OS)?;
+    let base_url = format!("{}/v{}", RESTIC_RELEASES_URL, version);
+    // Private (0700, random name) and removed on drop
+    let tempdir = tempfile::Builder::new()
+        .prefix("rbs-restic-install-")
+        .tempdir()?;
+    let workdir = tempdir.path();
+
+    let result = (|| {
+        info!(version = %version, asset = %asset, "Downloading restic");
+        let archive = workdir.join(&asset);
+        let checksums = workdir.join("SHA256SUMS");
+        download(&format!("{}/{}", base_url, asset), &archive)?;
+        download(&format!("{}/SHA256SUMS", base_url), &checksums)?;
+
+        let expected =
+            parse_checksum(&std::fs::read_to_string(&checksums)?, &asset).ok_or_else(|| {
+                BackupServiceError::CommandFailed(format!("No checksum for {} in release", asset))
+            })?;
+        let data = std::fs::read(&archive)?;
+        let actual = sha256_hex(&data);
+        if actual != expected {
+            return Err(BackupServiceError::CommandFailed(format!(
+                "Checksum mismatch for {}: expected {}, got {}",
+                asset, expected, actual
+            )));
+        }
+        info!("Checksum verified");
+        decompress_bzip2(&data, &asset)
+    })();
+    drop(tempdir);
+    install_binary(&result?, &path)?;
+
+    let output = Command::new(&path).arg("version").output()?;
+    let installed = parse_version(&String::from_utf8_lossy(&output.stdout));
+    if installed != parse_version(&version) {
+        return Err(BackupServiceError::CommandFailed(format!(
+            "{} does not report restic {} after installing",
+            path.display(),
+            version
+        )));
+    }
+    info!(
+        version = %version,
+        path = %path.display(),
+        "Installed restic; set RESTIC_BIN to this path unless it comes first in PATH"
+    );
+    Ok(())
+}
+
+/// `self update`: replace the running executable with the release binary
+pub fn execute_self_update(options: SelfUpdateOptions) -> Result<(), BackupServiceError> {
+    let current_exe = std::env::current_exe()?.canonicalize()?;
+    if let Some((tag, data)) = fetch_release(&options)? {
+        install_binary(&data, &current_exe)?;
+        info!(
+            from = %env!("CARGO_PKG_VERSION"),
+            to = %normalize_version(&tag),
+            path = %current_exe.display(),
+            "Updated restic-backup-service"
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_asset_name_for_supported_targets() -> Result<(), BackupServiceError> {
+        assert_eq!(
+            asset_name("x86_64", "linux")?,
+            "restic-backup-service-x86_64-linux"
+        );
+        assert_eq!(
+            asset_name("aarch64", "macos")?,
+            "restic-backup-service-aarch64-macos"
+        );
+        assert!(asset_name("riscv64", "linux").is_err());
+        assert!(asset_name("x86_64", "windows").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_restic_asset_name() -> Result<(), BackupServiceError> {
+        assert_eq!(
+            restic_asset_name("0.17.3", "x86_64", "linux")?,
+            "restic_0.17.3_linux_amd64.bz2"
+        );
+        assert_eq!(
+            restic_asset_name("0.17.3", "aarch64", "macos")?,
+            "restic_0.17.3_darwin_arm64.bz2"
+        );
+        assert!(restic_asset_name("0.17.3", "riscv64", "linux").is_err());
+        assert!(PINNED_RESTIC_VERSION >= MIN_RESTIC_VERSION);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_checksum_formats() {
+        let digest = "a".repeat(64);
+        assert_eq!(parse_checksum(&digest, "any"), Some(digest.clone()));
+
+        let listing = format!(
+            "{}  restic-backup-service-aarch64-linux\n{}  restic-backup-service-x86_64-linux\n",
+            "b".repeat(64),
+            "C".repeat(64)
+        );
+        assert_eq!(
+            parse_checksum(&listing, "restic-backup-service-x86_64-linux"),
+            Some("c".repeat(64))
+        );
+        assert_eq!(parse_checksum(&listing, "missing"), None);
+        assert_eq!(parse_checksum("not-a-digest  file", "file"), None);
+    }
+
+    #[test]
+    fn test_sha256_hex() {
+        assert_eq!(
+            sha256_hex(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn test_install_binary_replaces_atomically() -> Result<(), BackupServiceError> {
+        let dir = tempfile::tempdir()?;
+        let target = dir.path().join("bin").join(BINARY_NAME);
+        install_binary(b"v1", &target)?;
+        install_binary(b"v2", &target)?;
+        assert_eq!(std::fs::read(&target)?, b"v2");
+        assert!(
+            !dir.path()
+                .join("bin")
+                .join(".restic-backup-service.new")
+                .exists()
+        );
+        assert!(install_binary(b"v3", Path::new("/nix/store/abc/bin/x")).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_normalize_version() {
+        assert_eq!(normalize_version("v1.2.3"), "1.2.3");
+        assert_eq!(normalize_version("1.2.3"), "1.2.3");
+    }
+}
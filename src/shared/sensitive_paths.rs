@@ -0,0 +1,89 @@
+use std::path::{Path, PathBuf};
+
+/// Env var listing paths (comma-separated) only backed up when a run opts in
+///
+/// `~/x` expands to `x` below every user home in `/home`.
+pub const SENSITIVE_PATHS_ENV_VAR: &str = "BACKUP_SENSITIVE_PATHS";
+
+/// Extra tag on snapshots of sensitive paths
+pub const SENSITIVE_TAG: &str = "sensitive";
+
+/// Sensitive paths from BACKUP_SENSITIVE_PATHS, with `~/` expanded against /home
+pub fn sensitive_paths() -> Vec<PathBuf> {
+    let homes: Vec<PathBuf> = std::fs::read_dir("/home")
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok().map(|e| e.path()))
+                .filter(|p| p.is_dir())
+                .collect()
+        })
+        .unwrap_or_default();
+    expand_sensitive_paths(
+        &std::env::var(SENSITIVE_PATHS_ENV_VAR).unwrap_or_default(),
+        &homes,
+    )
+}
+
+/// Parse a comma-separated list, expanding `~/x` to `<home>/x` for each of `homes`
+pub fn expand_sensitive_paths(value: &str, homes: &[PathBuf]) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = Vec::new();
+    for entry in value.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        match entry.strip_prefix("~/") {
+            Some(relative) => paths.extend(homes.iter().map(|home| home.join(relative))),
+            None => paths.push(PathBuf::from(entry.trim_end_matches('/'))),
+        }
+    }
+    paths.sort();
+    paths.dedup();
+    paths
+}
+
+/// Whether `path` is a sensitive path or lies inside one
+pub fn is_sensitive(path: &Path, sensitive: &[PathBuf]) -> bool {
+    sensitive.iter().any(|s| path.starts_with(s))
+}
+
+/// Sensitive paths strictly inside `path`; they are excluded so only their own
+/// (tagged) repositories ever hold their contents
+pub fn nested_sensitive<'a>(path: &Path, sensitive: &'a [PathBuf]) -> Vec<&'a PathBuf> {
+    sensitive
+        .iter()
+        .filter(|s| s.starts_with(path) && s.as_path() != path)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_home_relative_entries() {
+        let homes = vec![PathBuf::from("/home/alice"), PathBuf::from("/home/bob")];
+        assert_eq!(
+            expand_sensitive_paths("~/.ssh, /etc/ssl/private/ ,", &homes),
+            vec![
+                PathBuf::from("/etc/ssl/private"),
+                PathBuf::from("/home/alice/.ssh"),
+                PathBuf::from("/home/bob/.ssh"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sensitive_and_nested() {
+        let sensitive = vec![
+            PathBuf::from("/home/alice/.gnupg"),
+            PathBuf::from("/home/alice/.ssh"),
+        ];
+        assert!(is_sensitive(Path::new("/home/alice/.ssh"), &sensitive));
+        assert!(is_sensitive(Path::new("/home/alice/.ssh/keys"), &sensitive));
+        // Component-wise: .sshfs is not inside .ssh
+        assert!(!is_sensitive(Path::new("/home/alice/.sshfs"), &sensitive));
+
+        assert_eq!(
+            nested_sensitive(Path::new("/home/alice"), &sensitive).len(),
+            2
+        );
+        assert!(nested_sensitive(Path::new("/home/alice/.ssh"), &sensitive).is_empty());
+    }
+}
@@ -0,0 +1,83 @@
+use crate::shared::container_quiesce;
+use std::collections::BTreeSet;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tracing::warn;
+
+/// Set once SIGTERM/SIGINT asked a long-running command to stop
+static SHUTDOWN_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+/// PIDs of the child processes currently running (restic, ssh), see `track_child`
+static CHILDREN: Mutex<BTreeSet<u32>> = Mutex::new(BTreeSet::new());
+
+/// Exit code of a process stopped by SIGINT (128 + 2)
+const EXIT_INTERRUPTED: i32 = 130;
+
+/// Ask running workflows to stop at the next safe point (e.g. between backup paths)
+pub fn request() {
+    SHUTDOWN_REQUESTED.store(true, Ordering::SeqCst);
+}
+
+pub fn is_requested() -> bool {
+    SHUTDOWN_REQUESTED.load(Ordering::SeqCst)
+}
+
+/// Registration of a running child process; dropping it (the child exited) unregisters it
+pub struct TrackedChild(u32);
+
+impl Drop for TrackedChild {
+    fn drop(&mut self) {
+        if let Ok(mut children) = CHILDREN.lock() {
+            children.remove(&self.0);
+        }
+    }
+}
+
+/// Register a spawned child so an interrupt can stop it cleanly; `None` once it has exited
+pub fn track_child(pid: Option<u32>) -> Option<TrackedChild> {
+    let pid = pid?;
+    CHILDREN.lock().ok()?.insert(pid);
+    Some(TrackedChild(pid))
+}
+
+/// Send SIGINT to every tracked child and return how many there were
+///
+/// restic handles SIGINT by removing its repository lock before it exits, unlike the
+/// SIGKILL a dropped child gets from `kill_on_drop`.
+fn interrupt_children() -> usize {
+    let children = CHILDREN.lock().map(|c| c.clone()).unwrap_or_default();
+    for pid in &children {
+        // SAFETY: kill only sends a signal; the PID belongs to a child we have not reaped yet
+        unsafe { libc::kill(*pid as libc::pid_t, libc::SIGINT) };
+    }
+    children.len()
+}
+
+/// Handle Ctrl-C for one-shot commands
+///
+/// Without running children (e.g. at a prompt) the process exits at once, after resuming
+/// containers quiesced for a volume backup. Otherwise the
+/// children are interrupted so they release their locks, no further work is started and
+/// the workflow prints its partial summary; a second Ctrl-C exits immediately.
+pub fn handle_interrupts() {
+    tokio::spawn(async {
+        if tokio::signal::ctrl_c().await.is_err() {
+            return;
+        }
+        request();
+        if interrupt_children() == 0 {
+            exit_interrupted().await;
+        }
+        warn!("Interrupted, stopping the running restic commands (Ctrl-C again to exit now)");
+        if tokio::signal::ctrl_c().await.is_ok() {
+            interrupt_children();
+            exit_interrupted().await;
+        }
+    });
+}
+
+/// Exit with 130 once paused or stopped containers are back; exiting skips every `Drop`
+async fn exit_interrupted() -> ! {
+    container_quiesce::resume_registered().await;
+    std::process::exit(EXIT_INTERRUPTED);
+}
@@ -0,0 +1,200 @@
+use crate::errors::BackupServiceError;
+use crate::shared::constants::{CATEGORY_DOCKER_VOLUME, CATEGORY_SYSTEM, CATEGORY_USER_HOME};
+use crate::shared::logs_workflow::parse_since;
+use crate::shared::operations::SnapshotInfo;
+use crate::shared::timestamps::parse_timestamp_at;
+use chrono::{DateTime, Local, SecondsFormat, TimeZone, Utc};
+use serde::{Serialize, Serializer};
+use std::path::PathBuf;
+
+/// Which snapshots `snapshots` shows; empty fields match everything
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct SnapshotFilter {
+    /// Backed-up path or any directory above it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_optional_time"
+    )]
+    pub since: Option<DateTime<Utc>>,
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_optional_time"
+    )]
+    pub until: Option<DateTime<Utc>>,
+    /// The snapshot must carry every one of these tags
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub categories: Vec<String>,
+}
+
+impl SnapshotFilter {
+    /// Build from CLI values, validating categories and time bounds
+    pub fn new(
+        path: Option<String>,
+        since: Option<&str>,
+        until: Option<&str>,
+        tags: Vec<String>,
+        categories: Vec<String>,
+    ) -> Result<Self, BackupServiceError> {
+        Self::new_at(path, since, until, tags, categories, Local::now())
+    }
+
+    fn new_at<Tz: TimeZone>(
+        path: Option<String>,
+        since: Option<&str>,
+        until: Option<&str>,
+        tags: Vec<String>,
+        categories: Vec<String>,
+        now: DateTime<Tz>,
+    ) -> Result<Self, BackupServiceError> {
+        for category in &categories {
+            if ![CATEGORY_USER_HOME, CATEGORY_DOCKER_VOLUME, CATEGORY_SYSTEM]
+                .contains(&category.as_str())
+            {
+                return Err(BackupServiceError::ConfigurationError(format!(
+                    "Unknown backup category: {}.\n\nValid categories are: {}, {}, {}",
+                    category, CATEGORY_USER_HOME, CATEGORY_DOCKER_VOLUME, CATEGORY_SYSTEM
+                )));
+            }
+        }
+        let since = since.map(|v| parse_bound(v, now.clone())).transpose()?;
+        let until = until.map(|v| parse_bound(v, now.clone())).transpose()?;
+        if let (Some(since), Some(until)) = (since, until)
+            && since > until
+        {
+            return Err(BackupServiceError::ConfigurationError(
+                "--since is later than --until.\n\nSwap the two values".to_string(),
+            ));
+        }
+        Ok(Self {
+            path: path.map(|p| match p.trim_end_matches('/') {
+                "" => PathBuf::from("/"),
+                trimmed => PathBuf::from(trimmed),
+            }),
+            since,
+            until,
+            tags,
+            categories,
+        })
+    }
+
+    /// Whether a repository of this category needs to be read at all
+    pub fn wants_category(&self, category: &str) -> bool {
+        self.categories.is_empty() || self.categories.iter().any(|c| c == category)
+    }
+
+    pub fn matches(&self, snapshot: &SnapshotInfo, category: &str) -> bool {
+        self.wants_category(category)
+            && self
+                .path
+                .as_deref()
+                .is_none_or(|path| snapshot.path.starts_with(path))
+            && self.since.is_none_or(|since| snapshot.time >= since)
+            && self.until.is_none_or(|until| snapshot.time <= until)
+            && self.tags.iter().all(|tag| snapshot.tags.contains(tag))
+    }
+}
+
+/// RFC 3339 in UTC with second precision, like the other JSON outputs
+pub(crate) fn serialize_time<S: Serializer>(
+    time: &DateTime<Utc>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&time.to_rfc3339_opts(SecondsFormat::Secs, true))
+}
+
+fn serialize_optional_time<S: Serializer>(
+    time: &Option<DateTime<Utc>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match time {
+        Some(time) => serialize_time(time, serializer),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// A point in time (`2025-01-15`, `yesterday 14:00`, `2h ago`) or a span back from now (`7d`)
+fn parse_bound<Tz: TimeZone>(
+    value: &str,
+    now: DateTime<Tz>,
+) -> Result<DateTime<Utc>, BackupServiceError> {
+    parse_timestamp_at(value, now.clone()).or_else(|e| {
+        parse_since(value)
+            .map(|span| (now - span).with_timezone(&Utc))
+            .map_err(|_| e)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(time: &str, path: &str, tags: &[&str]) -> SnapshotInfo {
+        SnapshotInfo {
+            time: time.parse().unwrap(),
+            path: PathBuf::from(path),
+            id: "abc123".to_string(),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            summary: None,
+        }
+    }
+
+    fn now() -> DateTime<Utc> {
+        "2025-01-20T12:00:00Z".parse().unwrap()
+    }
+
+    #[test]
+    fn test_filter_by_path_time_and_tags() -> Result<(), BackupServiceError> {
+        let filter = SnapshotFilter::new_at(
+            Some("/home/tim/".to_string()),
+            Some("7d"),
+            Some("2025-01-19T00:00:00Z"),
+            vec!["user_home".to_string()],
+            vec![],
+            now(),
+        )?;
+        assert_eq!(filter.since, Some("2025-01-13T12:00:00Z".parse().unwrap()));
+
+        let inside = snapshot("2025-01-15T10:00:00Z", "/home/tim/docs", &["user_home"]);
+        assert!(filter.matches(&inside, "user_home"));
+        // /home/timothy is not below /home/tim
+        let sibling = snapshot("2025-01-15T10:00:00Z", "/home/timothy", &["user_home"]);
+        assert!(!filter.matches(&sibling, "user_home"));
+        let too_old = snapshot("2025-01-10T10:00:00Z", "/home/tim", &["user_home"]);
+        assert!(!filter.matches(&too_old, "user_home"));
+        let too_new = snapshot("2025-01-19T10:00:00Z", "/home/tim", &["user_home"]);
+        assert!(!filter.matches(&too_new, "user_home"));
+        let untagged = snapshot("2025-01-15T10:00:00Z", "/home/tim", &[]);
+        assert!(!filter.matches(&untagged, "user_home"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_filter_categories_and_validation() -> Result<(), BackupServiceError> {
+        let filter = SnapshotFilter::new_at(
+            None,
+            None,
+            None,
+            vec![],
+            vec!["docker_volume".to_string()],
+            now(),
+        )?;
+        assert!(filter.wants_category("docker_volume"));
+        assert!(!filter.wants_category("system"));
+        assert!(
+            SnapshotFilter::default()
+                .matches(&snapshot("2025-01-15T10:00:00Z", "/etc", &[]), "system")
+        );
+
+        let invalid_category =
+            SnapshotFilter::new_at(None, None, None, vec![], vec!["media".to_string()], now());
+        assert!(invalid_category.is_err());
+        let reversed = SnapshotFilter::new_at(None, Some("1d"), Some("7d"), vec![], vec![], now());
+        assert!(reversed.is_err());
+        assert!(SnapshotFilter::new_at(None, Some("soon"), None, vec![], vec![], now()).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,194 @@
+use crate::config::Config;
+use crate::errors::BackupServiceError;
+use crate::shared::display::DisplayFormatter;
+use crate::shared::error_policy::{DISCOVERY_ERROR_POLICY_ENV_VAR, ErrorPolicy};
+use crate::shared::operations::{
+    DiscoveryFailure, RepositoryOperations, SnapshotCollector, SnapshotInfo,
+};
+use crate::shared::snapshot_filter::{SnapshotFilter, serialize_time};
+use crate::utils::validate_credentials;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::json;
+use tokio::task::JoinSet;
+use tracing::{info, warn};
+
+/// `snapshots` options
+#[derive(Debug, Clone, Default)]
+pub struct SnapshotsOptions {
+    pub filter: SnapshotFilter,
+    pub json_output: bool,
+}
+
+/// One snapshot of the listing
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SnapshotRow {
+    #[serde(serialize_with = "serialize_time")]
+    pub time: DateTime<Utc>,
+    pub id: String,
+    pub path: String,
+    pub category: String,
+    pub repo_subpath: String,
+    pub tags: Vec<String>,
+    /// Bytes the snapshot added to the repository (restic >= 0.17)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data_added: Option<u64>,
+}
+
+/// Snapshots of one repository, tagged with where they came from
+pub struct RepositorySnapshots {
+    pub repo_subpath: String,
+    pub category: String,
+    pub snapshots: Vec<SnapshotInfo>,
+}
+
+/// Apply the filter to every repository's snapshots, newest first
+pub fn filter_snapshots(
+    repos: &[RepositorySnapshots],
+    filter: &SnapshotFilter,
+) -> Vec<SnapshotRow> {
+    let mut rows: Vec<SnapshotRow> = repos
+        .iter()
+        .flat_map(|repo| {
+            repo.snapshots
+                .iter()
+                .filter(|s| filter.matches(s, &repo.category))
+                .map(|s| SnapshotRow {
+                    time: s.time,
+                    id: s.id.clone(),
+                    path: s.path.display().to_string(),
+                    category: repo.category.clone(),
+                    repo_subpath: repo.repo_subpath.clone(),
+                    tags: s.tags.clone(),
+                    data_added: s.summary.as_ref().map(|summary| summary.data_added),
+                })
+        })
+        .collect();
+    rows.sort_by(|a, b| b.time.cmp(&a.time).then_with(|| a.path.cmp(&b.path)));
+    rows
+}
+
+/// List the snapshots of every repository of a host that match the filter
+pub async fn execute_snapshots_workflow(
+    config: Config,
+    host: Option<String>,
+    options: SnapshotsOptions,
+) -> Result<(), BackupServiceError> {
+    let hostname = host.unwrap_or_else(|| config.hostname.clone());
+    config.set_aws_env()?;
+    validate_credentials(&config).await?;
+
+    let operations = RepositoryOperations::new(config.clone())?;
+    let discovery = operations.discover_all_repositories(&hostname).await?;
+    let mut failures = discovery.failures;
+    // Repositories of unselected categories are never opened
+    let repos: Vec<_> = discovery
+        .repos
+        .into_iter()
+        .filter(|r| options.filter.wants_category(&r.category))
+        .collect();
+    if !options.json_output {
+        info!(hostname = %hostname, repo_count = %repos.len(), "Reading snapshots");
+    }
+
+    let collector = SnapshotCollector::new(config.clone(), &hostname)?;
+    let mut tasks = JoinSet::new();
+    for repo in repos {
+        let collector = collector.clone();
+        tasks.spawn(async move {
+            let result = collector.get_snapshots(&repo.repo_subpath).await;
+            (repo, result)
+        });
+    }
+
+    let policy = ErrorPolicy::from_env(DISCOVERY_ERROR_POLICY_ENV_VAR)?;
+    let mut scanned = Vec::new();
+    while let Some(joined) = tasks.join_next().await {
+        let (repo, result) = joined
+            .map_err(|e| BackupServiceError::CommandFailed(format!("Task join error: {}", e)))?;
+        match result {
+            Ok((_, snapshots)) => scanned.push(RepositorySnapshots {
+                repo_subpath: repo.repo_subpath,
+                category: repo.category,
+                snapshots,
+            }),
+            Err(e) if policy.is_fail_fast() => return Err(e),
+            Err(e) => {
+                warn!(repo_subpath = %repo.repo_subpath, error = %e, "Failed to get snapshots");
+                failures.push(DiscoveryFailure {
+                    scope: repo.repo_subpath,
+                    message: format!("failed to read snapshots ({})", e),
+                });
+            }
+        }
+    }
+
+    let rows = filter_snapshots(&scanned, &options.filter);
+    if options.json_output {
+        let output = json!({
+            "host": hostname,
+            "filter": options.filter,
+            "snapshots": rows,
+            "discovery_errors": failures,
+        });
+        DisplayFormatter::print_json(&output)?;
+    } else {
+        DisplayFormatter::display_snapshot_table(&rows)?;
+        DisplayFormatter::display_discovery_failures(&failures)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn snapshot(time: &str, path: &str, id: &str) -> SnapshotInfo {
+        SnapshotInfo {
+            time: time.parse().unwrap(),
+            path: PathBuf::from(path),
+            id: id.to_string(),
+            tags: vec!["system".to_string()],
+            summary: None,
+        }
+    }
+
+    #[test]
+    fn test_filter_snapshots_newest_first_across_repositories() {
+        let repos = vec![
+            RepositorySnapshots {
+                repo_subpath: "system/etc".to_string(),
+                category: "system".to_string(),
+                snapshots: vec![
+                    snapshot("2025-01-10T03:00:00Z", "/etc", "aaa111"),
+                    snapshot("2025-01-12T03:00:00Z", "/etc", "bbb222"),
+                ],
+            },
+            RepositorySnapshots {
+                repo_subpath: "docker_volume/app".to_string(),
+                category: "docker_volume".to_string(),
+                snapshots: vec![snapshot(
+                    "2025-01-11T03:00:00Z",
+                    "/mnt/docker-data/volumes/app",
+                    "ccc333",
+                )],
+            },
+        ];
+
+        let ids = |rows: Vec<SnapshotRow>| rows.into_iter().map(|r| r.id).collect::<Vec<_>>();
+        // Nothing is truncated, unlike the `list` timeline
+        assert_eq!(
+            ids(filter_snapshots(&repos, &SnapshotFilter::default())),
+            vec!["bbb222", "ccc333", "aaa111"]
+        );
+
+        let system_only = SnapshotFilter {
+            categories: vec!["system".to_string()],
+            ..SnapshotFilter::default()
+        };
+        let rows = filter_snapshots(&repos, &system_only);
+        assert_eq!(rows[0].repo_subpath, "system/etc");
+        assert_eq!(ids(rows), vec!["bbb222", "aaa111"]);
+    }
+}
@@ -0,0 +1,199 @@
+use crate::config::Config;
+use crate::errors::BackupServiceError;
+use crate::shared::commands::ResticCommandExecutor;
+use crate::shared::display::DisplayFormatter;
+use crate::shared::error_policy::{DISCOVERY_ERROR_POLICY_ENV_VAR, ErrorPolicy};
+use crate::shared::operations::{
+    DEFAULT_SCAN_JOBS, DiscoveryFailure, RepositoryData, RepositoryOperations, SCAN_JOBS_ENV_VAR,
+};
+use crate::utils::{resolve_jobs, validate_credentials};
+use serde::Serialize;
+use serde_json::json;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use tracing::{info, warn};
+
+/// `stats` options
+#[derive(Debug, Clone, Default)]
+pub struct StatsOptions {
+    pub json_output: bool,
+}
+
+/// Sizes of one repository
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RepoStats {
+    pub path: PathBuf,
+    pub repo_subpath: String,
+    pub category: String,
+    pub snapshot_count: usize,
+    /// Deduplicated data stored for all snapshots (`stats --mode raw-data`)
+    pub raw_data_bytes: u64,
+    /// Size of the latest snapshot once restored (`stats latest --mode restore-size`)
+    pub restore_size_bytes: u64,
+    /// Files in the latest snapshot
+    pub file_count: u64,
+}
+
+/// Sums over every repository of a host
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct StatsTotals {
+    pub repositories: usize,
+    pub snapshot_count: usize,
+    pub raw_data_bytes: u64,
+    pub restore_size_bytes: u64,
+    pub file_count: u64,
+}
+
+/// Sort repositories largest stored size first and add them up
+pub fn summarize(repos: &mut [RepoStats]) -> StatsTotals {
+    repos.sort_by(|a, b| {
+        b.raw_data_bytes
+            .cmp(&a.raw_data_bytes)
+            .then_with(|| a.repo_subpath.cmp(&b.repo_subpath))
+    });
+    repos
+        .iter()
+        .fold(StatsTotals::default(), |mut totals, repo| {
+            totals.repositories += 1;
+            totals.snapshot_count += repo.snapshot_count;
+            totals.raw_data_bytes += repo.raw_data_bytes;
+            totals.restore_size_bytes += repo.restore_size_bytes;
+            totals.file_count += repo.file_count;
+            totals
+        })
+}
+
+/// Read the raw-data and latest restore size of one repository
+async fn repository_stats(
+    config: Config,
+    hostname: String,
+    repo: RepositoryData,
+) -> Result<RepoStats, BackupServiceError> {
+    let repo_url = config.get_repo_url_for_host(&hostname, &repo.info.repo_subpath)?;
+    let restic_cmd = ResticCommandExecutor::new(config, repo_url)?;
+    let raw_data = restic_cmd.repository_stats("raw-data").await?;
+    let restore_size = restic_cmd.latest_stats("restore-size").await?;
+    Ok(RepoStats {
+        path: repo.info.native_path,
+        repo_subpath: repo.info.repo_subpath,
+        category: repo.info.category,
+        snapshot_count: repo.snapshot_count,
+        raw_data_bytes: raw_data["total_size"].as_u64().unwrap_or(0),
+        restore_size_bytes: restore_size["total_size"].as_u64().unwrap_or(0),
+        file_count: restore_size["total_file_count"].as_u64().unwrap_or(0),
+    })
+}
+
+/// Report the size of every repository of a host and the total
+pub async fn execute_stats_workflow(
+    config: Config,
+    host: Option<String>,
+    options: StatsOptions,
+) -> Result<(), BackupServiceError> {
+    let hostname = host.unwrap_or_else(|| config.hostname.clone());
+    config.set_aws_env()?;
+    validate_credentials(&config).await?;
+
+    let operations = RepositoryOperations::new(config.clone())?;
+    let scan = operations.scan_repositories(&hostname).await?;
+    let mut failures = scan.failures;
+    if !options.json_output {
+        info!(
+            hostname = %hostname,
+            repo_count = %scan.repos.len(),
+            "Reading repository sizes"
+        );
+    }
+
+    let permits = Arc::new(Semaphore::new(resolve_jobs(
+        None,
+        SCAN_JOBS_ENV_VAR,
+        DEFAULT_SCAN_JOBS,
+    )?));
+    let mut tasks = JoinSet::new();
+    for repo in scan.repos {
+        let repo_subpath = repo.info.repo_subpath.clone();
+        let stats = repository_stats(config.clone(), hostname.clone(), repo);
+        let permits = Arc::clone(&permits);
+        tasks.spawn(async move {
+            let _permit = permits.acquire_owned().await;
+            (repo_subpath, stats.await)
+        });
+    }
+
+    let policy = ErrorPolicy::from_env(DISCOVERY_ERROR_POLICY_ENV_VAR)?;
+    let mut repos = Vec::new();
+    while let Some(joined) = tasks.join_next().await {
+        let (repo_subpath, result) = joined
+            .map_err(|e| BackupServiceError::CommandFailed(format!("Task join error: {}", e)))?;
+        match result {
+            Ok(stats) => repos.push(stats),
+            Err(e) if policy.is_fail_fast() => return Err(e),
+            Err(e) => {
+                warn!(repo_subpath = %repo_subpath, error = %e, "Failed to read repository stats");
+                failures.push(DiscoveryFailure {
+                    scope: repo_subpath,
+                    message: format!("failed to read stats ({})", e),
+                });
+            }
+        }
+    }
+    let totals = summarize(&mut repos);
+
+    if options.json_output {
+        let output = json!({
+            "host": hostname,
+            "repositories": repos,
+            "total": totals,
+            "discovery_errors": failures,
+        });
+        DisplayFormatter::print_json(&output)?;
+    } else {
+        DisplayFormatter::display_stats_table(&repos, &totals)?;
+        DisplayFormatter::display_discovery_failures(&failures)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn repo(repo_subpath: &str, raw_data_bytes: u64, file_count: u64) -> RepoStats {
+        RepoStats {
+            path: PathBuf::from("/").join(repo_subpath),
+            repo_subpath: repo_subpath.to_string(),
+            category: "system".to_string(),
+            snapshot_count: 3,
+            raw_data_bytes,
+            restore_size_bytes: raw_data_bytes * 2,
+            file_count,
+        }
+    }
+
+    #[test]
+    fn test_summarize_sorts_by_size() {
+        let mut repos = vec![
+            repo("system/etc", 4_096, 900),
+            repo("system/var_lib", 1 << 30, 120_000),
+            repo("system/srv", 4_096, 10),
+        ];
+        let totals = summarize(&mut repos);
+
+        let order: Vec<_> = repos.iter().map(|r| r.repo_subpath.as_str()).collect();
+        assert_eq!(order, ["system/var_lib", "system/etc", "system/srv"]);
+        assert_eq!(
+            totals,
+            StatsTotals {
+                repositories: 3,
+                snapshot_count: 9,
+                raw_data_bytes: (1 << 30) + 8_192,
+                restore_size_bytes: ((1 << 30) + 8_192) * 2,
+                file_count: 120_910,
+            }
+        );
+        assert_eq!(summarize(&mut []), StatsTotals::default());
+    }
+}
@@ -0,0 +1,324 @@
+use crate::config::Config;
+use crate::errors::BackupServiceError;
+use crate::shared::budgets::BudgetAlert;
+use crate::shared::cron::CronSchedule;
+use crate::shared::daemon_workflow::BACKUP_SCHEDULE_ENV_VAR;
+use crate::shared::display::DisplayFormatter;
+use crate::shared::history_workflow::{HistoryRecord, history_file, read_history};
+use crate::shared::logs_workflow::parse_since;
+use crate::shared::paths::PathUtilities;
+use chrono::{DateTime, Duration, Local, Utc};
+use serde::Serialize;
+use serde_json::json;
+
+/// Env var with the age after which a path counts as stale (default 26h)
+pub const STATUS_MAX_AGE_ENV_VAR: &str = "STATUS_MAX_AGE";
+/// A daily backup plus two hours of slack
+const DEFAULT_MAX_AGE: &str = "26h";
+
+/// `status` options
+#[derive(Debug, Clone, Default)]
+pub struct StatusOptions {
+    /// Host whose runs are read (default: this host)
+    pub host: Option<String>,
+    /// `--max-age`, default STATUS_MAX_AGE, else 26h
+    pub max_age: Option<String>,
+    pub json_output: bool,
+}
+
+/// Backup state of one path, from the backup history
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PathStatus {
+    pub path: String,
+    /// RFC 3339 time of the newest recorded run of the path
+    pub last_run: Option<String>,
+    pub last_status: Option<String>,
+    /// RFC 3339 time of the newest run that saved a snapshot (completed or degraded)
+    pub last_success: Option<String>,
+    pub last_snapshot_id: Option<String>,
+    /// Seconds since the last success
+    pub age_secs: Option<i64>,
+    /// Seconds past the first scheduled run after the last success (BACKUP_SCHEDULE), or past
+    /// the maximum age without a schedule; 0 when on time
+    pub behind_secs: i64,
+    /// Warnings of the newest run
+    pub warnings: Vec<String>,
+    /// Never backed up successfully, or the last success is older than the maximum age
+    pub stale: bool,
+}
+
+fn resolve_max_age(flag: Option<&str>) -> Result<(Duration, String), BackupServiceError> {
+    let label = match flag {
+        Some(value) => value.trim().to_string(),
+        None => std::env::var(STATUS_MAX_AGE_ENV_VAR)
+            .ok()
+            .filter(|v| !v.trim().is_empty())
+            .unwrap_or_else(|| DEFAULT_MAX_AGE.to_string()),
+    };
+    let max_age = parse_since(&label).map_err(|_| {
+        BackupServiceError::ConfigurationError(format!(
+            "Invalid maximum backup age: {}.\n\nUse a number with a unit: 26h, 2d, 1w",
+            label
+        ))
+    })?;
+    Ok((max_age, label))
+}
+
+/// BACKUP_SCHEDULE when set; timers outside the daemon are not known here
+fn schedule_from_env() -> Result<Option<CronSchedule>, BackupServiceError> {
+    match std::env::var(BACKUP_SCHEDULE_ENV_VAR) {
+        Ok(expression) if !expression.trim().is_empty() => {
+            Ok(Some(CronSchedule::parse(&expression)?))
+        }
+        _ => Ok(None),
+    }
+}
+
+fn parse_time(time: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(time)
+        .ok()
+        .map(|t| t.with_timezone(&Utc))
+}
+
+/// Status of `paths` plus every other path of the host's newest run (docker volumes,
+/// `run` arguments), in that order
+pub fn path_statuses(
+    records: &[HistoryRecord],
+    host: &str,
+    paths: &[String],
+    now: DateTime<Utc>,
+    max_age: Duration,
+    schedule: Option<&CronSchedule>,
+) -> Vec<PathStatus> {
+    let records: Vec<&HistoryRecord> = records.iter().filter(|r| r.host == host).collect();
+    let mut selected: Vec<String> = paths.to_vec();
+    if let Some(newest) = records.last() {
+        for entry in &newest.paths {
+            if !selected.contains(&entry.path) {
+                selected.push(entry.path.clone());
+            }
+        }
+    }
+
+    selected
+        .into_iter()
+        .map(|path| {
+            let runs: Vec<(&str, &_)> = records
+                .iter()
+                .flat_map(|r| {
+                    r.paths
+                        .iter()
+                        .filter(|e| e.path == path)
+                        .map(|e| (r.finished_at.as_str(), e))
+                })
+                .collect();
+            let last = runs.last();
+            let success = runs
+                .iter()
+                .rev()
+                .find(|(_, e)| matches!(e.status.as_str(), "completed" | "degraded"));
+            let success_time = success.and_then(|(time, _)| parse_time(time));
+            let age = success_time.map(|time| now - time);
+            let behind = match (success_time, schedule, age) {
+                (Some(time), Some(schedule), _) => schedule
+                    .next_after(&time.with_timezone(&Local))
+                    .map(|due| now - due.with_timezone(&Utc))
+                    .unwrap_or_else(Duration::zero),
+                (Some(_), None, Some(age)) => age - max_age,
+                _ => Duration::zero(),
+            };
+            PathStatus {
+                last_run: last.map(|(time, _)| time.to_string()),
+                last_status: last.map(|(_, e)| e.status.clone()),
+                last_success: success.map(|(time, _)| time.to_string()),
+                last_snapshot_id: success.and_then(|(_, e)| e.snapshot_id.clone()),
+                age_secs: age.map(|age| age.num_seconds()),
+                behind_secs: behind.num_seconds().max(0),
+                warnings: last.map(|(_, e)| e.warnings.clone()).unwrap_or_default(),
+                stale: age.is_none_or(|age| age > max_age),
+                path,
+            }
+        })
+        .collect()
+}
+
+/// Budgets exceeded after the host's newest recorded run
+pub fn latest_budget_alerts(records: &[HistoryRecord], host: &str) -> Vec<BudgetAlert> {
+    records
+        .iter()
+        .rfind(|r| r.host == host)
+        .map(|r| r.budget_alerts.clone())
+        .unwrap_or_default()
+}
+
+/// Report when each path was last backed up; any stale path makes it fail, and so does a
+/// repository over its budget after the newest run
+pub async fn execute_status_workflow(
+    config: Config,
+    options: StatusOptions,
+) -> Result<(), BackupServiceError> {
+    let hostname = options.host.unwrap_or_else(|| config.hostname.clone());
+    let (max_age, max_age_label) = resolve_max_age(options.max_age.as_deref())?;
+    let schedule = schedule_from_env()?;
+    let paths: Vec<String> = PathUtilities::configured_paths(&config)
+        .iter()
+        .map(|p| p.display().to_string())
+        .collect();
+    let records = read_history(&history_file())?;
+    let budget_alerts = latest_budget_alerts(&records, &hostname);
+    let statuses = path_statuses(
+        &records,
+        &hostname,
+        &paths,
+        Utc::now(),
+        max_age,
+        schedule.as_ref(),
+    );
+    let stale: Vec<&str> = statuses
+        .iter()
+        .filter(|s| s.stale)
+        .map(|s| s.path.as_str())
+        .collect();
+
+    if options.json_output {
+        let output = json!({
+            "host": hostname,
+            "max_age": max_age_label,
+            "max_age_secs": max_age.num_seconds(),
+            "paths": statuses,
+            "stale": stale.len(),
+            "budget_alerts": budget_alerts,
+        });
+        DisplayFormatter::print_json(&output)?;
+    } else {
+        DisplayFormatter::display_status(&hostname, &max_age_label, &statuses, &budget_alerts)?;
+    }
+
+    if !stale.is_empty() {
+        Err(BackupServiceError::StaleBackups(format!(
+            "{} of {} paths not backed up within {}: {}",
+            stale.len(),
+            statuses.len(),
+            max_age_label,
+            stale.join(", ")
+        )))
+    } else if !budget_alerts.is_empty() {
+        Err(BackupServiceError::BudgetExceeded(
+            budget_alerts
+                .iter()
+                .map(|a| format!("{}: {}", a.path.display(), a.describe()))
+                .collect::<Vec<_>>()
+                .join("; "),
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::budgets::BudgetKind;
+    use crate::shared::history_workflow::HistoryPath;
+
+    fn record(time: &str, entries: &[(&str, &str)]) -> HistoryRecord {
+        HistoryRecord {
+            finished_at: time.to_string(),
+            host: "web1".to_string(),
+            interrupted: false,
+            paths: entries
+                .iter()
+                .map(|(path, status)| HistoryPath {
+                    path: path.to_string(),
+                    status: status.to_string(),
+                    snapshot_id: (*status == "completed").then(|| "4f1c2a9e".to_string()),
+                    bytes_added: None,
+                    duration_secs: None,
+                    warnings: if *status == "degraded" {
+                        vec!["empty snapshot".to_string()]
+                    } else {
+                        Vec::new()
+                    },
+                    error: None,
+                })
+                .collect(),
+            budget_alerts: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_path_statuses() {
+        let records = [
+            record(
+                "2025-01-14T03:00:00Z",
+                &[("/home/tim", "completed"), ("/etc", "completed")],
+            ),
+            record(
+                "2025-01-15T03:00:00Z",
+                &[
+                    ("/home/tim", "failed"),
+                    ("/etc", "degraded"),
+                    ("/var/lib/docker/volumes/db", "completed"),
+                ],
+            ),
+        ];
+        let now: DateTime<Utc> = "2025-01-15T12:00:00Z".parse().unwrap();
+        let paths = ["/home/tim".to_string(), "/srv".to_string()];
+        let statuses = path_statuses(&records, "web1", &paths, now, Duration::hours(26), None);
+        let names: Vec<&str> = statuses.iter().map(|s| s.path.as_str()).collect();
+        assert_eq!(
+            names,
+            ["/home/tim", "/srv", "/etc", "/var/lib/docker/volumes/db"]
+        );
+
+        // Last success 33h ago: stale and 7h past the maximum age
+        let home = &statuses[0];
+        assert_eq!(home.last_status.as_deref(), Some("failed"));
+        assert_eq!(home.last_success.as_deref(), Some("2025-01-14T03:00:00Z"));
+        assert_eq!(home.age_secs, Some(33 * 3600));
+        assert_eq!(home.behind_secs, 7 * 3600);
+        assert!(home.stale);
+        // Never backed up
+        assert!(statuses[1].stale && statuses[1].last_run.is_none());
+        // A degraded run still saved a snapshot, its warnings are reported
+        assert!(!statuses[2].stale);
+        assert_eq!(statuses[2].warnings, ["empty snapshot"]);
+
+        // With a daily 03:00 schedule, /home/tim missed the run due at 2025-01-15 03:00 local
+        let schedule = CronSchedule::parse("0 3 * * *").unwrap();
+        let statuses = path_statuses(
+            &records,
+            "web1",
+            &paths[..1],
+            now,
+            Duration::hours(48),
+            Some(&schedule),
+        );
+        assert!(!statuses[0].stale);
+        assert!(statuses[0].behind_secs > 0);
+        assert!(path_statuses(&records, "db1", &[], now, Duration::hours(26), None).is_empty());
+    }
+
+    #[test]
+    fn test_latest_budget_alerts() {
+        let alert = BudgetAlert {
+            path: "/home/tim".into(),
+            repo_subpath: "user_home/tim".to_string(),
+            kind: BudgetKind::SnapshotCount,
+            actual: 120,
+            limit: 100,
+        };
+        let mut over = record("2025-01-14T03:00:00Z", &[("/home/tim", "completed")]);
+        over.budget_alerts = vec![alert.clone()];
+        let mut other_host = record("2025-01-15T03:00:00Z", &[]);
+        other_host.host = "db1".to_string();
+
+        assert_eq!(
+            latest_budget_alerts(&[over.clone(), other_host.clone()], "web1"),
+            [alert]
+        );
+        // Only the newest run counts: a later run back within budget clears the alert
+        let within = record("2025-01-15T03:00:00Z", &[("/home/tim", "completed")]);
+        assert!(latest_budget_alerts(&[over, other_host, within], "web1").is_empty());
+    }
+}
@@ -0,0 +1,181 @@
+use crate::errors::BackupServiceError;
+use crate::shared::logs_workflow::parse_since;
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+
+/// Display format for snapshot times: local wall clock plus its UTC offset
+const LOCAL_FORMAT: &str = "%Y-%m-%d %H:%M %:z";
+
+/// Snapshot time in the local time zone, e.g. `2025-01-15 11:30 +01:00`
+pub fn format_local(time: DateTime<Utc>) -> String {
+    time.with_timezone(&Local).format(LOCAL_FORMAT).to_string()
+}
+
+/// Parse a user-supplied timestamp relative to the local clock
+pub fn parse_timestamp(input: &str) -> Result<DateTime<Utc>, BackupServiceError> {
+    parse_timestamp_at(input, Local::now())
+}
+
+/// Parse a timestamp, reading zone-less inputs in `now`'s time zone
+///
+/// Accepts RFC 3339 (`2025-01-15T10:30:00Z`, `...+01:00`), local date/times
+/// (`2025-01-15 14:00`, `2025-01-15T14:00:00`, `2025-01-15`), `HH:MM` (today),
+/// `now`, `today`/`yesterday` with an optional `HH:MM`, and `<N><unit> ago` (`2h ago`).
+pub fn parse_timestamp_at<Tz: TimeZone>(
+    input: &str,
+    now: DateTime<Tz>,
+) -> Result<DateTime<Utc>, BackupServiceError> {
+    let input = input.trim();
+    if let Ok(time) = DateTime::parse_from_rfc3339(input) {
+        return Ok(time.with_timezone(&Utc));
+    }
+
+    let lower = input.to_lowercase();
+    let zone = now.timezone();
+    let today = now.date_naive();
+
+    if lower == "now" {
+        return Ok(now.with_timezone(&Utc));
+    }
+    if let Some(amount) = lower.strip_suffix(" ago") {
+        let span = parse_since(&amount.replace(' ', "")).map_err(|_| invalid_timestamp(input))?;
+        return Ok((now - span).with_timezone(&Utc));
+    }
+
+    for (word, offset) in [("today", 0), ("yesterday", 1)] {
+        if let Some(rest) = lower.strip_prefix(word) {
+            let day = today - Duration::days(offset);
+            let time = match rest.trim() {
+                "" => NaiveTime::MIN,
+                clock => parse_clock(clock).ok_or_else(|| invalid_timestamp(input))?,
+            };
+            return local_to_utc(&zone, day.and_time(time), input);
+        }
+    }
+
+    if let Some(time) = parse_clock(&lower) {
+        return local_to_utc(&zone, today.and_time(time), input);
+    }
+    for format in [
+        "%Y-%m-%d %H:%M:%S",
+        "%Y-%m-%d %H:%M",
+        "%Y-%m-%dT%H:%M:%S",
+        "%Y-%m-%dT%H:%M",
+    ] {
+        if let Ok(naive) = NaiveDateTime::parse_from_str(input, format) {
+            return local_to_utc(&zone, naive, input);
+        }
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(input, "%Y-%m-%d") {
+        return local_to_utc(&zone, date.and_time(NaiveTime::MIN), input);
+    }
+
+    Err(invalid_timestamp(input))
+}
+
+fn parse_clock(value: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(value, "%H:%M")
+        .or_else(|_| NaiveTime::parse_from_str(value, "%H:%M:%S"))
+        .ok()
+}
+
+/// Resolve a wall-clock time; during a DST fold the earlier instant is used
+fn local_to_utc<Tz: TimeZone>(
+    zone: &Tz,
+    naive: NaiveDateTime,
+    input: &str,
+) -> Result<DateTime<Utc>, BackupServiceError> {
+    zone.from_local_datetime(&naive)
+        .earliest()
+        .map(|time| time.with_timezone(&Utc))
+        .ok_or_else(|| {
+            BackupServiceError::ConfigurationError(format!(
+                "Timestamp {} does not exist in the local time zone (skipped by a DST change)",
+                input
+            ))
+        })
+}
+
+fn invalid_timestamp(input: &str) -> BackupServiceError {
+    BackupServiceError::ConfigurationError(format!(
+        "Invalid timestamp: {}.\n\nUse RFC 3339 (2025-01-15T10:30:00Z), a local time \
+        (2025-01-15 14:00, 14:00), \"yesterday 14:00\" or \"2h ago\"",
+        input
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::FixedOffset;
+
+    fn berlin_now() -> DateTime<FixedOffset> {
+        DateTime::parse_from_rfc3339("2025-01-15T09:00:00+01:00").unwrap()
+    }
+
+    fn utc(value: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(value)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_local_inputs_use_the_local_offset() -> Result<(), BackupServiceError> {
+        let now = berlin_now();
+        assert_eq!(
+            parse_timestamp_at("2025-01-14 14:00", now)?,
+            utc("2025-01-14T13:00:00Z")
+        );
+        assert_eq!(
+            parse_timestamp_at("2025-01-14T14:00:30", now)?,
+            utc("2025-01-14T13:00:30Z")
+        );
+        assert_eq!(
+            parse_timestamp_at("08:15", now)?,
+            utc("2025-01-15T07:15:00Z")
+        );
+        assert_eq!(
+            parse_timestamp_at("2025-01-10", now)?,
+            utc("2025-01-09T23:00:00Z")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_fuzzy_inputs() -> Result<(), BackupServiceError> {
+        let now = berlin_now();
+        assert_eq!(
+            parse_timestamp_at("yesterday 14:00", now)?,
+            utc("2025-01-14T13:00:00Z")
+        );
+        assert_eq!(
+            parse_timestamp_at("Today", now)?,
+            utc("2025-01-14T23:00:00Z")
+        );
+        assert_eq!(
+            parse_timestamp_at("2h ago", now)?,
+            utc("2025-01-15T06:00:00Z")
+        );
+        assert_eq!(
+            parse_timestamp_at("3 d ago", now)?,
+            utc("2025-01-12T08:00:00Z")
+        );
+        assert_eq!(parse_timestamp_at("now", now)?, utc("2025-01-15T08:00:00Z"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_explicit_offsets_win_and_garbage_fails() -> Result<(), BackupServiceError> {
+        let now = berlin_now();
+        assert_eq!(
+            parse_timestamp_at("2025-01-15T12:00:00Z", now)?,
+            utc("2025-01-15T12:00:00Z")
+        );
+        assert_eq!(
+            parse_timestamp_at("2025-01-15T12:00:00-05:00", now)?,
+            utc("2025-01-15T17:00:00Z")
+        );
+        assert!(parse_timestamp_at("last tuesday", now).is_err());
+        assert!(parse_timestamp_at("yesterday noon", now).is_err());
+        Ok(())
+    }
+}
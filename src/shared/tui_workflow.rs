@@ -0,0 +1,791 @@
+use crate::config::Config;
+use crate::errors::BackupServiceError;
+use crate::i18n::{t, t_args};
+use crate::shared::logs_workflow::mute_console;
+use crate::shared::operations::{RepositoryData, RepositoryOperations, ScanResult};
+use crate::shared::restore_workflow::{RestoreOptions, RestoreWorkflow};
+use crate::shared::timestamps::format_local;
+use crate::utils::validate_credentials;
+use chrono::{DateTime, NaiveDate, Utc};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Clear, List, ListItem, ListState, Paragraph, Sparkline, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+use std::io::IsTerminal;
+use std::path::PathBuf;
+
+/// Days shown in the snapshot timeline above the snapshot list
+const TIMELINE_DAYS: usize = 30;
+
+/// `--action` values offered by the restore wizard, in order
+pub const WIZARD_ACTIONS: [&str; 3] = ["copy", "move", "leave"];
+/// `--overwrite` values offered by the restore wizard, in order
+pub const WIZARD_OVERWRITE: [&str; 3] = ["replace", "skip", "keep-both"];
+
+/// `tui` options
+#[derive(Debug, Clone, Default)]
+pub struct TuiOptions {
+    /// Rescan the first host instead of using the scan cache (`--refresh`)
+    pub refresh: bool,
+}
+
+/// Panes of the dashboard, left to right
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pane {
+    Hosts,
+    Repositories,
+    Snapshots,
+}
+
+/// Steps of the restore wizard
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WizardStep {
+    Action,
+    Overwrite,
+    Confirm,
+}
+
+/// Restore wizard state: the step and the option highlighted on each list step
+#[derive(Debug, Clone, PartialEq)]
+pub struct Wizard {
+    pub step: WizardStep,
+    pub action: usize,
+    pub overwrite: usize,
+}
+
+/// A restore chosen in the wizard, run once the dashboard is closed
+#[derive(Debug, Clone, PartialEq)]
+pub struct RestoreRequest {
+    pub host: String,
+    pub path: PathBuf,
+    pub snapshot_id: String,
+    pub time: DateTime<Utc>,
+    pub action: String,
+    pub overwrite: String,
+}
+
+/// What the event loop does after a key press
+#[derive(Debug, Clone, PartialEq)]
+pub enum TuiCommand {
+    None,
+    Quit,
+    /// Scan the highlighted host, bypassing the scan cache when `refresh`
+    LoadHost {
+        refresh: bool,
+    },
+    Restore(RestoreRequest),
+}
+
+/// Row of the repository tree: a category heading or a repository (index into `repos`)
+#[derive(Debug, Clone, PartialEq)]
+pub enum TreeRow {
+    Category { name: String, count: usize },
+    Repository(usize),
+}
+
+/// Dashboard state, kept free of terminal handling so navigation can be tested
+#[derive(Debug, Clone)]
+pub struct Dashboard {
+    pub hosts: Vec<String>,
+    pub host: usize,
+    /// Host whose repositories are shown
+    pub loaded_host: Option<String>,
+    /// Sorted by category and path, snapshots newest first
+    pub repos: Vec<RepositoryData>,
+    pub repo: usize,
+    pub snapshot: usize,
+    pub focus: Pane,
+    pub wizard: Option<Wizard>,
+    pub status: String,
+}
+
+impl Dashboard {
+    /// Start on `current` when the store has it, else on the first host
+    pub fn new(hosts: Vec<String>, current: &str) -> Self {
+        let host = hosts.iter().position(|h| h == current).unwrap_or(0);
+        Self {
+            hosts,
+            host,
+            loaded_host: None,
+            repos: Vec::new(),
+            repo: 0,
+            snapshot: 0,
+            focus: Pane::Hosts,
+            wizard: None,
+            status: String::new(),
+        }
+    }
+
+    pub fn highlighted_host(&self) -> Option<&str> {
+        self.hosts.get(self.host).map(String::as_str)
+    }
+
+    /// Show the scan of `host`, grouped by category with the newest snapshots first
+    pub fn set_repositories(&mut self, host: &str, scan: ScanResult) {
+        let mut repos = scan.repos;
+        repos.sort_by(|a, b| {
+            a.info
+                .category
+                .cmp(&b.info.category)
+                .then_with(|| a.info.native_path.cmp(&b.info.native_path))
+        });
+        for repo in &mut repos {
+            repo.snapshots.sort_by_key(|s| std::cmp::Reverse(s.time));
+        }
+        self.status = if scan.failures.is_empty() {
+            t_args(
+                "tui-loaded",
+                &[
+                    ("count", repos.len().to_string()),
+                    ("host", host.to_string()),
+                ],
+            )
+        } else {
+            t_args(
+                "tui-discovery-errors",
+                &[
+                    ("count", repos.len().to_string()),
+                    ("host", host.to_string()),
+                    ("failures", scan.failures.len().to_string()),
+                ],
+            )
+        };
+        self.repos = repos;
+        self.repo = 0;
+        self.snapshot = 0;
+        self.loaded_host = Some(host.to_string());
+    }
+
+    /// Category headings followed by their repositories
+    pub fn tree_rows(&self) -> Vec<TreeRow> {
+        let mut rows = Vec::new();
+        for (index, repo) in self.repos.iter().enumerate() {
+            let category = &repo.info.category;
+            if index == 0 || self.repos[index - 1].info.category != *category {
+                rows.push(TreeRow::Category {
+                    name: category.clone(),
+                    count: self
+                        .repos
+                        .iter()
+                        .filter(|r| r.info.category == *category)
+                        .count(),
+                });
+            }
+            rows.push(TreeRow::Repository(index));
+        }
+        rows
+    }
+
+    pub fn selected_repository(&self) -> Option<&RepositoryData> {
+        self.repos.get(self.repo)
+    }
+
+    /// The restore the wizard would start for the highlighted snapshot
+    fn restore_request(&self, wizard: &Wizard) -> Option<RestoreRequest> {
+        let repo = self.selected_repository()?;
+        let snapshot = repo.snapshots.get(self.snapshot)?;
+        Some(RestoreRequest {
+            host: self.loaded_host.clone()?,
+            path: repo.info.native_path.clone(),
+            snapshot_id: snapshot.id.clone(),
+            time: snapshot.time,
+            action: WIZARD_ACTIONS[wizard.action].to_string(),
+            overwrite: WIZARD_OVERWRITE[wizard.overwrite].to_string(),
+        })
+    }
+
+    pub fn handle_key(&mut self, key: KeyCode) -> TuiCommand {
+        if self.wizard.is_some() {
+            return self.handle_wizard_key(key);
+        }
+        match key {
+            KeyCode::Char('q') | KeyCode::Esc => return TuiCommand::Quit,
+            KeyCode::Tab | KeyCode::Right | KeyCode::Char('l') => {
+                self.focus = match self.focus {
+                    Pane::Hosts => Pane::Repositories,
+                    _ => Pane::Snapshots,
+                }
+            }
+            KeyCode::BackTab | KeyCode::Left | KeyCode::Char('h') => {
+                self.focus = match self.focus {
+                    Pane::Snapshots => Pane::Repositories,
+                    _ => Pane::Hosts,
+                }
+            }
+            KeyCode::Up | KeyCode::Char('k') => self.move_selection(-1),
+            KeyCode::Down | KeyCode::Char('j') => self.move_selection(1),
+            // Rescans the shown host, wherever the host list is highlighted
+            KeyCode::Char('R') if self.loaded_host.is_some() => {
+                if let Some(index) = self
+                    .hosts
+                    .iter()
+                    .position(|h| Some(h.as_str()) == self.loaded_host.as_deref())
+                {
+                    self.host = index;
+                }
+                return TuiCommand::LoadHost { refresh: true };
+            }
+            KeyCode::Enter => match self.focus {
+                Pane::Hosts => {
+                    self.focus = Pane::Repositories;
+                    if self.highlighted_host().is_some()
+                        && self.highlighted_host() != self.loaded_host.as_deref()
+                    {
+                        return TuiCommand::LoadHost { refresh: false };
+                    }
+                }
+                Pane::Repositories => self.focus = Pane::Snapshots,
+                Pane::Snapshots => self.open_wizard(),
+            },
+            KeyCode::Char('r') => self.open_wizard(),
+            _ => {}
+        }
+        TuiCommand::None
+    }
+
+    fn move_selection(&mut self, delta: isize) {
+        let (index, len) = match self.focus {
+            Pane::Hosts => (&mut self.host, self.hosts.len()),
+            Pane::Repositories => (&mut self.repo, self.repos.len()),
+            Pane::Snapshots => (
+                &mut self.snapshot,
+                self.repos.get(self.repo).map_or(0, |r| r.snapshots.len()),
+            ),
+        };
+        if len == 0 {
+            return;
+        }
+        *index = index.saturating_add_signed(delta).min(len - 1);
+        if self.focus == Pane::Repositories {
+            self.snapshot = 0;
+        }
+    }
+
+    /// Start the wizard on the highlighted snapshot; without one there is nothing to restore
+    fn open_wizard(&mut self) {
+        let has_snapshot = self
+            .selected_repository()
+            .is_some_and(|r| self.snapshot < r.snapshots.len());
+        if has_snapshot {
+            self.focus = Pane::Snapshots;
+            self.wizard = Some(Wizard {
+                step: WizardStep::Action,
+                action: 0,
+                overwrite: 0,
+            });
+        }
+    }
+
+    fn handle_wizard_key(&mut self, key: KeyCode) -> TuiCommand {
+        let Some(mut wizard) = self.wizard.take() else {
+            return TuiCommand::None;
+        };
+        match (wizard.step, key) {
+            (WizardStep::Action, KeyCode::Esc) => return TuiCommand::None,
+            (WizardStep::Overwrite, KeyCode::Esc) => wizard.step = WizardStep::Action,
+            (WizardStep::Confirm, KeyCode::Esc) => {
+                wizard.step = match WIZARD_ACTIONS[wizard.action] {
+                    "leave" => WizardStep::Action,
+                    _ => WizardStep::Overwrite,
+                }
+            }
+            (WizardStep::Action, KeyCode::Up | KeyCode::Char('k')) => {
+                wizard.action = wizard.action.saturating_sub(1)
+            }
+            (WizardStep::Action, KeyCode::Down | KeyCode::Char('j')) => {
+                wizard.action = (wizard.action + 1).min(WIZARD_ACTIONS.len() - 1)
+            }
+            (WizardStep::Overwrite, KeyCode::Up | KeyCode::Char('k')) => {
+                wizard.overwrite = wizard.overwrite.saturating_sub(1)
+            }
+            (WizardStep::Overwrite, KeyCode::Down | KeyCode::Char('j')) => {
+                wizard.overwrite = (wizard.overwrite + 1).min(WIZARD_OVERWRITE.len() - 1)
+            }
+            // Files left in the staging directory overwrite nothing, so leave skips the policy
+            (WizardStep::Action, KeyCode::Enter) => {
+                wizard.step = match WIZARD_ACTIONS[wizard.action] {
+                    "leave" => WizardStep::Confirm,
+                    _ => WizardStep::Overwrite,
+                }
+            }
+            (WizardStep::Overwrite, KeyCode::Enter) => wizard.step = WizardStep::Confirm,
+            (WizardStep::Confirm, KeyCode::Enter) => {
+                if let Some(request) = self.restore_request(&wizard) {
+                    return TuiCommand::Restore(request);
+                }
+                return TuiCommand::None;
+            }
+            _ => {}
+        }
+        self.wizard = Some(wizard);
+        TuiCommand::None
+    }
+}
+
+/// Snapshots per day over the `days` days up to and including `today`, oldest first
+pub fn daily_counts(snapshot_times: &[DateTime<Utc>], today: NaiveDate, days: usize) -> Vec<u64> {
+    let mut counts = vec![0; days];
+    for time in snapshot_times {
+        let age = (today - time.date_naive()).num_days();
+        if (0..days as i64).contains(&age) {
+            counts[days - 1 - age as usize] += 1;
+        }
+    }
+    counts
+}
+
+fn highlight() -> Style {
+    Style::default().add_modifier(Modifier::REVERSED)
+}
+
+fn pane_block(title: String, focused: bool) -> Block<'static> {
+    let block = Block::bordered().title(title);
+    if focused {
+        block.border_style(Style::default().add_modifier(Modifier::BOLD))
+    } else {
+        block
+    }
+}
+
+fn draw(frame: &mut Frame, dashboard: &Dashboard) {
+    let [main, status] =
+        Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(frame.area());
+    let [hosts_area, repos_area, snapshots_area] = Layout::horizontal([
+        Constraint::Percentage(20),
+        Constraint::Percentage(40),
+        Constraint::Percentage(40),
+    ])
+    .areas(main);
+
+    let hosts: Vec<ListItem> = dashboard
+        .hosts
+        .iter()
+        .map(|host| {
+            let marker = if Some(host.as_str()) == dashboard.loaded_host.as_deref() {
+                "● "
+            } else {
+                "  "
+            };
+            ListItem::new(format!("{}{}", marker, host))
+        })
+        .collect();
+    let mut host_state = ListState::default().with_selected(Some(dashboard.host));
+    frame.render_stateful_widget(
+        List::new(hosts)
+            .block(pane_block(t("tui-hosts"), dashboard.focus == Pane::Hosts))
+            .highlight_style(highlight()),
+        hosts_area,
+        &mut host_state,
+    );
+
+    let rows = dashboard.tree_rows();
+    let selected_row = rows
+        .iter()
+        .position(|row| *row == TreeRow::Repository(dashboard.repo));
+    let tree: Vec<ListItem> = rows
+        .iter()
+        .map(|row| match row {
+            TreeRow::Category { name, count } => ListItem::new(format!("{} ({})", name, count))
+                .style(Style::default().add_modifier(Modifier::BOLD)),
+            TreeRow::Repository(index) => {
+                let repo = &dashboard.repos[*index];
+                ListItem::new(format!(
+                    "  {} ({})",
+                    repo.info.native_path.display(),
+                    repo.snapshot_count
+                ))
+            }
+        })
+        .collect();
+    let repos_title = match &dashboard.loaded_host {
+        Some(host) => t_args("tui-repositories-of", &[("host", host.clone())]),
+        None => t("tui-repositories"),
+    };
+    let mut tree_state = ListState::default().with_selected(selected_row);
+    if tree.is_empty() && dashboard.loaded_host.is_some() {
+        frame.render_widget(
+            Paragraph::new(t("tui-no-repositories")).block(pane_block(
+                repos_title,
+                dashboard.focus == Pane::Repositories,
+            )),
+            repos_area,
+        );
+    } else {
+        frame.render_stateful_widget(
+            List::new(tree)
+                .block(pane_block(
+                    repos_title,
+                    dashboard.focus == Pane::Repositories,
+                ))
+                .highlight_style(highlight()),
+            repos_area,
+            &mut tree_state,
+        );
+    }
+
+    draw_snapshots(frame, dashboard, snapshots_area);
+    frame.render_widget(
+        Paragraph::new(Line::from(format!(
+            "{}  │  {}",
+            dashboard.status,
+            t("tui-help")
+        ))),
+        status,
+    );
+
+    if let Some(wizard) = &dashboard.wizard {
+        draw_wizard(frame, dashboard, wizard);
+    }
+}
+
+fn draw_snapshots(frame: &mut Frame, dashboard: &Dashboard, area: Rect) {
+    let block = pane_block(t("tui-snapshots"), dashboard.focus == Pane::Snapshots);
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+    let Some(repo) = dashboard.selected_repository() else {
+        return;
+    };
+    if repo.snapshots.is_empty() {
+        frame.render_widget(Paragraph::new(t("tui-no-snapshots")), inner);
+        return;
+    }
+    let [timeline_area, list_area] =
+        Layout::vertical([Constraint::Length(4), Constraint::Min(0)]).areas(inner);
+    let times: Vec<DateTime<Utc>> = repo.snapshots.iter().map(|s| s.time).collect();
+    frame.render_widget(
+        Sparkline::default()
+            .block(Block::default().title(t_args(
+                "tui-timeline",
+                &[("days", TIMELINE_DAYS.to_string())],
+            )))
+            .data(daily_counts(&times, Utc::now().date_naive(), TIMELINE_DAYS)),
+        timeline_area,
+    );
+
+    let items: Vec<ListItem> = repo
+        .snapshots
+        .iter()
+        .map(|s| {
+            let tags = if s.tags.is_empty() {
+                String::new()
+            } else {
+                format!("  [{}]", s.tags.join(", "))
+            };
+            ListItem::new(format!(
+                "{}  {}{}",
+                format_local(s.time),
+                &s.id[..s.id.len().min(8)],
+                tags
+            ))
+        })
+        .collect();
+    let mut state = ListState::default().with_selected(Some(dashboard.snapshot));
+    frame.render_stateful_widget(
+        List::new(items).highlight_style(highlight()),
+        list_area,
+        &mut state,
+    );
+}
+
+/// A `width` x `height` rectangle centered in `area`
+fn centered(area: Rect, width: u16, height: u16) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    )
+}
+
+fn draw_wizard(frame: &mut Frame, dashboard: &Dashboard, wizard: &Wizard) {
+    let area = centered(frame.area(), 72, 12);
+    frame.render_widget(Clear, area);
+    let block = Block::bordered().title(t("tui-wizard-title"));
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+    let [body, help] = Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(inner);
+
+    let (question, options, selected) = match wizard.step {
+        WizardStep::Action => (
+            t("prompt-post-restore"),
+            WIZARD_ACTIONS
+                .iter()
+                .map(|action| t(&format!("action-{}", action)))
+                .collect(),
+            wizard.action,
+        ),
+        WizardStep::Overwrite => (
+            t("tui-wizard-overwrite"),
+            WIZARD_OVERWRITE
+                .iter()
+                .map(|policy| t(&format!("overwrite-{}", policy)))
+                .collect::<Vec<_>>(),
+            wizard.overwrite,
+        ),
+        WizardStep::Confirm => {
+            let Some(request) = dashboard.restore_request(wizard) else {
+                return;
+            };
+            let overwrite = match request.action.as_str() {
+                "leave" => String::new(),
+                _ => format!("\n{}", t(&format!("overwrite-{}", request.overwrite))),
+            };
+            let text = format!(
+                "{}\n\n{}{}\n\n{}",
+                t_args(
+                    "tui-wizard-confirm",
+                    &[
+                        ("path", request.path.display().to_string()),
+                        ("host", request.host.clone()),
+                        ("time", format_local(request.time)),
+                        (
+                            "snapshot",
+                            request.snapshot_id[..request.snapshot_id.len().min(8)].to_string()
+                        ),
+                    ],
+                ),
+                t(&format!("action-{}", request.action)),
+                overwrite,
+                t("tui-wizard-staging-note"),
+            );
+            frame.render_widget(Paragraph::new(text).wrap(Wrap { trim: false }), body);
+            frame.render_widget(Paragraph::new(t("tui-wizard-run")), help);
+            return;
+        }
+    };
+    let [question_area, options_area] =
+        Layout::vertical([Constraint::Length(2), Constraint::Min(0)]).areas(body);
+    frame.render_widget(Paragraph::new(question), question_area);
+    let items: Vec<ListItem> = options.into_iter().map(ListItem::new).collect();
+    let mut state = ListState::default().with_selected(Some(selected));
+    frame.render_stateful_widget(
+        List::new(items).highlight_style(highlight()),
+        options_area,
+        &mut state,
+    );
+    frame.render_widget(Paragraph::new(t("tui-wizard-help")), help);
+}
+
+/// Draw and handle keys until the user quits or confirms a restore
+async fn run_dashboard(
+    terminal: &mut DefaultTerminal,
+    dashboard: &mut Dashboard,
+    operations: &RepositoryOperations,
+    refresh: bool,
+) -> Result<Option<RestoreRequest>, BackupServiceError> {
+    let mut command = if dashboard.hosts.is_empty() {
+        dashboard.status = t("tui-no-hosts");
+        TuiCommand::None
+    } else {
+        TuiCommand::LoadHost { refresh }
+    };
+    loop {
+        match command {
+            TuiCommand::Quit => return Ok(None),
+            TuiCommand::Restore(request) => return Ok(Some(request)),
+            TuiCommand::LoadHost { refresh } => {
+                let host = dashboard.highlighted_host().unwrap_or_default().to_string();
+                dashboard.status = t_args("tui-loading", &[("host", host.clone())]);
+                terminal.draw(|frame| draw(frame, dashboard))?;
+                match operations.scan_repositories_cached(&host, refresh).await {
+                    Ok(scan) => dashboard.set_repositories(&host, scan),
+                    Err(e) => {
+                        dashboard.status = t_args(
+                            "tui-load-failed",
+                            &[("host", host), ("error", e.to_string())],
+                        )
+                    }
+                }
+            }
+            TuiCommand::None => {}
+        }
+        terminal.draw(|frame| draw(frame, dashboard))?;
+        command = match tokio::task::block_in_place(event::read)? {
+            Event::Key(key) if key.kind == KeyEventKind::Press => dashboard.handle_key(key.code),
+            _ => TuiCommand::None,
+        };
+    }
+}
+
+/// Restore options for a wizard request; without `assume_yes`, clearing a non-empty
+/// staging directory is still confirmed in the terminal once the screen is closed
+fn restore_options(request: &RestoreRequest) -> RestoreOptions {
+    RestoreOptions {
+        assume_yes: false,
+        action: Some(request.action.clone()),
+        overwrite: Some(request.overwrite.clone()),
+        ..RestoreOptions::default()
+    }
+}
+
+/// Full-screen dashboard over hosts, repositories and snapshots; a restore confirmed in
+/// its wizard runs after the screen is closed, with the usual restore output
+pub async fn execute_tui_workflow(
+    config: Config,
+    host: Option<String>,
+    options: TuiOptions,
+) -> Result<(), BackupServiceError> {
+    if !std::io::stdout().is_terminal() || !std::io::stdin().is_terminal() {
+        return Err(BackupServiceError::ConfigurationError(
+            "tui needs an interactive terminal.\n\n\
+            Use list, snapshots or restore --yes from scripts"
+                .to_string(),
+        ));
+    }
+    let hostname = host.unwrap_or_else(|| config.hostname.clone());
+    config.set_aws_env()?;
+    validate_credentials(&config).await?;
+
+    let operations = RepositoryOperations::new(config.clone())?;
+    let mut dashboard = Dashboard::new(operations.get_available_hosts().await?, &hostname);
+    mute_console(true);
+    let mut terminal = ratatui::try_init()?;
+    let result = run_dashboard(&mut terminal, &mut dashboard, &operations, options.refresh).await;
+    ratatui::restore();
+    mute_console(false);
+
+    let Some(request) = result? else {
+        return Ok(());
+    };
+    // The exact snapshot time selects this snapshot
+    let restore_options = restore_options(&request);
+    let workflow = RestoreWorkflow::new(
+        config,
+        Some(request.host),
+        Some(request.path.to_string_lossy().to_string()),
+        Some(request.time.to_rfc3339()),
+        restore_options,
+    )?;
+    workflow.execute_interactive_restore().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::operations::{RepositoryInfo, SnapshotInfo};
+    use chrono::{Duration, TimeZone};
+
+    fn repo(path: &str, category: &str, hours: &[i64]) -> RepositoryData {
+        let base = Utc.with_ymd_and_hms(2025, 1, 12, 3, 0, 0).unwrap();
+        let snapshots: Vec<SnapshotInfo> = hours
+            .iter()
+            .map(|h| SnapshotInfo {
+                time: base + Duration::hours(*h),
+                path: PathBuf::from(path),
+                id: format!("{:08x}", h),
+                tags: Vec::new(),
+                summary: None,
+            })
+            .collect();
+        RepositoryData {
+            info: RepositoryInfo {
+                native_path: PathBuf::from(path),
+                repo_subpath: path.trim_start_matches('/').to_string(),
+                category: category.to_string(),
+            },
+            snapshot_count: snapshots.len(),
+            snapshots,
+        }
+    }
+
+    #[test]
+    fn test_dashboard_navigation_and_wizard() {
+        let mut dashboard = Dashboard::new(vec!["nas".to_string(), "web1".to_string()], "web1");
+        assert_eq!(dashboard.highlighted_host(), Some("web1"));
+        assert_eq!(
+            dashboard.handle_key(KeyCode::Enter),
+            TuiCommand::LoadHost { refresh: false }
+        );
+        dashboard.set_repositories(
+            "web1",
+            ScanResult {
+                repos: vec![
+                    repo("/home/tim", "user_home", &[0]),
+                    repo("/etc", "system", &[0, 24, 48]),
+                    repo("/home/anna", "user_home", &[]),
+                ],
+                failures: Vec::new(),
+            },
+        );
+        assert_eq!(
+            dashboard.tree_rows(),
+            [
+                TreeRow::Category {
+                    name: "system".to_string(),
+                    count: 1
+                },
+                TreeRow::Repository(0),
+                TreeRow::Category {
+                    name: "user_home".to_string(),
+                    count: 2
+                },
+                TreeRow::Repository(1),
+                TreeRow::Repository(2),
+            ]
+        );
+        // Newest snapshot first
+        assert_eq!(dashboard.repos[0].snapshots[0].id, "00000030");
+
+        // Opening the host moved on to its repositories; the selection stops at the last one
+        assert_eq!(dashboard.focus, Pane::Repositories);
+        dashboard.handle_key(KeyCode::Down);
+        dashboard.handle_key(KeyCode::Down);
+        dashboard.handle_key(KeyCode::Down);
+        assert_eq!(dashboard.repo, 2);
+        dashboard.handle_key(KeyCode::Up);
+        // /home/anna has no snapshot to restore
+        dashboard.handle_key(KeyCode::Char('r'));
+        assert_eq!(dashboard.wizard, None);
+
+        dashboard.handle_key(KeyCode::Up);
+        dashboard.handle_key(KeyCode::Enter);
+        assert_eq!(dashboard.focus, Pane::Snapshots);
+        dashboard.handle_key(KeyCode::Down);
+        dashboard.handle_key(KeyCode::Enter);
+        assert!(dashboard.wizard.is_some());
+
+        // copy -> keep-both -> confirm
+        dashboard.handle_key(KeyCode::Enter);
+        dashboard.handle_key(KeyCode::Down);
+        dashboard.handle_key(KeyCode::Down);
+        dashboard.handle_key(KeyCode::Down);
+        dashboard.handle_key(KeyCode::Enter);
+        assert_eq!(dashboard.wizard.as_ref().unwrap().step, WizardStep::Confirm);
+        let TuiCommand::Restore(request) = dashboard.handle_key(KeyCode::Enter) else {
+            panic!("confirming the wizard starts a restore");
+        };
+        assert_eq!(request.host, "web1");
+        assert_eq!(request.path, PathBuf::from("/etc"));
+        assert_eq!(request.snapshot_id, "00000018");
+        assert_eq!(request.action, "copy");
+        assert_eq!(request.overwrite, "keep-both");
+        // The wizard does not answer the clear-destination prompt for the user
+        let options = restore_options(&request);
+        assert!(!options.assume_yes);
+        assert_eq!(options.action.as_deref(), Some("copy"));
+
+        // Leave skips the overwrite step, Esc steps back and finally closes the wizard
+        dashboard.handle_key(KeyCode::Char('r'));
+        dashboard.handle_key(KeyCode::Down);
+        dashboard.handle_key(KeyCode::Down);
+        dashboard.handle_key(KeyCode::Enter);
+        assert_eq!(dashboard.wizard.as_ref().unwrap().step, WizardStep::Confirm);
+        dashboard.handle_key(KeyCode::Esc);
+        assert_eq!(dashboard.wizard.as_ref().unwrap().step, WizardStep::Action);
+        assert_eq!(dashboard.handle_key(KeyCode::Esc), TuiCommand::None);
+        assert_eq!(dashboard.wizard, None);
+        assert_eq!(dashboard.handle_key(KeyCode::Char('q')), TuiCommand::Quit);
+    }
+
+    #[test]
+    fn test_daily_counts() {
+        let today = NaiveDate::from_ymd_opt(2025, 1, 12).unwrap();
+        let at = |day: u32, hour: u32| Utc.with_ymd_and_hms(2025, 1, day, hour, 0, 0).unwrap();
+        let counts = daily_counts(&[at(12, 3), at(12, 15), at(10, 3), at(1, 3)], today, 5);
+        assert_eq!(counts, [0, 0, 1, 0, 2]);
+    }
+}
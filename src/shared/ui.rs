@@ -1,8 +1,152 @@
 use crate::errors::BackupServiceError;
+use crate::i18n::{t, t_args};
 use crate::shared::constants::{CATEGORY_DOCKER_VOLUME, CATEGORY_SYSTEM, CATEGORY_USER_HOME};
 use crate::shared::operations::RepositorySelectionItem;
-use chrono::{DateTime, Duration, Utc};
+use crate::shared::timestamps::{format_local, parse_timestamp};
+use chrono::{DateTime, Duration, Local, Utc};
 use dialoguer::{Confirm, MultiSelect, Select};
+use std::collections::BTreeMap;
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::io::{BufRead, IsTerminal, Write};
+use std::path::{Component, Path};
+
+/// Env var enabling numbered text prompts instead of arrow-key menus
+pub const PLAIN_PROMPTS_ENV_VAR: &str = "RBS_PLAIN_PROMPTS";
+
+/// Env var listing hosts (comma-separated) that destructive operations must never touch
+pub const PROTECT_HOSTS_ENV_VAR: &str = "PROTECT_HOSTS";
+
+/// Plain prompts are used when requested or when the terminal cannot draw menus
+pub fn plain_prompts_enabled() -> bool {
+    std::env::var(PLAIN_PROMPTS_ENV_VAR).ok().as_deref() == Some("1")
+        || std::env::var("TERM").ok().as_deref() == Some("dumb")
+}
+
+/// Single-choice prompt: arrow-key menu, or a numbered list in plain mode
+pub fn select_item<T: ToString>(
+    prompt: &str,
+    items: &[T],
+    default: usize,
+) -> Result<usize, BackupServiceError> {
+    if !plain_prompts_enabled() {
+        return Ok(Select::new()
+            .with_prompt(prompt)
+            .items(items)
+            .default(default)
+            .interact()?);
+    }
+
+    print_numbered_items(prompt, items);
+    loop {
+        let input = read_plain_line(&format!(
+            "{} ",
+            t_args(
+                "plain-enter-number",
+                &[("default", (default + 1).to_string())]
+            )
+        ))?;
+        match parse_plain_selection(&input, items.len(), default) {
+            Some(index) => return Ok(index),
+            None => println!(
+                "{}",
+                t_args("plain-invalid-number", &[("max", items.len().to_string())])
+            ),
+        }
+    }
+}
+
+/// Multi-choice prompt: toggle menu, or comma-separated numbers/ranges in plain mode
+pub fn multi_select_items<T: ToString>(
+    prompt: &str,
+    items: &[T],
+) -> Result<Vec<usize>, BackupServiceError> {
+    if !plain_prompts_enabled() {
+        return Ok(MultiSelect::new()
+            .with_prompt(prompt)
+            .items(items)
+            .interact()?);
+    }
+
+    print_numbered_items(prompt, items);
+    loop {
+        let input = read_plain_line(&format!("{} ", t("plain-enter-numbers")))?;
+        match parse_plain_multi_selection(&input, items.len()) {
+            Some(indices) => return Ok(indices),
+            None => println!(
+                "{}",
+                t_args("plain-invalid-numbers", &[("max", items.len().to_string())])
+            ),
+        }
+    }
+}
+
+fn print_numbered_items<T: ToString>(prompt: &str, items: &[T]) {
+    println!("{}:", prompt);
+    for (i, item) in items.iter().enumerate() {
+        println!("  {}. {}", i + 1, item.to_string());
+    }
+}
+
+fn read_plain_line(prompt: &str) -> Result<String, BackupServiceError> {
+    print!("{}", prompt);
+    std::io::stdout().flush()?;
+    let mut line = String::new();
+    if std::io::stdin().lock().read_line(&mut line)? == 0 {
+        return Err(BackupServiceError::ConfigurationError(
+            "Input closed while waiting for a selection".to_string(),
+        ));
+    }
+    Ok(line.trim().to_string())
+}
+
+/// Parse a 1-based number; empty input selects the default
+fn parse_plain_selection(input: &str, len: usize, default: usize) -> Option<usize> {
+    let input = input.trim();
+    if input.is_empty() {
+        return (default < len).then_some(default);
+    }
+    let n: usize = input.parse().ok()?;
+    (1..=len).contains(&n).then(|| n - 1)
+}
+
+/// Parse comma/space separated 1-based numbers and ranges into sorted, unique indices
+fn parse_plain_multi_selection(input: &str, len: usize) -> Option<Vec<usize>> {
+    let mut indices = Vec::new();
+    for part in input
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|p| !p.is_empty())
+    {
+        let (start, end) = match part.split_once('-') {
+            Some((a, b)) => (
+                a.trim().parse::<usize>().ok()?,
+                b.trim().parse::<usize>().ok()?,
+            ),
+            None => {
+                let n = part.parse::<usize>().ok()?;
+                (n, n)
+            }
+        };
+        if start == 0 || end > len || start > end {
+            return None;
+        }
+        indices.extend((start - 1)..end);
+    }
+    indices.sort_unstable();
+    indices.dedup();
+    Some(indices)
+}
+
+/// Parse a yes/no answer; empty input selects the default
+fn parse_plain_confirm(input: &str, default: bool) -> Option<bool> {
+    match input.trim().to_lowercase().as_str() {
+        "" => Some(default),
+        "y" | "yes" | "j" | "ja" => Some(true),
+        "n" | "no" | "nein" => Some(false),
+        _ => None,
+    }
+}
+
 /// Handle category-based repository selection
 fn handle_category_selection(
     backup_data: &[RepositorySelectionItem],
@@ -15,6 +159,38 @@ fn handle_category_selection(
         .collect()
 }
 
+/// Owner of a `/home/<user>/...` repository path
+fn home_user(path: &Path) -> Option<String> {
+    let mut components = path.components().skip_while(|c| c == &Component::RootDir);
+    match (components.next(), components.next()) {
+        (Some(Component::Normal(home)), Some(Component::Normal(user))) if home == "home" => {
+            Some(user.to_string_lossy().to_string())
+        }
+        _ => None,
+    }
+}
+
+/// Users with at least one repository under /home, sorted, with their repository counts
+fn home_users(backup_data: &[RepositorySelectionItem]) -> Vec<(String, usize)> {
+    let mut users: BTreeMap<String, usize> = BTreeMap::new();
+    for user in backup_data.iter().filter_map(|r| home_user(&r.path)) {
+        *users.entry(user).or_default() += 1;
+    }
+    users.into_iter().collect()
+}
+
+/// All repositories under /home/<user>
+fn handle_user_selection(
+    backup_data: &[RepositorySelectionItem],
+    user: &str,
+) -> Vec<RepositorySelectionItem> {
+    backup_data
+        .iter()
+        .filter(|r| home_user(&r.path).as_deref() == Some(user))
+        .cloned()
+        .collect()
+}
+
 /// Host selection data
 #[derive(Debug, Clone)]
 pub struct HostSelection {
@@ -53,11 +229,7 @@ pub async fn select_host(
             .position(|h| h == &current_host)
             .unwrap_or(0);
 
-        let selection = Select::new()
-            .with_prompt("Select hostname")
-            .items(&available_hosts)
-            .default(default)
-            .interact()?;
+        let selection = select_item(&t("prompt-select-host"), &available_hosts, default)?;
 
         available_hosts[selection].clone()
     };
@@ -97,57 +269,91 @@ pub async fn select_repositories(
             .count();
 
         info!(
-            "Found backups: User Home ({}), Docker Volumes ({}), System ({})",
-            user_home_count, docker_count, system_count
+            "{}",
+            t_args(
+                "found-backups",
+                &[
+                    ("user_home", user_home_count.to_string()),
+                    ("docker", docker_count.to_string()),
+                    ("system", system_count.to_string()),
+                ],
+            )
         );
 
         let categories = vec![
-            "All (everything)",
-            "User Home (all user directories)",
-            "Docker Volumes (all docker volumes)",
-            "System (all system paths)",
-            "Custom Selection (choose specific repositories)",
-            "Individual Repository (single selection)",
+            t("scope-all"),
+            t("scope-user-home"),
+            t("scope-single-user"),
+            t("scope-docker"),
+            t("scope-system"),
+            t("scope-custom"),
+            t("scope-single"),
         ];
 
-        let selection = Select::new()
-            .with_prompt("Select what to restore")
-            .items(&categories)
-            .default(0)
-            .interact()?;
+        let selection = select_item(&t("prompt-select-restore-scope"), &categories, 0)?;
 
         match selection {
             0 => backup_data.clone(),
             1 => handle_category_selection(&backup_data, CATEGORY_USER_HOME),
-            2 => handle_category_selection(&backup_data, CATEGORY_DOCKER_VOLUME),
-            3 => handle_category_selection(&backup_data, CATEGORY_SYSTEM),
-            4 => {
+            2 => {
+                let users = home_users(&backup_data);
+                if users.is_empty() {
+                    return Err(BackupServiceError::ConfigurationError(
+                        "No repositories under /home found for this host".to_string(),
+                    ));
+                }
+                let items: Vec<String> = users
+                    .iter()
+                    .map(|(user, count)| {
+                        t_args(
+                            "user-item",
+                            &[("user", user.clone()), ("count", count.to_string())],
+                        )
+                    })
+                    .collect();
+
+                let selection = select_item(&t("prompt-select-user"), &items, 0)?;
+
+                handle_user_selection(&backup_data, &users[selection].0)
+            }
+            3 => handle_category_selection(&backup_data, CATEGORY_DOCKER_VOLUME),
+            4 => handle_category_selection(&backup_data, CATEGORY_SYSTEM),
+            5 => {
                 let items: Vec<String> = backup_data
                     .iter()
-                    .map(|r| format!("{} ({} snapshots)", r.path.display(), r.snapshots.len()))
+                    .map(|r| {
+                        t_args(
+                            "repo-item",
+                            &[
+                                ("path", r.path.display().to_string()),
+                                ("count", r.snapshots.len().to_string()),
+                            ],
+                        )
+                    })
                     .collect();
 
-                let selections = MultiSelect::new()
-                    .with_prompt("Select repositories (space to toggle, enter to confirm)")
-                    .items(&items)
-                    .interact()?;
+                let selections = multi_select_items(&t("prompt-select-repositories"), &items)?;
 
                 selections
                     .into_iter()
                     .map(|i| backup_data[i].clone())
                     .collect()
             }
-            5 => {
+            6 => {
                 let items: Vec<String> = backup_data
                     .iter()
-                    .map(|r| format!("{} ({} snapshots)", r.path.display(), r.snapshots.len()))
+                    .map(|r| {
+                        t_args(
+                            "repo-item",
+                            &[
+                                ("path", r.path.display().to_string()),
+                                ("count", r.snapshots.len().to_string()),
+                            ],
+                        )
+                    })
                     .collect();
 
-                let selection = Select::new()
-                    .with_prompt("Select repository")
-                    .items(&items)
-                    .default(0)
-                    .interact()?;
+                let selection = select_item(&t("prompt-select-repository"), &items, 0)?;
 
                 vec![backup_data[selection].clone()]
             }
@@ -170,7 +376,7 @@ pub async fn select_timestamp(
     timestamp_opt: Option<String>,
 ) -> Result<TimestampSelection, BackupServiceError> {
     let selected_timestamp = if let Some(ts) = timestamp_opt {
-        ts.parse::<DateTime<Utc>>()?
+        parse_timestamp(&ts)?
     } else {
         let mut all_timestamps: Vec<DateTime<Utc>> = selected_repos
             .iter()
@@ -205,11 +411,16 @@ pub async fn select_timestamp(
                     .filter(|t| **t >= window_time && **t < window_end)
                     .count();
 
-                let label = format!(
-                    "{} to {} ({} snapshots)",
-                    window_time.format("%Y-%m-%d %H:%M"),
-                    window_end.format("%H:%M"),
-                    count
+                let label = t_args(
+                    "time-window-label",
+                    &[
+                        ("start", format_local(window_time)),
+                        (
+                            "end",
+                            window_end.with_timezone(&Local).format("%H:%M").to_string(),
+                        ),
+                        ("count", count.to_string()),
+                    ],
                 );
 
                 time_windows.push(label);
@@ -217,16 +428,12 @@ pub async fn select_timestamp(
             }
         }
 
-        info!("Available restore time windows (5-minute groups):");
+        info!("{}", t("time-windows-header"));
         for (i, window) in time_windows.iter().enumerate() {
             info!("  {}. {}", i + 1, window);
         }
 
-        let selection = Select::new()
-            .with_prompt("Select time window [1]")
-            .items(&time_windows)
-            .default(0)
-            .interact()?;
+        let selection = select_item(&t("prompt-select-time-window"), &time_windows, 0)?;
 
         window_times[selection]
     };
@@ -236,6 +443,17 @@ pub async fn select_timestamp(
 
 /// Simple confirmation dialog
 pub async fn confirm_action(prompt: &str, default: bool) -> Result<bool, BackupServiceError> {
+    if plain_prompts_enabled() {
+        let hint = if default { "[Y/n]" } else { "[y/N]" };
+        loop {
+            let input = read_plain_line(&format!("{} {} ", prompt, hint))?;
+            match parse_plain_confirm(&input, default) {
+                Some(answer) => return Ok(answer),
+                None => println!("{}", t("plain-answer-yes-no")),
+            }
+        }
+    }
+
     let result = Confirm::new()
         .with_prompt(prompt)
         .default(default)
@@ -243,6 +461,118 @@ pub async fn confirm_action(prompt: &str, default: bool) -> Result<bool, BackupS
     Ok(result)
 }
 
+/// Hosts listed in PROTECT_HOSTS
+pub fn protected_hosts() -> Vec<String> {
+    std::env::var(PROTECT_HOSTS_ENV_VAR)
+        .unwrap_or_default()
+        .split(',')
+        .map(|h| h.trim().to_string())
+        .filter(|h| !h.is_empty())
+        .collect()
+}
+
+/// Refuse any destructive operation against a protected host
+pub fn ensure_host_not_protected(
+    operation: &str,
+    hostname: &str,
+    protected: &[String],
+) -> Result<(), BackupServiceError> {
+    if protected.iter().any(|h| h == hostname) {
+        let args = [
+            ("operation", operation.to_string()),
+            ("host", hostname.to_string()),
+            ("variable", PROTECT_HOSTS_ENV_VAR.to_string()),
+        ];
+        return Err(BackupServiceError::ConfigurationError(format!(
+            "{}\n\n{}",
+            t_args("host-protected", &args),
+            t_args("host-protected-hint", &args)
+        )));
+    }
+    Ok(())
+}
+
+/// One-time code the operator can type instead of the hostname
+fn generate_confirmation_code(operation: &str, hostname: &str) -> String {
+    // RandomState is seeded per process, so the code differs on every run
+    let hash = RandomState::new().hash_one((operation, hostname));
+    format!("{:06X}", hash & 0xFF_FFFF)
+}
+
+/// Typed confirmation matches the hostname exactly or the code case-insensitively
+fn confirmation_matches(input: &str, hostname: &str, code: &str) -> bool {
+    let input = input.trim();
+    input == hostname || input.eq_ignore_ascii_case(code)
+}
+
+/// Multi-factor confirmation for destructive operations against a host's repositories
+///
+/// Protected hosts are always refused. With `--yes`, the hostname must be repeated via
+/// `--confirm`; otherwise the operator has to type the hostname or a generated code.
+pub fn confirm_destructive(
+    operation: &str,
+    hostname: &str,
+    assume_yes: bool,
+    confirm: Option<&str>,
+) -> Result<(), BackupServiceError> {
+    ensure_host_not_protected(operation, hostname, &protected_hosts())?;
+
+    if assume_yes {
+        return match confirm.map(str::trim) {
+            Some(value) if value == hostname => Ok(()),
+            Some(value) => Err(BackupServiceError::ConfigurationError(t_args(
+                "confirm-mismatch",
+                &[("value", value.to_string()), ("host", hostname.to_string())],
+            ))),
+            None => Err(BackupServiceError::ConfigurationError(t_args(
+                "confirm-required",
+                &[
+                    ("host", hostname.to_string()),
+                    ("operation", operation.to_string()),
+                ],
+            ))),
+        };
+    }
+
+    if !std::io::stdin().is_terminal() {
+        return Err(BackupServiceError::ConfigurationError(format!(
+            "{}\n\n{}",
+            t_args(
+                "confirm-no-terminal",
+                &[("operation", operation.to_string())]
+            ),
+            t_args(
+                "confirm-no-terminal-hint",
+                &[("host", hostname.to_string())]
+            )
+        )));
+    }
+
+    let code = generate_confirmation_code(operation, hostname);
+    println!(
+        "{}",
+        t_args(
+            "destructive-warning",
+            &[
+                ("operation", operation.to_string()),
+                ("host", hostname.to_string()),
+            ],
+        )
+    );
+    let input = read_plain_line(&format!(
+        "{} ",
+        t_args(
+            "prompt-type-confirmation",
+            &[("host", hostname.to_string()), ("code", code.clone())],
+        )
+    ))?;
+
+    if !confirmation_matches(&input, hostname, &code) {
+        return Err(BackupServiceError::ConfigurationError(t("confirm-aborted")));
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -274,6 +604,48 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_user_selection_groups_home_repositories() {
+        let backup_data = vec![
+            create_test_repository_item(
+                "/home/alice/Documents",
+                "user_home/alice/Documents",
+                "user_home",
+                vec![],
+            ),
+            create_test_repository_item("/home/bob", "user_home/bob", "user_home", vec![]),
+            create_test_repository_item(
+                "/home/alice/.config",
+                "user_home/alice/.config",
+                "user_home",
+                vec![],
+            ),
+            create_test_repository_item(
+                "/etc/home/carol",
+                "system/etc/home/carol",
+                "system",
+                vec![],
+            ),
+        ];
+
+        assert_eq!(
+            home_users(&backup_data),
+            vec![("alice".to_string(), 2), ("bob".to_string(), 1)]
+        );
+        let alice: Vec<PathBuf> = handle_user_selection(&backup_data, "alice")
+            .into_iter()
+            .map(|r| r.path)
+            .collect();
+        assert_eq!(
+            alice,
+            vec![
+                PathBuf::from("/home/alice/Documents"),
+                PathBuf::from("/home/alice/.config")
+            ]
+        );
+        assert!(handle_user_selection(&backup_data, "carol").is_empty());
+    }
+
     #[tokio::test]
     async fn test_select_host_with_host_opt() -> Result<(), BackupServiceError> {
         let available_hosts = vec!["host1".to_string(), "host2".to_string()];
@@ -620,6 +992,68 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_parse_plain_selection() {
+        assert_eq!(parse_plain_selection("2", 3, 0), Some(1));
+        assert_eq!(parse_plain_selection(" 3 ", 3, 0), Some(2));
+        assert_eq!(parse_plain_selection("", 3, 1), Some(1));
+        assert_eq!(parse_plain_selection("0", 3, 0), None);
+        assert_eq!(parse_plain_selection("4", 3, 0), None);
+        assert_eq!(parse_plain_selection("abc", 3, 0), None);
+        assert_eq!(parse_plain_selection("", 0, 0), None);
+    }
+
+    #[test]
+    fn test_parse_plain_multi_selection() {
+        assert_eq!(parse_plain_multi_selection("1,3", 5), Some(vec![0, 2]));
+        assert_eq!(
+            parse_plain_multi_selection("5-7, 1 2", 8),
+            Some(vec![0, 1, 4, 5, 6])
+        );
+        assert_eq!(parse_plain_multi_selection("2,2,1-2", 3), Some(vec![0, 1]));
+        assert_eq!(parse_plain_multi_selection("", 3), Some(vec![]));
+        assert_eq!(parse_plain_multi_selection("0", 3), None);
+        assert_eq!(parse_plain_multi_selection("2-9", 3), None);
+        assert_eq!(parse_plain_multi_selection("3-1", 3), None);
+        assert_eq!(parse_plain_multi_selection("x", 3), None);
+    }
+
+    #[test]
+    fn test_parse_plain_confirm() {
+        assert_eq!(parse_plain_confirm("", true), Some(true));
+        assert_eq!(parse_plain_confirm("", false), Some(false));
+        assert_eq!(parse_plain_confirm("Y", false), Some(true));
+        assert_eq!(parse_plain_confirm("no", true), Some(false));
+        assert_eq!(parse_plain_confirm("ja", false), Some(true));
+        assert_eq!(parse_plain_confirm("maybe", true), None);
+    }
+
+    #[test]
+    fn test_protected_host_is_refused() {
+        let protected = vec!["nas".to_string(), "prod-db".to_string()];
+        let err = ensure_host_not_protected("prune", "prod-db", &protected).unwrap_err();
+        assert!(err.to_string().contains("PROTECT_HOSTS"));
+        assert!(ensure_host_not_protected("prune", "laptop", &protected).is_ok());
+    }
+
+    #[test]
+    fn test_confirmation_matching() {
+        let code = generate_confirmation_code("prune", "laptop");
+        assert_eq!(code.len(), 6);
+        assert!(confirmation_matches(" laptop ", "laptop", &code));
+        assert!(confirmation_matches(&code.to_lowercase(), "laptop", &code));
+        assert!(!confirmation_matches("Laptop", "laptop", &code));
+        assert!(!confirmation_matches("", "laptop", &code));
+    }
+
+    #[test]
+    fn test_assume_yes_requires_matching_hostname() {
+        assert!(confirm_destructive("prune", "laptop", true, Some("laptop")).is_ok());
+        assert!(confirm_destructive("prune", "laptop", true, Some("desktop")).is_err());
+        let err = confirm_destructive("prune", "laptop", true, None).unwrap_err();
+        assert!(err.to_string().contains("--confirm laptop"));
+    }
+
     #[test]
     fn test_host_default_selection_logic() -> Result<(), BackupServiceError> {
         // Test the host default selection logic
@@ -0,0 +1,360 @@
+use crate::config::Config;
+use crate::errors::BackupServiceError;
+use crate::shared::commands::ResticCommandExecutor;
+use crate::shared::display::DisplayFormatter;
+use crate::shared::logs_workflow::parse_since;
+use crate::shared::operations::{DiscoveryFailure, RepositoryOperations};
+use crate::shared::snapshot_filter::serialize_time;
+use crate::utils::validate_credentials;
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use serde_json::{Value, json};
+use tokio::task::JoinSet;
+use tracing::{error, info, warn};
+
+/// How old a lock taken on another machine must be before it is removed (`--min-age` overrides it)
+pub const UNLOCK_MIN_AGE_ENV_VAR: &str = "UNLOCK_MIN_AGE";
+const DEFAULT_MIN_AGE: &str = "1h";
+
+/// restic refreshes the locks it holds every 5 minutes and calls them stale after 30
+const RESTIC_STALE_MINUTES: i64 = 30;
+
+/// `unlock` options
+#[derive(Debug, Clone, Default)]
+pub struct UnlockOptions {
+    /// Minimum lock age (`--min-age`, default from UNLOCK_MIN_AGE, else 1h)
+    pub min_age: Option<String>,
+    /// Only report the locks (`--dry-run`)
+    pub dry_run: bool,
+    pub json_output: bool,
+}
+
+/// Safety checks a lock has to pass before it is removed
+#[derive(Debug, Clone)]
+pub struct StaleLockPolicy {
+    min_age: Duration,
+    label: String,
+}
+
+impl StaleLockPolicy {
+    /// `--min-age` when given, else UNLOCK_MIN_AGE, else 1h
+    pub fn resolve(flag: Option<&str>) -> Result<Self, BackupServiceError> {
+        let label = match flag {
+            Some(value) => value.trim().to_string(),
+            None => std::env::var(UNLOCK_MIN_AGE_ENV_VAR)
+                .ok()
+                .filter(|v| !v.trim().is_empty())
+                .unwrap_or_else(|| DEFAULT_MIN_AGE.to_string()),
+        };
+        let min_age = parse_since(&label).map_err(|_| {
+            BackupServiceError::ConfigurationError(format!(
+                "Invalid lock age: {}.\n\nUse a number with a unit: 30m, 2h, 1d",
+                label
+            ))
+        })?;
+        if min_age < Duration::minutes(RESTIC_STALE_MINUTES) {
+            return Err(BackupServiceError::ConfigurationError(format!(
+                "Lock age {} is shorter than restic's {} minute refresh window.\n\n\
+                A younger lock may belong to a backup that is still running; use {}m or more",
+                label, RESTIC_STALE_MINUTES, RESTIC_STALE_MINUTES
+            )));
+        }
+        Ok(Self { min_age, label })
+    }
+
+    pub fn describe(&self) -> &str {
+        &self.label
+    }
+
+    /// Why a lock has to stay, or None when it is stale
+    ///
+    /// Locks of this machine are judged by their process, whatever their age; locks of
+    /// other machines only by age, since a running restic keeps refreshing its lock.
+    fn keep_reason(
+        &self,
+        lock: &RepoLock,
+        now: DateTime<Utc>,
+        this_host: &str,
+        process_alive: impl Fn(u32) -> bool,
+    ) -> Option<String> {
+        if lock.hostname == this_host {
+            return process_alive(lock.pid)
+                .then(|| format!("process {} on this machine is still running", lock.pid));
+        }
+        let age = now - lock.time;
+        (age < self.min_age).then(|| {
+            format!(
+                "refreshed {}m ago on {}, younger than {}",
+                age.num_minutes().max(0),
+                lock.hostname,
+                self.label
+            )
+        })
+    }
+}
+
+/// A lock stored in a repository (`restic cat lock`)
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RepoLock {
+    pub id: String,
+    #[serde(serialize_with = "serialize_time")]
+    pub time: DateTime<Utc>,
+    pub exclusive: bool,
+    pub hostname: String,
+    pub username: String,
+    pub pid: u32,
+    /// Why the lock was left in place; absent for stale locks
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kept_because: Option<String>,
+}
+
+impl RepoLock {
+    fn from_json(id: &str, lock: &Value) -> Result<Self, BackupServiceError> {
+        let time = lock["time"]
+            .as_str()
+            .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+            .ok_or_else(|| {
+                BackupServiceError::CommandFailed(format!("Lock {} has no readable time", id))
+            })?;
+        Ok(Self {
+            id: id.to_string(),
+            time: time.with_timezone(&Utc),
+            exclusive: lock["exclusive"].as_bool().unwrap_or_default(),
+            hostname: lock["hostname"].as_str().unwrap_or_default().to_string(),
+            username: lock["username"].as_str().unwrap_or_default().to_string(),
+            pid: lock["pid"].as_u64().unwrap_or_default() as u32,
+            kept_because: None,
+        })
+    }
+}
+
+/// Locks of one repository and whether they were removed
+#[derive(Debug, Clone, Serialize)]
+pub struct RepoUnlock {
+    pub repo_subpath: String,
+    pub locks: Vec<RepoLock>,
+    /// `restic unlock` ran: every lock was stale
+    pub unlocked: bool,
+}
+
+/// System hostname, as restic records it in its locks
+pub fn machine_hostname() -> String {
+    hostname::get()
+        .map(|h| h.to_string_lossy().to_string())
+        .unwrap_or_else(|_| "unknown".to_string())
+}
+
+fn process_alive(pid: u32) -> bool {
+    // Signal 0 only checks that the process exists; EPERM means it does, owned by someone else
+    let result = unsafe { libc::kill(pid as libc::pid_t, 0) };
+    result == 0 || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+/// Check a repository's locks and run `restic unlock` when every one of them is stale
+///
+/// A single lock that fails the checks keeps the repository untouched, because `restic
+/// unlock` cannot remove locks selectively.
+pub async fn unlock_stale(
+    restic_cmd: &ResticCommandExecutor,
+    policy: &StaleLockPolicy,
+    dry_run: bool,
+) -> Result<(Vec<RepoLock>, bool), BackupServiceError> {
+    let now = Utc::now();
+    let this_host = machine_hostname();
+    let mut locks = Vec::new();
+    for (id, lock) in restic_cmd.locks().await? {
+        let mut lock = RepoLock::from_json(&id, &lock)?;
+        lock.kept_because = policy.keep_reason(&lock, now, &this_host, process_alive);
+        locks.push(lock);
+    }
+    let stale = !locks.is_empty() && locks.iter().all(|l| l.kept_because.is_none());
+    if stale && !dry_run {
+        restic_cmd.unlock().await?;
+    }
+    Ok((locks, stale && !dry_run))
+}
+
+/// Remove stale locks from every repository of a host
+pub async fn execute_unlock_workflow(
+    config: Config,
+    host: Option<String>,
+    options: UnlockOptions,
+) -> Result<(), BackupServiceError> {
+    let hostname = host.unwrap_or_else(|| config.hostname.clone());
+    let policy = StaleLockPolicy::resolve(options.min_age.as_deref())?;
+    config.set_aws_env()?;
+    validate_credentials(&config).await?;
+
+    let operations = RepositoryOperations::new(config.clone())?;
+    let discovery = operations.discover_all_repositories(&hostname).await?;
+    let mut failures = discovery.failures;
+    let repo_count = discovery.repos.len();
+    if !options.json_output {
+        info!(
+            hostname = %hostname,
+            repo_count = %repo_count,
+            min_age = %policy.describe(),
+            dry_run = %options.dry_run,
+            "Checking repositories for stale locks"
+        );
+    }
+
+    let mut tasks = JoinSet::new();
+    for repo in discovery.repos {
+        let config = config.clone();
+        let hostname = hostname.clone();
+        let policy = policy.clone();
+        let dry_run = options.dry_run;
+        tasks.spawn(async move {
+            let result = async {
+                let repo_url = config.get_repo_url_for_host(&hostname, &repo.repo_subpath)?;
+                let restic_cmd = ResticCommandExecutor::new(config, repo_url)?;
+                unlock_stale(&restic_cmd, &policy, dry_run).await
+            }
+            .await;
+            (repo.repo_subpath, result)
+        });
+    }
+
+    let mut reviewed = Vec::new();
+    while let Some(joined) = tasks.join_next().await {
+        let (repo_subpath, result) = joined
+            .map_err(|e| BackupServiceError::CommandFailed(format!("Task join error: {}", e)))?;
+        match result {
+            Ok((locks, unlocked)) if !locks.is_empty() => reviewed.push(RepoUnlock {
+                repo_subpath,
+                locks,
+                unlocked,
+            }),
+            Ok(_) => {}
+            Err(e) => {
+                error!(repo_subpath = %repo_subpath, error = %e, "Failed to check locks");
+                failures.push(DiscoveryFailure {
+                    scope: repo_subpath,
+                    message: format!("failed to check locks ({})", e),
+                });
+            }
+        }
+    }
+    reviewed.sort_by(|a, b| a.repo_subpath.cmp(&b.repo_subpath));
+
+    if options.json_output {
+        let output = json!({
+            "host": hostname,
+            "min_age": policy.describe(),
+            "dry_run": options.dry_run,
+            "repositories": reviewed,
+            "errors": failures,
+        });
+        DisplayFormatter::print_json(&output)?;
+    } else {
+        log_review(&reviewed, options.dry_run);
+    }
+
+    if !failures.is_empty() {
+        return Err(BackupServiceError::CommandFailed(format!(
+            "Could not check the locks of {} of {} repositories",
+            failures.len(),
+            repo_count
+        )));
+    }
+    Ok(())
+}
+
+fn log_review(reviewed: &[RepoUnlock], dry_run: bool) {
+    if reviewed.is_empty() {
+        info!("No locks found");
+        return;
+    }
+    for repo in reviewed {
+        let kept: Vec<_> = repo
+            .locks
+            .iter()
+            .filter_map(|l| l.kept_because.as_ref().map(|reason| (l, reason)))
+            .collect();
+        if kept.is_empty() {
+            info!(
+                repo_subpath = %repo.repo_subpath,
+                locks = %repo.locks.len(),
+                "{}",
+                if dry_run { "Would remove stale locks" } else { "Removed stale locks" }
+            );
+        }
+        for (lock, reason) in kept {
+            warn!(
+                repo_subpath = %repo.repo_subpath,
+                lock_id = %lock.id.chars().take(8).collect::<String>(),
+                exclusive = %lock.exclusive,
+                reason = %reason,
+                "Lock kept, repository left locked"
+            );
+        }
+    }
+    let unlocked = reviewed
+        .iter()
+        .filter(|r| r.locks.iter().all(|l| l.kept_because.is_none()))
+        .count();
+    info!(
+        locked_repos = %reviewed.len(),
+        stale = %unlocked,
+        kept = %(reviewed.len() - unlocked),
+        "Lock check finished"
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lock(hostname: &str, pid: u32, time: &str) -> RepoLock {
+        RepoLock::from_json(
+            "4f1c2a9e",
+            &json!({
+                "time": time,
+                "exclusive": true,
+                "hostname": hostname,
+                "username": "root",
+                "pid": pid,
+            }),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_stale_lock_checks() -> Result<(), BackupServiceError> {
+        let policy = StaleLockPolicy::resolve(Some("2h"))?;
+        let now: DateTime<Utc> = "2025-01-20T12:00:00Z".parse().unwrap();
+        let alive = |pid| pid == 100;
+
+        // Other machines: by age only
+        let old = lock("nas", 100, "2025-01-20T09:30:00.123456+01:00");
+        assert_eq!(
+            old.time,
+            "2025-01-20T08:30:00.123456Z"
+                .parse::<DateTime<Utc>>()
+                .unwrap()
+        );
+        assert!(policy.keep_reason(&old, now, "desktop", alive).is_none());
+        let recent = lock("nas", 7, "2025-01-20T11:15:00Z");
+        assert!(policy.keep_reason(&recent, now, "desktop", alive).is_some());
+
+        // This machine: by process, whatever the age
+        let running = lock("desktop", 100, "2025-01-19T12:00:00Z");
+        assert!(
+            policy
+                .keep_reason(&running, now, "desktop", alive)
+                .is_some()
+        );
+        let crashed = lock("desktop", 7, "2025-01-20T11:59:00Z");
+        assert!(
+            policy
+                .keep_reason(&crashed, now, "desktop", alive)
+                .is_none()
+        );
+
+        assert!(StaleLockPolicy::resolve(Some("10m")).is_err());
+        assert!(StaleLockPolicy::resolve(Some("soon")).is_err());
+        assert!(RepoLock::from_json("x", &json!({"pid": 1})).is_err());
+        Ok(())
+    }
+}
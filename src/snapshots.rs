@@ -0,0 +1,12 @@
+use crate::config::Config;
+use crate::errors::BackupServiceError;
+use crate::shared::snapshots_workflow::{SnapshotsOptions, execute_snapshots_workflow};
+
+// CLI command to list a host's snapshots across all repositories, filtered
+pub async fn list_snapshots(
+    config: Config,
+    host: Option<String>,
+    options: SnapshotsOptions,
+) -> Result<(), BackupServiceError> {
+    execute_snapshots_workflow(config, host, options).await
+}
@@ -0,0 +1,17 @@
+use crate::config::Config;
+use crate::errors::BackupServiceError;
+use crate::shared::stats_workflow::{StatsOptions, execute_stats_workflow};
+use crate::utils::show_size;
+
+// CLI command to show the size of one path, or of every repository of a host
+pub async fn show_stats(
+    config: Config,
+    host: Option<String>,
+    path: Option<String>,
+    options: StatsOptions,
+) -> Result<(), BackupServiceError> {
+    match path {
+        Some(path) => show_size(config, host, path, options.json_output).await,
+        None => execute_stats_workflow(config, host, options).await,
+    }
+}
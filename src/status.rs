@@ -0,0 +1,8 @@
+use crate::config::Config;
+use crate::errors::BackupServiceError;
+use crate::shared::status_workflow::{StatusOptions, execute_status_workflow};
+
+// CLI command to report per path when it was last backed up, failing on stale paths
+pub async fn show_status(config: Config, options: StatusOptions) -> Result<(), BackupServiceError> {
+    execute_status_workflow(config, options).await
+}
@@ -0,0 +1,12 @@
+use crate::config::Config;
+use crate::errors::BackupServiceError;
+use crate::shared::tui_workflow::{TuiOptions, execute_tui_workflow};
+
+// CLI command for the full-screen dashboard with its restore wizard
+pub async fn run_dashboard(
+    config: Config,
+    host: Option<String>,
+    options: TuiOptions,
+) -> Result<(), BackupServiceError> {
+    execute_tui_workflow(config, host, options).await
+}
@@ -0,0 +1,12 @@
+use crate::config::Config;
+use crate::errors::BackupServiceError;
+use crate::shared::unlock_workflow::{UnlockOptions, execute_unlock_workflow};
+
+// CLI command to remove stale restic locks from all repositories of a host
+pub async fn run_unlock(
+    config: Config,
+    host: Option<String>,
+    options: UnlockOptions,
+) -> Result<(), BackupServiceError> {
+    execute_unlock_workflow(config, host, options).await
+}
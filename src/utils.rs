@@ -1,52 +1,42 @@
 use crate::config::Config;
 use crate::errors::BackupServiceError;
+use crate::shared::repo_store::RepoStore;
 use std::path::Path;
-use std::process::Command;
 use tracing::{error, info, warn};
 
-// Test AWS credentials by attempting S3 bucket listing with AWS CLI
+// Test the backend credentials by listing the repository base (one key on S3)
 pub async fn validate_credentials(config: &Config) -> Result<(), BackupServiceError> {
     info!("Validating credentials...");
 
-    let s3_bucket = config.s3_bucket()?;
-
-    // Execute AWS CLI to test S3 access with configured credentials
-    let output = Command::new("aws")
-        .args([
-            "s3",
-            "ls",
-            &format!("s3://{}/", s3_bucket),
-            "--endpoint-url",
-            &config.s3_endpoint()?,
-        ])
-        .env("AWS_ACCESS_KEY_ID", &config.aws_access_key_id)
-        .env("AWS_SECRET_ACCESS_KEY", &config.aws_secret_access_key)
-        .env("AWS_DEFAULT_REGION", &config.aws_default_region)
-        .output()
-        .map_err(|_| BackupServiceError::aws_command_failed())?;
-
-    if output.status.success() {
-        info!("Credentials validated successfully");
-        Ok(())
-    } else {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        let error = BackupServiceError::from_stderr(&stderr, "credential validation");
-
-        error!(error = %error, "Credential validation failed");
-
-        Err(error.with_validation_context())
+    match RepoStore::new(config.clone())?.validate().await {
+        Ok(_) => {
+            info!("Credentials validated successfully");
+            Ok(())
+        }
+        Err(error) => {
+            error!(error = %error, "Credential validation failed");
+            Err(error.with_validation_context())
+        }
     }
 }
 
 // Calculate and display backup size for a specific path
-pub async fn show_size(config: Config, path: String) -> Result<(), BackupServiceError> {
+pub async fn show_size(
+    config: Config,
+    host: Option<String>,
+    path: String,
+    json_output: bool,
+) -> Result<(), BackupServiceError> {
     use crate::shared::commands::ResticCommandExecutor;
-    use crate::shared::paths::PathMapper;
+    use crate::shared::display::DisplayFormatter;
+    use crate::shared::paths::resolve_repo_subpath;
+    use serde_json::json;
 
     // Map native filesystem path to repository structure
     let native_path = Path::new(&path);
-    let repo_subpath = PathMapper::path_to_repo_subpath(native_path)?;
-    let repo_url = config.get_repo_url(&repo_subpath)?;
+    let hostname = host.unwrap_or_else(|| config.hostname.clone());
+    let repo_subpath = resolve_repo_subpath(&config, &hostname, native_path).await?;
+    let repo_url = config.get_repo_url_for_host(&hostname, &repo_subpath)?;
     let restic_cmd = ResticCommandExecutor::new(config, repo_url)?;
 
     info!(path = %path, "Checking size for path");
@@ -55,12 +45,30 @@ pub async fn show_size(config: Config, path: String) -> Result<(), BackupService
 
     if snapshots.is_empty() {
         warn!(path = %path, "No snapshots found for path");
+        if json_output {
+            DisplayFormatter::print_json(&json!({
+                "path": path,
+                "repo_subpath": repo_subpath,
+                "snapshot_count": 0,
+                "size_bytes": null,
+            }))?;
+        }
         return Ok(());
     }
 
     let total_size = restic_cmd.stats(&path).await?;
     let size_str = format_bytes(total_size)?;
-    info!(path = %path, size = %size_str, "Path size calculated");
+    if json_output {
+        DisplayFormatter::print_json(&json!({
+            "path": path,
+            "repo_subpath": repo_subpath,
+            "snapshot_count": snapshots.len(),
+            "size_bytes": total_size,
+            "size": size_str,
+        }))?;
+    } else {
+        info!(path = %path, size = %size_str, "Path size calculated");
+    }
 
     Ok(())
 }
@@ -86,10 +94,90 @@ pub fn format_bytes(bytes: u64) -> Result<String, BackupServiceError> {
     Ok(formatted)
 }
 
+// Parse a human size like `500M`, `2G`, `1.5T` or plain bytes (binary units, as restic uses)
+pub fn parse_size(value: &str) -> Result<u64, BackupServiceError> {
+    let trimmed = value.trim();
+    let split = trimmed
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(trimmed.len());
+    let (number, unit) = trimmed.split_at(split);
+
+    let multiplier: u64 = match unit.trim().to_uppercase().trim_end_matches('B') {
+        "" => 1,
+        "K" => 1 << 10,
+        "M" => 1 << 20,
+        "G" => 1 << 30,
+        "T" => 1 << 40,
+        _ => {
+            return Err(BackupServiceError::ConfigurationError(format!(
+                "Invalid size '{}': expected a number with optional K, M, G or T suffix",
+                value
+            )));
+        }
+    };
+
+    let number: f64 = number.parse().map_err(|_| {
+        BackupServiceError::ConfigurationError(format!(
+            "Invalid size '{}': expected a number with optional K, M, G or T suffix",
+            value
+        ))
+    })?;
+
+    Ok((number * multiplier as f64) as u64)
+}
+
+// Parallel repository jobs: CLI flag > env variable (e.g. PRUNE_JOBS) > default
+pub fn resolve_jobs(
+    cli: Option<usize>,
+    env_var: &str,
+    default: usize,
+) -> Result<usize, BackupServiceError> {
+    let jobs = match cli {
+        Some(n) => n,
+        None => match std::env::var(env_var) {
+            Ok(v) if !v.trim().is_empty() => v.trim().parse().map_err(|_| {
+                BackupServiceError::ConfigurationError(format!(
+                    "Invalid {}: {}.\n\nUse a positive number of parallel repositories",
+                    env_var, v
+                ))
+            })?,
+            _ => default,
+        },
+    };
+    if jobs == 0 {
+        return Err(BackupServiceError::ConfigurationError(
+            "Parallel jobs must be at least 1".to_string(),
+        ));
+    }
+    Ok(jobs)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_parse_size() -> Result<(), BackupServiceError> {
+        assert_eq!(parse_size("0")?, 0);
+        assert_eq!(parse_size("512")?, 512);
+        assert_eq!(parse_size("1K")?, 1024);
+        assert_eq!(parse_size("100M")?, 104857600);
+        assert_eq!(parse_size("2G")?, 2147483648);
+        assert_eq!(parse_size("2GB")?, 2147483648);
+        assert_eq!(parse_size("1.5t")?, 1649267441664);
+        assert!(parse_size("").is_err());
+        assert!(parse_size("ten").is_err());
+        assert!(parse_size("5X").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_jobs_from_cli() -> Result<(), BackupServiceError> {
+        assert_eq!(resolve_jobs(Some(3), "PRUNE_JOBS", 4)?, 3);
+        assert!(resolve_jobs(Some(0), "PRUNE_JOBS", 4).is_err());
+        Ok(())
+    }
+
     #[test]
     fn test_format_bytes_basic_units() -> Result<(), BackupServiceError> {
         assert_eq!(format_bytes(0)?, "0 B");
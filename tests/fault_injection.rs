@@ -0,0 +1,101 @@
+//! Runs the built binary against a local repository base with an injected fault and
+//! checks what an operator would see: retries, the classified error and the exit code.
+
+use std::path::Path;
+use std::process::{Command, Output};
+
+/// Stands in for restic: answers the dependency preflight's `restic version` and fails
+/// anything else, so a run that gets past the injected fault would show up
+const FAKE_RESTIC: &str = "#!/bin/sh\n\
+if [ \"$1\" = version ]; then echo 'restic 0.17.3 compiled with go1.22.0 on linux/amd64'; exit 0; fi\n\
+echo 'fake restic must not run' >&2\n\
+exit 1\n";
+
+/// `restic-backup-service run` with `fault` injected, isolated in `dir`
+fn run_with_fault(dir: &Path, fault: &str, envs: &[(&str, &str)]) -> Output {
+    use std::os::unix::fs::PermissionsExt;
+
+    let restic = dir.join("restic");
+    std::fs::write(&restic, FAKE_RESTIC).unwrap();
+    std::fs::set_permissions(&restic, std::fs::Permissions::from_mode(0o755)).unwrap();
+    let data = dir.join("data");
+    std::fs::create_dir_all(&data).unwrap();
+    std::fs::write(data.join("file.txt"), "backed up").unwrap();
+    std::fs::create_dir_all(dir.join("repo")).unwrap();
+
+    Command::new(env!("CARGO_BIN_EXE_restic-backup-service"))
+        .args(["--inject-fault", fault, "run"])
+        .env_clear()
+        .env("PATH", "/usr/bin:/bin")
+        .env("HOME", dir)
+        .env("XDG_CONFIG_HOME", dir.join("config"))
+        .env("XDG_CACHE_HOME", dir.join("cache"))
+        .env("RBS_LOG_DIR", dir.join("logs"))
+        .env("RESTIC_BIN", &restic)
+        .env("RESTIC_PASSWORD", "integration")
+        .env("RESTIC_REPO_BASE", dir.join("repo"))
+        .env("BACKUP_PATHS", &data)
+        .env("BACKUP_HOSTNAME", "itest")
+        .env("RBS_ALLOW_FAULT_INJECTION", "1")
+        .env("RESTIC_RETRY_BASE_DELAY", "0")
+        .env("NO_COLOR", "1")
+        .envs(envs.iter().copied())
+        .output()
+        .expect("the binary runs")
+}
+
+fn console(output: &Output) -> String {
+    format!(
+        "{}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    )
+}
+
+#[test]
+fn test_network_fault_is_retried_then_classified() {
+    let dir = tempfile::tempdir().unwrap();
+    let output = run_with_fault(
+        dir.path(),
+        "restic-network",
+        &[
+            ("RESTIC_RETRY_ATTEMPTS", "3"),
+            ("BACKUP_ERROR_POLICY", "fail-fast"),
+        ],
+    );
+    let log = console(&output);
+
+    // Three attempts: two retries, then the error is reported as a network failure
+    assert_eq!(
+        log.matches("Transient failure, retrying").count(),
+        2,
+        "{log}"
+    );
+    assert!(
+        log.contains("Network error: Cannot connect to repository"),
+        "{log}"
+    );
+    assert!(!log.contains("fake restic must not run"), "{log}");
+    assert_eq!(output.status.code(), Some(11), "{log}");
+}
+
+#[test]
+fn test_permanent_fault_fails_the_path_without_retries() {
+    let dir = tempfile::tempdir().unwrap();
+    let output = run_with_fault(dir.path(), "restic-failure", &[]);
+    let log = console(&output);
+
+    assert_eq!(
+        log.matches("Transient failure, retrying").count(),
+        0,
+        "{log}"
+    );
+    assert!(log.contains("simulated restic failure"), "{log}");
+    assert!(log.contains("Backup failed for 1 of 1 paths"), "{log}");
+    // Every path failed: a plain failure, not a partial backup
+    assert_eq!(output.status.code(), Some(1), "{log}");
+
+    // The failed run is still recorded for `status` and `history`
+    let history = std::fs::read_to_string(dir.path().join("logs/backup-history.jsonl")).unwrap();
+    assert!(history.contains("\"status\":\"failed\""), "{history}");
+}